@@ -1,47 +1,77 @@
-use std::{cmp, sync::Arc, time::Duration};
+use std::{sync::Arc, time::Duration};
 
-use crate::ChainState;
+use crate::{
+    event_indexer::index_logs,
+    light_sync::{light_start_block, SyncMode},
+    log_backfill::{backfill_logs, LogSyncConfig},
+    ChainState,
+};
 
 use anyhow::{anyhow, bail, Result};
 use contract_interface::da_entrance::{DataUploadFilter, ErasureCommitmentVerifiedFilter};
-use ethers::{abi::RawLog, prelude::EthLogDecode, providers::Middleware, types::BlockNumber};
+use ethers::{
+    abi::RawLog,
+    prelude::EthLogDecode,
+    providers::Middleware,
+    types::{BlockNumber, Log},
+};
+use events::NodeEvent;
+use futures::StreamExt;
 use storage::{
     blob_status_db::{BlobStatus, BlobStatusDB},
-    misc_db::MiscDB,
+    misc_db::{MiscDB, SyncCheckpoint},
 };
 use tokio::time::sleep;
 
-const MAX_LOGS_PAGINATION: u64 = 1000;
-
-pub async fn start_da_monitor(chain_state: Arc<ChainState>, start_block_number: u64) -> Result<()> {
-    let maybe_progress = chain_state.db.read().await.get_progress().await?;
-    match maybe_progress {
-        Some(_) => {}
+/// Poll the DA entrance logs from the stored checkpoint, from `start_block_number` on a new
+/// database. Polling errors are retried, the monitor only returns on storage errors.
+pub async fn run_da_monitor(
+    chain_state: Arc<ChainState>,
+    start_block_number: u64,
+    log_sync: LogSyncConfig,
+) -> Result<()> {
+    match chain_state.db.get_checkpoint().await? {
+        Some(checkpoint) => {
+            info!(
+                block_number = checkpoint.block_number,
+                epoch = ?checkpoint.epoch,
+                start_block_number,
+                "resuming da entrance sync from checkpoint"
+            );
+        }
         None => {
-            chain_state
-                .db
-                .write()
-                .await
-                .put_progress(start_block_number)
-                .await?;
+            let start = match log_sync.mode {
+                SyncMode::Full => start_block_number,
+                SyncMode::Light => {
+                    match light_start_block(&chain_state, start_block_number).await {
+                        Ok(block_number) => block_number,
+                        Err(e) => {
+                            warn!(
+                                start_block_number,
+                                "cannot find the light sync start block, syncing in full: {:?}", e
+                            );
+                            start_block_number
+                        }
+                    }
+                }
+            };
+            chain_state.db.put_progress(start).await?;
         }
     }
-    tokio::spawn(async move {
-        loop {
-            match check_da_logs(chain_state.clone()).await {
-                Ok(_) => {}
-                Err(e) => {
-                    error!("poll check_new_epoch error: {:?}", e);
-                }
+    loop {
+        match check_da_logs(chain_state.clone(), &log_sync).await {
+            Ok(_) => {}
+            Err(e) => {
+                error!("poll check_new_epoch error: {:?}", e);
             }
-            sleep(Duration::from_secs(5)).await;
         }
-    });
-    Ok(())
+        sleep(Duration::from_secs(5)).await;
+    }
 }
 
-async fn check_da_logs(chain_state: Arc<ChainState>) -> Result<()> {
-    let from = chain_state.db.read().await.get_progress().await?.unwrap();
+async fn check_da_logs(chain_state: Arc<ChainState>, log_sync: &LogSyncConfig) -> Result<()> {
+    let checkpoint = chain_state.db.get_checkpoint().await?.unwrap();
+    let from = checkpoint.block_number;
     match chain_state
         .provider
         .get_block(BlockNumber::Finalized)
@@ -50,13 +80,13 @@ async fn check_da_logs(chain_state: Arc<ChainState>) -> Result<()> {
         Some(b) => {
             if let Some(bn) = b.number {
                 let to = bn.as_u64();
+                chain_state.sync_progress.update(from, to, 0).await;
                 if to >= from {
                     info!(
                         "checking da entrance logs from {:?} to {:?} block..",
                         from, to
                     );
-                    check_data_logs(chain_state.clone(), from, to).await?;
-                    chain_state.db.write().await.put_progress(to + 1).await?;
+                    check_data_logs(chain_state.clone(), checkpoint, to, log_sync).await?;
                 }
             } else {
                 bail!(anyhow!("block number is empty"));
@@ -69,26 +99,51 @@ async fn check_da_logs(chain_state: Arc<ChainState>) -> Result<()> {
     Ok(())
 }
 
-async fn check_data_logs(chain_state: Arc<ChainState>, from: u64, to: u64) -> Result<()> {
-    let mut l = from;
-    while l <= to {
-        let r = cmp::min(l + MAX_LOGS_PAGINATION, to);
-        check_data_upload(chain_state.clone(), l, r).await?;
-        check_data_verified(chain_state.clone(), l, r).await?;
-        l = r + 1;
+/// Sync the logs up to `to`, fetched in parallel ranges and applied in block order. Every range
+/// is checkpointed so a restart does not scan it again.
+async fn check_data_logs(
+    chain_state: Arc<ChainState>,
+    mut checkpoint: SyncCheckpoint,
+    to: u64,
+    log_sync: &LogSyncConfig,
+) -> Result<()> {
+    let mut ranges = Box::pin(backfill_logs(
+        chain_state.clone(),
+        checkpoint.block_number,
+        to,
+        log_sync,
+    ));
+    while let Some(logs) = ranges.next().await {
+        let logs = logs?;
+        let r = logs.to;
+        if log_sync.index_events {
+            let all = logs
+                .uploads
+                .iter()
+                .chain(&logs.verifies)
+                .chain(&logs.sampling);
+            index_logs(&chain_state.db, all).await?;
+        }
+        let (uploads, upload_epoch) = check_data_upload(&chain_state, logs.uploads).await?;
+        let (verifies, verify_epoch) = check_data_verified(&chain_state, logs.verifies).await?;
+        checkpoint = SyncCheckpoint {
+            block_number: r + 1,
+            epoch: checkpoint.epoch.max(upload_epoch).max(verify_epoch),
+        };
+        chain_state.db.put_checkpoint(checkpoint).await?;
+        chain_state
+            .sync_progress
+            .update(r + 1, to, uploads + verifies)
+            .await;
     }
     Ok(())
 }
 
-async fn check_data_upload(chain_state: Arc<ChainState>, l: u64, r: u64) -> Result<()> {
-    let filter: ethers::types::Filter = chain_state
-        .da_entrance
-        .data_upload_filter()
-        .from_block(l)
-        .to_block(r)
-        .address(chain_state.da_entrance.address().into())
-        .filter;
-    for log in chain_state.provider.get_logs(&filter).await? {
+/// Store the blobs uploaded by `logs`, returns the number of events and their latest epoch.
+async fn check_data_upload(chain_state: &ChainState, logs: Vec<Log>) -> Result<(u64, Option<u64>)> {
+    let events = logs.len() as u64;
+    let mut latest_epoch = None;
+    for log in logs {
         match DataUploadFilter::decode_log(&RawLog {
             topics: log.topics,
             data: log.data.to_vec(),
@@ -96,10 +151,9 @@ async fn check_data_upload(chain_state: Arc<ChainState>, l: u64, r: u64) -> Resu
             Ok(event) => {
                 let epoch = event.epoch.as_u64();
                 let quorum_id = event.quorum_id.as_u64();
+                latest_epoch = latest_epoch.max(Some(epoch));
                 let maybe_blob_status = chain_state
                     .db
-                    .read()
-                    .await
                     .get_blob_status(epoch, quorum_id, event.data_root)
                     .await?;
                 match maybe_blob_status {
@@ -107,14 +161,17 @@ async fn check_data_upload(chain_state: Arc<ChainState>, l: u64, r: u64) -> Resu
                     None => {
                         chain_state
                             .db
-                            .write()
-                            .await
                             .put_blob(epoch, quorum_id, event.data_root, BlobStatus::UPLOADED)
                             .await?;
                         info!(
                             "new file found, epoch: {:?}, quorum_id: {:?}, data_root: {:X?}",
                             epoch, quorum_id, event.data_root
                         );
+                        chain_state.events.publish(NodeEvent::BlobUploaded {
+                            epoch,
+                            quorum_id,
+                            storage_root: event.data_root,
+                        });
                     }
                 }
             }
@@ -123,18 +180,16 @@ async fn check_data_upload(chain_state: Arc<ChainState>, l: u64, r: u64) -> Resu
             }
         }
     }
-    Ok(())
+    Ok((events, latest_epoch))
 }
 
-async fn check_data_verified(chain_state: Arc<ChainState>, l: u64, r: u64) -> Result<()> {
-    let filter: ethers::types::Filter = chain_state
-        .da_entrance
-        .erasure_commitment_verified_filter()
-        .from_block(l)
-        .to_block(r)
-        .address(chain_state.da_entrance.address().into())
-        .filter;
-    for log in chain_state.provider.get_logs(&filter).await? {
+async fn check_data_verified(
+    chain_state: &ChainState,
+    logs: Vec<Log>,
+) -> Result<(u64, Option<u64>)> {
+    let events = logs.len() as u64;
+    let mut latest_epoch = None;
+    for log in logs {
         match ErasureCommitmentVerifiedFilter::decode_log(&RawLog {
             topics: log.topics,
             data: log.data.to_vec(),
@@ -142,10 +197,9 @@ async fn check_data_verified(chain_state: Arc<ChainState>, l: u64, r: u64) -> Re
             Ok(event) => {
                 let epoch = event.epoch.as_u64();
                 let quorum_id = event.quorum_id.as_u64();
+                latest_epoch = latest_epoch.max(Some(epoch));
                 let maybe_blob_status = chain_state
                     .db
-                    .read()
-                    .await
                     .get_blob_status(epoch, quorum_id, event.data_root)
                     .await?;
                 let mut need_write = true;
@@ -155,14 +209,17 @@ async fn check_data_verified(chain_state: Arc<ChainState>, l: u64, r: u64) -> Re
                 if need_write {
                     chain_state
                         .db
-                        .write()
-                        .await
                         .put_blob(epoch, quorum_id, event.data_root, BlobStatus::VERIFIED)
                         .await?;
                     info!(
                         "file verified, epoch: {:?}, quorum_id: {:?}, data_root: {:X?}",
                         epoch, quorum_id, event.data_root
                     );
+                    chain_state.events.publish(NodeEvent::BlobVerified {
+                        epoch,
+                        quorum_id,
+                        storage_root: event.data_root,
+                    });
                 }
             }
             Err(e) => {
@@ -170,5 +227,5 @@ async fn check_data_verified(chain_state: Arc<ChainState>, l: u64, r: u64) -> Re
             }
         }
     }
-    Ok(())
+    Ok((events, latest_epoch))
 }
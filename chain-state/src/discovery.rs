@@ -0,0 +1,255 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use ethers::types::{H160, U256};
+use storage::quorum_db::QuorumDB;
+use task_executor::TaskExecutor;
+use tokio::{net::TcpStream, sync::RwLock, task::JoinSet, time::timeout};
+
+use crate::ChainState;
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(30);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Refresh the registry periodically in case signers update their sockets within an epoch.
+const REFRESH_ROUNDS: u64 = 20;
+
+#[derive(Debug, Clone)]
+pub struct PeerInfo {
+    pub address: H160,
+    pub socket: String,
+    pub quorums: BTreeSet<u64>,
+    pub alive: bool,
+    pub last_seen: Option<Instant>,
+}
+
+#[derive(Default)]
+struct PeerTableInner {
+    epoch: Option<u64>,
+    peers: BTreeMap<H160, PeerInfo>,
+}
+
+/// Signers of the current epoch from the on-chain registry, with their liveness.
+#[derive(Clone, Default)]
+pub struct PeerTable {
+    inner: Arc<RwLock<PeerTableInner>>,
+}
+
+impl PeerTable {
+    pub async fn epoch(&self) -> Option<u64> {
+        self.inner.read().await.epoch
+    }
+
+    pub async fn peers(&self) -> Vec<PeerInfo> {
+        self.inner.read().await.peers.values().cloned().collect()
+    }
+
+    /// Sockets of the signers of a quorum, alive ones first. Returns `None` if `epoch` is not tracked.
+    pub async fn quorum_sockets(&self, epoch: u64, quorum_id: u64) -> Option<Vec<String>> {
+        let inner = self.inner.read().await;
+        if inner.epoch != Some(epoch) {
+            return None;
+        }
+        let mut peers: Vec<&PeerInfo> = inner
+            .peers
+            .values()
+            .filter(|peer| peer.quorums.contains(&quorum_id))
+            .collect();
+        peers.sort_by_key(|peer| !peer.alive);
+        Some(peers.into_iter().map(|peer| peer.socket.clone()).collect())
+    }
+
+    async fn replace(&self, epoch: u64, mut peers: BTreeMap<H160, PeerInfo>) {
+        let mut inner = self.inner.write().await;
+        // keep liveness of known peers
+        for (address, peer) in peers.iter_mut() {
+            if let Some(known) = inner.peers.get(address) {
+                if known.socket == peer.socket {
+                    peer.alive = known.alive;
+                    peer.last_seen = known.last_seen;
+                }
+            }
+        }
+        inner.epoch = Some(epoch);
+        inner.peers = peers;
+    }
+
+    async fn set_alive(&self, address: H160, alive: bool) {
+        if let Some(peer) = self.inner.write().await.peers.get_mut(&address) {
+            peer.alive = alive;
+            if alive {
+                peer.last_seen = Some(Instant::now());
+            }
+        }
+    }
+}
+
+impl ChainState {
+    pub fn peer_table(&self) -> &PeerTable {
+        &self.peers
+    }
+
+    /// Read the other signers of all quorums of `epoch` from the on-chain registry.
+    async fn discover_peers(&self, epoch: u64) -> Result<BTreeMap<H160, PeerInfo>> {
+        let quorum_cnt = self
+            .da_signers
+            .quorum_count(U256::from(epoch))
+            .call()
+            .await?
+            .as_u64();
+        let mut quorums: BTreeMap<H160, BTreeSet<u64>> = BTreeMap::new();
+        for quorum_id in 0..quorum_cnt {
+            let signers = self
+                .da_signers
+                .get_quorum(U256::from(epoch), U256::from(quorum_id))
+                .call()
+                .await?;
+            for signer in signers {
+                if signer != self.signer_address {
+                    quorums.entry(signer).or_default().insert(quorum_id);
+                }
+            }
+        }
+        if quorums.is_empty() {
+            return Ok(BTreeMap::new());
+        }
+        let details = self
+            .da_signers
+            .get_signer(quorums.keys().cloned().collect())
+            .call()
+            .await?;
+        Ok(details
+            .into_iter()
+            .filter_map(|detail| {
+                let address = detail.signer;
+                quorums.remove(&address).map(|quorums| {
+                    (
+                        address,
+                        PeerInfo {
+                            address,
+                            socket: detail.socket,
+                            quorums,
+                            alive: false,
+                            last_seen: None,
+                        },
+                    )
+                })
+            })
+            .collect())
+    }
+}
+
+/// Maintain the peer table of the chain state: refresh it from the signer registry on new epochs
+/// and probe the peers periodically.
+pub fn start_peer_discovery(executor: TaskExecutor, chain_state: Arc<ChainState>) {
+    executor.spawn(
+        async move {
+            let mut rounds = 0u64;
+            loop {
+                if let Err(e) = refresh_peers(&chain_state, rounds % REFRESH_ROUNDS == 0).await {
+                    error!("peer discovery error: {:?}", e);
+                }
+                probe_peers(chain_state.peer_table()).await;
+                rounds += 1;
+                tokio::time::sleep(PROBE_INTERVAL).await;
+            }
+        },
+        "peer_discovery",
+    );
+}
+
+async fn refresh_peers(chain_state: &ChainState, force: bool) -> Result<()> {
+    let epoch = match chain_state.db.get_latest_epoch().await? {
+        Some(epoch) => epoch,
+        None => return Ok(()),
+    };
+    if !force && chain_state.peers.epoch().await == Some(epoch) {
+        return Ok(());
+    }
+    let peers = chain_state.discover_peers(epoch).await?;
+    info!("discovered {:?} peers of epoch {:?}", peers.len(), epoch);
+    chain_state.peers.replace(epoch, peers).await;
+    Ok(())
+}
+
+async fn probe_peers(table: &PeerTable) {
+    let mut probes = JoinSet::new();
+    for peer in table.peers().await {
+        probes.spawn(async move {
+            let addr = match peer.socket.split_once("://") {
+                Some((_, addr)) => addr.to_string(),
+                None => peer.socket.clone(),
+            };
+            let alive = matches!(
+                timeout(PROBE_TIMEOUT, TcpStream::connect(addr)).await,
+                Ok(Ok(_))
+            );
+            (peer.address, alive)
+        });
+    }
+    while let Some(res) = probes.join_next().await {
+        if let Ok((address, alive)) = res {
+            table.set_alive(address, alive).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(address: u64, socket: &str, quorums: &[u64], alive: bool) -> (H160, PeerInfo) {
+        let address = H160::from_low_u64_be(address);
+        (
+            address,
+            PeerInfo {
+                address,
+                socket: socket.to_string(),
+                quorums: quorums.iter().cloned().collect(),
+                alive,
+                last_seen: None,
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn peer_table_test() {
+        let table = PeerTable::default();
+        assert_eq!(table.quorum_sockets(1, 0).await, None);
+        table
+            .replace(
+                1,
+                [
+                    peer(1, "a:1", &[0], false),
+                    peer(2, "b:1", &[0, 1], false),
+                    peer(3, "c:1", &[1], false),
+                ]
+                .into_iter()
+                .collect(),
+            )
+            .await;
+        table.set_alive(H160::from_low_u64_be(2), true).await;
+        assert_eq!(
+            table.quorum_sockets(1, 0).await,
+            Some(vec!["b:1".to_string(), "a:1".to_string()])
+        );
+        assert_eq!(table.quorum_sockets(2, 0).await, None);
+
+        // liveness is kept unless the socket changes
+        table
+            .replace(
+                2,
+                [peer(1, "a:2", &[0], false), peer(2, "b:1", &[0], false)]
+                    .into_iter()
+                    .collect(),
+            )
+            .await;
+        assert_eq!(
+            table.quorum_sockets(2, 0).await,
+            Some(vec!["b:1".to_string(), "a:2".to_string()])
+        );
+    }
+}
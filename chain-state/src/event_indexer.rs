@@ -0,0 +1,90 @@
+use anyhow::{anyhow, Result};
+use contract_interface::da_entrance::DAEntranceEvents;
+use ethers::{
+    abi::RawLog,
+    prelude::EthLogDecode,
+    types::{Log, U256},
+};
+use storage::{
+    event_db::{ChainEvent, ChainEventDB, ChainEventKind},
+    Storage,
+};
+
+fn to_be_bytes(value: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
+/// The indexed form of a DA entrance log, `None` for the events not indexed.
+fn decode_log(log: &Log) -> Result<Option<ChainEvent>> {
+    let event = DAEntranceEvents::decode_log(&RawLog {
+        topics: log.topics.clone(),
+        data: log.data.to_vec(),
+    })?;
+    let kind = match event {
+        DAEntranceEvents::DataUploadFilter(x) => ChainEventKind::DataUpload {
+            sender: x.sender.0,
+            data_root: x.data_root,
+            epoch: x.epoch.as_u64(),
+            quorum_id: x.quorum_id.as_u64(),
+            blob_price: x.blob_price.try_into().unwrap_or(u128::MAX),
+        },
+        DAEntranceEvents::ErasureCommitmentVerifiedFilter(x) => {
+            ChainEventKind::ErasureCommitmentVerified {
+                data_root: x.data_root,
+                epoch: x.epoch.as_u64(),
+                quorum_id: x.quorum_id.as_u64(),
+            }
+        }
+        DAEntranceEvents::NewSampleRoundFilter(x) => ChainEventKind::NewSampleRound {
+            sample_round: x.sample_round.as_u64(),
+            sample_height: x.sample_height.as_u64(),
+            sample_seed: x.sample_seed,
+            podas_target: to_be_bytes(x.podas_target),
+        },
+        DAEntranceEvents::DarewardFilter(x) => ChainEventKind::DasReward {
+            beneficiary: x.beneficiary.0,
+            sample_round: x.sample_round.as_u64(),
+            epoch: x.epoch.as_u64(),
+            quorum_id: x.quorum_id.as_u64(),
+            data_root: x.data_root,
+            quality: to_be_bytes(x.quality),
+            line_index: x.line_index.as_u64(),
+            subline_index: x.subline_index.as_u64(),
+            reward: x.reward.try_into().unwrap_or(u128::MAX),
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(ChainEvent {
+        block_number: log
+            .block_number
+            .ok_or_else(|| anyhow!("log without block number"))?
+            .as_u64(),
+        log_index: log
+            .log_index
+            .ok_or_else(|| anyhow!("log without log index"))?
+            .as_u64(),
+        tx_hash: log.transaction_hash.unwrap_or_default().0,
+        kind,
+    }))
+}
+
+/// Persist the events of `logs` in the event index, logs that cannot be decoded are skipped.
+pub(crate) async fn index_logs<'a>(
+    db: &Storage,
+    logs: impl IntoIterator<Item = &'a Log>,
+) -> Result<u64> {
+    let mut events = vec![];
+    for log in logs {
+        match decode_log(log) {
+            Ok(Some(event)) => events.push(event),
+            Ok(None) => {}
+            Err(e) => {
+                error!("cannot index log: e={:?}", e);
+            }
+        }
+    }
+    db.put_chain_events(&events).await?;
+    Ok(events.len() as u64)
+}
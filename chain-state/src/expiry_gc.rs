@@ -0,0 +1,120 @@
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use contract_interface::da_entrance::NewSampleRoundFilter;
+use ethers::{
+    contract::EthEvent,
+    providers::Middleware,
+    types::{BlockNumber, Filter},
+};
+use events::NodeEvent;
+use storage::{expiry_db::ExpiryDB, slice_db::SliceDB, usage_db::UsageDB};
+use tokio::time::sleep;
+
+use crate::ChainState;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+const DELETE_BATCH_SIZE: usize = 1000;
+
+/// Delete the slices of the epochs the contract no longer samples, once their storage period
+/// expires. The sample range moves with the sample rounds, so it is read again on every
+/// `NewSampleRound` event.
+pub async fn run_expiry_gc(chain_state: Arc<ChainState>) -> Result<()> {
+    let mut checked_block = None;
+    loop {
+        match check_new_sample_rounds(&chain_state, checked_block).await {
+            Ok((block, new_round)) => {
+                if new_round {
+                    if let Err(e) = collect_expired_epochs(&chain_state).await {
+                        warn!("cannot delete expired slices: {:?}", e);
+                    }
+                }
+                checked_block = Some(block);
+            }
+            Err(e) => {
+                warn!("cannot check sample rounds: {:?}", e);
+            }
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Finalized block, and whether a sample round started after `checked_block`. The first check
+/// always counts as a new round.
+async fn check_new_sample_rounds(
+    chain_state: &ChainState,
+    checked_block: Option<u64>,
+) -> Result<(u64, bool)> {
+    let finalized = chain_state
+        .provider
+        .get_block(BlockNumber::Finalized)
+        .await?
+        .and_then(|b| b.number)
+        .ok_or_else(|| anyhow!("finalized block returns None"))?
+        .as_u64();
+    let from = match checked_block {
+        Some(block) if block >= finalized => return Ok((block, false)),
+        Some(block) => block + 1,
+        None => return Ok((finalized, true)),
+    };
+    let filter = Filter::new()
+        .from_block(from)
+        .to_block(finalized)
+        .address(chain_state.da_entrance.address())
+        .topic0(NewSampleRoundFilter::signature());
+    let logs = chain_state.provider.get_logs(&filter).await?;
+    Ok((finalized, !logs.is_empty()))
+}
+
+async fn collect_expired_epochs(chain_state: &ChainState) -> Result<()> {
+    let start_epoch = chain_state
+        .da_entrance
+        .sample_range()
+        .call()
+        .await?
+        .start_epoch;
+    let db = &chain_state.db;
+    let first_epoch = match db.get_expired_epoch().await? {
+        Some(epoch) => epoch,
+        None => db
+            .get_slice_usage(None)
+            .await?
+            .keys()
+            .next()
+            .map_or(start_epoch, |(epoch, _)| *epoch),
+    };
+    for epoch in first_epoch..start_epoch {
+        let blobs = db.get_epoch_info(epoch).await?;
+        let mut quorums: BTreeSet<u64> = db
+            .get_slice_usage(Some(epoch))
+            .await?
+            .into_keys()
+            .map(|(_, quorum_id)| quorum_id)
+            .collect();
+        quorums.extend(blobs.iter().map(|x| x.quorum_id));
+        let mut deleted = 0;
+        for quorum_id in quorums {
+            loop {
+                let n = db
+                    .delete_expired_slices(epoch, quorum_id, DELETE_BATCH_SIZE)
+                    .await?;
+                if n == 0 {
+                    break;
+                }
+                deleted += n;
+            }
+        }
+        for blob in blobs {
+            chain_state.events.publish(NodeEvent::BlobPruned {
+                epoch,
+                quorum_id: blob.quorum_id,
+                storage_root: blob.storage_root,
+            });
+        }
+        db.put_expired_epoch(epoch + 1).await?;
+        if deleted > 0 {
+            info!(epoch, deleted, "slices of expired epoch deleted");
+        }
+    }
+    Ok(())
+}
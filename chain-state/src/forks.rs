@@ -0,0 +1,131 @@
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use ethers::{providers::Middleware, types::BlockNumber};
+use serde::Deserialize;
+
+use crate::ChainState;
+
+/// Forks understood by this build, none yet. A fork active in the schedule and missing here
+/// requires an upgrade, its behavior switch is added with its name.
+pub const KNOWN_FORKS: &[&str] = &[];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activation {
+    Block(u64),
+    Epoch(u64),
+}
+
+impl Activation {
+    fn is_reached(&self, block: u64, epoch: u64) -> bool {
+        match *self {
+            Activation::Block(x) => block >= x,
+            Activation::Epoch(x) => epoch >= x,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ForkActivation {
+    pub name: String,
+    #[serde(flatten)]
+    pub activation: Activation,
+}
+
+impl ForkActivation {
+    pub fn is_known(&self) -> bool {
+        KNOWN_FORKS.contains(&self.name.as_str())
+    }
+}
+
+/// Network upgrade schedule, e.g.
+/// `{"forks": [{"name": "some_fork", "block": 1000}, {"name": "other_fork", "epoch": 20}]}`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ForkSchedule {
+    forks: Vec<ForkActivation>,
+}
+
+impl ForkSchedule {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let schedule: Self = serde_json::from_slice(&std::fs::read(path.as_ref())?)
+            .map_err(|e| anyhow!("invalid fork schedule {:?}: {:?}", path.as_ref(), e))?;
+        Ok(schedule)
+    }
+
+    pub fn pending(&self, block: u64, epoch: u64) -> Vec<&ForkActivation> {
+        self.forks
+            .iter()
+            .filter(|fork| !fork.activation.is_reached(block, epoch))
+            .collect()
+    }
+
+    /// Forks unknown to this build which are already active.
+    pub fn active_unsupported(&self, block: u64, epoch: u64) -> Vec<&str> {
+        self.forks
+            .iter()
+            .filter(|fork| !fork.is_known() && fork.activation.is_reached(block, epoch))
+            .map(|fork| fork.name.as_str())
+            .collect()
+    }
+}
+
+impl ChainState {
+    /// Check the fork schedule against the finalized chain head, logging upcoming activations.
+    /// Returns the active forks this build does not support.
+    pub async fn check_forks(&self, log_pending: bool) -> Result<Vec<String>> {
+        let block = match self.provider.get_block(BlockNumber::Finalized).await? {
+            Some(b) => match b.number {
+                Some(bn) => bn.as_u64(),
+                None => bail!(anyhow!("block number is empty")),
+            },
+            None => bail!(anyhow!("finalized block returns None")),
+        };
+        let epoch = self
+            .da_signers
+            .epoch_number()
+            .block(block)
+            .call()
+            .await?
+            .as_u64();
+        if log_pending {
+            for fork in self.forks.pending(block, epoch) {
+                if fork.is_known() {
+                    info!(
+                        "upcoming fork {:?} activates at {:?}",
+                        fork.name, fork.activation
+                    );
+                } else {
+                    warn!(
+                        "upcoming fork {:?} at {:?} is not supported by this build, upgrade before activation",
+                        fork.name, fork.activation
+                    );
+                }
+            }
+        }
+        Ok(self
+            .forks
+            .active_unsupported(block, epoch)
+            .into_iter()
+            .map(|name| name.to_string())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fork_schedule_test() {
+        let schedule: ForkSchedule = serde_json::from_str(
+            r#"{"forks": [{"name": "a", "block": 100}, {"name": "b", "epoch": 5}]}"#,
+        )
+        .unwrap();
+        assert_eq!(schedule.pending(99, 0).len(), 2);
+        assert_eq!(schedule.pending(100, 4).len(), 1);
+        assert!(schedule.pending(100, 5).is_empty());
+        assert!(schedule.active_unsupported(99, 4).is_empty());
+        assert_eq!(schedule.active_unsupported(100, 4), vec!["a"]);
+    }
+}
@@ -2,21 +2,43 @@
 extern crate tracing;
 
 pub mod da_handler;
+pub mod discovery;
+pub mod event_indexer;
+pub mod expiry_gc;
+pub mod forks;
+pub mod light_sync;
+pub mod log_backfill;
+pub mod peer_auth;
+pub mod quorum_transition;
+pub mod recovery;
+pub mod registration_watch;
+pub mod retrieval_envelope;
+pub mod signer_keys;
 pub mod signers_handler;
+pub mod stake_monitor;
+pub mod sync_progress;
 pub mod transactor;
 
 use std::{str::FromStr, sync::Arc, time::Duration};
 
 use anyhow::Result;
 
+use ark_bn254::G1Affine;
 use chain_utils::DA_SIGNER_ADDRESS;
 use contract_interface::{DAEntrance, DASigners};
+use discovery::PeerTable;
 use ethers::{
-    providers::{Http, HttpRateLimitRetryPolicy, Provider, RetryClient, RetryClientBuilder},
-    types::H160,
+    providers::{
+        Http, HttpRateLimitRetryPolicy, Middleware, Provider, RetryClient, RetryClientBuilder,
+    },
+    types::{H160, U256},
 };
+use events::EventBus;
+use forks::ForkSchedule;
+use signers_handler::deserialize_g1_point;
 use storage::Storage;
-use tokio::sync::{Mutex, RwLock};
+use sync_progress::SyncProgress;
+use tokio::sync::Mutex;
 use transactor::Transactor;
 
 pub struct ChainState {
@@ -25,7 +47,12 @@ pub struct ChainState {
     da_signers: Arc<DASigners<Provider<RetryClient<Http>>>>,
     transactor: Arc<Mutex<Transactor>>,
     signer_address: H160,
-    db: Arc<RwLock<Storage>>,
+    chain_id: u64,
+    db: Arc<Storage>,
+    forks: ForkSchedule,
+    events: EventBus,
+    peers: PeerTable,
+    sync_progress: SyncProgress,
 }
 
 impl ChainState {
@@ -33,7 +60,10 @@ impl ChainState {
         eth_rpc_url: &str,
         da_entrance_address: H160,
         transactor: Arc<Mutex<Transactor>>,
-        db: Arc<RwLock<Storage>>,
+        db: Arc<Storage>,
+        forks: ForkSchedule,
+        events: EventBus,
+        sync_progress: SyncProgress,
     ) -> Result<Self> {
         let provider = Arc::new(Provider::new(
             RetryClientBuilder::default()
@@ -51,13 +81,79 @@ impl ChainState {
             provider.clone(),
         ));
         let signer_address = transactor.lock().await.signer_address();
+        let chain_id = provider.get_chainid().await?.as_u64();
         Ok(Self {
             provider,
             da_entrance,
             da_signers,
             transactor,
             signer_address,
+            chain_id,
             db,
+            forks,
+            events,
+            peers: PeerTable::default(),
+            sync_progress,
         })
     }
+
+    pub fn sync_progress(&self) -> &SyncProgress {
+        &self.sync_progress
+    }
+
+    pub fn signer_address(&self) -> H160 {
+        self.signer_address
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.chain_id
+    }
+
+    /// Whether the erasure commitment of a blob is verified on chain.
+    pub async fn commitment_exists(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        data_root: [u8; 32],
+    ) -> Result<bool> {
+        Ok(self
+            .da_entrance
+            .commitment_exists(data_root, U256::from(epoch), U256::from(quorum_id))
+            .call()
+            .await?)
+    }
+
+    /// Erasure commitment recorded on chain for a blob, `None` if the contract has no commitment
+    /// for it yet.
+    pub async fn onchain_erasure_commitment(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        data_root: [u8; 32],
+    ) -> Result<Option<G1Affine>> {
+        let onchain = self
+            .da_entrance
+            .verified_erasure_commitment(data_root, U256::from(epoch), U256::from(quorum_id))
+            .call()
+            .await?;
+        if onchain.x.is_zero() && onchain.y.is_zero() {
+            return Ok(None);
+        }
+        Ok(Some(deserialize_g1_point(onchain.x, onchain.y)?))
+    }
+
+    /// Whether `commitment` is the erasure commitment recorded on chain for a blob, `None` if the
+    /// contract has no commitment for it yet.
+    pub async fn matches_onchain_commitment(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        data_root: [u8; 32],
+        commitment: G1Affine,
+    ) -> Result<Option<bool>> {
+        Ok(self
+            .onchain_erasure_commitment(epoch, quorum_id, data_root)
+            .await?
+            .map(|onchain| onchain == commitment))
+    }
 }
@@ -0,0 +1,77 @@
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{BlockNumber, U256},
+};
+
+use crate::ChainState;
+
+/// Where a new database starts syncing the DA entrance logs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// From `start_block_number`, the deployment of the contract.
+    #[default]
+    Full,
+    /// From the first block of the earliest epoch of the epoch window the signer is registered
+    /// for, or of the current epoch if it is not registered yet. Blobs of older epochs are not
+    /// known to the node.
+    Light,
+}
+
+async fn epoch_at(chain_state: &ChainState, block_number: u64) -> Result<u64> {
+    Ok(chain_state
+        .da_signers
+        .epoch_number()
+        .block(block_number)
+        .call()
+        .await?
+        .as_u64())
+}
+
+/// Start block of the light sync, not before `min_block`. The epochs of past blocks are read
+/// from the chain, so the rpc must serve the state of the blocks searched.
+pub(crate) async fn light_start_block(chain_state: &ChainState, min_block: u64) -> Result<u64> {
+    let finalized = chain_state
+        .provider
+        .get_block(BlockNumber::Finalized)
+        .await?
+        .and_then(|b| b.number)
+        .ok_or_else(|| anyhow!("finalized block returns None"))?
+        .as_u64();
+    let epoch = epoch_at(chain_state, finalized).await?;
+    let window = chain_state
+        .da_entrance
+        .epoch_window_size()
+        .call()
+        .await?
+        .as_u64();
+    let mut start_epoch = epoch;
+    for e in epoch.saturating_sub(window)..=epoch {
+        if chain_state
+            .da_signers
+            .registered_epoch(chain_state.signer_address, U256::from(e))
+            .call()
+            .await?
+        {
+            start_epoch = e;
+            break;
+        }
+    }
+    // first block of `start_epoch`
+    let (mut l, mut r) = (min_block, finalized);
+    while l < r {
+        let mid = l + (r - l) / 2;
+        if epoch_at(chain_state, mid).await? >= start_epoch {
+            r = mid;
+        } else {
+            l = mid + 1;
+        }
+    }
+    info!(
+        epoch,
+        start_epoch,
+        start_block = l,
+        "light sync starts from the first block of the earliest registered epoch"
+    );
+    Ok(l)
+}
@@ -0,0 +1,214 @@
+use std::{
+    cmp,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Result;
+use contract_interface::da_entrance::{DarewardFilter, NewSampleRoundFilter};
+use ethers::{
+    contract::EthEvent,
+    providers::Middleware,
+    types::{Filter, Log},
+};
+use futures::{
+    future::BoxFuture,
+    stream::{self, Stream, StreamExt},
+    FutureExt,
+};
+
+use crate::{light_sync::SyncMode, ChainState};
+
+/// Fetching of the DA entrance logs while catching up.
+#[derive(Debug, Clone)]
+pub struct LogSyncConfig {
+    /// Where a new database starts syncing.
+    pub mode: SyncMode,
+    /// Block ranges fetched at once.
+    pub concurrency: usize,
+    /// Blocks of a range, ranges are shrunk while the provider rejects them.
+    pub max_range: u64,
+    /// Also fetch the sampling events and persist every event in the event index.
+    pub index_events: bool,
+}
+
+impl Default for LogSyncConfig {
+    fn default() -> Self {
+        Self {
+            mode: SyncMode::Full,
+            concurrency: 4,
+            max_range: 1000,
+            index_events: false,
+        }
+    }
+}
+
+/// DA entrance logs of the blocks `from..=to`.
+pub(crate) struct RangeLogs {
+    pub to: u64,
+    pub uploads: Vec<Log>,
+    pub verifies: Vec<Log>,
+    /// `NewSampleRound` and `DAReward` logs, only fetched to be indexed.
+    pub sampling: Vec<Log>,
+}
+
+/// Size of the next ranges, halved below the ranges the provider rejects and doubled back on
+/// success, so the sync follows the limits of the provider.
+struct RangeSizer {
+    size: AtomicU64,
+    max: u64,
+}
+
+impl RangeSizer {
+    fn size(&self) -> u64 {
+        self.size.load(Ordering::Relaxed)
+    }
+
+    fn on_success(&self) {
+        let size = self.size();
+        self.size.store(
+            cmp::min(size.saturating_mul(2), self.max),
+            Ordering::Relaxed,
+        );
+    }
+
+    fn on_rejected(&self, range: u64) {
+        self.size.fetch_min((range / 2).max(1), Ordering::Relaxed);
+    }
+}
+
+async fn fetch_logs(
+    chain_state: &ChainState,
+    from: u64,
+    to: u64,
+    index_events: bool,
+) -> Result<RangeLogs> {
+    let address = chain_state.da_entrance.address();
+    let uploads = chain_state
+        .da_entrance
+        .data_upload_filter()
+        .from_block(from)
+        .to_block(to)
+        .address(address.into())
+        .filter;
+    let verifies = chain_state
+        .da_entrance
+        .erasure_commitment_verified_filter()
+        .from_block(from)
+        .to_block(to)
+        .address(address.into())
+        .filter;
+    let sampling = if index_events {
+        let filter = Filter::new()
+            .from_block(from)
+            .to_block(to)
+            .address(address)
+            .topic0(vec![
+                NewSampleRoundFilter::signature(),
+                DarewardFilter::signature(),
+            ]);
+        chain_state.provider.get_logs(&filter).await?
+    } else {
+        vec![]
+    };
+    Ok(RangeLogs {
+        to,
+        uploads: chain_state.provider.get_logs(&uploads).await?,
+        verifies: chain_state.provider.get_logs(&verifies).await?,
+        sampling,
+    })
+}
+
+/// Fetch a range, splitting it in halves while the provider rejects it.
+fn fetch_range(
+    chain_state: Arc<ChainState>,
+    sizer: Arc<RangeSizer>,
+    from: u64,
+    to: u64,
+    index_events: bool,
+) -> BoxFuture<'static, Result<RangeLogs>> {
+    async move {
+        let e = match fetch_logs(&chain_state, from, to, index_events).await {
+            Ok(logs) => {
+                sizer.on_success();
+                return Ok(logs);
+            }
+            Err(e) if from < to => e,
+            Err(e) => return Err(e),
+        };
+        sizer.on_rejected(to - from + 1);
+        let mid = from + (to - from) / 2;
+        warn!(
+            from,
+            to, "cannot get logs of the range, splitting it: {:?}", e
+        );
+        let mut logs =
+            fetch_range(chain_state.clone(), sizer.clone(), from, mid, index_events).await?;
+        let right = fetch_range(chain_state, sizer, mid + 1, to, index_events).await?;
+        logs.to = to;
+        logs.uploads.extend(right.uploads);
+        logs.verifies.extend(right.verifies);
+        logs.sampling.extend(right.sampling);
+        Ok(logs)
+    }
+    .boxed()
+}
+
+/// Logs of the blocks `from..=to` in consecutive ranges, fetched in parallel and yielded in block
+/// order so they are applied in order.
+pub(crate) fn backfill_logs(
+    chain_state: Arc<ChainState>,
+    from: u64,
+    to: u64,
+    config: &LogSyncConfig,
+) -> impl Stream<Item = Result<RangeLogs>> {
+    let (max, index_events) = (config.max_range.max(1), config.index_events);
+    let sizer = Arc::new(RangeSizer {
+        size: AtomicU64::new(max),
+        max,
+    });
+    let ranges = stream::unfold(from, {
+        let sizer = sizer.clone();
+        move |l| {
+            let size = sizer.size();
+            async move {
+                (l <= to).then(|| {
+                    let r = cmp::min(l.saturating_add(size - 1), to);
+                    ((l, r), r + 1)
+                })
+            }
+        }
+    });
+    ranges
+        .map(move |(l, r)| fetch_range(chain_state.clone(), sizer.clone(), l, r, index_events))
+        .buffered(config.concurrency.max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_sizer_test() {
+        let sizer = RangeSizer {
+            size: AtomicU64::new(1000),
+            max: 1000,
+        };
+        sizer.on_rejected(1000);
+        assert_eq!(sizer.size(), 500);
+        // a late rejection of a larger range does not grow the size
+        sizer.on_rejected(2000);
+        assert_eq!(sizer.size(), 500);
+        sizer.on_rejected(1);
+        assert_eq!(sizer.size(), 1);
+        sizer.on_success();
+        sizer.on_success();
+        assert_eq!(sizer.size(), 4);
+        for _ in 0..20 {
+            sizer.on_success();
+        }
+        assert_eq!(sizer.size(), 1000);
+    }
+}
@@ -0,0 +1,130 @@
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::{Bn254, Fq2, Fr, G1Affine, G2Affine};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_serialize::CanonicalDeserialize;
+use contract_interface::da_signers::G2Point;
+use ethers::{types::H160, utils::keccak256};
+use utils::{left_pad_zeros, map_to_g1};
+
+use crate::ChainState;
+
+const PEER_AUTH_DOMAIN: &[u8] = "0G_DA_P2P_Peer_Auth".as_bytes();
+
+/// Message signed by a signer to bind a p2p peer id to its account. The peer id is the identity
+/// key authenticated by the Noise handshake of every p2p connection, so the attestation cannot be
+/// used by another session.
+fn peer_auth_hash(peer_id: &[u8], signer_address: H160, chain_id: u64) -> G1Affine {
+    let mut message = PEER_AUTH_DOMAIN.to_vec();
+    message.append(&mut signer_address.to_fixed_bytes().to_vec());
+    message.append(&mut left_pad_zeros(chain_id, 32));
+    message.append(&mut peer_id.to_vec());
+    map_to_g1(keccak256(message).to_vec())
+}
+
+pub fn deserialize_g2_point(point: &G2Point) -> Result<G2Affine> {
+    let coordinate = |values: &[ethers::types::U256; 2]| -> Result<Fq2> {
+        let mut bytes = vec![0u8; 64];
+        values[0].to_little_endian(&mut bytes[0..32]);
+        values[1].to_little_endian(&mut bytes[32..64]);
+        Ok(Fq2::deserialize_uncompressed(&*bytes)?)
+    };
+    let point = G2Affine::new_unchecked(coordinate(&point.x)?, coordinate(&point.y)?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        bail!(anyhow!("invalid G2 point"));
+    }
+    Ok(point)
+}
+
+pub fn sign_peer_id(key: Fr, peer_id: &[u8], signer_address: H160, chain_id: u64) -> G1Affine {
+    (peer_auth_hash(peer_id, signer_address, chain_id) * key).into_affine()
+}
+
+pub fn verify_peer_id(
+    pub_key_g2: G2Affine,
+    signature: G1Affine,
+    peer_id: &[u8],
+    signer_address: H160,
+    chain_id: u64,
+) -> bool {
+    Bn254::pairing(signature, G2Affine::generator())
+        == Bn254::pairing(
+            peer_auth_hash(peer_id, signer_address, chain_id),
+            pub_key_g2,
+        )
+}
+
+impl ChainState {
+    /// Sign the p2p peer id of the node with the signer key.
+    pub async fn attest_peer_id(&self, key: Fr, peer_id: &[u8]) -> Result<G1Affine> {
+        Ok(sign_peer_id(
+            key,
+            peer_id,
+            self.signer_address,
+            self.chain_id,
+        ))
+    }
+
+    /// G2 public key registered by `signer_address`, `None` if it is not a signer.
+    pub async fn registered_pub_key_g2(&self, signer_address: H160) -> Result<Option<G2Affine>> {
+        if !self.da_signers.is_signer(signer_address).call().await? {
+            return Ok(None);
+        }
+        let detail = self
+            .da_signers
+            .get_signer(vec![signer_address])
+            .call()
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("signer {:?} not found", signer_address))?;
+        Ok(Some(deserialize_g2_point(&detail.pk_g2)?))
+    }
+
+    /// Whether `signature` proves that the registered signer `signer_address` runs `peer_id`.
+    pub async fn verify_peer_attestation(
+        &self,
+        signer_address: H160,
+        peer_id: &[u8],
+        signature: G1Affine,
+    ) -> Result<bool> {
+        Ok(match self.registered_pub_key_g2(signer_address).await? {
+            Some(pub_key_g2) => verify_peer_id(
+                pub_key_g2,
+                signature,
+                peer_id,
+                signer_address,
+                self.chain_id,
+            ),
+            None => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::g2;
+
+    use super::*;
+    use crate::signers_handler::serialize_g2_point;
+
+    #[test]
+    fn peer_auth_test() {
+        let key = Fr::from(42);
+        let pub_key = (g2::G2Affine::generator() * key).into_affine();
+        assert_eq!(
+            deserialize_g2_point(&serialize_g2_point(pub_key)).unwrap(),
+            pub_key
+        );
+
+        let signer = H160::from_low_u64_be(1);
+        let signature = sign_peer_id(key, b"peer", signer, 16600);
+        assert!(verify_peer_id(pub_key, signature, b"peer", signer, 16600));
+        assert!(!verify_peer_id(pub_key, signature, b"other", signer, 16600));
+        assert!(!verify_peer_id(
+            pub_key,
+            signature,
+            b"peer",
+            H160::from_low_u64_be(2),
+            16600
+        ));
+    }
+}
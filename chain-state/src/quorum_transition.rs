@@ -0,0 +1,115 @@
+use std::collections::BTreeSet;
+
+use anyhow::Result;
+use storage::quorum_db::{AssignedSlices, QuorumDB};
+
+use crate::{signers_handler::fetch_assigned_slices, ChainState};
+
+/// Membership of the signer in the quorums of an epoch, compared with the previous epoch.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct QuorumTransition {
+    pub epoch: u64,
+    /// Quorums the signer is assigned rows in, but was not in the previous epoch.
+    pub joined: Vec<u64>,
+    /// Quorums the signer was assigned rows in the previous epoch, but is not anymore.
+    pub left: Vec<u64>,
+    pub rows_before: u64,
+    pub rows_after: u64,
+}
+
+fn member_of(quorums: &[AssignedSlices]) -> BTreeSet<u64> {
+    quorums
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| !x.0.is_empty())
+        .map(|(quorum_id, _)| quorum_id as u64)
+        .collect()
+}
+
+fn rows(quorums: &[AssignedSlices]) -> u64 {
+    quorums.iter().map(|x| x.0.len() as u64).sum()
+}
+
+impl QuorumTransition {
+    pub fn new(epoch: u64, previous: &[AssignedSlices], next: &[AssignedSlices]) -> Self {
+        let (before, after) = (member_of(previous), member_of(next));
+        Self {
+            epoch,
+            joined: after.difference(&before).copied().collect(),
+            left: before.difference(&after).copied().collect(),
+            rows_before: rows(previous),
+            rows_after: rows(next),
+        }
+    }
+
+    pub fn is_changed(&self) -> bool {
+        !self.joined.is_empty() || !self.left.is_empty() || self.rows_before != self.rows_after
+    }
+}
+
+impl ChainState {
+    /// Quorums of `epoch`, installed from chain if they are not stored yet. Epochs whose
+    /// quorums are not formed yet are not stored, so they are fetched again later.
+    pub async fn fetch_quorum_if_missing(&self, epoch: u64) -> Result<u64> {
+        match self.db.get_quorum_num(epoch).await? {
+            Some(cnt) if cnt > 0 => Ok(cnt),
+            _ => self.install_quorums(epoch).await,
+        }
+    }
+
+    /// Fetch the assignments of `epoch` and install them at once, the assignments of the previous
+    /// epoch are kept and compared with them.
+    async fn install_quorums(&self, epoch: u64) -> Result<u64> {
+        let assigned = fetch_assigned_slices(&self.da_signers, self.signer_address, epoch).await?;
+        if assigned.is_empty() {
+            return Ok(0);
+        }
+        let previous = match epoch.checked_sub(1) {
+            Some(previous_epoch) => self.db.get_quorums(previous_epoch).await?,
+            None => None,
+        };
+        let transition =
+            QuorumTransition::new(epoch, previous.as_deref().unwrap_or_default(), &assigned);
+        let quorum_cnt = assigned.len() as u64;
+        self.db.put_quorums(epoch, assigned).await?;
+        if transition.rows_after == 0 {
+            warn!(
+                epoch,
+                quorums = quorum_cnt,
+                "signer is assigned to no quorum, its sign requests are rejected"
+            );
+        } else if transition.is_changed() {
+            info!(
+                epoch,
+                joined = ?transition.joined,
+                left = ?transition.left,
+                rows_before = transition.rows_before,
+                rows_after = transition.rows_after,
+                "quorum membership changed"
+            );
+        } else {
+            info!(epoch, quorums = quorum_cnt, "quorums installed");
+        }
+        Ok(quorum_cnt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transition_test() {
+        let previous = vec![AssignedSlices(vec![1, 2]), AssignedSlices(vec![])];
+        let next = vec![
+            AssignedSlices(vec![]),
+            AssignedSlices(vec![3]),
+            AssignedSlices(vec![4, 5]),
+        ];
+        let transition = QuorumTransition::new(2, &previous, &next);
+        assert_eq!(transition.joined, vec![1, 2]);
+        assert_eq!(transition.left, vec![0]);
+        assert_eq!((transition.rows_before, transition.rows_after), (2, 3));
+        assert!(!QuorumTransition::new(2, &next, &next).is_changed());
+    }
+}
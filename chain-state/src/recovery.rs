@@ -0,0 +1,130 @@
+use std::{collections::BTreeSet, str::FromStr, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use ark_bn254::Fr;
+use chain_utils::DA_SIGNER_ADDRESS;
+use contract_interface::DASigners;
+use ethers::{
+    providers::Middleware,
+    types::{TransactionRequest, H160, U256},
+};
+use storage::quorum_db::AssignedSlices;
+
+use crate::signers_handler::{bls_pub_key_g1, fetch_assigned_slices};
+
+/// On-chain registration of a signer compared with a local identity.
+#[derive(Debug, Clone)]
+pub struct RegistrationCheck {
+    pub registered: bool,
+    /// The registered BLS public key matches the local key.
+    pub key_matches: bool,
+    pub socket: Option<String>,
+}
+
+/// Queries of the signer registry used to recover the identity of a node, without a running node.
+pub struct Recovery<M> {
+    da_signers: DASigners<M>,
+    signer_address: H160,
+}
+
+impl<M: Middleware> Recovery<M> {
+    pub fn new(client: Arc<M>, signer_address: H160) -> Self {
+        Self {
+            da_signers: DASigners::new(H160::from_str(DA_SIGNER_ADDRESS).unwrap(), client),
+            signer_address,
+        }
+    }
+
+    pub async fn check_registration(
+        &self,
+        signer_bls_private_key: Fr,
+    ) -> Result<RegistrationCheck> {
+        if !self
+            .da_signers
+            .is_signer(self.signer_address)
+            .call()
+            .await
+            .map_err(|e| anyhow!("Cannot query signer: {:?}", e))?
+        {
+            return Ok(RegistrationCheck {
+                registered: false,
+                key_matches: false,
+                socket: None,
+            });
+        }
+        let detail = self
+            .da_signers
+            .get_signer(vec![self.signer_address])
+            .call()
+            .await
+            .map_err(|e| anyhow!("Cannot query signer: {:?}", e))?
+            .pop()
+            .ok_or_else(|| anyhow!("signer {:?} not found", self.signer_address))?;
+        Ok(RegistrationCheck {
+            registered: true,
+            key_matches: detail.pk_g1 == bls_pub_key_g1(signer_bls_private_key),
+            socket: Some(detail.socket),
+        })
+    }
+
+    pub async fn current_epoch(&self) -> Result<u64> {
+        Ok(self
+            .da_signers
+            .epoch_number()
+            .call()
+            .await
+            .map_err(|e| anyhow!("Cannot query epoch: {:?}", e))?
+            .as_u64())
+    }
+
+    /// Other signers of the quorums of `epoch` registered with `socket`.
+    pub async fn socket_conflicts(&self, epoch: u64, socket: &str) -> Result<Vec<H160>> {
+        let quorum_cnt = self
+            .da_signers
+            .quorum_count(U256::from(epoch))
+            .call()
+            .await
+            .map_err(|e| anyhow!("Cannot query quorums: {:?}", e))?
+            .as_u64();
+        let mut signers = BTreeSet::new();
+        for quorum_id in 0..quorum_cnt {
+            let quorum = self
+                .da_signers
+                .get_quorum(U256::from(epoch), U256::from(quorum_id))
+                .call()
+                .await
+                .map_err(|e| anyhow!("Cannot query quorum: {:?}", e))?;
+            signers.extend(quorum.into_iter().filter(|x| *x != self.signer_address));
+        }
+        if signers.is_empty() {
+            return Ok(vec![]);
+        }
+        let details = self
+            .da_signers
+            .get_signer(signers.into_iter().collect())
+            .call()
+            .await
+            .map_err(|e| anyhow!("Cannot query signers: {:?}", e))?;
+        Ok(details
+            .into_iter()
+            .filter(|detail| detail.socket == socket)
+            .map(|detail| detail.signer)
+            .collect())
+    }
+
+    /// Transaction updating the registered socket of the signer.
+    pub fn update_socket_tx(&self, socket: String) -> Result<TransactionRequest> {
+        let input_data = self
+            .da_signers
+            .update_socket(socket)
+            .calldata()
+            .ok_or_else(|| anyhow!("cannot encode socket update"))?;
+        Ok(TransactionRequest::new()
+            .to(self.da_signers.address())
+            .data(input_data))
+    }
+
+    pub async fn fetch_assigned_slices(&self, epoch: u64) -> Result<Vec<AssignedSlices>> {
+        fetch_assigned_slices(&self.da_signers, self.signer_address, epoch).await
+    }
+}
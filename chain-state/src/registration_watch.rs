@@ -0,0 +1,76 @@
+/// Alert when less than 1 / `ALERT_FRACTION` of the epoch is left to register for the next one.
+const ALERT_FRACTION: u64 = 5;
+
+/// Tracks epoch boundaries to estimate how long is left to register for the next epoch. The epoch
+/// length is learnt from the last complete epoch observed, so nothing is estimated before the node
+/// has seen two epoch changes.
+#[derive(Default)]
+pub struct RegistrationWatch {
+    epoch: Option<u64>,
+    /// Block the current epoch was first seen at, unknown for the epoch the node started in.
+    epoch_start: Option<u64>,
+    epoch_blocks: Option<u64>,
+    alerted_epoch: Option<u64>,
+}
+
+impl RegistrationWatch {
+    /// Update with the current epoch at `block`, returns whether a new epoch started since the last
+    /// update.
+    pub fn on_epoch(&mut self, epoch: u64, block: u64) -> bool {
+        match self.epoch {
+            Some(current) if current == epoch => false,
+            Some(_) => {
+                if let Some(start) = self.epoch_start {
+                    self.epoch_blocks = Some(block.saturating_sub(start));
+                }
+                self.epoch = Some(epoch);
+                self.epoch_start = Some(block);
+                true
+            }
+            None => {
+                self.epoch = Some(epoch);
+                false
+            }
+        }
+    }
+
+    /// Estimated blocks left until the next epoch starts.
+    pub fn blocks_left(&self, block: u64) -> Option<u64> {
+        Some((self.epoch_start? + self.epoch_blocks?).saturating_sub(block))
+    }
+
+    /// Blocks left if the registration window of the next epoch is about to close and it is not
+    /// alerted yet for the current epoch.
+    pub fn should_alert(&mut self, block: u64) -> Option<u64> {
+        let blocks_left = self.blocks_left(block)?;
+        if self.alerted_epoch == self.epoch
+            || blocks_left * ALERT_FRACTION > self.epoch_blocks.unwrap_or_default()
+        {
+            return None;
+        }
+        self.alerted_epoch = self.epoch;
+        Some(blocks_left)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registration_watch_test() {
+        let mut watch = RegistrationWatch::default();
+        assert!(!watch.on_epoch(5, 1000));
+        assert!(watch.on_epoch(6, 1050));
+        // length of the epoch unknown yet
+        assert_eq!(watch.blocks_left(1060), None);
+        assert!(!watch.on_epoch(6, 1100));
+        assert!(watch.on_epoch(7, 1150));
+        assert_eq!(watch.blocks_left(1160), Some(90));
+        assert_eq!(watch.should_alert(1220), None);
+        assert_eq!(watch.should_alert(1235), Some(15));
+        assert_eq!(watch.should_alert(1240), None);
+        assert!(watch.on_epoch(8, 1250));
+        assert_eq!(watch.should_alert(1335), Some(15));
+    }
+}
@@ -0,0 +1,109 @@
+use anyhow::Result;
+use ark_bn254::{Bn254, Fr, G1Affine, G2Affine};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ethers::{types::H160, utils::keccak256};
+use utils::{left_pad_zeros, map_to_g1};
+
+use crate::ChainState;
+
+const RETRIEVAL_ENVELOPE_DOMAIN: &[u8] = "0G_DA_Retrieval_Envelope".as_bytes();
+
+/// What the serving node signs of a retrieval response: the blob identity, the digest of the
+/// returned content and when it was served.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvelopeMessage {
+    pub epoch: u64,
+    pub quorum_id: u64,
+    pub storage_root: [u8; 32],
+    pub content_digest: [u8; 32],
+    pub timestamp: u64,
+}
+
+fn envelope_hash(message: &EnvelopeMessage, signer_address: H160, chain_id: u64) -> G1Affine {
+    let mut bytes = RETRIEVAL_ENVELOPE_DOMAIN.to_vec();
+    bytes.append(&mut signer_address.to_fixed_bytes().to_vec());
+    bytes.append(&mut left_pad_zeros(chain_id, 32));
+    bytes.append(&mut left_pad_zeros(message.epoch, 32));
+    bytes.append(&mut left_pad_zeros(message.quorum_id, 32));
+    bytes.extend_from_slice(&message.storage_root);
+    bytes.extend_from_slice(&message.content_digest);
+    bytes.append(&mut left_pad_zeros(message.timestamp, 32));
+    map_to_g1(keccak256(bytes).to_vec())
+}
+
+pub fn sign_envelope(
+    key: Fr,
+    message: &EnvelopeMessage,
+    signer_address: H160,
+    chain_id: u64,
+) -> G1Affine {
+    (envelope_hash(message, signer_address, chain_id) * key).into_affine()
+}
+
+pub fn verify_envelope(
+    pub_key_g2: G2Affine,
+    signature: G1Affine,
+    message: &EnvelopeMessage,
+    signer_address: H160,
+    chain_id: u64,
+) -> bool {
+    Bn254::pairing(signature, G2Affine::generator())
+        == Bn254::pairing(envelope_hash(message, signer_address, chain_id), pub_key_g2)
+}
+
+impl ChainState {
+    /// Sign a retrieval response served by this node with the signer key.
+    pub fn sign_envelope(&self, key: Fr, message: &EnvelopeMessage) -> G1Affine {
+        sign_envelope(key, message, self.signer_address, self.chain_id)
+    }
+
+    /// Whether `signature` proves that the registered signer `signer_address` served `message`.
+    pub async fn verify_envelope(
+        &self,
+        signer_address: H160,
+        message: &EnvelopeMessage,
+        signature: G1Affine,
+    ) -> Result<bool> {
+        Ok(match self.registered_pub_key_g2(signer_address).await? {
+            Some(pub_key_g2) => verify_envelope(
+                pub_key_g2,
+                signature,
+                message,
+                signer_address,
+                self.chain_id,
+            ),
+            None => false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::g2;
+
+    use super::*;
+
+    #[test]
+    fn envelope_test() {
+        let key = Fr::from(7);
+        let pub_key = (g2::G2Affine::generator() * key).into_affine();
+        let signer = H160::from_low_u64_be(1);
+        let message = EnvelopeMessage {
+            epoch: 3,
+            quorum_id: 1,
+            storage_root: [1; 32],
+            content_digest: [2; 32],
+            timestamp: 1_700_000_000,
+        };
+        let signature = sign_envelope(key, &message, signer, 16600);
+        assert!(verify_envelope(pub_key, signature, &message, signer, 16600));
+        let tampered = EnvelopeMessage {
+            content_digest: [3; 32],
+            ..message
+        };
+        assert!(!verify_envelope(
+            pub_key, signature, &tampered, signer, 16600
+        ));
+        assert!(!verify_envelope(pub_key, signature, &message, signer, 1));
+    }
+}
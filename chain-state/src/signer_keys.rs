@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use ark_bn254::Fr;
+use tokio::sync::RwLock;
+
+/// BLS keys of the signer by the first epoch they sign. A rotated key only takes over from the
+/// rotation epoch, earlier epochs keep the key their quorums were formed with.
+#[derive(Clone)]
+pub struct SignerKeys {
+    keys: Arc<RwLock<Vec<(u64, Fr)>>>,
+}
+
+impl SignerKeys {
+    pub fn new(key: Fr) -> Self {
+        Self {
+            keys: Arc::new(RwLock::new(vec![(0, key)])),
+        }
+    }
+
+    pub async fn key_for_epoch(&self, epoch: u64) -> Fr {
+        let keys = self.keys.read().await;
+        keys.iter()
+            .rev()
+            .find(|(from_epoch, _)| *from_epoch <= epoch)
+            .unwrap_or(&keys[0])
+            .1
+    }
+
+    /// The latest key, which is the one registered on chain.
+    pub async fn latest(&self) -> Fr {
+        self.keys.read().await.last().unwrap().1
+    }
+
+    /// Sign with `key` from `from_epoch` on, replacing rotations scheduled from the same epoch or
+    /// later.
+    pub async fn rotate(&self, key: Fr, from_epoch: u64) {
+        let mut keys = self.keys.write().await;
+        while keys.len() > 1 && keys.last().unwrap().0 >= from_epoch {
+            keys.pop();
+        }
+        keys.push((from_epoch, key));
+    }
+
+    /// First epoch of the latest key, `None` if the key was never rotated.
+    pub async fn rotation_epoch(&self) -> Option<u64> {
+        let keys = self.keys.read().await;
+        (keys.len() > 1).then(|| keys.last().unwrap().0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn signer_keys_test() {
+        let keys = SignerKeys::new(Fr::from(1));
+        assert_eq!(keys.key_for_epoch(5).await, Fr::from(1));
+        assert_eq!(keys.rotation_epoch().await, None);
+
+        keys.rotate(Fr::from(2), 10).await;
+        assert_eq!(keys.key_for_epoch(9).await, Fr::from(1));
+        assert_eq!(keys.key_for_epoch(10).await, Fr::from(2));
+        assert_eq!(keys.rotation_epoch().await, Some(10));
+
+        keys.rotate(Fr::from(3), 12).await;
+        assert_eq!(keys.key_for_epoch(11).await, Fr::from(2));
+        assert_eq!(keys.key_for_epoch(12).await, Fr::from(3));
+
+        // a rotation scheduled earlier replaces the later ones
+        keys.rotate(Fr::from(4), 11).await;
+        assert_eq!(keys.key_for_epoch(10).await, Fr::from(2));
+        assert_eq!(keys.key_for_epoch(12).await, Fr::from(4));
+        assert_eq!(keys.rotation_epoch().await, Some(11));
+    }
+}
@@ -1,24 +1,39 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::{anyhow, bail, Result};
-use ark_bn254::{g1, g2, Fr, G1Affine, G2Affine};
-use ark_ec::{AffineRepr, CurveGroup};
+use ark_bn254::{g1, g2, Bn254, Fq, Fr, G1Affine, G2Affine};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
 
-use ark_serialize::CanonicalSerialize;
-use contract_interface::da_signers::{G1Point, G2Point, SignerDetail};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use contract_interface::{
+    da_signers::{G1Point, G2Point, RegisterSignerCall, SignerDetail, UpdateSocketCall},
+    DASigners,
+};
 
 use ethers::{
+    abi::AbiEncode,
     providers::Middleware,
     types::{BlockNumber, TransactionRequest, H160, U256},
     utils::keccak256,
 };
 
-use storage::quorum_db::{AssignedSlices, QuorumDB};
+use events::NodeEvent;
+use storage::{
+    misc_db::MiscDB,
+    quorum_db::AssignedSlices,
+    registration_db::{EpochRegistration, RegistrationDB, RegistrationStatus, SignerRegistration},
+};
 
 use tokio::time::sleep;
 use utils::{left_pad_zeros, map_to_g1};
 
-use crate::{transactor::TransactionInfo, ChainState};
+use crate::{
+    peer_auth::deserialize_g2_point, registration_watch::RegistrationWatch,
+    signer_keys::SignerKeys, transactor::TransactionInfo, ChainState,
+};
 
 const PUBKEY_REGISTRATION_DOMAIN: &[u8] = "0G_BN254_Pubkey_Registration".as_bytes();
 
@@ -40,6 +55,20 @@ pub fn serialize_g1_point(point: G1Affine) -> G1Point {
     G1Point { x, y }
 }
 
+/// The point of the coordinates of a contract `G1Point`, checked to be in the group.
+pub fn deserialize_g1_point(x: U256, y: U256) -> Result<G1Affine> {
+    let coordinate = |value: U256| -> Result<Fq> {
+        let mut bytes = vec![0u8; 32];
+        value.to_little_endian(&mut bytes);
+        Ok(Fq::deserialize_uncompressed(&*bytes)?)
+    };
+    let point = G1Affine::new_unchecked(coordinate(x)?, coordinate(y)?);
+    if !point.is_on_curve() || !point.is_in_correct_subgroup_assuming_on_curve() {
+        bail!(anyhow!("invalid G1 point"));
+    }
+    Ok(point)
+}
+
 pub fn serialize_g2_point(point: G2Affine) -> G2Point {
     let mut value: Vec<u8> = Vec::new();
     point
@@ -65,7 +94,60 @@ pub fn serialize_g2_point(point: G2Affine) -> G2Point {
     G2Point { x, y }
 }
 
-fn signer_registration_hash(signer_address: H160, chain_id: u64) -> G1Affine {
+/// Serialized G1 public key of a BLS private key, as registered on chain.
+pub fn bls_pub_key_g1(key: Fr) -> G1Point {
+    serialize_g1_point((g1::G1Affine::generator() * key).into_affine())
+}
+
+/// Serialized G2 public key of a BLS private key, as registered on chain.
+pub fn bls_pub_key_g2(key: Fr) -> G2Point {
+    serialize_g2_point((g2::G2Affine::generator() * key).into_affine())
+}
+
+/// Calldata of `registerSigner` for the BLS key of `signer_address`, signed for `chain_id`.
+pub fn signer_registration_calldata(
+    signer_bls_private_key: Fr,
+    signer_address: H160,
+    socket: String,
+    chain_id: u64,
+) -> Vec<u8> {
+    let signature = proof_of_possession(signer_bls_private_key, signer_address, chain_id);
+    RegisterSignerCall {
+        signer: SignerDetail {
+            signer: signer_address,
+            socket,
+            pk_g1: bls_pub_key_g1(signer_bls_private_key),
+            pk_g2: bls_pub_key_g2(signer_bls_private_key),
+        },
+        signature: serialize_g1_point(signature),
+    }
+    .encode()
+}
+
+/// Proof of possession of a BLS key: its signature of the registration message of the signer
+/// account, submitted with `registerSigner` so a key cannot be registered without its private key,
+/// which rules out rogue keys in aggregated signatures.
+pub fn proof_of_possession(key: Fr, signer_address: H160, chain_id: u64) -> G1Affine {
+    (signer_registration_hash(signer_address, chain_id) * key).into_affine()
+}
+
+/// Check a proof of possession of the G2 key of a signer, and that its G1 key is the same key.
+pub fn verify_proof_of_possession(
+    signer_address: H160,
+    chain_id: u64,
+    pk_g1: G1Affine,
+    pk_g2: G2Affine,
+    proof: G1Affine,
+) -> bool {
+    let hash = signer_registration_hash(signer_address, chain_id);
+    !pk_g2.is_zero()
+        && Bn254::pairing(proof, G2Affine::generator()) == Bn254::pairing(hash, pk_g2)
+        && Bn254::pairing(pk_g1, G2Affine::generator())
+            == Bn254::pairing(G1Affine::generator(), pk_g2)
+}
+
+/// `keccak256(signer_address ++ chain_id ++ "0G_BN254_Pubkey_Registration")` mapped to G1.
+pub fn signer_registration_hash(signer_address: H160, chain_id: u64) -> G1Affine {
     let mut message = vec![];
     message.append(&mut signer_address.to_fixed_bytes().to_vec());
     message.append(&mut left_pad_zeros(chain_id, 32));
@@ -81,125 +163,342 @@ fn epoch_registration_hash(signer_address: H160, epoch: u64, chain_id: u64) -> G
     map_to_g1(keccak256(message).to_vec())
 }
 
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
 impl ChainState {
+    /// Register the signer if it is not, or update its registered socket to `socket` if it
+    /// changed. The outcome is recorded for the admin API, a failed socket update is not fatal.
     pub async fn check_signer_registration(
         &self,
         signer_bls_private_key: Fr,
         socket: String,
     ) -> Result<()> {
+        let mut registration = SignerRegistration {
+            registered: true,
+            socket: Some(socket.clone()),
+            configured_socket: socket.clone(),
+            checked_at: now(),
+            last_error: None,
+        };
+        if !self
+            .da_signers
+            .is_signer(self.signer_address)
+            .call()
+            .await?
+        {
+            if self.register_signer(signer_bls_private_key, socket).await? {
+                info!("signer registered");
+                self.record_signer_registration(&registration).await;
+                return Ok(());
+            }
+            registration.registered = false;
+            registration.socket = None;
+            registration.last_error = Some("register signer failed".to_string());
+            self.record_signer_registration(&registration).await;
+            bail!(anyhow!("register signer failed"));
+        }
+        if let Err(e) = self.update_socket_if_changed(&mut registration).await {
+            warn!("cannot update the registered socket: {:?}", e);
+            registration.last_error = Some(e.to_string());
+        }
+        self.record_signer_registration(&registration).await;
+        Ok(())
+    }
+
+    /// Fail if the signer account is registered with a BLS key other than `keys`, the ETH and BLS
+    /// keys of the config then belong to different operators. An unregistered account passes.
+    pub async fn verify_registered_operator(&self, keys: &[Fr]) -> Result<()> {
         if !self
             .da_signers
             .is_signer(self.signer_address)
             .call()
             .await?
         {
-            let signer_pub_key_g1 =
-                (g1::G1Affine::generator() * signer_bls_private_key).into_affine();
-            let signer_pub_key_g2 =
-                (g2::G2Affine::generator() * signer_bls_private_key).into_affine();
-            let hash = signer_registration_hash(
-                self.signer_address,
-                self.provider.get_chainid().await?.as_u64(),
+            return Ok(());
+        }
+        let detail = self
+            .da_signers
+            .get_signer(vec![self.signer_address])
+            .call()
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("signer {:?} not found", self.signer_address))?;
+        if !keys.iter().any(|key| detail.pk_g1 == bls_pub_key_g1(*key)) {
+            bail!(anyhow!(
+                "signer account {:?} is registered with another BLS key, the ETH and BLS keys of the config are not the same operator",
+                self.signer_address
+            ));
+        }
+        Ok(())
+    }
+
+    /// Send `updateSocket` if the registered socket differs from the configured one.
+    async fn update_socket_if_changed(&self, registration: &mut SignerRegistration) -> Result<()> {
+        let detail = self
+            .da_signers
+            .get_signer(vec![self.signer_address])
+            .call()
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("signer {:?} not found", self.signer_address))?;
+        registration.socket = Some(detail.socket.clone());
+        if detail.socket == registration.configured_socket {
+            return Ok(());
+        }
+        info!(
+            "registered socket {:?} differs from socket_address {:?}, updating it",
+            detail.socket, registration.configured_socket
+        );
+        let tx_request = TransactionRequest::new()
+            .to(self.da_signers.address())
+            .data(
+                UpdateSocketCall {
+                    socket: registration.configured_socket.clone(),
+                }
+                .encode(),
             );
-            let signature = (hash * signer_bls_private_key).into_affine();
-            let maybe_input_data = self
-                .da_signers
-                .register_signer(
-                    SignerDetail {
-                        signer: self.signer_address,
-                        socket: socket.clone(),
-                        pk_g1: serialize_g1_point(signer_pub_key_g1),
-                        pk_g2: serialize_g2_point(signer_pub_key_g2),
-                    },
-                    serialize_g1_point(signature),
-                )
-                .calldata();
-            if let Some(input_data) = maybe_input_data {
-                info!(
-                    "try to register signer: account {:?}, pubkey g1 {:?}, pubkey g2: {:?}, socket: {:?}",
+        if !self
+            .transactor
+            .lock()
+            .await
+            .send(
+                tx_request,
+                TransactionInfo::UpdateSocket(
                     self.signer_address,
-                    signer_pub_key_g1,
-                    signer_pub_key_g2,
-                    socket,
-                );
-                let tx_request = TransactionRequest::new()
-                    .to(self.da_signers.address())
-                    .data(input_data);
-                match self
-                    .transactor
-                    .lock()
-                    .await
-                    .send(
-                        tx_request,
-                        TransactionInfo::RegisterSigner(self.signer_address),
-                    )
-                    .await
-                {
-                    Ok(success) => {
-                        if success {
-                            info!("signer registered");
-                            return Ok(());
-                        }
-                        bail!(anyhow!("register signer failed"));
-                    }
-                    Err(e) => {
-                        bail!(anyhow!(e));
-                    }
-                }
-            }
+                    registration.configured_socket.clone(),
+                ),
+            )
+            .await?
+        {
+            bail!(anyhow!("update socket transaction failed"));
         }
+        info!("registered socket updated");
+        registration.socket = Some(registration.configured_socket.clone());
         Ok(())
     }
 
-    pub async fn fetch_quorum_if_missing(&self, epoch: u64) -> Result<u64> {
-        let maybe_quorum_num = self.db.read().await.get_quorum_num(epoch).await?;
-        match maybe_quorum_num {
-            Some(cnt) => Ok(cnt),
-            None => {
-                info!("updating quorums of epoch: {:?}", epoch);
-                let quorum_cnt = (self
-                    .da_signers
-                    .quorum_count(U256::from(epoch))
-                    .call()
-                    .await?)
-                    .as_u32() as i32;
-                let mut assigned = vec![];
-                for i in 0..quorum_cnt {
-                    let quorum = self
-                        .da_signers
-                        .get_quorum(U256::from(epoch), U256::from(i))
-                        .call()
-                        .await?;
-                    let assigned_slices: Vec<u64> = quorum
-                        .into_iter()
-                        .enumerate()
-                        .filter(|&(_, signer)| signer == self.signer_address)
-                        .map(|(idx, _)| idx as u64)
-                        .collect();
-                    assigned.push(AssignedSlices(assigned_slices));
+    async fn record_signer_registration(&self, registration: &SignerRegistration) {
+        if let Err(e) = self.db.put_signer_registration(registration).await {
+            warn!("cannot record signer registration: {:?}", e);
+        }
+    }
+
+    /// Rotate the signer key to `new_key` by registering the signer again with it. The old key
+    /// keeps signing until the first epoch the node registers for with the new key, which is
+    /// persisted so a restart in between keeps serving with both keys.
+    pub async fn rotate_signer_key(
+        &self,
+        keys: &SignerKeys,
+        old_key: Fr,
+        new_key: Fr,
+    ) -> Result<()> {
+        let detail = self
+            .da_signers
+            .get_signer(vec![self.signer_address])
+            .call()
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("signer {:?} not found", self.signer_address))?;
+        if detail.pk_g1 == bls_pub_key_g1(new_key) {
+            match self.db.get_key_rotation_epoch().await? {
+                Some(epoch) => keys.rotate(new_key, epoch).await,
+                None => {
+                    warn!(
+                        "signer key already rotated on chain, signing every epoch with the new key"
+                    );
+                    keys.rotate(new_key, 0).await
                 }
-                self.db.write().await.put_quorums(epoch, assigned).await?;
-                Ok(quorum_cnt as u64)
             }
+            return Ok(());
+        }
+        if detail.pk_g1 != bls_pub_key_g1(old_key) {
+            bail!(anyhow!(
+                "on-chain key of signer {:?} matches neither signer_bls_private_key nor new_signer_bls_private_key",
+                self.signer_address
+            ));
+        }
+
+        info!("rotating signer key of account {:?}", self.signer_address);
+        // persisted before the registration is sent, so that a restart after it lands still
+        // signs the epochs already registered for with the old key
+        let from_epoch = self.key_rotation_epoch().await?;
+        self.db.put_key_rotation_epoch(from_epoch).await?;
+        let registered = self.register_signer(new_key, detail.socket).await;
+        if !matches!(registered, Ok(true)) {
+            if let Err(e) = self.db.delete_key_rotation_epoch().await {
+                warn!("cannot delete key rotation epoch: {:?}", e);
+            }
+        }
+        if !registered? {
+            bail!(anyhow!(
+                "re-register signer with the new key failed, the DASigners contract may not accept key updates"
+            ));
         }
+        // the epoch may have advanced while the registration was pending
+        let from_epoch = from_epoch.max(self.key_rotation_epoch().await?);
+        self.db.put_key_rotation_epoch(from_epoch).await?;
+        keys.rotate(new_key, from_epoch).await;
+        info!(
+            "signer key rotated, the new key signs from epoch {:?}",
+            from_epoch
+        );
+        Ok(())
+    }
+
+    /// First epoch a key registered now signs, the epochs already registered for keep the old key.
+    async fn key_rotation_epoch(&self) -> Result<u64> {
+        let epoch = self.da_signers.epoch_number().call().await?.as_u64();
+        let registered = self
+            .da_signers
+            .registered_epoch(self.signer_address, U256::from(epoch + 1))
+            .call()
+            .await?;
+        Ok(if registered { epoch + 2 } else { epoch + 1 })
+    }
+
+    /// Send the signer registration with `signer_bls_private_key`, returns whether it succeeded.
+    /// The keys registered on chain are then checked against the proof of possession sent.
+    async fn register_signer(&self, signer_bls_private_key: Fr, socket: String) -> Result<bool> {
+        let chain_id = self.provider.get_chainid().await?.as_u64();
+        let input_data = signer_registration_calldata(
+            signer_bls_private_key,
+            self.signer_address,
+            socket.clone(),
+            chain_id,
+        );
+        info!(
+            "try to register signer: account {:?}, pubkey g1 {:?}, pubkey g2: {:?}, socket: {:?}",
+            self.signer_address,
+            bls_pub_key_g1(signer_bls_private_key),
+            bls_pub_key_g2(signer_bls_private_key),
+            socket,
+        );
+        let tx_request = TransactionRequest::new()
+            .to(self.da_signers.address())
+            .data(input_data);
+        let registered = self
+            .transactor
+            .lock()
+            .await
+            .send(
+                tx_request,
+                TransactionInfo::RegisterSigner(self.signer_address),
+            )
+            .await?;
+        if registered {
+            self.check_registered_key(signer_bls_private_key, chain_id)
+                .await?;
+        }
+        Ok(registered)
+    }
+
+    /// Check the keys returned by `getSigner` against the proof of possession of
+    /// `signer_bls_private_key`, so a registration the contract stored differently is caught.
+    async fn check_registered_key(&self, signer_bls_private_key: Fr, chain_id: u64) -> Result<()> {
+        let detail = self
+            .da_signers
+            .get_signer(vec![self.signer_address])
+            .call()
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow!("signer {:?} not found", self.signer_address))?;
+        if !verify_proof_of_possession(
+            self.signer_address,
+            chain_id,
+            deserialize_g1_point(detail.pk_g1.x, detail.pk_g1.y)?,
+            deserialize_g2_point(&detail.pk_g2)?,
+            proof_of_possession(signer_bls_private_key, self.signer_address, chain_id),
+        ) {
+            bail!(anyhow!(
+                "keys registered on chain for signer {:?} do not match the proof of possession of the signer key",
+                self.signer_address
+            ));
+        }
+        Ok(())
     }
 }
 
-pub fn start_epoch_registration(chain_state: Arc<ChainState>, signer_bls_private_key: Fr) {
-    tokio::spawn(async move {
-        loop {
-            match check_epoch(chain_state.clone(), signer_bls_private_key).await {
-                Ok(_) => {}
-                Err(e) => {
-                    error!("poll check_new_epoch error: {:?}", e);
-                }
+/// Slices assigned to `signer` in every quorum of `epoch`, from the on-chain registry.
+pub async fn fetch_assigned_slices<M: Middleware>(
+    da_signers: &DASigners<M>,
+    signer: H160,
+    epoch: u64,
+) -> Result<Vec<AssignedSlices>> {
+    let quorum_cnt = da_signers
+        .quorum_count(U256::from(epoch))
+        .call()
+        .await
+        .map_err(|e| anyhow!("Cannot query quorums: {:?}", e))?
+        .as_u64();
+    let mut assigned = vec![];
+    for i in 0..quorum_cnt {
+        let quorum = da_signers
+            .get_quorum(U256::from(epoch), U256::from(i))
+            .call()
+            .await
+            .map_err(|e| anyhow!("Cannot query quorum: {:?}", e))?;
+        let assigned_slices: Vec<u64> = quorum
+            .into_iter()
+            .enumerate()
+            .filter(|&(_, x)| x == signer)
+            .map(|(idx, _)| idx as u64)
+            .collect();
+        assigned.push(AssignedSlices(assigned_slices));
+    }
+    Ok(assigned)
+}
+
+impl ChainState {
+    /// Sockets of the other signers of a quorum, from the on-chain registry.
+    pub async fn get_quorum_sockets(&self, epoch: u64, quorum_id: u64) -> Result<Vec<String>> {
+        let mut signers = self
+            .da_signers
+            .get_quorum(U256::from(epoch), U256::from(quorum_id))
+            .call()
+            .await?;
+        signers.sort();
+        signers.dedup();
+        signers.retain(|signer| *signer != self.signer_address);
+        if signers.is_empty() {
+            return Ok(vec![]);
+        }
+        let details = self.da_signers.get_signer(signers).call().await?;
+        Ok(details.into_iter().map(|detail| detail.socket).collect())
+    }
+}
+
+/// Register for every next epoch. With `wait_sync`, registration waits until the DA entrance logs
+/// are synced, so the node does not take assignments it cannot honor yet.
+pub async fn run_epoch_registration(
+    chain_state: Arc<ChainState>,
+    signer_keys: SignerKeys,
+    wait_sync: bool,
+) -> Result<()> {
+    if wait_sync && !chain_state.sync_progress.is_synced() {
+        info!("epoch registration waits for da entrance logs to sync");
+        chain_state.sync_progress.wait_synced().await;
+    }
+    let mut watch = RegistrationWatch::default();
+    loop {
+        match check_epoch(chain_state.clone(), &signer_keys, &mut watch).await {
+            Ok(_) => {}
+            Err(e) => {
+                error!("poll check_new_epoch error: {:?}", e);
             }
-            sleep(Duration::from_secs(5)).await;
         }
-    });
+        sleep(Duration::from_secs(5)).await;
+    }
 }
 
-async fn check_epoch(chain_state: Arc<ChainState>, signer_bls_private_key: Fr) -> Result<()> {
+async fn check_epoch(
+    chain_state: Arc<ChainState>,
+    signer_keys: &SignerKeys,
+    watch: &mut RegistrationWatch,
+) -> Result<()> {
     match chain_state
         .provider
         .get_block(BlockNumber::Finalized)
@@ -215,9 +514,28 @@ async fn check_epoch(chain_state: Arc<ChainState>, signer_bls_private_key: Fr) -
                     .await?)
                     .as_u64();
                 check_new_quorums(chain_state.clone(), epoch).await?;
-                check_new_registration(chain_state.clone(), signer_bls_private_key, epoch + 1)
-                    .await?;
-                Ok(())
+                if watch.on_epoch(epoch, bn.as_u64()) {
+                    check_missed_registration(&chain_state, epoch).await?;
+                }
+                let signer_bls_private_key = signer_keys.key_for_epoch(epoch + 1).await;
+                let res =
+                    check_new_registration(chain_state.clone(), signer_bls_private_key, epoch + 1)
+                        .await;
+                if res.is_err() {
+                    if let Some(blocks_left) = watch.should_alert(bn.as_u64()) {
+                        error!(
+                            target: "alert",
+                            "registration for epoch {:?} still fails and its window closes in about {:?} blocks",
+                            epoch + 1,
+                            blocks_left
+                        );
+                        chain_state.events.publish(NodeEvent::RegistrationAtRisk {
+                            epoch: epoch + 1,
+                            blocks_left,
+                        });
+                    }
+                }
+                res
             } else {
                 bail!(anyhow!("block number is empty"));
             }
@@ -233,12 +551,20 @@ async fn check_new_registration(
     signer_bls_private_key: Fr,
     next_epoch: u64,
 ) -> Result<()> {
-    if !chain_state
+    if chain_state
         .da_signers
         .registered_epoch(chain_state.signer_address, U256::from(next_epoch))
         .call()
         .await?
     {
+        record_registration(
+            &chain_state,
+            next_epoch,
+            RegistrationStatus::REGISTERED,
+            None,
+        )
+        .await;
+    } else {
         info!("registering for next epoch: {:?}", next_epoch);
         let hash = epoch_registration_hash(
             chain_state.signer_address,
@@ -259,7 +585,8 @@ async fn check_new_registration(
             let tx_request = TransactionRequest::new()
                 .to(chain_state.da_signers.address())
                 .data(input_data);
-            match chain_state
+            record_registration(&chain_state, next_epoch, RegistrationStatus::PENDING, None).await;
+            let res = chain_state
                 .transactor
                 .lock()
                 .await
@@ -267,16 +594,29 @@ async fn check_new_registration(
                     tx_request,
                     TransactionInfo::RegisterEpoch(chain_state.signer_address, next_epoch),
                 )
-                .await
-            {
-                Ok(success) => {
-                    if success {
-                        info!("epoch {:?} registered", next_epoch);
-                        return Ok(());
-                    }
-                    bail!(anyhow!(format!("register epoch {:?} failed", next_epoch)));
+                .await;
+            match res {
+                Ok(true) => {
+                    info!("epoch {:?} registered", next_epoch);
+                    record_registration(
+                        &chain_state,
+                        next_epoch,
+                        RegistrationStatus::REGISTERED,
+                        None,
+                    )
+                    .await;
+                    chain_state
+                        .events
+                        .publish(NodeEvent::RegistrationSucceeded { epoch: next_epoch });
+                    return Ok(());
+                }
+                Ok(false) => {
+                    let error = format!("register epoch {:?} failed", next_epoch);
+                    record_registration_failure(&chain_state, next_epoch, error.clone()).await;
+                    bail!(anyhow!(error));
                 }
                 Err(e) => {
+                    record_registration_failure(&chain_state, next_epoch, e.to_string()).await;
                     bail!(anyhow!(e));
                 }
             }
@@ -285,8 +625,85 @@ async fn check_new_registration(
     Ok(())
 }
 
+/// Mark the registration of the epoch just started as missed if the signer is not registered for it.
+async fn check_missed_registration(chain_state: &ChainState, epoch: u64) -> Result<()> {
+    if chain_state
+        .da_signers
+        .registered_epoch(chain_state.signer_address, U256::from(epoch))
+        .call()
+        .await?
+    {
+        return Ok(());
+    }
+    error!(
+        target: "alert",
+        "epoch {:?} started without the signer registered for it", epoch
+    );
+    record_registration(chain_state, epoch, RegistrationStatus::MISSED, None).await;
+    chain_state
+        .events
+        .publish(NodeEvent::RegistrationMissed { epoch });
+    Ok(())
+}
+
+async fn record_registration_failure(chain_state: &ChainState, epoch: u64, error: String) {
+    record_registration(
+        chain_state,
+        epoch,
+        RegistrationStatus::FAILED,
+        Some(error.clone()),
+    )
+    .await;
+    chain_state
+        .events
+        .publish(NodeEvent::RegistrationFailed { epoch, error });
+}
+
+/// Update the registration record of `epoch`, a `PENDING` update counts a sent transaction.
+async fn record_registration(
+    chain_state: &ChainState,
+    epoch: u64,
+    status: RegistrationStatus,
+    error: Option<String>,
+) {
+    let db = &chain_state.db;
+    let previous = match db.get_epoch_registration(epoch).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            warn!("cannot read epoch registration: {:?}", e);
+            return;
+        }
+    };
+    if status == RegistrationStatus::REGISTERED
+        && previous.as_ref().map(|x| x.status) == Some(RegistrationStatus::REGISTERED)
+    {
+        return;
+    }
+    let mut registration = previous.unwrap_or(EpochRegistration {
+        epoch,
+        status,
+        attempts: 0,
+        last_error: None,
+        updated_at: 0,
+    });
+    if status == RegistrationStatus::PENDING {
+        registration.attempts += 1;
+    }
+    registration.status = status;
+    if error.is_some() {
+        registration.last_error = error;
+    }
+    registration.updated_at = now();
+    if let Err(e) = db.put_epoch_registration(&registration).await {
+        warn!("cannot record epoch registration: {:?}", e);
+    }
+}
+
+/// Install the quorums of the current epoch, and of the next one once formed so they are in
+/// place before it starts.
 async fn check_new_quorums(chain_state: Arc<ChainState>, epoch: u64) -> Result<()> {
     chain_state.fetch_quorum_if_missing(epoch).await?;
+    chain_state.fetch_quorum_if_missing(epoch + 1).await?;
     Ok(())
 }
 
@@ -295,6 +712,32 @@ mod tests {
     use super::*;
     use std::str::FromStr;
 
+    #[test]
+    fn proof_of_possession_test() {
+        let key = Fr::from(5);
+        let signer = H160::repeat_byte(1);
+        let pk_g1 = (G1Affine::generator() * key).into_affine();
+        let pk_g2 = (G2Affine::generator() * key).into_affine();
+        let proof = proof_of_possession(key, signer, 16600);
+        assert!(verify_proof_of_possession(
+            signer, 16600, pk_g1, pk_g2, proof
+        ));
+        // bound to the account and the chain
+        assert!(!verify_proof_of_possession(
+            H160::repeat_byte(2),
+            16600,
+            pk_g1,
+            pk_g2,
+            proof
+        ));
+        assert!(!verify_proof_of_possession(signer, 1, pk_g1, pk_g2, proof));
+        // the G1 key of another key
+        let other = (G1Affine::generator() * Fr::from(6)).into_affine();
+        assert!(!verify_proof_of_possession(
+            signer, 16600, other, pk_g2, proof
+        ));
+    }
+
     #[test]
     fn serialize_g1_point_test() {
         let point = G1Affine::new(
@@ -324,5 +767,10 @@ mod tests {
             )
             .unwrap()
         );
+        assert_eq!(
+            deserialize_g1_point(serialized.x, serialized.y).unwrap(),
+            point
+        );
+        assert!(deserialize_g1_point(serialized.x, serialized.x).is_err());
     }
 }
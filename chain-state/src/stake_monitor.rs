@@ -0,0 +1,150 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use ethers::types::U256;
+use events::NodeEvent;
+use tokio::{sync::RwLock, time::sleep};
+
+use crate::ChainState;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Alerting on the stake of the signer.
+#[derive(Debug, Clone)]
+pub struct StakeMonitorConfig {
+    /// Quorum rows the signer must be assigned in an epoch, fewer raise an alert.
+    pub min_assigned_rows: u64,
+}
+
+impl Default for StakeMonitorConfig {
+    fn default() -> Self {
+        Self {
+            min_assigned_rows: 1,
+        }
+    }
+}
+
+/// Weight of the signer in the quorums of an epoch. Quorum rows are assigned in proportion to
+/// the stake delegated to the signer, so they are the stake as it counts for DA.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StakeStatus {
+    pub epoch: u64,
+    pub quorums: u64,
+    /// Rows of all quorums assigned to the signer.
+    pub assigned_rows: u64,
+    /// Rows of all quorums.
+    pub total_rows: u64,
+    /// Quorums without a row assigned to the signer.
+    pub quorums_without_rows: u64,
+    /// Assigned rows of the epoch checked before, `None` for the first one.
+    pub previous_assigned_rows: Option<u64>,
+    pub below_threshold: bool,
+}
+
+/// The latest stake checked, shared with the admin API.
+#[derive(Clone, Default)]
+pub struct StakeMonitor {
+    status: Arc<RwLock<Option<StakeStatus>>>,
+}
+
+impl StakeMonitor {
+    pub async fn status(&self) -> Option<StakeStatus> {
+        self.status.read().await.clone()
+    }
+}
+
+/// Check the stake of the signer in every epoch once its quorums are formed, the next epoch
+/// first so a drop is noticed before it starts.
+pub async fn run_stake_monitor(
+    chain_state: Arc<ChainState>,
+    monitor: StakeMonitor,
+    config: StakeMonitorConfig,
+) -> Result<()> {
+    loop {
+        if let Err(e) = check_stake(&chain_state, &monitor, &config).await {
+            warn!("cannot check signer stake: {:?}", e);
+        }
+        sleep(POLL_INTERVAL).await;
+    }
+}
+
+async fn quorum_count(chain_state: &ChainState, epoch: u64) -> Result<u64> {
+    Ok(chain_state
+        .da_signers
+        .quorum_count(U256::from(epoch))
+        .call()
+        .await?
+        .as_u64())
+}
+
+async fn check_stake(
+    chain_state: &ChainState,
+    monitor: &StakeMonitor,
+    config: &StakeMonitorConfig,
+) -> Result<()> {
+    let mut epoch = chain_state.da_signers.epoch_number().call().await?.as_u64() + 1;
+    let mut quorums = quorum_count(chain_state, epoch).await?;
+    if quorums == 0 {
+        epoch -= 1;
+        quorums = quorum_count(chain_state, epoch).await?;
+    }
+    let previous = monitor.status().await;
+    if previous.as_ref().map_or(false, |x| x.epoch == epoch) {
+        return Ok(());
+    }
+    let mut status = StakeStatus {
+        epoch,
+        quorums,
+        previous_assigned_rows: previous.map(|x| x.assigned_rows),
+        ..Default::default()
+    };
+    for quorum_id in 0..quorums {
+        let rows = chain_state
+            .da_signers
+            .get_quorum(U256::from(epoch), U256::from(quorum_id))
+            .call()
+            .await?;
+        let assigned = rows
+            .iter()
+            .filter(|x| **x == chain_state.signer_address)
+            .count() as u64;
+        status.assigned_rows += assigned;
+        status.total_rows += rows.len() as u64;
+        if assigned == 0 {
+            status.quorums_without_rows += 1;
+        }
+    }
+    status.below_threshold = status.assigned_rows < config.min_assigned_rows;
+    info!(
+        epoch,
+        quorums,
+        assigned_rows = status.assigned_rows,
+        total_rows = status.total_rows,
+        "signer stake checked"
+    );
+    if status.below_threshold {
+        error!(
+            target: "alert",
+            "signer is assigned {:?} quorum rows in epoch {:?}, below the threshold of {:?}, check its stake and delegations",
+            status.assigned_rows,
+            epoch,
+            config.min_assigned_rows
+        );
+        chain_state.events.publish(NodeEvent::StakeBelowThreshold {
+            epoch,
+            assigned_rows: status.assigned_rows,
+        });
+    } else if let Some(previous) = status
+        .previous_assigned_rows
+        .filter(|x| status.assigned_rows < *x)
+    {
+        warn!(
+            epoch,
+            previous,
+            assigned_rows = status.assigned_rows,
+            "signer is assigned fewer quorum rows than in the previous epoch"
+        );
+    }
+    *monitor.status.write().await = Some(status);
+    Ok(())
+}
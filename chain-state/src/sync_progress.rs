@@ -0,0 +1,152 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use tokio::sync::{watch, RwLock};
+
+const LOG_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncStatus {
+    /// First block processed since the node started.
+    pub start_block: u64,
+    /// Next block to process.
+    pub current_block: u64,
+    /// Finalized head the node is catching up to.
+    pub target_block: u64,
+    /// DA entrance events processed since the node started.
+    pub events_processed: u64,
+    pub synced: bool,
+    pub eta: Option<Duration>,
+}
+
+#[derive(Default)]
+struct SyncProgressInner {
+    status: SyncStatus,
+    started_at: Option<Instant>,
+    logged_at: Option<Instant>,
+}
+
+/// Progress of the DA entrance log sync, the node is synced once it caught up with the finalized
+/// head.
+#[derive(Clone)]
+pub struct SyncProgress {
+    inner: Arc<RwLock<SyncProgressInner>>,
+    synced: Arc<watch::Sender<bool>>,
+}
+
+impl Default for SyncProgress {
+    fn default() -> Self {
+        let (synced, _) = watch::channel(false);
+        Self {
+            inner: Default::default(),
+            synced: Arc::new(synced),
+        }
+    }
+}
+
+impl SyncProgress {
+    pub async fn status(&self) -> SyncStatus {
+        self.inner.read().await.status.clone()
+    }
+
+    pub fn is_synced(&self) -> bool {
+        *self.synced.borrow()
+    }
+
+    pub async fn wait_synced(&self) {
+        let mut synced = self.synced.subscribe();
+        while !*synced.borrow_and_update() {
+            // the sender lives as long as the progress
+            let _ = synced.changed().await;
+        }
+    }
+
+    /// Record that blocks before `current_block` are processed with `events` more events, while the
+    /// finalized head is `target_block`.
+    pub(crate) async fn update(&self, current_block: u64, target_block: u64, events: u64) {
+        let mut inner = self.inner.write().await;
+        let now = Instant::now();
+        let started_at = match inner.started_at {
+            Some(started_at) => started_at,
+            None => {
+                inner.status.start_block = current_block;
+                *inner.started_at.insert(now)
+            }
+        };
+        let status = &mut inner.status;
+        status.current_block = current_block;
+        status.target_block = target_block;
+        status.events_processed += events;
+        // new blocks are always behind once caught up
+        let synced = self.is_synced() || current_block > target_block;
+        status.eta = if synced {
+            Some(Duration::ZERO)
+        } else {
+            estimate_eta(
+                current_block - status.start_block,
+                now - started_at,
+                target_block + 1 - current_block,
+            )
+        };
+        let status = status.clone();
+        if !synced && inner.logged_at.map_or(true, |t| now - t >= LOG_INTERVAL) {
+            inner.logged_at = Some(now);
+            info!(
+                current_block = status.current_block,
+                target_block = status.target_block,
+                events_processed = status.events_processed,
+                eta_secs = status.eta.map(|eta| eta.as_secs()),
+                "syncing da entrance logs"
+            );
+        }
+        inner.status.synced = synced;
+        if synced && !self.synced.send_replace(true) {
+            info!(
+                current_block = status.current_block,
+                events_processed = status.events_processed,
+                "da entrance logs synced"
+            );
+        }
+    }
+}
+
+/// Remaining time at the average rate so far, `None` before any progress.
+fn estimate_eta(done_blocks: u64, elapsed: Duration, remaining_blocks: u64) -> Option<Duration> {
+    if done_blocks == 0 {
+        return None;
+    }
+    Some(elapsed.mul_f64(remaining_blocks as f64 / done_blocks as f64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_eta_test() {
+        assert_eq!(estimate_eta(0, Duration::from_secs(10), 100), None);
+        assert_eq!(
+            estimate_eta(50, Duration::from_secs(10), 100),
+            Some(Duration::from_secs(20))
+        );
+    }
+
+    #[tokio::test]
+    async fn sync_progress_test() {
+        let progress = SyncProgress::default();
+        progress.update(100, 1000, 3).await;
+        progress.update(500, 1000, 2).await;
+        let status = progress.status().await;
+        assert_eq!(status.start_block, 100);
+        assert_eq!(status.events_processed, 5);
+        assert!(!status.synced && !progress.is_synced());
+
+        progress.update(1001, 1000, 0).await;
+        assert!(progress.is_synced());
+        progress.update(1001, 1010, 0).await;
+        progress.wait_synced().await;
+        assert_eq!(progress.status().await.eta, Some(Duration::ZERO));
+    }
+}
@@ -1,34 +1,49 @@
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
 use anyhow::{anyhow, bail, Result};
 
-use chain_utils::DefaultMiddleware;
-use ethers::types::H160;
-use ethers::{
-    providers::Middleware,
-    signers::{LocalWallet, Signer},
-    types::TransactionRequest,
+use chain_utils::nonce_manager::NonceManager;
+use ethers::types::{transaction::eip2718::TypedTransaction, TransactionRequest};
+use ethers::types::{H160, H256};
+use storage::{
+    tx_history_db::{TxAttempt, TxHistoryDB, TxOutcome},
+    Storage,
 };
 
 #[derive(Debug, Clone)]
 pub enum TransactionInfo {
     RegisterSigner(H160),
     RegisterEpoch(H160, u64),
+    UpdateSocket(H160, String),
 }
 
 pub struct Transactor {
-    signer: LocalWallet,
-    client: DefaultMiddleware,
+    nonce_manager: NonceManager,
+    /// Keeps the outcome of every transaction for the admin API.
+    db: Arc<Storage>,
+    /// Refuse to send any transaction, for archive nodes.
+    read_only: bool,
 }
 
 impl Transactor {
-    pub fn new(middleware: DefaultMiddleware) -> Result<Self> {
+    pub fn new(nonce_manager: NonceManager, db: Arc<Storage>) -> Result<Self> {
         Ok(Self {
-            signer: middleware.signer().clone(),
-            client: middleware,
+            nonce_manager,
+            db,
+            read_only: false,
         })
     }
 
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
     pub fn signer_address(&self) -> H160 {
-        self.signer.address()
+        self.nonce_manager.address()
     }
 
     // return continue(true) or break(false)
@@ -40,7 +55,7 @@ impl Transactor {
         if e_str.contains("insufficient funds for transfer") {
             warn!(
                 "sender {:?} balance is insufficient.",
-                self.signer.address()
+                self.signer_address()
             );
             return false;
         }
@@ -56,40 +71,79 @@ impl Transactor {
         tx_no_sender: TransactionRequest,
         tx_info: TransactionInfo,
     ) -> Result<bool> {
-        let tx = tx_no_sender.clone().from(self.signer.address());
+        if self.read_only {
+            bail!(anyhow!(
+                "transactions are disabled in archive mode, {:?} not sent",
+                tx_info
+            ));
+        }
+        let tx = TypedTransaction::Legacy(tx_no_sender);
         loop {
-            match self.client.send_transaction(tx.clone(), None).await {
-                Ok(pending_tx) => {
-                    let hash = pending_tx.tx_hash();
+            // fees are estimated again on every resend
+            match self.nonce_manager.send(tx.clone()).await {
+                Ok(sent) => {
+                    let mut hash = sent.hash;
                     info!(
                         "new transaction sent with hash {:?}, tx_info: {:?}",
                         hash, tx_info,
                     );
                     let mut status = 2;
-                    match pending_tx.await {
-                        Ok(maybe_receipt) => {
-                            if let Some(receipt) = maybe_receipt {
-                                if let Some(x) = receipt.status {
-                                    status = x.as_u32();
-                                }
+                    let block_number = match self.nonce_manager.confirm(sent).await {
+                        Ok(Some(receipt)) => {
+                            hash = receipt.transaction_hash;
+                            if let Some(x) = receipt.status {
+                                status = x.as_u32();
                             }
+                            receipt.block_number.map(|n| n.as_u64())
+                        }
+                        Ok(None) => {
+                            info!("transaction {:?} dropped, its nonce is used.", hash);
+                            self.record(&tx_info, Some(hash), TxOutcome::DROPPED, None)
+                                .await;
+                            return Ok(false);
                         }
                         Err(e) => {
                             info!("transaction {:?} error: {:?}", hash, e);
+                            self.record(
+                                &tx_info,
+                                Some(hash),
+                                TxOutcome::FAILED,
+                                Some(e.to_string()),
+                            )
+                            .await;
+                            return Ok(true);
                         }
-                    }
+                    };
                     match status {
                         0 => {
-                            info!("transaction {:?} failed.", hash);
+                            let reason = match block_number {
+                                Some(n) => self.nonce_manager.revert_reason(&tx, n).await,
+                                None => None,
+                            };
+                            error!(
+                                target: "alert",
+                                "transaction {:?} reverted, tx_info: {:?}, reason: {:?}",
+                                hash, tx_info, reason
+                            );
+                            self.record(&tx_info, Some(hash), TxOutcome::REVERTED, reason)
+                                .await;
                             return Ok(false);
                         }
                         1 => {
-                            info!("transaction {:?} success.", hash)
+                            info!("transaction {:?} success.", hash);
+                            self.record(&tx_info, Some(hash), TxOutcome::SUCCEEDED, None)
+                                .await;
                         }
-                        2 => {
-                            info!("transaction {:?} confirmed, status unknown.", hash)
+                        _ => {
+                            info!("transaction {:?} confirmed, status unknown.", hash);
+                            self.record(
+                                &tx_info,
+                                Some(hash),
+                                TxOutcome::SUCCEEDED,
+                                Some("receipt has no status".to_string()),
+                            )
+                            .await;
                         }
-                        _ => {}
                     }
                     return Ok(true);
                 }
@@ -98,10 +152,33 @@ impl Transactor {
                     if self.handle_send_error(&e_str, tx_info.clone()) {
                         continue;
                     } else {
+                        self.record(&tx_info, None, TxOutcome::FAILED, Some(e_str.clone()))
+                            .await;
                         bail!(anyhow!(e_str));
                     }
                 }
             }
         }
     }
+
+    async fn record(
+        &self,
+        tx_info: &TransactionInfo,
+        hash: Option<H256>,
+        outcome: TxOutcome,
+        reason: Option<String>,
+    ) {
+        let attempt = TxAttempt {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            info: format!("{:?}", tx_info),
+            tx_hash: hash.map(|hash| hash.0),
+            outcome,
+            reason,
+        };
+        if let Err(e) = self.db.put_tx_attempt(&attempt).await {
+            warn!("cannot record transaction attempt: {:?}", e);
+        }
+    }
 }
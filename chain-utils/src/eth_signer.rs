@@ -0,0 +1,263 @@
+use std::{fmt, future::Future, time::Duration};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::{
+    providers::{Http, Provider},
+    signers::{LocalWallet, Signer, WalletError},
+    types::{
+        transaction::{eip2718::TypedTransaction, eip712::Eip712},
+        Address, Bytes, Signature, H256,
+    },
+    utils::rlp::Rlp,
+};
+use serde::{Deserialize, Serialize};
+use tokio::time::timeout;
+
+/// Key of an account sending transactions.
+#[derive(Clone)]
+pub enum EthKey {
+    Private(H256),
+    /// Key held by an external signer answering `eth_signTransaction`, such as Web3Signer, or Clef
+    /// which has every transaction approved on its Ledger or Trezor device.
+    External {
+        url: String,
+        address: Address,
+        approval_timeout: Duration,
+    },
+}
+
+#[derive(Debug)]
+pub enum EthSignerError {
+    Wallet(WalletError),
+    External(String),
+    /// The external signer, or its operator, did not approve in time.
+    ApprovalTimeout(Duration),
+}
+
+impl fmt::Display for EthSignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EthSignerError::Wallet(e) => write!(f, "{}", e),
+            EthSignerError::External(e) => write!(f, "external signer: {}", e),
+            EthSignerError::ApprovalTimeout(t) => write!(f, "not approved within {:?}", t),
+        }
+    }
+}
+
+impl std::error::Error for EthSignerError {}
+
+async fn approved<T>(
+    approval_timeout: Duration,
+    signing: impl Future<Output = Result<T, EthSignerError>>,
+) -> Result<T, EthSignerError> {
+    timeout(approval_timeout, signing)
+        .await
+        .map_err(|_| EthSignerError::ApprovalTimeout(approval_timeout))?
+}
+
+/// Reply of Web3Signer, or of Clef.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SignTransactionReply {
+    Raw(Bytes),
+    Clef { raw: Bytes },
+}
+
+/// Signature of a transaction signed by an external signer, checked to be of `tx` by `address`.
+fn signed_transaction_signature(
+    tx: &TypedTransaction,
+    raw: &[u8],
+    address: Address,
+) -> Result<Signature, EthSignerError> {
+    let (signed, signature) = TypedTransaction::decode_signed(&Rlp::new(raw))
+        .map_err(|e| EthSignerError::External(format!("invalid signed transaction: {}", e)))?;
+    if signed.sighash() != tx.sighash() {
+        return Err(EthSignerError::External(
+            "signed another transaction than the one requested".to_string(),
+        ));
+    }
+    signature
+        .verify(tx.sighash(), address)
+        .map_err(|e| EthSignerError::External(format!("invalid signature: {}", e)))?;
+    Ok(signature)
+}
+
+#[derive(Debug)]
+pub struct ExternalSigner {
+    client: Provider<Http>,
+    address: Address,
+    chain_id: u64,
+    approval_timeout: Duration,
+}
+
+impl ExternalSigner {
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, EthSignerError> {
+        let mut tx = tx.clone();
+        tx.set_from(self.address);
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.chain_id);
+        }
+        let reply: SignTransactionReply = self
+            .client
+            .request("eth_signTransaction", [&tx])
+            .await
+            .map_err(|e| EthSignerError::External(e.to_string()))?;
+        let raw = match reply {
+            SignTransactionReply::Raw(raw) | SignTransactionReply::Clef { raw } => raw,
+        };
+        signed_transaction_signature(&tx, &raw, self.address)
+    }
+
+    async fn sign_message(&self, message: &[u8]) -> Result<Signature, EthSignerError> {
+        let signature: Bytes = self
+            .client
+            .request("eth_sign", (self.address, Bytes::from(message.to_vec())))
+            .await
+            .map_err(|e| EthSignerError::External(e.to_string()))?;
+        let signature = Signature::try_from(signature.as_ref())
+            .map_err(|e| EthSignerError::External(format!("invalid signature: {}", e)))?;
+        signature
+            .verify(message, self.address)
+            .map_err(|e| EthSignerError::External(format!("invalid signature: {}", e)))?;
+        Ok(signature)
+    }
+}
+
+/// Signer of the transactions of an [`EthKey`].
+#[derive(Debug)]
+pub enum EthSigner {
+    Local(LocalWallet),
+    External(ExternalSigner),
+}
+
+impl EthSigner {
+    /// Signer of `key`.
+    pub async fn new(key: &EthKey, chain_id: u64) -> Result<Self> {
+        match key {
+            EthKey::Private(key) => Ok(EthSigner::Local(
+                LocalWallet::from_bytes(&key[..])
+                    .map_err(|e| anyhow!("Invalid validator private key: {:?}", e))?
+                    .with_chain_id(chain_id),
+            )),
+            EthKey::External {
+                url,
+                address,
+                approval_timeout,
+            } => Ok(EthSigner::External(ExternalSigner {
+                client: Provider::<Http>::try_from(url.as_str())
+                    .map_err(|e| anyhow!("Invalid external signer url {:?}: {:?}", url, e))?,
+                address: *address,
+                chain_id,
+                approval_timeout: *approval_timeout,
+            })),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for EthSigner {
+    type Error = EthSignerError;
+
+    async fn sign_message<S: Send + Sync + AsRef<[u8]>>(
+        &self,
+        message: S,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            EthSigner::Local(wallet) => wallet
+                .sign_message(message)
+                .await
+                .map_err(EthSignerError::Wallet),
+            EthSigner::External(signer) => {
+                approved(
+                    signer.approval_timeout,
+                    signer.sign_message(message.as_ref()),
+                )
+                .await
+            }
+        }
+    }
+
+    async fn sign_transaction(&self, tx: &TypedTransaction) -> Result<Signature, Self::Error> {
+        match self {
+            EthSigner::Local(wallet) => wallet
+                .sign_transaction(tx)
+                .await
+                .map_err(EthSignerError::Wallet),
+            EthSigner::External(signer) => {
+                approved(signer.approval_timeout, signer.sign_transaction(tx)).await
+            }
+        }
+    }
+
+    async fn sign_typed_data<T: Eip712 + Send + Sync>(
+        &self,
+        payload: &T,
+    ) -> Result<Signature, Self::Error> {
+        match self {
+            EthSigner::Local(wallet) => wallet
+                .sign_typed_data(payload)
+                .await
+                .map_err(EthSignerError::Wallet),
+            EthSigner::External(_) => Err(EthSignerError::External(
+                "typed data signing is not supported".to_string(),
+            )),
+        }
+    }
+
+    fn address(&self) -> Address {
+        match self {
+            EthSigner::Local(wallet) => wallet.address(),
+            EthSigner::External(signer) => signer.address,
+        }
+    }
+
+    fn chain_id(&self) -> u64 {
+        match self {
+            EthSigner::Local(wallet) => wallet.chain_id(),
+            EthSigner::External(signer) => signer.chain_id,
+        }
+    }
+
+    fn with_chain_id<T: Into<u64>>(self, chain_id: T) -> Self {
+        match self {
+            EthSigner::Local(wallet) => EthSigner::Local(wallet.with_chain_id(chain_id)),
+            EthSigner::External(mut signer) => {
+                signer.chain_id = chain_id.into();
+                EthSigner::External(signer)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::types::TransactionRequest;
+
+    use super::*;
+
+    #[test]
+    fn external_signature_test() {
+        let wallet = LocalWallet::from_bytes(&[1; 32])
+            .unwrap()
+            .with_chain_id(16600u64);
+        let mut tx: TypedTransaction = TransactionRequest::new()
+            .to(Address::repeat_byte(2))
+            .nonce(3)
+            .gas(21000)
+            .gas_price(1)
+            .chain_id(16600)
+            .into();
+        tx.set_from(wallet.address());
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        let raw = tx.rlp_signed(&signature);
+        assert_eq!(
+            signed_transaction_signature(&tx, &raw, wallet.address()).unwrap(),
+            signature
+        );
+        assert!(signed_transaction_signature(&tx, &raw, Address::repeat_byte(4)).is_err());
+        let mut other = tx.clone();
+        other.set_nonce(4);
+        assert!(signed_transaction_signature(&other, &raw, wallet.address()).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, BlockNumber, Eip1559TransactionRequest, U256},
+};
+
+#[derive(Debug, Clone, Default)]
+pub struct GasConfig {
+    /// Send EIP-1559 transactions, falls back to legacy ones if the chain has no base fee.
+    pub eip1559: bool,
+    /// Cap of the max fee per gas, or of the gas price of legacy transactions, in wei.
+    pub max_fee_per_gas: Option<U256>,
+    /// Cap of the priority fee per gas, in wei.
+    pub max_priority_fee_per_gas: Option<U256>,
+    /// Replace transactions pending longer than this with bumped fees.
+    pub stuck_timeout: Option<Duration>,
+    /// Blocks on top of the receipt, including its own, before a transaction is confirmed.
+    pub confirmations: Option<u64>,
+}
+
+/// Prices transactions from the current chain fees within the configured caps.
+#[derive(Debug, Clone, Default)]
+pub struct GasStrategy {
+    config: GasConfig,
+}
+
+impl GasStrategy {
+    pub fn new(config: GasConfig) -> Self {
+        Self { config }
+    }
+
+    /// Set the fees of `tx`, converting it to the transaction type of the strategy. Fails without
+    /// sending if the base fee is above the cap, as the transaction could not be included.
+    pub async fn apply<M: Middleware>(
+        &self,
+        client: &M,
+        tx: TypedTransaction,
+    ) -> Result<TypedTransaction> {
+        if self.config.eip1559 {
+            let base_fee = client
+                .get_block(BlockNumber::Latest)
+                .await
+                .map_err(|e| anyhow!("Cannot get latest block: {:?}", e))?
+                .and_then(|block| block.base_fee_per_gas);
+            if let Some(base_fee) = base_fee {
+                let (max_fee, priority_fee) = client
+                    .estimate_eip1559_fees(None)
+                    .await
+                    .map_err(|e| anyhow!("Cannot estimate fees: {:?}", e))?;
+                let (max_fee, priority_fee) =
+                    self.cap_eip1559_fees(base_fee, max_fee, priority_fee)?;
+                let mut tx = to_eip1559(tx);
+                if let TypedTransaction::Eip1559(inner) = &mut tx {
+                    inner.max_fee_per_gas = Some(max_fee);
+                    inner.max_priority_fee_per_gas = Some(priority_fee);
+                }
+                return Ok(tx);
+            }
+        }
+        let gas_price = client
+            .get_gas_price()
+            .await
+            .map_err(|e| anyhow!("Cannot get gas price: {:?}", e))?;
+        let mut tx = to_legacy(tx);
+        tx.set_gas_price(match self.config.max_fee_per_gas {
+            Some(cap) => gas_price.min(cap),
+            None => gas_price,
+        });
+        Ok(tx)
+    }
+
+    pub fn stuck_timeout(&self) -> Option<Duration> {
+        self.config.stuck_timeout
+    }
+
+    pub fn confirmations(&self) -> u64 {
+        self.config.confirmations.unwrap_or(1).max(1)
+    }
+
+    /// Raise the fees of a stuck transaction enough for the replacement to be accepted. Returns false
+    /// if the raised fees would exceed the caps.
+    pub fn bump(&self, tx: &mut TypedTransaction) -> bool {
+        match tx {
+            TypedTransaction::Eip1559(inner) => {
+                let max_fee = bumped(inner.max_fee_per_gas.unwrap_or_default());
+                let priority_fee = bumped(inner.max_priority_fee_per_gas.unwrap_or_default());
+                if exceeds(max_fee, self.config.max_fee_per_gas)
+                    || exceeds(priority_fee, self.config.max_priority_fee_per_gas)
+                {
+                    return false;
+                }
+                inner.max_fee_per_gas = Some(max_fee);
+                inner.max_priority_fee_per_gas = Some(priority_fee);
+            }
+            tx => {
+                let gas_price = bumped(tx.gas_price().unwrap_or_default());
+                if exceeds(gas_price, self.config.max_fee_per_gas) {
+                    return false;
+                }
+                tx.set_gas_price(gas_price);
+            }
+        }
+        true
+    }
+
+    /// Returns the max fee and priority fee per gas.
+    fn cap_eip1559_fees(
+        &self,
+        base_fee: U256,
+        max_fee: U256,
+        priority_fee: U256,
+    ) -> Result<(U256, U256)> {
+        let mut priority_fee = match self.config.max_priority_fee_per_gas {
+            Some(cap) => priority_fee.min(cap),
+            None => priority_fee,
+        };
+        // the estimation leaves room for the base fee to grow in the next blocks
+        let mut max_fee = max_fee.max(base_fee + priority_fee);
+        if let Some(cap) = self.config.max_fee_per_gas {
+            if cap < base_fee {
+                bail!(anyhow!(
+                    "base fee {} exceeds the max fee per gas cap {}",
+                    base_fee,
+                    cap
+                ));
+            }
+            max_fee = max_fee.min(cap);
+        }
+        priority_fee = priority_fee.min(max_fee - base_fee);
+        Ok((max_fee, priority_fee))
+    }
+}
+
+/// Nodes accept a replacement with fees raised by at least 10%.
+fn bumped(fee: U256) -> U256 {
+    fee + fee / 8 + 1
+}
+
+fn exceeds(fee: U256, cap: Option<U256>) -> bool {
+    cap.map_or(false, |cap| fee > cap)
+}
+
+fn to_eip1559(tx: TypedTransaction) -> TypedTransaction {
+    if let TypedTransaction::Eip1559(_) = tx {
+        return tx;
+    }
+    let mut inner = Eip1559TransactionRequest::new();
+    inner.from = tx.from().cloned();
+    inner.to = tx.to().cloned();
+    inner.gas = tx.gas().cloned();
+    inner.value = tx.value().cloned();
+    inner.data = tx.data().cloned();
+    inner.nonce = tx.nonce().cloned();
+    inner.chain_id = tx.chain_id();
+    TypedTransaction::Eip1559(inner)
+}
+
+fn to_legacy(tx: TypedTransaction) -> TypedTransaction {
+    match tx {
+        TypedTransaction::Eip1559(inner) => TypedTransaction::Legacy(inner.into()),
+        tx => tx,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cap_eip1559_fees_test() {
+        let strategy = GasStrategy::new(GasConfig {
+            eip1559: true,
+            max_fee_per_gas: Some(U256::from(100)),
+            max_priority_fee_per_gas: Some(U256::from(5)),
+            stuck_timeout: None,
+            confirmations: None,
+        });
+        // within the caps
+        assert_eq!(
+            strategy
+                .cap_eip1559_fees(U256::from(10), U256::from(30), U256::from(2))
+                .unwrap(),
+            (U256::from(30), U256::from(2))
+        );
+        // both fees capped
+        assert_eq!(
+            strategy
+                .cap_eip1559_fees(U256::from(60), U256::from(130), U256::from(10))
+                .unwrap(),
+            (U256::from(100), U256::from(5))
+        );
+        // the priority fee gives way to the base fee under the cap
+        assert_eq!(
+            strategy
+                .cap_eip1559_fees(U256::from(98), U256::from(200), U256::from(10))
+                .unwrap(),
+            (U256::from(100), U256::from(2))
+        );
+        assert!(strategy
+            .cap_eip1559_fees(U256::from(101), U256::from(200), U256::from(1))
+            .is_err());
+    }
+
+    #[test]
+    fn bump_test() {
+        let strategy = GasStrategy::new(GasConfig {
+            eip1559: true,
+            max_fee_per_gas: Some(U256::from(120)),
+            max_priority_fee_per_gas: None,
+            stuck_timeout: None,
+            confirmations: None,
+        });
+        let mut tx = TypedTransaction::Eip1559(
+            Eip1559TransactionRequest::new()
+                .max_fee_per_gas(100)
+                .max_priority_fee_per_gas(8),
+        );
+        assert!(strategy.bump(&mut tx));
+        let inner = tx.as_eip1559_ref().unwrap();
+        assert_eq!(inner.max_fee_per_gas, Some(U256::from(113)));
+        assert_eq!(inner.max_priority_fee_per_gas, Some(U256::from(10)));
+        // the next bump exceeds the cap
+        assert!(!strategy.bump(&mut tx));
+        assert_eq!(
+            tx.as_eip1559_ref().unwrap().max_fee_per_gas,
+            Some(U256::from(113))
+        );
+
+        let mut tx = TypedTransaction::Legacy(Default::default());
+        tx.set_gas_price(80);
+        assert!(strategy.bump(&mut tx));
+        assert_eq!(tx.gas_price(), Some(U256::from(91)));
+    }
+}
@@ -0,0 +1,308 @@
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    signers::Signer,
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockNumber, Bytes, TransactionReceipt,
+        H256, U256,
+    },
+    utils::keccak256,
+};
+use tokio::{sync::Mutex, time::sleep};
+
+use crate::{gas::GasStrategy, revert::replay_revert_reason, DefaultMiddleware};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_STUCK_TIMEOUT: Duration = Duration::from_secs(120);
+/// Replacements of a stuck transaction before giving up.
+const MAX_REPLACEMENTS: usize = 5;
+/// Resends of a transaction failed by rpc errors before giving up.
+const MAX_SEND_RETRIES: u32 = 5;
+const SEND_RETRY_BACKOFF: Duration = Duration::from_secs(2);
+/// Errors no resend can fix. A transaction not approved on the device or external signer is not sent
+/// again, its nonce stays free for the next one.
+const PERMANENT_ERRORS: &[&str] = &["revert", "insufficient funds", "not approved"];
+
+/// A transaction sent by the nonce manager and not confirmed yet.
+#[derive(Debug, Clone)]
+pub struct SentTransaction {
+    pub nonce: U256,
+    /// Hash of the latest replacement.
+    pub hash: H256,
+    tx: TypedTransaction,
+}
+
+#[derive(Default)]
+struct NonceState {
+    next: Option<U256>,
+    pending: BTreeMap<U256, H256>,
+}
+
+/// Sends the transactions of an account. Nonce allocation is serialized so concurrent senders of the
+/// account never race on a nonce, and transactions pending for too long are replaced with bumped
+/// fees. Clones share the state, senders of the same account must use clones of one manager.
+#[derive(Clone)]
+pub struct NonceManager {
+    client: DefaultMiddleware,
+    gas: GasStrategy,
+    state: Arc<Mutex<NonceState>>,
+}
+
+impl NonceManager {
+    pub fn new(client: DefaultMiddleware, gas: GasStrategy) -> Self {
+        Self {
+            client,
+            gas,
+            state: Default::default(),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.client.address()
+    }
+
+    /// Nonces and hashes of the transactions waiting for confirmation.
+    pub async fn pending_transactions(&self) -> Vec<(U256, H256)> {
+        let state = self.state.lock().await;
+        state
+            .pending
+            .iter()
+            .map(|(nonce, hash)| (*nonce, *hash))
+            .collect()
+    }
+
+    /// Price `tx` and send it with the next nonce of the account, retrying on rpc errors. The
+    /// transaction is signed once: a failed broadcast is retried with the same signed transaction,
+    /// so a broadcast that reached the node despite the error is never sent again under another
+    /// nonce.
+    pub async fn send(&self, tx: TypedTransaction) -> Result<SentTransaction> {
+        let mut attempt = 0;
+        // nothing is broadcast before the transaction is signed, its preparation is retried whole
+        let (sent, raw) = loop {
+            match self.sign_next(tx.clone()).await {
+                Ok(signed) => break signed,
+                Err(e) if attempt < MAX_SEND_RETRIES && !is_permanent(&e) => {
+                    attempt += 1;
+                    warn!("prepare transaction error, retry {:?}: {:?}", attempt, e);
+                    sleep(SEND_RETRY_BACKOFF * attempt).await;
+                }
+                Err(e) => return Err(self.send_failed(e)),
+            }
+        };
+        loop {
+            match self.broadcast(&raw, sent.hash).await {
+                Ok(()) => return Ok(sent),
+                Err(e) if attempt < MAX_SEND_RETRIES && !is_permanent(&e) => {
+                    attempt += 1;
+                    warn!(
+                        "send transaction {:?} error, retry {:?}: {:?}",
+                        sent.hash, attempt, e
+                    );
+                    sleep(SEND_RETRY_BACKOFF * attempt).await;
+                }
+                Err(e) => {
+                    let mut state = self.state.lock().await;
+                    state.pending.remove(&sent.nonce);
+                    // the nonce may not be used, read it from chain again
+                    state.next = None;
+                    return Err(self.send_failed(e));
+                }
+            }
+        }
+    }
+
+    fn send_failed(&self, e: anyhow::Error) -> anyhow::Error {
+        if e.to_string().contains("insufficient funds") {
+            error!(
+                target: "alert",
+                "account {:?} cannot pay for transactions: {:?}",
+                self.address(),
+                e
+            );
+        }
+        e
+    }
+
+    /// Price and sign `tx` with the next nonce of the account, the nonce is reserved for it.
+    async fn sign_next(&self, tx: TypedTransaction) -> Result<(SentTransaction, Bytes)> {
+        let mut state = self.state.lock().await;
+        let chain_nonce = self
+            .client
+            .get_transaction_count(self.address(), Some(BlockNumber::Pending.into()))
+            .await
+            .map_err(|e| anyhow!("Cannot get nonce: {:?}", e))?;
+        // the rpc may not see the latest transactions of this account yet
+        let nonce = state.next.map_or(chain_nonce, |next| next.max(chain_nonce));
+        let mut tx = self.gas.apply(&*self.client, tx).await?;
+        tx.set_from(self.address());
+        tx.set_nonce(nonce);
+        let (raw, hash) = self.sign(&mut tx).await?;
+        state.next = Some(nonce + 1);
+        state.pending.insert(nonce, hash);
+        Ok((SentTransaction { nonce, hash, tx }, raw))
+    }
+
+    /// The signed transaction and its hash.
+    async fn sign(&self, tx: &mut TypedTransaction) -> Result<(Bytes, H256)> {
+        if tx.chain_id().is_none() {
+            tx.set_chain_id(self.client.signer().chain_id());
+        }
+        let signature = self
+            .client
+            .signer()
+            .sign_transaction(tx)
+            .await
+            .map_err(|e| anyhow!(e.to_string()))?;
+        let raw = tx.rlp_signed(&signature);
+        let hash = H256::from(keccak256(&raw));
+        Ok((raw, hash))
+    }
+
+    /// Broadcast a signed transaction, done as well if the node already has it from an earlier
+    /// attempt.
+    async fn broadcast(&self, raw: &Bytes, hash: H256) -> Result<()> {
+        match self.client.send_raw_transaction(raw.clone()).await {
+            Ok(_) => Ok(()),
+            Err(e) => match self.client.get_transaction(hash).await {
+                Ok(Some(_)) => Ok(()),
+                _ => Err(anyhow!(e.to_string())),
+            },
+        }
+    }
+
+    /// Wait for the receipt of a sent transaction to reach the confirmation depth, replacing the
+    /// transaction with bumped fees each time it is pending longer than the stuck timeout. Returns
+    /// `None` if the nonce is taken by another transaction.
+    pub async fn confirm(&self, sent: SentTransaction) -> Result<Option<TransactionReceipt>> {
+        let nonce = sent.nonce;
+        let hash = sent.hash;
+        let res = self.wait_receipt(sent).await;
+        if let Err(e) = &res {
+            error!(
+                target: "alert",
+                "transaction {:?} of account {:?} failed: {:?}",
+                hash,
+                self.address(),
+                e
+            );
+        }
+        let mut state = self.state.lock().await;
+        state.pending.remove(&nonce);
+        if !matches!(res, Ok(Some(_))) {
+            // the nonce may not be used, read it from chain again
+            state.next = None;
+        }
+        res
+    }
+
+    async fn wait_receipt(&self, mut sent: SentTransaction) -> Result<Option<TransactionReceipt>> {
+        let stuck_timeout = self.gas.stuck_timeout().unwrap_or(DEFAULT_STUCK_TIMEOUT);
+        let confirmations = self.gas.confirmations();
+        let mut hashes = vec![sent.hash];
+        let mut sent_at = Instant::now();
+        let mut replacements = 0;
+        loop {
+            sleep(POLL_INTERVAL).await;
+            let mined_nonce = match self
+                .client
+                .get_transaction_count(self.address(), Some(BlockNumber::Latest.into()))
+                .await
+            {
+                Ok(nonce) => nonce,
+                Err(e) => {
+                    warn!("cannot get nonce: {:?}", e);
+                    continue;
+                }
+            };
+            // any of the replacements may be mined, a receipt disappears if its block is reorged
+            if let Some(receipt) = self.find_receipt(&hashes).await {
+                let included = receipt.block_number.map_or(0, |n| n.as_u64());
+                match self.client.get_block_number().await {
+                    Ok(latest) if latest.as_u64() + 1 >= included + confirmations => {
+                        return Ok(Some(receipt));
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!("cannot get block number: {:?}", e),
+                }
+                sent_at = Instant::now();
+                continue;
+            }
+            if mined_nonce > sent.nonce {
+                return Ok(None);
+            }
+            if sent_at.elapsed() < stuck_timeout {
+                continue;
+            }
+            if replacements == MAX_REPLACEMENTS {
+                return Err(anyhow!(
+                    "transaction {:?} is stuck after {} replacements",
+                    sent.hash,
+                    replacements
+                ));
+            }
+            replacements += 1;
+            sent_at = Instant::now();
+            if !self.gas.bump(&mut sent.tx) {
+                warn!(
+                    "transaction {:?} is stuck, fees cannot be raised above the caps",
+                    sent.hash
+                );
+                continue;
+            }
+            match self.send_raw(&mut sent.tx).await {
+                Ok(hash) => {
+                    info!(
+                        "stuck transaction {:?} replaced by {:?}, nonce: {:?}",
+                        sent.hash, hash, sent.nonce
+                    );
+                    sent.hash = hash;
+                    hashes.push(hash);
+                    self.state.lock().await.pending.insert(sent.nonce, hash);
+                }
+                Err(e) => {
+                    warn!("cannot replace stuck transaction {:?}: {:?}", sent.hash, e);
+                }
+            }
+        }
+    }
+
+    /// Why `tx` reverted in block `block_number`, replayed on the state before the block.
+    pub async fn revert_reason(&self, tx: &TypedTransaction, block_number: u64) -> Option<String> {
+        let mut tx = tx.clone();
+        tx.set_from(self.address());
+        replay_revert_reason(
+            &*self.client,
+            &tx,
+            Some(BlockNumber::Number(block_number.saturating_sub(1).into()).into()),
+        )
+        .await
+    }
+
+    async fn find_receipt(&self, hashes: &[H256]) -> Option<TransactionReceipt> {
+        for hash in hashes {
+            match self.client.get_transaction_receipt(*hash).await {
+                Ok(Some(receipt)) => return Some(receipt),
+                Ok(None) => {}
+                Err(e) => warn!("cannot get receipt of {:?}: {:?}", hash, e),
+            }
+        }
+        None
+    }
+
+    async fn send_raw(&self, tx: &mut TypedTransaction) -> Result<H256> {
+        let (raw, hash) = self.sign(tx).await?;
+        self.broadcast(&raw, hash).await?;
+        Ok(hash)
+    }
+}
+
+fn is_permanent(e: &anyhow::Error) -> bool {
+    let e = e.to_string();
+    PERMANENT_ERRORS.iter().any(|x| e.contains(x))
+}
@@ -0,0 +1,85 @@
+use ethers::{
+    abi::{decode, ParamType, Token},
+    providers::{Middleware, MiddlewareError},
+    types::{transaction::eip2718::TypedTransaction, BlockId},
+    utils::hex,
+};
+
+/// Selector of `Error(string)`.
+const ERROR_SELECTOR: [u8; 4] = [0x08, 0xc3, 0x79, 0xa0];
+/// Selector of `Panic(uint256)`.
+const PANIC_SELECTOR: [u8; 4] = [0x4e, 0x48, 0x7b, 0x71];
+
+/// Human readable reason of a revert from its return data. Custom errors are returned in hex, as
+/// their ABI is not known here.
+pub fn decode_revert_reason(data: &[u8]) -> Option<String> {
+    if data.len() < 4 {
+        return None;
+    }
+    let (selector, args) = data.split_at(4);
+    if selector == ERROR_SELECTOR {
+        if let Ok([Token::String(reason)]) = decode(&[ParamType::String], args).as_deref() {
+            return Some(reason.clone());
+        }
+    }
+    if selector == PANIC_SELECTOR {
+        if let Ok([Token::Uint(code)]) = decode(&[ParamType::Uint(256)], args).as_deref() {
+            return Some(format!("panic code {:#x}", code));
+        }
+    }
+    Some(format!("custom error 0x{}", hex::encode(data)))
+}
+
+/// Replay `tx` with `eth_call` at `block` and return why it reverts, `None` if it does not.
+pub async fn replay_revert_reason<M: Middleware>(
+    client: &M,
+    tx: &TypedTransaction,
+    block: Option<BlockId>,
+) -> Option<String> {
+    let e = client.call(tx, block).await.err()?;
+    match e.as_error_response() {
+        Some(response) => Some(
+            response
+                .as_revert_data()
+                .and_then(|data| decode_revert_reason(&data))
+                .unwrap_or_else(|| response.message.clone()),
+        ),
+        None => Some(e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::{abi::encode, types::U256};
+
+    use super::*;
+
+    #[test]
+    fn decode_revert_reason_test() {
+        let data = [
+            ERROR_SELECTOR.to_vec(),
+            encode(&[Token::String("not registered".to_string())]),
+        ]
+        .concat();
+        assert_eq!(
+            decode_revert_reason(&data),
+            Some("not registered".to_string())
+        );
+
+        let data = [
+            PANIC_SELECTOR.to_vec(),
+            encode(&[Token::Uint(U256::from(0x11))]),
+        ]
+        .concat();
+        assert_eq!(
+            decode_revert_reason(&data),
+            Some("panic code 0x11".to_string())
+        );
+
+        assert_eq!(
+            decode_revert_reason(&[0xde, 0xad, 0xbe, 0xef]),
+            Some("custom error 0xdeadbeef".to_string())
+        );
+        assert_eq!(decode_revert_reason(&[]), None);
+    }
+}
@@ -3,7 +3,7 @@ use std::error::Error;
 use ark_bn254::{G1Affine, G1Projective};
 use ark_ff::Fp;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use grpc::signer::{signer_client::SignerClient, BatchSignRequest, SignRequest};
+use grpc::signer::{signer_client::SignerClient, BatchSignRequest, SignRequest, SliceEncoding};
 use utils::hex_to_bytes;
 
 #[tokio::main]
@@ -26,7 +26,10 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 erasure_commitment: serialized_erasure_commitment,
                 storage_root: data_root.clone(),
                 encoded_slice: vec![],
+                options: Default::default(),
+                encoding: SliceEncoding::Uncompressed as i32,
             }],
+            partial_success: false,
         })
         .await
         .unwrap();
@@ -6,10 +6,17 @@ mod line_candidate;
 mod line_metadata;
 mod mine;
 mod mock_data;
+mod progress;
+mod reward_watcher;
+mod sample_cache;
+mod scheduler;
 mod service;
 mod stage1;
 mod stage2;
 mod submitter;
 mod watcher;
 
+pub use mine::verify_line_proof;
+pub use progress::{DasProgress, DasRound, DasSubmissionResult};
+pub use scheduler::{DasScheduler, DasSchedulerConfig};
 pub use service::DasMineService;
@@ -1,11 +1,13 @@
+use std::sync::Arc;
+
 use contract_interface::da_sample::SampleResponse;
 use ethers::types::U256;
-use once_cell::unsync::Lazy;
 use storage::slice_db::{SliceDB, SliceIndex};
 
 use crate::{
-    constants::{LINE_BYTES, NUM_SUBLINES, SUBLINE_BYTES},
-    mine::{build_subline_merkle, calculate_data_quality, serialize_line},
+    constants::{NUM_SUBLINES, SUBLINE_BYTES},
+    mine::{calculate_data_quality, serialize_line},
+    sample_cache::{SampleCache, SampleLine},
     watcher::SampleTask,
 };
 
@@ -25,43 +27,21 @@ impl LineCandidate {
         }
     }
 
-    pub async fn mine(&self, db: &impl SliceDB) -> Result<Vec<SampleResponse>, String> {
-        let line_hits = self.find_valid_answer(db).await?;
-        self.make_sample_response(db, line_hits).await
+    /// The line of the candidate, `None` if it is not stored.
+    pub(crate) async fn load_line(
+        &self,
+        db: &impl SliceDB,
+        cache: &SampleCache,
+    ) -> Result<Option<Arc<SampleLine>>, String> {
+        cache.get_or_load(db, &self.index).await
     }
 
-    async fn find_valid_answer(&self, db: &impl SliceDB) -> Result<Vec<LineHit>, String> {
-        let SliceIndex {
-            epoch,
-            quorum_id,
-            storage_root,
-            index,
-        } = self.index;
-
-        let line = if let Some(data) = db
-            .get_slice_data(epoch, quorum_id, storage_root, index as usize)
-            .await
-            .map_err(|e| {
-                format!(
-                    "Cannot load slice data, slice index {:?}, error {:?}",
-                    index, e
-                )
-            })? {
-            data
-        } else {
-            return Ok(vec![]);
-        };
-
-        if line.len() * 32 != LINE_BYTES {
-            return Err(format!("Incorrect slice length {}", line.len()));
-        }
-
+    /// Sublines of `line` under the target, CPU bound.
+    pub(crate) fn find_valid_answer(&self, line: &SampleLine) -> Vec<LineHit> {
         const SUBLINE_ITEMS: usize = SUBLINE_BYTES / 32;
         let mut found = vec![];
 
-        let lazy_subline_merkle = Lazy::new(|| build_subline_merkle(&line));
-
-        for (subline_index, subline) in line.chunks_exact(SUBLINE_ITEMS).enumerate() {
+        for (subline_index, subline) in line.data.chunks_exact(SUBLINE_ITEMS).enumerate() {
             let data_quality = U256::from_big_endian(&calculate_data_quality(
                 self.line_quality,
                 subline_index as u64,
@@ -74,7 +54,7 @@ impl LineCandidate {
             }
 
             const DEPTH: usize = NUM_SUBLINES.trailing_zeros() as usize;
-            let subline_merkle = &*lazy_subline_merkle;
+            let subline_merkle = line.subline_merkle();
             let proof = (1..=DEPTH)
                 .rev()
                 .map(|d| {
@@ -92,10 +72,10 @@ impl LineCandidate {
             });
         }
 
-        Ok(found)
+        found
     }
 
-    async fn make_sample_response(
+    pub(crate) async fn make_sample_response(
         &self,
         db: &impl SliceDB,
         line_hits: Vec<LineHit>,
@@ -76,14 +76,14 @@ impl LineMetadata {
         start_epoch: u64,
         num_batch: usize,
         task: SampleTask,
-    ) -> (Vec<LineCandidate>, Option<u64>) {
+    ) -> (Vec<LineCandidate>, Option<u64>, usize) {
         debug!(start_epoch, "DA data size {}", self.data.len());
         if self
             .data
             .last_key_value()
             .map_or(true, |(&epoch, _)| epoch < start_epoch)
         {
-            return (vec![], None);
+            return (vec![], None, 0);
         }
 
         let mut answer = vec![];
@@ -126,6 +126,6 @@ impl LineMetadata {
 
         debug!("{:?} lines processed", cnt);
 
-        (answer, Some(last_epoch))
+        (answer, Some(last_epoch), cnt)
     }
 }
@@ -81,6 +81,36 @@ pub fn build_subline_merkle(data: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
     tree.into()
 }
 
+/// Check a stored line against the blob merkle roots through the merkle proof of its slice.
+pub fn verify_line_proof(
+    index: usize,
+    line: &[[u8; 32]],
+    merkle_proof: &[[u8; 32]],
+    blob_roots: &[[u8; 32]; 3],
+) -> bool {
+    if line.len() * 32 != LINE_BYTES {
+        return false;
+    }
+    let subline_merkle = build_subline_merkle(line);
+    let mut node = keccak_chunked(&subline_merkle[0], 2)[0];
+
+    let leaves = 1usize << merkle_proof.len();
+    let root_index = index / leaves;
+    let mut position = index % leaves;
+    if root_index >= blob_roots.len() {
+        return false;
+    }
+    for sibling in merkle_proof {
+        node = if position & 1 == 0 {
+            keccak_chunked(&[node, *sibling], 2)[0]
+        } else {
+            keccak_chunked(&[*sibling, node], 2)[0]
+        };
+        position >>= 1;
+    }
+    node == blob_roots[root_index]
+}
+
 pub fn keccak_chunked(input: &[[u8; 32]], chunk_size: usize) -> Vec<[u8; 32]> {
     input
         .chunks_exact(chunk_size)
@@ -2,13 +2,12 @@ use rand::{thread_rng, Rng};
 use storage::slice_db::SliceDB;
 use zg_encoder::{EncodedBlob, RawBlob, RawData, ZgEncoderParams};
 
-pub async fn store_mock_data(param_dir: &str, store: &dyn SliceDB) {
+pub async fn store_mock_data(param_dir: &str, store: &(dyn SliceDB + Sync)) {
     let params = ZgEncoderParams::from_dir_mont(param_dir, false, None);
 
-    let mut rng = thread_rng();
     for _ in 0..5 {
         let mut data = vec![0u8; 1024];
-        rng.fill(data.as_mut_slice());
+        thread_rng().fill(data.as_mut_slice());
 
         let raw_data: RawData = data[..].try_into().unwrap();
         let raw_blob: RawBlob = raw_data.into();
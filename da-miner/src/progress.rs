@@ -0,0 +1,133 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ethers::types::{H256, U256};
+use storage::das_reward_db::SubmissionStatus;
+
+use crate::watcher::SampleTask;
+
+/// Mining progress of a sample round.
+#[derive(Debug, Clone, Default)]
+pub struct DasRound {
+    pub sample_seed: H256,
+    /// Qualities not larger than this are valid answers.
+    pub podas_target: U256,
+    /// Unix timestamp in seconds the round was received at.
+    pub started_at: u64,
+    /// Stored lines whose line quality is computed.
+    pub scanned_lines: u64,
+    /// Lines passing the line quality filter, mined by the second stage.
+    pub candidate_lines: u64,
+    /// Sublines under the target, sent to the submitter.
+    pub answers: u64,
+}
+
+/// Outcome of the latest sampling answer sent on chain.
+#[derive(Debug, Clone)]
+pub struct DasSubmissionResult {
+    /// Unix timestamp in seconds.
+    pub timestamp: u64,
+    pub epoch: u64,
+    pub quorum_id: u64,
+    pub tx_hash: Option<[u8; 32]>,
+    pub status: SubmissionStatus,
+}
+
+#[derive(Default)]
+struct Progress {
+    round: Option<DasRound>,
+    last_submission: Option<DasSubmissionResult>,
+}
+
+/// The miner's view of the current sample round, updated by the mining stages and the submitter
+/// so operators can tell whether mining progresses.
+#[derive(Clone, Default)]
+pub struct DasProgress {
+    inner: Arc<Mutex<Progress>>,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+impl DasProgress {
+    pub fn round(&self) -> Option<DasRound> {
+        self.inner.lock().unwrap().round.clone()
+    }
+
+    pub fn last_submission(&self) -> Option<DasSubmissionResult> {
+        self.inner.lock().unwrap().last_submission.clone()
+    }
+
+    pub(crate) fn on_new_round(&self, task: SampleTask) {
+        self.inner.lock().unwrap().round = Some(DasRound {
+            sample_seed: task.sample_seed,
+            podas_target: task.podas_target,
+            started_at: now(),
+            ..Default::default()
+        });
+    }
+
+    pub(crate) fn on_closed_round(&self, sample_seed: H256) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner
+            .round
+            .as_ref()
+            .map_or(false, |x| x.sample_seed == sample_seed)
+        {
+            inner.round = None;
+        }
+    }
+
+    /// Count the work of a round, ignored if the round is over.
+    pub(crate) fn on_mined(&self, sample_seed: H256, update: impl FnOnce(&mut DasRound)) {
+        if let Some(round) = self.inner.lock().unwrap().round.as_mut() {
+            if round.sample_seed == sample_seed {
+                update(round);
+            }
+        }
+    }
+
+    pub(crate) fn on_submission(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        tx_hash: Option<[u8; 32]>,
+        status: SubmissionStatus,
+    ) {
+        self.inner.lock().unwrap().last_submission = Some(DasSubmissionResult {
+            timestamp: now(),
+            epoch,
+            quorum_id,
+            tx_hash,
+            status,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_test() {
+        let progress = DasProgress::default();
+        let task = SampleTask {
+            sample_seed: H256::repeat_byte(1),
+            podas_target: U256::from(100),
+        };
+        progress.on_new_round(task);
+        progress.on_mined(task.sample_seed, |x| x.scanned_lines += 10);
+        progress.on_mined(H256::repeat_byte(2), |x| x.scanned_lines += 10);
+        assert_eq!(progress.round().unwrap().scanned_lines, 10);
+
+        progress.on_closed_round(H256::repeat_byte(2));
+        assert!(progress.round().is_some());
+        progress.on_closed_round(task.sample_seed);
+        assert!(progress.round().is_none());
+    }
+}
@@ -0,0 +1,109 @@
+use std::{cmp, sync::Arc, time::Duration};
+
+use chain_utils::{DefaultMiddleware, DefaultMiddlewareInner};
+use contract_interface::DASample;
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockNumber},
+};
+use storage::{
+    das_reward_db::{DasReward, DasRewardDB},
+    Storage,
+};
+use tokio::time::sleep;
+
+use crate::service::DasTasks;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+const MAX_LOGS_PAGINATION: u64 = 1000;
+
+/// Records the `DAReward` events paid to the miner account.
+pub struct DasRewardWatcher {
+    provider: DefaultMiddleware,
+    da_contract: DASample<DefaultMiddlewareInner>,
+    store: Arc<Storage>,
+}
+
+impl DasRewardWatcher {
+    pub fn spawn(
+        tasks: &DasTasks,
+        provider: DefaultMiddleware,
+        da_address: Address,
+        store: Arc<Storage>,
+    ) {
+        let da_contract = DASample::new(da_address, provider.clone());
+        let watcher = Self {
+            provider,
+            da_contract,
+            store,
+        };
+        tasks.spawn(watcher.start(), "das_reward_watcher");
+    }
+
+    async fn start(self) {
+        loop {
+            if let Err(error) = self.check_rewards().await {
+                warn!(?error, "Cannot check DAS rewards");
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    async fn check_rewards(&self) -> Result<(), String> {
+        let latest = self
+            .provider
+            .get_block_number()
+            .await
+            .map_err(|e| format!("Cannot get block number: {:?}", e))?
+            .as_u64();
+        // rewards before the first run are not tracked
+        let from = self
+            .store
+            .get_reward_progress()
+            .await
+            .map_err(|e| format!("Cannot get reward progress: {:?}", e))?
+            .unwrap_or(latest);
+        let mut l = from;
+        while l <= latest {
+            let r = cmp::min(l + MAX_LOGS_PAGINATION, latest);
+            let events = self
+                .da_contract
+                .da_reward_filter()
+                .topic1(self.provider.address())
+                .from_block(BlockNumber::Number(l.into()))
+                .to_block(BlockNumber::Number(r.into()))
+                .query_with_meta()
+                .await
+                .map_err(|e| format!("Cannot query reward events: {:?}", e))?;
+            let store = &self.store;
+            for (event, meta) in events {
+                let reward = DasReward {
+                    sample_round: event.sample_round.as_u64(),
+                    epoch: event.epoch.as_u64(),
+                    quorum_id: event.quorum_id.as_u64(),
+                    data_root: event.data_root,
+                    line_index: event.line_index.as_u64(),
+                    subline_index: event.subline_index.as_u64(),
+                    reward: event.reward.try_into().unwrap_or(u128::MAX),
+                    tx_hash: meta.transaction_hash.0,
+                };
+                info!(
+                    epoch = reward.epoch,
+                    quorum = reward.quorum_id,
+                    reward = reward.reward,
+                    "Receive DAS reward"
+                );
+                store
+                    .put_das_reward(&reward)
+                    .await
+                    .map_err(|e| format!("Cannot store reward: {:?}", e))?;
+            }
+            store
+                .put_reward_progress(r + 1)
+                .await
+                .map_err(|e| format!("Cannot store reward progress: {:?}", e))?;
+            l = r + 1;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use once_cell::sync::OnceCell;
+use storage::slice_db::{SliceDB, SliceIndex};
+
+use crate::{constants::LINE_BYTES, mine::build_subline_merkle};
+
+/// Sampling intermediates of a stored line. They do not depend on the sample seed, so every
+/// sampling round mining the line again reuses them.
+pub(crate) struct SampleLine {
+    pub data: Vec<[u8; 32]>,
+    subline_merkle: OnceCell<Vec<Vec<[u8; 32]>>>,
+}
+
+impl SampleLine {
+    fn new(data: Vec<[u8; 32]>) -> Self {
+        Self {
+            data,
+            subline_merkle: OnceCell::new(),
+        }
+    }
+
+    /// Merkle tree of the sublines, only built once a subline of the line hits.
+    pub fn subline_merkle(&self) -> &Vec<Vec<[u8; 32]>> {
+        self.subline_merkle
+            .get_or_init(|| build_subline_merkle(&self.data))
+    }
+}
+
+#[derive(Default)]
+struct Lines {
+    entries: HashMap<SliceIndex, (u64, Arc<SampleLine>)>,
+    /// Entries by the tick of their last use, the first one is evicted.
+    by_use: BTreeMap<u64, SliceIndex>,
+    tick: u64,
+}
+
+/// Least recently used lines loaded by the miner, bounded in lines of `LINE_BYTES` each. Lines
+/// never change once stored, so entries need no invalidation.
+pub(crate) struct SampleCache {
+    capacity: usize,
+    lines: Mutex<Lines>,
+}
+
+impl SampleCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            lines: Mutex::new(Lines::default()),
+        }
+    }
+
+    fn get(&self, index: &SliceIndex) -> Option<Arc<SampleLine>> {
+        let mut lines = self.lines.lock().unwrap();
+        lines.tick += 1;
+        let tick = lines.tick;
+        let (used, line) = lines.entries.get_mut(index)?;
+        let last_used = std::mem::replace(used, tick);
+        let line = line.clone();
+        lines.by_use.remove(&last_used);
+        lines.by_use.insert(tick, index.clone());
+        Some(line)
+    }
+
+    fn insert(&self, index: SliceIndex, line: Arc<SampleLine>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut lines = self.lines.lock().unwrap();
+        lines.tick += 1;
+        let tick = lines.tick;
+        if let Some((last_used, _)) = lines.entries.insert(index.clone(), (tick, line)) {
+            lines.by_use.remove(&last_used);
+        }
+        lines.by_use.insert(tick, index);
+        while lines.entries.len() > self.capacity {
+            let (_, evicted) = lines.by_use.pop_first().unwrap();
+            lines.entries.remove(&evicted);
+        }
+    }
+
+    /// The line at `index`, loaded from the db unless cached. `None` if it is not stored.
+    pub async fn get_or_load(
+        &self,
+        db: &impl SliceDB,
+        index: &SliceIndex,
+    ) -> Result<Option<Arc<SampleLine>>, String> {
+        if let Some(line) = self.get(index) {
+            return Ok(Some(line));
+        }
+        let data = match db
+            .get_slice_data(
+                index.epoch,
+                index.quorum_id,
+                index.storage_root,
+                index.index as usize,
+            )
+            .await
+            .map_err(|e| {
+                format!(
+                    "Cannot load slice data, slice index {:?}, error {:?}",
+                    index.index, e
+                )
+            })? {
+            Some(data) => data,
+            None => return Ok(None),
+        };
+        if data.len() * 32 != LINE_BYTES {
+            return Err(format!("Incorrect slice length {}", data.len()));
+        }
+        let line = Arc::new(SampleLine::new(data));
+        self.insert(index.clone(), line.clone());
+        Ok(Some(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(index: u64) -> SliceIndex {
+        SliceIndex {
+            epoch: 1,
+            quorum_id: 0,
+            storage_root: [0; 32],
+            index,
+        }
+    }
+
+    #[test]
+    fn evict_test() {
+        let cache = SampleCache::new(2);
+        let line = || Arc::new(SampleLine::new(vec![]));
+        cache.insert(index(0), line());
+        cache.insert(index(1), line());
+        assert!(cache.get(&index(0)).is_some());
+        cache.insert(index(2), line());
+        assert!(cache.get(&index(0)).is_some());
+        assert!(cache.get(&index(1)).is_none());
+        assert!(cache.get(&index(2)).is_some());
+        assert_eq!(cache.lines.lock().unwrap().by_use.len(), 2);
+    }
+}
@@ -0,0 +1,198 @@
+use std::{sync::Arc, time::Duration};
+
+use events::EventBus;
+use tokio::{
+    sync::{watch, RwLock},
+    time::sleep,
+};
+
+use crate::progress::DasProgress;
+
+const LOAD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Default)]
+pub struct DasSchedulerConfig {
+    /// Number of line candidates mined in parallel.
+    pub concurrency: Option<usize>,
+    /// Only mine epochs not smaller than this, besides the on-chain sample range.
+    pub start_epoch: Option<u64>,
+    /// Only mine epochs not larger than this, besides the on-chain sample range.
+    pub end_epoch: Option<u64>,
+    /// Pause mining while the ongoing sign requests exceed this number.
+    pub max_sign_load: Option<u64>,
+    /// Lines kept loaded across sampling rounds, 256 if `None`.
+    pub sample_cache_lines: Option<usize>,
+    /// Answers sent back to back before waiting for their receipts, 8 if `None`.
+    pub submission_batch_size: Option<usize>,
+    /// Answers sent per sample round, the best ones first. Unlimited if `None`.
+    pub max_submissions_per_round: Option<u64>,
+}
+
+/// Controls when and what the DAS miner mines. Mining can be paused at runtime by the operator, and
+/// is duty cycled off while the node is busy signing.
+#[derive(Clone)]
+pub struct DasScheduler {
+    config: DasSchedulerConfig,
+    paused: Arc<watch::Sender<bool>>,
+    sign_load: Option<Arc<RwLock<u64>>>,
+    progress: DasProgress,
+    events: EventBus,
+}
+
+impl DasScheduler {
+    pub fn new(config: DasSchedulerConfig, sign_load: Option<Arc<RwLock<u64>>>) -> Self {
+        let (paused, _) = watch::channel(false);
+        Self {
+            config,
+            paused: Arc::new(paused),
+            sign_load,
+            progress: DasProgress::default(),
+            events: EventBus::default(),
+        }
+    }
+
+    /// Publish the events of the miner on `events`.
+    pub fn with_events(mut self, events: EventBus) -> Self {
+        self.events = events;
+        self
+    }
+
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    pub fn progress(&self) -> &DasProgress {
+        &self.progress
+    }
+
+    pub fn sample_cache_lines(&self) -> usize {
+        self.config.sample_cache_lines.unwrap_or(256)
+    }
+
+    pub fn submission_batch_size(&self) -> usize {
+        self.config.submission_batch_size.unwrap_or(8).max(1)
+    }
+
+    pub fn max_submissions_per_round(&self) -> Option<u64> {
+        self.config.max_submissions_per_round
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.config.concurrency.unwrap_or(1).max(1)
+    }
+
+    /// Intersect the on-chain sample range with the configured epoch range.
+    pub fn epoch_range(&self, start_epoch: u64, end_epoch: u64) -> (u64, u64) {
+        (
+            self.config
+                .start_epoch
+                .map_or(start_epoch, |x| x.max(start_epoch)),
+            self.config
+                .end_epoch
+                .map_or(end_epoch, |x| x.min(end_epoch)),
+        )
+    }
+
+    pub fn pause(&self) {
+        if !self.paused.send_replace(true) {
+            info!("DAS mining paused");
+        }
+    }
+
+    pub fn resume(&self) {
+        if self.paused.send_replace(false) {
+            info!("DAS mining resumed");
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    async fn overloaded(&self) -> bool {
+        match (&self.sign_load, self.config.max_sign_load) {
+            (Some(sign_load), Some(max_sign_load)) => *sign_load.read().await > max_sign_load,
+            _ => false,
+        }
+    }
+
+    pub async fn runnable(&self) -> bool {
+        !self.is_paused() && !self.overloaded().await
+    }
+
+    /// Wait until mining is neither paused nor duty cycled off.
+    pub async fn wait_runnable(&self) {
+        let mut paused = self.paused.subscribe();
+        loop {
+            if *paused.borrow_and_update() {
+                // the sender lives as long as the scheduler
+                let _ = paused.changed().await;
+                continue;
+            }
+            if !self.overloaded().await {
+                return;
+            }
+            sleep(LOAD_CHECK_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::timeout;
+
+    use super::*;
+
+    #[test]
+    fn epoch_range_test() {
+        let scheduler = DasScheduler::new(DasSchedulerConfig::default(), None);
+        assert_eq!(scheduler.epoch_range(3, 9), (3, 9));
+
+        let scheduler = DasScheduler::new(
+            DasSchedulerConfig {
+                start_epoch: Some(5),
+                end_epoch: Some(7),
+                ..Default::default()
+            },
+            None,
+        );
+        assert_eq!(scheduler.epoch_range(3, 9), (5, 7));
+        assert_eq!(scheduler.epoch_range(6, 20), (6, 7));
+        assert_eq!(scheduler.epoch_range(0, 4), (5, 4));
+    }
+
+    #[tokio::test]
+    async fn pause_resume_test() {
+        let sign_load = Arc::new(RwLock::new(0));
+        let scheduler = DasScheduler::new(
+            DasSchedulerConfig {
+                max_sign_load: Some(2),
+                ..Default::default()
+            },
+            Some(sign_load.clone()),
+        );
+        assert!(scheduler.runnable().await);
+
+        scheduler.pause();
+        assert!(scheduler.is_paused());
+        assert!(!scheduler.runnable().await);
+        let waiting = {
+            let scheduler = scheduler.clone();
+            tokio::spawn(async move { scheduler.wait_runnable().await })
+        };
+        sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+        scheduler.resume();
+        assert!(!scheduler.is_paused());
+        timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // duty cycled off while signing is busy
+        *sign_load.write().await = 3;
+        assert!(!scheduler.runnable().await);
+        *sign_load.write().await = 2;
+        assert!(scheduler.runnable().await);
+    }
+}
@@ -1,18 +1,56 @@
-use std::sync::Arc;
+use std::{future::Future, sync::Arc};
 
-use chain_utils::DefaultMiddleware;
-use contract_interface::da_sample::SampleResponse;
+use chain_utils::{nonce_manager::NonceManager, DefaultMiddleware};
+use contract_interface::{da_sample::SampleResponse, DASample};
 use ethers::types::Address;
 use storage::Storage;
 use task_executor::TaskExecutor;
-use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc, watch};
 
 use crate::{
-    line_candidate::LineCandidate, mock_data::store_mock_data, stage1::DasStage1Miner,
-    stage2::DasStage2Miner, submitter::DasSubmitter, watcher::DasWatcher,
+    line_candidate::LineCandidate, mock_data::store_mock_data, reward_watcher::DasRewardWatcher,
+    scheduler::DasScheduler, stage1::DasStage1Miner, stage2::DasStage2Miner,
+    submitter::DasSubmitter, watcher::DasWatcher,
 };
 
-pub struct DasMineService;
+/// Spawns the tasks of the mine service, stopped together once the service is dropped.
+pub(crate) struct DasTasks {
+    executor: TaskExecutor,
+    exited: mpsc::UnboundedSender<&'static str>,
+    stop: watch::Receiver<()>,
+}
+
+/// Reports the exit of a task, including by a panic.
+struct ExitGuard(&'static str, mpsc::UnboundedSender<&'static str>);
+
+impl Drop for ExitGuard {
+    fn drop(&mut self) {
+        let _ = self.1.send(self.0);
+    }
+}
+
+impl DasTasks {
+    pub fn spawn(&self, task: impl Future<Output = ()> + Send + 'static, name: &'static str) {
+        let (exited, mut stop) = (self.exited.clone(), self.stop.clone());
+        self.executor.spawn(
+            async move {
+                let _guard = ExitGuard(name, exited);
+                tokio::select! {
+                    _ = Box::pin(task) => {}
+                    _ = stop.changed() => {}
+                }
+            },
+            name,
+        );
+    }
+}
+
+/// The running tasks of the mine service. They run as long as none of them exits, then the
+/// others are stopped with the service.
+pub struct DasMineService {
+    exited: mpsc::UnboundedReceiver<&'static str>,
+    _stop: watch::Sender<()>,
+}
 
 impl DasMineService {
     pub async fn spawn(
@@ -20,13 +58,23 @@ impl DasMineService {
         provider: DefaultMiddleware,
         da_address: Address,
         das_test: bool,
-        store: Arc<RwLock<Storage>>,
-    ) -> Result<(), String> {
+        store: Arc<Storage>,
+        scheduler: DasScheduler,
+        nonce_manager: NonceManager,
+    ) -> Result<Self, String> {
         info_span!("start_mine_service");
 
+        let (exited_sender, exited) = mpsc::unbounded_channel();
+        let (stop, stop_receiver) = watch::channel(());
+        let tasks = DasTasks {
+            executor,
+            exited: exited_sender,
+            stop: stop_receiver,
+        };
+
         if das_test {
             info!("Start store mock da data");
-            store_mock_data("./params", &*store.read().await).await;
+            store_mock_data("./params", &*store).await;
         }
 
         let (on_chain_sender, on_chain_receiver) = broadcast::channel(1024);
@@ -35,36 +83,46 @@ impl DasMineService {
             mpsc::unbounded_channel::<Vec<LineCandidate>>();
         let (submission_sender, submission_receiver) = mpsc::unbounded_channel::<SampleResponse>();
 
-        DasWatcher::spawn(
-            executor.clone(),
-            provider.clone(),
-            on_chain_sender,
-            da_address,
-        )
-        .await?;
+        DasWatcher::spawn(&tasks, provider.clone(), on_chain_sender, da_address).await?;
 
         DasStage1Miner::spawn(
-            executor.clone(),
+            &tasks,
             store.clone(),
             on_chain_receiver.resubscribe(),
             first_stage_sender,
+            scheduler.clone(),
         );
 
         DasStage2Miner::spawn(
-            executor.clone(),
+            &tasks,
             store.clone(),
             first_stage_receiver,
             submission_sender,
+            scheduler.clone(),
         );
 
         DasSubmitter::spawn(
-            executor.clone(),
-            provider.clone(),
+            &tasks,
+            DASample::new(da_address, provider.clone()),
             on_chain_receiver.resubscribe(),
             submission_receiver,
-            da_address,
+            store.clone(),
+            nonce_manager,
+            scheduler.clone(),
         );
 
-        Ok(())
+        DasRewardWatcher::spawn(&tasks, provider.clone(), da_address, store);
+
+        Ok(Self {
+            exited,
+            _stop: stop,
+        })
+    }
+
+    /// Wait until one of the tasks exits, returns its name. The other tasks are stopped once the
+    /// service is dropped.
+    pub async fn exited(mut self) -> &'static str {
+        // every task reports its exit before its sender is dropped
+        self.exited.recv().await.unwrap_or("das_mine_service")
     }
 }
@@ -2,29 +2,32 @@ use std::{sync::Arc, time::Duration};
 
 use ethers::types::U256;
 use storage::Storage;
-use task_executor::TaskExecutor;
-use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::sync::{broadcast, mpsc};
 
 use crate::{
     line_candidate::LineCandidate,
     line_metadata::LineMetadata,
+    scheduler::DasScheduler,
+    service::DasTasks,
     watcher::{OnChainChangeMessage, SampleTask},
 };
 
 pub struct DasStage1Miner {
-    db: Arc<RwLock<Storage>>,
+    db: Arc<Storage>,
     on_chain_receiver: broadcast::Receiver<OnChainChangeMessage>,
     first_stage_sender: mpsc::UnboundedSender<Vec<LineCandidate>>,
+    scheduler: DasScheduler,
 
     lines: LineMetadata,
 }
 
 impl DasStage1Miner {
     pub fn spawn(
-        executor: TaskExecutor,
-        db: Arc<RwLock<Storage>>,
+        tasks: &DasTasks,
+        db: Arc<Storage>,
         on_chain_receiver: broadcast::Receiver<OnChainChangeMessage>,
         first_stage_sender: mpsc::UnboundedSender<Vec<LineCandidate>>,
+        scheduler: DasScheduler,
     ) {
         let lines = LineMetadata::default();
 
@@ -32,13 +35,11 @@ impl DasStage1Miner {
             db,
             on_chain_receiver,
             first_stage_sender,
+            scheduler,
             lines,
         };
 
-        executor.spawn(
-            async move { Box::pin(stage1_miner.start()).await },
-            "das_stage1_miner",
-        );
+        tasks.spawn(stage1_miner.start(), "das_stage1_miner");
     }
 
     async fn start(mut self) {
@@ -51,21 +52,25 @@ impl DasStage1Miner {
         const MINE_EPOCH_BATCH: usize = 20;
 
         loop {
+            let runnable = self.scheduler.runnable().await;
             tokio::select! {
                 biased;
 
                 msg = self.on_chain_receiver.recv(), if receive_channel_opened => {
                     match msg {
                         Ok(UpdateSampleRange(start_epoch, end_epoch)) => {
+                            let (start_epoch, end_epoch) = self.scheduler.epoch_range(start_epoch, end_epoch);
                             self.lines.set_epoch_range(start_epoch, end_epoch);
                         },
                         Ok(NewSampleTask(task)) => {
                             let tries = U256::max_value() / task.podas_target;
                             info!(?task, ?tries, "Get new sample task");
+                            self.scheduler.progress().on_new_round(task);
                             current_task = Some((task, 0));
                         },
                         Ok(ClosedSampleTask(hash)) => {
                             info!(?hash, "Close sample task");
+                            self.scheduler.progress().on_closed_round(hash);
                             if current_task.map_or(false, |t| t.0.sample_seed == hash) {
                                 current_task = None;
                             }
@@ -81,16 +86,22 @@ impl DasStage1Miner {
                 }
 
                 // FIXME: the db load task may suffer a starvation because of line tasks.
-                db = self.db.read(), if self.lines.needs_fetch() => {
-                    if let Err(error) = self.lines.fetch_epoch(&*db, Duration::from_millis(100)).await {
+                _ = async {}, if self.lines.needs_fetch() => {
+                    if let Err(error) = self.lines.fetch_epoch(&*self.db, Duration::from_millis(100)).await {
                         warn!(?error, "DB error when fetching epochs");
                     }
                 }
 
-                _ = async {}, if current_task.is_some() && send_channel_opened => {
+                _ = self.scheduler.wait_runnable(), if current_task.is_some() && !runnable => {}
+
+                _ = async {}, if current_task.is_some() && send_channel_opened && runnable => {
                     let (task, start_epoch) = current_task.unwrap();
-                    let (filtered_lines, last_epoch) = self.lines.iter_next_epoch(start_epoch, MINE_EPOCH_BATCH, task);
+                    let (filtered_lines, last_epoch, scanned) = self.lines.iter_next_epoch(start_epoch, MINE_EPOCH_BATCH, task);
                     info!(start_epoch, last_epoch, iter_lines = filtered_lines.len(), "Stage 1 mine");
+                    self.scheduler.progress().on_mined(task.sample_seed, |round| {
+                        round.scanned_lines += scanned as u64;
+                        round.candidate_lines += filtered_lines.len() as u64;
+                    });
 
                     current_task = last_epoch.map(|e| (task, e + 1));
                     if !filtered_lines.is_empty() &&  self.first_stage_sender.send(filtered_lines).is_err(){
@@ -2,36 +2,41 @@ use std::collections::BinaryHeap;
 use std::sync::Arc;
 
 use contract_interface::da_sample::SampleResponse;
+use ethers::types::H256;
+use futures::future::join_all;
 use storage::slice_db::SliceDB;
 use storage::Storage;
-use task_executor::TaskExecutor;
-use tokio::sync::mpsc;
-use tokio::sync::RwLock;
+use tokio::{sync::mpsc, task::spawn_blocking};
 
 use crate::line_candidate::LineCandidate;
+use crate::sample_cache::SampleCache;
+use crate::scheduler::DasScheduler;
+use crate::service::DasTasks;
 
 pub struct DasStage2Miner {
-    db: Arc<RwLock<Storage>>,
+    db: Arc<Storage>,
     first_stage_receiver: mpsc::UnboundedReceiver<Vec<LineCandidate>>,
     submission_sender: mpsc::UnboundedSender<SampleResponse>,
+    scheduler: DasScheduler,
+    cache: SampleCache,
 }
 
 impl DasStage2Miner {
     pub fn spawn(
-        executor: TaskExecutor,
-        db: Arc<RwLock<Storage>>,
+        tasks: &DasTasks,
+        db: Arc<Storage>,
         first_stage_receiver: mpsc::UnboundedReceiver<Vec<LineCandidate>>,
         submission_sender: mpsc::UnboundedSender<SampleResponse>,
+        scheduler: DasScheduler,
     ) {
         let stage2_miner = Self {
             db,
             first_stage_receiver,
             submission_sender,
+            cache: SampleCache::new(scheduler.sample_cache_lines()),
+            scheduler,
         };
-        executor.spawn(
-            async move { Box::pin(stage2_miner.start()).await },
-            "stage2_miner",
-        );
+        tasks.spawn(stage2_miner.start(), "stage2_miner");
     }
 
     pub async fn start(mut self) {
@@ -41,6 +46,7 @@ impl DasStage2Miner {
         let mut line_candidates = BinaryHeap::new();
 
         loop {
+            let runnable = self.scheduler.runnable().await;
             tokio::select! {
                 biased;
 
@@ -57,8 +63,10 @@ impl DasStage2Miner {
                     }
                 },
 
-                db = self.db.read(), if !line_candidates.is_empty() && miner_enabled => {
-                    if let Err(e) = self.mine(&*db, &mut line_candidates).await {
+                _ = self.scheduler.wait_runnable(), if !line_candidates.is_empty() && miner_enabled && !runnable => {}
+
+                _ = async {}, if !line_candidates.is_empty() && miner_enabled && runnable => {
+                    if let Err(e) = self.mine(&*self.db, &mut line_candidates).await {
                         warn!(error = e, "Unexpected error, mine service stopped");
                         miner_enabled = false;
                         self.first_stage_receiver.close();
@@ -78,12 +86,53 @@ impl DasStage2Miner {
         db: &impl SliceDB,
         line_candidates: &mut BinaryHeap<LineCandidate>,
     ) -> Result<(), String> {
-        while let Some(candidate) = line_candidates.pop() {
-            for sample_response in candidate.mine(db).await? {
-                info!("Hit a valid answer");
-                if self.submission_sender.send(sample_response).is_err() {
-                    warn!("Submission channel closed.");
-                    return Err("Submission channel closed".to_string());
+        while !line_candidates.is_empty() {
+            // leave the rest to the next round once paused
+            if !self.scheduler.runnable().await {
+                break;
+            }
+            let candidates: Vec<LineCandidate> = (0..self.scheduler.concurrency())
+                .map_while(|_| line_candidates.pop())
+                .collect();
+            // loading the lines is io bound and searching them cpu bound, the searches run on the
+            // blocking threads so a batch of `concurrency` candidates uses as many cores
+            let lines = join_all(
+                candidates
+                    .iter()
+                    .map(|candidate| candidate.load_line(db, &self.cache)),
+            )
+            .await;
+            let searches = candidates
+                .into_iter()
+                .zip(lines)
+                .map(|(candidate, line)| async move {
+                    let line = match line? {
+                        Some(line) => line,
+                        None => return Ok((candidate, vec![])),
+                    };
+                    spawn_blocking(move || {
+                        let line_hits = candidate.find_valid_answer(&line);
+                        (candidate, line_hits)
+                    })
+                    .await
+                    .map_err(|e| format!("Line search failed: {:?}", e))
+                });
+            for result in join_all(searches).await {
+                let (candidate, line_hits) = result?;
+                if line_hits.is_empty() {
+                    continue;
+                }
+                for sample_response in candidate.make_sample_response(db, line_hits).await? {
+                    info!("Hit a valid answer");
+                    self.scheduler
+                        .progress()
+                        .on_mined(H256(sample_response.sample_seed), |round| {
+                            round.answers += 1
+                        });
+                    if self.submission_sender.send(sample_response).is_err() {
+                        warn!("Submission channel closed.");
+                        return Err("Submission channel closed".to_string());
+                    }
                 }
             }
         }
@@ -1,35 +1,53 @@
-use chain_utils::{DefaultMiddleware, DefaultMiddlewareInner};
+use std::sync::Arc;
+
+use chain_utils::{
+    nonce_manager::{NonceManager, SentTransaction},
+    DefaultMiddlewareInner,
+};
 use contract_interface::{da_sample::SampleResponse, DASample};
-use ethers::{abi::Address, contract::ContractCall, providers::PendingTransaction, utils::hex};
-use task_executor::TaskExecutor;
+use ethers::{
+    contract::ContractCall,
+    types::{H256, U64},
+    utils::hex,
+};
+use events::NodeEvent;
+use futures::future::join_all;
+use storage::{
+    das_reward_db::{DasRewardDB, SampleSubmission, SubmissionStatus},
+    Storage,
+};
 use tokio::sync::{broadcast, mpsc};
 
-use crate::watcher::OnChainChangeMessage;
+use crate::{scheduler::DasScheduler, service::DasTasks, watcher::OnChainChangeMessage};
 
 pub struct DasSubmitter {
     da_contract: DASample<DefaultMiddlewareInner>,
+    nonce_manager: NonceManager,
     on_chain_receiver: broadcast::Receiver<OnChainChangeMessage>,
     submission_receiver: mpsc::UnboundedReceiver<SampleResponse>,
+    store: Arc<Storage>,
+    scheduler: DasScheduler,
 }
 
 impl DasSubmitter {
     pub fn spawn(
-        executor: TaskExecutor,
-        provider: DefaultMiddleware,
+        tasks: &DasTasks,
+        da_contract: DASample<DefaultMiddlewareInner>,
         on_chain_receiver: broadcast::Receiver<OnChainChangeMessage>,
         submission_receiver: mpsc::UnboundedReceiver<SampleResponse>,
-        da_address: Address,
+        store: Arc<Storage>,
+        nonce_manager: NonceManager,
+        scheduler: DasScheduler,
     ) {
-        let da_contract = DASample::new(da_address, provider.clone());
         let submitter = Self {
             da_contract,
+            nonce_manager,
             submission_receiver,
             on_chain_receiver,
+            store,
+            scheduler,
         };
-        executor.spawn(
-            async move { Box::pin(submitter.start()).await },
-            "das_submitter",
-        );
+        tasks.spawn(submitter.start(), "das_submitter");
     }
 
     async fn start(mut self) {
@@ -37,6 +55,8 @@ impl DasSubmitter {
 
         let mut enabled = true;
         let mut current_task = None;
+        // answers sent in the current round
+        let mut submitted = 0u64;
 
         loop {
             tokio::select! {
@@ -46,6 +66,7 @@ impl DasSubmitter {
                     match msg {
                         Ok(NewSampleTask(task)) => {
                             current_task = Some(task);
+                            submitted = 0;
                         },
                         Ok(ClosedSampleTask(hash)) => {
                             if current_task.map_or(false, |t| t.sample_seed == hash) {
@@ -69,16 +90,60 @@ impl DasSubmitter {
                         warn!("Submission channel closed.");
                     }
 
-                    let response = msg.unwrap();
-                    if response.sample_seed == current_task.unwrap().sample_seed.0 {
-                        let _ = self.submit_response(response).await;
+                    let mut batch = vec![msg.unwrap()];
+                    // answers found while the previous batch was confirmed go together
+                    while batch.len() < self.scheduler.submission_batch_size() {
+                        match self.submission_receiver.try_recv() {
+                            Ok(response) => batch.push(response),
+                            Err(_) => break,
+                        }
                     }
+                    let sample_seed = current_task.unwrap().sample_seed.0;
+                    batch.retain(|response| response.sample_seed == sample_seed);
+                    submitted += self.submit_batch(batch, submitted).await;
                 }
             }
         }
     }
 
-    async fn submit_response(&self, response: SampleResponse) -> Result<(), ()> {
+    /// Send the best answers of a batch within the round cap back to back, so they take
+    /// consecutive nonces, then wait for all of them. Returns the number of answers sent.
+    async fn submit_batch(&self, mut batch: Vec<SampleResponse>, submitted: u64) -> u64 {
+        let room = self
+            .scheduler
+            .max_submissions_per_round()
+            .map_or(usize::MAX, |max| max.saturating_sub(submitted) as usize);
+        batch.sort_by_key(|response| response.quality);
+        if batch.len() > room {
+            info!(
+                dropped = batch.len() - room,
+                submitted, "Round submission cap reached, dropping answers"
+            );
+            batch.truncate(room);
+        }
+
+        let mut sent = vec![];
+        for response in batch {
+            if let Ok(submission) = self.send_response(response).await {
+                sent.push(submission);
+            }
+        }
+        let num_sent = sent.len() as u64;
+        if num_sent > 1 {
+            info!(num_sent, "Sent a batch of sample responses");
+        }
+        join_all(
+            sent.into_iter()
+                .map(|(submission, tx)| self.confirm_response(submission, tx)),
+        )
+        .await;
+        num_sent
+    }
+
+    async fn send_response(
+        &self,
+        response: SampleResponse,
+    ) -> Result<(SampleSubmission, SentTransaction), ()> {
         info_span!("submit_response");
         info!(
             epoch = response.epoch,
@@ -105,30 +170,95 @@ impl DasSubmitter {
             return Err(());
         }
 
+        let mut submission = SampleSubmission {
+            sample_seed: response.sample_seed,
+            epoch: response.epoch,
+            quorum_id: response.quorum_id,
+            data_root: response.data_root,
+            line_index: response.line_index,
+            subline_index: response.subline_index,
+            tx_hash: None,
+            status: SubmissionStatus::FAILED,
+        };
+
         let submission_call: ContractCall<_, _> =
-            self.da_contract.submit_sampling_response(response).legacy();
+            self.da_contract.submit_sampling_response(response);
         debug!(transaction = ?submission_call.tx, "Construct transaction");
 
         let estimate_gas = submission_call.estimate_gas().await;
         debug!(result = ?estimate_gas, "Estimate gas");
 
-        let pending_transaction: PendingTransaction<'_, _> =
-            submission_call.send().await.map_err(|e| {
+        let sent = match self.nonce_manager.send(submission_call.tx).await {
+            Ok(sent) => sent,
+            Err(e) => {
                 warn!(error = ?e, "Fail to send sample response transaction");
-            })?;
-        debug!(hash = ?pending_transaction.tx_hash(), "Send sample transaction");
+                self.record(&submission).await;
+                return Err(());
+            }
+        };
+        debug!(hash = ?sent.hash, nonce = ?sent.nonce, "Send sample transaction");
+        submission.tx_hash = Some(sent.hash.0);
+        submission.status = SubmissionStatus::SUBMITTED;
+        if let Err(error) = self.store.put_sample_submission(&submission).await {
+            warn!(?error, "Fail to record sample submission");
+        }
+        Ok((submission, sent))
+    }
+
+    async fn confirm_response(&self, mut submission: SampleSubmission, sent: SentTransaction) {
+        submission.status = SubmissionStatus::FAILED;
+        let res = self.wait_receipt(sent, &mut submission).await;
+        if res.is_ok() {
+            submission.status = SubmissionStatus::CONFIRMED;
+            self.scheduler
+                .events()
+                .publish(NodeEvent::DasSubmissionConfirmed {
+                    epoch: submission.epoch,
+                    quorum_id: submission.quorum_id,
+                    data_root: submission.data_root,
+                    tx_hash: submission.tx_hash.unwrap_or_default(),
+                });
+        }
+        self.record(&submission).await;
+    }
 
-        let receipt = pending_transaction
+    async fn wait_receipt(
+        &self,
+        sent: SentTransaction,
+        submission: &mut SampleSubmission,
+    ) -> Result<(), ()> {
+        let receipt = self
+            .nonce_manager
+            .confirm(sent)
             .await
             .map_err(|error| {
                 warn!(?error, "Fail to execute sample transaction");
             })?
             .ok_or_else(|| {
-                warn!("Transaction not executed after 3 retires");
+                warn!("Transaction dropped, its nonce is used by another one");
             })?;
+        // the transaction may be replaced with bumped fees
+        submission.tx_hash = Some(receipt.transaction_hash.0);
 
+        if receipt.status != Some(U64::from(1)) {
+            warn!(hash = ?H256::from(submission.tx_hash.unwrap()), "Sample transaction reverted");
+            return Err(());
+        }
         info!("Submit response success");
         debug!(?receipt, "Receipt");
         Ok(())
     }
+
+    /// Store the final state of a submission and report it as the latest one.
+    async fn record(&self, submission: &SampleSubmission) {
+        if let Err(error) = self.store.put_sample_submission(submission).await {
+            warn!(?error, "Fail to record sample submission");
+        }
+        self.scheduler.progress().on_submission(
+            submission.epoch,
+            submission.quorum_id,
+            submission.tx_hash,
+            submission.status,
+        );
+    }
 }
@@ -3,13 +3,14 @@ use contract_interface::{
     da_sample::{self},
     DASample,
 };
-use task_executor::TaskExecutor;
 
 use ethers::types::{Address, H256, U256};
 
 use tokio::sync::broadcast;
 use tokio::time::{sleep, Duration, Instant};
 
+use crate::service::DasTasks;
+
 #[derive(Debug, Clone, Copy)]
 pub struct SampleTask {
     pub sample_seed: H256,
@@ -38,7 +39,7 @@ pub struct DasWatcher {
 
 impl DasWatcher {
     pub async fn spawn(
-        executor: TaskExecutor,
+        tasks: &DasTasks,
         provider: DefaultMiddleware,
         sender: broadcast::Sender<OnChainChangeMessage>,
         da_address: Address,
@@ -50,10 +51,7 @@ impl DasWatcher {
             sender,
             last_status: None,
         };
-        executor.spawn(
-            async move { Box::pin(das_watcher.start()).await },
-            "das_watcher",
-        );
+        tasks.spawn(das_watcher.start(), "das_watcher");
 
         Ok(())
     }
@@ -0,0 +1,106 @@
+use tokio::sync::broadcast;
+
+const DEFAULT_EVENT_CAPACITY: usize = 1024;
+
+/// Lifecycle and blob events of a node, for embedding applications and internal monitors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeEvent {
+    /// All services are started.
+    Started,
+    /// The node is shutting down.
+    Stopping,
+    /// A blob is submitted to the `DAEntrance` contract.
+    BlobUploaded {
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    },
+    /// The erasure commitment of a blob is verified on chain.
+    BlobVerified {
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    },
+    /// Slices of a blob are written to the database, `rows` of them.
+    SlicesStored {
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        rows: u64,
+    },
+    /// Slices of a blob could not be written to the database, the write may be retried.
+    SliceWriteFailed {
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    },
+    /// The node verified and signed the slices of a blob.
+    BlobSigned {
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    },
+    /// The node rejected a sign request of a blob.
+    BlobRejected {
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        reason: String,
+    },
+    /// Sign requests of `failed_blobs` blobs in a row failed verification, the encoder params
+    /// likely mismatch the clients'.
+    VerificationFailureSpike { failed_blobs: u64 },
+    /// Slices of a blob are deleted, its storage period expired on chain.
+    BlobPruned {
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    },
+    /// The registration window of the next epoch closes in about `blocks_left` blocks and the
+    /// signer is not registered for it yet.
+    RegistrationAtRisk { epoch: u64, blocks_left: u64 },
+    /// The signer registered for an epoch.
+    RegistrationSucceeded { epoch: u64 },
+    /// A registration transaction of the signer for an epoch failed, it is retried.
+    RegistrationFailed { epoch: u64, error: String },
+    /// An epoch started without the signer registered for it, it earns nothing in the epoch.
+    RegistrationMissed { epoch: u64 },
+    /// The signer is assigned fewer quorum rows in an epoch than the stake monitor threshold,
+    /// its stake or delegations dropped.
+    StakeBelowThreshold { epoch: u64, assigned_rows: u64 },
+    /// A DAS response of the miner is confirmed on chain.
+    DasSubmissionConfirmed {
+        epoch: u64,
+        quorum_id: u64,
+        data_root: [u8; 32],
+        tx_hash: [u8; 32],
+    },
+}
+
+/// Broadcast channel of node events. Publishing never blocks, slow subscribers miss events.
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<NodeEvent>,
+}
+
+impl EventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: NodeEvent) {
+        // no subscriber is not an error
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
@@ -1,8 +1,52 @@
 use std::env;
 use std::path::PathBuf;
+use std::process::Command;
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.trim().to_string())
+}
+
+/// Build metadata read by `build_info`. Nothing depends on the time or path of the build, so the
+/// same commit and toolchain give the same values.
+fn emit_build_info() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-changed=../.git/HEAD");
+    println!("cargo:rerun-if-changed=../.git/refs/heads");
+
+    // builds from a source archive have no git folder, the commit is given instead
+    let git_commit = env::var("GIT_COMMIT")
+        .ok()
+        .or_else(|| command_output("git", &["rev-parse", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+    let dirty = command_output("git", &["status", "--porcelain", "--untracked-files=no"])
+        .map_or(false, |x| !x.is_empty());
+    let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    let rustc_version =
+        command_output(&rustc, &["--version"]).unwrap_or_else(|| "unknown".to_string());
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|x| x.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+
+    println!("cargo:rustc-env=BUILD_GIT_COMMIT={}", git_commit);
+    println!("cargo:rustc-env=BUILD_GIT_DIRTY={}", dirty);
+    println!("cargo:rustc-env=BUILD_RUSTC_VERSION={}", rustc_version);
+    println!("cargo:rustc-env=BUILD_FEATURES={}", features.join(","));
+    println!("cargo:rustc-env=BUILD_PROFILE={}", env::var("PROFILE")?);
+    println!("cargo:rustc-env=BUILD_TARGET={}", env::var("TARGET")?);
+    Ok(())
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("cargo:rerun-if-changed=proto");
+    emit_build_info()?;
 
     let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
 
@@ -11,7 +55,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .build_client(true)
         .build_server(true)
         .file_descriptor_set_path(out_dir.join("signer_descriptor.bin"))
-        .compile(&["proto/signer.proto"], &["proto"])?;
+        .compile(
+            &[
+                "proto/signer.proto",
+                "proto/signer_v2.proto",
+                "proto/admin.proto",
+            ],
+            &["proto"],
+        )?;
 
     Ok(())
 }
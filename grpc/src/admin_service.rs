@@ -0,0 +1,616 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use chain_state::{stake_monitor::StakeMonitor, sync_progress::SyncProgress};
+use da_miner::DasScheduler;
+use storage::{
+    das_reward_db::{DasRewardDB, SubmissionStatus},
+    event_db::{ChainEvent as StoredChainEvent, ChainEventDB, ChainEventKind},
+    reconcile_db::{self, ReconcileDB},
+    registration_db::{RegistrationDB, RegistrationStatus as EpochRegistrationStatus},
+    tx_history_db::{TxHistoryDB, TxOutcome},
+    usage_db::UsageDB,
+    Storage,
+};
+use tokio::sync::RwLock;
+use tonic::{transport::Server, Code, Request, Response, Status};
+
+use self::admin::{
+    admin_server::{Admin, AdminServer},
+    chain_event::Event,
+    chain_events_request::Query,
+    ChainEvent, ChainEventsReply, ChainEventsRequest, ClusterStatus, DasAccountingReply,
+    DasAccountingRequest, DasStatus, Empty, EpochRegistration, EpochUsage, Inconsistency,
+    InconsistencyKind, MaintenanceRequest, MaintenanceStatus, QuorumUsage, ReconcileAction,
+    ReconcileReport, RegistrationStatus, RegistrationStatusReply, RegistrationStatusRequest,
+    RuntimeMetrics, RuntimeMetricsReply, StakeStatus, StorageUsageReply, StorageUsageRequest,
+    SyncStatus, TransactionAttempt, TransactionHistory, TransactionHistoryRequest,
+    TransactionOutcome, VerificationMetricsReply,
+};
+use crate::{
+    cluster::ClusterConfig,
+    maintenance::{Maintenance, DEFAULT_MAINTENANCE_RETRY_AFTER},
+    runtime_monitor::RuntimeMonitor,
+    verification_metrics::{HistogramSnapshot, VerificationMetrics, BUCKET_BOUNDS_US},
+};
+
+const DEFAULT_TX_HISTORY_LIMIT: u32 = 20;
+const DEFAULT_REGISTRATION_LIMIT: u32 = 10;
+const DEFAULT_CHAIN_EVENTS_LIMIT: u32 = 100;
+
+pub mod admin {
+    tonic::include_proto!("admin");
+}
+
+#[derive(Clone)]
+pub struct AdminService {
+    pub(crate) db: Arc<Storage>,
+    das_scheduler: Option<DasScheduler>,
+    sync_progress: SyncProgress,
+    runtime_monitor: RuntimeMonitor,
+    cluster: Option<ClusterConfig>,
+    verification_metrics: VerificationMetrics,
+    stake_monitor: StakeMonitor,
+    maintenance: Maintenance,
+    sign_load: Arc<RwLock<u64>>,
+}
+
+fn chain_event(x: StoredChainEvent) -> ChainEvent {
+    let event = match x.kind {
+        ChainEventKind::DataUpload {
+            sender,
+            data_root,
+            epoch,
+            quorum_id,
+            blob_price,
+        } => Event::DataUpload(admin::DataUploadEvent {
+            sender: sender.to_vec(),
+            data_root: data_root.to_vec(),
+            epoch,
+            quorum_id,
+            blob_price: blob_price.to_string(),
+        }),
+        ChainEventKind::ErasureCommitmentVerified {
+            data_root,
+            epoch,
+            quorum_id,
+        } => Event::ErasureCommitmentVerified(admin::ErasureCommitmentVerifiedEvent {
+            data_root: data_root.to_vec(),
+            epoch,
+            quorum_id,
+        }),
+        ChainEventKind::NewSampleRound {
+            sample_round,
+            sample_height,
+            sample_seed,
+            podas_target,
+        } => Event::NewSampleRound(admin::NewSampleRoundEvent {
+            sample_round,
+            sample_height,
+            sample_seed: sample_seed.to_vec(),
+            podas_target: podas_target.to_vec(),
+        }),
+        ChainEventKind::DasReward {
+            beneficiary,
+            sample_round,
+            epoch,
+            quorum_id,
+            data_root,
+            quality,
+            line_index,
+            subline_index,
+            reward,
+        } => Event::DasReward(admin::DasRewardEvent {
+            beneficiary: beneficiary.to_vec(),
+            sample_round,
+            epoch,
+            quorum_id,
+            data_root: data_root.to_vec(),
+            quality: quality.to_vec(),
+            line_index,
+            subline_index,
+            reward: reward.to_string(),
+        }),
+    };
+    ChainEvent {
+        block_number: x.block_number,
+        log_index: x.log_index,
+        tx_hash: x.tx_hash.to_vec(),
+        event: Some(event),
+    }
+}
+
+fn histogram(snapshot: HistogramSnapshot) -> Option<admin::Histogram> {
+    Some(admin::Histogram {
+        bucket_bounds_us: BUCKET_BOUNDS_US.to_vec(),
+        bucket_counts: snapshot.buckets,
+        count: snapshot.count,
+        sum_us: snapshot.sum_us,
+    })
+}
+
+impl AdminService {
+    pub fn new(
+        db: Arc<Storage>,
+        das_scheduler: Option<DasScheduler>,
+        sync_progress: SyncProgress,
+        runtime_monitor: RuntimeMonitor,
+    ) -> Self {
+        Self {
+            db,
+            das_scheduler,
+            sync_progress,
+            runtime_monitor,
+            cluster: None,
+            verification_metrics: VerificationMetrics::default(),
+            stake_monitor: StakeMonitor::default(),
+            maintenance: Maintenance::default(),
+            sign_load: Default::default(),
+        }
+    }
+
+    pub fn with_cluster(mut self, cluster: Option<ClusterConfig>) -> Self {
+        self.cluster = cluster;
+        self
+    }
+
+    pub fn with_verification_metrics(mut self, verification_metrics: VerificationMetrics) -> Self {
+        self.verification_metrics = verification_metrics;
+        self
+    }
+
+    pub fn with_stake_monitor(mut self, stake_monitor: StakeMonitor) -> Self {
+        self.stake_monitor = stake_monitor;
+        self
+    }
+
+    /// Maintenance flag of the signer, and its ongoing sign requests.
+    pub fn with_maintenance(
+        mut self,
+        maintenance: Maintenance,
+        sign_load: Arc<RwLock<u64>>,
+    ) -> Self {
+        self.maintenance = maintenance;
+        self.sign_load = sign_load;
+        self
+    }
+
+    async fn maintenance_status(&self) -> MaintenanceStatus {
+        let ongoing_sign_requests = *self.sign_load.read().await;
+        match self.maintenance.state() {
+            Some(state) => MaintenanceStatus {
+                enabled: true,
+                since: state.since,
+                retry_after_secs: state.retry_after.as_secs(),
+                reason: state.reason,
+                ongoing_sign_requests,
+            },
+            None => MaintenanceStatus {
+                ongoing_sign_requests,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn das_status(&self) -> DasStatus {
+        let progress = self
+            .das_scheduler
+            .as_ref()
+            .map(|scheduler| scheduler.progress());
+        DasStatus {
+            enabled: self.das_scheduler.is_some(),
+            paused: self
+                .das_scheduler
+                .as_ref()
+                .map_or(false, |scheduler| scheduler.is_paused()),
+            round: progress.and_then(|x| x.round()).map(|round| {
+                let mut podas_target = [0u8; 32];
+                round.podas_target.to_big_endian(&mut podas_target);
+                admin::DasRound {
+                    sample_seed: round.sample_seed.0.to_vec(),
+                    podas_target: podas_target.to_vec(),
+                    started_at: round.started_at,
+                    scanned_lines: round.scanned_lines,
+                    candidate_lines: round.candidate_lines,
+                    answers: round.answers,
+                }
+            }),
+            last_submission: progress.and_then(|x| x.last_submission()).map(|x| {
+                admin::DasSubmissionResult {
+                    timestamp: x.timestamp,
+                    epoch: x.epoch,
+                    quorum_id: x.quorum_id,
+                    tx_hash: x.tx_hash.map_or(vec![], |hash| hash.to_vec()),
+                    confirmed: x.status == SubmissionStatus::CONFIRMED,
+                }
+            }),
+        }
+    }
+
+    fn das_scheduler(&self) -> Result<&DasScheduler, Status> {
+        self.das_scheduler
+            .as_ref()
+            .ok_or_else(|| Status::new(Code::FailedPrecondition, "DAS is not enabled"))
+    }
+}
+
+#[tonic::async_trait]
+impl Admin for AdminService {
+    async fn pause_das(&self, _request: Request<Empty>) -> Result<Response<DasStatus>, Status> {
+        self.das_scheduler()?.pause();
+        Ok(Response::new(self.das_status()))
+    }
+
+    async fn resume_das(&self, _request: Request<Empty>) -> Result<Response<DasStatus>, Status> {
+        self.das_scheduler()?.resume();
+        Ok(Response::new(self.das_status()))
+    }
+
+    async fn get_das_status(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<DasStatus>, Status> {
+        Ok(Response::new(self.das_status()))
+    }
+
+    async fn get_das_accounting(
+        &self,
+        request: Request<DasAccountingRequest>,
+    ) -> Result<Response<DasAccountingReply>, Status> {
+        let accounting = self
+            .db
+            .get_das_accounting(request.into_inner().epoch)
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        Ok(Response::new(DasAccountingReply {
+            submissions: accounting.submissions,
+            confirmed: accounting.confirmed,
+            failed: accounting.failed,
+            success_rate: if accounting.submissions == 0 {
+                0.0
+            } else {
+                accounting.confirmed as f64 / accounting.submissions as f64
+            },
+            rewards: accounting.rewards,
+            total_reward: accounting.total_reward.to_string(),
+        }))
+    }
+
+    async fn get_reconcile_report(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<ReconcileReport>, Status> {
+        let report = match self
+            .db
+            .get_reconcile_report()
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?
+        {
+            Some(report) => report,
+            None => return Ok(Response::new(ReconcileReport::default())),
+        };
+        Ok(Response::new(ReconcileReport {
+            available: true,
+            finished_at: report.finished_at,
+            start_epoch: report.start_epoch,
+            end_epoch: report.end_epoch,
+            checked_blobs: report.checked_blobs,
+            inconsistencies: report
+                .inconsistencies
+                .into_iter()
+                .map(|x| Inconsistency {
+                    epoch: x.epoch,
+                    quorum_id: x.quorum_id,
+                    storage_root: x.storage_root.to_vec(),
+                    kind: match x.kind {
+                        reconcile_db::InconsistencyKind::VerifiedWithoutSlices => {
+                            InconsistencyKind::VerifiedWithoutSlices
+                        }
+                        reconcile_db::InconsistencyKind::SlicesWithoutStatus => {
+                            InconsistencyKind::SlicesWithoutStatus
+                        }
+                    } as i32,
+                    action: match x.action {
+                        reconcile_db::ReconcileAction::Refetched => ReconcileAction::Refetched,
+                        reconcile_db::ReconcileAction::RestoredVerified => {
+                            ReconcileAction::RestoredVerified
+                        }
+                        reconcile_db::ReconcileAction::RestoredUploaded => {
+                            ReconcileAction::RestoredUploaded
+                        }
+                        reconcile_db::ReconcileAction::Flagged => ReconcileAction::Flagged,
+                    } as i32,
+                    missing_slices: x.missing_slices,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_sync_status(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<SyncStatus>, Status> {
+        let status = self.sync_progress.status().await;
+        Ok(Response::new(SyncStatus {
+            start_block: status.start_block,
+            current_block: status.current_block,
+            target_block: status.target_block,
+            events_processed: status.events_processed,
+            synced: status.synced,
+            eta_seconds: status.eta.map(|eta| eta.as_secs()),
+        }))
+    }
+
+    async fn get_transaction_history(
+        &self,
+        request: Request<TransactionHistoryRequest>,
+    ) -> Result<Response<TransactionHistory>, Status> {
+        let limit = request
+            .into_inner()
+            .limit
+            .unwrap_or(DEFAULT_TX_HISTORY_LIMIT);
+        let attempts = self
+            .db
+            .get_tx_attempts(limit as usize)
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        Ok(Response::new(TransactionHistory {
+            attempts: attempts
+                .into_iter()
+                .map(|x| TransactionAttempt {
+                    timestamp: x.timestamp,
+                    info: x.info,
+                    tx_hash: x.tx_hash.map_or(vec![], |hash| hash.to_vec()),
+                    outcome: match x.outcome {
+                        TxOutcome::SUCCEEDED => TransactionOutcome::Succeeded,
+                        TxOutcome::REVERTED => TransactionOutcome::Reverted,
+                        TxOutcome::DROPPED => TransactionOutcome::Dropped,
+                        TxOutcome::FAILED => TransactionOutcome::Failed,
+                    } as i32,
+                    reason: x.reason.unwrap_or_default(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_registration_status(
+        &self,
+        request: Request<RegistrationStatusRequest>,
+    ) -> Result<Response<RegistrationStatusReply>, Status> {
+        let limit = request
+            .into_inner()
+            .limit
+            .unwrap_or(DEFAULT_REGISTRATION_LIMIT);
+        let registrations = self
+            .db
+            .get_epoch_registrations(limit as usize)
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        let signer = self
+            .db
+            .get_signer_registration()
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        Ok(Response::new(RegistrationStatusReply {
+            signer: signer.map(|x| admin::SignerRegistration {
+                registered: x.registered,
+                socket: x.socket.unwrap_or_default(),
+                configured_socket: x.configured_socket,
+                checked_at: x.checked_at,
+                last_error: x.last_error.unwrap_or_default(),
+            }),
+            registrations: registrations
+                .into_iter()
+                .map(|x| EpochRegistration {
+                    epoch: x.epoch,
+                    status: match x.status {
+                        EpochRegistrationStatus::PENDING => RegistrationStatus::Pending,
+                        EpochRegistrationStatus::REGISTERED => RegistrationStatus::Registered,
+                        EpochRegistrationStatus::FAILED => RegistrationStatus::RegistrationFailed,
+                        EpochRegistrationStatus::MISSED => RegistrationStatus::Missed,
+                    } as i32,
+                    attempts: x.attempts,
+                    last_error: x.last_error.unwrap_or_default(),
+                    updated_at: x.updated_at,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_runtime_metrics(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<RuntimeMetricsReply>, Status> {
+        Ok(Response::new(RuntimeMetricsReply {
+            runtimes: self
+                .runtime_monitor
+                .stats()
+                .into_iter()
+                .map(|(name, stats)| RuntimeMetrics {
+                    name,
+                    workers: stats.workers as u64,
+                    probes: stats.probes,
+                    mean_scheduling_delay_us: stats.mean_delay().as_micros() as u64,
+                    max_scheduling_delay_us: stats.max_delay.as_micros() as u64,
+                    stalls: stats.stalls,
+                    last_stall_at: stats.last_stall_at,
+                })
+                .collect(),
+        }))
+    }
+
+    async fn get_verification_metrics(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<VerificationMetricsReply>, Status> {
+        let pool_threads = rayon::current_num_threads();
+        let throughput = self.verification_metrics.throughput(pool_threads);
+        Ok(Response::new(VerificationMetricsReply {
+            metrics: self
+                .verification_metrics
+                .snapshot()
+                .into_iter()
+                .map(|x| admin::VerificationMetrics {
+                    quorum_id: x.label.quorum_id,
+                    batch_size: x.label.batch_size,
+                    slices: x.slices,
+                    slice_verify: histogram(x.slice_verify),
+                    blob_verify: histogram(x.blob_verify),
+                    decode: histogram(x.decode),
+                })
+                .collect(),
+            slices_per_second: throughput.slices_per_second,
+            pool_utilization: throughput.pool_utilization,
+            pool_threads: pool_threads as u64,
+            window_seconds: throughput.window.as_secs(),
+        }))
+    }
+
+    async fn get_storage_usage(
+        &self,
+        request: Request<StorageUsageRequest>,
+    ) -> Result<Response<StorageUsageReply>, Status> {
+        let epoch = request.into_inner().epoch;
+        let db = &self.db;
+        let internal = |e: anyhow::Error| Status::new(Code::Internal, e.to_string());
+        let usage = db.get_slice_usage(epoch).await.map_err(internal)?;
+        let reclaimable = db.get_reclaimable_usage(epoch).await.map_err(internal)?;
+        let mut reply = StorageUsageReply {
+            db_size_bytes: db.disk_size().map_err(internal)?,
+            ..Default::default()
+        };
+        for ((epoch, quorum_id), usage) in usage {
+            let reclaimable = reclaimable
+                .get(&(epoch, quorum_id))
+                .copied()
+                .unwrap_or_default();
+            reply.slices += usage.slices;
+            reply.bytes += usage.bytes;
+            reply.reclaimable_bytes += reclaimable.bytes;
+            if reply.epochs.last().map(|x| x.epoch) != Some(epoch) {
+                reply.epochs.push(EpochUsage {
+                    epoch,
+                    ..Default::default()
+                });
+            }
+            let epoch_usage = reply.epochs.last_mut().unwrap();
+            epoch_usage.slices += usage.slices;
+            epoch_usage.bytes += usage.bytes;
+            epoch_usage.reclaimable_bytes += reclaimable.bytes;
+            reply.quorums.push(QuorumUsage {
+                epoch,
+                quorum_id,
+                slices: usage.slices,
+                bytes: usage.bytes,
+                reclaimable_slices: reclaimable.slices,
+                reclaimable_bytes: reclaimable.bytes,
+            });
+        }
+        Ok(Response::new(reply))
+    }
+
+    async fn get_chain_events(
+        &self,
+        request: Request<ChainEventsRequest>,
+    ) -> Result<Response<ChainEventsReply>, Status> {
+        let request = request.into_inner();
+        let limit = request.limit.unwrap_or(DEFAULT_CHAIN_EVENTS_LIMIT) as usize;
+        let events = match request.query {
+            Some(Query::Blocks(range)) => {
+                self.db
+                    .get_chain_events(range.from_block, range.to_block, limit)
+                    .await
+            }
+            Some(Query::Epoch(epoch)) => self.db.get_chain_events_by_epoch(epoch, limit).await,
+            Some(Query::DataRoot(data_root)) => {
+                let data_root = data_root.try_into().map_err(|_| {
+                    Status::new(Code::InvalidArgument, "data root must be 32 bytes")
+                })?;
+                self.db
+                    .get_chain_events_by_data_root(data_root, limit)
+                    .await
+            }
+            None => return Err(Status::new(Code::InvalidArgument, "query is not set")),
+        }
+        .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        Ok(Response::new(ChainEventsReply {
+            events: events.into_iter().map(chain_event).collect(),
+        }))
+    }
+
+    async fn get_stake_status(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<StakeStatus>, Status> {
+        let status = match self.stake_monitor.status().await {
+            Some(status) => status,
+            None => return Ok(Response::new(StakeStatus::default())),
+        };
+        Ok(Response::new(StakeStatus {
+            available: true,
+            epoch: status.epoch,
+            quorums: status.quorums,
+            assigned_rows: status.assigned_rows,
+            total_rows: status.total_rows,
+            quorums_without_rows: status.quorums_without_rows,
+            previous_assigned_rows: status.previous_assigned_rows,
+            below_threshold: status.below_threshold,
+        }))
+    }
+
+    async fn get_cluster_status(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<ClusterStatus>, Status> {
+        let cluster = self
+            .cluster
+            .as_ref()
+            .ok_or_else(|| Status::new(Code::FailedPrecondition, "cluster is not configured"))?;
+        let members = cluster.status().await;
+        Ok(Response::new(ClusterStatus {
+            name: cluster.name.clone(),
+            member: cluster.member.clone(),
+            reachable_members: members.iter().filter(|x| x.reachable).count() as u64,
+            members,
+        }))
+    }
+
+    async fn set_maintenance(
+        &self,
+        request: Request<MaintenanceRequest>,
+    ) -> Result<Response<MaintenanceStatus>, Status> {
+        let request = request.into_inner();
+        if request.enabled {
+            let retry_after = request
+                .retry_after_secs
+                .map_or(DEFAULT_MAINTENANCE_RETRY_AFTER, Duration::from_secs);
+            self.maintenance.enable(retry_after, request.reason.clone());
+            warn!(
+                "maintenance enabled, retry after {:?}: {}",
+                retry_after, request.reason
+            );
+        } else {
+            self.maintenance.disable();
+            info!("maintenance disabled");
+        }
+        Ok(Response::new(self.maintenance_status().await))
+    }
+
+    async fn get_maintenance_status(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<MaintenanceStatus>, Status> {
+        Ok(Response::new(self.maintenance_status().await))
+    }
+}
+
+pub async fn run_admin_server(
+    addr: SocketAddr,
+    admin_service: AdminService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("admin grpc server listening {:?}", addr);
+    Server::builder()
+        .add_service(AdminServer::new(admin_service))
+        .serve(addr)
+        .await?;
+    Ok(())
+}
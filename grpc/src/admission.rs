@@ -0,0 +1,141 @@
+use std::sync::Mutex;
+
+use tonic::{Code, Status};
+
+use crate::signer::BatchSignRequest;
+
+/// Budgets of the encoded slices of the sign batches handled at once, unlimited if `None`.
+#[derive(Debug, Clone, Default)]
+pub struct AdmissionConfig {
+    pub max_inflight_slices: Option<u64>,
+    pub max_inflight_bytes: Option<u64>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Inflight {
+    pub slices: u64,
+    pub bytes: u64,
+}
+
+impl Inflight {
+    pub fn of_batch(batch: &BatchSignRequest) -> Self {
+        let mut size = Self::default();
+        for req in batch.requests.iter() {
+            size.slices += req.encoded_slice.len() as u64;
+            size.bytes += req
+                .encoded_slice
+                .iter()
+                .map(|x| x.len() as u64)
+                .sum::<u64>();
+        }
+        size
+    }
+}
+
+/// Admits sign batches while the slices and bytes in flight fit the budgets, so the memory used
+/// by verification is bounded whatever the size of the batches.
+pub(crate) struct Admission {
+    config: AdmissionConfig,
+    inflight: Mutex<Inflight>,
+}
+
+/// Share of the budgets held by an admitted batch, given back on drop.
+pub(crate) struct AdmissionPermit<'a> {
+    admission: &'a Admission,
+    size: Inflight,
+}
+
+impl Admission {
+    pub fn new(config: AdmissionConfig) -> Self {
+        Self {
+            config,
+            inflight: Mutex::new(Inflight::default()),
+        }
+    }
+
+    pub fn inflight(&self) -> Inflight {
+        *self.inflight.lock().unwrap()
+    }
+
+    pub fn admit(&self, size: Inflight) -> Result<AdmissionPermit<'_>, Status> {
+        let mut inflight = self.inflight.lock().unwrap();
+        for (used, requested, max, unit) in [
+            (
+                inflight.slices,
+                size.slices,
+                self.config.max_inflight_slices,
+                "slices",
+            ),
+            (
+                inflight.bytes,
+                size.bytes,
+                self.config.max_inflight_bytes,
+                "bytes",
+            ),
+        ] {
+            let max = match max {
+                Some(max) => max,
+                None => continue,
+            };
+            if requested > max {
+                return Err(Status::new(
+                    Code::InvalidArgument,
+                    format!(
+                        "batch of {} {} exceeds the in-flight budget of {}, split it",
+                        requested, unit, max
+                    ),
+                ));
+            }
+            if used + requested > max {
+                return Err(Status::new(
+                    Code::ResourceExhausted,
+                    format!("{} of {} {} in flight, retry later", used, max, unit),
+                ));
+            }
+        }
+        inflight.slices += size.slices;
+        inflight.bytes += size.bytes;
+        Ok(AdmissionPermit {
+            admission: self,
+            size,
+        })
+    }
+}
+
+impl Drop for AdmissionPermit<'_> {
+    fn drop(&mut self) {
+        let mut inflight = self.admission.inflight.lock().unwrap();
+        inflight.slices -= self.size.slices;
+        inflight.bytes -= self.size.bytes;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn admit_test() {
+        let admission = Admission::new(AdmissionConfig {
+            max_inflight_slices: Some(10),
+            max_inflight_bytes: Some(1000),
+        });
+        let size = |slices, bytes| Inflight { slices, bytes };
+
+        let permit = admission.admit(size(6, 100)).unwrap();
+        assert_eq!(
+            admission.admit(size(5, 100)).err().unwrap().code(),
+            Code::ResourceExhausted
+        );
+        assert_eq!(
+            admission.admit(size(1, 2000)).err().unwrap().code(),
+            Code::InvalidArgument
+        );
+        let other = admission.admit(size(4, 900)).unwrap();
+        assert_eq!(admission.inflight(), size(10, 1000));
+
+        drop(permit);
+        drop(other);
+        assert_eq!(admission.inflight(), Inflight::default());
+    }
+}
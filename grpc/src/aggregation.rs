@@ -0,0 +1,200 @@
+use std::collections::HashSet;
+
+use ark_bn254::{Bn254, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::Zero;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use chain_state::signers_handler::verify_proof_of_possession;
+use ethers::types::H160;
+use tonic::{Code, Status};
+
+use crate::service::{
+    blob_verified_hash,
+    signer::{AggregateSignaturesReply, AggregateSignaturesRequest, ProofOfPossession},
+    SignerService,
+};
+
+/// Partial signatures accepted in a request.
+const MAX_PARTIAL_SIGNATURES: usize = 4096;
+
+fn invalid(message: String) -> Status {
+    Status::new(Code::InvalidArgument, message)
+}
+
+/// Verify every partial signature of a request against its public key over the
+/// `blob_verified_hash` of the blob, and sum the signatures and the public keys. The keys are not
+/// checked to be registered, but each comes with its proof of possession so no key is crafted to
+/// cancel the others.
+pub(crate) fn aggregate_signatures(
+    request: &AggregateSignaturesRequest,
+) -> Result<AggregateSignaturesReply, Status> {
+    if request.signatures.is_empty() {
+        return Err(invalid("no signatures to aggregate".to_string()));
+    }
+    if request.signatures.len() > MAX_PARTIAL_SIGNATURES {
+        return Err(invalid(format!(
+            "{} signatures exceed the limit of {}",
+            request.signatures.len(),
+            MAX_PARTIAL_SIGNATURES
+        )));
+    }
+    let (storage_root, erasure_commitment) =
+        SignerService::decode_blob(&request.storage_root, &request.erasure_commitment)?;
+    let hash = blob_verified_hash(
+        storage_root,
+        request.epoch,
+        request.quorum_id,
+        erasure_commitment,
+    );
+
+    let mut public_keys = HashSet::new();
+    let mut aggregate_signature = G1Projective::zero();
+    let mut aggregate_public_key = G2Projective::zero();
+    for (i, partial) in request.signatures.iter().enumerate() {
+        let signature = G1Affine::deserialize_uncompressed(&*partial.signature)
+            .map_err(|e| invalid(format!("signature {}: {:?}", i, e)))?;
+        let public_key = G2Affine::deserialize_uncompressed(&*partial.public_key)
+            .map_err(|e| invalid(format!("public key {}: {:?}", i, e)))?;
+        if public_key.is_zero() {
+            return Err(invalid(format!("public key {} is the identity", i)));
+        }
+        if !public_keys.insert(partial.public_key.as_slice()) {
+            return Err(invalid(format!("public key {} is given twice", i)));
+        }
+        check_proof_of_possession(partial.proof_of_possession.as_ref(), public_key)
+            .map_err(|e| invalid(format!("public key {}: {}", i, e)))?;
+        if Bn254::pairing(signature, G2Affine::generator()) != Bn254::pairing(hash, public_key) {
+            return Err(invalid(format!(
+                "signature {} does not verify against its public key",
+                i
+            )));
+        }
+        aggregate_signature += signature;
+        aggregate_public_key += public_key;
+    }
+
+    let mut reply = AggregateSignaturesReply::default();
+    aggregate_signature
+        .into_affine()
+        .serialize_uncompressed(&mut reply.aggregate_signature)
+        .map_err(|e| Status::new(Code::Internal, format!("{:?}", e)))?;
+    aggregate_public_key
+        .into_affine()
+        .serialize_uncompressed(&mut reply.aggregate_public_key)
+        .map_err(|e| Status::new(Code::Internal, format!("{:?}", e)))?;
+    Ok(reply)
+}
+
+/// Check the proof of possession of a G2 public key, it may be signed for any signer account.
+fn check_proof_of_possession(
+    proof: Option<&ProofOfPossession>,
+    public_key: G2Affine,
+) -> Result<(), String> {
+    let proof = match proof {
+        Some(proof) => proof,
+        None => return Err("proof of possession is missing".to_string()),
+    };
+    let signer_address: [u8; 20] = proof
+        .signer_address
+        .as_slice()
+        .try_into()
+        .map_err(|_| "invalid signer address of the proof of possession".to_string())?;
+    let deserialize_error = |e| format!("invalid proof of possession: {:?}", e);
+    let public_key_g1 =
+        G1Affine::deserialize_uncompressed(&*proof.public_key_g1).map_err(deserialize_error)?;
+    let public_key_g2 =
+        G2Affine::deserialize_uncompressed(&*proof.public_key_g2).map_err(deserialize_error)?;
+    let signature =
+        G1Affine::deserialize_uncompressed(&*proof.signature).map_err(deserialize_error)?;
+    if public_key_g2 != public_key
+        || !verify_proof_of_possession(
+            H160(signer_address),
+            proof.chain_id,
+            public_key_g1,
+            public_key_g2,
+            signature,
+        )
+    {
+        return Err("proof of possession does not verify".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::Fr;
+    use chain_state::signers_handler::proof_of_possession;
+
+    use crate::service::{sign_message, signer::PartialSignature};
+
+    use super::*;
+
+    fn partial(hash: G1Affine, key: u64) -> PartialSignature {
+        let key = Fr::from(key);
+        let mut partial = PartialSignature::default();
+        sign_message(hash, key)
+            .serialize_uncompressed(&mut partial.signature)
+            .unwrap();
+        (G2Affine::generator() * key)
+            .into_affine()
+            .serialize_uncompressed(&mut partial.public_key)
+            .unwrap();
+        let signer_address = H160::repeat_byte(1);
+        let mut proof = ProofOfPossession {
+            signer_address: signer_address.as_bytes().to_vec(),
+            chain_id: 16600,
+            public_key_g2: partial.public_key.clone(),
+            ..Default::default()
+        };
+        (G1Affine::generator() * key)
+            .into_affine()
+            .serialize_uncompressed(&mut proof.public_key_g1)
+            .unwrap();
+        proof_of_possession(key, signer_address, 16600)
+            .serialize_uncompressed(&mut proof.signature)
+            .unwrap();
+        partial.proof_of_possession = Some(proof);
+        partial
+    }
+
+    #[test]
+    fn aggregate_test() {
+        let commitment = G1Affine::generator();
+        let mut request = AggregateSignaturesRequest {
+            epoch: 1,
+            quorum_id: 2,
+            storage_root: vec![1; 32],
+            ..Default::default()
+        };
+        commitment
+            .serialize_compressed(&mut request.erasure_commitment)
+            .unwrap();
+        let hash = blob_verified_hash([1; 32], 1, 2, commitment.into_group());
+        request.signatures = vec![partial(hash, 3), partial(hash, 4)];
+        let reply = aggregate_signatures(&request).unwrap();
+        assert_eq!(
+            G1Affine::deserialize_uncompressed(&*reply.aggregate_signature).unwrap(),
+            sign_message(hash, Fr::from(7))
+        );
+        assert_eq!(
+            G2Affine::deserialize_uncompressed(&*reply.aggregate_public_key).unwrap(),
+            (G2Affine::generator() * Fr::from(7)).into_affine()
+        );
+
+        // signed for another blob
+        let other = blob_verified_hash([2; 32], 1, 2, commitment.into_group());
+        request.signatures.push(partial(other, 5));
+        assert!(aggregate_signatures(&request).is_err());
+        request.signatures[2] = partial(hash, 3);
+        assert!(aggregate_signatures(&request).is_err());
+
+        // keys without their own proof of possession
+        request.signatures.truncate(2);
+        request.signatures[1].proof_of_possession = None;
+        assert!(aggregate_signatures(&request).is_err());
+        request.signatures[1].proof_of_possession = partial(hash, 3).proof_of_possession;
+        assert!(aggregate_signatures(&request).is_err());
+        request.signatures[1] = partial(hash, 4);
+        assert!(aggregate_signatures(&request).is_ok());
+    }
+}
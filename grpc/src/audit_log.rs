@@ -0,0 +1,276 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Result};
+use ethers::utils::keccak256;
+use serde::{Deserialize, Serialize};
+use tonic::Status;
+
+/// Result of the entries of signed requests.
+pub const SIGNED: &str = "signed";
+
+/// A signing operation of the node. Every entry holds the hash of the previous one, so changing,
+/// removing or reordering entries breaks the chain from there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: u64,
+    /// Milliseconds since the unix epoch.
+    pub timestamp: u64,
+    /// Name of the sign client of the token of the request, or its address.
+    pub client: String,
+    pub epoch: u64,
+    pub quorum_id: u64,
+    pub storage_root: String,
+    pub commitment: String,
+    /// Empty if the request is not signed.
+    pub signature: String,
+    /// `signed`, or the error returned for the request.
+    pub result: String,
+    pub prev_hash: String,
+}
+
+/// Line of the log, an entry with its hash.
+#[derive(Serialize, Deserialize)]
+struct AuditRecord {
+    #[serde(flatten)]
+    entry: AuditEntry,
+    hash: String,
+}
+
+impl AuditEntry {
+    /// `keccak256` of the JSON encoding of the entry, which includes the previous hash.
+    pub fn hash(&self) -> Result<[u8; 32]> {
+        Ok(keccak256(serde_json::to_vec(self)?))
+    }
+}
+
+/// A sign request to audit.
+pub struct SignOperation<'a> {
+    pub client: &'a str,
+    pub epoch: u64,
+    pub quorum_id: u64,
+    pub storage_root: &'a [u8],
+    pub commitment: &'a [u8],
+    pub result: &'a Result<Vec<u8>, Status>,
+}
+
+struct AuditHead {
+    file: File,
+    /// Length of the complete entries, a failed append is truncated back to it.
+    len: u64,
+    next_seq: u64,
+    last_hash: [u8; 32],
+}
+
+impl AuditHead {
+    /// Chain the entry after the last one and write it, synced to disk before returning.
+    fn append(&mut self, mut entry: AuditEntry) -> Result<()> {
+        entry.seq = self.next_seq;
+        entry.prev_hash = hex::encode(self.last_hash);
+        let hash = entry.hash()?;
+        let mut line = serde_json::to_vec(&AuditRecord {
+            entry,
+            hash: hex::encode(hash),
+        })?;
+        line.push(b'\n');
+        if let Err(e) = self
+            .file
+            .write_all(&line)
+            .and_then(|()| self.file.sync_data())
+        {
+            // a partial line would make the next entries unreadable
+            if let Err(e) = self.file.set_len(self.len) {
+                error!(
+                    "cannot truncate the audit log after a failed append: {:?}",
+                    e
+                );
+            }
+            return Err(e.into());
+        }
+        self.len += line.len() as u64;
+        self.next_seq += 1;
+        self.last_hash = hash;
+        Ok(())
+    }
+}
+
+/// Append-only log of the signing operations, one JSON entry per line.
+pub struct AuditLog {
+    head: Arc<Mutex<AuditHead>>,
+}
+
+impl AuditLog {
+    /// Open the log at `path` to append after its last entry, it is created if missing. A last
+    /// line left half-written by a crash is truncated.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let mut content = vec![];
+        let file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(path)?;
+        (&file).read_to_end(&mut content)?;
+        let len = content
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |i| i + 1);
+        if len < content.len() {
+            warn!(
+                bytes = content.len() - len,
+                "truncating a half-written entry at the end of the audit log"
+            );
+            file.set_len(len as u64)?;
+            file.sync_data()?;
+        }
+        let mut head = AuditHead {
+            file,
+            len: len as u64,
+            next_seq: 0,
+            last_hash: [0; 32],
+        };
+        let last = content[..len]
+            .split(|byte| *byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .last();
+        if let Some(line) = last {
+            let record: AuditRecord = serde_json::from_slice(line)
+                .map_err(|e| anyhow!("last entry of the audit log is malformed: {:?}", e))?;
+            head.next_seq = record.entry.seq + 1;
+            head.last_hash = <[u8; 32]>::try_from(hex::decode(&record.hash)?.as_slice())?;
+        }
+        Ok(Self {
+            head: Arc::new(Mutex::new(head)),
+        })
+    }
+
+    /// Append the entry of an operation, synced to disk before returning. The file is written on
+    /// a blocking thread.
+    pub async fn append(&self, op: SignOperation<'_>) -> Result<()> {
+        let (signature, result) = match op.result {
+            Ok(signature) => (hex::encode(signature), SIGNED.to_string()),
+            Err(status) => (
+                String::new(),
+                format!("{:?}: {}", status.code(), status.message()),
+            ),
+        };
+        // sequence number and previous hash are set in order under the lock
+        let entry = AuditEntry {
+            seq: 0,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() as u64),
+            client: op.client.to_string(),
+            epoch: op.epoch,
+            quorum_id: op.quorum_id,
+            storage_root: hex::encode(op.storage_root),
+            commitment: hex::encode(op.commitment),
+            signature,
+            result,
+            prev_hash: String::new(),
+        };
+        let head = self.head.clone();
+        tokio::task::spawn_blocking(move || head.lock().unwrap().append(entry))
+            .await
+            .map_err(|e| anyhow!("audit log task failed: {:?}", e))?
+    }
+}
+
+/// Entries and last hash of a verified log.
+#[derive(Debug, PartialEq, Eq)]
+pub struct AuditLogHead {
+    pub entries: u64,
+    pub last_hash: [u8; 32],
+}
+
+/// Check the hash chain of the log at `path`, failing at the first entry that does not follow the
+/// previous one. Removed trailing entries are only detected by comparing the last hash with one
+/// recorded earlier.
+pub fn verify_audit_log(path: impl AsRef<Path>) -> Result<AuditLogHead> {
+    let mut head = AuditLogHead {
+        entries: 0,
+        last_hash: [0; 32],
+    };
+    for line in BufReader::new(File::open(path.as_ref())?).lines() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        let n = head.entries;
+        let record: AuditRecord = serde_json::from_str(&line)
+            .map_err(|e| anyhow!("entry {} is malformed: {:?}", n, e))?;
+        if record.entry.seq != n {
+            bail!(anyhow!(
+                "entry {} has sequence number {}",
+                n,
+                record.entry.seq
+            ));
+        }
+        if record.entry.prev_hash != hex::encode(head.last_hash) {
+            bail!(anyhow!("entry {} does not follow the previous entry", n));
+        }
+        let hash = record.entry.hash()?;
+        if record.hash != hex::encode(hash) {
+            bail!(anyhow!("entry {} does not match its hash", n));
+        }
+        head.entries += 1;
+        head.last_hash = hash;
+    }
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn audit_chain_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.log");
+        let signed = Ok(vec![1, 2]);
+        let rejected = Err(Status::new(Code::InvalidArgument, "verification failed"));
+        let op = |epoch, result| SignOperation {
+            client: "client-a",
+            epoch,
+            quorum_id: 0,
+            storage_root: &[1; 32],
+            commitment: &[2; 64],
+            result,
+        };
+        AuditLog::open(&path)
+            .unwrap()
+            .append(op(1, &signed))
+            .await
+            .unwrap();
+        // appends continue the chain after a reopen
+        let log = AuditLog::open(&path).unwrap();
+        log.append(op(2, &rejected)).await.unwrap();
+        drop(log);
+        // a half-written entry is dropped on open
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"seq\":2,\"time").unwrap();
+        drop(file);
+        let log = AuditLog::open(&path).unwrap();
+        log.append(op(3, &signed)).await.unwrap();
+        let head = verify_audit_log(&path).unwrap();
+        assert_eq!(head.entries, 3);
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let tampered = content.replacen("\"epoch\":2", "\"epoch\":4", 1);
+        std::fs::write(&path, tampered).unwrap();
+        assert!(verify_audit_log(&path).is_err());
+        let lines: Vec<&str> = content.lines().collect();
+        std::fs::write(&path, format!("{}\n{}\n", lines[0], lines[2])).unwrap();
+        assert!(verify_audit_log(&path).is_err());
+    }
+}
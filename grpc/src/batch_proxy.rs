@@ -0,0 +1,350 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use anyhow::Result;
+use futures::future::join_all;
+use tonic::{
+    transport::{Channel, Endpoint},
+    Code, Request, Status,
+};
+
+use crate::{
+    request_id::forward_request_id,
+    service::push_sign_result,
+    signer::{signer_client::SignerClient, BatchSignReply, BatchSignRequest, Empty, SignRequest},
+    SignerService, MESSAGE_SIZE_LIMIT,
+};
+
+const BACKEND_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Splitting of oversized sign batches into sub-batches signed by backend instances of the same
+/// signer, or by the local pipeline.
+#[derive(Clone)]
+pub struct BatchProxyConfig {
+    /// Urls of the backend instances, they must run the signer of this node.
+    pub backends: Vec<String>,
+    /// Whether the local pipeline also signs sub-batches.
+    pub local: bool,
+    /// Requests per sub-batch for backends advertising no limit.
+    pub sub_batch_size: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BackendState {
+    /// Advertised requests per batch, no limit if `None`.
+    pub max_batch_requests: Option<u64>,
+    pub max_ongoing: u64,
+    pub ongoing: u64,
+    pub reachable: bool,
+}
+
+struct Backend {
+    url: String,
+    /// `None` for the local pipeline.
+    client: Option<SignerClient<Channel>>,
+    state: Mutex<BackendState>,
+    /// Sub-batches sent by the proxy and not answered yet.
+    inflight: AtomicU64,
+}
+
+impl Backend {
+    fn state(&self, local_state: &BackendState) -> BackendState {
+        match &self.client {
+            Some(_) => *self.state.lock().unwrap(),
+            None => *local_state,
+        }
+    }
+
+    fn load(&self, local_state: &BackendState, planned: u64) -> Option<f64> {
+        let state = self.state(local_state);
+        if !state.reachable {
+            return None;
+        }
+        let ongoing = state.ongoing + self.inflight.load(Ordering::Relaxed) + planned;
+        Some(ongoing as f64 / state.max_ongoing.max(1) as f64)
+    }
+
+    fn batch_size(&self, local_state: &BackendState, default: u64) -> u64 {
+        self.state(local_state)
+            .max_batch_requests
+            .unwrap_or(default)
+            .max(1)
+    }
+
+    async fn batch_sign(
+        &self,
+        local: &SignerService,
+        request_id: Option<&str>,
+        requests: Vec<SignRequest>,
+        partial_success: bool,
+    ) -> Result<BatchSignReply, Status> {
+        self.inflight.fetch_add(1, Ordering::Relaxed);
+        let request = BatchSignRequest {
+            requests,
+            partial_success,
+        };
+        let reply = match &self.client {
+            Some(client) => {
+                let mut request = Request::new(request);
+                forward_request_id(&mut request, request_id);
+                client
+                    .clone()
+                    .batch_sign(request)
+                    .await
+                    .map(|reply| reply.into_inner())
+            }
+            None => local
+                .batch_sign_local(Request::new(request))
+                .await
+                .map(|reply| reply.into_inner()),
+        };
+        self.inflight.fetch_sub(1, Ordering::Relaxed);
+        reply
+    }
+}
+
+/// Signs batches of any size by splitting them into sub-batches sized after the advertised limits
+/// of the backends, each sent to the least loaded one, and recombining the signatures in order.
+pub struct BatchProxy {
+    backends: Vec<Backend>,
+    sub_batch_size: u64,
+}
+
+impl BatchProxy {
+    pub fn new(config: &BatchProxyConfig) -> Result<Self> {
+        let mut backends = vec![];
+        if config.local {
+            backends.push(Backend {
+                url: "local".to_string(),
+                client: None,
+                state: Default::default(),
+                inflight: AtomicU64::new(0),
+            });
+        }
+        for url in config.backends.iter() {
+            let channel = Endpoint::from_shared(url.clone())?
+                .timeout(BACKEND_TIMEOUT)
+                .connect_lazy();
+            backends.push(Backend {
+                url: url.clone(),
+                client: Some(
+                    SignerClient::new(channel)
+                        .max_decoding_message_size(MESSAGE_SIZE_LIMIT)
+                        .max_encoding_message_size(MESSAGE_SIZE_LIMIT),
+                ),
+                state: Default::default(),
+                inflight: AtomicU64::new(0),
+            });
+        }
+        Ok(Self {
+            backends,
+            sub_batch_size: config.sub_batch_size,
+        })
+    }
+
+    /// Poll the limits and the load of the remote backends.
+    pub async fn refresh_backends(&self) {
+        for backend in self.backends.iter() {
+            let (mut info_client, mut status_client) = match &backend.client {
+                Some(client) => (client.clone(), client.clone()),
+                None => continue,
+            };
+            let state = match tokio::try_join!(
+                info_client.get_node_info(Request::new(Empty {})),
+                status_client.get_status(Request::new(Empty {}))
+            ) {
+                Ok((info, status)) => {
+                    let (info, status) = (info.into_inner(), status.into_inner());
+                    BackendState {
+                        max_batch_requests: info.max_batch_requests,
+                        max_ongoing: status.max_ongoing_sign_requests,
+                        ongoing: status.ongoing_sign_requests,
+                        reachable: true,
+                    }
+                }
+                Err(e) => {
+                    debug!("batch proxy backend {} unreachable: {:?}", backend.url, e);
+                    BackendState::default()
+                }
+            };
+            *backend.state.lock().unwrap() = state;
+        }
+    }
+
+    /// Index of the least loaded reachable backend not in `excluded`, counting the sub-batches
+    /// `planned` for each backend.
+    fn pick_backend(
+        &self,
+        local_state: &BackendState,
+        planned: &[u64],
+        excluded: &[usize],
+    ) -> Option<usize> {
+        self.backends
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !excluded.contains(i))
+            .filter_map(|(i, backend)| Some((i, backend.load(local_state, planned[i])?)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    pub(crate) async fn batch_sign(
+        &self,
+        local: &SignerService,
+        request_id: Option<String>,
+        request: BatchSignRequest,
+    ) -> Result<BatchSignReply, Status> {
+        let local_state = local.batch_limits().await;
+        let mut remaining = request.requests;
+        let mut planned = vec![0; self.backends.len()];
+        let mut sub_batches = vec![];
+        while !remaining.is_empty() {
+            let backend = self
+                .pick_backend(&local_state, &planned, &[])
+                .ok_or_else(|| Status::new(Code::Unavailable, "no signer backend reachable"))?;
+            planned[backend] += 1;
+            let size = self.backends[backend]
+                .batch_size(&local_state, self.sub_batch_size)
+                .min(remaining.len() as u64) as usize;
+            sub_batches.push((backend, remaining.drain(..size).collect::<Vec<_>>()));
+        }
+        debug!(sub_batches = sub_batches.len(), "batch split");
+
+        let sizes: Vec<usize> = sub_batches.iter().map(|(_, x)| x.len()).collect();
+        let replies = join_all(sub_batches.into_iter().map(|(backend, requests)| {
+            self.sign_sub_batch(
+                local,
+                request_id.as_deref(),
+                backend,
+                requests,
+                request.partial_success,
+            )
+        }))
+        .await;
+        let mut reply = BatchSignReply::default();
+        for (sub_reply, size) in replies.into_iter().zip(sizes) {
+            match sub_reply.and_then(|x| check_sub_reply(x, size, request.partial_success)) {
+                Ok(mut sub_reply) => {
+                    reply.signatures.append(&mut sub_reply.signatures);
+                    reply.results.append(&mut sub_reply.results);
+                }
+                // the requests of a failed sub-batch fail alone in a partial success batch
+                Err(status) if request.partial_success => {
+                    for _ in 0..size {
+                        push_sign_result(&mut reply, Err(status.clone()));
+                    }
+                }
+                Err(status) => return Err(status),
+            }
+        }
+        Ok(reply)
+    }
+
+    /// Sign a sub-batch on `backend`, moving to other backends while they are unavailable or busy.
+    async fn sign_sub_batch(
+        &self,
+        local: &SignerService,
+        request_id: Option<&str>,
+        mut backend: usize,
+        requests: Vec<SignRequest>,
+        partial_success: bool,
+    ) -> Result<BatchSignReply, Status> {
+        let mut tried = vec![];
+        loop {
+            let status = match self.backends[backend]
+                .batch_sign(local, request_id, requests.clone(), partial_success)
+                .await
+            {
+                Ok(reply) => return Ok(reply),
+                Err(status) => status,
+            };
+            if !matches!(status.code(), Code::Unavailable | Code::ResourceExhausted) {
+                return Err(status);
+            }
+            warn!(
+                "batch proxy backend {} failed, trying another one: {:?}",
+                self.backends[backend].url, status
+            );
+            tried.push(backend);
+            let local_state = local.batch_limits().await;
+            backend = match self.pick_backend(&local_state, &vec![0; self.backends.len()], &tried) {
+                Some(next) => next,
+                None => return Err(status),
+            };
+        }
+    }
+}
+
+/// Check a backend answered every request of its sub-batch, so replies stay aligned with the
+/// requests of the batch.
+fn check_sub_reply(
+    reply: BatchSignReply,
+    size: usize,
+    partial_success: bool,
+) -> Result<BatchSignReply, Status> {
+    let results = if partial_success { size } else { 0 };
+    if reply.signatures.len() != size || reply.results.len() != results {
+        return Err(Status::new(
+            Code::Internal,
+            format!(
+                "backend replied {} signatures and {} results to {} requests",
+                reply.signatures.len(),
+                reply.results.len(),
+                size
+            ),
+        ));
+    }
+    Ok(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn pick_backend_test() {
+        let proxy = BatchProxy::new(&BatchProxyConfig {
+            backends: vec!["http://127.0.0.1:1".into(), "http://127.0.0.1:2".into()],
+            local: false,
+            sub_batch_size: 4,
+        })
+        .unwrap();
+        let local_state = BackendState::default();
+        assert_eq!(proxy.pick_backend(&local_state, &[0, 0], &[]), None);
+
+        let state = |max_batch_requests, ongoing| BackendState {
+            max_batch_requests,
+            max_ongoing: 10,
+            ongoing,
+            reachable: true,
+        };
+        *proxy.backends[0].state.lock().unwrap() = state(Some(2), 3);
+        *proxy.backends[1].state.lock().unwrap() = state(None, 4);
+        assert_eq!(proxy.pick_backend(&local_state, &[0, 0], &[]), Some(0));
+        // sub-batches already planned count as load
+        assert_eq!(proxy.pick_backend(&local_state, &[2, 0], &[]), Some(1));
+        assert_eq!(proxy.pick_backend(&local_state, &[0, 0], &[0]), Some(1));
+
+        assert_eq!(proxy.backends[0].batch_size(&local_state, 4), 2);
+        assert_eq!(proxy.backends[1].batch_size(&local_state, 4), 4);
+    }
+
+    #[test]
+    fn check_sub_reply_test() {
+        let mut reply = BatchSignReply::default();
+        push_sign_result(&mut reply, Ok(vec![1; 64]));
+        push_sign_result(&mut reply, Err(Status::new(Code::Internal, "failed")));
+        assert!(check_sub_reply(reply.clone(), 2, true).is_ok());
+        assert!(check_sub_reply(reply.clone(), 3, true).is_err());
+        assert!(check_sub_reply(reply.clone(), 2, false).is_err());
+
+        reply.results.clear();
+        assert!(check_sub_reply(reply.clone(), 2, false).is_ok());
+        assert!(check_sub_reply(reply, 1, false).is_err());
+    }
+}
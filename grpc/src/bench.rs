@@ -0,0 +1,174 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::{Fr, G1Projective};
+use rand::{thread_rng, Rng};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use zg_encoder::{
+    DeferredVerifier, EncodedBlob, EncodedSlice, RawBlob, RawData, ZgEncoderParams, ZgSignerParams,
+};
+
+use crate::replay::load_recorded_request;
+use crate::service::{blob_verified_hash, sign_message, SignerService};
+
+const BENCH_EPOCH: u64 = 1;
+const BENCH_QUORUM_ID: u64 = 0;
+
+/// Slices verified and signed by the benchmark, as in a sign request.
+pub struct BenchBlob {
+    pub storage_root: [u8; 32],
+    pub erasure_commitment: G1Projective,
+    pub encoded_slices: Vec<EncodedSlice>,
+}
+
+impl BenchBlob {
+    /// Slices of a request recorded by the node, e.g. with `record_transcript`.
+    pub fn load(path: &str) -> Result<Self> {
+        let record = load_recorded_request(path)?;
+        let req = record
+            .request
+            .as_ref()
+            .ok_or_else(|| anyhow!("recorded request {:?} is empty", path))?;
+        let (storage_root, erasure_commitment) =
+            SignerService::decode_root(req).map_err(|e| anyhow!(e.message().to_string()))?;
+        let encoded_slices = SignerService::decode_encoded_slices(req)
+            .map_err(|e| anyhow!(e.message().to_string()))?;
+        Ok(Self {
+            storage_root,
+            erasure_commitment,
+            encoded_slices,
+        })
+    }
+
+    /// Encode a random blob with the full encoder params of `params_dir` and keep its first
+    /// `slices` rows.
+    pub fn generate(params_dir: &str, slices: usize) -> Result<Self> {
+        let params = ZgEncoderParams::from_dir_mont(params_dir, false, None);
+        let mut data = vec![0u8; 1024];
+        thread_rng().fill(data.as_mut_slice());
+        let raw_data: RawData = data[..]
+            .try_into()
+            .map_err(|e| anyhow!("cannot make the sample blob: {:?}", e))?;
+        let raw_blob: RawBlob = raw_data.into();
+        let encoded_blob = EncodedBlob::build(&raw_blob, &params);
+        Ok(Self {
+            storage_root: encoded_blob.get_file_root(),
+            erasure_commitment: encoded_blob.get_commitment(),
+            encoded_slices: (0..slices)
+                .map(|index| encoded_blob.get_row(index))
+                .collect(),
+        })
+    }
+}
+
+/// Latency and throughput of verifying and signing a blob with a number of verification threads.
+#[derive(Debug, Clone)]
+pub struct BenchResult {
+    pub threads: usize,
+    pub iterations: usize,
+    pub slices: usize,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl BenchResult {
+    pub fn blobs_per_sec(&self) -> f64 {
+        1.0 / self.mean.as_secs_f64()
+    }
+
+    pub fn slices_per_sec(&self) -> f64 {
+        self.slices as f64 / self.mean.as_secs_f64()
+    }
+}
+
+/// Verify the slices of `blob` and sign it as `BatchSign` does, `iterations` times on a pool of
+/// each of `threads`, after a warm-up iteration.
+pub fn run_benchmark(
+    params: &ZgSignerParams,
+    blob: &BenchBlob,
+    threads: &[usize],
+    iterations: usize,
+) -> Result<Vec<BenchResult>> {
+    if iterations == 0 {
+        bail!(anyhow!("at least one iteration is needed"));
+    }
+    let key = Fr::from(thread_rng().gen::<u64>());
+    let mut results = vec![];
+    for &num_threads in threads {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .map_err(|e| anyhow!("cannot build a pool of {} threads: {:?}", num_threads, e))?;
+        let mut latencies = pool.install(|| -> Result<Vec<Duration>> {
+            verify_and_sign(params, blob, key)?;
+            (0..iterations)
+                .map(|_| {
+                    let ts = Instant::now();
+                    verify_and_sign(params, blob, key)?;
+                    Ok(ts.elapsed())
+                })
+                .collect()
+        })?;
+        latencies.sort();
+        let result = BenchResult {
+            threads: num_threads,
+            iterations,
+            slices: blob.encoded_slices.len(),
+            mean: latencies.iter().sum::<Duration>() / iterations as u32,
+            p50: percentile(&latencies, 50),
+            p99: percentile(&latencies, 99),
+            max: latencies[iterations - 1],
+        };
+        info!(threads = num_threads, mean = ?result.mean, "benchmarked");
+        results.push(result);
+    }
+    Ok(results)
+}
+
+fn verify_and_sign(params: &ZgSignerParams, blob: &BenchBlob, key: Fr) -> Result<()> {
+    let deferred_verifier = DeferredVerifier::new();
+    blob.encoded_slices
+        .par_iter()
+        .map(|slice| {
+            slice.verify(
+                params,
+                &blob.erasure_commitment,
+                &blob.storage_root,
+                Some(deferred_verifier.clone()),
+            )
+        })
+        .collect::<Result<(), _>>()
+        .map_err(|e| anyhow!("sample slices fail verification: {:?}", e))?;
+    if !deferred_verifier.fast_check() {
+        bail!(anyhow!("sample slices fail the deferred pairing check"));
+    }
+    let hash = blob_verified_hash(
+        blob.storage_root,
+        BENCH_EPOCH,
+        BENCH_QUORUM_ID,
+        blob.erasure_commitment,
+    );
+    sign_message(hash, key);
+    Ok(())
+}
+
+/// Nearest-rank percentile of sorted latencies.
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    let rank = (sorted.len() * p + 99) / 100;
+    sorted[rank.max(1) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_test() {
+        let latencies: Vec<Duration> = (1..=200).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&latencies, 50), Duration::from_millis(100));
+        assert_eq!(percentile(&latencies, 99), Duration::from_millis(198));
+        assert_eq!(percentile(&latencies[..1], 99), Duration::from_millis(1));
+    }
+}
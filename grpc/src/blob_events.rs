@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+
+use events::{EventBus, NodeEvent};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Code, Status};
+use tracing::Instrument;
+
+use crate::service::signer::{self, BlobEvent, BlobEventKind};
+
+/// Events buffered ahead of a subscriber, it lags behind the event bus once they are full.
+const BLOB_EVENTS_BUFFER: usize = 64;
+
+pub(crate) type BlobEventStream = ReceiverStream<Result<BlobEvent, Status>>;
+
+/// Blobs a subscriber is notified of.
+#[derive(Debug, Default)]
+struct BlobEventFilter {
+    from_epoch: Option<u64>,
+    to_epoch: Option<u64>,
+    storage_roots: HashSet<[u8; 32]>,
+}
+
+impl TryFrom<signer::BlobEventFilter> for BlobEventFilter {
+    type Error = Status;
+
+    fn try_from(filter: signer::BlobEventFilter) -> Result<Self, Status> {
+        let storage_roots = filter
+            .storage_roots
+            .into_iter()
+            .map(|x| {
+                x.try_into()
+                    .map_err(|_| Status::new(Code::InvalidArgument, "storage root"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            from_epoch: filter.from_epoch,
+            to_epoch: filter.to_epoch,
+            storage_roots,
+        })
+    }
+}
+
+impl BlobEventFilter {
+    fn matches(&self, event: &BlobEvent) -> bool {
+        self.from_epoch.map_or(true, |x| event.epoch >= x)
+            && self.to_epoch.map_or(true, |x| event.epoch <= x)
+            && (self.storage_roots.is_empty()
+                || <[u8; 32]>::try_from(event.storage_root.as_slice())
+                    .map_or(false, |x| self.storage_roots.contains(&x)))
+    }
+}
+
+/// The blob event of a node event, `None` for the events not about a blob.
+fn blob_event(event: NodeEvent) -> Option<BlobEvent> {
+    let (kind, epoch, quorum_id, storage_root) = match &event {
+        NodeEvent::BlobUploaded {
+            epoch,
+            quorum_id,
+            storage_root,
+        } => (BlobEventKind::BlobUploaded, epoch, quorum_id, storage_root),
+        NodeEvent::SlicesStored {
+            epoch,
+            quorum_id,
+            storage_root,
+            ..
+        } => (BlobEventKind::SlicesStored, epoch, quorum_id, storage_root),
+        NodeEvent::BlobVerified {
+            epoch,
+            quorum_id,
+            storage_root,
+        } => (BlobEventKind::BlobVerified, epoch, quorum_id, storage_root),
+        NodeEvent::BlobSigned {
+            epoch,
+            quorum_id,
+            storage_root,
+        } => (BlobEventKind::BlobSigned, epoch, quorum_id, storage_root),
+        NodeEvent::BlobRejected {
+            epoch,
+            quorum_id,
+            storage_root,
+            ..
+        } => (BlobEventKind::BlobRejected, epoch, quorum_id, storage_root),
+        NodeEvent::BlobPruned {
+            epoch,
+            quorum_id,
+            storage_root,
+        } => (BlobEventKind::BlobPruned, epoch, quorum_id, storage_root),
+        _ => return None,
+    };
+    let mut blob_event = BlobEvent {
+        kind: kind as i32,
+        epoch: *epoch,
+        quorum_id: *quorum_id,
+        storage_root: storage_root.to_vec(),
+        ..Default::default()
+    };
+    match event {
+        NodeEvent::SlicesStored { rows, .. } => blob_event.rows = rows,
+        NodeEvent::BlobRejected { reason, .. } => blob_event.reason = reason,
+        _ => {}
+    }
+    Some(blob_event)
+}
+
+/// Stream the blob events of `events` matching `filter` until the client goes away. A client
+/// that lags behind the bus misses events, so its stream ends with an error.
+pub(crate) fn subscribe_blob_events(
+    events: &EventBus,
+    filter: signer::BlobEventFilter,
+) -> Result<BlobEventStream, Status> {
+    let filter = BlobEventFilter::try_from(filter)?;
+    let mut receiver = events.subscribe();
+    let (tx, rx) = mpsc::channel(BLOB_EVENTS_BUFFER);
+    tokio::spawn(
+        async move {
+            loop {
+                let event = tokio::select! {
+                    _ = tx.closed() => return,
+                    event = receiver.recv() => event,
+                };
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(missed)) => {
+                        let _ = tx
+                            .send(Err(Status::new(
+                                Code::ResourceExhausted,
+                                format!("{} blob events missed, subscribe again", missed),
+                            )))
+                            .await;
+                        return;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                let event = match blob_event(event) {
+                    Some(event) if filter.matches(&event) => event,
+                    _ => continue,
+                };
+                if tx.send(Ok(event)).await.is_err() {
+                    return;
+                }
+            }
+        }
+        .in_current_span(),
+    );
+    Ok(ReceiverStream::new(rx))
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_stream::StreamExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn subscribe_test() {
+        let events = EventBus::default();
+        let mut stream = subscribe_blob_events(
+            &events,
+            signer::BlobEventFilter {
+                from_epoch: Some(2),
+                to_epoch: None,
+                storage_roots: vec![vec![1; 32]],
+            },
+        )
+        .unwrap();
+        events.publish(NodeEvent::Started);
+        events.publish(NodeEvent::BlobSigned {
+            epoch: 1,
+            quorum_id: 0,
+            storage_root: [1; 32],
+        });
+        events.publish(NodeEvent::SlicesStored {
+            epoch: 2,
+            quorum_id: 0,
+            storage_root: [2; 32],
+            rows: 3,
+        });
+        events.publish(NodeEvent::SlicesStored {
+            epoch: 2,
+            quorum_id: 0,
+            storage_root: [1; 32],
+            rows: 3,
+        });
+        let event = stream.next().await.unwrap().unwrap();
+        assert_eq!(event.kind, BlobEventKind::SlicesStored as i32);
+        assert_eq!((event.epoch, event.rows), (2, 3));
+
+        assert!(subscribe_blob_events(
+            &events,
+            signer::BlobEventFilter {
+                storage_roots: vec![vec![1; 31]],
+                ..Default::default()
+            },
+        )
+        .is_err());
+    }
+}
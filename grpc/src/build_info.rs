@@ -0,0 +1,50 @@
+use crate::signer;
+
+/// Setup, depth and degree of the encoder params this build verifies with, as in the param file
+/// names `amt-{kind}-coset{i}-{setup}-{depth}-{degree}.bin`.
+pub const PARAMS_COMPAT_VERSION: &str = "5DWgDV-10-20";
+
+/// Metadata of the running binary, embedded at build time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuildInfo {
+    pub version: String,
+    pub git_commit: String,
+    /// Built from a tree with uncommitted changes.
+    pub git_dirty: bool,
+    pub rustc_version: String,
+    pub features: Vec<String>,
+    pub profile: String,
+    pub target: String,
+    pub params_version: String,
+}
+
+pub fn build_info() -> BuildInfo {
+    BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: env!("BUILD_GIT_COMMIT").to_string(),
+        git_dirty: env!("BUILD_GIT_DIRTY") == "true",
+        rustc_version: env!("BUILD_RUSTC_VERSION").to_string(),
+        features: env!("BUILD_FEATURES")
+            .split(',')
+            .filter(|x| !x.is_empty())
+            .map(String::from)
+            .collect(),
+        profile: env!("BUILD_PROFILE").to_string(),
+        target: env!("BUILD_TARGET").to_string(),
+        params_version: PARAMS_COMPAT_VERSION.to_string(),
+    }
+}
+
+impl From<BuildInfo> for signer::BuildInfo {
+    fn from(info: BuildInfo) -> Self {
+        Self {
+            git_commit: info.git_commit,
+            git_dirty: info.git_dirty,
+            rustc_version: info.rustc_version,
+            features: info.features,
+            profile: info.profile,
+            target: info.target,
+            params_version: info.params_version,
+        }
+    }
+}
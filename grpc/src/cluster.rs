@@ -0,0 +1,177 @@
+use std::{str::FromStr, time::Duration};
+
+use anyhow::{anyhow, bail, Result};
+use futures::future::join_all;
+use tonic::{transport::Endpoint, Request};
+
+use crate::{
+    admin::ClusterMemberStatus,
+    signer::{signer_client::SignerClient, Empty},
+};
+
+const MEMBER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What a member of a cluster does, features pick their peers among the members by role.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterRole {
+    /// Signs and stores blobs, its sign outcomes are compared with the ones of this node.
+    Signer,
+    /// Signs sub-batches for the batch proxy.
+    SignBackend,
+    /// Stores and serves slices, a source of resync.
+    Retrieval,
+}
+
+impl ClusterRole {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ClusterRole::Signer => "signer",
+            ClusterRole::SignBackend => "sign_backend",
+            ClusterRole::Retrieval => "retrieval",
+        }
+    }
+}
+
+impl FromStr for ClusterRole {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "signer" => Ok(ClusterRole::Signer),
+            "sign_backend" => Ok(ClusterRole::SignBackend),
+            "retrieval" => Ok(ClusterRole::Retrieval),
+            _ => bail!(anyhow!("Unknown cluster role `{}`", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClusterMember {
+    pub name: String,
+    /// Url of the signer grpc service.
+    pub grpc_address: String,
+    pub roles: Vec<ClusterRole>,
+    /// Whether the member runs the signer keys of this node, or its own.
+    pub shared_identity: bool,
+}
+
+/// Nodes run by the same operator. Every member gets the same members, along with its own name.
+#[derive(Debug, Clone)]
+pub struct ClusterConfig {
+    pub name: String,
+    /// Name of this node among the members.
+    pub member: String,
+    pub members: Vec<ClusterMember>,
+}
+
+impl ClusterConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (i, member) in self.members.iter().enumerate() {
+            if self.members[..i].iter().any(|x| x.name == member.name) {
+                bail!(anyhow!("Duplicated cluster member `{}`", member.name));
+            }
+            if member.roles.contains(&ClusterRole::SignBackend) && !member.shared_identity {
+                bail!(anyhow!(
+                    "Cluster member `{}` must share the identity to be a sign backend",
+                    member.name
+                ));
+            }
+        }
+        if !self.members.iter().any(|x| x.name == self.member) {
+            bail!(anyhow!("Cluster member `{}` is not listed", self.member));
+        }
+        Ok(())
+    }
+
+    /// Grpc addresses of the other members running the signer of this node with `role`.
+    pub fn sibling_addresses(&self, role: ClusterRole) -> Vec<String> {
+        self.members
+            .iter()
+            .filter(|x| x.name != self.member && x.shared_identity && x.roles.contains(&role))
+            .map(|x| x.grpc_address.clone())
+            .collect()
+    }
+
+    /// Status of every member, polled from their signer service.
+    pub async fn status(&self) -> Vec<ClusterMemberStatus> {
+        join_all(self.members.iter().map(member_status)).await
+    }
+}
+
+async fn member_status(member: &ClusterMember) -> ClusterMemberStatus {
+    let mut status = ClusterMemberStatus {
+        name: member.name.clone(),
+        grpc_address: member.grpc_address.clone(),
+        roles: member.roles.iter().map(|x| x.name().to_string()).collect(),
+        shared_identity: member.shared_identity,
+        ..Default::default()
+    };
+    let result = async {
+        let channel = Endpoint::from_shared(member.grpc_address.clone())?
+            .timeout(MEMBER_TIMEOUT)
+            .connect_timeout(MEMBER_TIMEOUT)
+            .connect()
+            .await?;
+        let mut client = SignerClient::new(channel);
+        let info = client.get_node_info(Request::new(Empty {})).await?;
+        let reply = client.get_status(Request::new(Empty {})).await?;
+        anyhow::Ok((info.into_inner(), reply.into_inner()))
+    }
+    .await;
+    match result {
+        Ok((info, reply)) => {
+            status.reachable = true;
+            status.version = info.version;
+            status.status_code = reply.status_code;
+            status.conditions = reply.conditions.len() as u64;
+            status.ongoing_sign_requests = reply.ongoing_sign_requests;
+            status.max_ongoing_sign_requests = reply.max_ongoing_sign_requests;
+        }
+        Err(e) => status.error = format!("{:?}", e),
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sibling_addresses_test() {
+        let member = |name: &str, roles: Vec<ClusterRole>, shared_identity| ClusterMember {
+            name: name.into(),
+            grpc_address: format!("http://{}:34000", name),
+            roles,
+            shared_identity,
+        };
+        let mut cluster = ClusterConfig {
+            name: "test".into(),
+            member: "a".into(),
+            members: vec![
+                member("a", vec![ClusterRole::Signer], true),
+                member(
+                    "b",
+                    vec![ClusterRole::Signer, ClusterRole::SignBackend],
+                    true,
+                ),
+                member("c", vec![ClusterRole::Signer], false),
+                member("d", vec![ClusterRole::Retrieval], true),
+            ],
+        };
+        cluster.validate().unwrap();
+        assert_eq!(
+            cluster.sibling_addresses(ClusterRole::Signer),
+            vec!["http://b:34000"]
+        );
+        assert_eq!(
+            cluster.sibling_addresses(ClusterRole::Retrieval),
+            vec!["http://d:34000"]
+        );
+
+        cluster.members[2].roles.push(ClusterRole::SignBackend);
+        assert!(cluster.validate().is_err());
+        cluster.members[2].roles.pop();
+        cluster.member = "e".into();
+        assert!(cluster.validate().is_err());
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    fs,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use anyhow::anyhow;
+use futures::{Stream, StreamExt};
+use socket2::{Domain, Protocol, SockRef, Socket, TcpKeepalive, Type};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    net::{TcpListener, TcpStream},
+    sync::watch,
+    time::{sleep, Sleep},
+};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{
+    server::{Connected, TcpConnectInfo},
+    Certificate, Identity, ServerTlsConfig,
+};
+
+/// Pending connections of a listener.
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// Lifecycle of the client connections of the signer and retrieval grpc servers.
+#[derive(Debug, Clone)]
+pub struct GrpcConnectionConfig {
+    /// Interval of the http2 pings keeping idle connections alive through NATs and load
+    /// balancers, no pings if `None`.
+    pub http2_keepalive_interval: Option<Duration>,
+    /// Connections are closed when a ping is not answered in time, 20 seconds if `None`.
+    pub http2_keepalive_timeout: Option<Duration>,
+    pub tcp_keepalive: Option<Duration>,
+    /// Connections older than this are closed so clients reconnect and spread over the nodes
+    /// behind a load balancer. tonic cannot send a GOAWAY to a single connection, calls still
+    /// running on it fail with `UNAVAILABLE`.
+    pub max_connection_age: Option<Duration>,
+    /// On shutdown a GOAWAY is sent to all connections, the calls running are given this long.
+    pub shutdown_grace: Duration,
+}
+
+impl Default for GrpcConnectionConfig {
+    fn default() -> Self {
+        Self {
+            http2_keepalive_interval: None,
+            http2_keepalive_timeout: None,
+            tcp_keepalive: None,
+            max_connection_age: None,
+            shutdown_grace: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Certificate of a grpc listener, in PEM files.
+#[derive(Debug, Clone)]
+pub struct GrpcTlsConfig {
+    pub cert_path: String,
+    pub key_path: String,
+    /// Clients must present a certificate signed by this CA, any client is accepted if `None`.
+    pub client_ca_path: Option<String>,
+}
+
+impl GrpcTlsConfig {
+    pub fn load(&self) -> anyhow::Result<ServerTlsConfig> {
+        let read = |path: &str| {
+            fs::read(path).map_err(|e| anyhow!("cannot read tls file {:?}: {:?}", path, e))
+        };
+        let mut tls = ServerTlsConfig::new().identity(Identity::from_pem(
+            read(&self.cert_path)?,
+            read(&self.key_path)?,
+        ));
+        if let Some(path) = &self.client_ca_path {
+            tls = tls.client_ca_root(Certificate::from_pem(read(path)?));
+        }
+        Ok(tls)
+    }
+}
+
+/// A socket the signer services are served on.
+#[derive(Debug, Clone)]
+pub struct GrpcListener {
+    pub address: SocketAddr,
+    /// Accept only IPv6 clients on an IPv6 address, IPv4 ones are accepted too otherwise.
+    pub ipv6_only: bool,
+    /// Plaintext if `None`.
+    pub tls: Option<GrpcTlsConfig>,
+}
+
+impl GrpcListener {
+    pub fn new(address: SocketAddr) -> Self {
+        Self {
+            address,
+            ipv6_only: false,
+            tls: None,
+        }
+    }
+
+    /// Make the IPv6 listeners sharing their port with an IPv4 one IPv6 only, a dual-stack socket
+    /// would take the IPv4 port too.
+    pub fn split_dual_stack(listeners: &mut [GrpcListener]) {
+        let ipv4_ports: Vec<u16> = listeners
+            .iter()
+            .filter(|x| x.address.is_ipv4())
+            .map(|x| x.address.port())
+            .collect();
+        for listener in listeners.iter_mut() {
+            if listener.address.is_ipv6() && ipv4_ports.contains(&listener.address.port()) {
+                listener.ipv6_only = true;
+            }
+        }
+    }
+
+    fn bind(&self) -> std::io::Result<TcpListener> {
+        let socket = Socket::new(
+            Domain::for_address(self.address),
+            Type::STREAM,
+            Some(Protocol::TCP),
+        )?;
+        if self.address.is_ipv6() {
+            socket.set_only_v6(self.ipv6_only)?;
+        }
+        socket.set_reuse_address(true)?;
+        socket.set_nonblocking(true)?;
+        socket.bind(&self.address.into())?;
+        socket.listen(LISTEN_BACKLOG)?;
+        TcpListener::from_std(socket.into())
+    }
+}
+
+fn accepted(conn: &TcpStream, config: &GrpcConnectionConfig) -> std::io::Result<()> {
+    conn.set_nodelay(true)?;
+    if let Some(keepalive) = config.tcp_keepalive {
+        SockRef::from(conn).set_tcp_keepalive(&TcpKeepalive::new().with_time(keepalive))?;
+    }
+    Ok(())
+}
+
+/// Connections accepted on `listener`, closed once they reach their max age.
+pub(crate) fn incoming(
+    listener: &GrpcListener,
+    config: &GrpcConnectionConfig,
+) -> Result<
+    impl Stream<
+        Item = Result<
+            AgedConn<
+                impl AsyncRead
+                    + AsyncWrite
+                    + Connected<ConnectInfo = TcpConnectInfo>
+                    + Unpin
+                    + Send
+                    + 'static,
+            >,
+            std::io::Error,
+        >,
+    >,
+    Box<dyn std::error::Error>,
+> {
+    let config = config.clone();
+    let incoming = TcpListenerStream::new(listener.bind()?);
+    Ok(incoming.map(move |conn| {
+        let conn = conn?;
+        accepted(&conn, &config)?;
+        Ok(AgedConn::new(conn, config.max_connection_age))
+    }))
+}
+
+/// Resolves once the node is stopping, so the servers send a GOAWAY and drain their calls.
+pub(crate) async fn shutdown_signal(mut stopping: watch::Receiver<bool>) {
+    while !*stopping.borrow_and_update() {
+        if stopping.changed().await.is_err() {
+            // the node is gone, nothing to drain for
+            return;
+        }
+    }
+}
+
+/// Connection reading as closed by the client once it is older than its max age.
+pub(crate) struct AgedConn<IO> {
+    inner: IO,
+    expiry: Option<Pin<Box<Sleep>>>,
+}
+
+impl<IO> AgedConn<IO> {
+    fn new(inner: IO, max_age: Option<Duration>) -> Self {
+        Self {
+            inner,
+            expiry: max_age.map(|age| Box::pin(sleep(age))),
+        }
+    }
+}
+
+impl<IO: Connected> Connected for AgedConn<IO> {
+    type ConnectInfo = IO::ConnectInfo;
+
+    fn connect_info(&self) -> Self::ConnectInfo {
+        self.inner.connect_info()
+    }
+}
+
+impl<IO: AsyncRead + Unpin> AsyncRead for AgedConn<IO> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if let Some(expiry) = self.expiry.as_mut() {
+            if expiry.as_mut().poll(cx).is_ready() {
+                return Poll::Ready(Ok(()));
+            }
+        }
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<IO: AsyncWrite + Unpin> AsyncWrite for AgedConn<IO> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn max_age_test() {
+        let (client, server) = tokio::io::duplex(64);
+        let mut client = client;
+        let mut conn = AgedConn::new(server, Some(Duration::from_millis(50)));
+        let mut buf = [0u8; 4];
+
+        client.write_all(b"ping").await.unwrap();
+        conn.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+
+        sleep(Duration::from_millis(60)).await;
+        client.write_all(b"late").await.unwrap();
+        assert_eq!(conn.read(&mut buf).await.unwrap(), 0);
+    }
+
+    #[test]
+    fn split_dual_stack_test() {
+        let mut listeners: Vec<GrpcListener> = ["0.0.0.0:34000", "[::]:34000", "[::]:34001"]
+            .iter()
+            .map(|x| GrpcListener::new(x.parse().unwrap()))
+            .collect();
+        GrpcListener::split_dual_stack(&mut listeners);
+        assert_eq!(
+            listeners.iter().map(|x| x.ipv6_only).collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+    }
+}
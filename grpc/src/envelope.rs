@@ -0,0 +1,175 @@
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::{G1Affine, G2Affine};
+use ark_serialize::CanonicalDeserialize;
+use chain_state::retrieval_envelope::{verify_envelope, EnvelopeMessage};
+use ethers::{types::H160, utils::keccak256};
+
+use crate::signer::{RetrievalEnvelope, StoredSlice};
+
+/// Digest of the slices of a `RetrieveStoredSlices` response, signed in its envelope.
+pub fn stored_slices_digest(slices: &[StoredSlice]) -> [u8; 32] {
+    let mut bytes = vec![];
+    for slice in slices {
+        bytes.extend_from_slice(&slice.row_index.to_be_bytes());
+        bytes.extend_from_slice(&(slice.light_slice.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(&slice.light_slice);
+        bytes.extend_from_slice(&(slice.data.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(&slice.data);
+        // left out when empty, so slices without it keep their digest
+        if !slice.encoded_slice.is_empty() {
+            bytes.extend_from_slice(&(slice.encoded_slice.len() as u64).to_be_bytes());
+            bytes.extend_from_slice(&slice.encoded_slice);
+        }
+    }
+    keccak256(bytes)
+}
+
+/// Digest of the encoded slices returned for a request of `BatchRetrieve` or `RepairSlices`,
+/// signed in its envelope.
+pub fn encoded_slices_digest(slices: &[Vec<u8>]) -> [u8; 32] {
+    let mut bytes = vec![];
+    for slice in slices {
+        bytes.extend_from_slice(&(slice.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(slice);
+    }
+    keccak256(bytes)
+}
+
+/// Row a custody challenge picks among the rows assigned to the node, so the node cannot choose
+/// which row it proves.
+pub fn custody_row(
+    challenge: &[u8; 32],
+    storage_root: [u8; 32],
+    assigned_slices: &[u64],
+) -> Option<u64> {
+    if assigned_slices.is_empty() {
+        return None;
+    }
+    let seed = keccak256([challenge.as_slice(), storage_root.as_slice()].concat());
+    let seed = u64::from_be_bytes(seed[24..].try_into().unwrap());
+    Some(assigned_slices[(seed % assigned_slices.len() as u64) as usize])
+}
+
+/// Digest of the slice answering a custody challenge, signed in the envelope of the proof. The
+/// challenge makes it fresh, so a proof cannot be computed ahead and the data dropped.
+pub fn custody_digest(challenge: &[u8; 32], slice: &StoredSlice) -> [u8; 32] {
+    keccak256(
+        [
+            challenge.as_slice(),
+            &stored_slices_digest(std::slice::from_ref(slice)),
+        ]
+        .concat(),
+    )
+}
+
+/// Check that `envelope` signs the slices of `content_digest` of the requested blob with the key
+/// registered on chain by the signer of the envelope, returns the signer.
+pub fn verify_retrieval_envelope(
+    envelope: &RetrievalEnvelope,
+    epoch: u64,
+    quorum_id: u64,
+    storage_root: [u8; 32],
+    content_digest: [u8; 32],
+    pub_key_g2: G2Affine,
+    chain_id: u64,
+) -> Result<H160> {
+    if envelope.content_digest != content_digest {
+        bail!(anyhow!("content digest mismatch"));
+    }
+    if envelope.signer.len() != 20 {
+        bail!(anyhow!("invalid signer"));
+    }
+    let signer = H160::from_slice(&envelope.signer);
+    let signature = G1Affine::deserialize_uncompressed(&*envelope.signature)?;
+    let message = EnvelopeMessage {
+        epoch,
+        quorum_id,
+        storage_root,
+        content_digest,
+        timestamp: envelope.timestamp,
+    };
+    if !verify_envelope(pub_key_g2, signature, &message, signer, chain_id) {
+        bail!(anyhow!("invalid envelope signature"));
+    }
+    Ok(signer)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{g2, Fr};
+    use ark_ec::{AffineRepr, CurveGroup};
+    use ark_serialize::CanonicalSerialize;
+    use chain_state::retrieval_envelope::sign_envelope;
+
+    use super::*;
+
+    #[test]
+    fn custody_row_test() {
+        let assigned = [3, 17, 40, 41];
+        let rows: Vec<_> = (0..32u8)
+            .map(|x| custody_row(&[x; 32], [5; 32], &assigned).unwrap())
+            .collect();
+        assert!(rows.iter().all(|row| assigned.contains(row)));
+        assert!(assigned.iter().all(|row| rows.contains(row)));
+        assert_eq!(custody_row(&[1; 32], [5; 32], &assigned), Some(rows[1]));
+        assert_eq!(custody_row(&[1; 32], [5; 32], &[]), None);
+
+        let slice = StoredSlice {
+            row_index: 3,
+            light_slice: vec![1; 8],
+            data: vec![2; 8],
+            encoded_slice: vec![],
+        };
+        assert_ne!(
+            custody_digest(&[1; 32], &slice),
+            custody_digest(&[2; 32], &slice)
+        );
+    }
+
+    #[test]
+    fn retrieval_envelope_test() {
+        let key = Fr::from(11);
+        let pub_key = (g2::G2Affine::generator() * key).into_affine();
+        let signer = H160::from_low_u64_be(9);
+        let slices = vec![vec![1u8; 64], vec![2u8; 64]];
+        let content_digest = encoded_slices_digest(&slices);
+        let message = EnvelopeMessage {
+            epoch: 2,
+            quorum_id: 0,
+            storage_root: [5; 32],
+            content_digest,
+            timestamp: 1_700_000_000,
+        };
+        let mut signature = vec![];
+        sign_envelope(key, &message, signer, 16600)
+            .serialize_uncompressed(&mut signature)
+            .unwrap();
+        let envelope = RetrievalEnvelope {
+            signer: signer.as_bytes().to_vec(),
+            content_digest: content_digest.to_vec(),
+            timestamp: message.timestamp,
+            signature,
+        };
+        assert_eq!(
+            verify_retrieval_envelope(&envelope, 2, 0, [5; 32], content_digest, pub_key, 16600)
+                .unwrap(),
+            signer
+        );
+
+        // tampered content, or the envelope replayed for another blob
+        let tampered = encoded_slices_digest(&[vec![1u8; 64], vec![3u8; 64]]);
+        assert!(
+            verify_retrieval_envelope(&envelope, 2, 0, [5; 32], tampered, pub_key, 16600).is_err()
+        );
+        assert!(verify_retrieval_envelope(
+            &envelope,
+            2,
+            1,
+            [5; 32],
+            content_digest,
+            pub_key,
+            16600
+        )
+        .is_err());
+    }
+}
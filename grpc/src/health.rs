@@ -0,0 +1,118 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+
+use storage::error::StorageErrorKind;
+
+/// Number of distinct blobs failing verification in a row, without any success in between, to
+/// suspect the encoder params are mismatched rather than the data being bad.
+const PARAMS_MISMATCH_THRESHOLD: u64 = 5;
+
+#[derive(Default)]
+struct DetectorState {
+    consecutive_failed_blobs: u64,
+    last_failed_root: Option<[u8; 32]>,
+    suspected: bool,
+}
+
+/// Tells systematic verification failures, most likely caused by encoder params or encoder version
+/// mismatched with the clients, from occasional bad data.
+#[derive(Default)]
+pub struct ParamsMismatchDetector {
+    state: Mutex<DetectorState>,
+}
+
+pub enum DetectorUpdate {
+    /// A params mismatch is suspected for the first time since the last successful verification.
+    Suspected(u64),
+    Unchanged,
+}
+
+impl ParamsMismatchDetector {
+    pub fn on_verified(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.suspected {
+            info!("slice verification succeeded again, encoder params mismatch cleared");
+        }
+        *state = DetectorState::default();
+    }
+
+    pub fn on_verify_failed(&self, storage_root: [u8; 32]) -> DetectorUpdate {
+        let mut state = self.state.lock().unwrap();
+        // retries of the same blob count once
+        if state.last_failed_root == Some(storage_root) {
+            return DetectorUpdate::Unchanged;
+        }
+        state.last_failed_root = Some(storage_root);
+        state.consecutive_failed_blobs += 1;
+        if !state.suspected && state.consecutive_failed_blobs >= PARAMS_MISMATCH_THRESHOLD {
+            state.suspected = true;
+            return DetectorUpdate::Suspected(state.consecutive_failed_blobs);
+        }
+        DetectorUpdate::Unchanged
+    }
+
+    pub fn suspected(&self) -> bool {
+        self.state.lock().unwrap().suspected
+    }
+
+    pub fn consecutive_failed_blobs(&self) -> u64 {
+        self.state.lock().unwrap().consecutive_failed_blobs
+    }
+}
+
+/// Storage failures of the sign path by kind, transient ones are counted on every failed attempt.
+#[derive(Default)]
+pub struct StorageErrorCounters {
+    transient: AtomicU64,
+    permanent: AtomicU64,
+}
+
+impl StorageErrorCounters {
+    pub fn on_error(&self, kind: StorageErrorKind) {
+        match kind {
+            StorageErrorKind::Transient => &self.transient,
+            StorageErrorKind::Permanent => &self.permanent,
+        }
+        .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn transient(&self) -> u64 {
+        self.transient.load(Ordering::Relaxed)
+    }
+
+    pub fn permanent(&self) -> u64 {
+        self.permanent.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_mismatch_detector_test() {
+        let detector = ParamsMismatchDetector::default();
+        for i in 0..PARAMS_MISMATCH_THRESHOLD - 1 {
+            assert!(matches!(
+                detector.on_verify_failed([i as u8; 32]),
+                DetectorUpdate::Unchanged
+            ));
+            // retry of the same blob
+            detector.on_verify_failed([i as u8; 32]);
+        }
+        assert!(!detector.suspected());
+        assert!(matches!(
+            detector.on_verify_failed([0xff; 32]),
+            DetectorUpdate::Suspected(PARAMS_MISMATCH_THRESHOLD)
+        ));
+        assert!(matches!(
+            detector.on_verify_failed([0xfe; 32]),
+            DetectorUpdate::Unchanged
+        ));
+        assert!(detector.suspected());
+        detector.on_verified();
+        assert!(!detector.suspected());
+    }
+}
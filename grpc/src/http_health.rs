@@ -0,0 +1,230 @@
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serde_json::json;
+
+use crate::service::signer::HealthCondition;
+use crate::SignerService;
+
+/// Conditions under which a node should not be sent sign requests, the others are reported only.
+const NOT_READY_CONDITIONS: [HealthCondition; 5] = [
+    HealthCondition::EncoderParamsMismatch,
+    HealthCondition::StorageOnly,
+    HealthCondition::Syncing,
+    HealthCondition::StorageErrors,
+    HealthCondition::Maintenance,
+];
+
+#[derive(Default)]
+struct ProbeState {
+    /// Signers of every identity and network, set once they are all started with their params.
+    signers: RwLock<Option<Vec<Arc<SignerService>>>>,
+    stopping: AtomicBool,
+}
+
+/// Liveness and readiness of the node over plain http, for orchestrators without grpc health
+/// probes. The probes answer as soon as the node starts, before the params are loaded.
+#[derive(Clone, Default)]
+pub struct HealthProbes(Arc<ProbeState>);
+
+struct Probe {
+    healthy: bool,
+    status: &'static str,
+    conditions: Vec<HealthCondition>,
+    error: Option<String>,
+}
+
+impl HealthProbes {
+    /// Mark the node started, its signers are probed from now on.
+    pub fn set_signers(&self, signers: Vec<Arc<SignerService>>) {
+        *self.0.signers.write().unwrap() = Some(signers);
+    }
+
+    /// Mark the node stopping, it is not ready any more.
+    pub fn set_stopping(&self) {
+        self.0.stopping.store(true, Ordering::Relaxed);
+    }
+
+    fn signers(&self) -> Option<Vec<Arc<SignerService>>> {
+        self.0.signers.read().unwrap().clone()
+    }
+
+    /// Live unless the database of a signer cannot be read or has failed to store slices.
+    async fn liveness(&self) -> Probe {
+        let signers = match self.signers() {
+            Some(signers) => signers,
+            None => return Probe::healthy("starting", vec![]),
+        };
+        let mut conditions = vec![];
+        for signer in &signers {
+            if let Err(e) = signer.check_db().await {
+                return Probe::failed(format!("database unreadable: {:?}", e));
+            }
+            conditions.extend(
+                signer
+                    .health_conditions()
+                    .into_iter()
+                    .filter(|x| *x == HealthCondition::StorageErrors),
+            );
+        }
+        Probe::new(conditions)
+    }
+
+    /// Ready once started with the params loaded, while every signer is synced with the chain and
+    /// accepts sign requests.
+    async fn readiness(&self) -> Probe {
+        if self.0.stopping.load(Ordering::Relaxed) {
+            return Probe::unhealthy("stopping", vec![]);
+        }
+        let signers = match self.signers() {
+            Some(signers) => signers,
+            None => return Probe::unhealthy("starting", vec![]),
+        };
+        let mut conditions = vec![];
+        for signer in &signers {
+            if let Err(e) = signer.check_db().await {
+                return Probe::failed(format!("database unreadable: {:?}", e));
+            }
+            for condition in signer.health_conditions() {
+                if !conditions.contains(&condition) {
+                    conditions.push(condition);
+                }
+            }
+        }
+        Probe::new(conditions)
+    }
+}
+
+impl Probe {
+    /// Healthy unless one of `conditions` keeps the node from signing.
+    fn new(conditions: Vec<HealthCondition>) -> Self {
+        if conditions.iter().any(|x| NOT_READY_CONDITIONS.contains(x)) {
+            Self::unhealthy("unavailable", conditions)
+        } else {
+            Self::healthy("ok", conditions)
+        }
+    }
+
+    fn healthy(status: &'static str, conditions: Vec<HealthCondition>) -> Self {
+        Self {
+            healthy: true,
+            status,
+            conditions,
+            error: None,
+        }
+    }
+
+    fn unhealthy(status: &'static str, conditions: Vec<HealthCondition>) -> Self {
+        Self {
+            healthy: false,
+            status,
+            conditions,
+            error: None,
+        }
+    }
+
+    fn failed(error: String) -> Self {
+        Self {
+            error: Some(error),
+            ..Self::unhealthy("unavailable", vec![])
+        }
+    }
+
+    fn into_response(self) -> Response<Body> {
+        let body = json!({
+            "status": self.status,
+            "conditions": self
+                .conditions
+                .iter()
+                .map(|x| x.as_str_name())
+                .collect::<Vec<_>>(),
+            "error": self.error,
+        });
+        Response::builder()
+            .status(if self.healthy {
+                StatusCode::OK
+            } else {
+                StatusCode::SERVICE_UNAVAILABLE
+            })
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+}
+
+async fn serve_probe(
+    probes: HealthProbes,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let probe = match (request.method(), request.uri().path()) {
+        (&Method::GET | &Method::HEAD, "/healthz") => probes.liveness().await,
+        (&Method::GET | &Method::HEAD, "/readyz") => probes.readiness().await,
+        _ => {
+            return Ok(Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::empty())
+                .unwrap())
+        }
+    };
+    Ok(probe.into_response())
+}
+
+/// Serve `/healthz` and `/readyz`, answering 200 when healthy and 503 otherwise with the
+/// conditions of the node as json.
+pub async fn run_health_server(
+    addr: SocketAddr,
+    probes: HealthProbes,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("http health server listening {:?}", addr);
+    Server::try_bind(&addr)?
+        .serve(make_service_fn(move |_| {
+            let probes = probes.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    serve_probe(probes.clone(), request)
+                }))
+            }
+        }))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn probe_test() {
+        let probes = HealthProbes::default();
+        let liveness = probes.liveness().await;
+        assert!(liveness.healthy);
+        assert_eq!(liveness.status, "starting");
+        assert!(!probes.readiness().await.healthy);
+
+        probes.set_signers(vec![]);
+        assert!(probes.readiness().await.healthy);
+        probes.set_stopping();
+        let readiness = probes.readiness().await;
+        assert!(!readiness.healthy);
+        assert_eq!(readiness.status, "stopping");
+        assert!(probes.liveness().await.healthy);
+
+        assert!(Probe::new(vec![HealthCondition::Overloaded]).healthy);
+        let probe = Probe::new(vec![HealthCondition::Archive, HealthCondition::Syncing]);
+        assert!(!probe.healthy);
+        assert_eq!(
+            probe.into_response().status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+}
@@ -1,43 +1,231 @@
 #[macro_use]
 extern crate tracing;
 
+mod admin_service;
+mod admission;
+mod aggregation;
+pub mod audit_log;
+mod batch_proxy;
+pub mod bench;
+mod blob_events;
+mod build_info;
+mod cluster;
+mod connection;
+mod envelope;
+mod health;
+mod http_health;
+mod load_shedding;
+mod maintenance;
+mod network;
+mod params;
+mod protocol;
+pub mod replay;
+mod request_id;
+mod runtime_monitor;
+mod self_test;
 mod service;
+mod sign_options;
+mod sign_quota;
+mod slice_verifier;
+mod slice_writer;
+mod status_page;
+mod trace_context;
+mod verification_metrics;
+mod verification_queue;
 
-use crate::service::signer::signer_server::SignerServer;
-use ark_bn254::Fr;
-use chain_state::ChainState;
+use crate::service::signer::{
+    retrieval_server::RetrievalServer, signer_server::SignerServer,
+    v2::signer_server::SignerServer as SignerV2Server,
+};
+pub use admin_service::{admin, run_admin_server, AdminService};
+pub use admission::AdmissionConfig;
+pub use audit_log::AuditLog;
+pub use batch_proxy::{BatchProxy, BatchProxyConfig};
+pub use build_info::{build_info, BuildInfo, PARAMS_COMPAT_VERSION};
+pub use cluster::{ClusterConfig, ClusterMember, ClusterRole};
+pub use connection::{GrpcConnectionConfig, GrpcListener, GrpcTlsConfig};
+pub use envelope::{
+    custody_digest, custody_row, encoded_slices_digest, stored_slices_digest,
+    verify_retrieval_envelope,
+};
+use events::EventBus;
+pub use http_health::{run_health_server, HealthProbes};
+pub use load_shedding::{LoadShedder, LoadSheddingConfig, ResourceMonitor, ResourceUsage};
+pub use maintenance::{Maintenance, MaintenanceState, RETRY_AFTER_METADATA_KEY};
+use network::RetrievalService;
+pub use network::{NetworkRouter, NETWORK_METADATA_KEY};
+pub use params::{ParamsLoadMode, ParamsVersion};
+use protocol::SignerV2Service;
+pub use protocol::PROTOCOL_VERSIONS;
+pub use request_id::REQUEST_ID_METADATA_KEY;
+#[cfg(feature = "tokio-console")]
+pub use runtime_monitor::tokio_console_layer;
+pub use runtime_monitor::{RuntimeMonitor, RuntimeStats, DEFAULT_TOKIO_CONSOLE_ADDRESS};
 pub use service::signer;
-use service::SignerService;
-use std::{net::SocketAddr, sync::Arc};
-use storage::Storage;
-use tokio::sync::RwLock;
+pub use service::SignerService;
+pub use sign_quota::{SignClient, SignQuotaConfig, AUTHORIZATION_METADATA_KEY};
+pub use slice_verifier::SliceVerifier;
+pub use slice_writer::AckMode;
+pub use status_page::run_status_page_server;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::sync::{watch, RwLock};
 use tonic::transport::Server;
+pub use verification_metrics::VerificationMetrics;
+pub use verification_queue::VerificationQueue;
 
-const MESSAGE_SIZE_LIMIT: usize = 1024 * 1024 * 1024; // 1G
+pub(crate) const MESSAGE_SIZE_LIMIT: usize = 1024 * 1024 * 1024; // 1G
 
+pub struct SignerConfig {
+    pub encoder_params_dir: String,
+    pub params_load_mode: ParamsLoadMode,
+    /// Params of later encoder versions, `encoder_params_dir` serves the epochs before them.
+    pub params_versions: Vec<ParamsVersion>,
+    /// Holds the verify params of the schedule above, shared with the tasks recovering slices.
+    pub slice_verifier: SliceVerifier,
+    pub max_ongoing_sign_request: Option<u64>,
+    /// Load the full encoder params to serve `RepairSlices`.
+    pub enable_slice_repair: bool,
+    /// Folder to dump sign requests failing verification.
+    pub request_dump_dir: Option<String>,
+    /// Bus receiving a `BlobSigned` or `BlobRejected` event for every handled sign request.
+    pub events: EventBus,
+    /// Number of ongoing sign requests, shared with the DAS scheduler.
+    pub sign_load: Arc<RwLock<u64>>,
+    pub put_slice_retry: PutSliceRetryConfig,
+    /// Requests accepted in a sign batch, advertised to clients.
+    pub max_batch_sign_requests: Option<u64>,
+    /// Split sign batches over backend instances instead of signing them whole locally.
+    pub batch_proxy: Option<Arc<BatchProxy>>,
+    /// Verification timings, shared with the admin service.
+    pub verification_metrics: VerificationMetrics,
+    /// Per epoch quotas of the authenticated sign clients.
+    pub sign_quota: Option<SignQuotaConfig>,
+    /// Keep the signed slices whole, to serve their opening proofs to retrieval clients.
+    pub store_opening_proofs: bool,
+    pub ack_mode: AckMode,
+    /// Budgets of the slices and bytes of the sign batches handled at once.
+    pub admission: AdmissionConfig,
+    /// Log every sign request and its result is appended to, shared by the signers of the node.
+    pub audit_log: Option<Arc<AuditLog>>,
+    /// Archive replica: retrieval and status are served, every call that signs is rejected.
+    pub archive: bool,
+    /// Maintenance flag of the node, toggled by the admin service.
+    pub maintenance: Maintenance,
+    /// Sheds new sign batches while the host is saturated.
+    pub load_shedder: Option<LoadShedder>,
+    /// Orders the sign requests waiting to verify, they verify as they come if `None`.
+    pub verification_queue: Option<VerificationQueue>,
+}
+
+/// Limits of the signer and retrieval grpc servers.
+#[derive(Debug, Clone)]
+pub struct GrpcLimits {
+    pub max_decoding_message_size: usize,
+    pub max_encoding_message_size: usize,
+    /// Streams of a connection handled at once, the http2 default if `None`.
+    pub max_concurrent_streams: Option<u32>,
+    /// Calls running longer fail with `CANCELLED`, streamed retrievals included.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for GrpcLimits {
+    fn default() -> Self {
+        Self {
+            max_decoding_message_size: MESSAGE_SIZE_LIMIT,
+            max_encoding_message_size: MESSAGE_SIZE_LIMIT,
+            max_concurrent_streams: None,
+            timeout: None,
+        }
+    }
+}
+
+impl GrpcLimits {
+    fn server(&self, connection: &GrpcConnectionConfig) -> Server {
+        let builder = Server::builder()
+            .max_concurrent_streams(self.max_concurrent_streams)
+            .http2_keepalive_interval(connection.http2_keepalive_interval)
+            .http2_keepalive_timeout(connection.http2_keepalive_timeout);
+        match self.timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        }
+    }
+}
+
+/// Retries of slice writes failing on transient storage errors, before failing the request.
+#[derive(Clone)]
+pub struct PutSliceRetryConfig {
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled on every retry.
+    pub backoff: Duration,
+}
+
+impl Default for PutSliceRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Serve the `Signer` service in versions 1 and 2, along with the `Retrieval` one on the same
+/// listener, until `stopping` turns true.
 pub async fn run_server(
-    db: Arc<RwLock<Storage>>,
-    chain_state: Arc<ChainState>,
-    signer_bls_private_key: Fr,
-    addr: SocketAddr,
-    encoder_params_dir: String,
-    max_ongoing_sign_request: Option<u64>,
+    router: NetworkRouter,
+    listener: &GrpcListener,
+    limits: &GrpcLimits,
+    connection: &GrpcConnectionConfig,
+    stopping: watch::Receiver<bool>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let signer_service = SignerService::new(
-        db,
-        chain_state,
-        signer_bls_private_key,
-        encoder_params_dir,
-        max_ongoing_sign_request,
+    info!(
+        tls = listener.tls.is_some(),
+        "grpc server listening {:?}", listener.address
     );
-    info!("grpc server listening {:?}", addr);
-    Server::builder()
+    let mut server = limits.server(connection);
+    if let Some(tls) = &listener.tls {
+        server = server.tls_config(tls.load()?)?;
+    }
+    let incoming = connection::incoming(listener, connection)?;
+    server
+        .add_service(
+            SignerServer::new(router.clone())
+                .max_decoding_message_size(limits.max_decoding_message_size)
+                .max_encoding_message_size(limits.max_encoding_message_size),
+        )
+        .add_service(
+            SignerV2Server::new(SignerV2Service(router.clone()))
+                .max_decoding_message_size(limits.max_decoding_message_size)
+                .max_encoding_message_size(limits.max_encoding_message_size),
+        )
+        .add_service(
+            RetrievalServer::new(RetrievalService(router))
+                .max_decoding_message_size(limits.max_decoding_message_size)
+                .max_encoding_message_size(limits.max_encoding_message_size),
+        )
+        .serve_with_incoming_shutdown(incoming, connection::shutdown_signal(stopping))
+        .await?;
+    Ok(())
+}
+
+/// Serve only the `Retrieval` service, so heavy retrieval can be isolated from signing.
+pub async fn run_retrieval_server(
+    router: NetworkRouter,
+    addr: SocketAddr,
+    limits: &GrpcLimits,
+    connection: &GrpcConnectionConfig,
+    stopping: watch::Receiver<bool>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("retrieval grpc server listening {:?}", addr);
+    let incoming = connection::incoming(&GrpcListener::new(addr), connection)?;
+    limits
+        .server(connection)
         .add_service(
-            SignerServer::new(signer_service)
-                .max_decoding_message_size(MESSAGE_SIZE_LIMIT)
-                .max_encoding_message_size(MESSAGE_SIZE_LIMIT),
+            RetrievalServer::new(RetrievalService(router))
+                .max_decoding_message_size(limits.max_decoding_message_size)
+                .max_encoding_message_size(limits.max_encoding_message_size),
         )
-        .serve(addr)
+        .serve_with_incoming_shutdown(incoming, connection::shutdown_signal(stopping))
         .await?;
     Ok(())
 }
@@ -0,0 +1,302 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::time::{sleep, Instant};
+use tonic::{
+    metadata::{MetadataMap, MetadataValue},
+    Code, Status,
+};
+
+use crate::maintenance::RETRY_AFTER_METADATA_KEY;
+
+/// Host saturation thresholds above which new sign batches are shed, a check is off if `None`.
+#[derive(Debug, Clone)]
+pub struct LoadSheddingConfig {
+    pub sample_interval: Duration,
+    /// Share of the CPU time spent busy, in percent.
+    pub max_cpu_percent: Option<u64>,
+    /// Share of the memory not available, in percent.
+    pub max_memory_percent: Option<u64>,
+    /// Mean completion time of the disk requests.
+    pub max_io_latency: Option<Duration>,
+    /// Disks of `/proc/diskstats` to measure, all of them but loop and ram devices if empty.
+    pub disks: Vec<String>,
+    /// Time a new batch waits for the host to recover before it is rejected, rejected at once if
+    /// zero.
+    pub max_delay: Duration,
+}
+
+/// Resource usage of the host over the latest sample interval.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResourceUsage {
+    pub cpu_percent: f64,
+    pub memory_percent: f64,
+    pub io_latency: Duration,
+}
+
+/// Cumulative counters of `/proc`, usage is their difference between two samples.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Counters {
+    cpu_busy: u64,
+    cpu_total: u64,
+    io_ms: u64,
+    io_count: u64,
+}
+
+/// Busy and total jiffies of the `cpu` line of `/proc/stat`, idle and iowait are not busy.
+fn parse_cpu(stat: &str) -> Option<(u64, u64)> {
+    let fields: Vec<u64> = stat
+        .lines()
+        .find(|line| line.starts_with("cpu "))?
+        .split_whitespace()
+        .skip(1)
+        .map(|x| x.parse().ok())
+        .collect::<Option<_>>()?;
+    let total: u64 = fields.iter().sum();
+    let idle = fields.get(3)? + fields.get(4).unwrap_or(&0);
+    Some((total - idle, total))
+}
+
+/// Used memory of `/proc/meminfo` in percent.
+fn parse_memory(meminfo: &str) -> Option<f64> {
+    let field = |name: &str| -> Option<f64> {
+        meminfo
+            .lines()
+            .find_map(|line| line.strip_prefix(name))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    };
+    let total = field("MemTotal:")?;
+    let available = field("MemAvailable:")?;
+    if total == 0.0 {
+        return None;
+    }
+    Some((1.0 - available / total) * 100.0)
+}
+
+/// Milliseconds spent on and number of the completed reads and writes of `disks`.
+fn parse_diskstats(diskstats: &str, disks: &[String]) -> (u64, u64) {
+    let mut io_ms = 0;
+    let mut io_count = 0;
+    for line in diskstats.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() < 11 {
+            continue;
+        }
+        let name = fields[2];
+        let measured = if disks.is_empty() {
+            !name.starts_with("loop") && !name.starts_with("ram")
+        } else {
+            disks.iter().any(|disk| disk == name)
+        };
+        if !measured {
+            continue;
+        }
+        let field = |i: usize| fields[i].parse::<u64>().unwrap_or(0);
+        io_count += field(3) + field(7);
+        io_ms += field(6) + field(10);
+    }
+    (io_ms, io_count)
+}
+
+fn usage_between(before: &Counters, after: &Counters, memory_percent: f64) -> ResourceUsage {
+    let cpu_total = after.cpu_total.saturating_sub(before.cpu_total);
+    let io_count = after.io_count.saturating_sub(before.io_count);
+    ResourceUsage {
+        cpu_percent: match cpu_total {
+            0 => 0.0,
+            total => after.cpu_busy.saturating_sub(before.cpu_busy) as f64 * 100.0 / total as f64,
+        },
+        memory_percent,
+        io_latency: match io_count {
+            0 => Duration::ZERO,
+            count => Duration::from_micros(after.io_ms.saturating_sub(before.io_ms) * 1000 / count),
+        },
+    }
+}
+
+/// Samples the CPU, memory and disk usage of the host from `/proc`, the usage is unknown on
+/// other platforms and nothing is shed.
+#[derive(Clone, Default)]
+pub struct ResourceMonitor {
+    usage: Arc<Mutex<Option<ResourceUsage>>>,
+}
+
+impl ResourceMonitor {
+    fn sample(disks: &[String]) -> std::io::Result<(Counters, f64)> {
+        let invalid = |file| std::io::Error::new(std::io::ErrorKind::InvalidData, file);
+        let (cpu_busy, cpu_total) =
+            parse_cpu(&std::fs::read_to_string("/proc/stat")?).ok_or(invalid("/proc/stat"))?;
+        let memory_percent = parse_memory(&std::fs::read_to_string("/proc/meminfo")?)
+            .ok_or(invalid("/proc/meminfo"))?;
+        let (io_ms, io_count) =
+            parse_diskstats(&std::fs::read_to_string("/proc/diskstats")?, disks);
+        Ok((
+            Counters {
+                cpu_busy,
+                cpu_total,
+                io_ms,
+                io_count,
+            },
+            memory_percent,
+        ))
+    }
+
+    /// Sample the host every `config.sample_interval`, the future only ends if `/proc` cannot be
+    /// read.
+    pub async fn run(self, config: LoadSheddingConfig) {
+        let mut before = match Self::sample(&config.disks) {
+            Ok((counters, _)) => counters,
+            Err(e) => {
+                warn!(
+                    "cannot sample resource usage, load shedding is disabled: {:?}",
+                    e
+                );
+                return;
+            }
+        };
+        let mut saturated = false;
+        loop {
+            sleep(config.sample_interval).await;
+            let (after, memory_percent) = match Self::sample(&config.disks) {
+                Ok(sample) => sample,
+                Err(e) => {
+                    warn!("cannot sample resource usage: {:?}", e);
+                    continue;
+                }
+            };
+            let usage = usage_between(&before, &after, memory_percent);
+            before = after;
+            *self.usage.lock().unwrap() = Some(usage);
+            match (saturation(&config, &usage), saturated) {
+                (Some(reason), false) => {
+                    warn!("host is saturated, shedding new sign batches: {}", reason);
+                    saturated = true;
+                }
+                (None, true) => {
+                    info!("host recovered, sign batches are accepted again");
+                    saturated = false;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn usage(&self) -> Option<ResourceUsage> {
+        *self.usage.lock().unwrap()
+    }
+}
+
+/// The first resource of `usage` over its threshold.
+fn saturation(config: &LoadSheddingConfig, usage: &ResourceUsage) -> Option<String> {
+    if let Some(max) = config.max_cpu_percent {
+        if usage.cpu_percent > max as f64 {
+            return Some(format!("cpu usage {:.0}% over {}%", usage.cpu_percent, max));
+        }
+    }
+    if let Some(max) = config.max_memory_percent {
+        if usage.memory_percent > max as f64 {
+            return Some(format!(
+                "memory usage {:.0}% over {}%",
+                usage.memory_percent, max
+            ));
+        }
+    }
+    if let Some(max) = config.max_io_latency {
+        if usage.io_latency > max {
+            return Some(format!(
+                "disk latency {:?} over {:?}",
+                usage.io_latency, max
+            ));
+        }
+    }
+    None
+}
+
+/// Delays, then rejects, new sign batches while the host is saturated, so that verification
+/// storms do not starve DA sampling and the chain monitors.
+#[derive(Clone)]
+pub struct LoadShedder {
+    config: LoadSheddingConfig,
+    monitor: ResourceMonitor,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig, monitor: ResourceMonitor) -> Self {
+        Self { config, monitor }
+    }
+
+    /// Reason the host is saturated for, if it is.
+    pub(crate) fn saturation(&self) -> Option<String> {
+        saturation(&self.config, &self.monitor.usage()?)
+    }
+
+    /// `Unavailable` with a retry-after hint if the host is still saturated after `max_delay`.
+    pub(crate) async fn admit(&self) -> Result<(), Status> {
+        let deadline = Instant::now() + self.config.max_delay;
+        loop {
+            let reason = match self.saturation() {
+                Some(reason) => reason,
+                None => return Ok(()),
+            };
+            let now = Instant::now();
+            if now >= deadline {
+                let mut metadata = MetadataMap::new();
+                metadata.insert(
+                    RETRY_AFTER_METADATA_KEY,
+                    MetadataValue::from(self.config.sample_interval.as_secs().max(1)),
+                );
+                return Err(Status::with_metadata(
+                    Code::Unavailable,
+                    format!("node is overloaded, {}", reason),
+                    metadata,
+                ));
+            }
+            sleep(self.config.sample_interval.min(deadline - now)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resource_usage_test() {
+        let stat = "cpu  100 0 100 700 100 0 0 0 0 0\ncpu0 100 0 100 700 100 0 0 0 0 0\n";
+        assert_eq!(parse_cpu(stat), Some((200, 1000)));
+        let meminfo = "MemTotal:       1000 kB\nMemFree:         100 kB\nMemAvailable:    250 kB\n";
+        assert_eq!(parse_memory(meminfo), Some(75.0));
+        let diskstats = "   7       0 loop0 5 0 10 50 0 0 0 0 0 50 50\n\
+                         259       0 nvme0n1 10 0 80 30 10 0 80 70 0 100 100\n";
+        assert_eq!(parse_diskstats(diskstats, &[]), (100, 20));
+        assert_eq!(parse_diskstats(diskstats, &["loop0".to_string()]), (50, 5));
+
+        let before = Counters::default();
+        let after = Counters {
+            cpu_busy: 200,
+            cpu_total: 1000,
+            io_ms: 100,
+            io_count: 20,
+        };
+        let usage = usage_between(&before, &after, 75.0);
+        assert_eq!(usage.cpu_percent, 20.0);
+        assert_eq!(usage.io_latency, Duration::from_millis(5));
+
+        let mut config = LoadSheddingConfig {
+            sample_interval: Duration::from_secs(1),
+            max_cpu_percent: Some(90),
+            max_memory_percent: Some(80),
+            max_io_latency: Some(Duration::from_millis(10)),
+            disks: vec![],
+            max_delay: Duration::ZERO,
+        };
+        assert!(saturation(&config, &usage).is_none());
+        config.max_memory_percent = Some(70);
+        assert!(saturation(&config, &usage).unwrap().starts_with("memory"));
+    }
+}
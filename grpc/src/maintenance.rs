@@ -0,0 +1,92 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tonic::{
+    metadata::{MetadataMap, MetadataValue},
+    Code, Status,
+};
+
+/// Metadata key of the seconds to wait before retrying a request rejected for maintenance.
+pub const RETRY_AFTER_METADATA_KEY: &str = "retry-after";
+/// Retry hint of a maintenance enabled without one.
+pub const DEFAULT_MAINTENANCE_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceState {
+    /// Unix timestamp in seconds maintenance was enabled at.
+    pub since: u64,
+    pub retry_after: Duration,
+    pub reason: String,
+}
+
+/// Maintenance flag of the node, shared by the admin service and the signer services. While it is
+/// on, new sign batches are rejected and the ones in flight finish.
+#[derive(Clone, Default)]
+pub struct Maintenance(Arc<RwLock<Option<MaintenanceState>>>);
+
+impl Maintenance {
+    pub fn enable(&self, retry_after: Duration, reason: String) {
+        let since = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        *self.0.write().unwrap() = Some(MaintenanceState {
+            since,
+            retry_after,
+            reason,
+        });
+    }
+
+    pub fn disable(&self) {
+        *self.0.write().unwrap() = None;
+    }
+
+    pub fn state(&self) -> Option<MaintenanceState> {
+        self.0.read().unwrap().clone()
+    }
+
+    /// `Unavailable` with a retry-after hint while maintenance is on.
+    pub fn check(&self) -> Result<(), Status> {
+        let state = match self.state() {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+        let mut metadata = MetadataMap::new();
+        metadata.insert(
+            RETRY_AFTER_METADATA_KEY,
+            MetadataValue::from(state.retry_after.as_secs()),
+        );
+        let mut message = format!(
+            "node is under maintenance, retry after {}s",
+            state.retry_after.as_secs()
+        );
+        if !state.reason.is_empty() {
+            message = format!("{}: {}", message, state.reason);
+        }
+        Err(Status::with_metadata(Code::Unavailable, message, metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maintenance_test() {
+        let maintenance = Maintenance::default();
+        assert!(maintenance.check().is_ok());
+        maintenance
+            .clone()
+            .enable(Duration::from_secs(30), "upgrade".to_string());
+        let status = maintenance.check().unwrap_err();
+        assert_eq!(status.code(), Code::Unavailable);
+        assert_eq!(
+            status.metadata().get(RETRY_AFTER_METADATA_KEY).unwrap(),
+            "30"
+        );
+        assert!(status.message().ends_with("upgrade"));
+        maintenance.disable();
+        assert!(maintenance.check().is_ok());
+    }
+}
@@ -0,0 +1,242 @@
+use std::{collections::HashMap, sync::Arc};
+
+use tonic::{Code, Request, Response, Status};
+
+use crate::blob_events::BlobEventStream;
+use crate::request_id::with_request_id;
+use crate::service::signer::{
+    retrieval_server::Retrieval, signer_server::Signer, AggregateSignaturesReply,
+    AggregateSignaturesRequest, AssignmentReply, AssignmentRequest, BatchRetrieveReply,
+    BatchRetrieveRequest, BatchSignReply, BatchSignRequest, BlobEventFilter, BlobStatusReply,
+    BlobStatusRequest, CustodyChallenge, CustodyProof, Empty, NodeInfo, ProofOfPossession,
+    RepairRequest, RetrieveRequest, SignOutcomeReply, SignOutcomeRequest, Slices, StatusReply,
+    StoredSlices, StreamSlicesRequest,
+};
+use crate::service::SliceChunkStream;
+use crate::SignerService;
+
+/// Request metadata naming the network a request is for.
+pub const NETWORK_METADATA_KEY: &str = "network-id";
+
+/// Routes the signer RPCs to the service of the network named by the `network-id` metadata of the
+/// request, requests without it go to the default network.
+#[derive(Clone)]
+pub struct NetworkRouter {
+    default: Arc<SignerService>,
+    networks: Arc<HashMap<String, Arc<SignerService>>>,
+}
+
+impl NetworkRouter {
+    pub fn new(default: Arc<SignerService>, networks: HashMap<String, Arc<SignerService>>) -> Self {
+        Self {
+            default,
+            networks: Arc::new(networks),
+        }
+    }
+
+    fn route<T>(&self, request: &Request<T>) -> Result<&SignerService, Status> {
+        let network = match request.metadata().get(NETWORK_METADATA_KEY) {
+            Some(network) => network
+                .to_str()
+                .map_err(|_| Status::new(Code::InvalidArgument, "invalid network id"))?,
+            None => return Ok(&self.default),
+        };
+        self.networks
+            .get(network)
+            .map(|service| service.as_ref())
+            .ok_or_else(|| {
+                Status::new(
+                    Code::InvalidArgument,
+                    format!("unknown network: {}", network),
+                )
+            })
+    }
+}
+
+#[tonic::async_trait]
+impl Signer for NetworkRouter {
+    async fn batch_sign(
+        &self,
+        request: Request<BatchSignRequest>,
+    ) -> Result<Response<BatchSignReply>, Status> {
+        with_request_id("batch_sign", request, |request| async move {
+            self.route(&request)?.batch_sign(request).await
+        })
+        .await
+    }
+
+    async fn batch_retrieve(
+        &self,
+        request: Request<BatchRetrieveRequest>,
+    ) -> Result<Response<BatchRetrieveReply>, Status> {
+        with_request_id("batch_retrieve", request, |request| async move {
+            self.route(&request)?.batch_retrieve_inner(request).await
+        })
+        .await
+    }
+
+    async fn get_status(&self, request: Request<Empty>) -> Result<Response<StatusReply>, Status> {
+        with_request_id("get_status", request, |request| async move {
+            self.route(&request)?.get_status(request).await
+        })
+        .await
+    }
+
+    async fn repair_slices(
+        &self,
+        request: Request<RepairRequest>,
+    ) -> Result<Response<Slices>, Status> {
+        with_request_id("repair_slices", request, |request| async move {
+            self.route(&request)?.repair_slices(request).await
+        })
+        .await
+    }
+
+    async fn get_sign_outcome(
+        &self,
+        request: Request<SignOutcomeRequest>,
+    ) -> Result<Response<SignOutcomeReply>, Status> {
+        with_request_id("get_sign_outcome", request, |request| async move {
+            self.route(&request)?.get_sign_outcome(request).await
+        })
+        .await
+    }
+
+    async fn get_blob_status(
+        &self,
+        request: Request<BlobStatusRequest>,
+    ) -> Result<Response<BlobStatusReply>, Status> {
+        with_request_id("get_blob_status", request, |request| async move {
+            self.route(&request)?.get_blob_status(request).await
+        })
+        .await
+    }
+
+    async fn get_assignment(
+        &self,
+        request: Request<AssignmentRequest>,
+    ) -> Result<Response<AssignmentReply>, Status> {
+        with_request_id("get_assignment", request, |request| async move {
+            self.route(&request)?.get_assignment(request).await
+        })
+        .await
+    }
+
+    type StreamSlicesStream = SliceChunkStream;
+
+    async fn stream_slices(
+        &self,
+        request: Request<StreamSlicesRequest>,
+    ) -> Result<Response<SliceChunkStream>, Status> {
+        with_request_id("stream_slices", request, |request| async move {
+            self.route(&request)?.stream_slices_inner(request).await
+        })
+        .await
+    }
+
+    async fn prove_custody(
+        &self,
+        request: Request<CustodyChallenge>,
+    ) -> Result<Response<CustodyProof>, Status> {
+        with_request_id("prove_custody", request, |request| async move {
+            self.route(&request)?.prove_custody(request).await
+        })
+        .await
+    }
+
+    type SubscribeBlobEventsStream = BlobEventStream;
+
+    async fn subscribe_blob_events(
+        &self,
+        request: Request<BlobEventFilter>,
+    ) -> Result<Response<BlobEventStream>, Status> {
+        with_request_id("subscribe_blob_events", request, |request| async move {
+            self.route(&request)?.subscribe_blob_events(request).await
+        })
+        .await
+    }
+
+    async fn aggregate_signatures(
+        &self,
+        request: Request<AggregateSignaturesRequest>,
+    ) -> Result<Response<AggregateSignaturesReply>, Status> {
+        with_request_id("aggregate_signatures", request, |request| async move {
+            self.route(&request)?.aggregate_signatures(request).await
+        })
+        .await
+    }
+
+    async fn get_proof_of_possession(
+        &self,
+        request: Request<Empty>,
+    ) -> Result<Response<ProofOfPossession>, Status> {
+        with_request_id("get_proof_of_possession", request, |request| async move {
+            self.route(&request)?.get_proof_of_possession(request).await
+        })
+        .await
+    }
+
+    async fn retrieve_stored_slices(
+        &self,
+        request: Request<RetrieveRequest>,
+    ) -> Result<Response<StoredSlices>, Status> {
+        with_request_id("retrieve_stored_slices", request, |request| async move {
+            self.route(&request)?
+                .retrieve_stored_slices_inner(request)
+                .await
+        })
+        .await
+    }
+
+    async fn get_node_info(&self, request: Request<Empty>) -> Result<Response<NodeInfo>, Status> {
+        with_request_id("get_node_info", request, |request| async move {
+            let mut reply = self.route(&request)?.get_node_info(request).await?;
+            let mut networks: Vec<String> = self.networks.keys().cloned().collect();
+            networks.sort();
+            reply.get_mut().networks = networks;
+            Ok(reply)
+        })
+        .await
+    }
+}
+
+/// The retrieval RPCs of the signer services.
+pub struct RetrievalService(pub NetworkRouter);
+
+#[tonic::async_trait]
+impl Retrieval for RetrievalService {
+    async fn batch_retrieve(
+        &self,
+        request: Request<BatchRetrieveRequest>,
+    ) -> Result<Response<BatchRetrieveReply>, Status> {
+        with_request_id("batch_retrieve", request, |request| async move {
+            self.0.route(&request)?.batch_retrieve_inner(request).await
+        })
+        .await
+    }
+
+    async fn retrieve_stored_slices(
+        &self,
+        request: Request<RetrieveRequest>,
+    ) -> Result<Response<StoredSlices>, Status> {
+        with_request_id("retrieve_stored_slices", request, |request| async move {
+            self.0
+                .route(&request)?
+                .retrieve_stored_slices_inner(request)
+                .await
+        })
+        .await
+    }
+
+    type StreamSlicesStream = SliceChunkStream;
+
+    async fn stream_slices(
+        &self,
+        request: Request<StreamSlicesRequest>,
+    ) -> Result<Response<SliceChunkStream>, Status> {
+        with_request_id("stream_slices", request, |request| async move {
+            self.0.route(&request)?.stream_slices_inner(request).await
+        })
+        .await
+    }
+}
@@ -0,0 +1,148 @@
+use std::time::Instant;
+
+use once_cell::sync::OnceCell;
+
+/// When the encoder params are read from `encoder_params_dir`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParamsLoadMode {
+    /// Load them all when the service is created.
+    #[default]
+    Eager,
+    /// Load each set of params on its first use, the node starts at once and only keeps the params
+    /// it serves in memory.
+    Lazy,
+}
+
+/// Encoder params used from an epoch on, until the epoch of the next version.
+#[derive(Debug, Clone)]
+pub struct ParamsVersion {
+    pub version: String,
+    pub dir: String,
+    pub from_epoch: u64,
+}
+
+/// Encoder params of a folder, loaded according to a `ParamsLoadMode`.
+pub(crate) struct EncoderParams<T> {
+    name: String,
+    dir: String,
+    load: fn(&str) -> T,
+    params: OnceCell<T>,
+}
+
+impl<T> EncoderParams<T> {
+    pub fn new(name: String, dir: String, mode: ParamsLoadMode, load: fn(&str) -> T) -> Self {
+        let params = Self {
+            name,
+            dir,
+            load,
+            params: OnceCell::new(),
+        };
+        if mode == ParamsLoadMode::Eager {
+            params.get();
+        }
+        params
+    }
+
+    /// The params, loaded first if they are not yet. Blocks while they load.
+    pub fn get(&self) -> &T {
+        self.params.get_or_init(|| {
+            info!("loading {} params from {:?}", self.name, self.dir);
+            let ts = Instant::now();
+            let params = (self.load)(&self.dir);
+            info!("{} params loaded in {:?}", self.name, ts.elapsed());
+            params
+        })
+    }
+}
+
+/// Encoder params of every version, the default folder serving the epochs before the first one.
+pub(crate) struct ParamsSchedule<T> {
+    /// Ascending by first epoch.
+    versions: Vec<(u64, EncoderParams<T>)>,
+}
+
+impl<T> ParamsSchedule<T> {
+    pub fn new(
+        kind: &str,
+        default_dir: String,
+        versions: &[ParamsVersion],
+        mode: ParamsLoadMode,
+        load: fn(&str) -> T,
+    ) -> Self {
+        let mut schedule = vec![(
+            0,
+            EncoderParams::new(kind.to_string(), default_dir, mode, load),
+        )];
+        let mut versions = versions.to_vec();
+        versions.sort_by_key(|x| x.from_epoch);
+        for version in versions {
+            schedule.push((
+                version.from_epoch,
+                EncoderParams::new(
+                    format!("{} {}", kind, version.version),
+                    version.dir,
+                    mode,
+                    load,
+                ),
+            ));
+        }
+        Self { versions: schedule }
+    }
+
+    /// Params of the blobs of `epoch`.
+    pub fn for_epoch(&self, epoch: u64) -> &EncoderParams<T> {
+        self.versions
+            .iter()
+            .rev()
+            .find(|(from_epoch, _)| *from_epoch <= epoch)
+            .map_or(&self.versions[0].1, |(_, params)| params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::*;
+
+    static LOADS: AtomicU64 = AtomicU64::new(0);
+
+    fn load(dir: &str) -> String {
+        LOADS.fetch_add(1, Ordering::SeqCst);
+        dir.to_string()
+    }
+
+    #[test]
+    fn load_mode_test() {
+        let lazy = EncoderParams::new("test".into(), "params/".into(), ParamsLoadMode::Lazy, load);
+        assert_eq!(LOADS.load(Ordering::SeqCst), 0);
+        assert_eq!(lazy.get(), "params/");
+        assert_eq!(lazy.get(), "params/");
+        assert_eq!(LOADS.load(Ordering::SeqCst), 1);
+
+        let _eager =
+            EncoderParams::new("test".into(), "params/".into(), ParamsLoadMode::Eager, load);
+        assert_eq!(LOADS.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn params_schedule_test() {
+        let version = |version: &str, from_epoch| ParamsVersion {
+            version: version.into(),
+            dir: format!("params-{}/", version),
+            from_epoch,
+        };
+        let schedule = ParamsSchedule::new(
+            "verify",
+            "params/".into(),
+            &[version("v3", 20), version("v2", 10)],
+            ParamsLoadMode::Lazy,
+            |dir| dir.to_string(),
+        );
+        assert_eq!(schedule.for_epoch(0).get(), "params/");
+        assert_eq!(schedule.for_epoch(9).get(), "params/");
+        assert_eq!(schedule.for_epoch(10).get(), "params-v2/");
+        assert_eq!(schedule.for_epoch(19).get(), "params-v2/");
+        assert_eq!(schedule.for_epoch(25).get(), "params-v3/");
+    }
+}
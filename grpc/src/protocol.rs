@@ -0,0 +1,152 @@
+use tonic::{Code, Request, Response, Status};
+
+use crate::network::NetworkRouter;
+use crate::service::signer::{
+    self,
+    signer_server::Signer as SignerV1,
+    v2::{signer_server::Signer, BatchRetrieveRequest, BatchSignRequest, HelloReply, HelloRequest},
+    AssignmentReply, AssignmentRequest, BatchRetrieveReply, BatchSignReply, BlobStatusReply,
+    BlobStatusRequest, Empty, NodeInfo, StatusReply,
+};
+
+/// Protocol versions served by the node, version 1 is the `signer` package.
+pub const PROTOCOL_VERSIONS: [u32; 2] = [1, 2];
+
+/// Sign requests may carry commitments and slices in compressed form.
+pub const COMPRESSED_ENCODING: &str = "compressed_encoding";
+/// Retrievals may ask for the opening proofs of the rows. Only the rows signed since the node keeps
+/// proofs have one, the others are not found.
+pub const OPENING_PROOFS: &str = "opening_proofs";
+/// Blobs can be re-encoded by `RepairSlices`.
+pub const SLICE_REPAIR: &str = "slice_repair";
+/// Watchers may challenge the custody of signed blobs.
+pub const CUSTODY_PROOFS: &str = "custody_proofs";
+/// Sign requests are limited by per client quotas, clients send their token.
+pub const SIGN_QUOTA: &str = "sign_quota";
+
+/// Version of the session, the highest version spoken by the client and the node.
+fn negotiate(client_version: u32) -> Result<u32, Status> {
+    PROTOCOL_VERSIONS
+        .into_iter()
+        .filter(|version| *version <= client_version)
+        .max()
+        .ok_or_else(|| {
+            Status::new(
+                Code::FailedPrecondition,
+                format!(
+                    "unsupported protocol version {}, the node serves {:?}",
+                    client_version, PROTOCOL_VERSIONS
+                ),
+            )
+        })
+}
+
+/// Calls of version 2 are rejected unless they carry the version, so a client that skipped `Hello`
+/// finds out before its requests are handled with other semantics.
+fn check_version(version: u32) -> Result<(), Status> {
+    if version != 2 {
+        return Err(Status::new(
+            Code::FailedPrecondition,
+            format!(
+                "protocol version {} is not served by signer.v2, negotiate it with Hello",
+                version
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Move the message of a version 2 call into its version 1 form, keeping its metadata.
+fn into_v1<T, U>(request: Request<T>, convert: impl FnOnce(T) -> U) -> Request<U> {
+    let (metadata, extensions, message) = request.into_parts();
+    Request::from_parts(metadata, extensions, convert(message))
+}
+
+/// The `signer.v2` RPCs, handled by the version 1 ones of the router which tag them with their
+/// request id.
+pub struct SignerV2Service(pub NetworkRouter);
+
+#[tonic::async_trait]
+impl Signer for SignerV2Service {
+    async fn hello(&self, request: Request<HelloRequest>) -> Result<Response<HelloReply>, Status> {
+        let version = negotiate(request.get_ref().protocol_version)?;
+        let requested = request.get_ref().capabilities.clone();
+        let node = self
+            .0
+            .get_node_info(into_v1(request, |_| Empty {}))
+            .await?
+            .into_inner();
+        let capabilities = node
+            .capabilities
+            .iter()
+            .filter(|x| requested.is_empty() || requested.contains(x))
+            .cloned()
+            .collect();
+        Ok(Response::new(HelloReply {
+            protocol_version: version,
+            supported_versions: PROTOCOL_VERSIONS.to_vec(),
+            capabilities,
+            node: Some(node),
+        }))
+    }
+
+    async fn get_node_info(&self, request: Request<Empty>) -> Result<Response<NodeInfo>, Status> {
+        self.0.get_node_info(request).await
+    }
+
+    async fn batch_sign(
+        &self,
+        request: Request<BatchSignRequest>,
+    ) -> Result<Response<BatchSignReply>, Status> {
+        check_version(request.get_ref().protocol_version)?;
+        let request = into_v1(request, |x| signer::BatchSignRequest {
+            requests: x.requests,
+            partial_success: x.partial_success,
+        });
+        self.0.batch_sign(request).await
+    }
+
+    async fn batch_retrieve(
+        &self,
+        request: Request<BatchRetrieveRequest>,
+    ) -> Result<Response<BatchRetrieveReply>, Status> {
+        check_version(request.get_ref().protocol_version)?;
+        let request = into_v1(request, |x| signer::BatchRetrieveRequest {
+            requests: x.requests,
+        });
+        self.0.batch_retrieve(request).await
+    }
+
+    async fn get_status(&self, request: Request<Empty>) -> Result<Response<StatusReply>, Status> {
+        self.0.get_status(request).await
+    }
+
+    async fn get_blob_status(
+        &self,
+        request: Request<BlobStatusRequest>,
+    ) -> Result<Response<BlobStatusReply>, Status> {
+        self.0.get_blob_status(request).await
+    }
+
+    async fn get_assignment(
+        &self,
+        request: Request<AssignmentRequest>,
+    ) -> Result<Response<AssignmentReply>, Status> {
+        self.0.get_assignment(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn negotiate_test() {
+        assert_eq!(negotiate(1).unwrap(), 1);
+        assert_eq!(negotiate(2).unwrap(), 2);
+        assert_eq!(negotiate(7).unwrap(), 2);
+        assert_eq!(negotiate(0).unwrap_err().code(), Code::FailedPrecondition);
+        assert!(check_version(2).is_ok());
+        assert!(check_version(0).is_err());
+    }
+}
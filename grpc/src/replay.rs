@@ -0,0 +1,135 @@
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Result};
+use ark_serialize::CanonicalSerialize;
+use prost::Message;
+use zg_encoder::{DeferredVerifier, ZgSignerParams};
+
+use crate::service::signer::{RecordedSignRequest, SignRequest};
+use crate::service::{blob_verified_hash, SignerService};
+
+/// Dump a sign request for `replay-request`, `error` is empty if it was signed. Only the dumped
+/// request is kept, client address and other requests of the batch are dropped.
+pub fn dump_sign_request(
+    dir: impl AsRef<Path>,
+    request: &SignRequest,
+    assigned_slices: Vec<u64>,
+    error: String,
+) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir.as_ref())?;
+    let ts = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+    let path = dir.as_ref().join(format!(
+        "{}_{}_{}_{}.pb",
+        request.epoch,
+        request.quorum_id,
+        hex::encode(&request.storage_root),
+        ts
+    ));
+    let record = RecordedSignRequest {
+        request: Some(request.clone()),
+        assigned_slices,
+        error,
+    };
+    std::fs::write(&path, record.encode_to_vec())?;
+    Ok(path)
+}
+
+pub fn load_recorded_request(path: impl AsRef<Path>) -> Result<RecordedSignRequest> {
+    let raw = std::fs::read(path.as_ref())
+        .map_err(|e| anyhow!("cannot read recorded request {:?}: {:?}", path.as_ref(), e))?;
+    RecordedSignRequest::decode(&*raw)
+        .map_err(|e| anyhow!("invalid recorded request {:?}: {:?}", path.as_ref(), e))
+}
+
+/// Re-execute the verification pipeline of `BatchSign` on a recorded request step by step.
+/// Slices are verified one by one without the deferred verifier, so the failing slice is located.
+pub fn replay_sign_request(record: &RecordedSignRequest, params_dir: String) -> Result<()> {
+    let req = record
+        .request
+        .as_ref()
+        .ok_or_else(|| anyhow!("recorded request is empty"))?;
+    info!(
+        epoch = req.epoch,
+        quorum_id = req.quorum_id,
+        storage_root = hex::encode(&req.storage_root),
+        slices = req.encoded_slice.len(),
+        "replaying request, recorded error: {}",
+        record.error
+    );
+
+    info!("loading encoder params from {:?}", params_dir);
+    let params = ZgSignerParams::from_dir_mont(params_dir);
+
+    debug!("step 1: decode storage root and erasure commitment");
+    let (storage_root, erasure_commitment) =
+        SignerService::decode_root(req).map_err(|e| anyhow!(e.message().to_string()))?;
+    debug!(?erasure_commitment, "decoded");
+
+    debug!("step 2: decode encoded slices");
+    let encoded_slices =
+        SignerService::decode_encoded_slices(req).map_err(|e| anyhow!(e.message().to_string()))?;
+
+    debug!("step 3: check assigned slices");
+    if record.assigned_slices.len() != encoded_slices.len() {
+        bail!(anyhow!(
+            "received {} slices, {} assigned",
+            encoded_slices.len(),
+            record.assigned_slices.len()
+        ));
+    }
+    for (expected_index, slice) in record.assigned_slices.iter().zip(encoded_slices.iter()) {
+        if *expected_index != slice.index as u64 {
+            bail!(anyhow!(
+                "slice index {} received, {} assigned",
+                slice.index,
+                expected_index
+            ));
+        }
+    }
+
+    debug!("step 4: verify slices");
+    let mut failed = 0;
+    for slice in encoded_slices.iter() {
+        let ts = Instant::now();
+        match slice.verify(&params, &erasure_commitment, &storage_root, None) {
+            Ok(()) => debug!(
+                index = slice.index,
+                "slice verified in {:?} ms",
+                ts.elapsed().as_millis()
+            ),
+            Err(e) => {
+                error!(index = slice.index, "slice verification failed: {:?}", e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        bail!(anyhow!("{} slices failed verification", failed));
+    }
+
+    debug!("step 5: deferred pairing check");
+    let deferred_verifier = DeferredVerifier::new();
+    for slice in encoded_slices.iter() {
+        slice
+            .verify(
+                &params,
+                &erasure_commitment,
+                &storage_root,
+                Some(deferred_verifier.clone()),
+            )
+            .map_err(|e| anyhow!("slice {} verification failed: {:?}", slice.index, e))?;
+    }
+    if !deferred_verifier.fast_check() {
+        bail!(anyhow!("deferred pairing check failed"));
+    }
+
+    let hash = blob_verified_hash(storage_root, req.epoch, req.quorum_id, erasure_commitment);
+    let mut value = Vec::new();
+    hash.serialize_uncompressed(&mut value)?;
+    info!(
+        "request passes verification, signed message {}",
+        hex::encode(value)
+    );
+    Ok(())
+}
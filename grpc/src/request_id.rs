@@ -0,0 +1,110 @@
+use std::future::Future;
+
+use tonic::{metadata::MetadataValue, Request, Response, Status};
+use tracing::Instrument;
+
+/// Request metadata correlating a request with the node logs. Requests without it get a generated
+/// one, echoed in the reply metadata and in the message and metadata of errors.
+pub const REQUEST_ID_METADATA_KEY: &str = "request-id";
+
+/// Longest request id accepted from a client, longer ones are replaced by a generated id.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+fn generate_request_id() -> String {
+    hex::encode(rand::random::<[u8; 16]>())
+}
+
+/// Id of a request set by `with_request_id`, or sent by the client.
+pub(crate) fn request_id<T>(request: &Request<T>) -> Option<String> {
+    request
+        .metadata()
+        .get(REQUEST_ID_METADATA_KEY)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .map(|id| id.to_string())
+}
+
+/// Set the metadata of a request forwarded to another signer, so its logs have the same id.
+pub(crate) fn forward_request_id<T>(request: &mut Request<T>, id: Option<&str>) {
+    if let Some(value) = id.and_then(|id| MetadataValue::try_from(id).ok()) {
+        request
+            .metadata_mut()
+            .insert(REQUEST_ID_METADATA_KEY, value);
+    }
+}
+
+/// Handle `request` in a span carrying its id, the id of the client or a generated one, then echo
+/// the id in the reply or the error.
+pub(crate) async fn with_request_id<T, R, F>(
+    method: &'static str,
+    mut request: Request<T>,
+    handle: impl FnOnce(Request<T>) -> F,
+) -> Result<Response<R>, Status>
+where
+    F: Future<Output = Result<Response<R>, Status>>,
+{
+    let id = request_id(&request).unwrap_or_else(generate_request_id);
+    // a generated id is ascii hex, a client one was read as ascii
+    let value: MetadataValue<_> = id.parse().expect("request id is ascii");
+    request
+        .metadata_mut()
+        .insert(REQUEST_ID_METADATA_KEY, value.clone());
+    let span = info_span!("request", request_id = %id, method);
+    match handle(request).instrument(span).await {
+        Ok(mut reply) => {
+            reply.metadata_mut().insert(REQUEST_ID_METADATA_KEY, value);
+            Ok(reply)
+        }
+        Err(status) => {
+            let mut metadata = status.metadata().clone();
+            metadata.insert(REQUEST_ID_METADATA_KEY, value);
+            Err(Status::with_metadata(
+                status.code(),
+                format!("{} (request-id: {})", status.message(), id),
+                metadata,
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonic::Code;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn with_request_id_test() {
+        let mut request = Request::new(());
+        request
+            .metadata_mut()
+            .insert(REQUEST_ID_METADATA_KEY, "batch-42".parse().unwrap());
+        let reply = with_request_id("test", request, |request| async move {
+            assert_eq!(request_id(&request).as_deref(), Some("batch-42"));
+            Ok(Response::new(()))
+        })
+        .await
+        .unwrap();
+        assert_eq!(
+            reply.metadata().get(REQUEST_ID_METADATA_KEY).unwrap(),
+            "batch-42"
+        );
+
+        let status = with_request_id("test", Request::new(()), |_| async {
+            Err::<Response<()>, _>(Status::new(Code::NotFound, "blob not found"))
+        })
+        .await
+        .unwrap_err();
+        let id = status
+            .metadata()
+            .get(REQUEST_ID_METADATA_KEY)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert_eq!(id.len(), 32);
+        assert_eq!(
+            status.message(),
+            format!("blob not found (request-id: {})", id)
+        );
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::{runtime::Handle, time::sleep};
+
+/// Default listener of tokio-console, the one the console connects to.
+pub const DEFAULT_TOKIO_CONSOLE_ADDRESS: &str = "127.0.0.1:6669";
+
+/// Scheduling statistics of a runtime measured by its probe task.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuntimeStats {
+    pub workers: usize,
+    pub probes: u64,
+    pub total_delay: Duration,
+    pub max_delay: Duration,
+    /// Probes delayed over the stall threshold.
+    pub stalls: u64,
+    pub last_stall_at: Option<u64>,
+}
+
+impl RuntimeStats {
+    pub fn mean_delay(&self) -> Duration {
+        if self.probes == 0 {
+            return Duration::ZERO;
+        }
+        self.total_delay / self.probes as u32
+    }
+
+    fn record(&mut self, delay: Duration, stall_threshold: Duration) -> bool {
+        self.probes += 1;
+        self.total_delay += delay;
+        self.max_delay = self.max_delay.max(delay);
+        if delay < stall_threshold {
+            return false;
+        }
+        self.stalls += 1;
+        self.last_stall_at = Some(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        );
+        true
+    }
+}
+
+/// Watches how late the runtimes of the node poll a periodic probe task. A late probe means the
+/// workers are busy or blocked by a task not yielding, which stalls every other task of the
+/// runtime, e.g. the monitor or the transactor.
+#[derive(Clone, Default)]
+pub struct RuntimeMonitor {
+    runtimes: Arc<Mutex<BTreeMap<String, RuntimeStats>>>,
+}
+
+impl RuntimeMonitor {
+    /// Probe the runtime the returned future is spawned on every `interval`, the future never ends.
+    pub async fn probe(self, name: String, interval: Duration, stall_threshold: Duration) {
+        let workers = Handle::current().metrics().num_workers();
+        self.runtimes.lock().unwrap().insert(
+            name.clone(),
+            RuntimeStats {
+                workers,
+                ..Default::default()
+            },
+        );
+        loop {
+            let start = Instant::now();
+            sleep(interval).await;
+            let delay = start.elapsed().saturating_sub(interval);
+            let stalled = self
+                .runtimes
+                .lock()
+                .unwrap()
+                .get_mut(&name)
+                .map_or(false, |stats| stats.record(delay, stall_threshold));
+            if stalled {
+                warn!(
+                    runtime = name,
+                    delay_ms = delay.as_millis() as u64,
+                    "runtime stalled, a task may be blocking its worker threads"
+                );
+            }
+        }
+    }
+
+    /// Statistics of the probed runtimes by name.
+    pub fn stats(&self) -> Vec<(String, RuntimeStats)> {
+        self.runtimes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.clone()))
+            .collect()
+    }
+}
+
+/// Tracing layer serving the tasks of the runtimes to tokio-console on `address`, its server runs
+/// on a thread of its own. Tokio only records the tasks if the node is built with
+/// `--cfg tokio_unstable`.
+#[cfg(feature = "tokio-console")]
+pub fn tokio_console_layer<S>(address: std::net::SocketAddr) -> impl tracing_subscriber::Layer<S>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    console_subscriber::ConsoleLayer::builder()
+        .server_addr(address)
+        .spawn()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runtime_stats_test() {
+        let mut stats = RuntimeStats::default();
+        let threshold = Duration::from_millis(500);
+        assert!(!stats.record(Duration::from_millis(2), threshold));
+        assert!(stats.record(Duration::from_millis(800), threshold));
+        assert_eq!(stats.probes, 2);
+        assert_eq!(stats.stalls, 1);
+        assert!(stats.last_stall_at.is_some());
+        assert_eq!(stats.max_delay, Duration::from_millis(800));
+        assert_eq!(stats.mean_delay(), Duration::from_millis(401));
+    }
+}
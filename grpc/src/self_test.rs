@@ -0,0 +1,103 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G1Projective, G2Affine};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup};
+use ark_ff::Zero;
+use zg_encoder::EncodedSlice;
+
+use crate::replay::load_recorded_request;
+use crate::service::{blob_verified_hash, sign_message, SignerService};
+
+/// Blob of the known answer test of the signed message, its commitment is the generator.
+const VECTOR_STORAGE_ROOT: [u8; 32] = [0x11; 32];
+const VECTOR_EPOCH: u64 = 1;
+const VECTOR_QUORUM_ID: u64 = 2;
+/// `blob_verified_hash` of the test blob.
+const VECTOR_HASH_X: &str =
+    "3104132272622526655068902279970515367044771064982988265068273751564440697689";
+const VECTOR_HASH_Y: &str =
+    "14983672482514514723382346054400511740670770934276906876175822994665721348371";
+
+/// Check that the message of the test blob hashes to the known point, and that its signature by
+/// `signer_bls_private_key` passes the pairing check against the matching public key.
+pub(crate) fn check_signature(signer_bls_private_key: Fr) -> Result<()> {
+    let hash = blob_verified_hash(
+        VECTOR_STORAGE_ROOT,
+        VECTOR_EPOCH,
+        VECTOR_QUORUM_ID,
+        G1Affine::generator().into(),
+    );
+    let expected = G1Affine::new(
+        Fq::from_str(VECTOR_HASH_X).map_err(|_| anyhow!("invalid test vector"))?,
+        Fq::from_str(VECTOR_HASH_Y).map_err(|_| anyhow!("invalid test vector"))?,
+    );
+    if hash != expected {
+        bail!(anyhow!(
+            "message of the test blob hashes to {:?}, {:?} expected",
+            hash,
+            expected
+        ));
+    }
+
+    if signer_bls_private_key.is_zero() {
+        bail!(anyhow!("signer bls key is zero"));
+    }
+    let signature = sign_message(hash, signer_bls_private_key);
+    let public_key = (G2Affine::generator() * signer_bls_private_key).into_affine();
+    if Bn254::pairing(signature, G2Affine::generator()) != Bn254::pairing(hash, public_key) {
+        bail!(anyhow!(
+            "signature of the test blob fails the pairing check"
+        ));
+    }
+    Ok(())
+}
+
+/// Slices of a signed request recorded by `record_transcript`, verified on start.
+pub(crate) struct SelfTestVector {
+    pub epoch: u64,
+    pub storage_root: [u8; 32],
+    pub erasure_commitment: G1Projective,
+    pub assigned_slices: Vec<u64>,
+    pub encoded_slices: Vec<EncodedSlice>,
+}
+
+impl SelfTestVector {
+    pub fn load(path: &str) -> Result<Self> {
+        let record = load_recorded_request(path)?;
+        if !record.error.is_empty() {
+            bail!(anyhow!(
+                "self-test vector {:?} was rejected when recorded: {}",
+                path,
+                record.error
+            ));
+        }
+        let req = record
+            .request
+            .as_ref()
+            .ok_or_else(|| anyhow!("self-test vector {:?} is empty", path))?;
+        let (storage_root, erasure_commitment) =
+            SignerService::decode_root(req).map_err(|e| anyhow!(e.message().to_string()))?;
+        let encoded_slices = SignerService::decode_encoded_slices(req)
+            .map_err(|e| anyhow!(e.message().to_string()))?;
+        Ok(Self {
+            epoch: req.epoch,
+            storage_root,
+            erasure_commitment,
+            assigned_slices: record.assigned_slices,
+            encoded_slices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_signature_test() {
+        check_signature(Fr::from(1)).unwrap();
+        check_signature(Fr::from(123456789)).unwrap();
+        assert!(check_signature(Fr::zero()).is_err());
+    }
+}
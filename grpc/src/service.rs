@@ -1,68 +1,261 @@
 #![allow(unused)]
 
+use crate::admission::{Admission, Inflight};
+use crate::aggregation::aggregate_signatures;
+use crate::audit_log::{AuditLog, SignOperation};
+use crate::batch_proxy::{BackendState, BatchProxy};
+use crate::blob_events::{subscribe_blob_events, BlobEventStream};
+use crate::envelope::{custody_digest, custody_row, encoded_slices_digest, stored_slices_digest};
+use crate::health::{DetectorUpdate, ParamsMismatchDetector};
+use crate::load_shedding::LoadShedder;
+use crate::maintenance::Maintenance;
+use crate::params::ParamsSchedule;
+use crate::protocol::{
+    COMPRESSED_ENCODING, CUSTODY_PROOFS, OPENING_PROOFS, PROTOCOL_VERSIONS, SIGN_QUOTA,
+    SLICE_REPAIR,
+};
+use crate::replay::dump_sign_request;
+use crate::request_id::request_id;
+use crate::self_test::{check_signature, SelfTestVector};
 use crate::service::signer::signer_server::{Signer, SignerServer};
 use crate::service::signer::{BatchSignReply, BatchSignRequest};
+use crate::sign_options::{SignOptions, RECORD_TRANSCRIPT};
+use crate::sign_quota::SignQuota;
+use crate::slice_writer::{AckMode, SliceStore, SliceWrite, SliceWriter};
+use crate::trace_context::set_remote_parent;
+use crate::verification_metrics::{LabeledMetrics, VerificationMetrics};
+use crate::verification_queue::{VerificationPriority, VerificationQueue};
+use crate::{build_info, SignerConfig};
 use anyhow::{anyhow, bail};
-use ark_bn254::{Bn254, Fq, Fr, G1Affine, G1Projective};
+use ark_bn254::{Bn254, Fq, Fr, G1Affine, G1Projective, G2Affine};
 use ark_ec::{AffineRepr, CurveGroup};
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use chain_state::signers_handler::serialize_g1_point;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+use chain_state::retrieval_envelope::EnvelopeMessage;
+use chain_state::signer_keys::SignerKeys;
+use chain_state::signers_handler::{proof_of_possession, serialize_g1_point};
 use chain_state::ChainState;
 use ethers::abi::{self, Token};
 use ethers::types::{Res, U256};
 use ethers::utils::keccak256;
+use events::{EventBus, NodeEvent};
 use prost::Message;
 use rayon::iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator};
-use signer::{BatchRetrieveReply, BatchRetrieveRequest, Empty, Slices};
+use signer::{
+    AggregateSignaturesReply, AggregateSignaturesRequest, AssignmentReply, AssignmentRequest,
+    BatchRetrieveReply, BatchRetrieveRequest, BlobEventFilter, BlobStatusReply, BlobStatusRequest,
+    CustodyChallenge, CustodyProof, Empty, NodeInfo, ProofOfPossession, QuorumAssignment,
+    RepairRequest, RetrievalEnvelope, RetrieveRequest, SignOutcomeReply, SignOutcomeRequest,
+    SliceChunk, Slices, StoredSlice, StoredSlices, StreamSlicesRequest,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use storage::blob_status_db::{BlobStatus, BlobStatusDB};
+use storage::misc_db::MiscDB;
+use storage::opening_proof_db::OpeningProofDB;
 use storage::quorum_db::{AssignedSlices, QuorumDB};
-use storage::slice_db::SliceDB;
+use storage::scrub_db::ScrubDB;
+use storage::sign_outcome_db::{SignOutcome, SignOutcomeDB};
+use storage::slice_db::{SliceDB, SliceIndex};
 use storage::Storage;
-use tokio::sync::RwLock;
+use tokio::sync::{mpsc, RwLock};
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::metadata::KeyAndMutValueRef;
 use tonic::{Code, Request, Response, Status};
+use tracing::Instrument;
 use utils::map_to_g1;
-use zg_encoder::{DeferredVerifier, EncodedSlice, ZgEncoderParams, ZgSignerParams};
+use zg_encoder::constants::BLOB_ROW_ENCODED;
+use zg_encoder::{
+    DeferredVerifier, EncodedBlob, EncodedSlice, RawBlob, RawData, ZgEncoderParams, ZgSignerParams,
+};
 
-use self::signer::SignRequest;
+use self::signer::{sign_result, SignError, SignRequest, SignResult, SliceEncoding};
 
 pub mod signer {
     tonic::include_proto!("signer");
+
+    pub mod v2 {
+        tonic::include_proto!("signer.v2");
+    }
 }
 
 const DEFAULT_MAX_ONGOING_SIGN_REQUEST: u64 = 10;
+/// Epoch, quorum and digest of the content of a sign request, requests with the same key sign to
+/// the same signature.
+pub(crate) type SignRequestKey = (u64, u64, [u8; 32]);
+/// Size of a compressed G1 point, an uncompressed one takes twice.
+const G1_COMPRESSED_SIZE: usize = 32;
+/// Messages of a slice stream buffered ahead of the client, a slow client holds the reads.
+const STREAM_SLICES_BUFFER: usize = 4;
+
+/// Key of a sign request, duplicates in a batch are signed once. The commitment is keyed in its
+/// compressed form so both encodings of a point match.
+pub(crate) fn sign_request_key(req: &SignRequest) -> SignRequestKey {
+    let mut commitment = vec![];
+    match SignerService::decode_blob(&req.storage_root, &req.erasure_commitment) {
+        Ok((_, point)) => point
+            .into_affine()
+            .serialize_compressed(&mut commitment)
+            .expect("serialize to a vec"),
+        // an invalid commitment fails the same way in each copy
+        Err(_) => commitment.extend_from_slice(&req.erasure_commitment),
+    }
+    let options: BTreeMap<_, _> = req.options.iter().collect();
+    let mut content = vec![];
+    for part in [
+        req.storage_root.as_slice(),
+        &commitment,
+        &encoded_slices_digest(&req.encoded_slice),
+    ] {
+        content.extend_from_slice(&(part.len() as u64).to_be_bytes());
+        content.extend_from_slice(part);
+    }
+    for (key, value) in options {
+        for part in [key, value] {
+            content.extend_from_slice(&(part.len() as u64).to_be_bytes());
+            content.extend_from_slice(part.as_bytes());
+        }
+    }
+    (req.epoch, req.quorum_id, keccak256(content))
+}
+
+pub(crate) type SliceChunkStream = ReceiverStream<Result<SliceChunk, Status>>;
 
 pub struct SignerService {
-    db: Arc<RwLock<Storage>>,
-    chain_state: Arc<ChainState>,
-    signer_bls_private_key: Fr,
-    encoder_params: ZgSignerParams,
+    db: Arc<Storage>,
+    /// `None` in storage-only mode, signing is disabled.
+    chain_state: Option<Arc<ChainState>>,
+    signer_keys: SignerKeys,
+    encoder_params: Arc<ParamsSchedule<ZgSignerParams>>,
+    max_batch_sign_requests: Option<u64>,
+    batch_proxy: Option<Arc<BatchProxy>>,
+    repair_encoder_params: Option<Arc<ParamsSchedule<ZgEncoderParams>>>,
+    request_dump_dir: Option<String>,
+    events: EventBus,
+    params_mismatch: ParamsMismatchDetector,
+    slice_store: SliceStore,
+    /// Writer of the signed slices in the `Async` ack mode, they are written inline otherwise.
+    slice_writer: Option<SliceWriter>,
     max_ongoing_sign_request: u64,
     ongoing_sign_request_cnt: Arc<RwLock<u64>>,
+    admission: Admission,
+    verification_metrics: VerificationMetrics,
+    sign_quota: Option<SignQuota>,
+    store_opening_proofs: bool,
+    audit_log: Option<Arc<AuditLog>>,
+    archive: bool,
+    maintenance: Maintenance,
+    load_shedder: Option<LoadShedder>,
+    verification_queue: Option<VerificationQueue>,
 }
 
 impl SignerService {
     pub fn new(
-        db: Arc<RwLock<Storage>>,
-        chain_state: Arc<ChainState>,
-        signer_bls_private_key: Fr,
-        params_dir: String,
-        max_ongoing_sign_request: Option<u64>,
+        db: Arc<Storage>,
+        chain_state: Option<Arc<ChainState>>,
+        signer_keys: SignerKeys,
+        config: SignerConfig,
     ) -> Self {
+        let slice_store = SliceStore {
+            db: db.clone(),
+            retry: config.put_slice_retry,
+            errors: Default::default(),
+            events: config.events.clone(),
+        };
         Self {
+            slice_writer: match config.ack_mode {
+                AckMode::Durable => None,
+                AckMode::Async { queue_size } => {
+                    Some(SliceWriter::start(slice_store.clone(), queue_size))
+                }
+            },
+            slice_store,
+            sign_quota: config
+                .sign_quota
+                .map(|quota| SignQuota::new(quota, db.clone())),
             db,
             chain_state,
-            signer_bls_private_key,
-            encoder_params: ZgSignerParams::from_dir_mont(params_dir),
-            max_ongoing_sign_request: max_ongoing_sign_request
+            signer_keys,
+            repair_encoder_params: if config.enable_slice_repair {
+                Some(Arc::new(ParamsSchedule::new(
+                    "prove",
+                    config.encoder_params_dir.clone(),
+                    &config.params_versions,
+                    config.params_load_mode,
+                    |dir| ZgEncoderParams::from_dir_mont(dir, false, None),
+                )))
+            } else {
+                None
+            },
+            request_dump_dir: config.request_dump_dir,
+            events: config.events,
+            params_mismatch: ParamsMismatchDetector::default(),
+            encoder_params: config.slice_verifier.params(),
+            max_ongoing_sign_request: config
+                .max_ongoing_sign_request
                 .unwrap_or(DEFAULT_MAX_ONGOING_SIGN_REQUEST),
-            ongoing_sign_request_cnt: Arc::new(RwLock::new(0)),
+            ongoing_sign_request_cnt: config.sign_load,
+            admission: Admission::new(config.admission),
+            max_batch_sign_requests: config.max_batch_sign_requests,
+            batch_proxy: config.batch_proxy,
+            verification_metrics: config.verification_metrics,
+            store_opening_proofs: config.store_opening_proofs,
+            audit_log: config.audit_log,
+            archive: config.archive,
+            maintenance: config.maintenance,
+            load_shedder: config.load_shedder,
+            verification_queue: config.verification_queue,
         }
     }
 
+    /// Chain state of a node that signs, the status rejecting the calls that sign otherwise.
+    fn signing_chain_state(&self) -> Result<&Arc<ChainState>, Status> {
+        if self.archive {
+            return Err(Status::new(
+                Code::Unavailable,
+                "signing is disabled in archive mode",
+            ));
+        }
+        self.chain_state.as_ref().ok_or_else(|| {
+            Status::new(
+                Code::Unavailable,
+                "signing is disabled in storage-only mode",
+            )
+        })
+    }
+
+    /// Chain state to seal retrieval responses with, `None` if the node does not sign.
+    fn sealing_chain_state(&self) -> Option<Arc<ChainState>> {
+        self.signing_chain_state().ok().cloned()
+    }
+
+    /// Startup self-test of the sign path: the signed message and signature of a known blob with
+    /// the latest key, then the slices of `vector`, a recorded signed request, verified with the
+    /// params of its epoch. An error means the key or the params cannot be trusted to sign.
+    pub async fn self_test(&self, vector: Option<&str>) -> anyhow::Result<()> {
+        let ts = Instant::now();
+        check_signature(self.signer_keys.latest().await)?;
+        if let Some(path) = vector {
+            let vector = SelfTestVector::load(path)?;
+            self.verify_assigned_slices(
+                self.encoder_params.for_epoch(vector.epoch).get(),
+                vector.storage_root,
+                vector.erasure_commitment,
+                vector.assigned_slices,
+                &vector.encoded_slices,
+                &LabeledMetrics::default(),
+            )
+            .map_err(|e| anyhow!("slices of the self-test vector fail verification: {:?}", e))?;
+        } else {
+            warn!("no self-test vector configured, the encoder params are not tested");
+        }
+        info!("self-test passed in {:?} ms", ts.elapsed().as_millis());
+        Ok(())
+    }
+
     async fn on_incoming_batch_sign(&self) -> Result<(), Status> {
+        self.maintenance.check()?;
         let mut cnt = self.ongoing_sign_request_cnt.write().await;
         if *cnt > self.max_ongoing_sign_request {
             return Err(Status::new(Code::ResourceExhausted, "request pool is full"));
@@ -76,75 +269,327 @@ impl SignerService {
         *cnt -= 1;
     }
 
+    /// Limits and load of the local sign pipeline, as advertised to clients.
+    pub(crate) async fn batch_limits(&self) -> BackendState {
+        BackendState {
+            max_batch_requests: self.max_batch_sign_requests,
+            max_ongoing: self.max_ongoing_sign_request,
+            ongoing: *self.ongoing_sign_request_cnt.read().await,
+            reachable: true,
+        }
+    }
+
+    /// Sign a batch with the local pipeline.
+    pub(crate) async fn batch_sign_local(
+        &self,
+        request: Request<BatchSignRequest>,
+    ) -> Result<Response<BatchSignReply>, Status> {
+        let requests = request.get_ref().requests.len() as u64;
+        if let Some(max) = self.max_batch_sign_requests {
+            if requests > max {
+                return Err(Status::new(
+                    Code::InvalidArgument,
+                    format!(
+                        "batch of {} requests exceeds the limit of {}",
+                        requests, max
+                    ),
+                ));
+            }
+        }
+        // delayed batches wait before taking a share of the in-flight budgets
+        if let Some(load_shedder) = &self.load_shedder {
+            load_shedder.admit().await?;
+        }
+        let _permit = self
+            .admission
+            .admit(Inflight::of_batch(request.get_ref()))?;
+        self.on_incoming_batch_sign().await?;
+        let reply = self.batch_sign_inner(request).await;
+        self.on_complete_batch_sign().await;
+        reply
+    }
+
+    /// Wait for the slices acknowledged in the `Async` ack mode to be written, on shutdown.
+    pub async fn flush_slice_writes(&self) {
+        if let Some(writer) = &self.slice_writer {
+            writer.flush().await;
+        }
+    }
+
+    /// Keys accepted in the `options` of sign requests, depending on the node configuration.
+    fn supported_sign_options(&self) -> Vec<&'static str> {
+        let mut options = vec![];
+        if self.request_dump_dir.is_some() {
+            options.push(RECORD_TRANSCRIPT);
+        }
+        options
+    }
+
+    /// Optional features of the node advertised to clients, depending on its configuration.
+    fn capabilities(&self) -> Vec<&'static str> {
+        let mut capabilities = vec![COMPRESSED_ENCODING];
+        for (capability, enabled) in [
+            (OPENING_PROOFS, self.store_opening_proofs),
+            (SLICE_REPAIR, self.repair_encoder_params.is_some()),
+            (CUSTODY_PROOFS, self.signing_chain_state().is_ok()),
+            (SIGN_QUOTA, self.sign_quota.is_some()),
+        ] {
+            if enabled {
+                capabilities.push(capability);
+            }
+        }
+        capabilities
+    }
+
     async fn batch_sign_inner(
         &self,
         request: Request<BatchSignRequest>,
     ) -> Result<Response<BatchSignReply>, Status> {
         let remote_addr = request.remote_addr();
+        let client = self.client_identity(&request);
+        let client_priority = self
+            .sign_quota
+            .as_ref()
+            .map_or(0, |quota| quota.client_priority(request.metadata()));
         let request_content = request.into_inner();
         let ts = Instant::now();
 
         info!(?remote_addr, "Received request");
-        let mut reply = BatchSignReply { signatures: vec![] };
+        self.signing_chain_state()?;
+        let supported_options = self.supported_sign_options();
+        let batch_size = request_content.requests.len();
+        // position of the first copy of each blob, client retries may repeat a blob in a batch
+        let mut signed: HashMap<SignRequestKey, usize> = HashMap::new();
+        let mut results: Vec<Result<Vec<u8>, Status>> = vec![];
 
-        for req in request_content.requests.iter() {
-            let (storage_root, erasure_commitment) = Self::decode_root(req)?;
+        for (i, req) in request_content.requests.iter().enumerate() {
+            let key = sign_request_key(req);
+            if let Some(first) = signed.get(&key) {
+                debug!(position = i, first, "duplicate sign request in batch");
+                // audited at each position, a client sees every signature it was returned
+                let result = self.audit_sign(&client, req, results[*first].clone()).await;
+                match result {
+                    Err(status) if !request_content.partial_success => return Err(status),
+                    result => results.push(result),
+                }
+                continue;
+            }
+            signed.insert(key, i);
+            let result = match SignOptions::parse(&req.options, &supported_options) {
+                Ok(options) => {
+                    self.sign_request(req, &options, batch_size, client_priority)
+                        .instrument(info_span!(
+                            "sign_request",
+                            epoch = req.epoch,
+                            quorum_id = req.quorum_id
+                        ))
+                        .await
+                }
+                Err(status) => Err(status),
+            };
+            let result = self.audit_sign(&client, req, result).await;
+            match result {
+                Err(status) if !request_content.partial_success => return Err(status),
+                result => results.push(result),
+            }
+        }
 
-            self.check_blob_status(req, storage_root).await?;
+        let mut reply = BatchSignReply::default();
+        for result in results {
+            if request_content.partial_success {
+                push_sign_result(&mut reply, result);
+            } else {
+                reply.signatures.push(result?);
+            }
+        }
+        info!("responsed in {:?} ms", ts.elapsed().as_millis());
+        Ok(Response::new(reply))
+    }
 
-            let encoded_slices = Self::decode_encoded_slices(req)?;
+    /// Sign client of the token of a request, or its address.
+    fn client_identity(&self, request: &Request<BatchSignRequest>) -> String {
+        if let Some(name) = self
+            .sign_quota
+            .as_ref()
+            .and_then(|quota| quota.client_name(request.metadata()))
+        {
+            return name.to_string();
+        }
+        request
+            .remote_addr()
+            .map_or("unknown".to_string(), |addr| addr.to_string())
+    }
 
-            let res = self
-                .verify_encoded_slices(
-                    req.epoch,
-                    req.quorum_id,
-                    storage_root,
-                    erasure_commitment,
-                    &encoded_slices,
-                )
-                .await;
+    /// Append the result of a sign request to the audit log. A signature that cannot be audited is
+    /// not returned.
+    async fn audit_sign(
+        &self,
+        client: &str,
+        req: &SignRequest,
+        result: Result<Vec<u8>, Status>,
+    ) -> Result<Vec<u8>, Status> {
+        let audit_log = match &self.audit_log {
+            Some(audit_log) => audit_log,
+            None => return result,
+        };
+        let op = SignOperation {
+            client,
+            epoch: req.epoch,
+            quorum_id: req.quorum_id,
+            storage_root: &req.storage_root,
+            commitment: &req.erasure_commitment,
+            result: &result,
+        };
+        match audit_log.append(op).await {
+            Ok(()) => result,
+            Err(e) => {
+                error!("cannot append to the audit log: {:?}", e);
+                Err(Status::new(Code::Internal, "cannot write audit log"))
+            }
+        }
+    }
 
-            if let Err(error) = res {
-                return Err(match error {
-                    VerificationError::Internal(e) => Status::new(
-                        Code::Internal,
-                        format!("internal error on verification: {:?}", e),
-                    ),
-                    VerificationError::SliceMismatch => Status::new(
-                        Code::InvalidArgument,
-                        "received slices and assigned slices are mismatch",
-                    ),
-                    VerificationError::IncorrectSlice(e) => Status::new(
-                        Code::InvalidArgument,
-                        format!("verification failed: {:?}", e),
-                    ),
-                    VerificationError::DeferredVerifyFail => Status::new(
-                        Code::InvalidArgument,
-                        "received slice does not pass pairing check, the accelerated verification algorithm cannot detect the specific error location".to_string(),
+    /// Verify and sign a request of a batch, storing its slices. Each step is traced in its own span.
+    async fn sign_request(
+        &self,
+        req: &SignRequest,
+        options: &SignOptions,
+        batch_size: usize,
+        client_priority: u64,
+    ) -> Result<Vec<u8>, Status> {
+        let metrics = self.verification_metrics.labeled(req.quorum_id, batch_size);
+        let (storage_root, erasure_commitment) =
+            info_span!("decode").in_scope(|| Self::decode_root(req))?;
+
+        self.check_blob_status(req, storage_root)
+            .instrument(info_span!("check_blob_status"))
+            .await?;
+        let slot = match &self.verification_queue {
+            Some(queue) => Some(
+                queue
+                    .acquire(VerificationPriority::new(client_priority, req.epoch))
+                    .instrument(info_span!("verification_queue"))
+                    .await,
+            ),
+            None => None,
+        };
+        let ts = Instant::now();
+        let encoded_slices = info_span!("decode").in_scope(|| Self::decode_encoded_slices(req))?;
+        metrics.decode.record(ts.elapsed());
+
+        let res = self
+            .verify_encoded_slices(
+                req.epoch,
+                req.quorum_id,
+                storage_root,
+                erasure_commitment,
+                &encoded_slices,
+                &metrics,
+            )
+            .instrument(info_span!("verify_slices", slices = encoded_slices.len()))
+            .await;
+        drop(slot);
+
+        if let Err(error) = res {
+            let systematic = matches!(
+                error,
+                VerificationError::IncorrectSlice(_) | VerificationError::DeferredVerifyFail
+            );
+            let mut status = match error {
+                VerificationError::Internal(e) => Status::new(
+                    Code::Internal,
+                    format!("internal error on verification: {:?}", e),
+                ),
+                VerificationError::SliceMismatch => Status::new(
+                    Code::InvalidArgument,
+                    "received slices and assigned slices are mismatch",
+                ),
+                VerificationError::IncorrectSlice(e) => Status::new(
+                    Code::InvalidArgument,
+                    format!("verification failed: {:?}", e),
+                ),
+                VerificationError::DeferredVerifyFail => Status::new(
+                    Code::InvalidArgument,
+                    "received slice does not pass pairing check, the accelerated verification algorithm cannot detect the specific error location".to_string(),
+                ),
+                VerificationError::NotAssigned { epoch, quorum_id } => Status::new(
+                    Code::FailedPrecondition,
+                    format!(
+                        "signer is not assigned to quorum {} of epoch {}",
+                        quorum_id, epoch
                     ),
-                });
+                ),
+            };
+            if systematic {
+                status = self.check_params_mismatch(storage_root, status);
             }
+            self.dump_request(req, Some(status.message())).await;
+            self.record_sign_outcome(req, storage_root, Some(status.message()))
+                .await;
+            return Err(status);
+        }
+        self.params_mismatch.on_verified();
 
+        let signature = async {
             let hash =
                 blob_verified_hash(storage_root, req.epoch, req.quorum_id, erasure_commitment);
-            let signature = (hash * self.signer_bls_private_key).into_affine();
+            let signer_bls_private_key = self.signer_keys.key_for_epoch(req.epoch).await;
+            let signature = sign_message(hash, signer_bls_private_key);
             let mut value = Vec::new();
             signature.serialize_uncompressed(&mut value);
-            reply.signatures.push(value);
-            // write slices to db
-            self.db
-                .write()
-                .await
-                .put_slice(req.epoch, req.quorum_id, storage_root, encoded_slices)
-                .await
-                .map_err(|e| Status::new(Code::Internal, format!("put slice error: {:?}", e)))?;
+            value
         }
-
-        info!("responsed in {:?} ms", ts.elapsed().as_millis());
-        Ok(Response::new(reply))
+        .instrument(info_span!("sign"))
+        .await;
+        let opening_proofs: Vec<(u64, Vec<u8>)> = if self.store_opening_proofs {
+            encoded_slices
+                .iter()
+                .map(|slice| {
+                    let mut value = Vec::new();
+                    slice.serialize_compressed(&mut value).unwrap();
+                    (slice.index as u64, value)
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        // write slices to db
+        let write = SliceWrite {
+            epoch: req.epoch,
+            quorum_id: req.quorum_id,
+            storage_root,
+            slices: encoded_slices,
+            opening_proofs,
+        };
+        match &self.slice_writer {
+            Some(writer) => {
+                writer
+                    .enqueue(write)
+                    .instrument(info_span!("db_enqueue"))
+                    .await?
+            }
+            None => {
+                self.slice_store
+                    .write(write)
+                    .instrument(info_span!("db_write"))
+                    .await?
+            }
+        }
+        if let Err(e) = self
+            .db
+            .put_signature(req.epoch, req.quorum_id, storage_root, &signature)
+            .await
+        {
+            warn!("cannot record signature: {:?}", e);
+        }
+        self.record_sign_outcome(req, storage_root, None).await;
+        if options.record_transcript {
+            self.dump_request(req, None).await;
+        }
+        Ok(signature)
     }
 
-    async fn batch_retrieve_inner(
+    pub(crate) async fn batch_retrieve_inner(
         &self,
         request: Request<BatchRetrieveRequest>,
     ) -> Result<Response<BatchRetrieveReply>, Status> {
@@ -159,6 +604,8 @@ impl SignerService {
         for req in request_content.requests.iter() {
             let mut slices = Slices {
                 encoded_slice: vec![],
+                envelope: None,
+                opening_proofs: vec![],
             };
             let storage_root: [u8; 32] = req
                 .storage_root
@@ -167,8 +614,6 @@ impl SignerService {
                 .map_err(|_| Status::new(Code::InvalidArgument, "storage root"))?;
             let maybe_assigned_slices = self
                 .db
-                .read()
-                .await
                 .get_assgined_slices(req.epoch, req.quorum_id)
                 .await
                 .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
@@ -188,8 +633,6 @@ impl SignerService {
                         if j < assigned_slices.len() && assigned_slices[j] == *row_index as u64 {
                             let maybe_slice = self
                                 .db
-                                .read()
-                                .await
                                 .get_raw_slice(
                                     req.epoch,
                                     req.quorum_id,
@@ -222,10 +665,634 @@ impl SignerService {
                     ));
                 }
             }
+            if req.with_opening_proofs {
+                let mut row_indexes = req.row_indexes.clone();
+                row_indexes.sort_unstable();
+                row_indexes.dedup();
+                for row_index in row_indexes {
+                    let proof = self
+                        .stored_opening_proof(req.epoch, req.quorum_id, storage_root, row_index)
+                        .await?;
+                    slices.opening_proofs.push(proof);
+                }
+            }
+            slices.envelope = self
+                .seal_retrieval(
+                    req.epoch,
+                    req.quorum_id,
+                    storage_root,
+                    encoded_slices_digest(&slices.encoded_slice),
+                )
+                .await;
             reply.encoded_slice.push(slices);
         }
         Ok(Response::new(reply))
     }
+
+    /// Opening proof kept for a row. Proofs are only kept for the slices signed while
+    /// `store_opening_proofs` is set, they are not computed again from the stored rows, so a row
+    /// signed before is not found even though `OPENING_PROOFS` is advertised.
+    async fn stored_opening_proof(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        row_index: u32,
+    ) -> Result<Vec<u8>, Status> {
+        let proof = self
+            .db
+            .get_opening_proof(epoch, quorum_id, storage_root, row_index as u64)
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        match proof {
+            Some(proof) => Ok(proof),
+            None => Err(Status::new(
+                Code::NotFound,
+                format!(
+                    "opening proof of row {} is not stored, see store_opening_proofs",
+                    row_index
+                ),
+            )),
+        }
+    }
+
+    pub(crate) async fn retrieve_stored_slices_inner(
+        &self,
+        request: Request<RetrieveRequest>,
+    ) -> Result<Response<StoredSlices>, Status> {
+        let remote_addr = request.remote_addr();
+        let req = request.into_inner();
+
+        info!(?remote_addr, "Received stored slices request");
+        let storage_root: [u8; 32] = req
+            .storage_root
+            .clone()
+            .try_into()
+            .map_err(|_| Status::new(Code::InvalidArgument, "storage root"))?;
+        let mut row_indexes = req.row_indexes.clone();
+        row_indexes.sort_unstable();
+        row_indexes.dedup();
+        if row_indexes
+            .iter()
+            .any(|index| *index as usize >= BLOB_ROW_ENCODED)
+        {
+            return Err(Status::new(Code::InvalidArgument, "invalid row indexes"));
+        }
+        let mut reply = StoredSlices {
+            slices: vec![],
+            envelope: None,
+        };
+        let db = &self.db;
+        for row_index in row_indexes {
+            let light_slice = db
+                .get_slice(req.epoch, req.quorum_id, storage_root, row_index as usize)
+                .await
+                .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+            let data = db
+                .get_slice_data(req.epoch, req.quorum_id, storage_root, row_index as usize)
+                .await
+                .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+            if let (Some(light_slice), Some(data)) = (light_slice, data) {
+                let mut light_value = Vec::new();
+                light_slice.serialize_compressed(&mut light_value).unwrap();
+                let mut data_value = Vec::new();
+                data.serialize_uncompressed(&mut data_value).unwrap();
+                let encoded_slice = if req.with_opening_proofs {
+                    self.stored_opening_proof(req.epoch, req.quorum_id, storage_root, row_index)
+                        .await?
+                } else {
+                    vec![]
+                };
+                reply.slices.push(StoredSlice {
+                    row_index,
+                    light_slice: light_value,
+                    data: data_value,
+                    encoded_slice,
+                });
+            }
+        }
+        reply.envelope = self
+            .seal_retrieval(
+                req.epoch,
+                req.quorum_id,
+                storage_root,
+                stored_slices_digest(&reply.slices),
+            )
+            .await;
+        Ok(Response::new(reply))
+    }
+
+    async fn prove_custody_inner(
+        &self,
+        request: Request<CustodyChallenge>,
+    ) -> Result<Response<CustodyProof>, Status> {
+        let remote_addr = request.remote_addr();
+        let req = request.into_inner();
+
+        info!(?remote_addr, "Received custody challenge");
+        if self.signing_chain_state().is_err() {
+            return Err(Status::new(
+                Code::FailedPrecondition,
+                "custody proofs are only signed by signer nodes",
+            ));
+        }
+        let storage_root: [u8; 32] = req
+            .storage_root
+            .clone()
+            .try_into()
+            .map_err(|_| Status::new(Code::InvalidArgument, "storage root"))?;
+        let challenge: [u8; 32] = req
+            .challenge
+            .clone()
+            .try_into()
+            .map_err(|_| Status::new(Code::InvalidArgument, "challenge must be 32 bytes"))?;
+        let db = &self.db;
+        let internal = |e: anyhow::Error| Status::new(Code::Internal, e.to_string());
+        let assigned_slices = match db
+            .get_assgined_slices(req.epoch, req.quorum_id)
+            .await
+            .map_err(internal)?
+        {
+            Some(AssignedSlices(assigned_slices)) => assigned_slices,
+            None => {
+                return Err(Status::new(
+                    Code::Internal,
+                    format!("quorum of epoch {:?} not found", req.epoch),
+                ))
+            }
+        };
+        let row_index = custody_row(&challenge, storage_root, &assigned_slices)
+            .ok_or_else(|| Status::new(Code::NotFound, "no row assigned to the node"))?;
+        let light_slice = db
+            .get_slice(req.epoch, req.quorum_id, storage_root, row_index as usize)
+            .await
+            .map_err(internal)?;
+        let data = db
+            .get_slice_data(req.epoch, req.quorum_id, storage_root, row_index as usize)
+            .await
+            .map_err(internal)?;
+        let (light_slice, data) = match (light_slice, data) {
+            (Some(light_slice), Some(data)) => (light_slice, data),
+            _ => {
+                error!("slice is missing: epoch = {:?}, quorum = {:?}, storage_root = {:?}, row_index = {:?}", req.epoch, req.quorum_id, hex::encode(storage_root), row_index);
+                return Err(Status::new(Code::Internal, "slice is missing"));
+            }
+        };
+        let mut light_value = Vec::new();
+        light_slice.serialize_compressed(&mut light_value).unwrap();
+        let mut data_value = Vec::new();
+        data.serialize_uncompressed(&mut data_value).unwrap();
+        let slice = StoredSlice {
+            row_index: row_index as u32,
+            light_slice: light_value,
+            data: data_value,
+            encoded_slice: vec![],
+        };
+        let envelope = self
+            .seal_retrieval(
+                req.epoch,
+                req.quorum_id,
+                storage_root,
+                custody_digest(&challenge, &slice),
+            )
+            .await;
+        Ok(Response::new(CustodyProof {
+            slice: Some(slice),
+            envelope,
+        }))
+    }
+
+    pub(crate) async fn stream_slices_inner(
+        &self,
+        request: Request<StreamSlicesRequest>,
+    ) -> Result<Response<SliceChunkStream>, Status> {
+        let remote_addr = request.remote_addr();
+        let req = request.into_inner();
+
+        info!(?remote_addr, "Received stream slices request");
+        let storage_root: [u8; 32] = req
+            .storage_root
+            .clone()
+            .try_into()
+            .map_err(|_| Status::new(Code::InvalidArgument, "storage root"))?;
+        let assigned_slices = match self
+            .db
+            .get_assgined_slices(req.epoch, req.quorum_id)
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?
+        {
+            Some(AssignedSlices(assigned_slices)) => assigned_slices,
+            None => {
+                return Err(Status::new(
+                    Code::Internal,
+                    format!("quorum of epoch {:?} not found", req.epoch),
+                ))
+            }
+        };
+        let row_indexes = if req.row_indexes.is_empty() {
+            assigned_slices
+        } else {
+            let mut row_indexes: Vec<u64> = req.row_indexes.iter().map(|x| *x as u64).collect();
+            row_indexes.sort_unstable();
+            row_indexes.dedup();
+            if row_indexes
+                .iter()
+                .any(|x| assigned_slices.binary_search(x).is_err())
+            {
+                return Err(Status::new(Code::InvalidArgument, "invalid row indexes"));
+            }
+            row_indexes
+        };
+
+        let (tx, rx) = mpsc::channel(STREAM_SLICES_BUFFER);
+        let db = self.db.clone();
+        let chain_state = self.sealing_chain_state();
+        let signer_keys = self.signer_keys.clone();
+        tokio::spawn(
+            async move {
+                let sealer = RetrievalSealer {
+                    chain_state: chain_state.as_deref(),
+                    signer_keys: &signer_keys,
+                };
+                if let Err(status) =
+                    send_slice_chunks(&db, sealer, &req, storage_root, row_indexes, &tx).await
+                {
+                    let _ = tx.send(Err(status)).await;
+                }
+            }
+            .in_current_span(),
+        );
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    /// Sign a retrieval response of the slices of `content_digest`, `None` in storage-only mode.
+    async fn seal_retrieval(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        content_digest: [u8; 32],
+    ) -> Option<RetrievalEnvelope> {
+        let chain_state = self.sealing_chain_state();
+        RetrievalSealer {
+            chain_state: chain_state.as_deref(),
+            signer_keys: &self.signer_keys,
+        }
+        .seal(epoch, quorum_id, storage_root, content_digest)
+        .await
+    }
+
+    async fn get_sign_outcome_inner(
+        &self,
+        request: Request<SignOutcomeRequest>,
+    ) -> Result<Response<SignOutcomeReply>, Status> {
+        let req = request.into_inner();
+        let storage_root: [u8; 32] = req
+            .storage_root
+            .clone()
+            .try_into()
+            .map_err(|_| Status::new(Code::InvalidArgument, "storage root"))?;
+        let outcome = self
+            .db
+            .get_sign_outcome(req.epoch, req.quorum_id, storage_root)
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        let outcome = match outcome {
+            Some(SignOutcome::SIGNED) => signer::SignOutcome::Signed,
+            Some(SignOutcome::REJECTED) => signer::SignOutcome::Rejected,
+            None => signer::SignOutcome::Unknown,
+        };
+        Ok(Response::new(SignOutcomeReply {
+            outcome: outcome as i32,
+        }))
+    }
+
+    async fn get_blob_status_inner(
+        &self,
+        request: Request<BlobStatusRequest>,
+    ) -> Result<Response<BlobStatusReply>, Status> {
+        let req = request.into_inner();
+        let storage_root: [u8; 32] = req
+            .storage_root
+            .clone()
+            .try_into()
+            .map_err(|_| Status::new(Code::InvalidArgument, "storage root"))?;
+        let db = &self.db;
+        let internal = |e: anyhow::Error| Status::new(Code::Internal, e.to_string());
+        let status = match db
+            .get_blob_status(req.epoch, req.quorum_id, storage_root)
+            .await
+            .map_err(internal)?
+        {
+            Some(BlobStatus::UPLOADED) => signer::BlobStatus::Uploaded,
+            Some(BlobStatus::VERIFIED) => signer::BlobStatus::Verified,
+            None => signer::BlobStatus::Unspecified,
+        };
+        let outcome = match db
+            .get_sign_outcome(req.epoch, req.quorum_id, storage_root)
+            .await
+            .map_err(internal)?
+        {
+            Some(SignOutcome::SIGNED) => signer::SignOutcome::Signed,
+            Some(SignOutcome::REJECTED) => signer::SignOutcome::Rejected,
+            None => signer::SignOutcome::Unknown,
+        };
+        // a later rejection of the blob does not undo its signature
+        let signature = match outcome {
+            signer::SignOutcome::Signed => db
+                .get_signature(req.epoch, req.quorum_id, storage_root)
+                .await
+                .map_err(internal)?
+                .unwrap_or_default(),
+            _ => vec![],
+        };
+        Ok(Response::new(BlobStatusReply {
+            status: status as i32,
+            sign_outcome: outcome as i32,
+            signature,
+        }))
+    }
+
+    async fn get_assignment_inner(
+        &self,
+        request: Request<AssignmentRequest>,
+    ) -> Result<Response<AssignmentReply>, Status> {
+        let req = request.into_inner();
+        let db = &self.db;
+        let internal = |e: anyhow::Error| Status::new(Code::Internal, e.to_string());
+        let epoch = match req.epoch {
+            Some(epoch) => epoch,
+            None => db
+                .get_latest_epoch()
+                .await
+                .map_err(internal)?
+                .ok_or_else(|| Status::new(Code::NotFound, "no quorum known"))?,
+        };
+        let quorum_num = db
+            .get_quorum_num(epoch)
+            .await
+            .map_err(internal)?
+            .ok_or_else(|| {
+                Status::new(
+                    Code::NotFound,
+                    format!("quorum of epoch {:?} not found", epoch),
+                )
+            })?;
+        let mut quorums = vec![];
+        for quorum_id in 0..quorum_num {
+            if let Some(AssignedSlices(row_indexes)) = db
+                .get_assgined_slices(epoch, quorum_id)
+                .await
+                .map_err(internal)?
+            {
+                if !row_indexes.is_empty() {
+                    quorums.push(QuorumAssignment {
+                        quorum_id,
+                        row_indexes,
+                    });
+                }
+            }
+        }
+        Ok(Response::new(AssignmentReply {
+            epoch,
+            quorum_num,
+            quorums,
+        }))
+    }
+
+    async fn repair_slices_inner(
+        &self,
+        request: Request<RepairRequest>,
+    ) -> Result<Response<Slices>, Status> {
+        let encoder_params = self
+            .repair_encoder_params
+            .clone()
+            .ok_or_else(|| Status::new(Code::Unimplemented, "slice repair is not enabled"))?;
+        let remote_addr = request.remote_addr();
+        let req = request.into_inner();
+        let ts = Instant::now();
+
+        info!(?remote_addr, "Received repair request");
+        let storage_root: [u8; 32] = req
+            .storage_root
+            .clone()
+            .try_into()
+            .map_err(|_| Status::new(Code::InvalidArgument, "storage root"))?;
+        if req
+            .row_indexes
+            .iter()
+            .any(|index| *index as usize >= BLOB_ROW_ENCODED)
+        {
+            return Err(Status::new(Code::InvalidArgument, "invalid row indexes"));
+        }
+        let maybe_blob_status = self
+            .db
+            .get_blob_status(req.epoch, req.quorum_id, storage_root)
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
+        let blob_status = maybe_blob_status.ok_or(Status::new(Code::NotFound, "blob not found"))?;
+
+        // repair local slices only for blobs already verified on chain
+        let missing_assigned_slices = match blob_status {
+            BlobStatus::VERIFIED => self
+                .missing_assigned_slices(req.epoch, req.quorum_id, storage_root)
+                .await
+                .map_err(|e| Status::new(Code::Internal, e.to_string()))?,
+            BlobStatus::UPLOADED => None,
+        };
+
+        let row_indexes = req.row_indexes.clone();
+        let epoch = req.epoch;
+        let blob = req.blob;
+        let (requested, repaired) = tokio::task::spawn_blocking(move || {
+            let raw_data: RawData = blob[..].try_into().map_err(|e| {
+                Status::new(Code::InvalidArgument, format!("invalid blob: {:?}", e))
+            })?;
+            let raw_blob: RawBlob = raw_data.into();
+            let encoded_blob = EncodedBlob::build(&raw_blob, encoder_params.for_epoch(epoch).get());
+            if encoded_blob.get_file_root() != storage_root {
+                return Err(Status::new(
+                    Code::InvalidArgument,
+                    "blob does not match storage root",
+                ));
+            }
+            let requested: Vec<Vec<u8>> = row_indexes
+                .iter()
+                .map(|index| {
+                    let mut value = Vec::new();
+                    encoded_blob
+                        .get_row(*index as usize)
+                        .serialize_uncompressed(&mut value)
+                        .unwrap();
+                    value
+                })
+                .collect();
+            let repaired: Option<Vec<EncodedSlice>> = missing_assigned_slices.map(|indexes| {
+                indexes
+                    .iter()
+                    .map(|index| encoded_blob.get_row(*index as usize))
+                    .collect()
+            });
+            Ok((requested, repaired))
+        })
+        .await
+        .map_err(|e| Status::new(Code::Internal, format!("re-encoding error: {:?}", e)))??;
+
+        if let Some(slices) = repaired {
+            info!(
+                "repaired slices: epoch = {:?}, quorum = {:?}, storage_root = {:?}",
+                req.epoch,
+                req.quorum_id,
+                hex::encode(storage_root)
+            );
+            self.slice_store
+                .put_slice_with_retry(req.epoch, req.quorum_id, storage_root, slices)
+                .await?;
+        }
+
+        info!("responsed in {:?} ms", ts.elapsed().as_millis());
+        let envelope = self
+            .seal_retrieval(
+                req.epoch,
+                req.quorum_id,
+                storage_root,
+                encoded_slices_digest(&requested),
+            )
+            .await;
+        Ok(Response::new(Slices {
+            encoded_slice: requested,
+            envelope,
+            // the rows encoded from the blob carry their proofs
+            opening_proofs: vec![],
+        }))
+    }
+
+    /// All assigned slice indexes of the blob if any of them is missing or flagged corrupt locally.
+    async fn missing_assigned_slices(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    ) -> anyhow::Result<Option<Vec<u64>>> {
+        let db = &self.db;
+        let assigned_slices = match db.get_assgined_slices(epoch, quorum_id).await? {
+            Some(AssignedSlices(assigned_slices)) => assigned_slices,
+            None => return Ok(None),
+        };
+        for index in assigned_slices.iter() {
+            let corrupt = db
+                .is_corrupt_slice(&SliceIndex {
+                    epoch,
+                    quorum_id,
+                    storage_root,
+                    index: *index,
+                })
+                .await?;
+            if corrupt
+                || db
+                    .get_raw_slice(epoch, quorum_id, storage_root, *index as usize)
+                    .await?
+                    .is_none()
+            {
+                return Ok(Some(assigned_slices));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Signs retrieval responses with the latest signer key.
+struct RetrievalSealer<'a> {
+    chain_state: Option<&'a ChainState>,
+    signer_keys: &'a SignerKeys,
+}
+
+impl RetrievalSealer<'_> {
+    /// Sign a retrieval response of the slices of `content_digest`, `None` in storage-only mode.
+    async fn seal(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        content_digest: [u8; 32],
+    ) -> Option<RetrievalEnvelope> {
+        let chain_state = self.chain_state?;
+        let message = EnvelopeMessage {
+            epoch,
+            quorum_id,
+            storage_root,
+            content_digest,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        };
+        let signature = chain_state.sign_envelope(self.signer_keys.latest().await, &message);
+        let mut value = Vec::new();
+        signature.serialize_uncompressed(&mut value).unwrap();
+        Some(RetrievalEnvelope {
+            signer: chain_state.signer_address().as_bytes().to_vec(),
+            content_digest: content_digest.to_vec(),
+            timestamp: message.timestamp,
+            signature: value,
+        })
+    }
+}
+
+/// Send the chunks of the encoded rows in order, the last one carrying the envelope. It stops
+/// early if the client is gone.
+async fn send_slice_chunks(
+    db: &Storage,
+    sealer: RetrievalSealer<'_>,
+    req: &StreamSlicesRequest,
+    storage_root: [u8; 32],
+    row_indexes: Vec<u64>,
+    tx: &mpsc::Sender<Result<SliceChunk, Status>>,
+) -> Result<(), Status> {
+    let mut sent = vec![];
+    let mut pending: Option<SliceChunk> = None;
+    for row_index in row_indexes {
+        let slice = db
+            .get_raw_slice(req.epoch, req.quorum_id, storage_root, row_index as usize)
+            .await
+            .map_err(|e| Status::new(Code::Internal, e.to_string()))?
+            .ok_or_else(|| {
+                error!("slice is missing: epoch = {:?}, quorum = {:?}, storage_root = {:?}, row_index = {:?}", req.epoch, req.quorum_id, hex::encode(storage_root), row_index);
+                Status::new(Code::Internal, "slice is missing")
+            })?;
+        let chunk_size = match req.max_chunk_bytes {
+            0 => slice.len().max(1),
+            x => x as usize,
+        };
+        let chunks = slice.chunks(chunk_size).count();
+        for (i, data) in slice.chunks(chunk_size).enumerate() {
+            let chunk = SliceChunk {
+                row_index: row_index as u32,
+                offset: (i * chunk_size) as u64,
+                data: data.to_vec(),
+                last: i + 1 == chunks,
+                envelope: None,
+            };
+            // hold a chunk back, the last one of the stream carries the envelope
+            if let Some(chunk) = pending.replace(chunk) {
+                if tx.send(Ok(chunk)).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        sent.push(slice);
+    }
+    if let Some(mut chunk) = pending {
+        chunk.envelope = sealer
+            .seal(
+                req.epoch,
+                req.quorum_id,
+                storage_root,
+                encoded_slices_digest(&sent),
+            )
+            .await;
+        let _ = tx.send(Ok(chunk)).await;
+    }
+    Ok(())
 }
 
 #[tonic::async_trait]
@@ -234,10 +1301,35 @@ impl Signer for SignerService {
         &self,
         request: Request<BatchSignRequest>,
     ) -> Result<Response<BatchSignReply>, Status> {
-        self.on_incoming_batch_sign().await?;
-        let reply = self.batch_sign_inner(request).await;
-        self.on_complete_batch_sign().await;
-        reply
+        let span = info_span!("batch_sign", requests = request.get_ref().requests.len());
+        set_remote_parent(&span, request.metadata());
+        async {
+            let reservation = match &self.sign_quota {
+                Some(quota) => quota.reserve(&request).await?,
+                None => None,
+            };
+            let reply = match &self.batch_proxy {
+                Some(proxy) => proxy
+                    .batch_sign(self, request_id(&request), request.into_inner())
+                    .await
+                    .map(Response::new),
+                None => self.batch_sign_local(request).await,
+            };
+            match (&reply, &self.sign_quota, reservation) {
+                (Err(_), Some(quota), Some(reservation)) => quota.refund(reservation).await,
+                // requests failed in a partial success batch are not signed either
+                (Ok(reply), Some(quota), Some(reservation)) => {
+                    let failed = reservation.failed(reply.get_ref());
+                    if !failed.is_empty() {
+                        quota.refund(failed).await;
+                    }
+                }
+                _ => {}
+            }
+            reply
+        }
+        .instrument(span)
+        .await
     }
 
     async fn batch_retrieve(
@@ -251,16 +1343,183 @@ impl Signer for SignerService {
         &self,
         request: Request<Empty>,
     ) -> Result<Response<signer::StatusReply>, Status> {
-        let status = signer::StatusReply { status_code: 200 };
+        let inflight = self.admission.inflight();
+        let status = signer::StatusReply {
+            status_code: 200,
+            conditions: self
+                .health_conditions()
+                .into_iter()
+                .map(|condition| condition as i32)
+                .collect(),
+            storage_errors: Some(signer::StorageErrorCounts {
+                transient: self.slice_store.errors.transient(),
+                permanent: self.slice_store.errors.permanent(),
+            }),
+            ongoing_sign_requests: *self.ongoing_sign_request_cnt.read().await,
+            max_ongoing_sign_requests: self.max_ongoing_sign_request,
+            inflight_sign_slices: inflight.slices,
+            inflight_sign_bytes: inflight.bytes,
+        };
         Ok(Response::new(status))
     }
+
+    async fn repair_slices(
+        &self,
+        request: Request<RepairRequest>,
+    ) -> Result<Response<Slices>, Status> {
+        self.repair_slices_inner(request).await
+    }
+
+    async fn get_sign_outcome(
+        &self,
+        request: Request<SignOutcomeRequest>,
+    ) -> Result<Response<SignOutcomeReply>, Status> {
+        self.get_sign_outcome_inner(request).await
+    }
+
+    async fn get_blob_status(
+        &self,
+        request: Request<BlobStatusRequest>,
+    ) -> Result<Response<BlobStatusReply>, Status> {
+        self.get_blob_status_inner(request).await
+    }
+
+    async fn get_assignment(
+        &self,
+        request: Request<AssignmentRequest>,
+    ) -> Result<Response<AssignmentReply>, Status> {
+        self.get_assignment_inner(request).await
+    }
+
+    type StreamSlicesStream = SliceChunkStream;
+
+    async fn stream_slices(
+        &self,
+        request: Request<StreamSlicesRequest>,
+    ) -> Result<Response<SliceChunkStream>, Status> {
+        self.stream_slices_inner(request).await
+    }
+
+    async fn prove_custody(
+        &self,
+        request: Request<CustodyChallenge>,
+    ) -> Result<Response<CustodyProof>, Status> {
+        self.prove_custody_inner(request).await
+    }
+
+    type SubscribeBlobEventsStream = BlobEventStream;
+
+    async fn subscribe_blob_events(
+        &self,
+        request: Request<BlobEventFilter>,
+    ) -> Result<Response<BlobEventStream>, Status> {
+        info!(remote_addr = ?request.remote_addr(), "Received subscribe blob events request");
+        subscribe_blob_events(&self.events, request.into_inner()).map(Response::new)
+    }
+
+    async fn aggregate_signatures(
+        &self,
+        request: Request<AggregateSignaturesRequest>,
+    ) -> Result<Response<AggregateSignaturesReply>, Status> {
+        let request = request.into_inner();
+        info!(
+            epoch = request.epoch,
+            quorum_id = request.quorum_id,
+            signatures = request.signatures.len(),
+            "Received aggregate signatures request"
+        );
+        tokio::task::spawn_blocking(move || aggregate_signatures(&request))
+            .await
+            .map_err(|e| Status::new(Code::Internal, format!("{:?}", e)))?
+            .map(Response::new)
+    }
+
+    async fn get_proof_of_possession(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<ProofOfPossession>, Status> {
+        let chain_state = self.signing_chain_state()?;
+        let key = self.signer_keys.latest().await;
+        let (signer_address, chain_id) = (chain_state.signer_address(), chain_state.chain_id());
+        let mut reply = ProofOfPossession {
+            signer_address: signer_address.as_bytes().to_vec(),
+            chain_id,
+            ..Default::default()
+        };
+        let serialize_error = |e| Status::new(Code::Internal, format!("{:?}", e));
+        (G1Affine::generator() * key)
+            .into_affine()
+            .serialize_uncompressed(&mut reply.public_key_g1)
+            .map_err(serialize_error)?;
+        (G2Affine::generator() * key)
+            .into_affine()
+            .serialize_uncompressed(&mut reply.public_key_g2)
+            .map_err(serialize_error)?;
+        proof_of_possession(key, signer_address, chain_id)
+            .serialize_uncompressed(&mut reply.signature)
+            .map_err(serialize_error)?;
+        Ok(Response::new(reply))
+    }
+
+    async fn retrieve_stored_slices(
+        &self,
+        request: Request<RetrieveRequest>,
+    ) -> Result<Response<StoredSlices>, Status> {
+        self.retrieve_stored_slices_inner(request).await
+    }
+
+    async fn get_node_info(&self, request: Request<Empty>) -> Result<Response<NodeInfo>, Status> {
+        Ok(Response::new(NodeInfo {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            sign_options: self
+                .supported_sign_options()
+                .into_iter()
+                .map(String::from)
+                .collect(),
+            networks: vec![],
+            // a proxy accepts batches of any size
+            max_batch_requests: match self.batch_proxy {
+                Some(_) => None,
+                None => self.max_batch_sign_requests,
+            },
+            build: Some(build_info().into()),
+            protocol_versions: PROTOCOL_VERSIONS.to_vec(),
+            capabilities: self.capabilities().into_iter().map(String::from).collect(),
+        }))
+    }
+}
+
+/// Add the outcome of a request to the reply of a partial success batch.
+pub(crate) fn push_sign_result(reply: &mut BatchSignReply, result: Result<Vec<u8>, Status>) {
+    let result = match result {
+        Ok(signature) => {
+            reply.signatures.push(signature.clone());
+            sign_result::Result::Signature(signature)
+        }
+        Err(status) => {
+            reply.signatures.push(vec![]);
+            sign_result::Result::Error(SignError {
+                code: status.code() as i32,
+                message: status.message().to_string(),
+            })
+        }
+    };
+    reply.results.push(SignResult {
+        result: Some(result),
+    });
 }
 
+#[derive(Debug)]
 pub enum VerificationError {
     Internal(anyhow::Error),
     SliceMismatch,
     IncorrectSlice(zg_encoder::VerifierError),
     DeferredVerifyFail,
+    /// The signer has no slice of the quorum in the epoch.
+    NotAssigned {
+        epoch: u64,
+        quorum_id: u64,
+    },
 }
 
 impl From<&'static str> for VerificationError {
@@ -282,6 +1541,126 @@ impl From<zg_encoder::VerifierError> for VerificationError {
 }
 
 impl SignerService {
+    /// Unhealthy conditions of the node, reported by `GetStatus` and the http probes.
+    pub(crate) fn health_conditions(&self) -> Vec<signer::HealthCondition> {
+        let mut conditions = vec![];
+        if self.params_mismatch.suspected() {
+            conditions.push(signer::HealthCondition::EncoderParamsMismatch);
+        }
+        if self.archive {
+            conditions.push(signer::HealthCondition::Archive);
+        }
+        if self.maintenance.state().is_some() {
+            conditions.push(signer::HealthCondition::Maintenance);
+        }
+        if self
+            .load_shedder
+            .as_ref()
+            .map_or(false, |x| x.saturation().is_some())
+        {
+            conditions.push(signer::HealthCondition::Overloaded);
+        }
+        match &self.chain_state {
+            Some(chain_state) if !chain_state.sync_progress().is_synced() => {
+                conditions.push(signer::HealthCondition::Syncing);
+            }
+            Some(_) => {}
+            None => conditions.push(signer::HealthCondition::StorageOnly),
+        }
+        if self.slice_store.errors.permanent() > 0 {
+            conditions.push(signer::HealthCondition::StorageErrors);
+        }
+        conditions
+    }
+
+    /// Read the sync checkpoint, failing if the database cannot be read.
+    pub(crate) async fn check_db(&self) -> anyhow::Result<()> {
+        self.db.get_checkpoint().await?;
+        Ok(())
+    }
+
+    /// Track verification failures and add the params mismatch hypothesis to the error detail.
+    fn check_params_mismatch(&self, storage_root: [u8; 32], status: Status) -> Status {
+        if let DetectorUpdate::Suspected(failed_blobs) =
+            self.params_mismatch.on_verify_failed(storage_root)
+        {
+            error!(
+                target: "alert",
+                failed_blobs,
+                "{} blobs in a row failed verification, encoder params likely mismatch the clients', check encoder_params_dir and the encoder version",
+                failed_blobs
+            );
+            self.events
+                .publish(NodeEvent::VerificationFailureSpike { failed_blobs });
+        }
+        if !self.params_mismatch.suspected() {
+            return status;
+        }
+        Status::new(
+            status.code(),
+            format!(
+                "{}; {} blobs in a row failed verification on this node, its encoder params likely mismatch the client's",
+                status.message(),
+                self.params_mismatch.consecutive_failed_blobs()
+            ),
+        )
+    }
+
+    async fn record_sign_outcome(
+        &self,
+        req: &SignRequest,
+        storage_root: [u8; 32],
+        rejection: Option<&str>,
+    ) {
+        let outcome = match rejection {
+            Some(_) => SignOutcome::REJECTED,
+            None => SignOutcome::SIGNED,
+        };
+        if let Err(e) = self
+            .db
+            .put_sign_outcome(req.epoch, req.quorum_id, storage_root, outcome)
+            .await
+        {
+            warn!("cannot record sign outcome: {:?}", e);
+        }
+        self.events.publish(match rejection {
+            Some(reason) => NodeEvent::BlobRejected {
+                epoch: req.epoch,
+                quorum_id: req.quorum_id,
+                storage_root,
+                reason: reason.to_string(),
+            },
+            None => NodeEvent::BlobSigned {
+                epoch: req.epoch,
+                quorum_id: req.quorum_id,
+                storage_root,
+            },
+        });
+    }
+
+    /// Dump a sign request for `replay-request`, along with the error returned if it failed.
+    async fn dump_request(&self, req: &SignRequest, error: Option<&str>) {
+        let dir = match &self.request_dump_dir {
+            Some(dir) => dir,
+            None => return,
+        };
+        let assigned_slices = match self.db.get_assgined_slices(req.epoch, req.quorum_id).await {
+            Ok(Some(AssignedSlices(assigned_slices))) => assigned_slices,
+            _ => vec![],
+        };
+        let failed = error.is_some();
+        match dump_sign_request(
+            dir,
+            req,
+            assigned_slices,
+            error.unwrap_or_default().to_string(),
+        ) {
+            Ok(path) if failed => info!("failed request dumped to {:?}", path),
+            Ok(path) => info!("request transcript recorded to {:?}", path),
+            Err(e) => warn!("cannot dump request: {:?}", e),
+        }
+    }
+
     async fn check_blob_status(
         &self,
         req: &SignRequest,
@@ -289,8 +1668,6 @@ impl SignerService {
     ) -> Result<(), Status> {
         let maybe_blob_status = self
             .db
-            .read()
-            .await
             .get_blob_status(req.epoch, req.quorum_id, storage_root)
             .await
             .map_err(|e| Status::new(Code::Internal, e.to_string()))?;
@@ -301,22 +1678,35 @@ impl SignerService {
         }
     }
 
-    fn decode_root(req: &SignRequest) -> Result<([u8; 32], G1Projective), Status> {
-        let storage_root: [u8; 32] = req
-            .storage_root
-            .clone()
+    pub(crate) fn decode_root(req: &SignRequest) -> Result<([u8; 32], G1Projective), Status> {
+        Self::decode_blob(&req.storage_root, &req.erasure_commitment)
+    }
+
+    /// Storage root and erasure commitment of a blob, the commitment compressed or not.
+    pub(crate) fn decode_blob(
+        storage_root: &[u8],
+        erasure_commitment: &[u8],
+    ) -> Result<([u8; 32], G1Projective), Status> {
+        let storage_root: [u8; 32] = storage_root
             .try_into()
             .map_err(|_| Status::new(Code::InvalidArgument, "storage root"))?;
 
-        let (x, y) =
-            <(Fq, Fq)>::deserialize_uncompressed(&*req.erasure_commitment).map_err(|e| {
-                Status::new(
-                    Code::InvalidArgument,
-                    format!("failed to deserialize erasure commitment: {:?}", e),
-                )
-            })?;
-
-        let maybe_commitment = G1Affine::new_unchecked(x, y);
+        let deserialize_error = |e| {
+            Status::new(
+                Code::InvalidArgument,
+                format!("failed to deserialize erasure commitment: {:?}", e),
+            )
+        };
+        // the lengths of the two forms differ, the form is detected by length. The point is
+        // checked below in both forms.
+        let maybe_commitment = if erasure_commitment.len() == G1_COMPRESSED_SIZE {
+            G1Affine::deserialize_with_mode(erasure_commitment, Compress::Yes, Validate::No)
+                .map_err(deserialize_error)?
+        } else {
+            let (x, y) = <(Fq, Fq)>::deserialize_uncompressed(erasure_commitment)
+                .map_err(deserialize_error)?;
+            G1Affine::new_unchecked(x, y)
+        };
         if !maybe_commitment.is_on_curve()
             || !maybe_commitment.is_in_correct_subgroup_assuming_on_curve()
         {
@@ -329,18 +1719,23 @@ impl SignerService {
         Ok((storage_root, maybe_commitment.into_group()))
     }
 
-    fn decode_encoded_slices(req: &SignRequest) -> Result<Vec<EncodedSlice>, Status> {
+    pub(crate) fn decode_encoded_slices(req: &SignRequest) -> Result<Vec<EncodedSlice>, Status> {
         let ts = Instant::now();
+        let compress = match req.encoding() {
+            SliceEncoding::Uncompressed => Compress::No,
+            SliceEncoding::Compressed => Compress::Yes,
+        };
         let encoded_slices: Vec<EncodedSlice> = req
             .encoded_slice
             .par_iter()
             .map(|data| {
-                EncodedSlice::deserialize_uncompressed(&*data.to_vec()).map_err(|e| {
-                    Status::new(
-                        Code::InvalidArgument,
-                        format!("failed to deserialize slice: {:?}", e),
-                    )
-                })
+                EncodedSlice::deserialize_with_mode(&*data.to_vec(), compress, Validate::Yes)
+                    .map_err(|e| {
+                        Status::new(
+                            Code::InvalidArgument,
+                            format!("failed to deserialize slice: {:?}", e),
+                        )
+                    })
             })
             .collect::<Result<Vec<EncodedSlice>, Status>>()?;
         info!(
@@ -358,27 +1753,33 @@ impl SignerService {
         storage_root: [u8; 32],
         erasure_commitment: G1Projective,
         encoded_slices: &Vec<EncodedSlice>,
+        metrics: &LabeledMetrics,
     ) -> Result<(), VerificationError> {
         // in case quorum info is missing
-        let quorum_num = self.chain_state.fetch_quorum_if_missing(epoch).await?;
-        // check quorum_id
+        let quorum_num = self
+            .chain_state
+            .as_ref()
+            .ok_or("signing is disabled in storage-only mode")?
+            .fetch_quorum_if_missing(epoch)
+            .await?;
+        // quorums not formed yet, or not including the signer
         if quorum_num <= quorum_id {
-            return Err("quorum_id out of bound".into());
+            return Err(VerificationError::NotAssigned { epoch, quorum_id });
         }
         // check assigned slices
-        let maybe_assigned_slices = self
-            .db
-            .read()
-            .await
-            .get_assgined_slices(epoch, quorum_id)
-            .await?;
+        let maybe_assigned_slices = self.db.get_assgined_slices(epoch, quorum_id).await?;
         match maybe_assigned_slices {
+            Some(AssignedSlices(assigned_slices)) if assigned_slices.is_empty() => {
+                return Err(VerificationError::NotAssigned { epoch, quorum_id });
+            }
             Some(AssignedSlices(assigned_slices)) => {
                 self.verify_assigned_slices(
+                    self.encoder_params.for_epoch(epoch).get(),
                     storage_root,
                     erasure_commitment,
                     assigned_slices,
                     encoded_slices,
+                    metrics,
                 )?;
             }
             None => {
@@ -388,18 +1789,22 @@ impl SignerService {
         Ok(())
     }
 
-    fn verify_assigned_slices(
+    pub(crate) fn verify_assigned_slices(
         &self,
+        encoder_params: &ZgSignerParams,
         storage_root: [u8; 32],
         erasure_commitment: G1Projective,
         assigned_slices: Vec<u64>,
         encoded_slices: &Vec<EncodedSlice>,
+        metrics: &LabeledMetrics,
     ) -> Result<(), VerificationError> {
         if assigned_slices.len() != encoded_slices.len() {
             return Err(VerificationError::SliceMismatch);
         }
         let ts = Instant::now();
 
+        // verification time summed over the pool threads
+        let busy_ns = AtomicU64::new(0);
         let deferred_verifier = DeferredVerifier::new();
         let res: Result<(), _> = assigned_slices
             .par_iter()
@@ -409,17 +1814,28 @@ impl SignerService {
                 if *expected_index != slice.index as u64 {
                     Err(VerificationError::SliceMismatch)
                 } else {
-                    Ok(slice.verify(
-                        &self.encoder_params,
+                    let ts = Instant::now();
+                    let res = slice.verify(
+                        encoder_params,
                         &erasure_commitment,
                         &storage_root,
                         Some(verifier),
-                    )?)
+                    );
+                    let elapsed = ts.elapsed();
+                    metrics.slice_verify.record(elapsed);
+                    busy_ns.fetch_add(elapsed.as_nanos() as u64, Ordering::Relaxed);
+                    Ok(res?)
                 }
             })
             .collect();
 
         let deferred_pass = deferred_verifier.fast_check();
+        self.verification_metrics.on_blob_verified(
+            metrics,
+            assigned_slices.len() as u64,
+            ts.elapsed(),
+            Duration::from_nanos(busy_ns.into_inner()),
+        );
 
         info!(
             "used {:?} ms to verify {:?} slices.",
@@ -462,6 +1878,11 @@ pub fn blob_verified_hash(
     map_to_g1(hash.to_vec())
 }
 
+/// BLS signature of the message of a verified blob.
+pub(crate) fn sign_message(hash: G1Affine, signer_bls_private_key: Fr) -> G1Affine {
+    (hash * signer_bls_private_key).into_affine()
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -474,6 +1895,24 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn push_sign_result_test() {
+        let mut reply = BatchSignReply::default();
+        push_sign_result(&mut reply, Ok(vec![1; 64]));
+        push_sign_result(
+            &mut reply,
+            Err(Status::new(Code::InvalidArgument, "verification failed")),
+        );
+        assert_eq!(reply.signatures, vec![vec![1; 64], vec![]]);
+        assert_eq!(
+            reply.results[1].result,
+            Some(sign_result::Result::Error(SignError {
+                code: Code::InvalidArgument as i32,
+                message: "verification failed".into(),
+            }))
+        );
+    }
+
     #[test]
     fn blob_verified_hash_test() {
         let a = g1::G1Affine::generator() * Fr::from(1);
@@ -519,4 +1958,71 @@ mod tests {
             )
         );
     }
+    #[test]
+    fn decode_root_test() {
+        let commitment = (g1::G1Affine::generator() * Fr::from(7)).into_affine();
+        let (x, y) = commitment.xy().unwrap();
+        let mut uncompressed = vec![];
+        (*x, *y).serialize_uncompressed(&mut uncompressed).unwrap();
+        let mut compressed = vec![];
+        commitment.serialize_compressed(&mut compressed).unwrap();
+        assert_eq!(compressed.len(), G1_COMPRESSED_SIZE);
+
+        for erasure_commitment in [uncompressed, compressed] {
+            let req = SignRequest {
+                storage_root: vec![1; 32],
+                erasure_commitment,
+                ..Default::default()
+            };
+            let (storage_root, decoded) = SignerService::decode_root(&req).unwrap();
+            assert_eq!(storage_root, [1; 32]);
+            assert_eq!(decoded.into_affine(), commitment);
+        }
+
+        let req = SignRequest {
+            storage_root: vec![1; 32],
+            erasure_commitment: vec![0xff; G1_COMPRESSED_SIZE],
+            ..Default::default()
+        };
+        assert!(SignerService::decode_root(&req).is_err());
+    }
+
+    #[test]
+    fn sign_request_key_test() {
+        let commitment = (g1::G1Affine::generator() * Fr::from(7)).into_affine();
+        let (x, y) = commitment.xy().unwrap();
+        let mut uncompressed = vec![];
+        (*x, *y).serialize_uncompressed(&mut uncompressed).unwrap();
+        let mut compressed = vec![];
+        commitment.serialize_compressed(&mut compressed).unwrap();
+
+        let req = SignRequest {
+            storage_root: vec![1; 32],
+            erasure_commitment: uncompressed,
+            encoded_slice: vec![vec![2; 10]],
+            ..Default::default()
+        };
+        let key = sign_request_key(&req);
+        assert_eq!(
+            sign_request_key(&SignRequest {
+                erasure_commitment: compressed,
+                ..req.clone()
+            }),
+            key
+        );
+        assert_ne!(
+            sign_request_key(&SignRequest {
+                encoded_slice: vec![vec![3; 10]],
+                ..req.clone()
+            }),
+            key
+        );
+        assert_ne!(
+            sign_request_key(&SignRequest {
+                options: HashMap::from([(RECORD_TRANSCRIPT.to_string(), "true".to_string())]),
+                ..req
+            }),
+            key
+        );
+    }
 }
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use tonic::{Code, Status};
+
+/// Dump the request for `replay-request` even if it is signed, needs `request_dump_dir`.
+pub const RECORD_TRANSCRIPT: &str = "record_transcript";
+
+/// Experimental features requested by the client for one sign request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SignOptions {
+    pub record_transcript: bool,
+}
+
+impl SignOptions {
+    /// Parse the `options` of a sign request. Options not in `supported` are rejected rather than
+    /// ignored, so a client never assumes a feature the node did not apply.
+    pub fn parse(options: &HashMap<String, String>, supported: &[&str]) -> Result<Self, Status> {
+        let mut parsed = Self::default();
+        for (key, value) in options {
+            if !supported.contains(&key.as_str()) {
+                return Err(Status::new(
+                    Code::InvalidArgument,
+                    format!("unsupported sign option: {}", key),
+                ));
+            }
+            match key.as_str() {
+                RECORD_TRANSCRIPT => parsed.record_transcript = parse_bool(key, value)?,
+                _ => unreachable!("supported options are all handled"),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool, Status> {
+    value.parse().map_err(|_| {
+        Status::new(
+            Code::InvalidArgument,
+            format!("invalid value of sign option {}: {:?}", key, value),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sign_options_test() {
+        let options = HashMap::from([(RECORD_TRANSCRIPT.to_string(), "true".to_string())]);
+        assert_eq!(
+            SignOptions::parse(&options, &[RECORD_TRANSCRIPT]).unwrap(),
+            SignOptions {
+                record_transcript: true
+            }
+        );
+        assert!(SignOptions::parse(&options, &[]).is_err());
+
+        let options = HashMap::from([(RECORD_TRANSCRIPT.to_string(), "yes".to_string())]);
+        assert!(SignOptions::parse(&options, &[RECORD_TRANSCRIPT]).is_err());
+        assert_eq!(
+            SignOptions::parse(&HashMap::new(), &[]).unwrap(),
+            SignOptions::default()
+        );
+    }
+}
@@ -0,0 +1,344 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+};
+
+use storage::{
+    sign_quota_db::{QuotaUsage, SignQuotaDB},
+    Storage,
+};
+use tokio::sync::Mutex;
+use tonic::{metadata::MetadataMap, Code, Request, Status};
+
+use crate::service::sign_request_key;
+use crate::signer::{sign_result, BatchSignReply, BatchSignRequest};
+
+/// Request metadata authenticating a sign client, as `Bearer <token>`.
+pub const AUTHORIZATION_METADATA_KEY: &str = "authorization";
+
+/// A client of the sign service, authenticated by its token.
+#[derive(Debug, Clone)]
+pub struct SignClient {
+    pub name: String,
+    pub token: String,
+    /// Blobs signed for the client in an epoch, unlimited if `None`.
+    pub max_blobs_per_epoch: Option<u64>,
+    /// Bytes of encoded slices signed for the client in an epoch, unlimited if `None`.
+    pub max_bytes_per_epoch: Option<u64>,
+    /// Requests of higher priority clients are verified first when the verification queue is
+    /// full.
+    pub priority: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SignQuotaConfig {
+    pub clients: Vec<SignClient>,
+    /// Reject sign requests without the token of a client, otherwise they are not limited.
+    pub require_client: bool,
+}
+
+/// Usage reserved for a batch in the quota of its client, given back if the batch is not signed.
+pub(crate) struct QuotaReservation {
+    client: String,
+    usage: BTreeMap<u64, QuotaUsage>,
+    /// Epoch and usage of each request of the batch, in request order. Duplicates of an earlier
+    /// request are `None`, they are signed once and charged once.
+    requests: Vec<Option<(u64, QuotaUsage)>>,
+}
+
+impl QuotaReservation {
+    /// Part of the reservation of the requests that failed in the reply of a partial success
+    /// batch, to give back. It is empty for other batches, their reply has no results.
+    pub fn failed(&self, reply: &BatchSignReply) -> Self {
+        let mut usage: BTreeMap<u64, QuotaUsage> = BTreeMap::new();
+        for (request, result) in self.requests.iter().zip(reply.results.iter()) {
+            let (epoch, request) = match request {
+                Some(request) => request,
+                None => continue,
+            };
+            if matches!(result.result, Some(sign_result::Result::Error(_))) {
+                let total = usage.entry(*epoch).or_default();
+                *total = *total + *request;
+            }
+        }
+        Self {
+            client: self.client.clone(),
+            usage,
+            requests: vec![],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.usage.is_empty()
+    }
+}
+
+/// Quotas of the sign clients per epoch, the usage is persisted so restarts do not reset it.
+pub(crate) struct SignQuota {
+    config: SignQuotaConfig,
+    db: Arc<Storage>,
+    /// Serializes the updates of the usage.
+    lock: Mutex<()>,
+}
+
+fn internal(e: anyhow::Error) -> Status {
+    Status::new(Code::Internal, format!("sign quota error: {:?}", e))
+}
+
+impl SignQuota {
+    pub fn new(config: SignQuotaConfig, db: Arc<Storage>) -> Self {
+        Self {
+            config,
+            db,
+            lock: Mutex::new(()),
+        }
+    }
+
+    fn client(&self, metadata: &MetadataMap) -> Result<Option<&SignClient>, Status> {
+        let token = metadata
+            .get(AUTHORIZATION_METADATA_KEY)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match token {
+            Some(token) => self
+                .config
+                .clients
+                .iter()
+                .find(|client| client.token == token)
+                .map(Some)
+                .ok_or_else(|| Status::new(Code::Unauthenticated, "unknown client token")),
+            None if self.config.require_client => Err(Status::new(
+                Code::Unauthenticated,
+                "a client token is required",
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Name of the client authenticated by the token of a request, if any.
+    pub fn client_name(&self, metadata: &MetadataMap) -> Option<&str> {
+        match self.client(metadata) {
+            Ok(Some(client)) => Some(&client.name),
+            _ => None,
+        }
+    }
+
+    /// Priority of the client authenticated by the token of a request, 0 if none.
+    pub fn client_priority(&self, metadata: &MetadataMap) -> u64 {
+        match self.client(metadata) {
+            Ok(Some(client)) => client.priority,
+            _ => 0,
+        }
+    }
+
+    /// Reserve the blobs and bytes of a batch in the quota of its client, for the epoch of each
+    /// unique request. Batches of unauthenticated clients are not limited, `None` is returned.
+    pub async fn reserve(
+        &self,
+        request: &Request<BatchSignRequest>,
+    ) -> Result<Option<QuotaReservation>, Status> {
+        let client = match self.client(request.metadata())? {
+            Some(client) => client,
+            None => return Ok(None),
+        };
+        let mut batch: BTreeMap<u64, QuotaUsage> = BTreeMap::new();
+        let mut requests = vec![];
+        let mut unique = HashSet::new();
+        for req in request.get_ref().requests.iter() {
+            if !unique.insert(sign_request_key(req)) {
+                requests.push(None);
+                continue;
+            }
+            let usage = QuotaUsage {
+                blobs: 1,
+                bytes: req
+                    .encoded_slice
+                    .iter()
+                    .map(|x| x.len() as u64)
+                    .sum::<u64>(),
+            };
+            let total = batch.entry(req.epoch).or_default();
+            *total = *total + usage;
+            requests.push(Some((req.epoch, usage)));
+        }
+
+        let _guard = self.lock.lock().await;
+        let mut updated = vec![];
+        for (epoch, usage) in batch.iter() {
+            let mut clients = self
+                .db
+                .get_sign_quota_usage(*epoch)
+                .await
+                .map_err(internal)?;
+            let total = clients.get(&client.name).copied().unwrap_or_default() + *usage;
+            for (used, max, unit) in [
+                (total.blobs, client.max_blobs_per_epoch, "blobs"),
+                (total.bytes, client.max_bytes_per_epoch, "bytes"),
+            ] {
+                if let Some(max) = max.filter(|max| used > *max) {
+                    return Err(Status::new(
+                        Code::ResourceExhausted,
+                        format!(
+                            "client {} exceeds its quota of {} {} in epoch {}",
+                            client.name, max, unit, epoch
+                        ),
+                    ));
+                }
+            }
+            clients.insert(client.name.clone(), total);
+            updated.push((*epoch, clients));
+        }
+        for (epoch, clients) in updated {
+            self.db
+                .put_sign_quota_usage(epoch, &clients)
+                .await
+                .map_err(internal)?;
+        }
+        Ok(Some(QuotaReservation {
+            client: client.name.clone(),
+            usage: batch,
+            requests,
+        }))
+    }
+
+    /// Give back the reservation of a batch that was not signed.
+    pub async fn refund(&self, reservation: QuotaReservation) {
+        let _guard = self.lock.lock().await;
+        for (epoch, usage) in reservation.usage {
+            let res = async {
+                let mut clients = self.db.get_sign_quota_usage(epoch).await?;
+                if let Some(used) = clients.get_mut(&reservation.client) {
+                    *used = used.saturating_sub(usage);
+                }
+                self.db.put_sign_quota_usage(epoch, &clients).await
+            }
+            .await;
+            if let Err(e) = res {
+                warn!(
+                    client = reservation.client,
+                    epoch, "cannot refund the sign quota: {:?}", e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::signer::{SignError, SignRequest, SignResult};
+
+    use super::*;
+
+    fn batch(token: Option<&str>, epochs: &[u64]) -> Request<BatchSignRequest> {
+        let mut request = Request::new(BatchSignRequest {
+            requests: epochs
+                .iter()
+                .map(|epoch| SignRequest {
+                    epoch: *epoch,
+                    encoded_slice: vec![vec![0; 10]],
+                    ..Default::default()
+                })
+                .collect(),
+            partial_success: false,
+        });
+        if let Some(token) = token {
+            request.metadata_mut().insert(
+                AUTHORIZATION_METADATA_KEY,
+                format!("Bearer {}", token).parse().unwrap(),
+            );
+        }
+        request
+    }
+
+    #[tokio::test]
+    async fn reserve_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Storage::new(dir.path()).unwrap());
+        let quota = SignQuota::new(
+            SignQuotaConfig {
+                clients: vec![SignClient {
+                    name: "batcher".into(),
+                    token: "secret".into(),
+                    max_blobs_per_epoch: Some(2),
+                    max_bytes_per_epoch: None,
+                    priority: 0,
+                }],
+                require_client: false,
+            },
+            db.clone(),
+        );
+
+        assert!(quota
+            .reserve(&batch(None, &[1, 1, 1]))
+            .await
+            .unwrap()
+            .is_none());
+        let err = quota
+            .reserve(&batch(Some("other"), &[1]))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), Code::Unauthenticated);
+
+        quota
+            .reserve(&batch(Some("secret"), &[1, 2]))
+            .await
+            .unwrap();
+        let reservation = quota.reserve(&batch(Some("secret"), &[1])).await.unwrap();
+        let err = quota
+            .reserve(&batch(Some("secret"), &[1]))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.code(), Code::ResourceExhausted);
+
+        quota.refund(reservation.unwrap()).await;
+        let usage = db.get_sign_quota_usage(1).await.unwrap();
+        assert_eq!(
+            usage["batcher"],
+            QuotaUsage {
+                blobs: 1,
+                bytes: 10
+            }
+        );
+        quota.reserve(&batch(Some("secret"), &[1])).await.unwrap();
+
+        // failed requests of a partial success batch are given back
+        let reservation = quota
+            .reserve(&batch(Some("secret"), &[3, 4]))
+            .await
+            .unwrap()
+            .unwrap();
+        let reply = BatchSignReply {
+            signatures: vec![vec![1; 64], vec![]],
+            results: vec![
+                SignResult {
+                    result: Some(sign_result::Result::Signature(vec![1; 64])),
+                },
+                SignResult {
+                    result: Some(sign_result::Result::Error(SignError::default())),
+                },
+            ],
+        };
+        assert!(reservation.failed(&BatchSignReply::default()).is_empty());
+        quota.refund(reservation.failed(&reply)).await;
+        assert_eq!(
+            db.get_sign_quota_usage(3).await.unwrap()["batcher"].blobs,
+            1
+        );
+        assert_eq!(
+            db.get_sign_quota_usage(4).await.unwrap()["batcher"],
+            QuotaUsage::default()
+        );
+
+        // a request repeated in a batch is charged once
+        quota
+            .reserve(&batch(Some("secret"), &[5, 5]))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.get_sign_quota_usage(5).await.unwrap()["batcher"].blobs,
+            1
+        );
+    }
+}
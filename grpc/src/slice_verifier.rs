@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use ark_bn254::G1Projective;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use zg_encoder::{EncodedSlice, ZgSignerParams};
+
+use crate::params::{ParamsLoadMode, ParamsSchedule, ParamsVersion};
+
+/// Verifies slices that come without a sign request, recovered from peers or read back from the
+/// database, against the storage root and the erasure commitment of their blob. The params are
+/// shared with the signer services.
+#[derive(Clone)]
+pub struct SliceVerifier {
+    params: Arc<ParamsSchedule<ZgSignerParams>>,
+}
+
+impl SliceVerifier {
+    pub fn new(
+        encoder_params_dir: String,
+        params_versions: &[ParamsVersion],
+        params_load_mode: ParamsLoadMode,
+    ) -> Self {
+        Self {
+            params: Arc::new(ParamsSchedule::new(
+                "verify",
+                encoder_params_dir,
+                params_versions,
+                params_load_mode,
+                |dir| ZgSignerParams::from_dir_mont(dir),
+            )),
+        }
+    }
+
+    pub(crate) fn params(&self) -> Arc<ParamsSchedule<ZgSignerParams>> {
+        self.params.clone()
+    }
+
+    /// Verify the slices of a blob of `epoch` on the verification threads, every slice has its
+    /// own result so the valid ones can be kept.
+    pub async fn verify(
+        &self,
+        epoch: u64,
+        storage_root: [u8; 32],
+        erasure_commitment: G1Projective,
+        slices: Vec<EncodedSlice>,
+    ) -> Result<Vec<Result<EncodedSlice>>> {
+        let params = self.params.clone();
+        tokio::task::spawn_blocking(move || {
+            let params = params.for_epoch(epoch).get();
+            slices
+                .into_par_iter()
+                .map(|slice| {
+                    slice
+                        .verify(params, &erasure_commitment, &storage_root, None)
+                        .map_err(|e| {
+                            anyhow!("slice {} fails verification: {:?}", slice.index, e)
+                        })?;
+                    Ok(slice)
+                })
+                .collect()
+        })
+        .await
+        .map_err(|e| anyhow!("slice verification task failed: {:?}", e))
+    }
+}
@@ -0,0 +1,237 @@
+use std::sync::Arc;
+
+use events::{EventBus, NodeEvent};
+use storage::{
+    error::{StorageError, StorageErrorKind},
+    opening_proof_db::OpeningProofDB,
+    slice_db::SliceDB,
+    Storage,
+};
+use tokio::sync::{mpsc, oneshot};
+use tonic::{Code, Status};
+use zg_encoder::EncodedSlice;
+
+use crate::{health::StorageErrorCounters, PutSliceRetryConfig};
+
+/// When a signature is returned relative to the write of the signed slices.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AckMode {
+    /// Once the slices are stored.
+    #[default]
+    Durable,
+    /// Once the slices are verified, they are queued to a background writer of `queue_size`
+    /// blobs. Slices acknowledged but not written yet are lost if the node crashes.
+    Async { queue_size: usize },
+}
+
+/// Slices of a signed blob to store, with their opening proofs if they are kept.
+pub(crate) struct SliceWrite {
+    pub epoch: u64,
+    pub quorum_id: u64,
+    pub storage_root: [u8; 32],
+    pub slices: Vec<EncodedSlice>,
+    pub opening_proofs: Vec<(u64, Vec<u8>)>,
+}
+
+/// Storage of the signed slices and the counters of its failures.
+#[derive(Clone)]
+pub(crate) struct SliceStore {
+    pub db: Arc<Storage>,
+    pub retry: PutSliceRetryConfig,
+    pub errors: Arc<StorageErrorCounters>,
+    pub events: EventBus,
+}
+
+impl SliceStore {
+    /// Store slices, retrying transient storage errors with backoff before failing the request.
+    pub async fn put_slice_with_retry(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        slices: Vec<EncodedSlice>,
+    ) -> Result<(), Status> {
+        let rows = slices.len() as u64;
+        // serialize once, the attempts write the same records
+        let slices = match self
+            .db
+            .serialize_slices(epoch, quorum_id, storage_root, slices)
+        {
+            Ok(slices) => slices,
+            Err(e) => {
+                let kind = StorageError::kind_of(&e);
+                self.errors.on_error(kind);
+                error!(target: "alert", ?kind, "serialize slices error: {:?}", e);
+                return Err(Status::new(
+                    Code::Internal,
+                    format!("put slice error: {:?}", e),
+                ));
+            }
+        };
+        let mut backoff = self.retry.backoff;
+        let mut retries = 0;
+        loop {
+            let e = match self.db.put_serialized_slices(&slices).await {
+                Ok(()) => {
+                    self.events.publish(NodeEvent::SlicesStored {
+                        epoch,
+                        quorum_id,
+                        storage_root,
+                        rows,
+                    });
+                    return Ok(());
+                }
+                Err(e) => e,
+            };
+            let kind = StorageError::kind_of(&e);
+            self.errors.on_error(kind);
+            self.events.publish(NodeEvent::SliceWriteFailed {
+                epoch,
+                quorum_id,
+                storage_root,
+            });
+            if kind == StorageErrorKind::Permanent || retries >= self.retry.max_retries {
+                error!(target: "alert", ?kind, retries, "put slice error: {:?}", e);
+                return Err(Status::new(
+                    Code::Internal,
+                    format!("put slice error: {:?}", e),
+                ));
+            }
+            retries += 1;
+            warn!(
+                retries,
+                ?backoff,
+                "transient put slice error, retrying: {:?}",
+                e
+            );
+            tokio::time::sleep(backoff).await;
+            backoff *= 2;
+        }
+    }
+
+    /// Store the slices of a signed blob then their opening proofs, a failure to store the proofs
+    /// only loses them.
+    pub async fn write(&self, write: SliceWrite) -> Result<(), Status> {
+        self.put_slice_with_retry(
+            write.epoch,
+            write.quorum_id,
+            write.storage_root,
+            write.slices,
+        )
+        .await?;
+        if !write.opening_proofs.is_empty() {
+            if let Err(e) = self
+                .db
+                .put_opening_proofs(
+                    write.epoch,
+                    write.quorum_id,
+                    write.storage_root,
+                    write.opening_proofs,
+                )
+                .await
+            {
+                warn!("cannot store opening proofs: {:?}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Job {
+    Write(SliceWrite),
+    /// Answered once the writes queued before it are done.
+    Flush(oneshot::Sender<()>),
+}
+
+/// Background writer of the slices signed in the `Async` ack mode. The bounded queue slows signing
+/// down to the write rate once it is full.
+pub(crate) struct SliceWriter {
+    sender: mpsc::Sender<Job>,
+}
+
+impl SliceWriter {
+    pub fn start(store: SliceStore, queue_size: usize) -> Self {
+        let (sender, mut receiver) = mpsc::channel(queue_size.max(1));
+        tokio::spawn(async move {
+            while let Some(job) = receiver.recv().await {
+                match job {
+                    Job::Write(write) => {
+                        let (epoch, quorum_id) = (write.epoch, write.quorum_id);
+                        let storage_root = hex::encode(write.storage_root);
+                        if let Err(status) = store.write(write).await {
+                            error!(
+                                target: "alert",
+                                epoch,
+                                quorum_id,
+                                storage_root,
+                                "acknowledged slices are not stored: {}",
+                                status.message()
+                            );
+                        }
+                    }
+                    Job::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+        Self { sender }
+    }
+
+    /// Queue the write of signed slices, waiting for room in the queue.
+    pub async fn enqueue(&self, write: SliceWrite) -> Result<(), Status> {
+        self.sender
+            .send(Job::Write(write))
+            .await
+            .map_err(|_| Status::new(Code::Unavailable, "slice writer is stopped"))
+    }
+
+    /// Wait for the writes queued so far.
+    pub async fn flush(&self) {
+        let queued = self.sender.max_capacity() - self.sender.capacity();
+        info!(queued, "flushing slice writes");
+        let (done, wait) = oneshot::channel();
+        if self.sender.send(Job::Flush(done)).await.is_ok() {
+            let _ = wait.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn flush_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Arc::new(Storage::new(dir.path()).unwrap());
+        let writer = SliceWriter::start(
+            SliceStore {
+                db: db.clone(),
+                retry: PutSliceRetryConfig::default(),
+                errors: Default::default(),
+                events: EventBus::default(),
+            },
+            1,
+        );
+        for index in 0..3 {
+            writer
+                .enqueue(SliceWrite {
+                    epoch: 1,
+                    quorum_id: 0,
+                    storage_root: [7; 32],
+                    slices: vec![],
+                    opening_proofs: vec![(index, vec![index as u8; 8])],
+                })
+                .await
+                .unwrap();
+        }
+        writer.flush().await;
+        for index in 0..3 {
+            assert_eq!(
+                db.get_opening_proof(1, 0, [7; 32], index).await.unwrap(),
+                Some(vec![index as u8; 8])
+            );
+        }
+    }
+}
@@ -0,0 +1,406 @@
+use std::{
+    convert::Infallible,
+    fmt::Write,
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use storage::quorum_db::QuorumDB;
+use tonic::{Code, Status};
+
+use crate::admin_service::admin::{
+    admin_server::Admin, Empty, RegistrationStatus, RegistrationStatusRequest, StorageUsageRequest,
+    TransactionHistoryRequest, TransactionOutcome,
+};
+use crate::{build_info, AdminService};
+
+/// Epochs of the storage usage table, the latest first.
+const STATUS_PAGE_EPOCHS: usize = 10;
+/// Transactions and registrations scanned for errors.
+const STATUS_PAGE_HISTORY: u32 = 50;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn ago(now: u64, timestamp: u64) -> String {
+    match now.checked_sub(timestamp) {
+        Some(secs) if secs < 120 => format!("{}s ago", secs),
+        Some(secs) if secs < 7200 => format!("{}m ago", secs / 60),
+        Some(secs) => format!("{}h ago", secs / 3600),
+        None => "now".to_string(),
+    }
+}
+
+fn mib(bytes: u64) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// Table of `rows`, each of the cells of `header`, the cells are escaped.
+fn table(html: &mut String, header: &[&str], rows: Vec<Vec<String>>) {
+    if rows.is_empty() {
+        html.push_str("<p>none</p>");
+        return;
+    }
+    html.push_str("<table><tr>");
+    for cell in header {
+        let _ = write!(html, "<th>{}</th>", cell);
+    }
+    html.push_str("</tr>");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let _ = write!(html, "<td>{}</td>", escape(&cell));
+        }
+        html.push_str("</tr>");
+    }
+    html.push_str("</table>");
+}
+
+impl AdminService {
+    /// Status of the signer as a standalone html page, from the same sources as the admin API.
+    pub(crate) async fn status_page(&self) -> Result<String, Status> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let internal = |e: anyhow::Error| Status::new(Code::Internal, e.to_string());
+        let build = build_info();
+        let sync = self.get_sync_status(tonic::Request::new(Empty {})).await?;
+        let stake = self.get_stake_status(tonic::Request::new(Empty {})).await?;
+        let maintenance = self
+            .get_maintenance_status(tonic::Request::new(Empty {}))
+            .await?;
+        let verification = self
+            .get_verification_metrics(tonic::Request::new(Empty {}))
+            .await?;
+        let das = self.get_das_status(tonic::Request::new(Empty {})).await?;
+        let usage = self
+            .get_storage_usage(tonic::Request::new(StorageUsageRequest { epoch: None }))
+            .await?;
+        let registrations = self
+            .get_registration_status(tonic::Request::new(RegistrationStatusRequest {
+                limit: Some(STATUS_PAGE_HISTORY),
+            }))
+            .await?;
+        let transactions = self
+            .get_transaction_history(tonic::Request::new(TransactionHistoryRequest {
+                limit: Some(STATUS_PAGE_HISTORY),
+            }))
+            .await?;
+        let runtimes = self
+            .get_runtime_metrics(tonic::Request::new(Empty {}))
+            .await?;
+        let (sync, stake, maintenance, verification, das, usage) = (
+            sync.into_inner(),
+            stake.into_inner(),
+            maintenance.into_inner(),
+            verification.into_inner(),
+            das.into_inner(),
+            usage.into_inner(),
+        );
+        let epoch = self.db.get_latest_epoch().await.map_err(internal)?;
+        let quorums = match epoch {
+            Some(epoch) => self
+                .db
+                .get_quorums(epoch)
+                .await
+                .map_err(internal)?
+                .unwrap_or_default(),
+            None => vec![],
+        };
+
+        let mut html = String::new();
+        let _ = write!(
+            html,
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta http-equiv=\"refresh\" \
+             content=\"10\"><title>0g da node</title><style>body{{font-family:monospace}}\
+             table{{border-collapse:collapse}}td,th{{border:1px solid #999;padding:2px 8px;\
+             text-align:left}}</style></head><body><h1>0g da node {} ({})</h1>",
+            escape(&build.version),
+            escape(&build.git_commit),
+        );
+
+        html.push_str("<h2>Chain</h2>");
+        table(
+            &mut html,
+            &["epoch", "block", "target block", "synced", "maintenance"],
+            vec![vec![
+                epoch.map_or("unknown".to_string(), |x| x.to_string()),
+                sync.current_block.to_string(),
+                sync.target_block.to_string(),
+                match (sync.synced, sync.eta_seconds) {
+                    (true, _) => "yes".to_string(),
+                    (false, Some(eta)) => format!("no, {}s left", eta),
+                    (false, None) => "no".to_string(),
+                },
+                if maintenance.enabled {
+                    format!(
+                        "since {}: {}",
+                        ago(now, maintenance.since),
+                        maintenance.reason
+                    )
+                } else {
+                    "off".to_string()
+                },
+            ]],
+        );
+
+        html.push_str("<h2>Quorum assignments</h2>");
+        if stake.available {
+            let _ = write!(
+                html,
+                "<p>epoch {}: {} of {} rows in {} quorums, {} quorums without rows{}</p>",
+                stake.epoch,
+                stake.assigned_rows,
+                stake.total_rows,
+                stake.quorums,
+                stake.quorums_without_rows,
+                if stake.below_threshold {
+                    ", <b>below the stake threshold</b>"
+                } else {
+                    ""
+                }
+            );
+        }
+        table(
+            &mut html,
+            &["quorum", "assigned rows"],
+            quorums
+                .iter()
+                .enumerate()
+                .map(|(quorum_id, rows)| vec![quorum_id.to_string(), rows.0.len().to_string()])
+                .collect(),
+        );
+
+        html.push_str("<h2>Sign requests</h2>");
+        let _ = write!(
+            html,
+            "<p>{} ongoing, {:.0} slices/s and {:.0}% of {} verification threads busy over the last \
+             {}s</p>",
+            maintenance.ongoing_sign_requests,
+            verification.slices_per_second,
+            verification.pool_utilization * 100.0,
+            verification.pool_threads,
+            verification.window_seconds
+        );
+        table(
+            &mut html,
+            &[
+                "quorum",
+                "batch size",
+                "slices verified",
+                "mean blob verify",
+            ],
+            verification
+                .metrics
+                .iter()
+                .map(|x| {
+                    vec![
+                        x.quorum_id.to_string(),
+                        x.batch_size.to_string(),
+                        x.slices.to_string(),
+                        match &x.blob_verify {
+                            Some(h) if h.count > 0 => format!("{} ms", h.sum_us / h.count / 1000),
+                            _ => "-".to_string(),
+                        },
+                    ]
+                })
+                .collect(),
+        );
+
+        html.push_str("<h2>DAS mining</h2>");
+        table(
+            &mut html,
+            &[
+                "state",
+                "round",
+                "lines scanned",
+                "answers",
+                "last submission",
+            ],
+            vec![vec![
+                match (das.enabled, das.paused) {
+                    (false, _) => "disabled",
+                    (true, true) => "paused",
+                    (true, false) => "running",
+                }
+                .to_string(),
+                das.round.as_ref().map_or("-".to_string(), |x| {
+                    format!("started {}", ago(now, x.started_at))
+                }),
+                das.round.as_ref().map_or("-".to_string(), |x| {
+                    format!("{} ({} candidates)", x.scanned_lines, x.candidate_lines)
+                }),
+                das.round
+                    .as_ref()
+                    .map_or("-".to_string(), |x| x.answers.to_string()),
+                das.last_submission.as_ref().map_or("-".to_string(), |x| {
+                    format!(
+                        "epoch {} quorum {} {}, {}",
+                        x.epoch,
+                        x.quorum_id,
+                        ago(now, x.timestamp),
+                        if x.confirmed { "confirmed" } else { "failed" }
+                    )
+                }),
+            ]],
+        );
+
+        html.push_str("<h2>Storage</h2>");
+        let _ = write!(
+            html,
+            "<p>database {}, {} slices in {}, {} reclaimable</p>",
+            mib(usage.db_size_bytes),
+            usage.slices,
+            mib(usage.bytes),
+            mib(usage.reclaimable_bytes)
+        );
+        table(
+            &mut html,
+            &["epoch", "slices", "size", "reclaimable"],
+            usage
+                .epochs
+                .iter()
+                .rev()
+                .take(STATUS_PAGE_EPOCHS)
+                .map(|x| {
+                    vec![
+                        x.epoch.to_string(),
+                        x.slices.to_string(),
+                        mib(x.bytes),
+                        mib(x.reclaimable_bytes),
+                    ]
+                })
+                .collect(),
+        );
+
+        html.push_str("<h2>Recent errors</h2>");
+        let registrations = registrations.into_inner();
+        let mut errors = vec![];
+        if let Some(signer) = registrations.signer {
+            if !signer.last_error.is_empty() {
+                errors.push((
+                    signer.checked_at,
+                    "signer registration".to_string(),
+                    signer.last_error,
+                ));
+            }
+        }
+        for x in registrations.registrations {
+            if x.status == RegistrationStatus::RegistrationFailed as i32
+                || x.status == RegistrationStatus::Missed as i32
+            {
+                errors.push((
+                    x.updated_at,
+                    format!("registration of epoch {}", x.epoch),
+                    x.last_error,
+                ));
+            }
+        }
+        for x in transactions.into_inner().attempts {
+            if x.outcome != TransactionOutcome::Succeeded as i32 {
+                let outcome = TransactionOutcome::try_from(x.outcome)
+                    .map_or("UNKNOWN", |outcome| outcome.as_str_name());
+                errors.push((
+                    x.timestamp,
+                    format!("transaction {}", x.info),
+                    format!("{} {}", outcome, x.reason),
+                ));
+            }
+        }
+        for x in runtimes.into_inner().runtimes {
+            if let Some(last_stall_at) = x.last_stall_at {
+                errors.push((
+                    last_stall_at,
+                    format!("{} runtime", x.name),
+                    format!("{} stalls", x.stalls),
+                ));
+            }
+        }
+        errors.sort_by(|a, b| b.0.cmp(&a.0));
+        table(
+            &mut html,
+            &["when", "what", "error"],
+            errors
+                .into_iter()
+                .map(|(timestamp, what, error)| vec![ago(now, timestamp), what, error])
+                .collect(),
+        );
+
+        html.push_str("</body></html>");
+        Ok(html)
+    }
+}
+
+async fn serve_status_page(
+    admin_service: AdminService,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    if request.method() != Method::GET || request.uri().path() != "/" {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .unwrap());
+    }
+    let response = match admin_service.status_page().await {
+        Ok(html) => Response::builder()
+            .header("content-type", "text/html; charset=utf-8")
+            .body(Body::from(html)),
+        Err(e) => Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(e.message().to_string())),
+    };
+    Ok(response.unwrap())
+}
+
+/// Serve the status page of the admin API at `/` of an http listener.
+pub async fn run_status_page_server(
+    addr: SocketAddr,
+    admin_service: AdminService,
+) -> Result<(), Box<dyn std::error::Error>> {
+    info!("admin http server listening {:?}", addr);
+    Server::try_bind(&addr)?
+        .serve(make_service_fn(move |_| {
+            let admin_service = admin_service.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    serve_status_page(admin_service.clone(), request)
+                }))
+            }
+        }))
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_test() {
+        let mut html = String::new();
+        table(&mut html, &["what"], vec![]);
+        assert_eq!(html, "<p>none</p>");
+
+        html.clear();
+        table(
+            &mut html,
+            &["what", "error"],
+            vec![vec!["tx".to_string(), "<script>".to_string()]],
+        );
+        assert_eq!(
+            html,
+            "<table><tr><th>what</th><th>error</th></tr><tr><td>tx</td><td>&lt;script&gt;</td></tr></table>"
+        );
+        assert_eq!(ago(100, 40), "60s ago");
+        assert_eq!(ago(10_000, 1000), "2h ago");
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Upper bounds of the histogram buckets in microseconds, the last bucket counts the durations
+/// above the last bound.
+pub const BUCKET_BOUNDS_US: [u64; 16] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000, 2_500_000, 5_000_000, 10_000_000,
+];
+
+/// Window the throughput and the pool utilization are measured over.
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Durations in fixed buckets, recorded without locking from the verification threads.
+#[derive(Default)]
+pub struct Histogram {
+    buckets: [AtomicU64; BUCKET_BOUNDS_US.len() + 1],
+    count: AtomicU64,
+    sum_us: AtomicU64,
+}
+
+/// Copy of a histogram.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HistogramSnapshot {
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub sum_us: u64,
+}
+
+impl Histogram {
+    pub fn record(&self, duration: Duration) {
+        let us = duration.as_micros() as u64;
+        let bucket = BUCKET_BOUNDS_US.partition_point(|bound| *bound < us);
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        self.sum_us.fetch_add(us, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> HistogramSnapshot {
+        HistogramSnapshot {
+            buckets: self
+                .buckets
+                .iter()
+                .map(|x| x.load(Ordering::Relaxed))
+                .collect(),
+            count: self.count.load(Ordering::Relaxed),
+            sum_us: self.sum_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Verification timings of the sign requests of a quorum, in batches of a size bucket.
+#[derive(Default)]
+pub struct LabeledMetrics {
+    pub slice_verify: Histogram,
+    pub blob_verify: Histogram,
+    pub decode: Histogram,
+    pub slices: AtomicU64,
+}
+
+/// Label of `LabeledMetrics`, the batch size is rounded up to a power of two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MetricsLabel {
+    pub quorum_id: u64,
+    pub batch_size: u64,
+}
+
+/// Copy of the metrics of a label.
+#[derive(Debug, Clone)]
+pub struct LabeledSnapshot {
+    pub label: MetricsLabel,
+    pub slice_verify: HistogramSnapshot,
+    pub blob_verify: HistogramSnapshot,
+    pub decode: HistogramSnapshot,
+    pub slices: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Throughput {
+    pub slices_per_second: f64,
+    /// Share of the time of the verification threads spent verifying slices.
+    pub pool_utilization: f64,
+    pub window: Duration,
+}
+
+/// Blobs verified in the throughput window.
+struct VerifiedBlob {
+    at: Instant,
+    slices: u64,
+    /// Time spent verifying the slices, summed over the verification threads.
+    busy: Duration,
+}
+
+/// Verification latency and throughput of the sign requests, to size `max_verify_threads`.
+#[derive(Clone)]
+pub struct VerificationMetrics {
+    labels: Arc<Mutex<BTreeMap<MetricsLabel, Arc<LabeledMetrics>>>>,
+    window: Arc<Mutex<VecDeque<VerifiedBlob>>>,
+    started_at: Instant,
+}
+
+impl Default for VerificationMetrics {
+    fn default() -> Self {
+        Self {
+            labels: Default::default(),
+            window: Default::default(),
+            started_at: Instant::now(),
+        }
+    }
+}
+
+impl VerificationMetrics {
+    pub fn labeled(&self, quorum_id: u64, batch_size: usize) -> Arc<LabeledMetrics> {
+        let label = MetricsLabel {
+            quorum_id,
+            batch_size: (batch_size.max(1) as u64).next_power_of_two(),
+        };
+        self.labels
+            .lock()
+            .unwrap()
+            .entry(label)
+            .or_default()
+            .clone()
+    }
+
+    /// Record a verified blob, `busy` is the verification time summed over its slices.
+    pub fn on_blob_verified(
+        &self,
+        metrics: &LabeledMetrics,
+        slices: u64,
+        elapsed: Duration,
+        busy: Duration,
+    ) {
+        metrics.blob_verify.record(elapsed);
+        metrics.slices.fetch_add(slices, Ordering::Relaxed);
+        let now = Instant::now();
+        let mut window = self.window.lock().unwrap();
+        window.push_back(VerifiedBlob {
+            at: now,
+            slices,
+            busy,
+        });
+        while window
+            .front()
+            .map_or(false, |x| now.duration_since(x.at) > THROUGHPUT_WINDOW)
+        {
+            window.pop_front();
+        }
+    }
+
+    pub fn snapshot(&self) -> Vec<LabeledSnapshot> {
+        self.labels
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(label, metrics)| LabeledSnapshot {
+                label: *label,
+                slice_verify: metrics.slice_verify.snapshot(),
+                blob_verify: metrics.blob_verify.snapshot(),
+                decode: metrics.decode.snapshot(),
+                slices: metrics.slices.load(Ordering::Relaxed),
+            })
+            .collect()
+    }
+
+    /// Slices verified per second and the share of the verification pool kept busy, over the
+    /// last minute or the uptime if shorter.
+    pub fn throughput(&self, pool_threads: usize) -> Throughput {
+        let now = Instant::now();
+        let window = THROUGHPUT_WINDOW.min(now.duration_since(self.started_at));
+        let (slices, busy) = self
+            .window
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|x| now.duration_since(x.at) <= window)
+            .fold((0, Duration::ZERO), |(slices, busy), x| {
+                (slices + x.slices, busy + x.busy)
+            });
+        if window.is_zero() || pool_threads == 0 {
+            return Throughput {
+                slices_per_second: 0.0,
+                pool_utilization: 0.0,
+                window,
+            };
+        }
+        Throughput {
+            slices_per_second: slices as f64 / window.as_secs_f64(),
+            pool_utilization: busy.as_secs_f64() / (window.as_secs_f64() * pool_threads as f64),
+            window,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histogram_test() {
+        let histogram = Histogram::default();
+        histogram.record(Duration::from_micros(50));
+        histogram.record(Duration::from_micros(100));
+        histogram.record(Duration::from_millis(3));
+        histogram.record(Duration::from_secs(60));
+        let snapshot = histogram.snapshot();
+        assert_eq!(snapshot.count, 4);
+        assert_eq!(snapshot.sum_us, 60_003_150);
+        assert_eq!(snapshot.buckets[0], 2);
+        assert_eq!(snapshot.buckets[5], 1);
+        assert_eq!(snapshot.buckets[BUCKET_BOUNDS_US.len()], 1);
+    }
+
+    #[test]
+    fn throughput_test() {
+        let metrics = VerificationMetrics {
+            started_at: Instant::now() - Duration::from_secs(120),
+            ..Default::default()
+        };
+        let labeled = metrics.labeled(0, 1);
+        metrics.on_blob_verified(
+            &labeled,
+            1200,
+            Duration::from_secs(2),
+            Duration::from_secs(12),
+        );
+        let throughput = metrics.throughput(4);
+        assert_eq!(throughput.window, THROUGHPUT_WINDOW);
+        assert_eq!(throughput.slices_per_second, 20.0);
+        assert_eq!(throughput.pool_utilization, 0.05);
+        assert_eq!(labeled.blob_verify.snapshot().count, 1);
+    }
+
+    #[test]
+    fn labels_test() {
+        let metrics = VerificationMetrics::default();
+        metrics
+            .labeled(0, 3)
+            .decode
+            .record(Duration::from_millis(1));
+        metrics
+            .labeled(0, 4)
+            .decode
+            .record(Duration::from_millis(1));
+        metrics
+            .labeled(1, 0)
+            .decode
+            .record(Duration::from_millis(1));
+        let labels: Vec<_> = metrics
+            .snapshot()
+            .into_iter()
+            .map(|x| (x.label.quorum_id, x.label.batch_size, x.decode.count))
+            .collect();
+        assert_eq!(labels, vec![(0, 4, 2), (1, 1, 1)]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::oneshot;
+
+/// Order of a sign request waiting to verify: the higher client priority first, then the oldest
+/// epoch, nearest its signing deadline, then the first arrived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct VerificationPriority {
+    pub client: u64,
+    pub epoch: Reverse<u64>,
+}
+
+impl VerificationPriority {
+    pub fn new(client: u64, epoch: u64) -> Self {
+        Self {
+            client,
+            epoch: Reverse(epoch),
+        }
+    }
+}
+
+struct Waiter {
+    priority: VerificationPriority,
+    seq: Reverse<u64>,
+    wake: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.priority, self.seq).cmp(&(other.priority, other.seq))
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    available: usize,
+    next_seq: u64,
+    waiting: BinaryHeap<Waiter>,
+}
+
+/// Slots of the sign requests verified at once, shared by the signers of the node. Requests
+/// waiting for a slot are served by priority rather than in arrival order, so a burst of new
+/// requests does not delay the ones about to miss their epoch.
+#[derive(Clone)]
+pub struct VerificationQueue(Arc<Mutex<QueueState>>);
+
+/// Verification slot of a request, handed to the next waiter on drop.
+pub(crate) struct VerificationSlot(VerificationQueue);
+
+impl VerificationQueue {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self(Arc::new(Mutex::new(QueueState {
+            available: max_concurrent.max(1),
+            ..Default::default()
+        })))
+    }
+
+    /// Requests waiting for a slot.
+    pub fn waiting(&self) -> usize {
+        self.0.lock().unwrap().waiting.len()
+    }
+
+    pub(crate) async fn acquire(&self, priority: VerificationPriority) -> VerificationSlot {
+        let mut woken = {
+            let mut state = self.0.lock().unwrap();
+            if state.available > 0 {
+                state.available -= 1;
+                return VerificationSlot(self.clone());
+            }
+            let (wake, woken) = oneshot::channel();
+            let seq = Reverse(state.next_seq);
+            state.next_seq += 1;
+            state.waiting.push(Waiter {
+                priority,
+                seq,
+                wake,
+            });
+            WokenGuard { queue: self, woken }
+        };
+        // the sender is only dropped after a send, the slot is then ours
+        let _ = (&mut woken.woken).await;
+        VerificationSlot(self.clone())
+    }
+
+    fn release(&self) {
+        let mut state = self.0.lock().unwrap();
+        while let Some(waiter) = state.waiting.pop() {
+            // cancelled waiters dropped their receiver
+            if waiter.wake.send(()).is_ok() {
+                return;
+            }
+        }
+        state.available += 1;
+    }
+}
+
+/// Receiver of a waiting request, giving back a slot handed to it if the request is cancelled.
+struct WokenGuard<'a> {
+    queue: &'a VerificationQueue,
+    woken: oneshot::Receiver<()>,
+}
+
+impl Drop for WokenGuard<'_> {
+    fn drop(&mut self) {
+        self.woken.close();
+        if self.woken.try_recv().is_ok() {
+            self.queue.release();
+        }
+    }
+}
+
+impl Drop for VerificationSlot {
+    fn drop(&mut self) {
+        self.0.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::sleep;
+
+    #[tokio::test]
+    async fn priority_order_test() {
+        let queue = VerificationQueue::new(1);
+        let slot = queue.acquire(VerificationPriority::new(0, 10)).await;
+
+        let order = Arc::new(Mutex::new(vec![]));
+        let mut tasks = vec![];
+        for (name, client, epoch) in [
+            ("late epoch", 0, 12),
+            ("early epoch", 0, 11),
+            ("cancelled", 5, 12),
+            ("high client", 1, 12),
+            ("early epoch again", 0, 11),
+        ] {
+            let queue = queue.clone();
+            let order = order.clone();
+            tasks.push(tokio::spawn(async move {
+                let _slot = queue
+                    .acquire(VerificationPriority::new(client, epoch))
+                    .await;
+                order.lock().unwrap().push(name);
+            }));
+            // waiters of equal priority keep their arrival order
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(queue.waiting(), 5);
+        tasks.remove(2).abort();
+        sleep(Duration::from_millis(10)).await;
+
+        drop(slot);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![
+                "high client",
+                "early epoch",
+                "early epoch again",
+                "late epoch"
+            ]
+        );
+        // every slot was given back
+        assert_eq!(queue.0.lock().unwrap().available, 1);
+    }
+}
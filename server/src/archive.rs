@@ -0,0 +1,274 @@
+use std::io::{self, Read, Write};
+
+use anyhow::{anyhow, bail, Result};
+use ark_serialize::CanonicalSerialize;
+use grpc::signer::{ArchivedBlob, StoredSlice};
+use prost::Message;
+use storage::{
+    blob_status_db::{BlobStatus, BlobStatusDB},
+    opening_proof_db::OpeningProofDB,
+    slice_db::SliceDB,
+    Storage,
+};
+
+use crate::resync::{local_blob_roots, verify_stored_slice};
+
+/// Leading bytes of a slice archive, the last byte is the format version.
+const ARCHIVE_MAGIC: &[u8; 8] = b"0GDASLC1";
+/// Upper bound of an archived blob, so a corrupt length does not allocate unbounded memory.
+const MAX_RECORD_SIZE: usize = 1 << 30;
+
+/// Writes a slice archive: the magic bytes followed by length prefixed `ArchivedBlob` records.
+pub struct ArchiveWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    pub fn new(mut inner: W) -> Result<Self> {
+        inner.write_all(ARCHIVE_MAGIC)?;
+        Ok(Self { inner })
+    }
+
+    pub fn write_blob(&mut self, blob: &ArchivedBlob) -> Result<()> {
+        let record = blob.encode_to_vec();
+        self.inner.write_all(&(record.len() as u64).to_be_bytes())?;
+        self.inner.write_all(&record)?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<W> {
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+/// Reads the records of a slice archive written by `ArchiveWriter`.
+pub struct ArchiveReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> ArchiveReader<R> {
+    pub fn new(mut inner: R) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        inner.read_exact(&mut magic)?;
+        if &magic != ARCHIVE_MAGIC {
+            bail!(anyhow!(
+                "not a slice archive or unsupported archive version"
+            ));
+        }
+        Ok(Self { inner })
+    }
+
+    /// The next blob of the archive, `None` at the end of the archive.
+    pub fn next_blob(&mut self) -> Result<Option<ArchivedBlob>> {
+        let mut len = [0u8; 8];
+        // the archive may only end between records
+        match self.inner.read_exact(&mut len[..1]) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+        self.inner
+            .read_exact(&mut len[1..])
+            .map_err(|e| anyhow!("truncated archive: {:?}", e))?;
+        let len = u64::from_be_bytes(len) as usize;
+        if len > MAX_RECORD_SIZE {
+            bail!(anyhow!("archive record of {} bytes is too large", len));
+        }
+        let mut record = vec![0u8; len];
+        self.inner
+            .read_exact(&mut record)
+            .map_err(|e| anyhow!("truncated archive: {:?}", e))?;
+        Ok(Some(ArchivedBlob::decode(&*record)?))
+    }
+}
+
+/// A stored slice with its data, serialized as it is sent to peers. With `with_opening_proof`, the
+/// whole encoded slice is attached if the node keeps it, for peers to verify it against the erasure
+/// commitment.
+pub(crate) async fn stored_slice(
+    db: &Storage,
+    epoch: u64,
+    quorum_id: u64,
+    storage_root: [u8; 32],
+    index: usize,
+    with_opening_proof: bool,
+) -> Result<Option<StoredSlice>> {
+    let light_slice = db.get_slice(epoch, quorum_id, storage_root, index).await?;
+    let data = db
+        .get_slice_data(epoch, quorum_id, storage_root, index)
+        .await?;
+    if let (Some(light_slice), Some(data)) = (light_slice, data) {
+        let mut light_value = Vec::new();
+        light_slice.serialize_compressed(&mut light_value)?;
+        let mut data_value = Vec::new();
+        data.serialize_uncompressed(&mut data_value)?;
+        let encoded_slice = if with_opening_proof {
+            db.get_opening_proof(epoch, quorum_id, storage_root, index as u64)
+                .await?
+                .unwrap_or_default()
+        } else {
+            vec![]
+        };
+        return Ok(Some(StoredSlice {
+            row_index: index as u32,
+            light_slice: light_value,
+            data: data_value,
+            encoded_slice,
+        }));
+    }
+    Ok(None)
+}
+
+/// Write the slices of the epochs in `[from_epoch, to_epoch]` to an archive, one record per blob,
+/// returns the number of blobs and slices exported.
+pub async fn export_slices<W: Write>(
+    db: &Storage,
+    writer: &mut ArchiveWriter<W>,
+    from_epoch: u64,
+    to_epoch: u64,
+) -> Result<(usize, usize)> {
+    let (mut blobs, mut slices) = (0, 0);
+    for epoch in from_epoch..=to_epoch {
+        for blob in db.get_epoch_info(epoch).await? {
+            let (quorum_id, storage_root) = (blob.quorum_id, blob.storage_root);
+            let mut archived = ArchivedBlob {
+                epoch,
+                quorum_id,
+                storage_root: storage_root.to_vec(),
+                status: db
+                    .get_blob_status(epoch, quorum_id, storage_root)
+                    .await?
+                    .map_or(0, |status| status as u64),
+                slices: vec![],
+            };
+            for index in blob.indicies {
+                match stored_slice(db, epoch, quorum_id, storage_root, index as usize, false).await? {
+                    Some(slice) => archived.slices.push(slice),
+                    None => warn!(
+                        "slice {:?} of epoch {:?}, quorum {:?}, storage_root {:?} not readable, skipped",
+                        index,
+                        epoch,
+                        quorum_id,
+                        hex::encode(storage_root)
+                    ),
+                }
+            }
+            slices += archived.slices.len();
+            blobs += 1;
+            writer.write_blob(&archived)?;
+        }
+    }
+    Ok((blobs, slices))
+}
+
+#[derive(Debug, Default)]
+pub struct ImportStats {
+    pub blobs: usize,
+    pub imported: usize,
+    /// Slices already stored in the database.
+    pub skipped: usize,
+    /// Slices failing verification, they are not imported.
+    pub invalid: usize,
+}
+
+/// Verify the slices of an archive against their merkle roots and store the ones missing in the
+/// database, along with the status of their blobs.
+pub async fn import_slices<R: Read>(
+    db: &Storage,
+    reader: &mut ArchiveReader<R>,
+) -> Result<ImportStats> {
+    let mut stats = ImportStats::default();
+    while let Some(blob) = reader.next_blob()? {
+        let (epoch, quorum_id) = (blob.epoch, blob.quorum_id);
+        let storage_root: [u8; 32] = blob
+            .storage_root
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow!("storage root must be 32 bytes"))?;
+        stats.blobs += 1;
+
+        let stored: Vec<u16> = db
+            .get_epoch_info(epoch)
+            .await?
+            .into_iter()
+            .find(|x| x.quorum_id == quorum_id && x.storage_root == storage_root)
+            .map_or(vec![], |x| x.indicies);
+        let mut blob_roots = local_blob_roots(db, epoch, quorum_id, storage_root, &[]).await?;
+        let mut verified = vec![];
+        for slice in blob.slices.iter() {
+            if stored.contains(&(slice.row_index as u16)) {
+                stats.skipped += 1;
+                continue;
+            }
+            match verify_stored_slice(slice, &blob_roots) {
+                Ok((light_slice, data)) => {
+                    blob_roots.get_or_insert(light_slice.merkle_root);
+                    verified.push((light_slice, data));
+                }
+                Err(e) => {
+                    warn!(
+                        "invalid slice {:?} of epoch {:?}, quorum {:?}, storage_root {:?}: {:?}",
+                        slice.row_index,
+                        epoch,
+                        quorum_id,
+                        hex::encode(storage_root),
+                        e
+                    );
+                    stats.invalid += 1;
+                }
+            }
+        }
+
+        if let Ok(status) = BlobStatus::try_from(blob.status) {
+            if db
+                .get_blob_status(epoch, quorum_id, storage_root)
+                .await?
+                .is_none()
+            {
+                db.put_blob(epoch, quorum_id, storage_root, status).await?;
+            }
+        }
+        if !verified.is_empty() {
+            stats.imported += verified.len();
+            db.put_light_slices(epoch, quorum_id, storage_root, verified)
+                .await?;
+        }
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_round_trip_test() {
+        let blob = ArchivedBlob {
+            epoch: 3,
+            quorum_id: 1,
+            storage_root: vec![7; 32],
+            status: BlobStatus::VERIFIED as u64,
+            slices: vec![StoredSlice {
+                row_index: 5,
+                light_slice: vec![1, 2, 3],
+                data: vec![4; 64],
+                encoded_slice: vec![],
+            }],
+        };
+        let mut writer = ArchiveWriter::new(vec![]).unwrap();
+        writer.write_blob(&blob).unwrap();
+        writer.write_blob(&ArchivedBlob::default()).unwrap();
+        let archive = writer.finish().unwrap();
+
+        let mut reader = ArchiveReader::new(&*archive).unwrap();
+        assert_eq!(reader.next_blob().unwrap(), Some(blob));
+        assert_eq!(reader.next_blob().unwrap(), Some(ArchivedBlob::default()));
+        assert_eq!(reader.next_blob().unwrap(), None);
+
+        assert!(ArchiveReader::new(&archive[1..]).is_err());
+        let mut truncated = ArchiveReader::new(&archive[..archive.len() - 1]).unwrap();
+        assert!(truncated.next_blob().is_ok());
+        assert!(truncated.next_blob().is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use chain_state::ChainState;
+use grpc::{signer::StoredSlice, SliceVerifier};
+use storage::{
+    blob_status_db::{BlobStatus, BlobStatusDB},
+    slice_db::SliceDB,
+    Storage,
+};
+use task_executor::TaskExecutor;
+use tokio::{
+    sync::{mpsc, RwLock},
+    time::sleep,
+};
+
+use crate::{
+    config::BackfillConfig,
+    resync::{local_blob_roots, missing_slices, verify_recovered_slices, RecoveredBlob},
+};
+
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Slices of a blob received from peers or imported, to verify and store if they are missing.
+pub struct BackfillJob {
+    pub epoch: u64,
+    pub quorum_id: u64,
+    pub storage_root: [u8; 32],
+    pub slices: Vec<StoredSlice>,
+    /// Where the slices come from, for logs.
+    pub source: String,
+}
+
+/// Queue of the backfill verifier.
+#[derive(Clone)]
+pub struct BackfillQueue {
+    sender: mpsc::Sender<BackfillJob>,
+}
+
+impl BackfillQueue {
+    /// Queue a job, it is dropped if the queue is full as the slices can be backfilled again later.
+    pub fn push(&self, job: BackfillJob) {
+        if let Err(e) = self.sender.try_send(job) {
+            debug!("backfill job dropped: {:?}", e.to_string());
+        }
+    }
+}
+
+/// Verify backfilled slices as a low priority workload: jobs are only processed in the off-peak
+/// hours and while no sign request is ongoing, so they never compete with live signing.
+pub fn start_backfill_verifier(
+    executor: TaskExecutor,
+    chain_state: Arc<ChainState>,
+    db: Arc<Storage>,
+    config: BackfillConfig,
+    sign_load: Arc<RwLock<u64>>,
+    verifier: SliceVerifier,
+) -> BackfillQueue {
+    let (sender, mut receiver) = mpsc::channel(config.queue_capacity);
+    executor.spawn(
+        async move {
+            while let Some(job) = receiver.recv().await {
+                wait_idle(&config, &sign_load).await;
+                if let Err(e) = fill_slices(&chain_state, &db, &verifier, &job).await {
+                    warn!("invalid backfill slices from {}: {:?}", job.source, e);
+                }
+            }
+        },
+        "backfill_verifier",
+    );
+    BackfillQueue { sender }
+}
+
+async fn wait_idle(config: &BackfillConfig, sign_load: &RwLock<u64>) {
+    loop {
+        let hour = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() / 3600 % 24);
+        if is_off_peak(config.off_peak_hours, hour) && *sign_load.read().await == 0 {
+            return;
+        }
+        sleep(IDLE_POLL_INTERVAL).await;
+    }
+}
+
+/// Whether the UTC `hour` is in the `[start, end)` off-peak hours, which may wrap around midnight.
+fn is_off_peak(off_peak_hours: Option<(u64, u64)>, hour: u64) -> bool {
+    match off_peak_hours {
+        None => true,
+        Some((start, end)) if start <= end => start <= hour && hour < end,
+        Some((start, end)) => start <= hour || hour < end,
+    }
+}
+
+/// Verify the slices of a job filling missing assigned rows of a verified blob against its erasure
+/// commitment on chain and store the valid ones, returns the number of slices stored.
+pub(crate) async fn fill_slices(
+    chain_state: &ChainState,
+    db: &Storage,
+    verifier: &SliceVerifier,
+    job: &BackfillJob,
+) -> Result<usize> {
+    let (epoch, quorum_id, storage_root) = (job.epoch, job.quorum_id, job.storage_root);
+    // only fill slices of blobs verified on chain
+    match db.get_blob_status(epoch, quorum_id, storage_root).await? {
+        Some(BlobStatus::VERIFIED) => {}
+        _ => return Ok(0),
+    }
+    let missing = missing_slices(chain_state, db, epoch, quorum_id, storage_root).await?;
+    if missing.is_empty() {
+        return Ok(0);
+    }
+    let stored: Vec<_> = job
+        .slices
+        .iter()
+        .filter(|stored| missing.contains(&(stored.row_index as u64)))
+        .cloned()
+        .collect();
+    if stored.is_empty() {
+        return Ok(0);
+    }
+    let blob = RecoveredBlob::fetch(chain_state, epoch, quorum_id, storage_root).await?;
+    let mut blob_roots = local_blob_roots(db, epoch, quorum_id, storage_root, &missing).await?;
+    let recovered =
+        verify_recovered_slices(verifier, &blob, &stored, &mut blob_roots, &job.source).await?;
+    let filled = recovered.len();
+    if filled > 0 {
+        info!(
+            "filled {:?} slices from {}: epoch = {:?}, quorum = {:?}, storage_root = {:?}",
+            filled,
+            job.source,
+            epoch,
+            quorum_id,
+            hex::encode(storage_root)
+        );
+        db.put_light_slices(epoch, quorum_id, storage_root, recovered)
+            .await?;
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_off_peak_test() {
+        assert!(is_off_peak(None, 12));
+        assert!(is_off_peak(Some((1, 5)), 1));
+        assert!(!is_off_peak(Some((1, 5)), 5));
+        assert!(is_off_peak(Some((22, 6)), 23));
+        assert!(is_off_peak(Some((22, 6)), 3));
+        assert!(!is_off_peak(Some((22, 6)), 12));
+    }
+}
@@ -0,0 +1,168 @@
+use clap::{arg, command, Command};
+
+pub fn cli_app<'a>() -> Command<'a> {
+    command!()
+        .arg(arg!(-c --config <FILE> "Sets a custom config file").required(false))
+        .arg(
+            arg!(--set <KEY_VALUE> "Overrides a config key, e.g. `--set batch_proxy.enabled=true`")
+                .required(false)
+                .multiple_occurrences(true),
+        )
+        .subcommand(
+            Command::new("replay-request")
+                .about(
+                    "Re-executes a recorded sign request against the local verification pipeline",
+                )
+                .arg(arg!(-f --file <FILE> "Recorded request dumped by the node"))
+                .arg(arg!(-p --params <DIR> "Encoder params folder").required(false))
+                .arg(arg!(--"log-level" <LEVEL> "Log level, debug by default").required(false)),
+        )
+        .subcommand(
+            Command::new("recover")
+                .about("Recovers the signer identity after a host failure")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("backup")
+                        .about("Copies the identity keys of the config to a backup file")
+                        .arg(arg!(-c --config <FILE> "Node config file"))
+                        .arg(arg!(-o --output <FILE> "Backup file to write")),
+                )
+                .subcommand(
+                    Command::new("restore")
+                        .about("Restores the identity keys of a backup into the config once verified on chain")
+                        .arg(arg!(-c --config <FILE> "Node config file"))
+                        .arg(arg!(-f --file <FILE> "Backup file"))
+                        .arg(arg!(--force "Restore even if the keys do not match the on-chain registration")),
+                )
+                .subcommand(
+                    Command::new("verify")
+                        .about("Verifies that the on-chain registration matches the configured keys")
+                        .arg(arg!(-c --config <FILE> "Node config file")),
+                )
+                .subcommand(
+                    Command::new("socket")
+                        .about("Detects socket conflicts with the registry and other signers")
+                        .arg(arg!(-c --config <FILE> "Node config file"))
+                        .arg(arg!(--update "Register the configured socket if it differs")),
+                )
+                .subcommand(
+                    Command::new("resync-assignments")
+                        .about("Fetches the slice assignments of the latest epochs from chain again, the node must be stopped")
+                        .arg(arg!(-c --config <FILE> "Node config file"))
+                        .arg(arg!(--epochs <N> "Number of latest epochs, 3 by default").required(false)),
+                ),
+        )
+        .subcommand(
+            Command::new("inspect-db")
+                .about("Inspects the node database, the node must be stopped")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("blobs")
+                        .about("Lists the blob statuses of an epoch")
+                        .arg(arg!(-c --config <FILE> "Node config file"))
+                        .arg(arg!(--epoch <EPOCH> "Epoch"))
+                        .arg(arg!(--quorum <QUORUM> "Only blobs of the quorum").required(false)),
+                )
+                .subcommand(
+                    Command::new("assigned")
+                        .about("Shows the slices assigned to the node in a quorum")
+                        .arg(arg!(-c --config <FILE> "Node config file"))
+                        .arg(arg!(--epoch <EPOCH> "Epoch"))
+                        .arg(arg!(--quorum <QUORUM> "Quorum id")),
+                )
+                .subcommand(
+                    Command::new("slice")
+                        .about("Dumps the metadata of a stored slice")
+                        .arg(arg!(-c --config <FILE> "Node config file"))
+                        .arg(arg!(--epoch <EPOCH> "Epoch"))
+                        .arg(arg!(--quorum <QUORUM> "Quorum id"))
+                        .arg(arg!(--root <ROOT> "Storage root of the blob, in hex"))
+                        .arg(arg!(--index <INDEX> "Row index of the slice")),
+                )
+                .subcommand(
+                    Command::new("stats")
+                        .about("Shows the size of every column and of the database on disk")
+                        .arg(arg!(-c --config <FILE> "Node config file")),
+                ),
+        )
+        .subcommand(
+            Command::new("keygen")
+                .about("Generates a signer BLS key, and optionally an ETH key, into encrypted keystores")
+                .arg(arg!(-o --output <DIR> "Folder to write the keystores to"))
+                .arg(arg!(--"password-file" <FILE> "File containing the keystore password"))
+                .arg(arg!(--eth "Also generate the ETH key of the signer account"))
+                .arg(arg!(--"signer-address" <ADDRESS> "Signer account, if the ETH key is not generated").required(false))
+                .arg(arg!(--"chain-id" <ID> "Chain id to print the registration calldata for").required(false))
+                .arg(arg!(--socket <SOCKET> "Public socket of the node, for the registration calldata").required(false))
+                .arg(arg!(--"show-secret" "Print the generated keys in the config format")),
+        )
+        .subcommand(
+            Command::new("import-keystore")
+                .about("Loads a signer BLS key from an EIP-2335 keystore and prints its BN254 public keys")
+                .arg(arg!(-f --file <FILE> "Keystore of the key"))
+                .arg(arg!(--"password-file" <FILE> "File containing the keystore password"))
+                .arg(arg!(--"show-secret" "Print the imported key in the config format")),
+        )
+        .subcommand(
+            Command::new("verify-params")
+                .about("Checks the encoder param files against the published hashes and loads them")
+                .arg(arg!(-c --config <FILE> "Node config file, to check its encoder_params_dir").required(false))
+                .arg(arg!(-p --params <DIR> "Encoder params folder, instead of the one of the config").required(false)),
+        )
+        .subcommand(
+            Command::new("verify-build")
+                .about("Compares this binary with a signed release manifest")
+                .arg(arg!(-m --manifest <FILE> "Release manifest to compare with").required(false))
+                .arg(arg!(--signer <ADDRESS> "Address of the release key the manifest must be signed by").required(false))
+                .arg(arg!(--binary <FILE> "Binary to hash, the running one by default").required(false))
+                .arg(arg!(--"print-manifest" "Print the manifest of this binary, to be signed for a release")),
+        )
+        .subcommand(
+            Command::new("verify-audit-log")
+                .about("Checks the hash chain of a signing audit log")
+                .arg(arg!(-c --config <FILE> "Node config file, to check its audit_log_path").required(false))
+                .arg(arg!(-f --file <FILE> "Audit log, instead of the one of the config").required(false))
+                .arg(arg!(--head <HASH> "Last hash recorded earlier, to detect removed or added entries").required(false)),
+        )
+        .subcommand(
+            Command::new("export-slices")
+                .about("Exports the stored slices to a portable archive, the node must be stopped")
+                .arg(arg!(-c --config <FILE> "Node config file"))
+                .arg(arg!(-o --output <FILE> "Archive file to write"))
+                .arg(arg!(--"from-epoch" <EPOCH> "First epoch to export, 0 by default").required(false))
+                .arg(arg!(--"to-epoch" <EPOCH> "Last epoch to export, the latest one by default").required(false)),
+        )
+        .subcommand(
+            Command::new("import-slices")
+                .about("Verifies the slices of an archive and imports the missing ones, the node must be stopped")
+                .arg(arg!(-c --config <FILE> "Node config file"))
+                .arg(arg!(-f --file <FILE> "Archive file written by export-slices")),
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Describes the key layouts and value encodings of the database")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("dump")
+                        .about("Prints the key schema registry")
+                        .arg(arg!(--format <FORMAT> "json or markdown").required(false)),
+                ),
+        )
+        .subcommand(
+            Command::new("check-config")
+                .about("Validates a node config against the environment without starting the node")
+                .arg(arg!(-c --config <FILE> "Node config file"))
+                .arg(arg!(--offline "Skip the checks of the rpc endpoints"))
+                .arg(arg!(--format <FORMAT> "text or json").required(false)),
+        )
+        .subcommand(
+            Command::new("bench")
+                .about("Measures the verify and sign throughput and latency across thread counts, to size hardware")
+                .arg(arg!(-p --params <DIR> "Encoder params folder, params/ by default").required(false))
+                .arg(arg!(-f --file <FILE> "Recorded request to take the slices of, a blob is encoded otherwise").required(false))
+                .arg(arg!(--slices <N> "Slices of the encoded blob, 1024 by default").required(false))
+                .arg(arg!(--threads <LIST> "Comma separated thread counts, powers of 2 up to the cores by default").required(false))
+                .arg(arg!(--iterations <N> "Blobs verified per thread count, 10 by default").required(false)),
+        )
+        .allow_external_subcommands(true)
+}
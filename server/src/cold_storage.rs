@@ -0,0 +1,69 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use storage::{
+    cold_storage::{object_key, ColdStorageDB},
+    quorum_db::QuorumDB,
+    Storage,
+};
+use task_executor::TaskExecutor;
+use tokio::time::sleep;
+
+const TIERING_BATCH_SIZE: usize = 64;
+const TIERING_INTERVAL: Duration = Duration::from_secs(60);
+
+pub fn start_cold_storage_tiering(
+    executor: TaskExecutor,
+    db: Arc<Storage>,
+    tier_after_epochs: u64,
+) {
+    executor.spawn(
+        async move {
+            loop {
+                if let Err(e) = tier_old_epochs(&db, tier_after_epochs).await {
+                    error!("cold storage tiering error: {:?}", e);
+                }
+                sleep(TIERING_INTERVAL).await;
+            }
+        },
+        "cold_storage_tiering",
+    );
+}
+
+async fn tier_old_epochs(db: &Storage, tier_after_epochs: u64) -> Result<()> {
+    let latest_epoch = match db.get_latest_epoch().await? {
+        Some(epoch) => epoch,
+        None => return Ok(()),
+    };
+    let store = match db.cold_store() {
+        Some(store) => store,
+        None => return Ok(()),
+    };
+    loop {
+        let epoch = match db.get_first_local_epoch().await? {
+            Some(epoch) if epoch + tier_after_epochs < latest_epoch => epoch,
+            _ => return Ok(()),
+        };
+        info!("moving slices of epoch {:?} to cold storage..", epoch);
+        let mut moved = 0;
+        loop {
+            // upload without holding the db lock, slices of old epochs are never updated
+            let batch = db.next_tiering_batch(epoch, TIERING_BATCH_SIZE).await?;
+            if batch.is_empty() {
+                break;
+            }
+            let mut keys = vec![];
+            for (key, value) in batch.into_iter() {
+                store.put_object(&object_key(&key), value).await?;
+                keys.push(key);
+            }
+            moved += keys.len();
+            db.mark_tiered(keys).await?;
+        }
+        db.put_tiered_epoch(epoch).await?;
+        info!(
+            "epoch {:?} moved to cold storage, {:?} entries",
+            epoch, moved
+        );
+    }
+}
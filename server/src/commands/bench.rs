@@ -0,0 +1,82 @@
+use std::{thread::available_parallelism, time::Instant};
+
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use grpc::bench::{run_benchmark, BenchBlob};
+use zg_encoder::ZgSignerParams;
+
+const DEFAULT_SLICES: usize = 1024;
+const DEFAULT_ITERATIONS: usize = 10;
+
+fn parse_usize(matches: &ArgMatches, key: &str, default: usize) -> Result<usize> {
+    match matches.value_of(key) {
+        Some(value) => value
+            .parse()
+            .map_err(|e| anyhow!("invalid --{} {:?}: {:?}", key, value, e)),
+        None => Ok(default),
+    }
+}
+
+/// Thread counts of `--threads`, or the powers of 2 up to the cores and the cores themselves.
+fn thread_counts(matches: &ArgMatches) -> Result<Vec<usize>> {
+    if let Some(list) = matches.value_of("threads") {
+        let threads = list
+            .split(',')
+            .map(|x| match x.trim().parse::<usize>() {
+                Ok(n) if n > 0 => Ok(n),
+                _ => Err(anyhow!("invalid thread count {:?}", x)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        return Ok(threads);
+    }
+    let cores = available_parallelism().map_or(1, |n| n.get());
+    let mut threads: Vec<usize> = (0..).map(|i| 1 << i).take_while(|n| *n < cores).collect();
+    threads.push(cores);
+    Ok(threads)
+}
+
+/// Verify and sign sample slices as the node does and print a report per thread count.
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let params_dir = matches.value_of("params").unwrap_or("params/");
+    let threads = thread_counts(matches)?;
+    let iterations = parse_usize(matches, "iterations", DEFAULT_ITERATIONS)?;
+    if iterations == 0 {
+        bail!(anyhow!("--iterations must be positive"));
+    }
+
+    let ts = Instant::now();
+    let blob = match matches.value_of("file") {
+        Some(file) => BenchBlob::load(file)?,
+        None => {
+            let slices = parse_usize(matches, "slices", DEFAULT_SLICES)?;
+            println!("encoding a sample blob with the params of {:?}", params_dir);
+            BenchBlob::generate(params_dir, slices)?
+        }
+    };
+    let params = ZgSignerParams::from_dir_mont(params_dir);
+    println!(
+        "loaded {} slices and the params in {} ms, {} iterations per thread count",
+        blob.encoded_slices.len(),
+        ts.elapsed().as_millis(),
+        iterations
+    );
+
+    let results = run_benchmark(&params, &blob, &threads, iterations)?;
+    println!(
+        "{:>8}{:>12}{:>12}{:>12}{:>12}{:>12}{:>14}",
+        "threads", "mean ms", "p50 ms", "p99 ms", "max ms", "blobs/s", "slices/s"
+    );
+    for result in results {
+        println!(
+            "{:>8}{:>12.1}{:>12.1}{:>12.1}{:>12.1}{:>12.2}{:>14.0}",
+            result.threads,
+            result.mean.as_secs_f64() * 1000.0,
+            result.p50.as_secs_f64() * 1000.0,
+            result.p99.as_secs_f64() * 1000.0,
+            result.max.as_secs_f64() * 1000.0,
+            result.blobs_per_sec(),
+            result.slices_per_sec()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,475 @@
+use std::{
+    fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Result};
+use ark_ff::Zero;
+use chain_state::{forks::ForkSchedule, signers_handler::bls_pub_key_g1};
+use chain_utils::eth_signer::EthKey;
+use clap::ArgMatches;
+use ethers::{
+    providers::{Http, Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{H160, H256},
+};
+use grpc::{audit_log::verify_audit_log, GrpcTlsConfig};
+use serde::Serialize;
+use server::{params::KNOWN_PARAMS, Config};
+use storage::encryption::Keyring;
+use tokio::time::timeout;
+use tracing::Level;
+
+const RPC_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Ok,
+    Warn,
+    Error,
+}
+
+#[derive(Serialize)]
+struct Check {
+    name: String,
+    severity: Severity,
+    detail: String,
+}
+
+#[derive(Default, Serialize)]
+struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    fn push(&mut self, name: impl Into<String>, severity: Severity, detail: impl Into<String>) {
+        self.checks.push(Check {
+            name: name.into(),
+            severity,
+            detail: detail.into(),
+        });
+    }
+
+    /// Record the outcome of a check, its detail on success or its error.
+    fn check(&mut self, name: impl Into<String>, result: Result<String>) {
+        match result {
+            Ok(detail) => self.push(name, Severity::Ok, detail),
+            Err(e) => self.push(name, Severity::Error, format!("{:#}", e)),
+        }
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.checks
+            .iter()
+            .filter(|x| x.severity == severity)
+            .count()
+    }
+
+    fn print(&self) {
+        for check in &self.checks {
+            let severity = match check.severity {
+                Severity::Ok => "ok",
+                Severity::Warn => "WARN",
+                Severity::Error => "ERROR",
+            };
+            println!("{:<6}{:<32}{}", severity, check.name, check.detail);
+        }
+        println!(
+            "{} checks, {} warnings, {} errors",
+            self.checks.len(),
+            self.count(Severity::Warn),
+            self.count(Severity::Error)
+        );
+    }
+}
+
+fn eth_address(key: &H256) -> Result<H160> {
+    Ok(LocalWallet::from_bytes(&key[..])
+        .map_err(|e| anyhow!("invalid private key: {:?}", e))?
+        .address())
+}
+
+fn eth_key(key: &EthKey) -> Result<String> {
+    match key {
+        EthKey::Private(key) => eth_address(key).map(|x| format!("account {:?}", x)),
+        EthKey::External { url, address, .. } => {
+            Ok(format!("external signer {} of account {:?}", url, address))
+        }
+    }
+}
+
+fn listen_address(address: &str) -> Result<String> {
+    SocketAddr::from_str(address).map_err(|e| anyhow!("{:?} is not ip:port: {:?}", address, e))?;
+    Ok(address.to_string())
+}
+
+fn tls_certificate(tls: &GrpcTlsConfig) -> Result<String> {
+    tls.load()?;
+    Ok(match &tls.client_ca_path {
+        Some(path) => format!("clients authenticated by {:?}", path),
+        None => "clients not authenticated".to_string(),
+    })
+}
+
+/// Check that `path` is a writable folder, or can be created as one.
+fn writable_dir(path: &Path) -> Result<String> {
+    if !path.exists() {
+        let parent = path
+            .ancestors()
+            .skip(1)
+            .find(|x| x.exists())
+            .ok_or_else(|| anyhow!("no existing parent"))?;
+        writable_dir(parent)?;
+        return Ok(format!("{:?} will be created", path));
+    }
+    if !path.is_dir() {
+        bail!(anyhow!("{:?} is not a folder", path));
+    }
+    let probe = path.join(".check-config-probe");
+    fs::write(&probe, b"").map_err(|e| anyhow!("{:?} is not writable: {:?}", path, e.kind()))?;
+    fs::remove_file(&probe)?;
+    Ok(format!("{:?} is writable", path))
+}
+
+fn check_keys(report: &mut Report, config: &Config) {
+    report.check(
+        "signer_bls_private_key",
+        if config.signer_bls_private_key.is_zero() {
+            Err(anyhow!("key is zero"))
+        } else {
+            Ok(format!(
+                "public key G1 {:?}",
+                bls_pub_key_g1(config.signer_bls_private_key)
+            ))
+        },
+    );
+    if let Some(key) = config.new_signer_bls_private_key {
+        report.check(
+            "new_signer_bls_private_key",
+            if key.is_zero() {
+                Err(anyhow!("key is zero"))
+            } else {
+                Ok(format!("public key G1 {:?}", bls_pub_key_g1(key)))
+            },
+        );
+    }
+    report.check("signer_eth_private_key", eth_key(&config.signer_eth_key));
+    // only read with DAS enabled
+    if config.enable_das {
+        report.check("miner_eth_private_key", eth_key(&config.miner_eth_key));
+    }
+}
+
+fn check_addresses(report: &mut Report, config: &Config) {
+    report.check(
+        "da_entrance_address",
+        if config.da_entrance_address.is_zero() {
+            Err(anyhow!("address is zero"))
+        } else {
+            Ok(format!("{:?}", config.da_entrance_address))
+        },
+    );
+    report.check(
+        "grpc_listen_address",
+        listen_address(&config.grpc_listen_address),
+    );
+    if let Some(tls) = &config.grpc_tls {
+        report.check("grpc_tls", tls_certificate(tls));
+    }
+    for (i, listener) in config.grpc_listeners.iter().enumerate() {
+        report.check(
+            format!("grpc_listeners.{}", i),
+            match &listener.tls {
+                Some(tls) => tls_certificate(tls)
+                    .map(|tls| format!("{} over tls, {}", listener.address, tls)),
+                None => Ok(format!("{} in plaintext", listener.address)),
+            },
+        );
+    }
+    if let Some(address) = &config.admin_listen_address {
+        match SocketAddr::from_str(address) {
+            Ok(addr) if !addr.ip().is_loopback() => report.push(
+                "admin_listen_address",
+                Severity::Warn,
+                format!("{} is not a loopback address, keep it private", address),
+            ),
+            _ => report.check("admin_listen_address", listen_address(address)),
+        }
+    }
+    if let Some(address) = &config.grpc_runtimes.retrieval_listen_address {
+        report.check("retrieval_listen_address", listen_address(address));
+    }
+
+    let socket = &config.socket_address;
+    match socket.rsplit_once(':').map(|(_, port)| port.parse::<u16>()) {
+        _ if socket.contains('<') => {
+            report.push(
+                "socket_address",
+                Severity::Error,
+                format!("{:?} is a placeholder", socket),
+            );
+        }
+        Some(Ok(port)) => {
+            let listen_port = SocketAddr::from_str(&config.grpc_listen_address)
+                .ok()
+                .map(|x| x.port());
+            if listen_port.map_or(false, |x| x != port) {
+                report.push(
+                    "socket_address",
+                    Severity::Warn,
+                    format!(
+                        "port {} differs from the grpc listen port, fine only behind a port mapping",
+                        port
+                    ),
+                );
+            } else {
+                report.push("socket_address", Severity::Ok, socket.clone());
+            }
+        }
+        _ => report.push(
+            "socket_address",
+            Severity::Error,
+            format!("{:?} is not host:port", socket),
+        ),
+    }
+}
+
+fn check_params(report: &mut Report, config: &Config) {
+    let dir = &config.encoder_params_dir;
+    let missing: Vec<&str> = KNOWN_PARAMS
+        .iter()
+        .map(|(file, _)| *file)
+        .filter(|file| config.enable_slice_repair || !file.starts_with("amt-prove"))
+        .filter(|file| !Path::new(dir).join(file).exists())
+        .collect();
+    if missing.is_empty() {
+        report.push(
+            "encoder_params_dir",
+            Severity::Ok,
+            format!("{:?} has the params", dir),
+        );
+    } else if config.params_download.is_some() {
+        report.push(
+            "encoder_params_dir",
+            Severity::Warn,
+            format!("{} files missing, downloaded on start", missing.len()),
+        );
+    } else {
+        report.push(
+            "encoder_params_dir",
+            Severity::Error,
+            format!("{:?} misses {}", dir, missing.join(", ")),
+        );
+    }
+    // later versions are of other setups, only their presence is checked
+    for version in &config.params_versions {
+        let files = fs::read_dir(&version.dir).map(|entries| {
+            entries
+                .filter_map(|x| x.ok())
+                .filter(|x| x.file_name().to_string_lossy().starts_with("amt-verify"))
+                .count()
+        });
+        report.check(
+            format!("encoder_params_versions.{}", version.version),
+            match files {
+                Ok(0) => Err(anyhow!("{:?} has no verify params", version.dir)),
+                Ok(files) => Ok(format!("{:?} has {} verify params", version.dir, files)),
+                Err(e) => Err(anyhow!("cannot read {:?}: {:?}", version.dir, e.kind())),
+            },
+        );
+    }
+}
+
+fn check_paths(report: &mut Report, config: &Config) {
+    report.check("data_path", writable_dir(Path::new(&config.data_path)));
+    if let Some(dir) = &config.request_dump_dir {
+        report.check("request_dump_dir", writable_dir(Path::new(dir)));
+    }
+    if let Some(path) = &config.audit_log_path {
+        let path = Path::new(path);
+        report.check(
+            "audit_log_path",
+            if path.exists() {
+                verify_audit_log(path)
+                    .map(|head| format!("{:?} has {} chained entries", path, head.entries))
+            } else {
+                writable_dir(
+                    &path
+                        .parent()
+                        .map_or_else(|| PathBuf::from("."), Path::to_path_buf),
+                )
+            },
+        );
+    }
+    if let Some(log_file) = &config.log_file {
+        let dir = Path::new(&log_file.path)
+            .parent()
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf);
+        report.check("log_file.path", writable_dir(&dir));
+    }
+    if let Some(path) = &config.fork_schedule_path {
+        report.check(
+            "fork_schedule_path",
+            ForkSchedule::from_file(path).map(|_| format!("{:?} parsed", path)),
+        );
+    }
+    if let Some(encryption) = &config.encryption {
+        report.check(
+            "encryption",
+            Keyring::load(encryption).map(|_| format!("{} keys loaded", encryption.keys.len())),
+        );
+    }
+}
+
+fn check_instances(report: &mut Report, config: &Config) {
+    let mut data_paths = vec![config.data_path.as_str()];
+    let extra_listen_addresses: Vec<String> = config
+        .grpc_listeners
+        .iter()
+        .map(|x| x.address.to_string())
+        .collect();
+    let mut listen_addresses = vec![config.grpc_listen_address.as_str()];
+    listen_addresses.extend(extra_listen_addresses.iter().map(String::as_str));
+    for identity in &config.identities {
+        let name = format!("identities.{}", identity.socket_address);
+        report.check(
+            name.clone(),
+            eth_address(&identity.signer_eth_private_key)
+                .and_then(|_| listen_address(&identity.grpc_listen_address))
+                .map(|_| "keys and listen address are valid".to_string()),
+        );
+        report.check(
+            format!("{}.data_path", name),
+            writable_dir(Path::new(&identity.data_path)),
+        );
+        data_paths.push(&identity.data_path);
+        listen_addresses.push(&identity.grpc_listen_address);
+    }
+    for network in &config.networks {
+        report.check(
+            format!("networks.{}.data_path", network.name),
+            writable_dir(Path::new(&network.data_path)),
+        );
+        data_paths.push(&network.data_path);
+    }
+    for (name, values) in [
+        ("data paths", data_paths),
+        ("grpc listen addresses", listen_addresses),
+    ] {
+        let mut sorted = values.clone();
+        sorted.sort();
+        sorted.dedup();
+        if sorted.len() != values.len() {
+            report.push(
+                name,
+                Severity::Error,
+                "shared by several identities or networks",
+            );
+        }
+    }
+}
+
+/// Chain id and head of the rpc, and whether the DA entrance contract is deployed on it.
+async fn check_rpc(url: &str, da_entrance_address: H160) -> Result<String> {
+    let provider = Provider::<Http>::try_from(url)?;
+    let (chain_id, block, code) = timeout(RPC_TIMEOUT, async {
+        Ok::<_, anyhow::Error>((
+            provider.get_chainid().await?,
+            provider.get_block_number().await?,
+            provider.get_code(da_entrance_address, None).await?,
+        ))
+    })
+    .await
+    .map_err(|_| anyhow!("{} did not answer in {:?}", url, RPC_TIMEOUT))??;
+    if code.is_empty() {
+        bail!(anyhow!(
+            "no contract at {:?} on chain {}",
+            da_entrance_address,
+            chain_id
+        ));
+    }
+    Ok(format!("chain {}, block {}", chain_id, block))
+}
+
+async fn check_rpcs(report: &mut Report, config: &Config) {
+    let result = match &config.mock_chain {
+        Some(mock) => Ok(format!(
+            "mock chain {} listening on {}",
+            mock.chain_id, mock.listen_address
+        )),
+        None => check_rpc(&config.eth_rpc_url, config.da_entrance_address).await,
+    };
+    match result {
+        Err(e) if config.storage_only_fallback => report.push(
+            "eth_rpc_endpoint",
+            Severity::Warn,
+            format!("{}, the node starts storage-only", e),
+        ),
+        result => report.check("eth_rpc_endpoint", result),
+    }
+    for network in &config.networks {
+        report.check(
+            format!("networks.{}.eth_rpc_endpoint", network.name),
+            check_rpc(&network.eth_rpc_url, network.da_entrance_address).await,
+        );
+    }
+}
+
+/// Parse a node config and check its values against the environment, without starting anything.
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let config_file = matches.value_of("config").unwrap();
+    let mut report = Report::default();
+    match Config::from_file(config_file) {
+        Ok(config) => {
+            report.push("config", Severity::Ok, format!("{:?} parsed", config_file));
+            report.check(
+                "log_level",
+                Level::from_str(&config.log_level)
+                    .map(|x| x.to_string())
+                    .map_err(|e| anyhow!("{:?}", e)),
+            );
+            check_keys(&mut report, &config);
+            check_addresses(&mut report, &config);
+            check_params(&mut report, &config);
+            check_paths(&mut report, &config);
+            check_instances(&mut report, &config);
+            if !matches.is_present("offline") {
+                tokio::runtime::Runtime::new()?.block_on(check_rpcs(&mut report, &config));
+            }
+        }
+        Err(e) => report.push("config", Severity::Error, format!("{:#}", e)),
+    }
+
+    match matches.value_of("format").unwrap_or("text") {
+        "text" => report.print(),
+        "json" => println!("{}", serde_json::to_string_pretty(&report)?),
+        format => bail!(anyhow!("Unknown format `{}`", format)),
+    }
+    let errors = report.count(Severity::Error);
+    if errors > 0 {
+        bail!(anyhow!("{} errors found in the config", errors));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writable_dir_test() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().join("data");
+        assert!(writable_dir(&dir.join("db"))
+            .unwrap()
+            .contains("will be created"));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("file"), b"").unwrap();
+        assert!(writable_dir(&dir).is_ok());
+        assert!(writable_dir(&dir.join("file")).is_err());
+    }
+}
@@ -0,0 +1,22 @@
+use anyhow::Result;
+use chain_state::signers_handler::{bls_pub_key_g1, bls_pub_key_g2};
+use clap::ArgMatches;
+use server::keystore::{load_bls_keystore, read_password_file};
+
+/// Load the signer BLS key of a keystore as the node does with `signer_bls_keystore`, and print
+/// its public keys to check them against the registered ones.
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let file = matches.value_of("file").unwrap();
+    let password = read_password_file(matches.value_of("password-file").unwrap())?;
+    let bls_key = load_bls_keystore(file, &password)?;
+    println!("BLS public key G1: {:?}", bls_pub_key_g1(bls_key));
+    println!("BLS public key G2: {:?}", bls_pub_key_g2(bls_key));
+    if matches.is_present("show-secret") {
+        println!("# config values, keep them out of shell history and logs");
+        println!("signer_bls_private_key = \"{}\"", bls_key);
+    } else {
+        println!("# config values");
+        println!("signer_bls_keystore = {:?}", file);
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use server::Config;
+use storage::{
+    blob_status_db::BlobStatusDB,
+    quorum_db::{AssignedSlices, QuorumDB},
+    schema::COLUMN_NAMES,
+    scrub_db::ScrubDB,
+    slice_db::{SliceDB, SliceIndex},
+    Storage,
+};
+
+use super::open_db;
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let (name, sub_matches) = matches
+        .subcommand()
+        .ok_or_else(|| anyhow!("Missing inspect-db subcommand"))?;
+    let config = Config::from_file(sub_matches.value_of("config").unwrap())?;
+    let db = open_db(&config)?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    let epoch = || -> Result<u64> { Ok(u64::from_str(sub_matches.value_of("epoch").unwrap())?) };
+    let number = |key| -> Result<Option<u64>> {
+        Ok(sub_matches.value_of(key).map(u64::from_str).transpose()?)
+    };
+    match name {
+        "blobs" => runtime.block_on(list_blobs(&db, epoch()?, number("quorum")?)),
+        "assigned" => runtime.block_on(show_assigned(&db, epoch()?, number("quorum")?.unwrap())),
+        "slice" => {
+            let storage_root: [u8; 32] = hex::decode(
+                sub_matches
+                    .value_of("root")
+                    .unwrap()
+                    .trim_start_matches("0x"),
+            )?
+            .try_into()
+            .map_err(|_| anyhow!("storage root must be 32 bytes"))?;
+            let index = SliceIndex {
+                epoch: epoch()?,
+                quorum_id: number("quorum")?.unwrap(),
+                storage_root,
+                index: number("index")?.unwrap(),
+            };
+            runtime.block_on(show_slice(&db, &index))
+        }
+        "stats" => show_stats(&db),
+        _ => bail!(anyhow!("Unknown inspect-db subcommand `{}`", name)),
+    }
+}
+
+async fn list_blobs(db: &Storage, epoch: u64, quorum_id: Option<u64>) -> Result<()> {
+    let stored = db.get_epoch_info(epoch).await?;
+    let mut blobs = db.get_epoch_blobs(epoch).await?;
+    blobs.retain(|(quorum, _, _)| quorum_id.map_or(true, |x| x == *quorum));
+    blobs.sort_by_key(|(quorum, storage_root, _)| (*quorum, *storage_root));
+    println!("epoch {}: {} blobs", epoch, blobs.len());
+    for (quorum, storage_root, status) in blobs {
+        let slices = stored
+            .iter()
+            .find(|x| x.quorum_id == quorum && x.storage_root == storage_root)
+            .map_or(0, |x| x.indicies.len());
+        println!(
+            "quorum {} root 0x{} status {:?} slices {}",
+            quorum,
+            hex::encode(storage_root),
+            status,
+            slices
+        );
+    }
+    Ok(())
+}
+
+async fn show_assigned(db: &Storage, epoch: u64, quorum_id: u64) -> Result<()> {
+    let quorum_num = db.get_quorum_num(epoch).await?;
+    println!("epoch {}: {:?} quorums", epoch, quorum_num);
+    match db.get_assgined_slices(epoch, quorum_id).await? {
+        Some(AssignedSlices(slices)) => {
+            println!("quorum {}: {} assigned slices", quorum_id, slices.len());
+            println!("{:?}", slices);
+        }
+        None => println!("quorum {}: no assignment stored", quorum_id),
+    }
+    Ok(())
+}
+
+async fn show_slice(db: &Storage, index: &SliceIndex) -> Result<()> {
+    println!("{:?}", index);
+    let (epoch, quorum_id, storage_root, row) = (
+        index.epoch,
+        index.quorum_id,
+        index.storage_root,
+        index.index as usize,
+    );
+    match db.get_slice(epoch, quorum_id, storage_root, row).await? {
+        Some(light_slice) => {
+            println!(
+                "merkle roots: {:?}",
+                light_slice
+                    .merkle_root
+                    .map(|x| format!("0x{}", hex::encode(x)))
+            );
+            println!("merkle proof: {} nodes", light_slice.merkle_proof.len());
+        }
+        None => println!("slice not stored"),
+    }
+    match db
+        .get_slice_data(epoch, quorum_id, storage_root, row)
+        .await?
+    {
+        Some(data) => println!("data: {} bytes", data.len() * 32),
+        None => println!("data not stored"),
+    }
+    println!("corrupt: {}", db.is_corrupt_slice(index).await?);
+    Ok(())
+}
+
+fn show_stats(db: &Storage) -> Result<()> {
+    println!("{:<16}{:>14}{:>18}", "column", "keys", "bytes");
+    for (col, stats) in db.column_stats()?.iter().enumerate() {
+        println!(
+            "{:<16}{:>14}{:>18}",
+            COLUMN_NAMES.get(col).copied().unwrap_or("unknown"),
+            stats.keys,
+            stats.bytes
+        );
+    }
+    println!("on disk: {} bytes", db.disk_size()?);
+    Ok(())
+}
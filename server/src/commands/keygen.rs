@@ -0,0 +1,94 @@
+use std::{fs, path::Path, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::Fr;
+use ark_ff::{BigInteger, PrimeField, UniformRand};
+use chain_state::signers_handler::{bls_pub_key_g1, bls_pub_key_g2, signer_registration_calldata};
+use chain_utils::DA_SIGNER_ADDRESS;
+use clap::ArgMatches;
+use ethers::{
+    core::rand::thread_rng,
+    signers::{LocalWallet, Signer},
+    types::H160,
+    utils::hex,
+};
+
+const BLS_KEYSTORE_NAME: &str = "signer_bls_keystore.json";
+const ETH_KEYSTORE_NAME: &str = "signer_eth_keystore.json";
+
+/// Generate a signer BLS key, and an ETH key with `--eth`, into password encrypted keystores, then
+/// print what is needed to register the signer.
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let output = Path::new(matches.value_of("output").unwrap());
+    let password = fs::read_to_string(matches.value_of("password-file").unwrap())?;
+    let password = password.trim_end_matches(['\n', '\r']);
+    if password.is_empty() {
+        bail!(anyhow!("keystore password is empty"));
+    }
+    fs::create_dir_all(output)?;
+    for name in [BLS_KEYSTORE_NAME, ETH_KEYSTORE_NAME] {
+        if output.join(name).exists() {
+            bail!(anyhow!(
+                "{:?} already exists, refusing to overwrite a key",
+                output.join(name)
+            ));
+        }
+    }
+
+    let mut rng = thread_rng();
+    let bls_key = Fr::rand(&mut rng);
+    // web3 secret storage of the big endian key, the account of the keystore is meaningless
+    LocalWallet::encrypt_keystore(
+        output,
+        &mut rng,
+        bls_key.into_bigint().to_bytes_be(),
+        password,
+        Some(BLS_KEYSTORE_NAME),
+    )?;
+    println!("BLS keystore: {:?}", output.join(BLS_KEYSTORE_NAME));
+    println!("BLS public key G1: {:?}", bls_pub_key_g1(bls_key));
+    println!("BLS public key G2: {:?}", bls_pub_key_g2(bls_key));
+
+    let mut signer_address = matches
+        .value_of("signer-address")
+        .map(H160::from_str)
+        .transpose()?;
+    let mut eth_key = None;
+    if matches.is_present("eth") {
+        let (wallet, _) =
+            LocalWallet::new_keystore(output, &mut rng, password, Some(ETH_KEYSTORE_NAME))?;
+        println!("ETH keystore: {:?}", output.join(ETH_KEYSTORE_NAME));
+        println!("ETH account: {:?}", wallet.address());
+        signer_address = Some(wallet.address());
+        eth_key = Some(wallet.signer().to_bytes());
+    }
+
+    if matches.is_present("show-secret") {
+        println!("# config values, keep them out of shell history and logs");
+        println!("signer_bls_private_key = \"{}\"", bls_key);
+        if let Some(eth_key) = eth_key {
+            println!("signer_eth_private_key = \"{}\"", hex::encode(eth_key));
+        }
+    }
+
+    match (signer_address, matches.value_of("chain-id")) {
+        (Some(signer_address), Some(chain_id)) => {
+            let calldata = signer_registration_calldata(
+                bls_key,
+                signer_address,
+                matches.value_of("socket").unwrap_or_default().to_string(),
+                u64::from_str(chain_id)?,
+            );
+            println!(
+                "registration: send to {} from {:?} the calldata 0x{}",
+                DA_SIGNER_ADDRESS,
+                signer_address,
+                hex::encode(calldata)
+            );
+        }
+        _ => println!(
+            "the node registers the signer on start, set --chain-id and --eth or --signer-address to print the registration calldata"
+        ),
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+mod bench;
+mod check_config;
+mod import_keystore;
+mod inspect_db;
+mod keygen;
+mod recover;
+mod replay_request;
+mod schema;
+mod slice_archive;
+mod verify_audit_log;
+mod verify_build;
+mod verify_params;
+
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use server::Config;
+use storage::{cold_storage::make_object_store, encryption::Keyring, Storage};
+
+pub fn run_command(name: &str, matches: &ArgMatches) -> Result<()> {
+    match name {
+        "replay-request" => replay_request::run(matches),
+        "recover" => recover::run(matches),
+        "inspect-db" => inspect_db::run(matches),
+        "keygen" => keygen::run(matches),
+        "import-keystore" => import_keystore::run(matches),
+        "export-slices" => slice_archive::run_export(matches),
+        "import-slices" => slice_archive::run_import(matches),
+        "verify-params" => verify_params::run(matches),
+        "verify-build" => verify_build::run(matches),
+        "verify-audit-log" => verify_audit_log::run(matches),
+        "schema" => schema::run(matches),
+        "check-config" => check_config::run(matches),
+        "bench" => bench::run(matches),
+        _ => bail!(anyhow!("Unknown subcommand `{}`", name)),
+    }
+}
+
+/// Open the database of the node as the node does, so encrypted and tiered slices are readable.
+pub(crate) fn open_db(config: &Config) -> Result<Storage> {
+    let mut db = Storage::new(&config.data_path)
+        .map_err(|e| anyhow!("Cannot open db, stop the node first: {:?}", e))?;
+    if let Some(cold_storage) = &config.cold_storage {
+        db = db.with_cold_store(make_object_store(&cold_storage.store)?);
+    }
+    let keyring = match &config.encryption {
+        Some(encryption) => Some(Keyring::load(encryption)?),
+        None => None,
+    };
+    db.with_encryption(keyring)
+}
@@ -0,0 +1,294 @@
+use std::{fs, io::Write, str::FromStr};
+
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::Fr;
+use chain_state::recovery::Recovery;
+use chain_utils::{
+    eth_signer::EthKey, gas::GasStrategy, make_provider, nonce_manager::NonceManager,
+};
+use clap::ArgMatches;
+use ethers::types::{transaction::eip2718::TypedTransaction, H256};
+use server::Config;
+use storage::{quorum_db::QuorumDB, Storage};
+use tracing_subscriber::EnvFilter;
+
+const IDENTITY_KEYS: [&str; 2] = ["signer_bls_private_key", "signer_eth_private_key"];
+const DEFAULT_RESYNC_EPOCHS: u64 = 3;
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_new("info")?)
+        .init();
+
+    let (name, sub_matches) = matches
+        .subcommand()
+        .ok_or_else(|| anyhow!("Missing recover subcommand"))?;
+    let config_file = sub_matches.value_of("config").unwrap();
+    let runtime = tokio::runtime::Runtime::new()?;
+    match name {
+        "backup" => backup(config_file, sub_matches.value_of("output").unwrap()),
+        "restore" => runtime.block_on(restore(
+            config_file,
+            sub_matches.value_of("file").unwrap(),
+            sub_matches.is_present("force"),
+        )),
+        "verify" => runtime.block_on(verify(&Config::from_file(config_file)?)),
+        "socket" => runtime.block_on(check_socket(
+            &Config::from_file(config_file)?,
+            sub_matches.is_present("update"),
+        )),
+        "resync-assignments" => runtime.block_on(resync_assignments(
+            &Config::from_file(config_file)?,
+            sub_matches
+                .value_of("epochs")
+                .map(u64::from_str)
+                .transpose()?
+                .unwrap_or(DEFAULT_RESYNC_EPOCHS),
+        )),
+        _ => bail!(anyhow!("Unknown recover subcommand `{}`", name)),
+    }
+}
+
+/// Copy the identity keys of the node to `output`, in the config file format.
+fn backup(config_file: &str, output: &str) -> Result<()> {
+    let raw = read_raw_config(config_file)?;
+    let mut content = "# signer identity backup, restore it with `recover restore`\n".to_string();
+    for key in IDENTITY_KEYS {
+        content += &format!("{} = {:?}\n", key, raw.get_string(key)?);
+    }
+    write_private(output, &content)?;
+    info!(
+        "identity keys backed up to {:?}, keep the file offline",
+        output
+    );
+    Ok(())
+}
+
+/// Put the keys of a backup into the config file, once the chain confirms they are the registered
+/// identity. The previous config file is kept aside.
+async fn restore(config_file: &str, backup_file: &str, force: bool) -> Result<()> {
+    let config = Config::from_file(config_file)?;
+    let backup = read_raw_config(backup_file)?;
+    let bls_key = backup.get_string(IDENTITY_KEYS[0])?;
+    let eth_key = backup.get_string(IDENTITY_KEYS[1])?;
+    let bls_private_key =
+        Fr::from_str(&bls_key).map_err(|e| anyhow!("Invalid bls key in backup: {:?}", e))?;
+    let eth_private_key = H256::from_str(&eth_key)?;
+
+    let provider = make_provider(&config.eth_rpc_url, &EthKey::Private(eth_private_key)).await?;
+    let recovery = Recovery::new(provider.clone(), provider.address());
+    let check = recovery.check_registration(bls_private_key).await?;
+    info!(account = ?provider.address(), ?check, "registration of the backup identity");
+    if (!check.registered || !check.key_matches) && !force {
+        bail!(anyhow!(
+            "the backup does not match the on-chain registration of {:?}, use --force to restore it anyway",
+            provider.address()
+        ));
+    }
+
+    let content = fs::read_to_string(config_file)?;
+    let saved = format!("{}.bak", config_file);
+    write_private(&saved, &content)?;
+    let content = set_config_value(&content, IDENTITY_KEYS[0], &bls_key);
+    let content = set_config_value(&content, IDENTITY_KEYS[1], &eth_key);
+    fs::write(config_file, content)?;
+    info!(
+        "identity keys restored into {:?}, previous config saved to {:?}",
+        config_file, saved
+    );
+    Ok(())
+}
+
+async fn verify(config: &Config) -> Result<()> {
+    let provider = make_provider(&config.eth_rpc_url, &config.signer_eth_key).await?;
+    let recovery = Recovery::new(provider.clone(), provider.address());
+    let check = recovery
+        .check_registration(config.signer_bls_private_key)
+        .await?;
+    if !check.registered {
+        bail!(anyhow!(
+            "account {:?} is not a registered signer, the node registers it on start",
+            provider.address()
+        ));
+    }
+    if !check.key_matches {
+        bail!(anyhow!(
+            "signer_bls_private_key does not match the key registered for {:?}, restore the key from a backup",
+            provider.address()
+        ));
+    }
+    if check.socket.as_deref() != Some(config.socket_address.as_str()) {
+        warn!(
+            "registered socket {:?} differs from socket_address {:?}, run `recover socket --update`",
+            check.socket, config.socket_address
+        );
+    }
+    info!(account = ?provider.address(), "on-chain registration matches the local keys");
+    Ok(())
+}
+
+/// Compare the registered socket with the configured one and with the sockets of the other signers.
+async fn check_socket(config: &Config, update: bool) -> Result<()> {
+    let provider = make_provider(&config.eth_rpc_url, &config.signer_eth_key).await?;
+    let recovery = Recovery::new(provider.clone(), provider.address());
+    let epoch = recovery.current_epoch().await?;
+    let conflicts = recovery
+        .socket_conflicts(epoch, &config.socket_address)
+        .await?;
+    if !conflicts.is_empty() {
+        bail!(anyhow!(
+            "signers {:?} are registered with socket {:?} too, make sure no old host of this node runs under another account, or change socket_address",
+            conflicts,
+            config.socket_address
+        ));
+    }
+    let check = recovery
+        .check_registration(config.signer_bls_private_key)
+        .await?;
+    if !check.registered || check.socket.as_deref() == Some(config.socket_address.as_str()) {
+        info!("no socket conflict");
+        return Ok(());
+    }
+    if !update {
+        bail!(anyhow!(
+            "registered socket {:?} differs from socket_address {:?}, run with --update to register the configured one",
+            check.socket,
+            config.socket_address
+        ));
+    }
+    let nonce_manager = NonceManager::new(provider, GasStrategy::new(config.gas.clone()));
+    let tx = recovery.update_socket_tx(config.socket_address.clone())?;
+    let sent = nonce_manager.send(TypedTransaction::Legacy(tx)).await?;
+    match nonce_manager.confirm(sent).await? {
+        Some(receipt) if receipt.status.map_or(false, |x| x.as_u64() == 1) => {
+            info!("socket updated to {:?}", config.socket_address);
+            Ok(())
+        }
+        _ => bail!(anyhow!("socket update failed")),
+    }
+}
+
+/// Fetch the slice assignments of the latest epochs from chain again, replacing the stored ones.
+async fn resync_assignments(config: &Config, epochs: u64) -> Result<()> {
+    let provider = make_provider(&config.eth_rpc_url, &config.signer_eth_key).await?;
+    let recovery = Recovery::new(provider.clone(), provider.address());
+    let db = Storage::new(&config.data_path)
+        .map_err(|e| anyhow!("Cannot open db, stop the node first: {:?}", e))?;
+    let epoch = recovery.current_epoch().await?;
+    for epoch in epoch.saturating_sub(epochs.saturating_sub(1))..=epoch {
+        let assigned = recovery.fetch_assigned_slices(epoch).await?;
+        let slices: usize = assigned.iter().map(|x| x.0.len()).sum();
+        db.put_quorums(epoch, assigned).await?;
+        info!(epoch, slices, "assignments resynced");
+    }
+    Ok(())
+}
+
+/// Write a file only its owner can read, it holds private keys.
+fn write_private(path: &str, content: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content.as_bytes())?;
+    // the mode of an existing file is kept on open
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+fn read_raw_config(file: &str) -> Result<config::Config> {
+    Ok(config::Config::builder()
+        .add_source(config::File::new(file, config::FileFormat::Toml))
+        .build()?)
+}
+
+fn is_table_header(line: &str) -> bool {
+    let line = line.split('#').next().unwrap_or_default().trim();
+    line.starts_with('[') && line.ends_with(']') && !line.contains(',')
+}
+
+/// Replace the value of the top level `key` in a toml file content. A key not set is inserted
+/// above the first table and the comments leading it, as a line after a table header belongs to
+/// the table.
+fn set_config_value(content: &str, key: &str, value: &str) -> String {
+    let line = format!("{} = {:?}", key, value);
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+    let top_level = lines
+        .iter()
+        .position(|x| is_table_header(x))
+        .unwrap_or(lines.len());
+    let found = lines[..top_level]
+        .iter()
+        .position(|x| matches!(x.split_once('='), Some((k, _)) if k.trim() == key));
+    match found {
+        Some(index) => lines[index] = line,
+        None if top_level == lines.len() => lines.push(line),
+        None => {
+            let mut index = top_level;
+            while index > 0
+                && (lines[index - 1].trim().is_empty()
+                    || lines[index - 1].trim_start().starts_with('#'))
+            {
+                index -= 1;
+            }
+            lines.insert(index, line);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_config_value_test() {
+        let content = "log_level = \"info\"\nsigner_bls_private_key = \"1\"\n# signer_eth_private_key = \"\"\n";
+        assert_eq!(
+            set_config_value(content, "signer_bls_private_key", "2"),
+            "log_level = \"info\"\nsigner_bls_private_key = \"2\"\n# signer_eth_private_key = \"\"\n"
+        );
+        assert_eq!(
+            set_config_value(content, "signer_eth_private_key", "3"),
+            format!("{}signer_eth_private_key = \"3\"\n", content)
+        );
+
+        // a missing key must not land in the trailing table
+        let content =
+            "log_level = \"info\"\n\n# chain rpc\n[gas]\nsigner_bls_private_key = \"1\"\n";
+        assert_eq!(
+            set_config_value(content, "signer_bls_private_key", "2"),
+            "log_level = \"info\"\nsigner_bls_private_key = \"2\"\n\n# chain rpc\n[gas]\nsigner_bls_private_key = \"1\"\n"
+        );
+        assert_eq!(
+            set_config_value(
+                "[[grpc_listeners]]\naddress = \"[::]:1\"\n",
+                "log_level",
+                "info"
+            ),
+            "log_level = \"info\"\n[[grpc_listeners]]\naddress = \"[::]:1\"\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_private_test() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml.bak");
+        let path = path.to_str().unwrap();
+        fs::write(path, "old").unwrap();
+        write_private(path, "key = \"1\"\n").unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "key = \"1\"\n");
+        let mode = fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
@@ -0,0 +1,19 @@
+use std::str::FromStr;
+
+use anyhow::Result;
+use clap::ArgMatches;
+use grpc::replay::{load_recorded_request, replay_sign_request};
+use tracing::Level;
+use tracing_subscriber::EnvFilter;
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let log_level = matches.value_of("log-level").unwrap_or("debug");
+    let _ = Level::from_str(log_level)?;
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_new(log_level)?)
+        .init();
+
+    let record = load_recorded_request(matches.value_of("file").unwrap())?;
+    let params_dir = matches.value_of("params").unwrap_or("params/").to_string();
+    replay_sign_request(&record, params_dir)
+}
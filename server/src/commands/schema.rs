@@ -0,0 +1,67 @@
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use storage::schema::{schema_dump, KeySchema, ValueEncoding};
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let (name, sub_matches) = matches
+        .subcommand()
+        .ok_or_else(|| anyhow!("Missing schema subcommand"))?;
+    match name {
+        "dump" => match sub_matches.value_of("format").unwrap_or("json") {
+            "json" => println!("{}", serde_json::to_string_pretty(&schema_dump())?),
+            "markdown" => print!("{}", markdown()),
+            format => bail!(anyhow!("Unknown format `{}`", format)),
+        },
+        _ => bail!(anyhow!("Unknown schema subcommand `{}`", name)),
+    }
+    Ok(())
+}
+
+fn key_layout(schema: &KeySchema) -> String {
+    let mut parts: Vec<String> = schema
+        .prefix
+        .iter()
+        .map(|x| format!("`0x{:02x}`", x))
+        .collect();
+    parts.extend(
+        schema
+            .fields
+            .iter()
+            .map(|x| format!("{} ({})", x.name, x.field_type.name())),
+    );
+    parts.join(" \\| ")
+}
+
+fn value_encoding(value: ValueEncoding) -> String {
+    match value {
+        ValueEncoding::Empty => "empty".to_string(),
+        ValueEncoding::Flag => "`0x01`".to_string(),
+        ValueEncoding::U64Be(ty) => format!("u64_be ({})", ty),
+        ValueEncoding::Bincode(ty) => format!("bincode `{}`", ty),
+        ValueEncoding::Bcs(ty) => format!("bcs `{}`", ty),
+        ValueEncoding::ArkCompressed(ty) => format!("ark compressed `{}`", ty),
+        ValueEncoding::ArkUncompressed(ty) => format!("ark uncompressed `{}`", ty),
+    }
+}
+
+fn markdown() -> String {
+    let dump = schema_dump();
+    let mut out = format!("# Database schema, version {}\n", dump.version);
+    for column in dump.columns {
+        out += &format!("\n## Column {}: {}\n\n", column.column, column.name);
+        out += "| key | layout | value | encrypted | since | description |\n";
+        out += "|---|---|---|---|---|---|\n";
+        for key in column.keys {
+            out += &format!(
+                "| {} | {} | {} | {} | {} | {} |\n",
+                key.name,
+                key_layout(&key),
+                value_encoding(key.value),
+                if key.encrypted { "yes" } else { "no" },
+                key.since_version,
+                key.description
+            );
+        }
+    }
+    out
+}
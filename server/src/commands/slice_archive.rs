@@ -0,0 +1,77 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use server::{
+    archive::{export_slices, import_slices, ArchiveReader, ArchiveWriter},
+    Config,
+};
+use storage::quorum_db::QuorumDB;
+
+use super::open_db;
+
+/// Export the slices of the node with their blob metadata to an archive.
+pub fn run_export(matches: &ArgMatches) -> Result<()> {
+    let config = Config::from_file(matches.value_of("config").unwrap())?;
+    let db = open_db(&config)?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    let from_epoch = matches
+        .value_of("from-epoch")
+        .map(u64::from_str)
+        .transpose()?
+        .unwrap_or(0);
+    let to_epoch = match matches
+        .value_of("to-epoch")
+        .map(u64::from_str)
+        .transpose()?
+    {
+        Some(epoch) => epoch,
+        None => runtime
+            .block_on(db.get_latest_epoch())?
+            .ok_or_else(|| anyhow!("No epoch stored in the database"))?,
+    };
+    if from_epoch > to_epoch {
+        bail!(anyhow!(
+            "from epoch {} is after to epoch {}",
+            from_epoch,
+            to_epoch
+        ));
+    }
+
+    let output = matches.value_of("output").unwrap();
+    let file = File::options().write(true).create_new(true).open(output)?;
+    let mut writer = ArchiveWriter::new(BufWriter::new(file))?;
+    let (blobs, slices) =
+        runtime.block_on(export_slices(&db, &mut writer, from_epoch, to_epoch))?;
+    writer.finish()?.into_inner()?.sync_all()?;
+    println!(
+        "exported {} slices of {} blobs of epochs {}..={} to {}",
+        slices, blobs, from_epoch, to_epoch, output
+    );
+    Ok(())
+}
+
+/// Import the slices of an archive into the database of the node, verifying them first.
+pub fn run_import(matches: &ArgMatches) -> Result<()> {
+    let config = Config::from_file(matches.value_of("config").unwrap())?;
+    let db = open_db(&config)?;
+    let runtime = tokio::runtime::Runtime::new()?;
+    let file = File::open(matches.value_of("file").unwrap())?;
+    let mut reader = ArchiveReader::new(BufReader::new(file))?;
+    let stats = runtime.block_on(import_slices(&db, &mut reader))?;
+    println!(
+        "imported {} slices of {} blobs, {} already stored, {} invalid",
+        stats.imported, stats.blobs, stats.skipped, stats.invalid
+    );
+    if stats.invalid > 0 {
+        bail!(anyhow!(
+            "{} slices failed verification and were not imported",
+            stats.invalid
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use grpc::audit_log::verify_audit_log;
+use server::Config;
+
+/// Check the hash chain of the audit log, and that it ends at `--head` if given.
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let path = match matches.value_of("file") {
+        Some(file) => file.to_string(),
+        None => Config::from_file(
+            matches
+                .value_of("config")
+                .ok_or_else(|| anyhow!("either a config file or an audit log must be given"))?,
+        )?
+        .audit_log_path
+        .ok_or_else(|| anyhow!("audit_log_path is not set in the config"))?,
+    };
+    let head = verify_audit_log(&path).map_err(|e| anyhow!("{}: {:?}", path, e))?;
+    let last_hash = hex::encode(head.last_hash);
+    if let Some(expected) = matches.value_of("head") {
+        if expected.trim_start_matches("0x") != last_hash {
+            bail!(anyhow!(
+                "{}: the chain ends at {}, expected {}, entries were removed or added",
+                path,
+                last_hash,
+                expected
+            ));
+        }
+    }
+    println!("{}: {} entries, chain intact", path, head.entries);
+    println!("last hash: {}", last_hash);
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use ethers::types::{Address, Signature};
+use grpc::build_info;
+use serde::{Deserialize, Serialize};
+use server::params::file_sha256;
+
+/// What a release publishes about a binary. The field order is the one signed.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+struct ReleaseManifest {
+    version: String,
+    git_commit: String,
+    rustc_version: String,
+    features: Vec<String>,
+    target: String,
+    params_version: String,
+    binary_sha256: String,
+}
+
+/// A manifest with the EIP-191 signature of its compact json by the release key.
+#[derive(Serialize, Deserialize)]
+struct SignedManifest {
+    manifest: ReleaseManifest,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+}
+
+fn local_manifest(binary: &Path) -> Result<ReleaseManifest> {
+    let info = build_info();
+    Ok(ReleaseManifest {
+        version: info.version,
+        git_commit: info.git_commit,
+        rustc_version: info.rustc_version,
+        features: info.features,
+        target: info.target,
+        params_version: info.params_version,
+        binary_sha256: file_sha256(binary)?,
+    })
+}
+
+fn verify_signature(signed: &SignedManifest, signer: Address) -> Result<()> {
+    let signature: Signature = signed
+        .signature
+        .as_deref()
+        .ok_or_else(|| anyhow!("the manifest is not signed"))?
+        .parse()
+        .map_err(|e| anyhow!("Cannot parse the manifest signature: {:?}", e))?;
+    let message = serde_json::to_string(&signed.manifest)?;
+    signature
+        .verify(message, signer)
+        .map_err(|e| anyhow!("the manifest is not signed by {:?}: {:?}", signer, e))
+}
+
+/// Compare this binary with a signed release manifest, or print its own manifest to be signed.
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let binary = match matches.value_of("binary") {
+        Some(path) => PathBuf::from(path),
+        None => std::env::current_exe()?,
+    };
+    let info = build_info();
+    println!("binary: {:?}", binary);
+    println!("build: {:?}", info);
+    let local = local_manifest(&binary)?;
+    if matches.is_present("print-manifest") {
+        let unsigned = SignedManifest {
+            manifest: local,
+            signature: None,
+        };
+        println!("{}", serde_json::to_string_pretty(&unsigned)?);
+        return Ok(());
+    }
+
+    let manifest_path = matches
+        .value_of("manifest")
+        .ok_or_else(|| anyhow!("a release manifest must be given"))?;
+    let signer: Address = matches
+        .value_of("signer")
+        .ok_or_else(|| anyhow!("the release signer must be given"))?
+        .parse()
+        .map_err(|e| anyhow!("Cannot parse the release signer: {:?}", e))?;
+    let signed: SignedManifest = serde_json::from_slice(&fs::read(manifest_path)?)
+        .map_err(|e| anyhow!("Cannot parse the release manifest: {:?}", e))?;
+    verify_signature(&signed, signer)?;
+    println!("manifest signed by {:?}", signer);
+
+    let release = &signed.manifest;
+    let checks = [
+        ("version", &local.version, &release.version),
+        ("git commit", &local.git_commit, &release.git_commit),
+        (
+            "rustc version",
+            &local.rustc_version,
+            &release.rustc_version,
+        ),
+        (
+            "features",
+            &local.features.join(","),
+            &release.features.join(","),
+        ),
+        ("target", &local.target, &release.target),
+        (
+            "params version",
+            &local.params_version,
+            &release.params_version,
+        ),
+        (
+            "binary sha256",
+            &local.binary_sha256,
+            &release.binary_sha256,
+        ),
+    ];
+    let mut mismatches = 0;
+    for (name, local, release) in checks {
+        if local == release {
+            println!("{:<16}ok", name);
+        } else {
+            mismatches += 1;
+            println!(
+                "{:<16}MISMATCH binary {:?}, release {:?}",
+                name, local, release
+            );
+        }
+    }
+    if info.git_dirty {
+        mismatches += 1;
+        println!(
+            "{:<16}MISMATCH built from a tree with uncommitted changes",
+            "git tree"
+        );
+    }
+    if mismatches > 0 {
+        bail!(anyhow!(
+            "the binary differs from the release in {} checks",
+            mismatches
+        ));
+    }
+    println!("the binary matches the release");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ethers::signers::{LocalWallet, Signer};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn verify_signature_test() {
+        let wallet: LocalWallet =
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318"
+                .parse()
+                .unwrap();
+        let mut signed = SignedManifest {
+            manifest: local_manifest(&std::env::current_exe().unwrap()).unwrap(),
+            signature: None,
+        };
+        let message = serde_json::to_string(&signed.manifest).unwrap();
+        let signature = wallet.sign_message(message).await.unwrap();
+        signed.signature = Some(signature.to_string());
+        verify_signature(&signed, wallet.address()).unwrap();
+
+        signed.manifest.git_commit = "0".repeat(40);
+        assert!(verify_signature(&signed, wallet.address()).is_err());
+        assert!(verify_signature(&signed, Address::zero()).is_err());
+    }
+}
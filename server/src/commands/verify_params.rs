@@ -0,0 +1,171 @@
+use std::{collections::BTreeSet, fs, panic::catch_unwind, path::Path, time::Instant};
+
+use anyhow::{anyhow, bail, Result};
+use clap::ArgMatches;
+use server::{
+    params::{file_sha256, KNOWN_PARAMS},
+    Config,
+};
+use zg_encoder::{ZgEncoderParams, ZgSignerParams};
+
+const COSETS: u64 = 3;
+
+/// Metadata of a param file, from its name `amt-{kind}-coset{i}[-mont]-{setup}-{depth}-{degree}.bin`.
+#[derive(Debug, PartialEq, Eq)]
+struct ParamsFile {
+    prove: bool,
+    coset: u64,
+    setup: String,
+    depth: u64,
+    degree: u64,
+}
+
+fn parse_file_name(name: &str) -> Option<ParamsFile> {
+    let parts: Vec<&str> = name
+        .strip_prefix("amt-")?
+        .strip_suffix(".bin")?
+        .split('-')
+        .filter(|x| *x != "mont")
+        .collect();
+    if parts.len() != 5 {
+        return None;
+    }
+    Some(ParamsFile {
+        prove: match parts[0] {
+            "prove" => true,
+            "verify" => false,
+            _ => return None,
+        },
+        coset: parts[1].strip_prefix("coset")?.parse().ok()?,
+        setup: parts[2].to_string(),
+        depth: parts[3].parse().ok()?,
+        degree: parts[4].parse().ok()?,
+    })
+}
+
+/// Check the param files of `encoder_params_dir` against the published hashes and with each other,
+/// then load them as the node does.
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let params_dir =
+        match matches.value_of("params") {
+            Some(dir) => dir.to_string(),
+            None => {
+                Config::from_file(matches.value_of("config").ok_or_else(|| {
+                    anyhow!("either a config file or a params folder must be given")
+                })?)?
+                .encoder_params_dir
+            }
+        };
+    let dir = Path::new(&params_dir);
+    println!("params folder: {:?}", dir);
+
+    let mut problems = vec![];
+    let mut files = vec![];
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    println!(
+        "{:<42}{:>8}{:>7}{:>7}{:>8}{:>14}  sha256",
+        "file", "kind", "coset", "depth", "degree", "bytes"
+    );
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let file = match parse_file_name(&name) {
+            Some(file) => file,
+            None => continue,
+        };
+        let size = entry.metadata()?.len();
+        let digest = file_sha256(&entry.path())?;
+        let hash_check = match KNOWN_PARAMS.iter().find(|(known, _)| *known == name) {
+            Some((_, expected)) if *expected == digest => "ok",
+            Some(_) => {
+                problems.push(format!("{} does not match its published hash", name));
+                "MISMATCH"
+            }
+            None => "unknown",
+        };
+        if size == 0 {
+            problems.push(format!("{} is empty", name));
+        }
+        println!(
+            "{:<42}{:>8}{:>7}{:>7}{:>8}{:>14}  {}",
+            name,
+            if file.prove { "prove" } else { "verify" },
+            file.coset,
+            file.depth,
+            file.degree,
+            size,
+            hash_check
+        );
+        files.push(file);
+    }
+
+    let setups: BTreeSet<_> = files
+        .iter()
+        .map(|x| (x.setup.clone(), x.depth, x.degree))
+        .collect();
+    if setups.len() > 1 {
+        problems.push(format!(
+            "files of different setups or degrees are mixed: {:?}",
+            setups
+        ));
+    }
+    for prove in [false, true] {
+        let cosets: BTreeSet<u64> = files
+            .iter()
+            .filter(|x| x.prove == prove)
+            .map(|x| x.coset)
+            .collect();
+        let kind = if prove { "prove" } else { "verify" };
+        // prove params are only needed for slice repair
+        if (!prove || !cosets.is_empty()) && cosets != (0..COSETS).collect() {
+            problems.push(format!(
+                "{} params of cosets {:?} found, all of 0..{} are required",
+                kind, cosets, COSETS
+            ));
+        }
+    }
+    if !problems.is_empty() {
+        for problem in problems.iter() {
+            println!("error: {}", problem);
+        }
+        bail!(anyhow!("{} problems found in the params", problems.len()));
+    }
+
+    let ts = Instant::now();
+    catch_unwind(|| ZgSignerParams::from_dir_mont(dir))
+        .map_err(|_| anyhow!("verify params cannot be loaded"))?;
+    println!("verify params loaded in {:?}", ts.elapsed());
+    if files.iter().any(|x| x.prove) {
+        let ts = Instant::now();
+        catch_unwind(|| ZgEncoderParams::from_dir_mont(dir, false, None))
+            .map_err(|_| anyhow!("prove params cannot be loaded"))?;
+        println!("prove params loaded in {:?}", ts.elapsed());
+    }
+    println!("params are valid");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_file_name_test() {
+        assert_eq!(
+            parse_file_name("amt-prove-coset2-mont-5DWgDV-10-20.bin"),
+            Some(ParamsFile {
+                prove: true,
+                coset: 2,
+                setup: "5DWgDV".to_string(),
+                depth: 10,
+                degree: 20,
+            })
+        );
+        assert_eq!(
+            parse_file_name("amt-verify-coset0-5DWgDV-10-20.bin").map(|x| x.prove),
+            Some(false)
+        );
+        assert_eq!(parse_file_name("amt-verify-coset0-5DWgDV-10.bin"), None);
+        assert_eq!(parse_file_name("README.md"), None);
+    }
+}
@@ -1,22 +1,77 @@
-use std::str::FromStr;
+use std::{collections::HashSet, net::SocketAddr, str::FromStr, time::Duration};
 
 use anyhow::{anyhow, bail, Result};
 use ark_bn254::Fr;
 
+use chain_state::{
+    light_sync::SyncMode, log_backfill::LogSyncConfig, stake_monitor::StakeMonitorConfig,
+};
+use chain_utils::{eth_signer::EthKey, gas::GasConfig};
+use clap::ArgMatches;
 use config::ConfigError::NotFound;
+use da_miner::DasSchedulerConfig;
 use ethers::{
     abi::Address,
-    types::{H160, H256},
+    types::{H160, H256, U256},
+};
+use grpc::{
+    AckMode, AdmissionConfig, BatchProxyConfig, ClusterConfig, ClusterMember, ClusterRole,
+    GrpcConnectionConfig, GrpcLimits, GrpcListener, GrpcTlsConfig, LoadSheddingConfig,
+    ParamsLoadMode, ParamsVersion, PutSliceRetryConfig, SignClient, SignQuotaConfig,
+    DEFAULT_TOKIO_CONSOLE_ADDRESS,
+};
+use storage::{
+    cold_storage::{ColdStorageConfig, ObjectStoreConfig},
+    encryption::{EncryptionConfig, KeySource},
+};
+use zg_encoder::constants::BLOB_ROW_ENCODED;
+
+use crate::{
+    keystore::{load_bls_keystore, read_password_file},
+    webhook::WEBHOOK_EVENTS,
 };
 
-mod cli {
-    use clap::{arg, command, Command};
+const DEFAULT_BACKFILL_QUEUE_CAPACITY: usize = 1024;
+const DEFAULT_ASYNC_WRITE_QUEUE_SIZE: u64 = 64;
+const DEFAULT_ETH_SIGNER_APPROVAL_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_PARAMS_URL: &str = "https://da-encoder-params.s3.ap-northeast-3.amazonaws.com";
+const DEFAULT_SUB_BATCH_SIZE: u64 = 16;
+const DEFAULT_PROBE_INTERVAL_MS: u64 = 100;
+const DEFAULT_STALL_THRESHOLD_MS: u64 = 500;
+const DEFAULT_RESOURCE_SAMPLE_INTERVAL_MS: u64 = 1000;
+const DEFAULT_MOCK_CHAIN_ID: u64 = 31337;
+const DEFAULT_MOCK_BLOCK_INTERVAL_MS: u64 = 1000;
+const DEFAULT_MOCK_BLOCKS_PER_EPOCH: u64 = 30;
+const DEFAULT_MOCK_EPOCH_WINDOW_SIZE: u64 = 100;
+const DEFAULT_SUPERVISOR_MAX_FAILURES: u64 = 5;
+const DEFAULT_SUPERVISOR_INITIAL_BACKOFF_MS: u64 = 1000;
+const DEFAULT_SUPERVISOR_MAX_BACKOFF_MS: u64 = 60_000;
+const DEFAULT_LOG_FILE: &str = "./log/signer.log";
+const DEFAULT_LOG_MAX_FILES: u64 = 10;
+const DEFAULT_OTLP_ENDPOINT: &str = "http://127.0.0.1:4317";
+const DEFAULT_OTLP_SERVICE_NAME: &str = "0g-da-signer";
+const DEFAULT_WEBHOOK_MAX_RETRIES: u64 = 3;
+const DEFAULT_WEBHOOK_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_BLOB_SIGNED_TOPIC: &str = "0g-da.blob-signed";
+const DEFAULT_DAS_TOPIC: &str = "0g-da.das";
 
-    pub fn cli_app<'a>() -> Command<'a> {
-        command!()
-            .arg(arg!(-c --config <FILE> "Sets a custom config file"))
-            .allow_external_subcommands(true)
-    }
+/// Prefix of the environment variables overriding config keys, `__` separating nested keys, e.g.
+/// `DA_NODE_SIGNER_ETH_PRIVATE_KEY` or `DA_NODE_BATCH_PROXY__ENABLED`.
+pub const ENV_PREFIX: &str = "DA_NODE";
+/// Keys read from the environment as comma separated lists.
+const ENV_LIST_KEYS: [&str; 6] = [
+    "sign_monitor_peers",
+    "resync.peers",
+    "params_download.urls",
+    "batch_proxy.backends",
+    "webhook.urls",
+    "webhook.events",
+];
+
+/// A signer key of the config: the key itself, or `file:<path>`, `env:<VAR>` or `cmd:<command>`
+/// (e.g. a KMS decrypt call) to read it from, so each key can come from its own source.
+fn read_key(value: &str) -> Result<String> {
+    KeySource::from_str(value)?.read()
 }
 
 struct RawConfig(config::Config);
@@ -28,6 +83,37 @@ impl RawConfig {
             .map_err(|e| anyhow!("Cannot parse config key `{}` as string: {:?}", key, e))
     }
 
+    fn get_string_opt(&self, key: &'static str) -> Result<Option<String>> {
+        match self.0.get_string(key) {
+            Ok(x) => Ok(Some(x)),
+            Err(NotFound(_)) => Ok(None),
+            Err(e) => Err(anyhow!(
+                "Cannot parse config key `{}` as string: {:?}",
+                key,
+                e
+            )),
+        }
+    }
+
+    fn get_string_list_opt(&self, key: &'static str) -> Result<Vec<String>> {
+        match self.0.get_array(key) {
+            Ok(x) => x
+                .into_iter()
+                .map(|v| {
+                    v.into_string().map_err(|e| {
+                        anyhow!("Cannot parse config key `{}` as string list: {:?}", key, e)
+                    })
+                })
+                .collect(),
+            Err(NotFound(_)) => Ok(vec![]),
+            Err(e) => Err(anyhow!(
+                "Cannot parse config key `{}` as string list: {:?}",
+                key,
+                e
+            )),
+        }
+    }
+
     fn get_u64(&self, key: &'static str) -> Result<u64> {
         self.0
             .get_int(key)
@@ -40,16 +126,29 @@ impl RawConfig {
             .map_err(|err| anyhow!("Cannot parse config key `{}` as address: {:?}", key, err))
     }
 
-    fn get_bytes32(&self, key: &'static str) -> Result<H256> {
-        H256::from_str(&self.get_string(key)?)
-            .map_err(|err| anyhow!("Cannot parse config key `{}` as bytes32: {:?}", key, err))
+    /// A key read from the source its value names, see [`read_key`].
+    fn get_key(&self, key: &'static str) -> Result<String> {
+        read_key(&self.get_string(key)?)
+            .map_err(|err| anyhow!("Cannot read config key `{}`: {:?}", key, err))
     }
 
     fn get_bls_key(&self, key: &'static str) -> Result<Fr> {
-        Fr::from_str(&self.get_string(key)?)
+        Fr::from_str(&self.get_key(key)?)
             .map_err(|err| anyhow!("Cannot parse config key `{}` as bls key: {:?}", key, err))
     }
 
+    fn get_eth_key(&self, key: &'static str) -> Result<H256> {
+        H256::from_str(&self.get_key(key)?)
+            .map_err(|err| anyhow!("Cannot parse config key `{}` as eth key: {:?}", key, err))
+    }
+
+    fn get_bls_key_opt(&self, key: &'static str) -> Result<Option<Fr>> {
+        match self.get_string_opt(key)? {
+            Some(_) => Ok(Some(self.get_bls_key(key)?)),
+            None => Ok(None),
+        }
+    }
+
     fn get_u64_opt(&self, key: &'static str) -> Result<Option<u64>> {
         match self.0.get_int(key) {
             Ok(x) => Ok(Some(x as u64)),
@@ -58,6 +157,18 @@ impl RawConfig {
         }
     }
 
+    fn get_f64_opt(&self, key: &'static str) -> Result<Option<f64>> {
+        match self.0.get_float(key) {
+            Ok(x) => Ok(Some(x)),
+            Err(NotFound(_)) => Ok(None),
+            Err(e) => Err(anyhow!(
+                "Cannot parse config key `{}` as float: {:?}",
+                key,
+                e
+            )),
+        }
+    }
+
     fn get_bool_opt(&self, key: &'static str) -> Result<bool> {
         match self.0.get_bool(key) {
             Ok(x) => Ok(x),
@@ -71,60 +182,1361 @@ impl RawConfig {
     }
 }
 
+#[derive(Clone)]
+pub struct ResyncConfig {
+    /// Extra peers to fetch slices from, besides the signers of the quorum.
+    pub peers: Vec<String>,
+    /// Number of latest epochs to check for missing slices.
+    pub epochs: u64,
+}
+
+/// Worker threads of the grpc services, services without threads run on the main runtime.
+#[derive(Clone, Default)]
+pub struct GrpcRuntimesConfig {
+    pub signer_threads: Option<usize>,
+    pub retrieval_threads: Option<usize>,
+    pub admin_threads: Option<usize>,
+    /// Listen address of the retrieval only service, served on the retrieval threads.
+    pub retrieval_listen_address: Option<String>,
+}
+
+/// Restarts of the failed grpc servers, chain monitor, epoch registration and DAS service.
+#[derive(Clone)]
+pub struct SupervisorConfig {
+    /// Failures in a row after which the node is stopped.
+    pub max_failures: u32,
+    /// Backoff before the first restart, doubled on every failure in a row.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+#[derive(Clone)]
+pub struct ReconcileConfig {
+    /// Number of latest epochs to reconcile.
+    pub epochs: u64,
+}
+
+#[derive(Clone)]
+pub struct PreallocationConfig {
+    /// Blobs expected in an epoch, defaults to the number of blobs of the previous epoch.
+    pub expected_blobs_per_epoch: Option<u64>,
+    /// File holding the reserved space, defaults to `reserved_space` in the data path.
+    pub reserve_file: Option<String>,
+}
+
+/// Verification of backfilled slices, e.g. gossiped by peers, as a low priority workload.
+#[derive(Clone)]
+pub struct BackfillConfig {
+    /// Jobs queued before new ones are dropped.
+    pub queue_capacity: usize,
+    /// UTC `[start, end)` hours to verify in, any time if not set.
+    pub off_peak_hours: Option<(u64, u64)>,
+}
+
+/// Download of the published encoder params into `encoder_params_dir` before the node starts.
+#[derive(Clone)]
+pub struct ParamsDownloadConfig {
+    /// Base urls of the param files, tried in order.
+    pub urls: Vec<String>,
+    /// Also download the prove params, needed for slice repair.
+    pub prove: bool,
+}
+
+/// Self-test of the sign path before serving, the node refuses to start if it fails.
+#[derive(Clone)]
+pub struct SelfTestConfig {
+    /// Signed request recorded with `record_transcript`, whose slices are verified with the params
+    /// of its epoch. The params are not tested without it.
+    pub vector: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeMode {
+    Signer,
+    /// Replica serving retrieval and status queries: it syncs the chain and stores slices, but
+    /// never signs, registers or sends transactions.
+    Archive,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    /// A json object per line.
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogRotation {
+    Hourly,
+    Daily,
+    Never,
+}
+
+/// Logs written to a file besides the console, rotated by time and size.
+#[derive(Clone)]
+pub struct LogFileConfig {
+    pub path: String,
+    /// Filter of the file, `log_level` if not set.
+    pub level: String,
+    pub rotation: LogRotation,
+    /// Size in bytes from which the file is rotated.
+    pub max_size: Option<u64>,
+    /// Rotated files kept besides the current one.
+    pub max_files: usize,
+}
+
+/// Export of the request spans to an OpenTelemetry collector.
+#[derive(Clone)]
+pub struct OtlpConfig {
+    /// Grpc endpoint of the collector.
+    pub endpoint: String,
+    pub service_name: String,
+    /// Share of the traces started by the node to export, traces continued from a caller follow
+    /// its sampling decision.
+    pub sample_ratio: f64,
+}
+
+/// Probing of the scheduling delay of the node runtimes, to detect tasks blocking worker threads.
+#[derive(Clone)]
+pub struct RuntimeMonitorConfig {
+    pub probe_interval: Duration,
+    /// Scheduling delay of a probe to report a stall.
+    pub stall_threshold: Duration,
+    /// Listener serving the tasks of the runtimes to tokio-console, needs the `tokio-console`
+    /// feature.
+    pub tokio_console_address: Option<SocketAddr>,
+}
+
+/// In-process chain standing in for the rpc, the DASigners and the DAEntrance contracts, so the
+/// node runs standalone in CI and local devnets.
+#[derive(Clone, Debug)]
+pub struct MockChainConfig {
+    /// Listener of its json-rpc endpoint, for batchers to upload and verify blobs.
+    pub listen_address: String,
+    pub chain_id: u64,
+    pub block_interval: Duration,
+    pub blocks_per_epoch: u64,
+    /// Quorums formed in every epoch with registered signers.
+    pub quorums: u64,
+    /// Rows of a quorum, shared in turn by the signers registered for the epoch.
+    pub quorum_size: u64,
+    pub epoch_window_size: u64,
+}
+
+/// An extra signer identity served by the node, with its own keys, listener and database.
+#[derive(Clone)]
+pub struct IdentityConfig {
+    pub signer_bls_private_key: Fr,
+    pub signer_eth_private_key: H256,
+    pub miner_eth_private_key: H256,
+    pub socket_address: String,
+    pub grpc_listen_address: String,
+    pub data_path: String,
+}
+
+/// Another chain the signer serves, with its own DAEntrance contract, chain state and database.
+/// Sign requests select it with the `network-id` request metadata.
+#[derive(Clone)]
+pub struct NetworkConfig {
+    pub name: String,
+    pub eth_rpc_url: String,
+    pub da_entrance_address: H160,
+    pub start_block_number: u64,
+    pub data_path: String,
+}
+
+/// POSTs of node events to HTTP endpoints.
+#[derive(Clone)]
+pub struct WebhookConfig {
+    pub urls: Vec<String>,
+    /// Key of the HMAC-SHA256 signature of the payloads, they are not signed if not set.
+    pub secret: Option<String>,
+    /// Names of the events posted, all of them if empty.
+    pub events: Vec<String>,
+    /// Retries of a failed delivery.
+    pub max_retries: u32,
+    pub timeout: Duration,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MessageBusKind {
+    Kafka,
+    Nats,
+}
+
+/// Publishing of node events to Kafka or NATS JetStream, through an outbox in the database.
+#[derive(Clone)]
+pub struct MessageBusConfig {
+    pub kind: MessageBusKind,
+    /// Kafka bootstrap servers, or NATS server urls, comma separated.
+    pub servers: String,
+    pub blob_signed_topic: String,
+    /// Topic of the confirmed DAS submissions.
+    pub das_topic: String,
+}
+
+#[derive(Clone)]
+pub struct P2pConfig {
+    /// Multiaddr to listen for p2p connections.
+    pub listen_address: String,
+    /// Multiaddrs of peers to dial on startup.
+    pub bootnodes: Vec<String>,
+}
+
+#[derive(Clone)]
 pub struct Config {
     pub log_level: String,
+    pub log_format: LogFormat,
+    pub log_file: Option<LogFileConfig>,
+    pub otlp: Option<OtlpConfig>,
     pub encoder_params_dir: String,
+    pub params_download: Option<ParamsDownloadConfig>,
+    pub params_load_mode: ParamsLoadMode,
+    pub self_test: Option<SelfTestConfig>,
+    pub sign_quota: Option<SignQuotaConfig>,
+    pub supervisor: SupervisorConfig,
+    pub params_versions: Vec<ParamsVersion>,
     pub grpc_listen_address: String,
+    /// Certificate of `grpc_listen_address`, plaintext if `None`.
+    pub grpc_tls: Option<GrpcTlsConfig>,
+    /// Listeners of the signer services besides `grpc_listen_address`, e.g. an IPv6 socket.
+    pub grpc_listeners: Vec<GrpcListener>,
     pub max_ongoing_sign_request: Option<u64>,
+    pub max_batch_sign_requests: Option<u64>,
+    pub admission: AdmissionConfig,
+    pub batch_proxy: Option<BatchProxyConfig>,
     pub max_verify_threads: Option<usize>,
+    /// Sign requests verified at once, the others wait in a priority queue. Unbounded if `None`.
+    pub max_concurrent_verifications: Option<usize>,
+    pub enable_slice_repair: bool,
+    pub store_opening_proofs: bool,
+    pub scrub_slices_per_second: Option<u64>,
+    pub put_slice_retry: PutSliceRetryConfig,
+    pub ack_mode: AckMode,
+    pub request_dump_dir: Option<String>,
+    pub audit_log_path: Option<String>,
+    pub sign_monitor_peers: Vec<String>,
+    pub resync: Option<ResyncConfig>,
+    pub reconcile: Option<ReconcileConfig>,
+    pub p2p: Option<P2pConfig>,
+    pub preallocation: Option<PreallocationConfig>,
+    pub backfill: Option<BackfillConfig>,
+    pub runtime_monitor: Option<RuntimeMonitorConfig>,
+    pub load_shedding: Option<LoadSheddingConfig>,
+    /// Chain run in process in place of `eth_rpc_url`, with `chain_backend = "mock"`.
+    pub mock_chain: Option<MockChainConfig>,
+    pub webhook: Option<WebhookConfig>,
+    pub message_bus: Option<MessageBusConfig>,
     pub socket_address: String,
     pub eth_rpc_url: String,
     pub start_block_number: u64,
     pub da_entrance_address: H160,
     pub signer_bls_private_key: Fr,
-    pub signer_eth_private_key: H256,
-    pub miner_eth_private_key: H256,
+    /// Key to rotate the signer to, the old one keeps signing until the rotation epoch.
+    pub new_signer_bls_private_key: Option<Fr>,
+    pub signer_eth_key: EthKey,
+    pub miner_eth_key: EthKey,
     pub data_path: String,
+    pub mode: NodeMode,
     pub enable_das: bool,
     pub das_test: bool,
+    pub storage_only_fallback: bool,
+    pub register_after_sync: bool,
+    pub das_scheduler: DasSchedulerConfig,
+    pub gas: GasConfig,
+    pub admin_listen_address: Option<String>,
+    /// Listener of the html status page built from the admin API.
+    pub admin_http_listen_address: Option<String>,
+    /// Listener of the `/healthz` and `/readyz` http probes.
+    pub health_listen_address: Option<String>,
+    pub grpc_runtimes: GrpcRuntimesConfig,
+    pub grpc_limits: GrpcLimits,
+    pub grpc_connection: GrpcConnectionConfig,
+    pub log_sync: LogSyncConfig,
+    pub stake_monitor: StakeMonitorConfig,
+    pub expiry_gc: bool,
+    pub cold_storage: Option<ColdStorageConfig>,
+    pub fork_schedule_path: Option<String>,
+    pub encryption: Option<EncryptionConfig>,
+    pub cluster: Option<ClusterConfig>,
+    pub identities: Vec<IdentityConfig>,
+    pub networks: Vec<NetworkConfig>,
 }
 
 impl Config {
-    pub fn from_cli_file() -> Result<Self> {
-        let matches = cli::cli_app().get_matches();
-        let c = if let Some(config_file) = matches.value_of("config") {
-            RawConfig(
-                config::Config::builder()
-                    .add_source(config::File::with_name(config_file))
-                    .build()?,
-            )
-        } else {
-            bail!(anyhow!("Config file missing!"));
-        };
+    /// Loads the `--config` file, overridden by the environment then by the `--set` flags.
+    pub fn from_cli_file(matches: &ArgMatches) -> Result<Self> {
+        let overrides: Vec<&str> = matches
+            .values_of("set")
+            .map_or(vec![], |values| values.collect());
+        match matches.value_of("config") {
+            Some(config_file) => Self::from_sources(config_file, &overrides),
+            None => bail!(anyhow!("Config file missing!")),
+        }
+    }
+
+    /// Loads the file overridden by the environment.
+    pub fn from_file(config_file: &str) -> Result<Self> {
+        Self::from_sources(config_file, &[])
+    }
+
+    /// Loads the layers file < `DA_NODE_` environment variables < `key=value` overrides, the
+    /// values of the overrides parsed as toml, or taken as strings if they are not valid toml.
+    pub fn from_sources(config_file: &str, overrides: &[&str]) -> Result<Self> {
+        let mut environment = config::Environment::with_prefix(ENV_PREFIX)
+            .prefix_separator("_")
+            .separator("__")
+            .try_parsing(true)
+            .list_separator(",");
+        for key in ENV_LIST_KEYS {
+            environment = environment.with_list_parse_key(key);
+        }
+        let mut builder = config::Config::builder()
+            .add_source(config::File::with_name(config_file))
+            .add_source(environment);
+        for item in overrides {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Invalid override `{}`, expected key=value", item))?;
+            let (key, value) = (key.trim(), value.trim());
+            let literal = format!("{} = {}", key, value);
+            let source = config::File::from_str(&literal, config::FileFormat::Toml);
+            builder = if config::Config::builder()
+                .add_source(source.clone())
+                .build()
+                .is_ok()
+            {
+                builder.add_source(source)
+            } else {
+                builder.set_override(key, value)?
+            };
+        }
+        let c = RawConfig(builder.build()?);
 
         let enable_das = c.get_bool_opt("enable_das")?;
+        let signer_eth_key = Self::signer_eth_key(&c)?;
+        let mock_chain = Self::mock_chain_config(&c)?;
+        if mock_chain.is_some() && enable_das {
+            bail!(anyhow!(
+                "DA sampling is not supported on the mock chain backend"
+            ));
+        }
 
         Ok(Self {
+            mode: match c.get_string_opt("mode")?.as_deref() {
+                None | Some("signer") => NodeMode::Signer,
+                Some("archive") => NodeMode::Archive,
+                Some(mode) => bail!(anyhow!("Unknown mode `{}`", mode)),
+            },
             enable_das: c.get_bool_opt("enable_das")?,
             das_test: c.get_bool_opt("das_test")?,
+            storage_only_fallback: c.get_bool_opt("storage_only_fallback")?,
+            register_after_sync: c.get_bool_opt("register_after_sync")?,
+            das_scheduler: DasSchedulerConfig {
+                concurrency: c.get_u64_opt("das.concurrency")?.map(|x| x as usize),
+                start_epoch: c.get_u64_opt("das.start_epoch")?,
+                end_epoch: c.get_u64_opt("das.end_epoch")?,
+                max_sign_load: c.get_u64_opt("das.max_sign_load")?,
+                sample_cache_lines: c.get_u64_opt("das.sample_cache_lines")?.map(|x| x as usize),
+                submission_batch_size: c
+                    .get_u64_opt("das.submission_batch_size")?
+                    .map(|x| x as usize),
+                max_submissions_per_round: c.get_u64_opt("das.max_submissions_per_round")?,
+            },
+            gas: GasConfig {
+                eip1559: c.get_bool_opt("gas.eip1559")?,
+                max_fee_per_gas: c.get_u64_opt("gas.max_fee_per_gas")?.map(U256::from),
+                max_priority_fee_per_gas: c
+                    .get_u64_opt("gas.max_priority_fee_per_gas")?
+                    .map(U256::from),
+                stuck_timeout: c
+                    .get_u64_opt("gas.stuck_timeout_secs")?
+                    .map(Duration::from_secs),
+                confirmations: c.get_u64_opt("gas.confirmations")?,
+            },
+            admin_listen_address: c.get_string_opt("admin_listen_address")?,
+            admin_http_listen_address: c.get_string_opt("admin_http_listen_address")?,
+            health_listen_address: c.get_string_opt("health_listen_address")?,
+            grpc_runtimes: Self::grpc_runtimes_config(&c)?,
+            grpc_limits: Self::grpc_limits_config(&c)?,
+            grpc_connection: Self::grpc_connection_config(&c)?,
+            log_sync: Self::log_sync_config(&c)?,
+            stake_monitor: StakeMonitorConfig {
+                min_assigned_rows: c
+                    .get_u64_opt("stake_monitor.min_assigned_rows")?
+                    .unwrap_or(StakeMonitorConfig::default().min_assigned_rows),
+            },
+            expiry_gc: c.get_bool_opt("expiry_gc.enabled")?,
             log_level: c.get_string("log_level")?,
+            log_format: match c.get_string_opt("log_format")?.as_deref() {
+                None | Some("text") => LogFormat::Text,
+                Some("json") => LogFormat::Json,
+                Some(format) => bail!(anyhow!("Unknown log_format `{}`", format)),
+            },
+            log_file: Self::log_file_config(&c)?,
+            otlp: Self::otlp_config(&c)?,
             encoder_params_dir: c.get_string("encoder_params_dir")?,
+            params_download: Self::params_download_config(&c)?,
+            params_versions: Self::params_versions_config(&c)?,
+            params_load_mode: match c.get_string_opt("params_load_mode")?.as_deref() {
+                None | Some("eager") => ParamsLoadMode::Eager,
+                Some("lazy") => ParamsLoadMode::Lazy,
+                Some(mode) => bail!(anyhow!("Unknown params load mode `{}`", mode)),
+            },
+            sign_quota: Self::sign_quota_config(&c)?,
+            supervisor: SupervisorConfig {
+                max_failures: c
+                    .get_u64_opt("supervisor.max_failures")?
+                    .unwrap_or(DEFAULT_SUPERVISOR_MAX_FAILURES)
+                    .max(1) as u32,
+                initial_backoff: Duration::from_millis(
+                    c.get_u64_opt("supervisor.initial_backoff_ms")?
+                        .unwrap_or(DEFAULT_SUPERVISOR_INITIAL_BACKOFF_MS),
+                ),
+                max_backoff: Duration::from_millis(
+                    c.get_u64_opt("supervisor.max_backoff_ms")?
+                        .unwrap_or(DEFAULT_SUPERVISOR_MAX_BACKOFF_MS),
+                ),
+            },
+            self_test: match c.get_bool_opt("self_test.enabled")? {
+                true => Some(SelfTestConfig {
+                    vector: c.get_string_opt("self_test.vector")?,
+                }),
+                false => None,
+            },
             grpc_listen_address: c.get_string("grpc_listen_address")?,
+            grpc_tls: Self::tls_config(
+                c.get_string_opt("grpc_tls.cert_path")?,
+                c.get_string_opt("grpc_tls.key_path")?,
+                c.get_string_opt("grpc_tls.client_ca_path")?,
+                "grpc_tls",
+            )?,
+            grpc_listeners: Self::grpc_listeners_config(&c)?,
             max_ongoing_sign_request: c.get_u64_opt("max_ongoing_sign_request")?,
+            max_batch_sign_requests: c.get_u64_opt("max_batch_sign_requests")?,
+            admission: AdmissionConfig {
+                max_inflight_slices: c.get_u64_opt("max_inflight_sign_slices")?,
+                max_inflight_bytes: c.get_u64_opt("max_inflight_sign_bytes")?,
+            },
+            batch_proxy: Self::batch_proxy_config(&c)?,
             max_verify_threads: c.get_u64_opt("max_verify_threads")?.map(|x| x as usize),
+            max_concurrent_verifications: c
+                .get_u64_opt("max_concurrent_verifications")?
+                .map(|x| x as usize),
+            enable_slice_repair: c.get_bool_opt("enable_slice_repair")?,
+            store_opening_proofs: c.get_bool_opt("store_opening_proofs")?,
+            scrub_slices_per_second: c.get_u64_opt("scrub_slices_per_second")?,
+            put_slice_retry: Self::put_slice_retry_config(&c)?,
+            ack_mode: match c.get_string_opt("ack_mode")?.as_deref() {
+                None | Some("durable") => AckMode::Durable,
+                Some("async") => AckMode::Async {
+                    queue_size: c
+                        .get_u64_opt("async_write_queue_size")?
+                        .unwrap_or(DEFAULT_ASYNC_WRITE_QUEUE_SIZE)
+                        as usize,
+                },
+                Some(mode) => bail!(anyhow!("Unknown ack mode `{}`", mode)),
+            },
+            request_dump_dir: c.get_string_opt("request_dump_dir")?,
+            audit_log_path: c.get_string_opt("audit_log_path")?,
+            sign_monitor_peers: c.get_string_list_opt("sign_monitor_peers")?,
+            resync: Self::resync_config(&c)?,
+            reconcile: Self::reconcile_config(&c)?,
+            p2p: Self::p2p_config(&c)?,
+            preallocation: Self::preallocation_config(&c)?,
+            backfill: Self::backfill_config(&c)?,
+            runtime_monitor: Self::runtime_monitor_config(&c)?,
+            load_shedding: Self::load_shedding_config(&c)?,
+            mock_chain,
+            webhook: Self::webhook_config(&c)?,
+            message_bus: Self::message_bus_config(&c)?,
             socket_address: c.get_string("socket_address")?,
-            eth_rpc_url: c.get_string("eth_rpc_endpoint")?,
+            // pointed at the mock chain once it listens
+            eth_rpc_url: match &mock_chain {
+                Some(_) => c.get_string_opt("eth_rpc_endpoint")?.unwrap_or_default(),
+                None => c.get_string("eth_rpc_endpoint")?,
+            },
             start_block_number: c.get_u64("start_block_number")?,
             da_entrance_address: c.get_address("da_entrance_address")?,
-            signer_bls_private_key: c.get_bls_key("signer_bls_private_key")?,
-            signer_eth_private_key: c.get_bytes32("signer_eth_private_key")?,
-            miner_eth_private_key: if enable_das {
-                c.get_bytes32("miner_eth_private_key")
-                    .or_else(|_| c.get_bytes32("signer_eth_private_key"))?
+            signer_bls_private_key: Self::signer_bls_key(&c)?,
+            new_signer_bls_private_key: c.get_bls_key_opt("new_signer_bls_private_key")?,
+            miner_eth_key: if enable_das {
+                c.get_eth_key("miner_eth_private_key")
+                    .map(EthKey::Private)
+                    .unwrap_or_else(|_| signer_eth_key.clone())
             } else {
-                H256::zero()
+                EthKey::Private(H256::zero())
             },
+            signer_eth_key,
             data_path: c.get_string("data_path")?,
+            cold_storage: Self::cold_storage_config(&c)?,
+            fork_schedule_path: c.get_string_opt("fork_schedule_path")?,
+            encryption: Self::encryption_config(&c)?,
+            cluster: Self::cluster_config(&c)?,
+            identities: Self::identities_config(&c, enable_das)?,
+            networks: Self::networks_config(&c)?,
+        })
+    }
+
+    /// Config of an extra identity. Listeners and stores bound to a single node, namely p2p, admin,
+    /// the dedicated retrieval listener and cold storage, stay with the main identity.
+    pub fn for_identity(&self, identity: &IdentityConfig) -> Self {
+        let mut config = self.clone();
+        config.signer_bls_private_key = identity.signer_bls_private_key;
+        config.new_signer_bls_private_key = None;
+        config.signer_eth_key = EthKey::Private(identity.signer_eth_private_key);
+        config.miner_eth_key = EthKey::Private(identity.miner_eth_private_key);
+        config.socket_address = identity.socket_address.clone();
+        config.grpc_listen_address = identity.grpc_listen_address.clone();
+        config.grpc_tls = None;
+        config.grpc_listeners = vec![];
+        config.data_path = identity.data_path.clone();
+        config.p2p = None;
+        config.mock_chain = None;
+        config.admin_listen_address = None;
+        config.admin_http_listen_address = None;
+        config.grpc_runtimes.retrieval_listen_address = None;
+        config.grpc_runtimes.retrieval_threads = None;
+        config.cold_storage = None;
+        config.runtime_monitor = None;
+        config.webhook = None;
+        config.message_bus = None;
+        config.batch_proxy = None;
+        config.cluster = None;
+        config.identities = vec![];
+        config.networks = vec![];
+        config
+    }
+
+    /// Config of the signer on another network. It is served on the listeners of the main network,
+    /// so it has none of its own.
+    pub fn for_network(&self, network: &NetworkConfig) -> Self {
+        let mut config = self.clone();
+        config.eth_rpc_url = network.eth_rpc_url.clone();
+        config.da_entrance_address = network.da_entrance_address;
+        config.start_block_number = network.start_block_number;
+        config.data_path = network.data_path.clone();
+        config.new_signer_bls_private_key = None;
+        config.sign_monitor_peers = vec![];
+        config.p2p = None;
+        config.admin_listen_address = None;
+        config.admin_http_listen_address = None;
+        config.grpc_runtimes.retrieval_listen_address = None;
+        config.grpc_runtimes.retrieval_threads = None;
+        config.cold_storage = None;
+        config.fork_schedule_path = None;
+        config.runtime_monitor = None;
+        config.webhook = None;
+        config.message_bus = None;
+        config.batch_proxy = None;
+        config.cluster = None;
+        config.identities = vec![];
+        config.networks = vec![];
+        config
+    }
+
+    /// `peers` along with the grpc addresses of the cluster members sharing the signer of this node
+    /// in `role`.
+    pub fn with_cluster_peers(&self, peers: &[String], role: ClusterRole) -> Vec<String> {
+        let mut peers = peers.to_vec();
+        if let Some(cluster) = &self.cluster {
+            for address in cluster.sibling_addresses(role) {
+                if !peers.contains(&address) {
+                    peers.push(address);
+                }
+            }
+        }
+        peers
+    }
+
+    fn cold_storage_config(c: &RawConfig) -> Result<Option<ColdStorageConfig>> {
+        if !c.get_bool_opt("cold_storage.enabled")? {
+            return Ok(None);
+        }
+        let store = match c.get_string("cold_storage.backend")?.as_str() {
+            "local" => ObjectStoreConfig::Local {
+                path: c.get_string("cold_storage.local_path")?,
+            },
+            "s3" => ObjectStoreConfig::S3 {
+                endpoint: c.get_string("cold_storage.endpoint")?,
+                bucket: c.get_string("cold_storage.bucket")?,
+                region: c
+                    .get_string_opt("cold_storage.region")?
+                    .unwrap_or("us-east-1".to_string()),
+                access_key: c.get_string("cold_storage.access_key")?,
+                secret_key: c.get_string("cold_storage.secret_key")?,
+            },
+            backend => bail!(anyhow!("Unknown cold storage backend `{}`", backend)),
+        };
+        Ok(Some(ColdStorageConfig {
+            store,
+            tier_after_epochs: c.get_u64("cold_storage.tier_after_epochs")?,
+        }))
+    }
+
+    fn encryption_config(c: &RawConfig) -> Result<Option<EncryptionConfig>> {
+        if !c.get_bool_opt("encryption.enabled")? {
+            return Ok(None);
+        }
+        let keys =
+            c.0.get_table("encryption.keys")
+                .map_err(|e| anyhow!("Cannot parse config key `encryption.keys`: {:?}", e))?
+                .into_iter()
+                .map(|(id, source)| {
+                    let id = u8::from_str(&id)
+                        .map_err(|e| anyhow!("Invalid encryption key id `{}`: {:?}", id, e))?;
+                    let source = KeySource::from_str(&source.into_string()?)?;
+                    Ok((id, source))
+                })
+                .collect::<Result<Vec<_>>>()?;
+        let active_key_id = c.get_u64("encryption.active_key_id")?;
+        Ok(Some(EncryptionConfig {
+            active_key_id: u8::try_from(active_key_id)
+                .map_err(|_| anyhow!("Invalid active encryption key id {}", active_key_id))?,
+            keys,
+        }))
+    }
+
+    fn put_slice_retry_config(c: &RawConfig) -> Result<PutSliceRetryConfig> {
+        let default = PutSliceRetryConfig::default();
+        Ok(PutSliceRetryConfig {
+            max_retries: c
+                .get_u64_opt("put_slice_retry.max_retries")?
+                .map_or(default.max_retries, |x| x as u32),
+            backoff: c
+                .get_u64_opt("put_slice_retry.backoff_ms")?
+                .map_or(default.backoff, Duration::from_millis),
         })
     }
+
+    fn resync_config(c: &RawConfig) -> Result<Option<ResyncConfig>> {
+        if !c.get_bool_opt("resync.enabled")? {
+            return Ok(None);
+        }
+        Ok(Some(ResyncConfig {
+            peers: c.get_string_list_opt("resync.peers")?,
+            epochs: c.get_u64_opt("resync.epochs")?.unwrap_or(1),
+        }))
+    }
+
+    fn grpc_runtimes_config(c: &RawConfig) -> Result<GrpcRuntimesConfig> {
+        let config = GrpcRuntimesConfig {
+            signer_threads: c
+                .get_u64_opt("grpc_runtimes.signer_threads")?
+                .map(|x| x as usize),
+            retrieval_threads: c
+                .get_u64_opt("grpc_runtimes.retrieval_threads")?
+                .map(|x| x as usize),
+            admin_threads: c
+                .get_u64_opt("grpc_runtimes.admin_threads")?
+                .map(|x| x as usize),
+            retrieval_listen_address: c.get_string_opt("grpc_runtimes.retrieval_listen_address")?,
+        };
+        if config.retrieval_threads.is_some() && config.retrieval_listen_address.is_none() {
+            bail!(anyhow!(
+                "grpc_runtimes.retrieval_threads requires grpc_runtimes.retrieval_listen_address"
+            ));
+        }
+        Ok(config)
+    }
+
+    fn grpc_limits_config(c: &RawConfig) -> Result<GrpcLimits> {
+        let default = GrpcLimits::default();
+        Ok(GrpcLimits {
+            max_decoding_message_size: c
+                .get_u64_opt("grpc_limits.max_decoding_message_size")?
+                .map_or(default.max_decoding_message_size, |x| x as usize),
+            max_encoding_message_size: c
+                .get_u64_opt("grpc_limits.max_encoding_message_size")?
+                .map_or(default.max_encoding_message_size, |x| x as usize),
+            max_concurrent_streams: c
+                .get_u64_opt("grpc_limits.max_concurrent_streams")?
+                .map(|x| x as u32),
+            timeout: c
+                .get_u64_opt("grpc_limits.timeout_secs")?
+                .map(Duration::from_secs),
+        })
+    }
+
+    fn grpc_connection_config(c: &RawConfig) -> Result<GrpcConnectionConfig> {
+        let secs = |key: &'static str| -> Result<Option<Duration>> {
+            Ok(c.get_u64_opt(key)?.map(Duration::from_secs))
+        };
+        Ok(GrpcConnectionConfig {
+            http2_keepalive_interval: secs("grpc_connection.http2_keepalive_interval_secs")?,
+            http2_keepalive_timeout: secs("grpc_connection.http2_keepalive_timeout_secs")?,
+            tcp_keepalive: secs("grpc_connection.tcp_keepalive_secs")?,
+            max_connection_age: secs("grpc_connection.max_connection_age_secs")?,
+            shutdown_grace: secs("grpc_connection.shutdown_grace_secs")?
+                .unwrap_or(GrpcConnectionConfig::default().shutdown_grace),
+        })
+    }
+
+    fn log_sync_config(c: &RawConfig) -> Result<LogSyncConfig> {
+        let default = LogSyncConfig::default();
+        Ok(LogSyncConfig {
+            mode: match c.get_string_opt("sync_mode")?.as_deref() {
+                None | Some("full") => SyncMode::Full,
+                Some("light") => SyncMode::Light,
+                Some(mode) => bail!(anyhow!("Unknown sync mode `{}`", mode)),
+            },
+            concurrency: c
+                .get_u64_opt("log_sync.concurrency")?
+                .map_or(default.concurrency, |x| x as usize),
+            max_range: c
+                .get_u64_opt("log_sync.max_range")?
+                .unwrap_or(default.max_range),
+            index_events: c.get_bool_opt("log_sync.index_events")?,
+        })
+    }
+
+    fn reconcile_config(c: &RawConfig) -> Result<Option<ReconcileConfig>> {
+        if !c.get_bool_opt("reconcile.enabled")? {
+            return Ok(None);
+        }
+        Ok(Some(ReconcileConfig {
+            epochs: c.get_u64_opt("reconcile.epochs")?.unwrap_or(1),
+        }))
+    }
+
+    fn backfill_config(c: &RawConfig) -> Result<Option<BackfillConfig>> {
+        if !c.get_bool_opt("backfill.enabled")? {
+            return Ok(None);
+        }
+        let start = c.get_u64_opt("backfill.off_peak_start_hour")?;
+        let end = c.get_u64_opt("backfill.off_peak_end_hour")?;
+        let off_peak_hours = match (start, end) {
+            (Some(start), Some(end)) if start < 24 && end < 24 => Some((start, end)),
+            (None, None) => None,
+            _ => bail!(anyhow!(
+                "backfill.off_peak_start_hour and backfill.off_peak_end_hour must be both set to hours in [0, 24)"
+            )),
+        };
+        Ok(Some(BackfillConfig {
+            queue_capacity: c
+                .get_u64_opt("backfill.queue_capacity")?
+                .map_or(DEFAULT_BACKFILL_QUEUE_CAPACITY, |x| x as usize),
+            off_peak_hours,
+        }))
+    }
+
+    fn log_file_config(c: &RawConfig) -> Result<Option<LogFileConfig>> {
+        if !c.get_bool_opt("log_file.enabled")? {
+            return Ok(None);
+        }
+        Ok(Some(LogFileConfig {
+            path: c
+                .get_string_opt("log_file.path")?
+                .unwrap_or_else(|| DEFAULT_LOG_FILE.to_string()),
+            level: match c.get_string_opt("log_file.level")? {
+                Some(level) => level,
+                None => c.get_string("log_level")?,
+            },
+            rotation: match c.get_string_opt("log_file.rotation")?.as_deref() {
+                None | Some("daily") => LogRotation::Daily,
+                Some("hourly") => LogRotation::Hourly,
+                Some("never") => LogRotation::Never,
+                Some(rotation) => bail!(anyhow!("Unknown log_file.rotation `{}`", rotation)),
+            },
+            max_size: c
+                .get_u64_opt("log_file.max_size_mb")?
+                .map(|x| x * 1024 * 1024),
+            max_files: c
+                .get_u64_opt("log_file.max_files")?
+                .unwrap_or(DEFAULT_LOG_MAX_FILES) as usize,
+        }))
+    }
+
+    fn otlp_config(c: &RawConfig) -> Result<Option<OtlpConfig>> {
+        if !c.get_bool_opt("otlp.enabled")? {
+            return Ok(None);
+        }
+        let sample_ratio = c.get_f64_opt("otlp.sample_ratio")?.unwrap_or(1.0);
+        if !(0.0..=1.0).contains(&sample_ratio) {
+            bail!(anyhow!("otlp.sample_ratio must be between 0 and 1"));
+        }
+        Ok(Some(OtlpConfig {
+            endpoint: c
+                .get_string_opt("otlp.endpoint")?
+                .unwrap_or_else(|| DEFAULT_OTLP_ENDPOINT.to_string()),
+            service_name: c
+                .get_string_opt("otlp.service_name")?
+                .unwrap_or_else(|| DEFAULT_OTLP_SERVICE_NAME.to_string()),
+            sample_ratio,
+        }))
+    }
+
+    fn params_download_config(c: &RawConfig) -> Result<Option<ParamsDownloadConfig>> {
+        if !c.get_bool_opt("params_download.enabled")? {
+            return Ok(None);
+        }
+        let mut urls = c.get_string_list_opt("params_download.urls")?;
+        if urls.is_empty() {
+            urls.push(DEFAULT_PARAMS_URL.to_string());
+        }
+        Ok(Some(ParamsDownloadConfig {
+            urls,
+            prove: c.get_bool_opt("enable_slice_repair")?,
+        }))
+    }
+
+    fn batch_proxy_config(c: &RawConfig) -> Result<Option<BatchProxyConfig>> {
+        if !c.get_bool_opt("batch_proxy.enabled")? {
+            return Ok(None);
+        }
+        let config = BatchProxyConfig {
+            backends: c.get_string_list_opt("batch_proxy.backends")?,
+            local: !c.get_bool_opt("batch_proxy.backends_only")?,
+            sub_batch_size: c
+                .get_u64_opt("batch_proxy.sub_batch_size")?
+                .unwrap_or(DEFAULT_SUB_BATCH_SIZE),
+        };
+        if config.backends.is_empty() && !config.local {
+            bail!(anyhow!(
+                "batch_proxy.backends must be set with batch_proxy.backends_only"
+            ));
+        }
+        Ok(Some(config))
+    }
+
+    fn runtime_monitor_config(c: &RawConfig) -> Result<Option<RuntimeMonitorConfig>> {
+        if !c.get_bool_opt("runtime_monitor.enabled")? {
+            return Ok(None);
+        }
+        Ok(Some(RuntimeMonitorConfig {
+            probe_interval: Duration::from_millis(
+                c.get_u64_opt("runtime_monitor.probe_interval_ms")?
+                    .unwrap_or(DEFAULT_PROBE_INTERVAL_MS),
+            ),
+            stall_threshold: Duration::from_millis(
+                c.get_u64_opt("runtime_monitor.stall_threshold_ms")?
+                    .unwrap_or(DEFAULT_STALL_THRESHOLD_MS),
+            ),
+            tokio_console_address: Self::tokio_console_address(c)?,
+        }))
+    }
+
+    fn tokio_console_address(c: &RawConfig) -> Result<Option<SocketAddr>> {
+        if !c.get_bool_opt("runtime_monitor.tokio_console")? {
+            return Ok(None);
+        }
+        if !cfg!(feature = "tokio-console") {
+            bail!(anyhow!(
+                "runtime_monitor.tokio_console is not built in, rebuild with `--features tokio-console` and RUSTFLAGS=\"--cfg tokio_unstable\""
+            ));
+        }
+        let address = c
+            .get_string_opt("runtime_monitor.tokio_console_address")?
+            .unwrap_or(DEFAULT_TOKIO_CONSOLE_ADDRESS.to_string());
+        Ok(Some(SocketAddr::from_str(&address).map_err(|e| {
+            anyhow!(
+                "Cannot parse runtime_monitor.tokio_console_address {:?}: {:?}",
+                address,
+                e
+            )
+        })?))
+    }
+
+    fn load_shedding_config(c: &RawConfig) -> Result<Option<LoadSheddingConfig>> {
+        if !c.get_bool_opt("load_shedding.enabled")? {
+            return Ok(None);
+        }
+        let config = LoadSheddingConfig {
+            sample_interval: Duration::from_millis(
+                c.get_u64_opt("load_shedding.sample_interval_ms")?
+                    .unwrap_or(DEFAULT_RESOURCE_SAMPLE_INTERVAL_MS),
+            ),
+            max_cpu_percent: c.get_u64_opt("load_shedding.max_cpu_percent")?,
+            max_memory_percent: c.get_u64_opt("load_shedding.max_memory_percent")?,
+            max_io_latency: c
+                .get_u64_opt("load_shedding.max_io_latency_ms")?
+                .map(Duration::from_millis),
+            disks: c.get_string_list_opt("load_shedding.disks")?,
+            max_delay: Duration::from_millis(
+                c.get_u64_opt("load_shedding.max_delay_ms")?.unwrap_or(0),
+            ),
+        };
+        if config.max_cpu_percent.is_none()
+            && config.max_memory_percent.is_none()
+            && config.max_io_latency.is_none()
+        {
+            bail!(anyhow!(
+                "load_shedding needs one of max_cpu_percent, max_memory_percent or max_io_latency_ms"
+            ));
+        }
+        Ok(Some(config))
+    }
+
+    fn mock_chain_config(c: &RawConfig) -> Result<Option<MockChainConfig>> {
+        match c.get_string_opt("chain_backend")?.as_deref() {
+            None | Some("rpc") => return Ok(None),
+            Some("mock") => {}
+            Some(backend) => bail!(anyhow!("Unknown chain_backend `{}`", backend)),
+        }
+        let config = MockChainConfig {
+            listen_address: c
+                .get_string_opt("mock_chain.listen_address")?
+                .unwrap_or_else(|| "127.0.0.1:0".to_string()),
+            chain_id: c
+                .get_u64_opt("mock_chain.chain_id")?
+                .unwrap_or(DEFAULT_MOCK_CHAIN_ID),
+            block_interval: Duration::from_millis(
+                c.get_u64_opt("mock_chain.block_interval_ms")?
+                    .unwrap_or(DEFAULT_MOCK_BLOCK_INTERVAL_MS),
+            ),
+            blocks_per_epoch: c
+                .get_u64_opt("mock_chain.blocks_per_epoch")?
+                .unwrap_or(DEFAULT_MOCK_BLOCKS_PER_EPOCH),
+            quorums: c.get_u64_opt("mock_chain.quorums")?.unwrap_or(1),
+            quorum_size: c
+                .get_u64_opt("mock_chain.quorum_size")?
+                .unwrap_or(BLOB_ROW_ENCODED as u64),
+            epoch_window_size: c
+                .get_u64_opt("mock_chain.epoch_window_size")?
+                .unwrap_or(DEFAULT_MOCK_EPOCH_WINDOW_SIZE),
+        };
+        if config.block_interval.is_zero() || config.blocks_per_epoch == 0 {
+            bail!(anyhow!(
+                "mock_chain.block_interval_ms and mock_chain.blocks_per_epoch must be positive"
+            ));
+        }
+        if config.quorum_size == 0 || config.quorum_size > BLOB_ROW_ENCODED as u64 {
+            bail!(anyhow!(
+                "mock_chain.quorum_size must be between 1 and {}",
+                BLOB_ROW_ENCODED
+            ));
+        }
+        Ok(Some(config))
+    }
+
+    fn webhook_config(c: &RawConfig) -> Result<Option<WebhookConfig>> {
+        if !c.get_bool_opt("webhook.enabled")? {
+            return Ok(None);
+        }
+        let urls = c.get_string_list_opt("webhook.urls")?;
+        if urls.is_empty() {
+            bail!(anyhow!("webhook.urls is empty"));
+        }
+        let events = c.get_string_list_opt("webhook.events")?;
+        if let Some(event) = events
+            .iter()
+            .find(|x| !WEBHOOK_EVENTS.contains(&x.as_str()))
+        {
+            bail!(anyhow!(
+                "Unknown webhook event `{}`, expected one of {:?}",
+                event,
+                WEBHOOK_EVENTS
+            ));
+        }
+        Ok(Some(WebhookConfig {
+            urls,
+            secret: c.get_string_opt("webhook.secret")?,
+            events,
+            max_retries: c
+                .get_u64_opt("webhook.max_retries")?
+                .unwrap_or(DEFAULT_WEBHOOK_MAX_RETRIES) as u32,
+            timeout: Duration::from_secs(
+                c.get_u64_opt("webhook.timeout_secs")?
+                    .unwrap_or(DEFAULT_WEBHOOK_TIMEOUT_SECS),
+            ),
+        }))
+    }
+
+    /// Key of the signer account, held by the `eth_signer` external signer if enabled.
+    fn signer_eth_key(c: &RawConfig) -> Result<EthKey> {
+        if !c.get_bool_opt("eth_signer.enabled")? {
+            return Ok(EthKey::Private(c.get_eth_key("signer_eth_private_key")?));
+        }
+        let approval_timeout = Duration::from_secs(
+            c.get_u64_opt("eth_signer.approval_timeout_secs")?
+                .unwrap_or(DEFAULT_ETH_SIGNER_APPROVAL_TIMEOUT_SECS),
+        );
+        match c.get_string("eth_signer.kind")?.as_str() {
+            "external" => Ok(EthKey::External {
+                url: c.get_string("eth_signer.url")?,
+                address: c.get_address("eth_signer.address")?,
+                approval_timeout,
+            }),
+            kind => bail!(anyhow!("Unknown eth_signer.kind `{}`", kind)),
+        }
+    }
+
+    /// The signer BLS key of `signer_bls_keystore` if set, of `signer_bls_private_key` otherwise.
+    fn signer_bls_key(c: &RawConfig) -> Result<Fr> {
+        match c.get_string_opt("signer_bls_keystore")? {
+            Some(path) => {
+                let password = match c.get_string_opt("signer_bls_keystore_password_file")? {
+                    Some(file) => read_password_file(file)?,
+                    None => String::new(),
+                };
+                load_bls_keystore(path, &password)
+            }
+            None => c.get_bls_key("signer_bls_private_key"),
+        }
+    }
+
+    fn message_bus_config(c: &RawConfig) -> Result<Option<MessageBusConfig>> {
+        if !c.get_bool_opt("message_bus.enabled")? {
+            return Ok(None);
+        }
+        let kind = match c.get_string("message_bus.kind")?.as_str() {
+            "kafka" if cfg!(feature = "kafka") => MessageBusKind::Kafka,
+            "nats" if cfg!(feature = "nats") => MessageBusKind::Nats,
+            kind @ ("kafka" | "nats") => bail!(anyhow!(
+                "message_bus.kind `{}` is not built in, rebuild with `--features {}`",
+                kind,
+                kind
+            )),
+            kind => bail!(anyhow!("Unknown message_bus.kind `{}`", kind)),
+        };
+        Ok(Some(MessageBusConfig {
+            kind,
+            servers: c.get_string("message_bus.servers")?,
+            blob_signed_topic: c
+                .get_string_opt("message_bus.blob_signed_topic")?
+                .unwrap_or(DEFAULT_BLOB_SIGNED_TOPIC.to_string()),
+            das_topic: c
+                .get_string_opt("message_bus.das_topic")?
+                .unwrap_or(DEFAULT_DAS_TOPIC.to_string()),
+        }))
+    }
+
+    fn preallocation_config(c: &RawConfig) -> Result<Option<PreallocationConfig>> {
+        if !c.get_bool_opt("preallocation.enabled")? {
+            return Ok(None);
+        }
+        Ok(Some(PreallocationConfig {
+            expected_blobs_per_epoch: c.get_u64_opt("preallocation.expected_blobs_per_epoch")?,
+            reserve_file: c.get_string_opt("preallocation.reserve_file")?,
+        }))
+    }
+
+    fn identities_config(c: &RawConfig, enable_das: bool) -> Result<Vec<IdentityConfig>> {
+        let identities = match c.0.get_array("identities") {
+            Ok(identities) => identities,
+            Err(NotFound(_)) => return Ok(vec![]),
+            Err(e) => bail!(anyhow!("Cannot parse config key `identities`: {:?}", e)),
+        };
+        identities
+            .into_iter()
+            .enumerate()
+            .map(|(i, identity)| {
+                let mut table = identity
+                    .into_table()
+                    .map_err(|e| anyhow!("Cannot parse identity {}: {:?}", i, e))?;
+                let mut get = |key: &str| -> Result<Option<String>> {
+                    table
+                        .remove(key)
+                        .map(|v| v.into_string())
+                        .transpose()
+                        .map_err(|e| anyhow!("Cannot parse `{}` of identity {}: {:?}", key, i, e))
+                };
+                let required = |value: Option<String>, key: &str| -> Result<String> {
+                    value.ok_or_else(|| anyhow!("Missing `{}` of identity {}", key, i))
+                };
+                let signer_bls_private_key = Fr::from_str(&read_key(&required(
+                    get("signer_bls_private_key")?,
+                    "signer_bls_private_key",
+                )?)?)
+                .map_err(|e| anyhow!("Cannot parse bls key of identity {}: {:?}", i, e))?;
+                let signer_eth_private_key = H256::from_str(&read_key(&required(
+                    get("signer_eth_private_key")?,
+                    "signer_eth_private_key",
+                )?)?)?;
+                let socket_address = required(get("socket_address")?, "socket_address")?;
+                let grpc_listen_address =
+                    required(get("grpc_listen_address")?, "grpc_listen_address")?;
+                let data_path = required(get("data_path")?, "data_path")?;
+                let miner_eth_private_key = match get("miner_eth_private_key")? {
+                    Some(key) if enable_das => H256::from_str(&read_key(&key)?)?,
+                    None if enable_das => signer_eth_private_key,
+                    _ => H256::zero(),
+                };
+                Ok(IdentityConfig {
+                    signer_bls_private_key,
+                    signer_eth_private_key,
+                    miner_eth_private_key,
+                    socket_address,
+                    grpc_listen_address,
+                    data_path,
+                })
+            })
+            .collect()
+    }
+
+    fn params_versions_config(c: &RawConfig) -> Result<Vec<ParamsVersion>> {
+        let versions = match c.0.get_array("encoder_params_versions") {
+            Ok(versions) => versions,
+            Err(NotFound(_)) => return Ok(vec![]),
+            Err(e) => bail!(anyhow!(
+                "Cannot parse config key `encoder_params_versions`: {:?}",
+                e
+            )),
+        };
+        let mut epochs = HashSet::new();
+        versions
+            .into_iter()
+            .enumerate()
+            .map(|(i, version)| {
+                let mut table = version
+                    .into_table()
+                    .map_err(|e| anyhow!("Cannot parse params version {}: {:?}", i, e))?;
+                let mut get = |key: &str| {
+                    table
+                        .remove(key)
+                        .ok_or_else(|| anyhow!("Missing `{}` of params version {}", key, i))
+                };
+                let version = get("version")?.into_string()?;
+                let dir = get("dir")?.into_string()?;
+                let from_epoch = get("from_epoch")?.into_uint()?;
+                if from_epoch == 0 || !epochs.insert(from_epoch) {
+                    bail!(anyhow!(
+                        "`from_epoch` of params version `{}` must be positive and unique",
+                        version
+                    ));
+                }
+                Ok(ParamsVersion {
+                    version,
+                    dir,
+                    from_epoch,
+                })
+            })
+            .collect()
+    }
+
+    fn sign_quota_config(c: &RawConfig) -> Result<Option<SignQuotaConfig>> {
+        let clients = match c.0.get_array("sign_clients") {
+            Ok(clients) => clients,
+            Err(NotFound(_)) => vec![],
+            Err(e) => bail!(anyhow!("Cannot parse config key `sign_clients`: {:?}", e)),
+        };
+        let clients: Vec<SignClient> = clients
+            .into_iter()
+            .enumerate()
+            .map(|(i, client)| {
+                let mut table = client
+                    .into_table()
+                    .map_err(|e| anyhow!("Cannot parse sign client {}: {:?}", i, e))?;
+                let mut get = |key: &str| {
+                    table
+                        .remove(key)
+                        .ok_or_else(|| anyhow!("Missing `{}` of sign client {}", key, i))
+                };
+                let name = get("name")?.into_string()?;
+                let token = get("token")?.into_string()?;
+                if token.is_empty() {
+                    bail!(anyhow!("Token of sign client `{}` is empty", name));
+                }
+                let mut limit = |key: &str| -> Result<Option<u64>> {
+                    get(key)
+                        .ok()
+                        .map(|v| v.into_uint())
+                        .transpose()
+                        .map_err(|e| {
+                            anyhow!("Cannot parse `{}` of sign client `{}`: {:?}", key, name, e)
+                        })
+                };
+                Ok(SignClient {
+                    max_blobs_per_epoch: limit("max_blobs_per_epoch")?,
+                    max_bytes_per_epoch: limit("max_bytes_per_epoch")?,
+                    priority: limit("priority")?.unwrap_or(0),
+                    name,
+                    token,
+                })
+            })
+            .collect::<Result<_>>()?;
+        for (i, client) in clients.iter().enumerate() {
+            if clients[..i]
+                .iter()
+                .any(|x| x.name == client.name || x.token == client.token)
+            {
+                bail!(anyhow!(
+                    "Sign client `{}` duplicates the name or token of another one",
+                    client.name
+                ));
+            }
+        }
+        let require_client = c.get_bool_opt("require_sign_client")?;
+        if clients.is_empty() && !require_client {
+            return Ok(None);
+        }
+        Ok(Some(SignQuotaConfig {
+            clients,
+            require_client,
+        }))
+    }
+
+    fn cluster_config(c: &RawConfig) -> Result<Option<ClusterConfig>> {
+        if !c.get_bool_opt("cluster.enabled")? {
+            return Ok(None);
+        }
+        let members =
+            c.0.get_array("cluster.members")
+                .map_err(|e| anyhow!("Cannot parse config key `cluster.members`: {:?}", e))?
+                .into_iter()
+                .enumerate()
+                .map(|(i, member)| {
+                    let mut table = member
+                        .into_table()
+                        .map_err(|e| anyhow!("Cannot parse cluster member {}: {:?}", i, e))?;
+                    let mut get = |key: &str| {
+                        table
+                            .remove(key)
+                            .ok_or_else(|| anyhow!("Missing `{}` of cluster member {}", key, i))
+                    };
+                    let name = get("name")?.into_string()?;
+                    let grpc_address = get("grpc_address")?.into_string()?;
+                    let roles = get("roles")?
+                        .into_array()?
+                        .into_iter()
+                        .map(|role| ClusterRole::from_str(&role.into_string()?))
+                        .collect::<Result<_>>()?;
+                    let shared_identity = match get("identity")?.into_string()?.as_str() {
+                        "shared" => true,
+                        "distinct" => false,
+                        identity => bail!(anyhow!(
+                            "Identity of cluster member `{}` must be shared or distinct, not `{}`",
+                            name,
+                            identity
+                        )),
+                    };
+                    Ok(ClusterMember {
+                        name,
+                        grpc_address,
+                        roles,
+                        shared_identity,
+                    })
+                })
+                .collect::<Result<_>>()?;
+        let cluster = ClusterConfig {
+            name: c.get_string("cluster.name")?,
+            member: c.get_string("cluster.member")?,
+            members,
+        };
+        cluster.validate()?;
+        Ok(Some(cluster))
+    }
+
+    fn networks_config(c: &RawConfig) -> Result<Vec<NetworkConfig>> {
+        let networks = match c.0.get_array("networks") {
+            Ok(networks) => networks,
+            Err(NotFound(_)) => return Ok(vec![]),
+            Err(e) => bail!(anyhow!("Cannot parse config key `networks`: {:?}", e)),
+        };
+        let mut names = HashSet::new();
+        networks
+            .into_iter()
+            .enumerate()
+            .map(|(i, network)| {
+                let mut table = network
+                    .into_table()
+                    .map_err(|e| anyhow!("Cannot parse network {}: {:?}", i, e))?;
+                let mut get = |key: &str| -> Result<String> {
+                    table
+                        .remove(key)
+                        .ok_or_else(|| anyhow!("Missing `{}` of network {}", key, i))?
+                        .into_string()
+                        .map_err(|e| anyhow!("Cannot parse `{}` of network {}: {:?}", key, i, e))
+                };
+                let name = get("name")?;
+                if !names.insert(name.clone()) {
+                    bail!(anyhow!("Duplicated network name `{}`", name));
+                }
+                Ok(NetworkConfig {
+                    eth_rpc_url: get("eth_rpc_endpoint")?,
+                    da_entrance_address: H160::from_str(&get("da_entrance_address")?)?,
+                    start_block_number: u64::from_str(&get("start_block_number")?)?,
+                    data_path: get("data_path")?,
+                    name,
+                })
+            })
+            .collect()
+    }
+
+    fn tls_config(
+        cert_path: Option<String>,
+        key_path: Option<String>,
+        client_ca_path: Option<String>,
+        name: &str,
+    ) -> Result<Option<GrpcTlsConfig>> {
+        match (cert_path, key_path) {
+            (Some(cert_path), Some(key_path)) => Ok(Some(GrpcTlsConfig {
+                cert_path,
+                key_path,
+                client_ca_path,
+            })),
+            (None, None) if client_ca_path.is_none() => Ok(None),
+            _ => bail!(anyhow!(
+                "The tls certificate and key of {} must be set together",
+                name
+            )),
+        }
+    }
+
+    fn grpc_listeners_config(c: &RawConfig) -> Result<Vec<GrpcListener>> {
+        let listeners = match c.0.get_array("grpc_listeners") {
+            Ok(listeners) => listeners,
+            Err(NotFound(_)) => return Ok(vec![]),
+            Err(e) => bail!(anyhow!("Cannot parse config key `grpc_listeners`: {:?}", e)),
+        };
+        listeners
+            .into_iter()
+            .enumerate()
+            .map(|(i, listener)| {
+                let mut table = listener
+                    .into_table()
+                    .map_err(|e| anyhow!("Cannot parse grpc listener {}: {:?}", i, e))?;
+                let mut get = |key: &str| -> Result<Option<String>> {
+                    table
+                        .remove(key)
+                        .map(|v| v.into_string())
+                        .transpose()
+                        .map_err(|e| {
+                            anyhow!("Cannot parse `{}` of grpc listener {}: {:?}", key, i, e)
+                        })
+                };
+                let address = get("address")?
+                    .ok_or_else(|| anyhow!("Missing `address` of grpc listener {}", i))?;
+                let address = SocketAddr::from_str(&address).map_err(|e| {
+                    anyhow!(
+                        "Cannot parse address {:?} of grpc listener {}: {:?}",
+                        address,
+                        i,
+                        e
+                    )
+                })?;
+                let tls = Self::tls_config(
+                    get("tls_cert_path")?,
+                    get("tls_key_path")?,
+                    get("tls_client_ca_path")?,
+                    &format!("grpc listener {}", i),
+                )?;
+                let ipv6_only = table
+                    .remove("ipv6_only")
+                    .map(|v| v.into_bool())
+                    .transpose()
+                    .map_err(|e| {
+                        anyhow!("Cannot parse `ipv6_only` of grpc listener {}: {:?}", i, e)
+                    })?
+                    .unwrap_or(false);
+                Ok(GrpcListener {
+                    address,
+                    ipv6_only,
+                    tls,
+                })
+            })
+            .collect()
+    }
+
+    fn p2p_config(c: &RawConfig) -> Result<Option<P2pConfig>> {
+        if !c.get_bool_opt("p2p.enabled")? {
+            return Ok(None);
+        }
+        Ok(Some(P2pConfig {
+            listen_address: c
+                .get_string_opt("p2p.listen_address")?
+                .unwrap_or("/ip4/0.0.0.0/tcp/34001".to_string()),
+            bootnodes: c.get_string_list_opt("p2p.bootnodes")?,
+        }))
+    }
 }
@@ -1,35 +1,81 @@
 use anyhow::Result;
-use chain_state::transactor::Transactor;
-use chain_utils::DefaultMiddleware;
+use chain_state::{
+    signer_keys::SignerKeys, stake_monitor::StakeMonitor, sync_progress::SyncProgress,
+    transactor::Transactor,
+};
+use chain_utils::{gas::GasStrategy, nonce_manager::NonceManager, DefaultMiddleware};
+use grpc::{RuntimeMonitor, VerificationMetrics};
 use std::sync::Arc;
-use storage::Storage;
-use tokio::sync::{Mutex, RwLock};
+use storage::{cold_storage::make_object_store, encryption::Keyring, Storage};
+use tokio::sync::Mutex;
 
-use crate::config::Config;
+use crate::config::{Config, NodeMode};
 
 pub struct Context {
     pub config: Config,
-    pub transactor: Arc<Mutex<Transactor>>,
-    pub db: Arc<RwLock<Storage>>,
-    pub provider: DefaultMiddleware,
+    /// `None` in storage-only mode.
+    pub transactor: Option<Arc<Mutex<Transactor>>>,
+    pub db: Arc<Storage>,
+    pub provider: Option<DefaultMiddleware>,
+    /// Nonces of the signer account, shared by all its senders.
+    pub nonce_manager: Option<NonceManager>,
+    pub sync_progress: SyncProgress,
+    pub stake_monitor: StakeMonitor,
+    pub runtime_monitor: RuntimeMonitor,
+    pub verification_metrics: VerificationMetrics,
+    pub signer_keys: SignerKeys,
 }
 
 impl Context {
     pub async fn new(config: Config) -> Result<Self> {
-        let provider =
-            chain_utils::make_provider(&config.eth_rpc_url, &config.signer_eth_private_key)
-                .await
-                .unwrap();
-        let transactor: Arc<Mutex<Transactor>> =
-            Arc::new(Mutex::new(Transactor::new(provider.clone()).unwrap()));
+        let provider = match chain_utils::make_provider(&config.eth_rpc_url, &config.signer_eth_key)
+            .await
+        {
+            Ok(provider) => Some(provider),
+            Err(e) if config.storage_only_fallback => {
+                error!(
+                    target: "alert",
+                    "chain rpc unreachable, starting in storage-only mode, restart the node once it recovers: {:?}",
+                    e
+                );
+                None
+            }
+            Err(e) => return Err(e),
+        };
+        let nonce_manager = provider.as_ref().map(|provider| {
+            NonceManager::new(provider.clone(), GasStrategy::new(config.gas.clone()))
+        });
         // db
-        let db = Arc::new(RwLock::new(Storage::new(&config.data_path).unwrap()));
+        let mut storage = Storage::new(&config.data_path)?;
+        if let Some(cold_storage) = &config.cold_storage {
+            storage = storage.with_cold_store(make_object_store(&cold_storage.store)?);
+        }
+        let keyring = match &config.encryption {
+            Some(encryption) => Some(Keyring::load(encryption)?),
+            None => None,
+        };
+        storage = storage.with_encryption(keyring)?;
+        let db = Arc::new(storage);
+        let transactor = match &nonce_manager {
+            Some(nonce_manager) => Some(Arc::new(Mutex::new(
+                Transactor::new(nonce_manager.clone(), db.clone())?
+                    .with_read_only(config.mode == NodeMode::Archive),
+            ))),
+            None => None,
+        };
 
+        let signer_keys = SignerKeys::new(config.signer_bls_private_key);
         Ok(Self {
             config,
             transactor,
             db,
             provider,
+            nonce_manager,
+            sync_progress: SyncProgress::default(),
+            stake_monitor: StakeMonitor::default(),
+            runtime_monitor: RuntimeMonitor::default(),
+            verification_metrics: VerificationMetrics::default(),
+            signer_keys,
         })
     }
 }
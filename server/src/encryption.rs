@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use storage::{
+    cold_storage::ColdStorageDB, encryption::EncryptionDB, quorum_db::QuorumDB, Storage,
+};
+use task_executor::TaskExecutor;
+
+/// Values rewritten in a transaction.
+const REENCRYPTION_BATCH_SIZE: usize = 1024;
+
+/// Rewrite slice values encrypted with retired keys using the active key, epoch by epoch, resuming
+/// from the persisted cursor after a restart. Values already tiered to cold storage keep their
+/// key, so retired keys stay in the keyring.
+pub fn start_reencryption(executor: TaskExecutor, db: Arc<Storage>) {
+    executor.spawn(
+        async move {
+            match reencrypt_slices(&db).await {
+                Ok(0) => {}
+                Ok(n) => info!("re-encryption finished, {:?} values rewritten", n),
+                Err(e) => error!("re-encryption error: {:?}", e),
+            }
+        },
+        "reencryption",
+    );
+}
+
+async fn reencrypt_slices(db: &Storage) -> Result<usize> {
+    let (first_epoch, latest_epoch) = {
+        match (
+            db.get_first_local_epoch().await?,
+            db.get_latest_epoch().await?,
+        ) {
+            (Some(first), Some(latest)) => (first, latest),
+            _ => return Ok(0),
+        }
+    };
+    let first_epoch = match db.get_reencryption_epoch().await? {
+        Some(epoch) => epoch.max(first_epoch),
+        None => first_epoch,
+    };
+    let mut rewritten = 0;
+    for epoch in first_epoch..=latest_epoch {
+        rewritten += db.reencrypt_epoch(epoch, REENCRYPTION_BATCH_SIZE).await?;
+    }
+    Ok(rewritten)
+}
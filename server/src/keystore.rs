@@ -0,0 +1,210 @@
+use std::{fs, path::Path};
+
+use aes::Aes128;
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::Fr;
+use ark_ff::{PrimeField, Zero};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use ethers::signers::LocalWallet;
+use hmac::Hmac;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use unicode_normalization::UnicodeNormalization;
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+#[derive(Deserialize)]
+struct Keystore {
+    version: u64,
+    crypto: Option<KeystoreCrypto>,
+}
+
+#[derive(Deserialize)]
+struct KeystoreCrypto {
+    kdf: KeystoreModule<KdfParams>,
+    checksum: KeystoreModule<serde_json::Value>,
+    cipher: KeystoreModule<CipherParams>,
+}
+
+#[derive(Deserialize)]
+struct KeystoreModule<P> {
+    function: String,
+    params: P,
+    message: String,
+}
+
+/// Params of both the `scrypt` and the `pbkdf2` functions.
+#[derive(Deserialize)]
+struct KdfParams {
+    dklen: usize,
+    salt: String,
+    n: Option<u32>,
+    r: Option<u32>,
+    p: Option<u32>,
+    c: Option<u32>,
+    prf: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CipherParams {
+    iv: String,
+}
+
+/// Password bytes as EIP-2335 derives the key from: NFKD normalized, without control codes.
+fn normalize_password(password: &str) -> Vec<u8> {
+    password
+        .nfkd()
+        .filter(|c| !matches!(*c as u32, 0x00..=0x1f | 0x7f..=0x9f))
+        .collect::<String>()
+        .into_bytes()
+}
+
+fn derive_key(kdf: &KeystoreModule<KdfParams>, password: &[u8]) -> Result<Vec<u8>> {
+    let params = &kdf.params;
+    let salt = hex::decode(&params.salt)?;
+    let mut key = vec![0u8; params.dklen];
+    match kdf.function.as_str() {
+        "scrypt" => {
+            let (n, r, p) = match (params.n, params.r, params.p) {
+                (Some(n), Some(r), Some(p)) if n.is_power_of_two() => (n, r, p),
+                _ => bail!(anyhow!("invalid scrypt params")),
+            };
+            let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p)
+                .map_err(|e| anyhow!("invalid scrypt params: {:?}", e))?;
+            scrypt::scrypt(password, &salt, &params, &mut key)
+                .map_err(|e| anyhow!("scrypt: {:?}", e))?;
+        }
+        "pbkdf2" => {
+            if params.prf.as_deref() != Some("hmac-sha256") {
+                bail!(anyhow!("unsupported pbkdf2 prf {:?}", params.prf));
+            }
+            let c = params.c.ok_or_else(|| anyhow!("invalid pbkdf2 params"))?;
+            pbkdf2::pbkdf2::<Hmac<Sha256>>(password, &salt, c, &mut key);
+        }
+        function => bail!(anyhow!("unsupported kdf `{}`", function)),
+    }
+    if key.len() < 32 {
+        bail!(anyhow!("kdf key of {} bytes is too short", key.len()));
+    }
+    Ok(key)
+}
+
+/// Secret of an EIP-2335 (version 4) keystore.
+fn decrypt_eip2335(crypto: &KeystoreCrypto, password: &str) -> Result<Vec<u8>> {
+    let key = derive_key(&crypto.kdf, &normalize_password(password))?;
+    let message = hex::decode(&crypto.cipher.message)?;
+    if crypto.checksum.function != "sha256" {
+        bail!(anyhow!(
+            "unsupported checksum `{}`",
+            crypto.checksum.function
+        ));
+    }
+    let checksum = Sha256::new()
+        .chain_update(&key[16..32])
+        .chain_update(&message)
+        .finalize();
+    if hex::encode(checksum) != crypto.checksum.message.to_lowercase() {
+        bail!(anyhow!("wrong keystore password"));
+    }
+    if crypto.cipher.function != "aes-128-ctr" {
+        bail!(anyhow!("unsupported cipher `{}`", crypto.cipher.function));
+    }
+    let iv = hex::decode(&crypto.cipher.params.iv)?;
+    if iv.len() != 16 {
+        bail!(anyhow!("invalid aes-128-ctr iv"));
+    }
+    let mut secret = message;
+    Aes128Ctr::new(key[..16].into(), iv.as_slice().into()).apply_keystream(&mut secret);
+    Ok(secret)
+}
+
+/// Signer BLS key of a keystore: an EIP-2335 keystore of another staking stack, or a web3 secret
+/// storage written by `keygen`. Secrets of BLS12-381 keys may exceed the BN254 group order, they
+/// are reduced modulo it, so the key is not the one of the other stack.
+pub fn load_bls_keystore(path: impl AsRef<Path>, password: &str) -> Result<Fr> {
+    let path = path.as_ref();
+    let json = fs::read_to_string(path)
+        .map_err(|e| anyhow!("cannot read keystore {:?}: {:?}", path, e))?;
+    let keystore: Keystore =
+        serde_json::from_str(&json).map_err(|e| anyhow!("invalid keystore {:?}: {:?}", path, e))?;
+    let secret = match (keystore.version, &keystore.crypto) {
+        (4, Some(crypto)) => decrypt_eip2335(crypto, password),
+        (3, _) => LocalWallet::decrypt_keystore(path, password)
+            .map(|wallet| wallet.signer().to_bytes().to_vec())
+            .map_err(|e| anyhow!("{:?}", e)),
+        (version, _) => Err(anyhow!("unsupported keystore version {}", version)),
+    }
+    .map_err(|e| anyhow!("cannot decrypt keystore {:?}: {:?}", path, e))?;
+    let key = Fr::from_be_bytes_mod_order(&secret);
+    if key.is_zero() {
+        bail!(anyhow!("keystore {:?} holds a zero key", path));
+    }
+    Ok(key)
+}
+
+/// Keystore password of a password file, without the trailing newline.
+pub fn read_password_file(path: impl AsRef<Path>) -> Result<String> {
+    let password = fs::read_to_string(path.as_ref())
+        .map_err(|e| anyhow!("cannot read password file {:?}: {:?}", path.as_ref(), e))?;
+    Ok(password.trim_end_matches(['\n', '\r']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test vector of EIP-2335.
+    const PASSWORD: &str = "\u{1d531}\u{1d522}\u{1d530}\u{1d531}\u{1d52d}\u{1d51e}\u{1d530}\u{1d530}\u{1d534}\u{1d52c}\u{1d52f}\u{1d521}\u{1f511}";
+    const SECRET: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+    const PBKDF2_KEYSTORE: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "pbkdf2",
+                "params": {
+                    "dklen": 32,
+                    "c": 262144,
+                    "prf": "hmac-sha256",
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": { "iv": "264daa3f303d7259501c93d997d84fe6" },
+                "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+            }
+        },
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/0/0",
+        "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+        "version": 4
+    }"#;
+
+    #[test]
+    fn eip2335_test() {
+        assert_eq!(
+            normalize_password("a\u{7f}b\u{1}"),
+            normalize_password("ab")
+        );
+        let keystore: Keystore = serde_json::from_str(PBKDF2_KEYSTORE).unwrap();
+        let crypto = keystore.crypto.unwrap();
+        assert_eq!(
+            hex::encode(decrypt_eip2335(&crypto, PASSWORD).unwrap()),
+            SECRET
+        );
+        assert!(decrypt_eip2335(&crypto, "wrong").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("keystore.json");
+        fs::write(&path, PBKDF2_KEYSTORE).unwrap();
+        assert_eq!(
+            load_bls_keystore(&path, PASSWORD).unwrap(),
+            Fr::from_be_bytes_mod_order(&hex::decode(SECRET).unwrap())
+        );
+    }
+}
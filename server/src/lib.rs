@@ -0,0 +1,28 @@
+#[macro_use]
+extern crate tracing;
+
+pub mod archive;
+mod backfill;
+mod cold_storage;
+pub mod config;
+mod context;
+mod encryption;
+pub mod keystore;
+mod message_bus;
+mod mock_chain;
+mod node;
+mod p2p;
+pub mod params;
+mod preallocation;
+mod reconcile;
+mod resync;
+mod runtime;
+mod scrubber;
+mod sign_monitor;
+mod systemd;
+pub mod telemetry;
+mod webhook;
+
+pub use config::Config;
+pub use events::{EventBus, NodeEvent};
+pub use node::{Node, NodeBuilder, NodeHandle};
@@ -0,0 +1,283 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Result};
+use events::{EventBus, NodeEvent};
+use serde_json::json;
+use storage::{
+    outbox_db::{OutboxDB, OutboxMessage},
+    Storage,
+};
+use task_executor::TaskExecutor;
+use tokio::{
+    sync::{broadcast::error::RecvError, Notify},
+    time::{sleep, timeout},
+};
+
+use crate::config::MessageBusConfig;
+
+const DRAIN_BATCH_SIZE: usize = 100;
+/// The outbox is drained at least this often, besides on every new message.
+const DRAIN_INTERVAL: Duration = Duration::from_secs(5);
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+#[cfg(feature = "kafka")]
+const KAFKA_SEND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Outbox message of an event, `None` for the events not published.
+fn outbox_message(config: &MessageBusConfig, event: &NodeEvent) -> Option<OutboxMessage> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let (topic, key, payload) = match event {
+        NodeEvent::BlobSigned {
+            epoch,
+            quorum_id,
+            storage_root,
+        } => (
+            &config.blob_signed_topic,
+            format!(
+                "blob_signed:{}:{}:{}",
+                epoch,
+                quorum_id,
+                hex::encode(storage_root)
+            ),
+            json!({
+                "event": "blob_signed",
+                "timestamp": timestamp,
+                "epoch": epoch,
+                "quorum_id": quorum_id,
+                "storage_root": hex::encode(storage_root),
+            }),
+        ),
+        NodeEvent::DasSubmissionConfirmed {
+            epoch,
+            quorum_id,
+            data_root,
+            tx_hash,
+        } => (
+            &config.das_topic,
+            format!("das_submission_confirmed:{}", hex::encode(tx_hash)),
+            json!({
+                "event": "das_submission_confirmed",
+                "timestamp": timestamp,
+                "epoch": epoch,
+                "quorum_id": quorum_id,
+                "data_root": hex::encode(data_root),
+                "tx_hash": hex::encode(tx_hash),
+            }),
+        ),
+        _ => return None,
+    };
+    Some(OutboxMessage {
+        topic: topic.clone(),
+        key,
+        payload: payload.to_string().into_bytes(),
+    })
+}
+
+/// Producer of the message bus, its client is compiled in with the `kafka` or `nats` feature.
+enum Sink {
+    #[cfg(feature = "kafka")]
+    Kafka(rdkafka::producer::FutureProducer),
+    #[cfg(feature = "nats")]
+    Nats(async_nats::jetstream::Context),
+}
+
+impl Sink {
+    async fn connect(config: &MessageBusConfig) -> Result<Self> {
+        match config.kind {
+            #[cfg(feature = "kafka")]
+            crate::config::MessageBusKind::Kafka => Ok(Sink::Kafka(
+                rdkafka::ClientConfig::new()
+                    .set("bootstrap.servers", &config.servers)
+                    .set("acks", "all")
+                    .set("enable.idempotence", "true")
+                    .create()?,
+            )),
+            #[cfg(feature = "nats")]
+            crate::config::MessageBusKind::Nats => Ok(Sink::Nats(async_nats::jetstream::new(
+                async_nats::connect(config.servers.as_str()).await?,
+            ))),
+            #[allow(unreachable_patterns)]
+            kind => bail!(anyhow!(
+                "{:?} publishing is not built in, rebuild with its cargo feature",
+                kind
+            )),
+        }
+    }
+
+    /// Publish a message, returning once the bus has persisted it.
+    #[cfg_attr(not(any(feature = "kafka", feature = "nats")), allow(unused_variables))]
+    async fn publish(&self, message: &OutboxMessage) -> Result<()> {
+        match self {
+            #[cfg(feature = "kafka")]
+            Sink::Kafka(producer) => producer
+                .send(
+                    rdkafka::producer::FutureRecord::to(&message.topic)
+                        .key(&message.key)
+                        .payload(&message.payload),
+                    KAFKA_SEND_TIMEOUT,
+                )
+                .await
+                .map(|_| ())
+                .map_err(|(e, _)| anyhow!(e)),
+            #[cfg(feature = "nats")]
+            Sink::Nats(jetstream) => {
+                // the stream drops the messages published again within its duplicate window
+                let mut headers = async_nats::HeaderMap::new();
+                headers.insert("Nats-Msg-Id", message.key.as_str());
+                jetstream
+                    .publish_with_headers(
+                        message.topic.clone(),
+                        headers,
+                        message.payload.clone().into(),
+                    )
+                    .await?
+                    .await?;
+                Ok(())
+            }
+            #[allow(unreachable_patterns)]
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Publish the messages of the outbox in order, removing them once published. Returns the number
+/// of messages published, and the error that stopped it.
+async fn drain_outbox(db: &Storage, sink: &Sink) -> (usize, Option<anyhow::Error>) {
+    let messages = match db.peek_outbox(DRAIN_BATCH_SIZE).await {
+        Ok(messages) => messages,
+        Err(e) => return (0, Some(e)),
+    };
+    let mut published = vec![];
+    let mut error = None;
+    for (seq, message) in &messages {
+        if let Err(e) = sink.publish(message).await {
+            error = Some(e);
+            break;
+        }
+        published.push(*seq);
+    }
+    if let Err(e) = db.ack_outbox(&published).await {
+        // acked again once published again
+        error.get_or_insert(e);
+    }
+    (published.len(), error)
+}
+
+async fn run_publisher(db: Arc<Storage>, config: MessageBusConfig, new_messages: Arc<Notify>) {
+    let mut backoff = INITIAL_BACKOFF;
+    let sink = loop {
+        match Sink::connect(&config).await {
+            Ok(sink) => break sink,
+            Err(e) => warn!(servers = %config.servers, "cannot connect to message bus: {:?}", e),
+        }
+        sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    };
+    info!(kind = ?config.kind, "connected to message bus");
+    backoff = INITIAL_BACKOFF;
+    loop {
+        match drain_outbox(&db, &sink).await {
+            (_, Some(e)) => {
+                warn!("cannot publish to message bus: {:?}", e);
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+            (n, None) => {
+                backoff = INITIAL_BACKOFF;
+                if n < DRAIN_BATCH_SIZE {
+                    let _ = timeout(DRAIN_INTERVAL, new_messages.notified()).await;
+                }
+            }
+        }
+    }
+}
+
+/// Publish blob signed and DAS submission events to the message bus. Events are written to the
+/// outbox of `db` first and removed once the bus acknowledges them, so the ones in the outbox are
+/// delivered at least once across restarts and bus outages. Events are read from the event bus
+/// after they happen, an event skipped by a lagging reader, not written to the outbox or emitted
+/// right before a crash is lost, which raises an alert in the first two cases.
+pub fn start_message_bus_publisher(
+    executor: &TaskExecutor,
+    db: Arc<Storage>,
+    config: MessageBusConfig,
+    events: &EventBus,
+) {
+    let mut receiver = events.subscribe();
+    let new_messages = Arc::new(Notify::new());
+    let outbox_db = db.clone();
+    let outbox_config = config.clone();
+    let notify = new_messages.clone();
+    executor.spawn(
+        async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        error!(
+                            target: "alert",
+                            skipped, "message bus outbox lagged, events are not published"
+                        );
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                let message = match outbox_message(&outbox_config, &event) {
+                    Some(message) => message,
+                    None => continue,
+                };
+                let message_key = message.key.clone();
+                match outbox_db.push_outbox(vec![message]).await {
+                    Ok(()) => notify.notify_one(),
+                    Err(e) => error!(
+                        target: "alert",
+                        key = %message_key,
+                        "cannot write message bus outbox, the event is not published: {:?}",
+                        e
+                    ),
+                }
+            }
+        },
+        "message_bus_outbox",
+    );
+    executor.spawn(
+        run_publisher(db, config, new_messages),
+        "message_bus_publisher",
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::MessageBusKind;
+
+    use super::*;
+
+    #[test]
+    fn outbox_message_test() {
+        let config = MessageBusConfig {
+            kind: MessageBusKind::Kafka,
+            servers: "localhost:9092".to_string(),
+            blob_signed_topic: "signed".to_string(),
+            das_topic: "das".to_string(),
+        };
+        let message = outbox_message(
+            &config,
+            &NodeEvent::BlobSigned {
+                epoch: 2,
+                quorum_id: 1,
+                storage_root: [0; 32],
+            },
+        )
+        .unwrap();
+        assert_eq!(message.topic, "signed");
+        assert_eq!(message.key, format!("blob_signed:2:1:{}", "00".repeat(32)));
+        let payload: serde_json::Value = serde_json::from_slice(&message.payload).unwrap();
+        assert_eq!(payload["epoch"], 2);
+        assert!(outbox_message(&config, &NodeEvent::Started).is_none());
+    }
+}
@@ -0,0 +1,655 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    convert::Infallible,
+    net::SocketAddr,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use chain_utils::DA_SIGNER_ADDRESS;
+use contract_interface::{
+    da_entrance::{
+        DAEntranceCalls, DataUploadFilter, ErasureCommitmentVerifiedFilter, SampleRange,
+    },
+    da_signers::{DASignersCalls, SignerDetail},
+};
+use ethers::{
+    abi::{self, AbiDecode, Token, Tokenizable},
+    contract::EthEvent,
+    types::{
+        transaction::eip2718::TypedTransaction, Address, Block, BlockNumber, Bytes, FeeHistory,
+        Filter, FilterBlockOption, Log, TransactionReceipt, ValueOrArray, H256, U256, U64,
+    },
+    utils::{keccak256, rlp::Rlp},
+};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server,
+};
+use serde_json::{json, Value};
+use task_executor::TaskExecutor;
+
+use crate::config::MockChainConfig;
+
+const GAS_PRICE: u64 = 1_000_000_000;
+const GAS_LIMIT: u64 = 30_000_000;
+const GAS_USED: u64 = 100_000;
+
+/// State of the contracts, changed by the transactions sent to the mock chain.
+#[derive(Default)]
+struct ChainData {
+    signers: BTreeMap<Address, SignerDetail>,
+    /// Signers registered for each epoch.
+    epochs: BTreeMap<u64, BTreeSet<Address>>,
+    /// Erasure commitments of the verified blobs, by data root, epoch and quorum.
+    verified: HashMap<([u8; 32], u64, u64), (U256, U256)>,
+    nonces: HashMap<Address, u64>,
+    receipts: HashMap<H256, TransactionReceipt>,
+    logs: Vec<Log>,
+}
+
+/// A chain answering the rpc calls of the node as the DASigners and DAEntrance contracts would.
+/// Blocks are produced at a fixed interval and final at once. Transactions are mined in the
+/// current block without checking signatures of blobs or keys, the others than registrations,
+/// uploads and commitment verifications are mined with no effect.
+pub struct MockChain {
+    config: MockChainConfig,
+    da_entrance: Address,
+    da_signers: Address,
+    genesis: Instant,
+    genesis_timestamp: u64,
+    data: Mutex<ChainData>,
+}
+
+impl MockChain {
+    pub fn new(config: MockChainConfig, da_entrance: Address) -> Self {
+        Self {
+            config,
+            da_entrance,
+            da_signers: Address::from_str(DA_SIGNER_ADDRESS).unwrap(),
+            genesis: Instant::now(),
+            genesis_timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            data: Mutex::new(ChainData::default()),
+        }
+    }
+
+    fn block_number(&self) -> u64 {
+        1 + (self.genesis.elapsed().as_millis() / self.config.block_interval.as_millis()) as u64
+    }
+
+    fn epoch_at(&self, block: u64) -> u64 {
+        block / self.config.blocks_per_epoch
+    }
+
+    fn resolve_block(&self, block: Option<BlockNumber>) -> u64 {
+        let head = self.block_number();
+        match block {
+            Some(BlockNumber::Number(n)) => n.as_u64().min(head),
+            Some(BlockNumber::Earliest) => 0,
+            _ => head,
+        }
+    }
+
+    /// Rows of a quorum of `epoch`, dealt in turn to the signers registered for it, empty if none
+    /// or if the quorum does not exist.
+    fn quorum(&self, data: &ChainData, epoch: u64, quorum_id: u64) -> Vec<Address> {
+        let signers: Vec<Address> = match data.epochs.get(&epoch) {
+            Some(signers) if quorum_id < self.config.quorums => signers.iter().cloned().collect(),
+            _ => return vec![],
+        };
+        (0..self.config.quorum_size)
+            .map(|row| signers[((row + quorum_id) % signers.len() as u64) as usize])
+            .collect()
+    }
+
+    fn quorum_count(&self, data: &ChainData, epoch: u64) -> u64 {
+        match data.epochs.get(&epoch) {
+            Some(signers) if !signers.is_empty() => self.config.quorums,
+            _ => 0,
+        }
+    }
+
+    fn block_hash(number: u64) -> H256 {
+        H256(keccak256(number.to_be_bytes()))
+    }
+
+    fn block(&self, number: u64) -> Block<H256> {
+        Block {
+            hash: Some(Self::block_hash(number)),
+            parent_hash: Self::block_hash(number.saturating_sub(1)),
+            number: Some(U64::from(number)),
+            timestamp: U256::from(
+                self.genesis_timestamp + number * self.config.block_interval.as_secs(),
+            ),
+            gas_limit: U256::from(GAS_LIMIT),
+            base_fee_per_gas: Some(U256::from(GAS_PRICE)),
+            ..Default::default()
+        }
+    }
+
+    /// Reply of a json-rpc call, its error message otherwise.
+    fn handle(&self, method: &str, params: &[Value]) -> Result<Value> {
+        let param = |i: usize| params.get(i).cloned().unwrap_or(Value::Null);
+        Ok(match method {
+            "eth_chainId" => json!(U64::from(self.config.chain_id)),
+            "net_version" => json!(self.config.chain_id.to_string()),
+            "eth_blockNumber" => json!(U64::from(self.block_number())),
+            "eth_getBlockByNumber" => {
+                let block = serde_json::from_value::<BlockNumber>(param(0)).ok();
+                json!(self.block(self.resolve_block(block)))
+            }
+            "eth_gasPrice" | "eth_maxPriorityFeePerGas" => json!(U256::from(GAS_PRICE)),
+            "eth_feeHistory" => {
+                let blocks = serde_json::from_value::<U256>(param(0))
+                    .map_or(1, |x| x.as_usize())
+                    .max(1);
+                json!(FeeHistory {
+                    base_fee_per_gas: vec![U256::from(GAS_PRICE); blocks + 1],
+                    gas_used_ratio: vec![0.5; blocks],
+                    oldest_block: U256::from(self.block_number().saturating_sub(blocks as u64)),
+                    reward: vec![vec![U256::from(GAS_PRICE)]; blocks],
+                })
+            }
+            "eth_estimateGas" => json!(U256::from(GAS_USED)),
+            "eth_getBalance" => json!(U256::exp10(24)),
+            "eth_getCode" => {
+                let address: Address = serde_json::from_value(param(0))?;
+                if address == self.da_entrance || address == self.da_signers {
+                    json!(Bytes::from(vec![0xfe]))
+                } else {
+                    json!(Bytes::default())
+                }
+            }
+            "eth_getTransactionCount" => {
+                let address: Address = serde_json::from_value(param(0))?;
+                let data = self.data.lock().unwrap();
+                json!(U256::from(data.nonces.get(&address).copied().unwrap_or(0)))
+            }
+            "eth_call" => {
+                let tx = param(0);
+                let to: Address = serde_json::from_value(tx["to"].clone())?;
+                let input = match tx.get("input").or_else(|| tx.get("data")) {
+                    Some(input) => serde_json::from_value(input.clone())?,
+                    None => Bytes::default(),
+                };
+                let block = serde_json::from_value::<BlockNumber>(param(1)).ok();
+                json!(Bytes::from(self.call(
+                    to,
+                    &input,
+                    self.resolve_block(block)
+                )?))
+            }
+            "eth_sendRawTransaction" => {
+                let raw: Bytes = serde_json::from_value(param(0))?;
+                json!(self.send_raw_transaction(&raw)?)
+            }
+            "eth_getTransactionReceipt" => {
+                let hash: H256 = serde_json::from_value(param(0))?;
+                json!(self.data.lock().unwrap().receipts.get(&hash))
+            }
+            "eth_getLogs" => {
+                let filter: Filter = serde_json::from_value(param(0))?;
+                json!(self.logs(&filter))
+            }
+            _ => {
+                return Err(anyhow!(
+                    "method {} is not supported by the mock chain",
+                    method
+                ))
+            }
+        })
+    }
+
+    fn call(&self, to: Address, input: &[u8], block: u64) -> Result<Vec<u8>> {
+        let data = self.data.lock().unwrap();
+        let epoch = self.epoch_at(block);
+        let token = if to == self.da_signers {
+            match DASignersCalls::decode(input)? {
+                DASignersCalls::EpochNumber(_) => U256::from(epoch).into_token(),
+                DASignersCalls::QuorumCount(c) => {
+                    U256::from(self.quorum_count(&data, c.epoch.as_u64())).into_token()
+                }
+                DASignersCalls::GetQuorum(c) => self
+                    .quorum(&data, c.epoch.as_u64(), c.quorum_id.as_u64())
+                    .into_token(),
+                DASignersCalls::GetQuorumRow(c) => self
+                    .quorum(&data, c.epoch.as_u64(), c.quorum_id.as_u64())
+                    .get(c.row_index as usize)
+                    .copied()
+                    .ok_or_else(|| anyhow!("row not found"))?
+                    .into_token(),
+                DASignersCalls::GetSigner(c) => c
+                    .account
+                    .iter()
+                    .map(|account| {
+                        data.signers
+                            .get(account)
+                            .cloned()
+                            .unwrap_or_else(|| SignerDetail {
+                                signer: *account,
+                                ..Default::default()
+                            })
+                    })
+                    .collect::<Vec<_>>()
+                    .into_token(),
+                DASignersCalls::IsSigner(c) => data.signers.contains_key(&c.account).into_token(),
+                DASignersCalls::RegisteredEpoch(c) => data
+                    .epochs
+                    .get(&c.epoch.as_u64())
+                    .map_or(false, |signers| signers.contains(&c.account))
+                    .into_token(),
+                call => return Err(anyhow!("{:?} is not supported by the mock chain", call)),
+            }
+        } else if to == self.da_entrance {
+            match DAEntranceCalls::decode(input)? {
+                DAEntranceCalls::CurrentEpoch(_) => U256::from(epoch).into_token(),
+                DAEntranceCalls::EpochWindowSize(_) => {
+                    U256::from(self.config.epoch_window_size).into_token()
+                }
+                DAEntranceCalls::SampleRange(_) => SampleRange {
+                    start_epoch: epoch.saturating_sub(self.config.epoch_window_size),
+                    end_epoch: epoch,
+                }
+                .into_token(),
+                DAEntranceCalls::CommitmentExists(c) => data
+                    .verified
+                    .contains_key(&(c.data_root, c.epoch.as_u64(), c.quorum_id.as_u64()))
+                    .into_token(),
+                DAEntranceCalls::VerifiedErasureCommitment(c) => {
+                    let (x, y) = data
+                        .verified
+                        .get(&(c.data_root, c.epoch.as_u64(), c.quorum_id.as_u64()))
+                        .copied()
+                        .unwrap_or_default();
+                    Token::Tuple(vec![x.into_token(), y.into_token()])
+                }
+                call => return Err(anyhow!("{:?} is not supported by the mock chain", call)),
+            }
+        } else {
+            return Err(anyhow!("no contract at {:?}", to));
+        };
+        Ok(abi::encode(&[token]))
+    }
+
+    /// Mine a signed transaction in the current block, a failed one is mined reverted.
+    fn send_raw_transaction(&self, raw: &[u8]) -> Result<H256> {
+        let (tx, signature) = TypedTransaction::decode_signed(&Rlp::new(raw))
+            .map_err(|e| anyhow!("invalid transaction: {}", e))?;
+        let from = signature.recover(tx.sighash())?;
+        let hash = H256(keccak256(raw));
+        let block = self.block_number();
+        let mut data = self.data.lock().unwrap();
+        let nonce = data.nonces.entry(from).or_default();
+        let tx_nonce = tx.nonce().map_or(*nonce, |x| x.as_u64());
+        if tx_nonce != *nonce {
+            return Err(anyhow!("nonce {} of {:?} expected", nonce, from));
+        }
+        *nonce += 1;
+
+        let to = tx.to_addr().copied();
+        let input = tx.data().cloned().unwrap_or_default();
+        let mut logs = match to {
+            Some(to) => self.execute(&mut data, from, to, &input, block),
+            None => Ok(vec![]),
+        };
+        if let Err(e) = &logs {
+            warn!(?hash, ?from, "mock chain transaction reverted: {:?}", e);
+        }
+        let success = logs.is_ok();
+        let log_index = data.logs.len();
+        for (i, log) in logs.iter_mut().flatten().enumerate() {
+            log.block_number = Some(U64::from(block));
+            log.block_hash = Some(Self::block_hash(block));
+            log.transaction_hash = Some(hash);
+            log.log_index = Some(U256::from(log_index + i));
+            log.removed = Some(false);
+        }
+        let logs = logs.unwrap_or_default();
+        data.logs.extend(logs.iter().cloned());
+        data.receipts.insert(
+            hash,
+            TransactionReceipt {
+                transaction_hash: hash,
+                block_hash: Some(Self::block_hash(block)),
+                block_number: Some(U64::from(block)),
+                from,
+                to,
+                cumulative_gas_used: U256::from(GAS_USED),
+                gas_used: Some(U256::from(GAS_USED)),
+                effective_gas_price: Some(U256::from(GAS_PRICE)),
+                status: Some(U64::from(success as u64)),
+                logs,
+                ..Default::default()
+            },
+        );
+        Ok(hash)
+    }
+
+    fn execute(
+        &self,
+        data: &mut ChainData,
+        from: Address,
+        to: Address,
+        input: &[u8],
+        block: u64,
+    ) -> Result<Vec<Log>> {
+        let epoch = self.epoch_at(block);
+        if to == self.da_signers {
+            match DASignersCalls::decode(input)? {
+                DASignersCalls::RegisterSigner(c) => {
+                    if c.signer.signer != from {
+                        return Err(anyhow!("signer is not the sender"));
+                    }
+                    data.signers.insert(from, c.signer);
+                    // a devnet without signers serves its current epoch at once
+                    let current = data.epochs.entry(epoch).or_default();
+                    if current.is_empty() {
+                        current.insert(from);
+                    }
+                }
+                DASignersCalls::UpdateSocket(c) => {
+                    data.signers
+                        .get_mut(&from)
+                        .ok_or_else(|| anyhow!("signer not found"))?
+                        .socket = c.socket;
+                }
+                DASignersCalls::RegisterNextEpoch(_) => {
+                    if !data.signers.contains_key(&from) {
+                        return Err(anyhow!("signer not found"));
+                    }
+                    data.epochs.entry(epoch + 1).or_default().insert(from);
+                }
+                _ => {}
+            }
+            return Ok(vec![]);
+        }
+        if to != self.da_entrance {
+            return Ok(vec![]);
+        }
+        let mut logs = vec![];
+        match DAEntranceCalls::decode(input)? {
+            DAEntranceCalls::SubmitOriginalData(c) => {
+                let quorums = self.quorum_count(data, epoch);
+                if quorums == 0 {
+                    return Err(anyhow!("no quorum in epoch {}", epoch));
+                }
+                for data_root in c.data_roots {
+                    let quorum_id = U256::from_big_endian(&data_root) % quorums;
+                    logs.push(self.log(
+                        DataUploadFilter::signature(),
+                        vec![
+                            from.into_token(),
+                            Token::FixedBytes(data_root.to_vec()),
+                            U256::from(epoch).into_token(),
+                            quorum_id.into_token(),
+                            U256::zero().into_token(),
+                        ],
+                    ));
+                }
+            }
+            DAEntranceCalls::SubmitVerifiedCommitRoots(c) => {
+                for submission in c.submissions {
+                    let commitment = submission.erasure_commitment;
+                    data.verified.insert(
+                        (
+                            submission.data_root,
+                            submission.epoch.as_u64(),
+                            submission.quorum_id.as_u64(),
+                        ),
+                        (commitment.x, commitment.y),
+                    );
+                    logs.push(self.log(
+                        ErasureCommitmentVerifiedFilter::signature(),
+                        vec![
+                            Token::FixedBytes(submission.data_root.to_vec()),
+                            submission.epoch.into_token(),
+                            submission.quorum_id.into_token(),
+                        ],
+                    ));
+                }
+            }
+            _ => {}
+        }
+        Ok(logs)
+    }
+
+    fn log(&self, topic: H256, tokens: Vec<Token>) -> Log {
+        Log {
+            address: self.da_entrance,
+            topics: vec![topic],
+            data: abi::encode(&tokens).into(),
+            ..Default::default()
+        }
+    }
+
+    fn logs(&self, filter: &Filter) -> Vec<Log> {
+        let (from, to) = match filter.block_option {
+            FilterBlockOption::Range {
+                from_block,
+                to_block,
+            } => (self.resolve_block(from_block), self.resolve_block(to_block)),
+            FilterBlockOption::AtBlockHash(_) => return vec![],
+        };
+        let topic0 = &filter.topics[0];
+        self.data
+            .lock()
+            .unwrap()
+            .logs
+            .iter()
+            .filter(|log| {
+                let block = log.block_number.map_or(0, |x| x.as_u64());
+                let address_matches = match &filter.address {
+                    None => true,
+                    Some(ValueOrArray::Value(address)) => *address == log.address,
+                    Some(ValueOrArray::Array(addresses)) => addresses.contains(&log.address),
+                };
+                let topic_matches = match topic0 {
+                    None | Some(ValueOrArray::Value(None)) => true,
+                    Some(ValueOrArray::Value(Some(topic))) => *topic == log.topics[0],
+                    Some(ValueOrArray::Array(topics)) => topics
+                        .iter()
+                        .any(|topic| topic.map_or(true, |x| x == log.topics[0])),
+                };
+                from <= block && block <= to && address_matches && topic_matches
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+fn rpc_reply(chain: &MockChain, request: &Value) -> Value {
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = request.get("method").and_then(|x| x.as_str()).unwrap_or("");
+    let params = match request.get("params") {
+        Some(Value::Array(params)) => params.as_slice(),
+        _ => &[],
+    };
+    match chain.handle(method, params) {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => {
+            debug!(method, "mock chain call failed: {:?}", e);
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32000, "message": e.to_string() },
+            })
+        }
+    }
+}
+
+async fn serve_rpc(
+    chain: Arc<MockChain>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let reply = match hyper::body::to_bytes(request.into_body())
+        .await
+        .map_err(|e| anyhow!(e))
+        .and_then(|body| Ok(serde_json::from_slice::<Value>(&body)?))
+    {
+        Ok(Value::Array(requests)) => Value::Array(
+            requests
+                .iter()
+                .map(|request| rpc_reply(&chain, request))
+                .collect(),
+        ),
+        Ok(request) => rpc_reply(&chain, &request),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": null,
+            "error": { "code": -32700, "message": e.to_string() },
+        }),
+    };
+    Ok(Response::builder()
+        .header("content-type", "application/json")
+        .body(Body::from(reply.to_string()))
+        .unwrap())
+}
+
+/// Serve the json-rpc endpoint of a mock chain, returns its url.
+pub fn start_mock_chain(
+    executor: &TaskExecutor,
+    config: &MockChainConfig,
+    da_entrance: Address,
+) -> Result<String> {
+    let addr = SocketAddr::from_str(&config.listen_address)?;
+    let chain = Arc::new(MockChain::new(config.clone(), da_entrance));
+    let server = Server::try_bind(&addr)
+        .map_err(|e| anyhow!("cannot listen on {}: {:?}", addr, e))?
+        .serve(make_service_fn(move |_| {
+            let chain = chain.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| serve_rpc(chain.clone(), request)))
+            }
+        }));
+    let url = format!("http://{}", server.local_addr());
+    warn!(
+        %url,
+        chain_id = config.chain_id,
+        "running on the mock chain backend, nothing is sent to a real chain"
+    );
+    executor.spawn(
+        async move {
+            if let Err(e) = server.await {
+                error!("mock chain stopped: {:?}", e);
+            }
+        },
+        "mock_chain",
+    );
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use contract_interface::{
+        da_entrance::SubmitOriginalDataCall,
+        da_signers::{RegisterNextEpochCall, RegisterSignerCall},
+    };
+    use ethers::{
+        abi::AbiEncode,
+        signers::{LocalWallet, Signer},
+        types::TransactionRequest,
+    };
+    use std::time::Duration;
+
+    fn config() -> MockChainConfig {
+        MockChainConfig {
+            listen_address: "127.0.0.1:0".to_string(),
+            chain_id: 31337,
+            block_interval: Duration::from_secs(3600),
+            blocks_per_epoch: 1,
+            quorums: 2,
+            quorum_size: 4,
+            epoch_window_size: 100,
+        }
+    }
+
+    fn send(chain: &MockChain, wallet: &LocalWallet, to: Address, input: Vec<u8>) -> bool {
+        let nonce = chain
+            .data
+            .lock()
+            .unwrap()
+            .nonces
+            .get(&wallet.address())
+            .copied()
+            .unwrap_or(0);
+        let tx: TypedTransaction = TransactionRequest::new()
+            .to(to)
+            .data(input)
+            .nonce(nonce)
+            .gas(GAS_USED)
+            .gas_price(GAS_PRICE)
+            .chain_id(31337u64)
+            .into();
+        let signature = wallet.sign_transaction_sync(&tx).unwrap();
+        let hash = chain
+            .send_raw_transaction(&tx.rlp_signed(&signature))
+            .unwrap();
+        chain.data.lock().unwrap().receipts[&hash].status == Some(U64::from(1))
+    }
+
+    #[test]
+    fn mock_chain_test() {
+        let da_entrance = Address::repeat_byte(0xda);
+        let chain = MockChain::new(config(), da_entrance);
+        let wallets: Vec<LocalWallet> = (1..=2u8)
+            .map(|i| {
+                LocalWallet::from_bytes(&[i; 32])
+                    .unwrap()
+                    .with_chain_id(31337u64)
+            })
+            .collect();
+        let upload = SubmitOriginalDataCall {
+            data_roots: vec![[1; 32], [2; 32]],
+        }
+        .encode();
+
+        // no quorum before the first signer registers
+        assert!(!send(&chain, &wallets[0], da_entrance, upload.clone()));
+        for wallet in wallets.iter() {
+            let register = RegisterSignerCall {
+                signer: SignerDetail {
+                    signer: wallet.address(),
+                    socket: "127.0.0.1:34000".to_string(),
+                    ..Default::default()
+                },
+                signature: Default::default(),
+            };
+            assert!(send(&chain, wallet, chain.da_signers, register.encode()));
+            let register_epoch = RegisterNextEpochCall {
+                signature: Default::default(),
+            };
+            assert!(send(
+                &chain,
+                wallet,
+                chain.da_signers,
+                register_epoch.encode()
+            ));
+        }
+
+        let data = chain.data.lock().unwrap();
+        // the first signer serves the current epoch alone, both the next one
+        assert_eq!(chain.quorum(&data, 1, 0), vec![wallets[0].address(); 4]);
+        let next = chain.quorum(&data, 2, 1);
+        assert_eq!(next.len(), 4);
+        assert_eq!(
+            next.iter().filter(|x| **x == wallets[1].address()).count(),
+            2
+        );
+        assert_eq!(chain.quorum_count(&data, 2), 2);
+        assert!(chain.quorum(&data, 2, 2).is_empty());
+        drop(data);
+
+        assert!(send(&chain, &wallets[1], da_entrance, upload));
+        let filter = Filter::new()
+            .from_block(0)
+            .to_block(BlockNumber::Latest)
+            .address(da_entrance)
+            .topic0(DataUploadFilter::signature());
+        assert_eq!(chain.logs(&filter).len(), 2);
+    }
+}
@@ -0,0 +1,917 @@
+use std::{collections::HashMap, net::SocketAddr, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail, Result};
+use chain_state::{
+    da_handler::run_da_monitor, discovery::start_peer_discovery, expiry_gc::run_expiry_gc,
+    forks::ForkSchedule, signers_handler::run_epoch_registration, stake_monitor::run_stake_monitor,
+    transactor::Transactor, ChainState,
+};
+use chain_utils::{gas::GasStrategy, make_provider, nonce_manager::NonceManager};
+use da_miner::{DasMineService, DasScheduler};
+use events::{EventBus, NodeEvent};
+use grpc::{
+    run_admin_server, run_health_server, run_retrieval_server, run_server, run_status_page_server,
+    AdminService, AuditLog, BatchProxy, BatchProxyConfig, ClusterRole, GrpcListener, HealthProbes,
+    LoadShedder, LoadSheddingConfig, Maintenance, NetworkRouter, ResourceMonitor, SignerConfig,
+    SignerService, SliceVerifier, VerificationQueue,
+};
+use task_executor::{ShutdownReason, TaskExecutor};
+use tokio::{
+    runtime::Handle,
+    sync::{broadcast, watch, Mutex, RwLock},
+    time::{interval, timeout},
+};
+
+use crate::{
+    backfill::start_backfill_verifier,
+    cold_storage::start_cold_storage_tiering,
+    config::{Config, GrpcRuntimesConfig, NodeMode, RuntimeMonitorConfig, SupervisorConfig},
+    context::Context,
+    encryption::start_reencryption,
+    message_bus::start_message_bus_publisher,
+    mock_chain::start_mock_chain,
+    p2p::start_p2p,
+    params::download_params,
+    preallocation::start_preallocation,
+    reconcile::start_reconciliation,
+    resync::start_resync,
+    runtime::{make_environment, spawn_supervised, DedicatedRuntime, Environment},
+    scrubber::start_slice_scrubber,
+    sign_monitor::start_sign_monitor,
+    systemd::{notify_stopping, start_systemd_notify},
+    webhook::start_webhooks,
+};
+
+const BATCH_PROXY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Default)]
+pub struct NodeBuilder {
+    config: Option<Config>,
+    event_capacity: Option<usize>,
+}
+
+impl NodeBuilder {
+    pub fn config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Number of events buffered for each subscriber before the slowest one starts missing events.
+    pub fn event_capacity(mut self, event_capacity: usize) -> Self {
+        self.event_capacity = Some(event_capacity);
+        self
+    }
+
+    pub fn build(self) -> Result<Node> {
+        let config = self
+            .config
+            .ok_or_else(|| anyhow!("node config is not set"))?;
+        Ok(Node {
+            config,
+            events: self.event_capacity.map(EventBus::new).unwrap_or_default(),
+        })
+    }
+}
+
+/// A DA node which can be embedded in other applications. It must be started within a tokio runtime.
+pub struct Node {
+    config: Config,
+    events: EventBus,
+}
+
+impl Node {
+    pub fn builder() -> NodeBuilder {
+        NodeBuilder::default()
+    }
+
+    /// Subscribe node events, subscribe before `start` to receive the `Started` event.
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.events.subscribe()
+    }
+
+    pub async fn start(self) -> Result<NodeHandle> {
+        let (environment, executor) = make_environment(Handle::current());
+        let mut config = self.config;
+        let health_probes = HealthProbes::default();
+        if let Some(addr) = &config.health_listen_address {
+            start_health_server(
+                executor.clone(),
+                &config.supervisor,
+                SocketAddr::from_str(addr)?,
+                health_probes.clone(),
+            );
+        }
+        if let Some(params_download) = &config.params_download {
+            download_params(params_download, &config.encoder_params_dir).await?;
+        }
+        if let Some(mock_chain) = &config.mock_chain {
+            config.eth_rpc_url =
+                start_mock_chain(&executor, mock_chain, config.da_entrance_address)?;
+        }
+        let ctx = Context::new(config).await?;
+
+        // rayon
+        if let Some(num_threads) = ctx.config.max_verify_threads {
+            if let Err(e) = rayon::ThreadPoolBuilder::new()
+                .num_threads(num_threads)
+                .build_global()
+            {
+                warn!("cannot set verification threads: {:?}", e);
+            }
+        }
+
+        let grpc_runtimes = GrpcRuntimes::new(&ctx.config.grpc_runtimes)?;
+        if let Some(config) = &ctx.config.runtime_monitor {
+            start_runtime_monitor(&ctx, &executor, &grpc_runtimes, config);
+        }
+        if let Some(config) = &ctx.config.webhook {
+            start_webhooks(executor.clone(), config.clone(), &self.events);
+        }
+        if let Some(config) = &ctx.config.message_bus {
+            start_message_bus_publisher(&executor, ctx.db.clone(), config.clone(), &self.events);
+        }
+        let shared = SignerShared {
+            events: self.events.clone(),
+            audit_log: match &ctx.config.audit_log_path {
+                Some(path) => {
+                    Some(Arc::new(AuditLog::open(path).map_err(|e| {
+                        anyhow!("cannot open audit log {}: {:?}", path, e)
+                    })?))
+                }
+                None => None,
+            },
+            maintenance: Maintenance::default(),
+            load_shedder: ctx
+                .config
+                .load_shedding
+                .as_ref()
+                .map(|config| start_resource_monitor(&executor, config)),
+            verification_queue: ctx
+                .config
+                .max_concurrent_verifications
+                .map(VerificationQueue::new),
+            slice_verifier: SliceVerifier::new(
+                ctx.config.encoder_params_dir.clone(),
+                &ctx.config.params_versions,
+                ctx.config.params_load_mode,
+            ),
+        };
+        let (grpc_stopping, _) = watch::channel(false);
+        let mut signers = vec![];
+        let mut networks = HashMap::new();
+        for network in &ctx.config.networks {
+            info!(network = %network.name, "starting signer on network");
+            let network_ctx = Context::new(ctx.config.for_network(network)).await?;
+            if let Some(service) = start_identity(
+                &network_ctx,
+                executor.clone(),
+                &grpc_runtimes,
+                shared.clone(),
+            )
+            .await?
+            {
+                signers.push(service.clone());
+                networks.insert(network.name.clone(), service);
+            }
+        }
+        if let Some(service) =
+            start_identity(&ctx, executor.clone(), &grpc_runtimes, shared.clone()).await?
+        {
+            signers.push(service.clone());
+            start_grpc_server(
+                &ctx,
+                executor.clone(),
+                &grpc_runtimes,
+                NetworkRouter::new(service, networks),
+                &grpc_stopping,
+            )?;
+        }
+        for identity in &ctx.config.identities {
+            info!(
+                socket_address = %identity.socket_address,
+                "starting extra signer identity"
+            );
+            let identity_ctx = Context::new(ctx.config.for_identity(identity)).await?;
+            if let Some(service) = start_identity(
+                &identity_ctx,
+                executor.clone(),
+                &grpc_runtimes,
+                shared.clone(),
+            )
+            .await?
+            {
+                signers.push(service.clone());
+                start_grpc_server(
+                    &identity_ctx,
+                    executor.clone(),
+                    &grpc_runtimes,
+                    NetworkRouter::new(service, HashMap::new()),
+                    &grpc_stopping,
+                )?;
+            }
+        }
+
+        // storage-only nodes have no chain state to sync
+        start_systemd_notify(
+            executor.clone(),
+            ctx.transactor.is_some().then(|| ctx.sync_progress.clone()),
+        );
+        health_probes.set_signers(signers.clone());
+        self.events.publish(NodeEvent::Started);
+        Ok(NodeHandle {
+            environment,
+            executor,
+            events: self.events,
+            grpc_runtimes,
+            grpc_stopping,
+            health_probes,
+            shutdown_grace: ctx.config.grpc_connection.shutdown_grace,
+            signers,
+        })
+    }
+}
+
+/// Services shared by the signers of every identity and network of the node.
+#[derive(Clone)]
+struct SignerShared {
+    events: EventBus,
+    /// One chain for the signers of every identity and network.
+    audit_log: Option<Arc<AuditLog>>,
+    /// New sign batches of every identity and network are rejected while under maintenance.
+    maintenance: Maintenance,
+    load_shedder: Option<LoadShedder>,
+    /// Verification slots shared by every identity and network, they verify on the same threads.
+    verification_queue: Option<VerificationQueue>,
+    /// Verify params, loaded once for the signers of every identity and network.
+    slice_verifier: SliceVerifier,
+}
+
+/// Start the services of a signer identity on its own database: storage maintenance, chain state,
+/// registration and DAS. The returned signer service is not served yet, `None` if it failed to
+/// start in DAS test mode.
+async fn start_identity(
+    ctx: &Context,
+    executor: TaskExecutor,
+    grpc_runtimes: &GrpcRuntimes,
+    shared: SignerShared,
+) -> Result<Option<Arc<SignerService>>> {
+    if let Some(cold_storage) = &ctx.config.cold_storage {
+        start_cold_storage_tiering(
+            executor.clone(),
+            ctx.db.clone(),
+            cold_storage.tier_after_epochs,
+        );
+    }
+
+    if let Some(preallocation) = &ctx.config.preallocation {
+        start_preallocation(
+            executor.clone(),
+            ctx.db.clone(),
+            ctx.config.data_path.clone(),
+            preallocation.clone(),
+            &shared.events,
+        );
+    }
+
+    if ctx.config.encryption.is_some() {
+        start_reencryption(executor.clone(), ctx.db.clone());
+    }
+
+    let sign_load = Arc::new(RwLock::new(0));
+    let das_scheduler = ctx.config.enable_das.then(|| {
+        DasScheduler::new(ctx.config.das_scheduler.clone(), Some(sign_load.clone()))
+            .with_events(shared.events.clone())
+    });
+    let admin_service = AdminService::new(
+        ctx.db.clone(),
+        das_scheduler.clone(),
+        ctx.sync_progress.clone(),
+        ctx.runtime_monitor.clone(),
+    )
+    .with_cluster(ctx.config.cluster.clone())
+    .with_verification_metrics(ctx.verification_metrics.clone())
+    .with_stake_monitor(ctx.stake_monitor.clone())
+    .with_maintenance(shared.maintenance.clone(), sign_load.clone());
+    if let Some(admin_listen_address) = &ctx.config.admin_listen_address {
+        start_admin_server(
+            executor_on(&grpc_runtimes.admin, &executor),
+            &ctx.config.supervisor,
+            SocketAddr::from_str(admin_listen_address)?,
+            admin_service.clone(),
+        );
+    }
+    if let Some(admin_http_listen_address) = &ctx.config.admin_http_listen_address {
+        start_status_page_server(
+            executor_on(&grpc_runtimes.admin, &executor),
+            &ctx.config.supervisor,
+            SocketAddr::from_str(admin_http_listen_address)?,
+            admin_service,
+        );
+    }
+
+    start_das_service(executor.clone(), ctx, das_scheduler);
+    let rpc_res = start_server(ctx, executor.clone(), shared, sign_load).await;
+
+    let service = match rpc_res {
+        Ok(service) => service,
+        Err(e) if ctx.config.das_test => {
+            warn!("signer service failed to start in DAS test mode: {:?}", e);
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    };
+    if let Some(self_test) = &ctx.config.self_test {
+        service
+            .self_test(self_test.vector.as_deref())
+            .await
+            .map_err(|e| anyhow!("self-test failed, refusing to serve: {:?}", e))?;
+    }
+    Ok(Some(service))
+}
+
+/// Handle of a started node, dropping it stops all node services.
+pub struct NodeHandle {
+    environment: Environment,
+    executor: TaskExecutor,
+    events: EventBus,
+    #[allow(unused)]
+    grpc_runtimes: GrpcRuntimes,
+    /// Turned true to stop the grpc servers gracefully, closed once they all stopped.
+    grpc_stopping: watch::Sender<bool>,
+    health_probes: HealthProbes,
+    shutdown_grace: Duration,
+    signers: Vec<Arc<SignerService>>,
+}
+
+/// Dedicated runtimes of the grpc services, services without one run on the main runtime.
+struct GrpcRuntimes {
+    signer: Option<DedicatedRuntime>,
+    retrieval: Option<DedicatedRuntime>,
+    admin: Option<DedicatedRuntime>,
+}
+
+impl GrpcRuntimes {
+    fn new(config: &GrpcRuntimesConfig) -> Result<Self> {
+        let make = |name, threads: Option<usize>| {
+            threads
+                .map(|threads| DedicatedRuntime::new(name, threads))
+                .transpose()
+        };
+        Ok(Self {
+            signer: make("grpc-signer", config.signer_threads)?,
+            retrieval: make("grpc-retrieval", config.retrieval_threads)?,
+            admin: make("grpc-admin", config.admin_threads)?,
+        })
+    }
+}
+
+/// Probe the main runtime and the dedicated grpc runtimes.
+fn start_runtime_monitor(
+    ctx: &Context,
+    executor: &TaskExecutor,
+    grpc_runtimes: &GrpcRuntimes,
+    config: &RuntimeMonitorConfig,
+) {
+    let mut runtimes = vec![("main", executor.clone())];
+    for (name, runtime) in [
+        ("grpc-signer", &grpc_runtimes.signer),
+        ("grpc-retrieval", &grpc_runtimes.retrieval),
+        ("grpc-admin", &grpc_runtimes.admin),
+    ] {
+        if let Some(runtime) = runtime {
+            runtimes.push((name, runtime.executor(executor)));
+        }
+    }
+    for (name, runtime_executor) in runtimes {
+        runtime_executor.spawn(
+            ctx.runtime_monitor.clone().probe(
+                name.to_string(),
+                config.probe_interval,
+                config.stall_threshold,
+            ),
+            "runtime_probe",
+        );
+    }
+}
+
+fn start_resource_monitor(executor: &TaskExecutor, config: &LoadSheddingConfig) -> LoadShedder {
+    let monitor = ResourceMonitor::default();
+    executor.spawn(monitor.clone().run(config.clone()), "resource_monitor");
+    LoadShedder::new(config.clone(), monitor)
+}
+
+fn executor_on(runtime: &Option<DedicatedRuntime>, executor: &TaskExecutor) -> TaskExecutor {
+    match runtime {
+        Some(runtime) => runtime.executor(executor),
+        None => executor.clone(),
+    }
+}
+
+impl NodeHandle {
+    pub fn subscribe(&self) -> broadcast::Receiver<NodeEvent> {
+        self.events.subscribe()
+    }
+
+    pub fn executor(&self) -> &TaskExecutor {
+        &self.executor
+    }
+
+    /// Wait for a termination signal or an internal failure, then stop the node once the grpc
+    /// calls running and the slice writes it acknowledged are done.
+    pub async fn wait_shutdown_signal(mut self) {
+        self.environment.wait_shutdown_signal().await;
+        self.drain_grpc_servers().await;
+        for signer in self.signers.iter() {
+            signer.flush_slice_writes().await;
+        }
+        self.stop();
+    }
+
+    /// Send a GOAWAY to the grpc clients and wait for their calls, for the shutdown grace period
+    /// at most.
+    async fn drain_grpc_servers(&self) {
+        self.health_probes.set_stopping();
+        let _ = self.grpc_stopping.send(true);
+        if timeout(self.shutdown_grace, self.grpc_stopping.closed())
+            .await
+            .is_err()
+        {
+            warn!(
+                "grpc calls still running after {:?}, dropping them",
+                self.shutdown_grace
+            );
+        }
+    }
+
+    pub fn stop(self) {
+        info!("stopping node..");
+        notify_stopping();
+        self.events.publish(NodeEvent::Stopping);
+    }
+}
+
+fn make_signer_service(
+    chain_state: Option<Arc<ChainState>>,
+    ctx: &Context,
+    executor: TaskExecutor,
+    shared: SignerShared,
+    sign_load: Arc<RwLock<u64>>,
+) -> Result<Arc<SignerService>> {
+    let sign_monitor_peers = ctx
+        .config
+        .with_cluster_peers(&ctx.config.sign_monitor_peers, ClusterRole::Signer);
+    if !sign_monitor_peers.is_empty() {
+        start_sign_monitor(executor.clone(), sign_monitor_peers, &shared.events)?;
+    }
+    let batch_proxy = match &ctx.config.batch_proxy {
+        Some(config) => {
+            let config = BatchProxyConfig {
+                backends: ctx
+                    .config
+                    .with_cluster_peers(&config.backends, ClusterRole::SignBackend),
+                ..config.clone()
+            };
+            Some(start_batch_proxy(executor.clone(), &config)?)
+        }
+        None => None,
+    };
+    let signer_config = SignerConfig {
+        encoder_params_dir: ctx.config.encoder_params_dir.clone(),
+        params_load_mode: ctx.config.params_load_mode,
+        params_versions: ctx.config.params_versions.clone(),
+        slice_verifier: shared.slice_verifier,
+        max_ongoing_sign_request: ctx.config.max_ongoing_sign_request,
+        enable_slice_repair: ctx.config.enable_slice_repair,
+        request_dump_dir: ctx.config.request_dump_dir.clone(),
+        events: shared.events,
+        sign_load,
+        put_slice_retry: ctx.config.put_slice_retry.clone(),
+        max_batch_sign_requests: ctx.config.max_batch_sign_requests,
+        batch_proxy,
+        verification_metrics: ctx.verification_metrics.clone(),
+        sign_quota: ctx.config.sign_quota.clone(),
+        store_opening_proofs: ctx.config.store_opening_proofs,
+        ack_mode: ctx.config.ack_mode,
+        admission: ctx.config.admission.clone(),
+        audit_log: shared.audit_log,
+        archive: ctx.config.mode == NodeMode::Archive,
+        maintenance: shared.maintenance,
+        load_shedder: shared.load_shedder,
+        verification_queue: shared.verification_queue,
+    };
+    Ok(Arc::new(SignerService::new(
+        ctx.db.clone(),
+        chain_state,
+        ctx.signer_keys.clone(),
+        signer_config,
+    )))
+}
+
+/// Make the batch proxy and poll the limits and the load of its backends.
+fn start_batch_proxy(executor: TaskExecutor, config: &BatchProxyConfig) -> Result<Arc<BatchProxy>> {
+    let proxy = Arc::new(BatchProxy::new(config)?);
+    let polled = proxy.clone();
+    executor.spawn(
+        async move {
+            let mut interval = interval(BATCH_PROXY_POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                polled.refresh_backends().await;
+            }
+        },
+        "batch_proxy_poller",
+    );
+    Ok(proxy)
+}
+
+/// Serve the signer services of the router on the listeners of `ctx`.
+fn start_grpc_server(
+    ctx: &Context,
+    executor: TaskExecutor,
+    runtimes: &GrpcRuntimes,
+    router: NetworkRouter,
+    stopping: &watch::Sender<bool>,
+) -> Result<()> {
+    let mut listeners = vec![GrpcListener {
+        tls: ctx.config.grpc_tls.clone(),
+        ..GrpcListener::new(SocketAddr::from_str(&ctx.config.grpc_listen_address)?)
+    }];
+    listeners.extend(ctx.config.grpc_listeners.iter().cloned());
+    GrpcListener::split_dual_stack(&mut listeners);
+    let retrieval_listen_address = match &ctx.config.grpc_runtimes.retrieval_listen_address {
+        Some(addr) => Some(SocketAddr::from_str(addr)?),
+        None => None,
+    };
+
+    for listener in listeners {
+        info!("starting grpc server at {:?}", listener.address);
+        let service = router.clone();
+        let limits = ctx.config.grpc_limits.clone();
+        let connection = ctx.config.grpc_connection.clone();
+        let signer_stopping = stopping.subscribe();
+        spawn_supervised(
+            &executor_on(&runtimes.signer, &executor),
+            &ctx.config.supervisor,
+            "grpc_server",
+            move || {
+                let service = service.clone();
+                let listener = listener.clone();
+                let limits = limits.clone();
+                let connection = connection.clone();
+                let stopping = signer_stopping.clone();
+                async move {
+                    run_server(service, &listener, &limits, &connection, stopping)
+                        .await
+                        .map_err(|e| anyhow!("grpc server error: {:?}", e))
+                }
+            },
+        );
+    }
+
+    if let Some(addr) = retrieval_listen_address {
+        info!("starting retrieval grpc server at {:?}", addr);
+        let limits = ctx.config.grpc_limits.clone();
+        let connection = ctx.config.grpc_connection.clone();
+        let stopping = stopping.subscribe();
+        spawn_supervised(
+            &executor_on(&runtimes.retrieval, &executor),
+            &ctx.config.supervisor,
+            "retrieval_grpc_server",
+            move || {
+                let router = router.clone();
+                let limits = limits.clone();
+                let connection = connection.clone();
+                let stopping = stopping.clone();
+                async move {
+                    run_retrieval_server(router, addr, &limits, &connection, stopping)
+                        .await
+                        .map_err(|e| anyhow!("retrieval grpc server error: {:?}", e))
+                }
+            },
+        );
+    }
+    Ok(())
+}
+
+fn start_admin_server(
+    executor: TaskExecutor,
+    config: &SupervisorConfig,
+    addr: SocketAddr,
+    admin_service: AdminService,
+) {
+    info!("starting admin grpc server at {:?}", addr);
+    spawn_supervised(&executor, config, "admin_grpc_server", move || {
+        let admin_service = admin_service.clone();
+        async move {
+            run_admin_server(addr, admin_service)
+                .await
+                .map_err(|e| anyhow!("admin grpc server error: {:?}", e))
+        }
+    });
+}
+
+fn start_status_page_server(
+    executor: TaskExecutor,
+    config: &SupervisorConfig,
+    addr: SocketAddr,
+    admin_service: AdminService,
+) {
+    info!("starting admin http server at {:?}", addr);
+    spawn_supervised(&executor, config, "admin_http_server", move || {
+        let admin_service = admin_service.clone();
+        async move {
+            run_status_page_server(addr, admin_service)
+                .await
+                .map_err(|e| anyhow!("admin http server error: {:?}", e))
+        }
+    });
+}
+
+fn start_health_server(
+    executor: TaskExecutor,
+    config: &SupervisorConfig,
+    addr: SocketAddr,
+    probes: HealthProbes,
+) {
+    info!("starting http health server at {:?}", addr);
+    spawn_supervised(&executor, config, "health_server", move || {
+        let probes = probes.clone();
+        async move {
+            run_health_server(addr, probes)
+                .await
+                .map_err(|e| anyhow!("http health server error: {:?}", e))
+        }
+    });
+}
+
+fn start_fork_monitor(executor: TaskExecutor, chain_state: Arc<ChainState>) {
+    let shutdown_executor = executor.clone();
+    executor.spawn(
+        async move {
+            let mut rounds = 0u64;
+            loop {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                rounds += 1;
+                // log upcoming forks hourly
+                match chain_state.check_forks(rounds % 60 == 0).await {
+                    Ok(unsupported) if unsupported.is_empty() => {}
+                    Ok(unsupported) => {
+                        error!(
+                            "required forks {:?} are active but not supported by this build, please upgrade",
+                            unsupported
+                        );
+                        let _ = shutdown_executor
+                            .shutdown_sender()
+                            .try_send(ShutdownReason::Failure("unsupported fork activated"));
+                        return;
+                    }
+                    Err(e) => {
+                        error!("poll check_forks error: {:?}", e);
+                    }
+                }
+            }
+        },
+        "fork_monitor",
+    );
+}
+
+async fn setup_chain_state(
+    ctx: &Context,
+    transactor: Arc<Mutex<Transactor>>,
+    executor: TaskExecutor,
+    events: EventBus,
+) -> Result<Arc<ChainState>> {
+    let forks = match &ctx.config.fork_schedule_path {
+        Some(path) => ForkSchedule::from_file(path)?,
+        None => ForkSchedule::default(),
+    };
+    let chain_state = Arc::new(
+        ChainState::new(
+            &ctx.config.eth_rpc_url,
+            ctx.config.da_entrance_address,
+            transactor,
+            ctx.db.clone(),
+            forks,
+            events,
+            ctx.sync_progress.clone(),
+        )
+        .await?,
+    );
+    let unsupported = chain_state.check_forks(true).await?;
+    if !unsupported.is_empty() {
+        bail!(anyhow!(
+            "required forks {:?} are active but not supported by this build, please upgrade",
+            unsupported
+        ));
+    }
+    start_fork_monitor(executor.clone(), chain_state.clone());
+    // a rotation may have registered the new key already
+    let configured_keys: Vec<_> = std::iter::once(ctx.config.signer_bls_private_key)
+        .chain(ctx.config.new_signer_bls_private_key)
+        .collect();
+    chain_state
+        .verify_registered_operator(&configured_keys)
+        .await?;
+    if ctx.config.mode == NodeMode::Archive {
+        info!("archive mode, signer and epoch registration are disabled");
+    } else {
+        chain_state
+            .check_signer_registration(
+                ctx.config.signer_bls_private_key,
+                ctx.config.socket_address.clone(),
+            )
+            .await?;
+        if let Some(new_key) = ctx.config.new_signer_bls_private_key {
+            chain_state
+                .rotate_signer_key(&ctx.signer_keys, ctx.config.signer_bls_private_key, new_key)
+                .await?;
+        }
+        let (registered, signer_keys) = (chain_state.clone(), ctx.signer_keys.clone());
+        let register_after_sync = ctx.config.register_after_sync;
+        spawn_supervised(
+            &executor,
+            &ctx.config.supervisor,
+            "epoch_registration",
+            move || {
+                run_epoch_registration(registered.clone(), signer_keys.clone(), register_after_sync)
+            },
+        );
+    }
+    let (staked, stake_monitor) = (chain_state.clone(), ctx.stake_monitor.clone());
+    let stake_config = ctx.config.stake_monitor.clone();
+    spawn_supervised(
+        &executor,
+        &ctx.config.supervisor,
+        "stake_monitor",
+        move || run_stake_monitor(staked.clone(), stake_monitor.clone(), stake_config.clone()),
+    );
+    if ctx.config.expiry_gc {
+        let collected = chain_state.clone();
+        spawn_supervised(&executor, &ctx.config.supervisor, "expiry_gc", move || {
+            run_expiry_gc(collected.clone())
+        });
+    }
+    start_peer_discovery(executor.clone(), chain_state.clone());
+    let (monitored, start_block_number) = (chain_state.clone(), ctx.config.start_block_number);
+    let log_sync = ctx.config.log_sync.clone();
+    spawn_supervised(&executor, &ctx.config.supervisor, "da_monitor", move || {
+        run_da_monitor(monitored.clone(), start_block_number, log_sync.clone())
+    });
+    Ok(chain_state)
+}
+
+async fn start_server(
+    ctx: &Context,
+    executor: TaskExecutor,
+    shared: SignerShared,
+    sign_load: Arc<RwLock<u64>>,
+) -> Result<Arc<SignerService>> {
+    let transactor = match &ctx.transactor {
+        Some(transactor) => transactor.clone(),
+        None => {
+            warn!("storage-only mode, signing and registration are disabled");
+            if let Some(slices_per_second) = ctx.config.scrub_slices_per_second {
+                start_slice_scrubber(
+                    executor.clone(),
+                    ctx.db.clone(),
+                    slices_per_second,
+                    None,
+                    shared.slice_verifier.clone(),
+                );
+            }
+            if let Some(reconcile) = &ctx.config.reconcile {
+                start_reconciliation(
+                    executor.clone(),
+                    None,
+                    ctx.db.clone(),
+                    reconcile.clone(),
+                    None,
+                    shared.slice_verifier.clone(),
+                );
+            }
+            return make_signer_service(None, ctx, executor, shared, sign_load);
+        }
+    };
+    let chain_state =
+        setup_chain_state(ctx, transactor, executor.clone(), shared.events.clone()).await?;
+    if let Some(slices_per_second) = ctx.config.scrub_slices_per_second {
+        start_slice_scrubber(
+            executor.clone(),
+            ctx.db.clone(),
+            slices_per_second,
+            Some(chain_state.clone()),
+            shared.slice_verifier.clone(),
+        );
+    }
+    // cluster members storing the slices of this signer are resync sources too
+    let resync = ctx.config.resync.clone().map(|mut resync| {
+        for role in [ClusterRole::Signer, ClusterRole::Retrieval] {
+            resync.peers = ctx.config.with_cluster_peers(&resync.peers, role);
+        }
+        resync
+    });
+    if let Some(resync) = &resync {
+        start_resync(
+            executor.clone(),
+            chain_state.clone(),
+            ctx.db.clone(),
+            resync.clone(),
+            shared.slice_verifier.clone(),
+        );
+    }
+    if let Some(reconcile) = &ctx.config.reconcile {
+        start_reconciliation(
+            executor.clone(),
+            Some(chain_state.clone()),
+            ctx.db.clone(),
+            reconcile.clone(),
+            resync,
+            shared.slice_verifier.clone(),
+        );
+    }
+    // peers are authenticated by attestations signed with the signer key
+    if let Some(p2p) = ctx
+        .config
+        .p2p
+        .as_ref()
+        .filter(|_| ctx.config.mode != NodeMode::Archive)
+    {
+        let backfill = ctx.config.backfill.as_ref().map(|backfill| {
+            start_backfill_verifier(
+                executor.clone(),
+                chain_state.clone(),
+                ctx.db.clone(),
+                backfill.clone(),
+                sign_load.clone(),
+                shared.slice_verifier.clone(),
+            )
+        });
+        start_p2p(
+            executor.clone(),
+            chain_state.clone(),
+            ctx.db.clone(),
+            p2p.clone(),
+            &shared.events,
+            backfill,
+            ctx.signer_keys.clone(),
+            shared.slice_verifier.clone(),
+        )?;
+    }
+    make_signer_service(Some(chain_state), ctx, executor, shared, sign_load)
+}
+
+fn start_das_service(executor: TaskExecutor, ctx: &Context, das_scheduler: Option<DasScheduler>) {
+    let das_scheduler = match das_scheduler {
+        Some(das_scheduler) => das_scheduler,
+        None => return,
+    };
+    if ctx.transactor.is_none() {
+        warn!("storage-only mode, DA sampling is disabled");
+        return;
+    }
+    if ctx.config.mode == NodeMode::Archive {
+        info!("archive mode, DA sampling is disabled");
+        return;
+    }
+    let das_executor = executor.clone();
+    let config = ctx.config.clone();
+    let db = ctx.db.clone();
+    let signer_nonce_manager = ctx.nonce_manager.clone();
+    spawn_supervised(
+        &executor,
+        &ctx.config.supervisor,
+        "das_service",
+        move || {
+            let (executor, config, db) = (das_executor.clone(), config.clone(), db.clone());
+            let (das_scheduler, signer_nonce_manager) =
+                (das_scheduler.clone(), signer_nonce_manager.clone());
+            async move {
+                let provider = make_provider(&config.eth_rpc_url, &config.miner_eth_key)
+                    .await
+                    .map_err(|e| anyhow!("cannot make the miner provider: {:?}", e))?;
+                // share the nonces if the miner is also the signer account
+                let nonce_manager = match signer_nonce_manager {
+                    Some(nonce_manager) if nonce_manager.address() == provider.address() => {
+                        nonce_manager
+                    }
+                    _ => NonceManager::new(provider.clone(), GasStrategy::new(config.gas.clone())),
+                };
+                let service = DasMineService::spawn(
+                    executor,
+                    provider,
+                    config.da_entrance_address,
+                    config.das_test,
+                    db,
+                    das_scheduler,
+                    nonce_manager,
+                )
+                .await
+                .map_err(|e| anyhow!("cannot start the DA sampling mine service: {}", e))?;
+                info!("DA sampling mine service started");
+                // supervised until a task of the service exits, the others are stopped with it
+                let task = service.exited().await;
+                Err(anyhow!("DA sampling task {} exited", task))
+            }
+        },
+    );
+}
@@ -0,0 +1,314 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::G1Affine;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use chain_state::{signer_keys::SignerKeys, ChainState};
+use ethers::types::H160;
+use events::{EventBus, NodeEvent};
+use futures::StreamExt;
+use grpc::{
+    signer::{GossipSlices, PeerAttestation},
+    SliceVerifier,
+};
+use libp2p::{
+    gossipsub::{self, IdentTopic, MessageAuthenticity},
+    noise,
+    swarm::SwarmEvent,
+    tcp, yamux, Multiaddr, PeerId, Swarm,
+};
+use prost::Message;
+use storage::{
+    quorum_db::{AssignedSlices, QuorumDB},
+    Storage,
+};
+use task_executor::TaskExecutor;
+use tokio::{sync::broadcast::error::RecvError, time::interval};
+
+use crate::{
+    archive::stored_slice,
+    backfill::{fill_slices, BackfillJob, BackfillQueue},
+    config::P2pConfig,
+};
+
+const SUBSCRIBE_INTERVAL: Duration = Duration::from_secs(60);
+/// Rows per gossip message, a row is 32 KB of data sent along with its encoded slice.
+const GOSSIP_BATCH_SIZE: usize = 16;
+const MAX_TRANSMIT_SIZE: usize = 4 * 1024 * 1024;
+
+fn quorum_topic(quorum_id: u64) -> IdentTopic {
+    IdentTopic::new(format!("/0g-da/slices/{}", quorum_id))
+}
+
+fn auth_topic() -> IdentTopic {
+    IdentTopic::new("/0g-da/auth")
+}
+
+/// Join the gossip network of the quorums the node is assigned to. Slices signed by the node are
+/// announced to its quorums, and verified slices received from peers fill the missing assigned rows.
+///
+/// Connections are encrypted with Noise. Nodes attest their peer id with their registered signer key
+/// and slices are only accepted from authenticated peers, so only registered operators exchange
+/// slices.
+pub fn start_p2p(
+    executor: TaskExecutor,
+    chain_state: Arc<ChainState>,
+    db: Arc<Storage>,
+    config: P2pConfig,
+    events: &EventBus,
+    backfill: Option<BackfillQueue>,
+    signer_keys: SignerKeys,
+    verifier: SliceVerifier,
+) -> Result<()> {
+    let mut swarm = build_swarm()?;
+    swarm.listen_on(config.listen_address.parse()?)?;
+    swarm
+        .behaviour_mut()
+        .subscribe(&auth_topic())
+        .map_err(|e| anyhow!("{:?}", e))?;
+    for bootnode in config.bootnodes.iter() {
+        let addr: Multiaddr = bootnode.parse()?;
+        if let Err(e) = swarm.dial(addr) {
+            warn!("cannot dial p2p bootnode {}: {:?}", bootnode, e);
+        }
+    }
+    info!("p2p node started, peer id: {}", swarm.local_peer_id());
+
+    let mut receiver = events.subscribe();
+    executor.spawn(
+        async move {
+            let mut subscribe_interval = interval(SUBSCRIBE_INTERVAL);
+            // signers of the authenticated peers
+            let mut authenticated: HashMap<PeerId, H160> = HashMap::new();
+            loop {
+                tokio::select! {
+                    _ = subscribe_interval.tick() => {
+                        if let Err(e) = subscribe_quorums(&mut swarm, &db).await {
+                            error!("p2p subscribe error: {:?}", e);
+                        }
+                        announce_attestation(&mut swarm, &chain_state, &signer_keys).await;
+                    }
+                    event = receiver.recv() => match event {
+                        Ok(NodeEvent::BlobSigned { epoch, quorum_id, storage_root }) => {
+                            if let Err(e) = announce_slices(&mut swarm, &db, epoch, quorum_id, storage_root).await {
+                                warn!("cannot announce slices: {:?}", e);
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("p2p lagged, {} events skipped", skipped);
+                        }
+                        Err(RecvError::Closed) => return,
+                    },
+                    event = swarm.select_next_some() => match event {
+                        SwarmEvent::Behaviour(gossipsub::Event::Message { propagation_source, message, .. }) => {
+                            if message.topic == auth_topic().hash() {
+                                if let Err(e) = on_peer_attestation(&chain_state, &mut authenticated, message.source, &message.data).await {
+                                    warn!("invalid attestation from peer {:?}: {:?}", message.source, e);
+                                }
+                            } else if !message.source.map_or(false, |source| authenticated.contains_key(&source)) {
+                                debug!("slices from unauthenticated peer {:?} ignored", message.source);
+                            } else if let Err(e) = on_gossip_slices(&chain_state, &db, &verifier, &backfill, format!("peer {}", propagation_source), &message.data).await {
+                                warn!("invalid gossip slices from peer {}: {:?}", propagation_source, e);
+                            }
+                        }
+                        SwarmEvent::Behaviour(gossipsub::Event::Subscribed { topic, .. }) if topic == auth_topic().hash() => {
+                            // authenticate to the new peer right away
+                            announce_attestation(&mut swarm, &chain_state, &signer_keys).await;
+                        }
+                        SwarmEvent::NewListenAddr { address, .. } => {
+                            info!("p2p listening on {}", address);
+                        }
+                        SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                            debug!("p2p peer connected: {}", peer_id);
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        },
+        "p2p",
+    );
+    Ok(())
+}
+
+fn build_swarm() -> Result<Swarm<gossipsub::Behaviour>> {
+    let gossipsub_config = gossipsub::ConfigBuilder::default()
+        .max_transmit_size(MAX_TRANSMIT_SIZE)
+        .build()
+        .map_err(|e| anyhow!("invalid gossipsub config: {:?}", e))?;
+    Ok(libp2p::SwarmBuilder::with_new_identity()
+        .with_tokio()
+        .with_tcp(
+            tcp::Config::default(),
+            noise::Config::new,
+            yamux::Config::default,
+        )?
+        .with_behaviour(|key| {
+            Ok(gossipsub::Behaviour::new(
+                MessageAuthenticity::Signed(key.clone()),
+                gossipsub_config,
+            )?)
+        })
+        .map_err(|e| anyhow!("cannot create gossipsub: {:?}", e))?
+        .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(60)))
+        .build())
+}
+
+/// Publish the attestation of the local peer id by the signer key.
+async fn announce_attestation(
+    swarm: &mut Swarm<gossipsub::Behaviour>,
+    chain_state: &ChainState,
+    signer_keys: &SignerKeys,
+) {
+    let peer_id = swarm.local_peer_id().to_bytes();
+    let signature = match chain_state
+        .attest_peer_id(signer_keys.latest().await, &peer_id)
+        .await
+    {
+        Ok(signature) => signature,
+        Err(e) => {
+            warn!("cannot attest p2p peer id: {:?}", e);
+            return;
+        }
+    };
+    let mut value = Vec::new();
+    if let Err(e) = signature.serialize_uncompressed(&mut value) {
+        warn!("cannot serialize peer attestation: {:?}", e);
+        return;
+    }
+    let message = PeerAttestation {
+        signer: chain_state.signer_address().as_bytes().to_vec(),
+        peer_id,
+        signature: value,
+    };
+    if let Err(e) = swarm
+        .behaviour_mut()
+        .publish(auth_topic(), message.encode_to_vec())
+    {
+        // no peer subscribed yet
+        debug!("cannot publish peer attestation: {:?}", e);
+    }
+}
+
+/// Authenticate the author of an attestation if it is signed by the registered key of the signer.
+async fn on_peer_attestation(
+    chain_state: &ChainState,
+    authenticated: &mut HashMap<PeerId, H160>,
+    source: Option<PeerId>,
+    data: &[u8],
+) -> Result<()> {
+    let attestation = PeerAttestation::decode(data)?;
+    let source = source.ok_or_else(|| anyhow!("attestation without author"))?;
+    if attestation.peer_id != source.to_bytes() {
+        bail!(anyhow!("attestation of another peer"));
+    }
+    if attestation.signer.len() != 20 {
+        bail!(anyhow!("invalid signer address"));
+    }
+    let signer = H160::from_slice(&attestation.signer);
+    if authenticated.get(&source) == Some(&signer) {
+        return Ok(());
+    }
+    let signature = G1Affine::deserialize_uncompressed(&*attestation.signature)?;
+    if !chain_state
+        .verify_peer_attestation(signer, &attestation.peer_id, signature)
+        .await?
+    {
+        bail!(anyhow!("not signed by the registered key of {:?}", signer));
+    }
+    info!("p2p peer {} authenticated as signer {:?}", source, signer);
+    authenticated.insert(source, signer);
+    Ok(())
+}
+
+async fn subscribe_quorums(swarm: &mut Swarm<gossipsub::Behaviour>, db: &Storage) -> Result<()> {
+    let epoch = match db.get_latest_epoch().await? {
+        Some(epoch) => epoch,
+        None => return Ok(()),
+    };
+    let quorum_num = db.get_quorum_num(epoch).await?.unwrap_or(0);
+    for quorum_id in 0..quorum_num {
+        if let Some(AssignedSlices(assigned_slices)) =
+            db.get_assgined_slices(epoch, quorum_id).await?
+        {
+            if !assigned_slices.is_empty() {
+                // subscribing a topic again is a no-op
+                swarm
+                    .behaviour_mut()
+                    .subscribe(&quorum_topic(quorum_id))
+                    .map_err(|e| anyhow!("{:?}", e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn announce_slices(
+    swarm: &mut Swarm<gossipsub::Behaviour>,
+    db: &Storage,
+    epoch: u64,
+    quorum_id: u64,
+    storage_root: [u8; 32],
+) -> Result<()> {
+    let mut slices = vec![];
+    {
+        let assigned_slices = match db.get_assgined_slices(epoch, quorum_id).await? {
+            Some(AssignedSlices(assigned_slices)) => assigned_slices,
+            None => return Ok(()),
+        };
+        for index in assigned_slices {
+            if let Some(slice) =
+                stored_slice(db, epoch, quorum_id, storage_root, index as usize, true).await?
+            {
+                slices.push(slice);
+            }
+        }
+    }
+    let topic = quorum_topic(quorum_id);
+    for batch in slices.chunks(GOSSIP_BATCH_SIZE) {
+        let message = GossipSlices {
+            epoch,
+            quorum_id,
+            storage_root: storage_root.to_vec(),
+            slices: batch.to_vec(),
+        };
+        if let Err(e) = swarm
+            .behaviour_mut()
+            .publish(topic.clone(), message.encode_to_vec())
+        {
+            // e.g. no peer subscribed to the quorum yet, or a message over the transmit size
+            warn!("cannot publish slices: {:?}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Fill missing assigned rows with the gossiped slices, through the backfill queue if enabled.
+async fn on_gossip_slices(
+    chain_state: &ChainState,
+    db: &Storage,
+    verifier: &SliceVerifier,
+    backfill: &Option<BackfillQueue>,
+    source: String,
+    data: &[u8],
+) -> Result<()> {
+    let message = GossipSlices::decode(data)?;
+    let job = BackfillJob {
+        epoch: message.epoch,
+        quorum_id: message.quorum_id,
+        storage_root: message
+            .storage_root
+            .try_into()
+            .map_err(|_| anyhow!("invalid storage root"))?,
+        slices: message.slices,
+        source,
+    };
+    match backfill {
+        Some(backfill) => backfill.push(job),
+        None => {
+            fill_slices(chain_state, db, verifier, &job).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Write},
+    path::Path,
+};
+
+use anyhow::{anyhow, bail, Result};
+use sha2::{Digest, Sha256};
+
+use crate::config::ParamsDownloadConfig;
+
+/// SHA256 of the published param files, see `dev_support/download_params.sh`.
+pub const KNOWN_PARAMS: [(&str, &str); 6] = [
+    (
+        "amt-verify-coset0-5DWgDV-10-20.bin",
+        "18bb6b7ba10785a79810180ddd27a6d467d2c0e24e6335e5bc95998e02c6a4f6",
+    ),
+    (
+        "amt-verify-coset1-5DWgDV-10-20.bin",
+        "19b024fed13e0ba60b17184c998dcccf12119b4fd0ab7c46394b8e024e99c48a",
+    ),
+    (
+        "amt-verify-coset2-5DWgDV-10-20.bin",
+        "5660a89402df7d47885b304b566d92e1c42349744e202d45fc61a0893bd796c9",
+    ),
+    (
+        "amt-prove-coset0-mont-5DWgDV-10-20.bin",
+        "6c1d7837e5380ca7e09e1f396b4f8ff3ec546cabcccc7bc65f6439a75a791a80",
+    ),
+    (
+        "amt-prove-coset1-mont-5DWgDV-10-20.bin",
+        "a9f4f6b07a0d66620d652227233c42d12d2726c00f802eadd0e46db68917885a",
+    ),
+    (
+        "amt-prove-coset2-mont-5DWgDV-10-20.bin",
+        "0314657436c124f2b00c7bb4e239dc551ab4f0f732516ad9dd656ec12b091c17",
+    ),
+];
+
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 1 << 20];
+    loop {
+        let n = file.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Download the published param files missing or corrupt in `params_dir` from the first mirror
+/// serving them, a file is only moved in place once its checksum is verified.
+pub async fn download_params(config: &ParamsDownloadConfig, params_dir: &str) -> Result<()> {
+    let dir = Path::new(params_dir);
+    fs::create_dir_all(dir)?;
+    let client = reqwest::Client::new();
+    for (name, checksum) in KNOWN_PARAMS {
+        if name.starts_with("amt-prove") && !config.prove {
+            continue;
+        }
+        let path = dir.join(name);
+        if path.exists() {
+            if file_sha256(&path)? == checksum {
+                continue;
+            }
+            warn!(
+                "param file {} has a wrong checksum, downloading it again",
+                name
+            );
+        }
+        let mut downloaded = false;
+        for url in config.urls.iter() {
+            let url = format!("{}/{}", url.trim_end_matches('/'), name);
+            info!("downloading param file {}", url);
+            match download_file(&client, &url, &path, checksum).await {
+                Ok(()) => {
+                    downloaded = true;
+                    break;
+                }
+                Err(e) => warn!("cannot download param file {}: {:?}", url, e),
+            }
+        }
+        if !downloaded {
+            bail!(anyhow!("param file {} cannot be downloaded", name));
+        }
+    }
+    Ok(())
+}
+
+async fn download_file(
+    client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    checksum: &str,
+) -> Result<()> {
+    let mut response = client.get(url).send().await?.error_for_status()?;
+    let partial = path.with_extension("part");
+    let mut file = File::create(&partial)?;
+    let mut hasher = Sha256::new();
+    while let Some(chunk) = response.chunk().await? {
+        hasher.update(&chunk);
+        file.write_all(&chunk)?;
+    }
+    file.sync_all()?;
+    let actual = hex::encode(hasher.finalize());
+    if actual != checksum {
+        fs::remove_file(&partial)?;
+        bail!(anyhow!(
+            "checksum mismatch, expected {} but got {}",
+            checksum,
+            actual
+        ));
+    }
+    fs::rename(&partial, path)?;
+    Ok(())
+}
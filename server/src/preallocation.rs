@@ -0,0 +1,242 @@
+use std::{
+    fs::OpenOptions,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Result;
+use events::{EventBus, NodeEvent};
+use fs2::FileExt;
+use storage::{blob_status_db::BlobStatusDB, quorum_db::QuorumDB, slice_db::SliceDB, Storage};
+use task_executor::TaskExecutor;
+use tokio::{sync::broadcast::error::RecvError, time::interval};
+use zg_encoder::constants::{BLOB_COL_N, BLOB_UNIT};
+
+use crate::config::PreallocationConfig;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// Light slice and key overhead of a stored slice.
+const SLICE_OVERHEAD: u64 = 1024;
+const SLICE_SIZE: u64 = (BLOB_COL_N * BLOB_UNIT) as u64 + SLICE_OVERHEAD;
+/// Free space left out of the reservation for database writes and compactions.
+const MIN_FREE_SPACE: u64 = 1024 * 1024 * 1024;
+
+/// Reserve the projected disk usage of the current epoch in a preallocated file, shrunk as slices of
+/// the epoch are stored, so the space cannot be taken by others before the node needs it. An alert
+/// is raised early when the projected usage does not fit in the disk. The reservation is released
+/// as soon as a slice write fails, and shrunk once stored slices leave less than the minimum free
+/// space, without waiting for the next check.
+pub fn start_preallocation(
+    executor: TaskExecutor,
+    db: Arc<Storage>,
+    data_path: String,
+    config: PreallocationConfig,
+    events: &EventBus,
+) {
+    let mut receiver = events.subscribe();
+    executor.spawn(
+        async move {
+            let reserve_path = reserve_path(&data_path, &config);
+            let mut check_interval = interval(CHECK_INTERVAL);
+            let mut warned_epoch = None;
+            loop {
+                let write_failed = tokio::select! {
+                    _ = check_interval.tick() => {
+                        match update_reservation(&db, &data_path, &reserve_path, &config).await {
+                            Ok(Some((epoch, missing))) if warned_epoch != Some(epoch) => {
+                                error!(
+                                    target: "alert",
+                                    epoch,
+                                    "projected storage usage of the epoch exceeds free space by {} bytes",
+                                    missing
+                                );
+                                warned_epoch = Some(epoch);
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                error!("storage preallocation error: {:?}", e);
+                            }
+                        }
+                        continue;
+                    }
+                    event = receiver.recv() => match event {
+                        Ok(NodeEvent::SlicesStored { .. }) => false,
+                        Ok(NodeEvent::SliceWriteFailed { .. }) => true,
+                        Ok(_) => continue,
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!("storage preallocation lagged, {} events skipped", skipped);
+                            continue;
+                        }
+                        Err(RecvError::Closed) => return,
+                    },
+                };
+                let result = fs2::available_space(&data_path).and_then(|free| {
+                    shrink_reservation(&reserve_path, free, write_failed)
+                });
+                match result {
+                    Ok(Some(reserved)) => {
+                        warn!(
+                            write_failed,
+                            reserved, "storage reservation shrunk to free space for writes"
+                        );
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        error!("storage preallocation error: {:?}", e);
+                    }
+                }
+            }
+        },
+        "storage_preallocation",
+    );
+}
+
+fn reserve_path(data_path: &str, config: &PreallocationConfig) -> PathBuf {
+    match &config.reserve_file {
+        Some(reserve_file) => PathBuf::from(reserve_file),
+        None => Path::new(data_path).join("reserved_space"),
+    }
+}
+
+/// Size of the reservation file with `free` bytes available besides it, so that the minimum free
+/// space is left, the whole reservation is released after a failed write.
+fn shrunk_reservation(reserved: u64, free: u64, write_failed: bool) -> u64 {
+    if write_failed {
+        return 0;
+    }
+    reserved.saturating_sub(MIN_FREE_SPACE.saturating_sub(free))
+}
+
+/// Shrink the reservation file if writes may lack space, returns its new size if it is shrunk.
+fn shrink_reservation(
+    reserve_path: &Path,
+    free: u64,
+    write_failed: bool,
+) -> std::io::Result<Option<u64>> {
+    let file = match OpenOptions::new().write(true).open(reserve_path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let reserved = file.metadata()?.len();
+    let target = shrunk_reservation(reserved, free, write_failed);
+    if target == reserved {
+        return Ok(None);
+    }
+    file.set_len(target)?;
+    Ok(Some(target))
+}
+
+/// Projected bytes of slices of `epoch` still to be stored.
+async fn projected_remaining(
+    db: &Storage,
+    epoch: u64,
+    config: &PreallocationConfig,
+) -> Result<u64> {
+    let quorum_num = match db.get_quorum_num(epoch).await? {
+        Some(quorum_num) if quorum_num > 0 => quorum_num,
+        _ => return Ok(0),
+    };
+    let mut assigned = 0u64;
+    for quorum_id in 0..quorum_num {
+        if let Some(slices) = db.get_assgined_slices(epoch, quorum_id).await? {
+            assigned += slices.0.len() as u64;
+        }
+    }
+    // expect as many blobs as the previous epoch if not configured
+    let expected_blobs = match config.expected_blobs_per_epoch {
+        Some(blobs) => blobs,
+        None => db
+            .get_epoch_blobs(epoch.saturating_sub(1))
+            .await?
+            .len()
+            .max(1) as u64,
+    };
+    let projected_slices = expected_blobs * assigned / quorum_num;
+    let stored_slices: u64 = db
+        .get_epoch_info(epoch)
+        .await?
+        .iter()
+        .map(|blob| blob.indicies.len() as u64)
+        .sum();
+    Ok(projected_slices.saturating_sub(stored_slices) * SLICE_SIZE)
+}
+
+/// Resize the reservation file to the projected usage, returns the epoch and the missing bytes if
+/// the projected usage does not fit.
+async fn update_reservation(
+    db: &Storage,
+    data_path: &str,
+    reserve_path: &Path,
+    config: &PreallocationConfig,
+) -> Result<Option<(u64, u64)>> {
+    let (epoch, remaining) = {
+        let epoch = match db.get_latest_epoch().await? {
+            Some(epoch) => epoch,
+            None => return Ok(None),
+        };
+        (epoch, projected_remaining(db, epoch, config).await?)
+    };
+
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(reserve_path)?;
+    let reserved = file.metadata()?.len();
+    let available = fs2::available_space(data_path)? + reserved;
+    let target = remaining.min(available.saturating_sub(MIN_FREE_SPACE));
+    if target < reserved {
+        file.set_len(target)?;
+    } else if target > reserved {
+        file.allocate(target)?;
+    }
+    debug!(
+        epoch,
+        remaining,
+        reserved = target,
+        available,
+        "storage reservation updated"
+    );
+    if remaining + MIN_FREE_SPACE > available {
+        return Ok(Some((epoch, remaining + MIN_FREE_SPACE - available)));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shrunk_reservation_test() {
+        assert_eq!(shrunk_reservation(4096, MIN_FREE_SPACE, false), 4096);
+        assert_eq!(shrunk_reservation(4096, MIN_FREE_SPACE - 1024, false), 3072);
+        assert_eq!(shrunk_reservation(4096, 0, false), 0);
+        assert_eq!(shrunk_reservation(4096, MIN_FREE_SPACE, true), 0);
+    }
+
+    #[test]
+    fn shrink_reservation_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("reservation");
+        assert_eq!(shrink_reservation(&path, 0, true).unwrap(), None);
+
+        std::fs::File::create(&path).unwrap().set_len(4096).unwrap();
+        assert_eq!(
+            shrink_reservation(&path, MIN_FREE_SPACE, false).unwrap(),
+            None
+        );
+        assert_eq!(
+            shrink_reservation(&path, MIN_FREE_SPACE - 1024, false).unwrap(),
+            Some(3072)
+        );
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 3072);
+        assert_eq!(
+            shrink_reservation(&path, MIN_FREE_SPACE, true).unwrap(),
+            Some(0)
+        );
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+    }
+}
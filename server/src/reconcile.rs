@@ -0,0 +1,217 @@
+use std::{
+    collections::BTreeSet,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use chain_state::ChainState;
+use grpc::SliceVerifier;
+use storage::{
+    blob_status_db::{BlobStatus, BlobStatusDB},
+    quorum_db::{AssignedSlices, QuorumDB},
+    reconcile_db::{
+        Inconsistency, InconsistencyKind, ReconcileAction, ReconcileDB, ReconcileReport,
+    },
+    slice_db::SliceDB,
+    Storage,
+};
+use task_executor::TaskExecutor;
+use tokio::time::sleep;
+
+use crate::{
+    config::{ReconcileConfig, ResyncConfig},
+    resync::resync_blob,
+};
+
+const RECONCILE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Periodically check that blob statuses and stored slices of recent epochs agree, which a crash
+/// between the two writes or a partial pruning can break. Verified blobs without any stored slice are
+/// re-fetched from peers if resync is enabled, slices without a blob status get the status restored
+/// from chain. What cannot be fixed is flagged in the report kept for the admin API.
+pub fn start_reconciliation(
+    executor: TaskExecutor,
+    chain_state: Option<Arc<ChainState>>,
+    db: Arc<Storage>,
+    config: ReconcileConfig,
+    resync: Option<ResyncConfig>,
+    verifier: SliceVerifier,
+) {
+    executor.spawn(
+        async move {
+            loop {
+                match reconcile_recent_epochs(
+                    chain_state.as_deref(),
+                    &db,
+                    &config,
+                    &resync,
+                    &verifier,
+                )
+                .await
+                {
+                    Ok(Some(report)) => {
+                        let flagged = report
+                            .inconsistencies
+                            .iter()
+                            .filter(|x| x.action == ReconcileAction::Flagged)
+                            .count();
+                        if flagged > 0 {
+                            error!(
+                                target: "alert",
+                                start_epoch = report.start_epoch,
+                                end_epoch = report.end_epoch,
+                                "{} inconsistent blobs cannot be reconciled",
+                                flagged
+                            );
+                        }
+                        if let Err(e) = db.put_reconcile_report(&report).await {
+                            error!("store reconcile report error: {:?}", e);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        error!("blob reconciliation error: {:?}", e);
+                    }
+                }
+                sleep(RECONCILE_INTERVAL).await;
+            }
+        },
+        "blob_reconciliation",
+    );
+}
+
+async fn reconcile_recent_epochs(
+    chain_state: Option<&ChainState>,
+    db: &Storage,
+    config: &ReconcileConfig,
+    resync: &Option<ResyncConfig>,
+    verifier: &SliceVerifier,
+) -> Result<Option<ReconcileReport>> {
+    let latest_epoch = match db.get_latest_epoch().await? {
+        Some(epoch) => epoch,
+        None => return Ok(None),
+    };
+    let mut report = ReconcileReport {
+        start_epoch: latest_epoch.saturating_sub(config.epochs.saturating_sub(1)),
+        end_epoch: latest_epoch,
+        ..Default::default()
+    };
+    for epoch in report.start_epoch..=report.end_epoch {
+        reconcile_epoch(chain_state, db, resync, verifier, epoch, &mut report).await?;
+    }
+    report.finished_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    info!(
+        start_epoch = report.start_epoch,
+        end_epoch = report.end_epoch,
+        checked_blobs = report.checked_blobs,
+        inconsistencies = report.inconsistencies.len(),
+        "blob reconciliation finished"
+    );
+    Ok(Some(report))
+}
+
+async fn reconcile_epoch(
+    chain_state: Option<&ChainState>,
+    db: &Storage,
+    resync: &Option<ResyncConfig>,
+    verifier: &SliceVerifier,
+    epoch: u64,
+    report: &mut ReconcileReport,
+) -> Result<()> {
+    let (statuses, mut stored) = {
+        let statuses = db.get_epoch_blobs(epoch).await?;
+        let stored: BTreeSet<(u64, [u8; 32])> = db
+            .get_epoch_info(epoch)
+            .await?
+            .into_iter()
+            .map(|blob| (blob.quorum_id, blob.storage_root))
+            .collect();
+        (statuses, stored)
+    };
+    if let Some(chain_state) = chain_state {
+        chain_state.fetch_quorum_if_missing(epoch).await?;
+    }
+
+    for (quorum_id, storage_root, status) in statuses {
+        report.checked_blobs += 1;
+        if stored.remove(&(quorum_id, storage_root)) || !matches!(status, BlobStatus::VERIFIED) {
+            continue;
+        }
+        let assigned = match db.get_assgined_slices(epoch, quorum_id).await? {
+            Some(AssignedSlices(assigned)) if !assigned.is_empty() => assigned,
+            _ => continue,
+        };
+        warn!(
+            "verified blob without stored slices: epoch = {:?}, quorum = {:?}, storage_root = {:?}",
+            epoch,
+            quorum_id,
+            hex::encode(storage_root)
+        );
+        let remaining = match (chain_state, resync) {
+            (Some(chain_state), Some(resync)) => {
+                resync_blob(
+                    chain_state,
+                    db,
+                    resync,
+                    verifier,
+                    epoch,
+                    quorum_id,
+                    storage_root,
+                    assigned,
+                )
+                .await?
+            }
+            _ => assigned,
+        };
+        report.inconsistencies.push(Inconsistency {
+            epoch,
+            quorum_id,
+            storage_root,
+            kind: InconsistencyKind::VerifiedWithoutSlices,
+            action: if remaining.is_empty() {
+                ReconcileAction::Refetched
+            } else {
+                ReconcileAction::Flagged
+            },
+            missing_slices: remaining.len() as u64,
+        });
+    }
+
+    // the remaining stored blobs have no status record
+    for (quorum_id, storage_root) in stored {
+        report.checked_blobs += 1;
+        warn!(
+            "stored slices without blob status: epoch = {:?}, quorum = {:?}, storage_root = {:?}",
+            epoch,
+            quorum_id,
+            hex::encode(storage_root)
+        );
+        let action = match chain_state {
+            Some(chain_state) => {
+                let (status, action) = if chain_state
+                    .commitment_exists(epoch, quorum_id, storage_root)
+                    .await?
+                {
+                    (BlobStatus::VERIFIED, ReconcileAction::RestoredVerified)
+                } else {
+                    (BlobStatus::UPLOADED, ReconcileAction::RestoredUploaded)
+                };
+                db.put_blob(epoch, quorum_id, storage_root, status).await?;
+                action
+            }
+            None => ReconcileAction::Flagged,
+        };
+        report.inconsistencies.push(Inconsistency {
+            epoch,
+            quorum_id,
+            storage_root,
+            kind: InconsistencyKind::SlicesWithoutStatus,
+            action,
+            missing_slices: 0,
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,381 @@
+use std::{collections::HashSet, sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::G1Projective;
+use ark_ec::AffineRepr;
+use ark_serialize::{CanonicalDeserialize, Compress, Validate};
+use chain_state::ChainState;
+use da_miner::verify_line_proof;
+use grpc::{
+    signer::{signer_client::SignerClient, RetrieveRequest, StoredSlice},
+    SliceVerifier,
+};
+use storage::{
+    blob_status_db::{BlobStatus, BlobStatusDB},
+    quorum_db::{AssignedSlices, QuorumDB},
+    scrub_db::ScrubDB,
+    slice_db::{SliceDB, SliceIndex},
+    Storage,
+};
+use task_executor::TaskExecutor;
+use tokio::time::sleep;
+use zg_encoder::{EncodedSlice, LightEncodedSlice};
+
+use crate::config::ResyncConfig;
+
+const RESYNC_INTERVAL: Duration = Duration::from_secs(600);
+const FETCH_BATCH_SIZE: usize = 64;
+
+/// Recover missing or corrupt assigned slices of verified blobs from peers. Peers are the other
+/// signers of the quorum in the on-chain registry and the configured ones. A row is normally only
+/// held by its assigned signer, so the configured peers should be replicas of this node.
+///
+/// Recovered slices are verified against the erasure commitment of their blob, so peers must keep
+/// the opening proofs of their slices (`store_opening_proofs`).
+pub fn start_resync(
+    executor: TaskExecutor,
+    chain_state: Arc<ChainState>,
+    db: Arc<Storage>,
+    config: ResyncConfig,
+    verifier: SliceVerifier,
+) {
+    executor.spawn(
+        async move {
+            loop {
+                if let Err(e) = resync_recent_epochs(&chain_state, &db, &config, &verifier).await {
+                    error!("slice resync error: {:?}", e);
+                }
+                sleep(RESYNC_INTERVAL).await;
+            }
+        },
+        "slice_resync",
+    );
+}
+
+async fn resync_recent_epochs(
+    chain_state: &ChainState,
+    db: &Storage,
+    config: &ResyncConfig,
+    verifier: &SliceVerifier,
+) -> Result<()> {
+    let latest_epoch = match db.get_latest_epoch().await? {
+        Some(epoch) => epoch,
+        None => return Ok(()),
+    };
+    for epoch in latest_epoch.saturating_sub(config.epochs.saturating_sub(1))..=latest_epoch {
+        let blobs = db.get_epoch_blobs(epoch).await?;
+        for (quorum_id, storage_root, status) in blobs {
+            if !matches!(status, BlobStatus::VERIFIED) {
+                continue;
+            }
+            let missing = missing_slices(chain_state, db, epoch, quorum_id, storage_root).await?;
+            if missing.is_empty() {
+                continue;
+            }
+            info!(
+                "resyncing {:?} slices: epoch = {:?}, quorum = {:?}, storage_root = {:?}",
+                missing.len(),
+                epoch,
+                quorum_id,
+                hex::encode(storage_root)
+            );
+            let remaining = resync_blob(
+                chain_state,
+                db,
+                config,
+                verifier,
+                epoch,
+                quorum_id,
+                storage_root,
+                missing,
+            )
+            .await?;
+            if !remaining.is_empty() {
+                warn!(
+                    "{:?} slices cannot be recovered from peers: epoch = {:?}, quorum = {:?}, storage_root = {:?}",
+                    remaining.len(),
+                    epoch,
+                    quorum_id,
+                    hex::encode(storage_root)
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) async fn missing_slices(
+    chain_state: &ChainState,
+    db: &Storage,
+    epoch: u64,
+    quorum_id: u64,
+    storage_root: [u8; 32],
+) -> Result<Vec<u64>> {
+    chain_state.fetch_quorum_if_missing(epoch).await?;
+    let assigned_slices = match db.get_assgined_slices(epoch, quorum_id).await? {
+        Some(AssignedSlices(assigned_slices)) => assigned_slices,
+        None => return Ok(vec![]),
+    };
+    let mut missing = vec![];
+    for index in assigned_slices {
+        let corrupt = db
+            .is_corrupt_slice(&SliceIndex {
+                epoch,
+                quorum_id,
+                storage_root,
+                index,
+            })
+            .await?;
+        if corrupt
+            || db
+                .get_raw_slice(epoch, quorum_id, storage_root, index as usize)
+                .await?
+                .is_none()
+        {
+            missing.push(index);
+        }
+    }
+    Ok(missing)
+}
+
+/// Fetch slices from peers until all are recovered, returns the ones still missing.
+pub(crate) async fn resync_blob(
+    chain_state: &ChainState,
+    db: &Storage,
+    config: &ResyncConfig,
+    verifier: &SliceVerifier,
+    epoch: u64,
+    quorum_id: u64,
+    storage_root: [u8; 32],
+    missing: Vec<u64>,
+) -> Result<Vec<u64>> {
+    let blob = RecoveredBlob::fetch(chain_state, epoch, quorum_id, storage_root).await?;
+    let mut peers = config.peers.clone();
+    match chain_state
+        .peer_table()
+        .quorum_sockets(epoch, quorum_id)
+        .await
+    {
+        Some(sockets) => peers.extend(sockets),
+        None => peers.extend(chain_state.get_quorum_sockets(epoch, quorum_id).await?),
+    }
+
+    // recovered slices must be under the same merkle roots as the local ones of the blob
+    let mut blob_roots = local_blob_roots(db, epoch, quorum_id, storage_root, &missing).await?;
+
+    let mut missing: HashSet<u64> = missing.into_iter().collect();
+    for peer in peers {
+        if missing.is_empty() {
+            break;
+        }
+        let url = if peer.contains("://") {
+            peer.clone()
+        } else {
+            format!("http://{}", peer)
+        };
+        let mut client = match SignerClient::connect(url).await {
+            Ok(client) => client,
+            Err(e) => {
+                debug!("cannot connect to peer {}: {:?}", peer, e);
+                continue;
+            }
+        };
+        let mut row_indexes: Vec<u32> = missing.iter().map(|index| *index as u32).collect();
+        row_indexes.sort_unstable();
+        for batch in row_indexes.chunks(FETCH_BATCH_SIZE) {
+            let reply = match client
+                .retrieve_stored_slices(RetrieveRequest {
+                    epoch,
+                    quorum_id,
+                    storage_root: storage_root.to_vec(),
+                    row_indexes: batch.to_vec(),
+                    with_opening_proofs: true,
+                })
+                .await
+            {
+                Ok(reply) => reply.into_inner(),
+                Err(e) => {
+                    debug!("cannot fetch slices from peer {}: {:?}", peer, e);
+                    break;
+                }
+            };
+            let stored: Vec<_> = reply
+                .slices
+                .into_iter()
+                .filter(|stored| missing.contains(&(stored.row_index as u64)))
+                .collect();
+            let recovered = verify_recovered_slices(
+                verifier,
+                &blob,
+                &stored,
+                &mut blob_roots,
+                &format!("peer {}", peer),
+            )
+            .await?;
+            for (light_slice, _) in recovered.iter() {
+                missing.remove(&(light_slice.index as u64));
+            }
+            if !recovered.is_empty() {
+                info!("recovered {:?} slices from peer {}", recovered.len(), peer);
+                db.put_light_slices(epoch, quorum_id, storage_root, recovered)
+                    .await?;
+            }
+        }
+    }
+    Ok(missing.into_iter().collect())
+}
+
+/// Merkle roots of the slices of a blob stored locally, skipping the `missing` rows.
+pub(crate) async fn local_blob_roots(
+    db: &Storage,
+    epoch: u64,
+    quorum_id: u64,
+    storage_root: [u8; 32],
+    missing: &[u64],
+) -> Result<Option<[[u8; 32]; 3]>> {
+    for blob in db.get_epoch_info(epoch).await? {
+        if blob.quorum_id != quorum_id || blob.storage_root != storage_root {
+            continue;
+        }
+        for index in blob.indicies {
+            if missing.contains(&(index as u64)) {
+                continue;
+            }
+            if let Some(slice) = db
+                .get_slice(epoch, quorum_id, storage_root, index as usize)
+                .await?
+            {
+                return Ok(Some(slice.merkle_root));
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Blob of recovered slices, with the erasure commitment verified on chain.
+pub(crate) struct RecoveredBlob {
+    pub epoch: u64,
+    pub storage_root: [u8; 32],
+    pub erasure_commitment: G1Projective,
+}
+
+impl RecoveredBlob {
+    pub async fn fetch(
+        chain_state: &ChainState,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    ) -> Result<Self> {
+        match chain_state
+            .onchain_erasure_commitment(epoch, quorum_id, storage_root)
+            .await?
+        {
+            Some(erasure_commitment) => Ok(Self {
+                epoch,
+                storage_root,
+                erasure_commitment: erasure_commitment.into_group(),
+            }),
+            None => bail!(anyhow!(
+                "no erasure commitment on chain for the blob, its slices cannot be verified"
+            )),
+        }
+    }
+}
+
+/// Verify slices received from `source` against the storage root and the erasure commitment of
+/// their blob, then against its merkle roots, returns the valid ones to store. Slices without
+/// their opening proof cannot be verified and are rejected.
+pub(crate) async fn verify_recovered_slices(
+    verifier: &SliceVerifier,
+    blob: &RecoveredBlob,
+    stored: &[StoredSlice],
+    blob_roots: &mut Option<[[u8; 32]; 3]>,
+    source: &str,
+) -> Result<Vec<(LightEncodedSlice, Vec<[u8; 32]>)>> {
+    let mut slices = vec![];
+    for stored in stored {
+        match decode_encoded_slice(stored) {
+            Ok(slice) => slices.push(slice),
+            Err(e) => warn!(
+                "invalid slice {:?} from {}: {:?}",
+                stored.row_index, source, e
+            ),
+        }
+    }
+    let mut recovered = vec![];
+    for result in verifier
+        .verify(
+            blob.epoch,
+            blob.storage_root,
+            blob.erasure_commitment,
+            slices,
+        )
+        .await?
+    {
+        let verified = result.and_then(|slice| {
+            let data = slice.merkle_row();
+            let light_slice = slice.into_light_slice();
+            check_merkle_proof(&light_slice, &data, blob_roots)?;
+            Ok((light_slice, data))
+        });
+        match verified {
+            Ok((light_slice, data)) => {
+                blob_roots.get_or_insert(light_slice.merkle_root);
+                recovered.push((light_slice, data));
+            }
+            Err(e) => warn!("invalid slice from {}: {:?}", source, e),
+        }
+    }
+    Ok(recovered)
+}
+
+fn decode_encoded_slice(stored: &StoredSlice) -> Result<EncodedSlice> {
+    if stored.encoded_slice.is_empty() {
+        bail!(anyhow!("slice without its opening proof"));
+    }
+    let slice =
+        EncodedSlice::deserialize_with_mode(&*stored.encoded_slice, Compress::Yes, Validate::Yes)?;
+    if slice.index as u64 != stored.row_index as u64 {
+        bail!(anyhow!("slice index mismatch"));
+    }
+    Ok(slice)
+}
+
+/// Check a stored slice against its merkle roots only, for slices from a trusted source.
+pub(crate) fn verify_stored_slice(
+    stored: &StoredSlice,
+    blob_roots: &Option<[[u8; 32]; 3]>,
+) -> Result<(LightEncodedSlice, Vec<[u8; 32]>)> {
+    let light_slice = LightEncodedSlice::deserialize_with_mode(
+        &*stored.light_slice,
+        Compress::Yes,
+        Validate::Yes,
+    )?;
+    let data: Vec<[u8; 32]> = CanonicalDeserialize::deserialize_uncompressed(&*stored.data)?;
+    if light_slice.index as u64 != stored.row_index as u64 {
+        bail!(anyhow!("slice index mismatch"));
+    }
+    check_merkle_proof(&light_slice, &data, blob_roots)?;
+    Ok((light_slice, data))
+}
+
+fn check_merkle_proof(
+    light_slice: &LightEncodedSlice,
+    data: &[[u8; 32]],
+    blob_roots: &Option<[[u8; 32]; 3]>,
+) -> Result<()> {
+    if let Some(blob_roots) = blob_roots {
+        if *blob_roots != light_slice.merkle_root {
+            bail!(anyhow!("merkle roots mismatch with local slices"));
+        }
+    }
+    if !verify_line_proof(
+        light_slice.index as usize,
+        data,
+        &light_slice.merkle_proof,
+        &light_slice.merkle_root,
+    ) {
+        bail!(anyhow!("slice does not match its merkle root"));
+    }
+    Ok(())
+}
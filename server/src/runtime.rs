@@ -1,21 +1,117 @@
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
 use exit_future::Signal;
 use futures::channel::mpsc::Receiver;
 use futures::StreamExt;
 use task_executor::{ShutdownReason, TaskExecutor};
 use tokio::{
-    runtime::Runtime,
+    runtime::{Builder, Handle, Runtime},
     signal::unix::{signal, SignalKind},
+    time::sleep,
 };
 
-pub fn make_environment() -> Result<(Environment, Runtime, TaskExecutor), String> {
+use crate::config::SupervisorConfig;
+
+/// A run of a supervised task lasting this long is healthy, its failure count is reset.
+const SUPERVISED_RESET_AFTER: Duration = Duration::from_secs(300);
+
+/// Make an executor on the given runtime, all spawned tasks exit once the environment is dropped.
+pub fn make_environment(handle: Handle) -> (Environment, TaskExecutor) {
     let (signal, exit) = exit_future::signal();
     let (signal_tx, signal_rx) = futures::channel::mpsc::channel(1);
-    let runtime = tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
-        .build()
-        .map_err(|e| format!("Failed to start runtime: {:?}", e))?;
-    let executor = TaskExecutor::new(runtime.handle().clone(), exit, signal_tx);
-    Ok((Environment { signal, signal_rx }, runtime, executor))
+    let executor = TaskExecutor::new(handle, exit, signal_tx);
+    (Environment { signal, signal_rx }, executor)
+}
+
+/// Run the task made by `make_task` on `executor`, making a new one with backoff whenever it
+/// fails. A task returning `Ok` is done. The node is shut down once the task fails
+/// `max_failures` times in a row, runs shorter than `SUPERVISED_RESET_AFTER` counting as a row.
+pub fn spawn_supervised<F, T>(
+    executor: &TaskExecutor,
+    config: &SupervisorConfig,
+    name: &'static str,
+    make_task: F,
+) where
+    F: Fn() -> T + Send + 'static,
+    T: Future<Output = Result<()>> + Send + 'static,
+{
+    let config = config.clone();
+    let shutdown_executor = executor.clone();
+    executor.spawn(
+        async move {
+            let mut failures = 0;
+            let mut backoff = config.initial_backoff;
+            loop {
+                let started = Instant::now();
+                let error = match make_task().await {
+                    Ok(()) => return,
+                    Err(e) => e,
+                };
+                if started.elapsed() >= SUPERVISED_RESET_AFTER {
+                    failures = 0;
+                    backoff = config.initial_backoff;
+                }
+                failures += 1;
+                if failures >= config.max_failures {
+                    error!(
+                        task = name,
+                        failures, "supervised task keeps failing, stopping the node: {:?}", error
+                    );
+                    let _ = shutdown_executor
+                        .shutdown_sender()
+                        .try_send(ShutdownReason::Failure("supervised task keeps failing"));
+                    return;
+                }
+                warn!(
+                    task = name,
+                    failures, "supervised task failed, restarting in {:?}: {:?}", backoff, error
+                );
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(config.max_backoff);
+            }
+        },
+        name,
+    );
+}
+
+/// A runtime dedicated to some node services, so they do not share worker threads with the others.
+pub struct DedicatedRuntime {
+    runtime: Option<Runtime>,
+}
+
+impl DedicatedRuntime {
+    pub fn new(name: &str, worker_threads: usize) -> Result<Self> {
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(worker_threads.max(1))
+            .thread_name(name)
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            runtime: Some(runtime),
+        })
+    }
+
+    /// Executor spawning on this runtime, its tasks exit along with the ones of `executor`.
+    pub fn executor(&self, executor: &TaskExecutor) -> TaskExecutor {
+        TaskExecutor::new(
+            self.runtime.as_ref().unwrap().handle().clone(),
+            executor.exit(),
+            executor.shutdown_sender(),
+        )
+    }
+}
+
+impl Drop for DedicatedRuntime {
+    fn drop(&mut self) {
+        // a runtime cannot be dropped in async context
+        if let Some(runtime) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
 }
 
 pub struct Environment {
@@ -25,7 +121,7 @@ pub struct Environment {
 }
 
 impl Environment {
-    pub async fn wait_shutdown_signal(mut self) {
+    pub async fn wait_shutdown_signal(&mut self) {
         let mut sig_term = match signal(SignalKind::terminate()) {
             Ok(x) => x,
             Err(e) => {
@@ -0,0 +1,176 @@
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{anyhow, bail, Result};
+use ark_bn254::G1Projective;
+use ark_ec::AffineRepr;
+use ark_serialize::{CanonicalDeserialize, Compress, Validate};
+use chain_state::ChainState;
+use da_miner::verify_line_proof;
+use grpc::SliceVerifier;
+use storage::{
+    cold_storage::ColdStorageDB,
+    opening_proof_db::OpeningProofDB,
+    quorum_db::QuorumDB,
+    scrub_db::ScrubDB,
+    slice_db::{SliceDB, SliceIndex},
+    Storage,
+};
+use task_executor::TaskExecutor;
+use tokio::time::sleep;
+use zg_encoder::EncodedSlice;
+
+const SCRUB_PASS_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Re-verify stored slices against the merkle roots kept with them, at most `slices_per_second`,
+/// flagging corrupt or missing entries. With the chain state, slices kept whole with their opening
+/// proof are also verified against the erasure commitment of their blob on chain.
+pub fn start_slice_scrubber(
+    executor: TaskExecutor,
+    db: Arc<Storage>,
+    slices_per_second: u64,
+    chain_state: Option<Arc<ChainState>>,
+    verifier: SliceVerifier,
+) {
+    let delay = Duration::from_secs(1) / slices_per_second.max(1) as u32;
+    executor.spawn(
+        async move {
+            loop {
+                match scrub_next_epoch(&db, chain_state.as_deref(), &verifier, delay).await {
+                    Ok(true) => {}
+                    Ok(false) => sleep(SCRUB_PASS_INTERVAL).await,
+                    Err(e) => {
+                        error!("slice scrubber error: {:?}", e);
+                        sleep(SCRUB_PASS_INTERVAL).await;
+                    }
+                }
+            }
+        },
+        "slice_scrubber",
+    );
+}
+
+/// Scrub one epoch, returns false once a full pass is completed.
+async fn scrub_next_epoch(
+    db: &Storage,
+    chain_state: Option<&ChainState>,
+    verifier: &SliceVerifier,
+    delay: Duration,
+) -> Result<bool> {
+    let (epoch, latest_epoch) = {
+        let latest_epoch = match db.get_latest_epoch().await? {
+            Some(epoch) => epoch,
+            None => return Ok(false),
+        };
+        // slices moved to cold storage are not scrubbed
+        let first_epoch = match db.get_first_local_epoch().await? {
+            Some(epoch) => epoch,
+            None => return Ok(false),
+        };
+        let epoch = db.get_scrub_progress().await?.unwrap_or(0).max(first_epoch);
+        (epoch, latest_epoch)
+    };
+    if epoch > latest_epoch {
+        info!("slice scrubbing pass completed");
+        db.put_scrub_progress(0).await?;
+        return Ok(false);
+    }
+
+    let blobs = db.get_epoch_info(epoch).await?;
+    let mut corrupt = 0;
+    for blob in blobs.iter() {
+        // blobs without a commitment on chain are checked against their merkle roots only
+        let erasure_commitment = match chain_state {
+            Some(chain_state) => chain_state
+                .onchain_erasure_commitment(epoch, blob.quorum_id, blob.storage_root)
+                .await?
+                .map(|commitment| commitment.into_group()),
+            None => None,
+        };
+        for index in blob.indicies.iter() {
+            let slice_index = SliceIndex {
+                epoch,
+                quorum_id: blob.quorum_id,
+                storage_root: blob.storage_root,
+                index: *index as u64,
+            };
+            if let Err(e) = verify_slice(db, verifier, erasure_commitment, &slice_index).await {
+                error!(
+                    "corrupt slice: epoch = {:?}, quorum = {:?}, storage_root = {:?}, row_index = {:?}, error = {:?}",
+                    epoch,
+                    blob.quorum_id,
+                    hex::encode(blob.storage_root),
+                    index,
+                    e
+                );
+                db.put_corrupt_slice(&slice_index).await?;
+                corrupt += 1;
+            }
+            sleep(delay).await;
+        }
+    }
+    if corrupt > 0 {
+        warn!("{:?} corrupt slices found in epoch {:?}", corrupt, epoch);
+    }
+    db.put_scrub_progress(epoch + 1).await?;
+    Ok(true)
+}
+
+async fn verify_slice(
+    db: &Storage,
+    verifier: &SliceVerifier,
+    erasure_commitment: Option<G1Projective>,
+    index: &SliceIndex,
+) -> Result<()> {
+    let SliceIndex {
+        epoch,
+        quorum_id,
+        storage_root,
+        index,
+    } = *index;
+    let data = db
+        .get_slice_data(epoch, quorum_id, storage_root, index as usize)
+        .await?
+        .ok_or(anyhow!("slice data is missing"))?;
+    let slice = db
+        .get_slice(epoch, quorum_id, storage_root, index as usize)
+        .await?
+        .ok_or(anyhow!("encoded slice is missing"))?;
+    if slice.index as u64 != index {
+        bail!("slice index mismatch");
+    }
+    if !verify_line_proof(
+        index as usize,
+        &data,
+        &slice.merkle_proof,
+        &slice.merkle_root,
+    ) {
+        bail!("slice does not match its merkle root");
+    }
+
+    let erasure_commitment = match erasure_commitment {
+        Some(erasure_commitment) => erasure_commitment,
+        None => return Ok(()),
+    };
+    // only slices kept whole have the opening proof to verify
+    let raw_slice = match db
+        .get_opening_proof(epoch, quorum_id, storage_root, index)
+        .await?
+    {
+        Some(raw_slice) => raw_slice,
+        None => return Ok(()),
+    };
+    let encoded_slice =
+        EncodedSlice::deserialize_with_mode(&*raw_slice, Compress::Yes, Validate::Yes)?;
+    let encoded_slice = verifier
+        .verify(epoch, storage_root, erasure_commitment, vec![encoded_slice])
+        .await?
+        .remove(0)?;
+    // the stored rows must be the ones proven
+    if encoded_slice.index as u64 != index
+        || encoded_slice.merkle_row() != data
+        || encoded_slice.into_light_slice().merkle_root != slice.merkle_root
+    {
+        bail!("slice does not match its opening proof");
+    }
+    Ok(())
+}
@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use events::{EventBus, NodeEvent};
+use futures::StreamExt;
+use grpc::signer::{self, signer_client::SignerClient, SignOutcomeRequest};
+use storage::sign_outcome_db::SignOutcome;
+use task_executor::TaskExecutor;
+use tokio::sync::{
+    broadcast::error::RecvError,
+    mpsc::{self, error::TrySendError},
+};
+use tokio_util::time::DelayQueue;
+use tonic::transport::{Channel, Endpoint};
+
+/// Peers may receive the same sign request a bit later.
+const COMPARE_DELAY: Duration = Duration::from_secs(60);
+/// Sign results waiting for their comparison, later ones are not compared.
+const MAX_PENDING_COMPARISONS: usize = 4096;
+
+struct SignResult {
+    epoch: u64,
+    quorum_id: u64,
+    storage_root: [u8; 32],
+    outcome: SignOutcome,
+}
+
+impl SignResult {
+    fn from_event(event: NodeEvent) -> Option<Self> {
+        match event {
+            NodeEvent::BlobSigned {
+                epoch,
+                quorum_id,
+                storage_root,
+            } => Some(Self {
+                epoch,
+                quorum_id,
+                storage_root,
+                outcome: SignOutcome::SIGNED,
+            }),
+            NodeEvent::BlobRejected {
+                epoch,
+                quorum_id,
+                storage_root,
+                ..
+            } => Some(Self {
+                epoch,
+                quorum_id,
+                storage_root,
+                outcome: SignOutcome::REJECTED,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Compare the outcome of every sign request with the configured peers, a divergence raises an alert.
+pub fn start_sign_monitor(
+    executor: TaskExecutor,
+    peers: Vec<String>,
+    events: &EventBus,
+) -> Result<()> {
+    let mut clients = vec![];
+    for peer in peers {
+        let channel = Endpoint::from_shared(peer.clone())?.connect_lazy();
+        clients.push((peer, SignerClient::new(channel)));
+    }
+    let mut receiver = events.subscribe();
+    let (sender, mut results) = mpsc::channel(MAX_PENDING_COMPARISONS);
+    executor.spawn(
+        async move {
+            loop {
+                let result = match receiver.recv().await {
+                    Ok(event) => match SignResult::from_event(event) {
+                        Some(result) => result,
+                        None => continue,
+                    },
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("sign monitor lagged, {} events skipped", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                match sender.try_send(result) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(result)) => {
+                        warn!(
+                            epoch = result.epoch,
+                            quorum_id = result.quorum_id,
+                            "too many sign outcomes pending comparison, skipped"
+                        );
+                    }
+                    Err(TrySendError::Closed(_)) => return,
+                }
+            }
+        },
+        "sign_monitor",
+    );
+    executor.spawn(
+        async move {
+            // every result waits for the comparison delay in one queue, new results are taken
+            // only while it has room
+            let mut pending = DelayQueue::new();
+            loop {
+                tokio::select! {
+                    result = results.recv(), if pending.len() < MAX_PENDING_COMPARISONS => match result {
+                        Some(result) => {
+                            pending.insert(result, COMPARE_DELAY);
+                        }
+                        None => return,
+                    },
+                    Some(expired) = pending.next() => {
+                        compare_with_peers(&expired.into_inner(), clients.clone()).await;
+                    }
+                }
+            }
+        },
+        "sign_outcome_comparison",
+    );
+    Ok(())
+}
+
+async fn compare_with_peers(result: &SignResult, clients: Vec<(String, SignerClient<Channel>)>) {
+    for (peer, mut client) in clients {
+        let reply = match client
+            .get_sign_outcome(SignOutcomeRequest {
+                epoch: result.epoch,
+                quorum_id: result.quorum_id,
+                storage_root: result.storage_root.to_vec(),
+            })
+            .await
+        {
+            Ok(reply) => reply.into_inner(),
+            Err(e) => {
+                warn!("cannot query sign outcome from peer {}: {:?}", peer, e);
+                continue;
+            }
+        };
+        let diverged = matches!(
+            (result.outcome, reply.outcome()),
+            (SignOutcome::SIGNED, signer::SignOutcome::Rejected)
+                | (SignOutcome::REJECTED, signer::SignOutcome::Signed)
+        );
+        if diverged {
+            error!(
+                target: "alert",
+                epoch = result.epoch,
+                quorum_id = result.quorum_id,
+                storage_root = hex::encode(result.storage_root),
+                "sign outcome diverged: {:?} locally, {:?} on peer {}",
+                result.outcome,
+                reply.outcome(),
+                peer
+            );
+        } else {
+            debug!(
+                epoch = result.epoch,
+                quorum_id = result.quorum_id,
+                "sign outcome {:?} locally, {:?} on peer {}",
+                result.outcome,
+                reply.outcome(),
+                peer
+            );
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use chain_state::sync_progress::SyncProgress;
+use sd_notify::NotifyState;
+use task_executor::TaskExecutor;
+use tokio::time::{interval, timeout};
+
+/// Interval of the sync progress reports, each extending the start timeout of the unit.
+const SYNC_REPORT_INTERVAL: Duration = Duration::from_secs(30);
+
+fn notify(states: &[NotifyState]) {
+    if let Err(e) = sd_notify::notify(false, states) {
+        warn!("cannot notify systemd: {:?}", e);
+    }
+}
+
+/// Notify systemd once the node serves requests: after the encoder params are loaded and, unless
+/// in storage-only mode, the chain state is synced. Pings the watchdog from the main runtime if
+/// the unit sets `WatchdogSec`, so a wedged node is restarted. Does nothing outside a
+/// `Type=notify` unit.
+pub fn start_systemd_notify(executor: TaskExecutor, sync_progress: Option<SyncProgress>) {
+    executor.spawn(
+        async move {
+            if let Some(sync_progress) = sync_progress {
+                while timeout(SYNC_REPORT_INTERVAL, sync_progress.wait_synced())
+                    .await
+                    .is_err()
+                {
+                    let status = sync_progress.status().await;
+                    notify(&[
+                        NotifyState::Status(&format!(
+                            "syncing chain state, block {} of {}",
+                            status.current_block, status.target_block
+                        )),
+                        NotifyState::ExtendTimeoutUsec(2 * SYNC_REPORT_INTERVAL.as_micros() as u32),
+                    ]);
+                }
+            }
+            info!("node ready");
+            notify(&[NotifyState::Ready, NotifyState::Status("serving")]);
+        },
+        "systemd_notify",
+    );
+
+    let mut watchdog_usec = 0;
+    if !sd_notify::watchdog_enabled(false, &mut watchdog_usec) {
+        return;
+    }
+    info!(watchdog_usec, "pinging the systemd watchdog");
+    executor.spawn(
+        async move {
+            let mut interval = interval(Duration::from_micros(watchdog_usec) / 2);
+            loop {
+                interval.tick().await;
+                notify(&[NotifyState::Watchdog]);
+            }
+        },
+        "systemd_watchdog",
+    );
+}
+
+/// Tell systemd the node is stopping.
+pub fn notify_stopping() {
+    notify(&[NotifyState::Stopping]);
+}
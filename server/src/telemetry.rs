@@ -0,0 +1,134 @@
+use std::{str::FromStr, sync::Mutex};
+
+use anyhow::{anyhow, Result};
+use opentelemetry::{global, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::{self, Sampler},
+    Resource,
+};
+use rolling_file::{RollingConditionBasic, RollingFileAppender};
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{
+    fmt::{self, MakeWriter},
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+    EnvFilter, Layer, Registry,
+};
+
+use crate::{
+    config::{LogFileConfig, LogFormat, LogRotation, OtlpConfig},
+    Config,
+};
+
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Flushes the log file when dropped.
+static LOG_FILE_GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
+
+fn log_filter(level: &str) -> Result<EnvFilter> {
+    // make sure log level is valid string
+    let _ = Level::from_str(level)?;
+    Ok(EnvFilter::try_new(format!("{},hyper=warn", level))?)
+}
+
+fn fmt_layer<W>(format: LogFormat, writer: W, ansi: bool) -> BoxedLayer
+where
+    W: for<'a> MakeWriter<'a> + Send + Sync + 'static,
+{
+    let layer = fmt::layer().with_writer(writer).with_ansi(ansi);
+    match format {
+        LogFormat::Text => layer.boxed(),
+        LogFormat::Json => layer.json().boxed(),
+    }
+}
+
+fn log_file_layer(format: LogFormat, config: &LogFileConfig) -> Result<BoxedLayer> {
+    let mut condition = RollingConditionBasic::new();
+    condition = match config.rotation {
+        LogRotation::Hourly => condition.hourly(),
+        LogRotation::Daily => condition.daily(),
+        LogRotation::Never => condition,
+    };
+    if let Some(max_size) = config.max_size {
+        condition = condition.max_size(max_size);
+    }
+    if let Some(dir) = std::path::Path::new(&config.path).parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let appender = RollingFileAppender::new(&config.path, condition, config.max_files)
+        .map_err(|e| anyhow!("Cannot open log file {:?}: {:?}", config.path, e))?;
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    *LOG_FILE_GUARD.lock().unwrap() = Some(guard);
+    Ok(fmt_layer(format, writer, false)
+        .with_filter(log_filter(&config.level)?)
+        .boxed())
+}
+
+fn otlp_layer(config: &OtlpConfig) -> Result<BoxedLayer> {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let tracer = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(
+            opentelemetry_otlp::new_exporter()
+                .tonic()
+                .with_endpoint(config.endpoint.clone()),
+        )
+        .with_trace_config(
+            trace::config()
+                .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                    config.sample_ratio,
+                ))))
+                .with_resource(Resource::new(vec![KeyValue::new(
+                    "service.name",
+                    config.service_name.clone(),
+                )])),
+        )
+        .install_batch(runtime::Tokio)
+        .map_err(|e| anyhow!("Cannot start the OTLP exporter: {:?}", e))?;
+    Ok(tracing_opentelemetry::layer().with_tracer(tracer).boxed())
+}
+
+/// Install the console logs, the log file and the export of spans to an OTLP collector as
+/// configured. Must be called inside a tokio runtime when traces are exported.
+pub fn init_tracing(config: &Config) -> Result<()> {
+    let mut layers = vec![fmt_layer(config.log_format, std::io::stdout, true)
+        .with_filter(log_filter(&config.log_level)?)
+        .boxed()];
+    if let Some(log_file) = &config.log_file {
+        layers.push(log_file_layer(config.log_format, log_file)?);
+    }
+    if let Some(otlp) = &config.otlp {
+        layers.push(
+            otlp_layer(otlp)?
+                .with_filter(log_filter(&config.log_level)?)
+                .boxed(),
+        );
+    }
+    // the console reads the task events of tokio, they are not filtered by the log level
+    #[cfg(feature = "tokio-console")]
+    if let Some(address) = config
+        .runtime_monitor
+        .as_ref()
+        .and_then(|monitor| monitor.tokio_console_address)
+    {
+        layers.push(grpc::tokio_console_layer(address).boxed());
+    }
+    tracing_subscriber::registry()
+        .with(layers)
+        .try_init()
+        .map_err(|e| anyhow!("Cannot install the tracing subscriber: {:?}", e))
+}
+
+/// Flush the spans not exported yet.
+pub fn shutdown_tracing() {
+    global::shutdown_tracer_provider();
+}
+
+/// Write the logs still buffered to the log file, later logs only go to the console.
+pub fn flush_logs() {
+    LOG_FILE_GUARD.lock().unwrap().take();
+}
@@ -0,0 +1,200 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, bail, Result};
+use events::{EventBus, NodeEvent};
+use hmac::{Hmac, Mac};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use task_executor::TaskExecutor;
+use tokio::{sync::broadcast::error::RecvError, time::sleep};
+
+use crate::config::WebhookConfig;
+
+/// Header of the HMAC-SHA256 of the payload, `sha256=<hex>`.
+const SIGNATURE_HEADER: &str = "X-DA-Node-Signature";
+/// Header of the event name.
+const EVENT_HEADER: &str = "X-DA-Node-Event";
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Names of the events posted to webhooks.
+pub const WEBHOOK_EVENTS: [&str; 6] = [
+    "blob_signed",
+    "verification_failure_spike",
+    "registration_succeeded",
+    "registration_failed",
+    "registration_missed",
+    "das_submission_confirmed",
+];
+
+/// Name and data of the payload of an event, `None` for the events not posted.
+fn event_payload(event: &NodeEvent) -> Option<(&'static str, Value)> {
+    Some(match event {
+        NodeEvent::BlobSigned {
+            epoch,
+            quorum_id,
+            storage_root,
+        } => (
+            "blob_signed",
+            json!({
+                "epoch": epoch,
+                "quorum_id": quorum_id,
+                "storage_root": hex::encode(storage_root),
+            }),
+        ),
+        NodeEvent::VerificationFailureSpike { failed_blobs } => (
+            "verification_failure_spike",
+            json!({ "failed_blobs": failed_blobs }),
+        ),
+        NodeEvent::RegistrationSucceeded { epoch } => {
+            ("registration_succeeded", json!({ "epoch": epoch }))
+        }
+        NodeEvent::RegistrationFailed { epoch, error } => (
+            "registration_failed",
+            json!({ "epoch": epoch, "error": error }),
+        ),
+        NodeEvent::RegistrationMissed { epoch } => {
+            ("registration_missed", json!({ "epoch": epoch }))
+        }
+        NodeEvent::DasSubmissionConfirmed {
+            epoch,
+            quorum_id,
+            data_root,
+            tx_hash,
+        } => (
+            "das_submission_confirmed",
+            json!({
+                "epoch": epoch,
+                "quorum_id": quorum_id,
+                "data_root": hex::encode(data_root),
+                "tx_hash": hex::encode(tx_hash),
+            }),
+        ),
+        _ => return None,
+    })
+}
+
+fn sign_payload(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Post a payload, retrying failed deliveries and server errors with backoff.
+async fn deliver(
+    client: &reqwest::Client,
+    config: &WebhookConfig,
+    url: &str,
+    name: &str,
+    body: &str,
+) -> Result<()> {
+    let mut backoff = INITIAL_BACKOFF;
+    let mut retries = 0;
+    loop {
+        let mut request = client
+            .post(url)
+            .timeout(config.timeout)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, name)
+            .body(body.to_string());
+        if let Some(secret) = &config.secret {
+            request = request.header(SIGNATURE_HEADER, sign_payload(secret, body));
+        }
+        let e = match request.send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            // the endpoint rejects the payload, posting it again does not help
+            Ok(response)
+                if response.status().is_client_error()
+                    && response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS =>
+            {
+                bail!(anyhow!("rejected with status {}", response.status()))
+            }
+            Ok(response) => anyhow!("status {}", response.status()),
+            Err(e) => anyhow!(e),
+        };
+        if retries >= config.max_retries {
+            bail!(anyhow!("{:?} after {} retries", e, retries));
+        }
+        retries += 1;
+        sleep(backoff).await;
+        backoff *= 2;
+    }
+}
+
+/// Post the events of `events` selected by the config to its webhooks, as JSON payloads signed
+/// with its secret.
+pub fn start_webhooks(executor: TaskExecutor, config: WebhookConfig, events: &EventBus) {
+    let mut receiver = events.subscribe();
+    let client = reqwest::Client::new();
+    let config = Arc::new(config);
+    let spawn_executor = executor.clone();
+    executor.spawn(
+        async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("webhook dispatcher lagged, {} events skipped", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                };
+                let (name, data) = match event_payload(&event) {
+                    Some(payload) => payload,
+                    None => continue,
+                };
+                if !config.events.is_empty() && !config.events.iter().any(|x| x == name) {
+                    continue;
+                }
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                let body: Arc<str> = json!({
+                    "event": name,
+                    "timestamp": timestamp,
+                    "data": data,
+                })
+                .to_string()
+                .into();
+                for url in &config.urls {
+                    let url = url.clone();
+                    let (client, config, body) = (client.clone(), config.clone(), body.clone());
+                    spawn_executor.spawn(
+                        async move {
+                            if let Err(e) = deliver(&client, &config, &url, name, &body).await {
+                                warn!(url, event = name, "cannot deliver webhook: {:?}", e);
+                            }
+                        },
+                        "webhook_delivery",
+                    );
+                }
+            }
+        },
+        "webhook_dispatcher",
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn payload_test() {
+        let (name, data) = event_payload(&NodeEvent::RegistrationFailed {
+            epoch: 3,
+            error: "reverted".to_string(),
+        })
+        .unwrap();
+        assert_eq!(name, "registration_failed");
+        assert_eq!(data, json!({ "epoch": 3, "error": "reverted" }));
+        assert!(event_payload(&NodeEvent::Started).is_none());
+
+        let signature = sign_payload("secret", "{}");
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(b"{}");
+        mac.verify_slice(&hex::decode(&signature["sha256=".len()..]).unwrap())
+            .unwrap();
+    }
+}
@@ -1,11 +1,13 @@
 use crate::COL_BLOB_STATUS;
 
 use super::Storage;
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
+use kvdb::KeyValueDB;
 
 use std::convert::TryFrom;
 
+#[derive(Debug)]
 pub enum BlobStatus {
     UPLOADED = 1,
     VERIFIED = 2,
@@ -38,6 +40,8 @@ pub trait BlobStatusDB {
         quorum_id: u64,
         storage_root: [u8; 32],
     ) -> Result<Option<BlobStatus>>;
+    /// Quorum id, storage root and status of all blobs of an epoch.
+    async fn get_epoch_blobs(&self, epoch: u64) -> Result<Vec<(u64, [u8; 32], BlobStatus)>>;
 }
 
 fn get_blob_key(epoch: u64, quorum_id: u64, storage_root: [u8; 32]) -> Vec<u8> {
@@ -80,4 +84,21 @@ impl BlobStatusDB for Storage {
         }
         Ok(None)
     }
+
+    async fn get_epoch_blobs(&self, epoch: u64) -> Result<Vec<(u64, [u8; 32], BlobStatus)>> {
+        let mut answer = vec![];
+        for item in KeyValueDB::iter_with_prefix(&*self.db, COL_BLOB_STATUS, &epoch.to_be_bytes()) {
+            let (key, raw_data) = item?;
+            if key.len() != 8 + 8 + 32 {
+                bail!(anyhow!("Incorrect key format"));
+            }
+            let quorum_id = u64::from_be_bytes(key[8..16].try_into()?);
+            let storage_root: [u8; 32] = key[16..48].try_into()?;
+            let status: BlobStatus = u64::from_be_bytes(raw_data.try_into().unwrap())
+                .try_into()
+                .map_err(|_| anyhow!("error when convert u64 to BlobStatus"))?;
+            answer.push((quorum_id, storage_root, status));
+        }
+        Ok(answer)
+    }
 }
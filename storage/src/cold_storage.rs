@@ -0,0 +1,465 @@
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc};
+
+use crate::{
+    quorum_db::QuorumDB,
+    slice_db::{SliceIndex, BLOB_PREFIX, DATA_PREFIX, SLICE_PREFIX},
+    usage_db::UsageDelta,
+    COL_MISC, COL_SLICE, COL_TIERED_SLICE,
+};
+
+use super::Storage;
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use kvdb::KeyValueDB;
+use sha2::{Digest, Sha256};
+
+const TIERED_EPOCH_KEY: &[u8] = &[1];
+
+#[derive(Clone)]
+pub enum ObjectStoreConfig {
+    /// A directory, e.g. a bucket mounted through s3fs or gcsfuse.
+    Local { path: String },
+    /// An S3 compatible endpoint (AWS S3, GCS interoperability API, MinIO, ...).
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+#[derive(Clone)]
+pub struct ColdStorageConfig {
+    pub store: ObjectStoreConfig,
+    /// Slices of epochs older than `latest epoch - tier_after_epochs` are moved to the object store.
+    pub tier_after_epochs: u64,
+}
+
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    async fn put_object(&self, key: &str, value: Vec<u8>) -> Result<()>;
+
+    async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Delete an object, deleting a missing object succeeds.
+    async fn delete_object(&self, key: &str) -> Result<()>;
+}
+
+pub fn make_object_store(config: &ObjectStoreConfig) -> Result<Arc<dyn ObjectStore>> {
+    Ok(match config {
+        ObjectStoreConfig::Local { path } => {
+            std::fs::create_dir_all(path)?;
+            Arc::new(LocalObjectStore {
+                root: PathBuf::from(path),
+            })
+        }
+        ObjectStoreConfig::S3 {
+            endpoint,
+            bucket,
+            region,
+            access_key,
+            secret_key,
+        } => Arc::new(S3ObjectStore {
+            client: reqwest::Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.clone(),
+            region: region.clone(),
+            access_key: access_key.clone(),
+            secret_key: secret_key.clone(),
+        }),
+    })
+}
+
+/// Object key of a tiered slice value, derived from its local database key.
+pub fn object_key(db_key: &[u8]) -> String {
+    format!("slices/{}", hex::encode(db_key))
+}
+
+struct LocalObjectStore {
+    root: PathBuf,
+}
+
+#[async_trait]
+impl ObjectStore for LocalObjectStore {
+    async fn put_object(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, value).await?;
+        Ok(())
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match tokio::fs::read(self.root.join(key)).await {
+            Ok(value) => Ok(Some(value)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<()> {
+        match tokio::fs::remove_file(self.root.join(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+struct S3ObjectStore {
+    client: reqwest::Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3ObjectStore {
+    fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+        mac.update(data);
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// Build a path-style request signed with AWS signature version 4.
+    fn signed_request(
+        &self,
+        method: reqwest::Method,
+        key: &str,
+        body: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder> {
+        let url = reqwest::Url::parse(&format!("{}/{}/{}", self.endpoint, self.bucket, key))?;
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            _ => bail!(anyhow!("invalid object store endpoint {}", self.endpoint)),
+        };
+
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method,
+            url.path(),
+            host,
+            payload_hash,
+            amz_date,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [self.region.as_bytes(), b"s3", b"aws4_request"]
+            .iter()
+            .fold(
+                Self::hmac(
+                    format!("AWS4{}", self.secret_key).as_bytes(),
+                    date.as_bytes(),
+                ),
+                |key, data| Self::hmac(&key, data),
+            );
+        let signature = hex::encode(Self::hmac(&signing_key, string_to_sign.as_bytes()));
+
+        Ok(self
+            .client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header(
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    self.access_key, scope, signature
+                ),
+            )
+            .body(body))
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3ObjectStore {
+    async fn put_object(&self, key: &str, value: Vec<u8>) -> Result<()> {
+        let response = self
+            .signed_request(reqwest::Method::PUT, key, value)?
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            bail!(anyhow!(
+                "put object {} failed with status {}",
+                key,
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+
+    async fn get_object(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let response = self
+            .signed_request(reqwest::Method::GET, key, vec![])?
+            .send()
+            .await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !response.status().is_success() {
+            bail!(anyhow!(
+                "get object {} failed with status {}",
+                key,
+                response.status()
+            ));
+        }
+        Ok(Some(response.bytes().await?.to_vec()))
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<()> {
+        let response = self
+            .signed_request(reqwest::Method::DELETE, key, vec![])?
+            .send()
+            .await?;
+        if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
+            bail!(anyhow!(
+                "delete object {} failed with status {}",
+                key,
+                response.status()
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+pub trait ColdStorageDB {
+    /// The last epoch whose slices are completely moved to the object store.
+    async fn get_tiered_epoch(&self) -> Result<Option<u64>>;
+
+    async fn put_tiered_epoch(&self, epoch: u64) -> Result<()>;
+
+    /// The smallest epoch which still has slice or data records in the local database, or blob
+    /// records after the tiered epoch. Blob records of tiered epochs stay local.
+    async fn get_first_local_epoch(&self) -> Result<Option<u64>>;
+
+    /// Load at most `limit` slice entries of `epoch` still kept in the local database.
+    async fn next_tiering_batch(&self, epoch: u64, limit: usize)
+        -> Result<Vec<(Vec<u8>, Vec<u8>)>>;
+
+    /// Replace slice entries already uploaded to the object store by local index records.
+    async fn mark_tiered(&self, keys: Vec<Vec<u8>>) -> Result<()>;
+}
+
+impl Storage {
+    pub fn with_cold_store(mut self, cold_store: Arc<dyn ObjectStore>) -> Self {
+        self.cold_store = Some(cold_store);
+        self
+    }
+
+    pub fn cold_store(&self) -> Option<Arc<dyn ObjectStore>> {
+        self.cold_store.clone()
+    }
+
+    /// Epoch of the first slice database key starting with `prefix`.
+    fn first_epoch_with_prefix(&self, prefix: &[u8]) -> Result<Option<u64>> {
+        match KeyValueDB::iter_with_prefix(&*self.db, COL_SLICE, prefix).next() {
+            Some(item) => {
+                let (key, _) = item?;
+                let epoch: [u8; 8] = key[1..9].try_into()?;
+                Ok(Some(u64::from_be_bytes(epoch)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) async fn get_tiered_value(&self, db_key: &[u8]) -> Result<Option<Vec<u8>>> {
+        if self.db.get(COL_TIERED_SLICE, db_key)?.is_none() {
+            return Ok(None);
+        }
+        match &self.cold_store {
+            Some(store) => store.get_object(&object_key(db_key)).await,
+            None => bail!(anyhow!(
+                "slice is tiered to cold storage but cold storage is not configured"
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl ColdStorageDB for Storage {
+    async fn get_tiered_epoch(&self) -> Result<Option<u64>> {
+        if let Some(raw_data) = self.db.get(COL_MISC, TIERED_EPOCH_KEY)? {
+            return Ok(Some(u64::from_be_bytes(raw_data.try_into().unwrap())));
+        }
+        Ok(None)
+    }
+
+    async fn put_tiered_epoch(&self, epoch: u64) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(COL_MISC, TIERED_EPOCH_KEY, &epoch.to_be_bytes());
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_first_local_epoch(&self) -> Result<Option<u64>> {
+        let mut first = None;
+        for prefix in [SLICE_PREFIX, DATA_PREFIX] {
+            if let Some(epoch) = self.first_epoch_with_prefix(&[prefix])? {
+                first = Some(first.map_or(epoch, |first: u64| first.min(epoch)));
+            }
+        }
+        let tiered_epoch = match self.get_tiered_epoch().await? {
+            Some(epoch) => epoch,
+            None => {
+                return Ok(match self.first_epoch_with_prefix(&[BLOB_PREFIX])? {
+                    Some(epoch) => Some(first.map_or(epoch, |first| first.min(epoch))),
+                    None => first,
+                })
+            }
+        };
+        // look for blob records epoch by epoch, skipping those of the tiered epochs
+        let last = match (first, self.get_latest_epoch().await?) {
+            (Some(first), _) => first,
+            (None, Some(latest)) => latest + 1,
+            (None, None) => return Ok(None),
+        };
+        for epoch in tiered_epoch + 1..last {
+            let key_prefix: Vec<u8> = std::iter::once(BLOB_PREFIX)
+                .chain(epoch.to_be_bytes())
+                .collect();
+            if self.first_epoch_with_prefix(&key_prefix)?.is_some() {
+                return Ok(Some(epoch));
+            }
+        }
+        Ok(first)
+    }
+
+    async fn next_tiering_batch(
+        &self,
+        epoch: u64,
+        limit: usize,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let mut batch = vec![];
+        for prefix in [SLICE_PREFIX, DATA_PREFIX] {
+            let key_prefix: Vec<u8> = std::iter::once(prefix).chain(epoch.to_be_bytes()).collect();
+            for item in KeyValueDB::iter_with_prefix(&*self.db, COL_SLICE, &key_prefix) {
+                if batch.len() >= limit {
+                    return Ok(batch);
+                }
+                let (key, value) = item?;
+                batch.push((key.to_vec(), value));
+            }
+        }
+        Ok(batch)
+    }
+
+    async fn mark_tiered(&self, keys: Vec<Vec<u8>>) -> Result<()> {
+        let indexes = keys
+            .iter()
+            .map(|key| SliceIndex::from_slice_key(key))
+            .collect::<Result<Vec<_>>>()?;
+        let _guards = self
+            .locks
+            .lock_many(indexes.iter().map(|x| (x.epoch, x.quorum_id)));
+        let mut tx = self.db.transaction();
+        let mut usage: BTreeMap<(u64, u64), UsageDelta> = BTreeMap::new();
+        for (key, index) in keys.iter().zip(indexes) {
+            if let Some(value) = self.db.get(COL_SLICE, key)? {
+                usage
+                    .entry((index.epoch, index.quorum_id))
+                    .or_default()
+                    .replace(key, Some(value.len()), None);
+            }
+            tx.put(COL_TIERED_SLICE, key, &[]);
+            tx.delete(COL_SLICE, key);
+        }
+        self.apply_slice_usage(&mut tx, usage)?;
+        self.db.write(tx)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{expiry_db::ExpiryDB, quorum_db::AssignedSlices, slice_db::SliceDB};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn tiering_round_trip_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = make_object_store(&ObjectStoreConfig::Local {
+            path: dir.path().join("store").display().to_string(),
+        })
+        .unwrap();
+        let db = Storage::new(dir.path().join("db"))
+            .unwrap()
+            .with_cold_store(store.clone());
+        let mut keys = BTreeMap::new();
+        let mut tx = db.db.transaction();
+        let mut usage = BTreeMap::new();
+        for epoch in [1, 2] {
+            let slice_key = SliceIndex {
+                epoch,
+                quorum_id: 0,
+                storage_root: [1; 32],
+                index: 0,
+            }
+            .to_slice_key();
+            let mut data_key = slice_key.clone();
+            data_key[0] = DATA_PREFIX;
+            for key in [&slice_key, &data_key] {
+                tx.put(COL_SLICE, key, &[epoch as u8; 10]);
+                usage
+                    .entry((epoch, 0))
+                    .or_insert_with(UsageDelta::default)
+                    .replace(key, None, Some(10));
+            }
+            let blob_key: Vec<u8> = std::iter::once(BLOB_PREFIX)
+                .chain(slice_key[1..49].iter().copied())
+                .collect();
+            tx.put(COL_SLICE, &blob_key, &bcs::to_bytes(&vec![0u16]).unwrap());
+            keys.insert(epoch, (slice_key, data_key));
+        }
+        db.apply_slice_usage(&mut tx, usage).unwrap();
+        db.db.write(tx).unwrap();
+        db.put_quorums(2, vec![AssignedSlices(vec![0])])
+            .await
+            .unwrap();
+        assert_eq!(db.get_first_local_epoch().await.unwrap(), Some(1));
+
+        let batch = db.next_tiering_batch(1, 10).await.unwrap();
+        assert_eq!(batch.len(), 2);
+        let mut tiered = vec![];
+        for (key, value) in batch {
+            store.put_object(&object_key(&key), value).await.unwrap();
+            tiered.push(key);
+        }
+        db.mark_tiered(tiered).await.unwrap();
+        db.put_tiered_epoch(1).await.unwrap();
+        // the blob record of epoch 1 stays local
+        assert_eq!(db.get_first_local_epoch().await.unwrap(), Some(2));
+        assert_eq!(
+            db.get_raw_slice(1, 0, [1; 32], 0).await.unwrap(),
+            Some(vec![1; 10])
+        );
+
+        // data records left of a partly tiered epoch keep it local
+        db.mark_tiered(vec![keys[&2].0.clone()]).await.unwrap();
+        assert_eq!(db.get_first_local_epoch().await.unwrap(), Some(2));
+
+        while db.delete_expired_slices(1, 0, 1).await.unwrap() > 0 {}
+        for key in [&keys[&1].0, &keys[&1].1] {
+            assert!(store.get_object(&object_key(key)).await.unwrap().is_none());
+        }
+        assert!(db.get_raw_slice(1, 0, [1; 32], 0).await.unwrap().is_none());
+    }
+}
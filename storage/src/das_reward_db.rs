@@ -0,0 +1,163 @@
+use std::iter::once;
+
+use crate::{COL_DAS_REWARD, COL_MISC};
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+use kvdb::KeyValueDB;
+use serde::{Deserialize, Serialize};
+
+const REWARD_PROGRESS_KEY: &[u8] = &[4];
+const SUBMISSION_PREFIX: u8 = 0;
+const REWARD_PREFIX: u8 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SubmissionStatus {
+    /// The transaction is sent and waiting for its receipt.
+    SUBMITTED,
+    CONFIRMED,
+    /// The transaction is not sent, reverted or dropped.
+    FAILED,
+}
+
+/// A sampling answer submitted to the `DASample` contract.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SampleSubmission {
+    pub sample_seed: [u8; 32],
+    pub epoch: u64,
+    pub quorum_id: u64,
+    pub data_root: [u8; 32],
+    pub line_index: u32,
+    pub subline_index: u32,
+    pub tx_hash: Option<[u8; 32]>,
+    pub status: SubmissionStatus,
+}
+
+/// A `DAReward` event paid to the miner.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DasReward {
+    pub sample_round: u64,
+    pub epoch: u64,
+    pub quorum_id: u64,
+    pub data_root: [u8; 32],
+    pub line_index: u64,
+    pub subline_index: u64,
+    pub reward: u128,
+    pub tx_hash: [u8; 32],
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DasAccounting {
+    pub submissions: u64,
+    pub confirmed: u64,
+    pub failed: u64,
+    pub rewards: u64,
+    pub total_reward: u128,
+}
+
+#[async_trait]
+pub trait DasRewardDB {
+    async fn put_sample_submission(&self, submission: &SampleSubmission) -> Result<()>;
+    async fn put_das_reward(&self, reward: &DasReward) -> Result<()>;
+    /// Accounting of `epoch`, or of all epochs if not given.
+    async fn get_das_accounting(&self, epoch: Option<u64>) -> Result<DasAccounting>;
+    async fn put_reward_progress(&self, block_number: u64) -> Result<()>;
+    async fn get_reward_progress(&self) -> Result<Option<u64>>;
+}
+
+fn get_submission_key(submission: &SampleSubmission) -> Vec<u8> {
+    once(SUBMISSION_PREFIX)
+        .chain(submission.epoch.to_be_bytes())
+        .chain(submission.quorum_id.to_be_bytes())
+        .chain(submission.data_root)
+        .chain(submission.line_index.to_be_bytes())
+        .chain(submission.subline_index.to_be_bytes())
+        .chain(submission.sample_seed)
+        .collect()
+}
+
+fn get_reward_key(reward: &DasReward) -> Vec<u8> {
+    once(REWARD_PREFIX)
+        .chain(reward.epoch.to_be_bytes())
+        .chain(reward.sample_round.to_be_bytes())
+        .chain(reward.quorum_id.to_be_bytes())
+        .chain(reward.data_root)
+        .chain(reward.line_index.to_be_bytes())
+        .chain(reward.subline_index.to_be_bytes())
+        .collect()
+}
+
+fn get_prefix(prefix: u8, epoch: Option<u64>) -> Vec<u8> {
+    once(prefix)
+        .chain(epoch.into_iter().flat_map(|epoch| epoch.to_be_bytes()))
+        .collect()
+}
+
+#[async_trait]
+impl DasRewardDB for Storage {
+    async fn put_sample_submission(&self, submission: &SampleSubmission) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(
+            COL_DAS_REWARD,
+            &get_submission_key(submission),
+            &bincode::serialize(submission)?,
+        );
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn put_das_reward(&self, reward: &DasReward) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(
+            COL_DAS_REWARD,
+            &get_reward_key(reward),
+            &bincode::serialize(reward)?,
+        );
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_das_accounting(&self, epoch: Option<u64>) -> Result<DasAccounting> {
+        let mut accounting = DasAccounting::default();
+        for item in KeyValueDB::iter_with_prefix(
+            &*self.db,
+            COL_DAS_REWARD,
+            &get_prefix(SUBMISSION_PREFIX, epoch),
+        ) {
+            let (_, value) = item?;
+            let submission: SampleSubmission = bincode::deserialize(&value)?;
+            accounting.submissions += 1;
+            match submission.status {
+                SubmissionStatus::CONFIRMED => accounting.confirmed += 1,
+                SubmissionStatus::FAILED => accounting.failed += 1,
+                SubmissionStatus::SUBMITTED => {}
+            }
+        }
+        for item in KeyValueDB::iter_with_prefix(
+            &*self.db,
+            COL_DAS_REWARD,
+            &get_prefix(REWARD_PREFIX, epoch),
+        ) {
+            let (_, value) = item?;
+            let reward: DasReward = bincode::deserialize(&value)?;
+            accounting.rewards += 1;
+            accounting.total_reward = accounting.total_reward.saturating_add(reward.reward);
+        }
+        Ok(accounting)
+    }
+
+    async fn put_reward_progress(&self, block_number: u64) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(COL_MISC, REWARD_PROGRESS_KEY, &block_number.to_be_bytes());
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_reward_progress(&self) -> Result<Option<u64>> {
+        if let Some(raw_data) = self.db.get(COL_MISC, REWARD_PROGRESS_KEY)? {
+            return Ok(Some(u64::from_be_bytes(raw_data.try_into().unwrap())));
+        }
+        Ok(None)
+    }
+}
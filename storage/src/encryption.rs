@@ -0,0 +1,428 @@
+use std::{collections::HashMap, iter::once, str::FromStr};
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use kvdb::KeyValueDB;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    slice_db::{DATA_PREFIX, SLICE_PREFIX},
+    COL_MISC, COL_SLICE,
+};
+
+use super::Storage;
+
+const ENCRYPTED_KEY: &[u8] = &[2];
+const REENCRYPTION_CURSOR_KEY: &[u8] = &[10];
+const NONCE_LEN: usize = 12;
+
+#[derive(Clone)]
+pub enum KeySource {
+    /// The key itself, hex encoded for encryption keys.
+    Hex(String),
+    /// A file containing the key.
+    File(String),
+    /// An environment variable holding the key.
+    Env(String),
+    /// A shell command printing the key, e.g. a KMS call decrypting a wrapped data key.
+    Command(String),
+}
+
+impl FromStr for KeySource {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(if let Some(path) = s.strip_prefix("file:") {
+            KeySource::File(path.to_string())
+        } else if let Some(var) = s.strip_prefix("env:") {
+            KeySource::Env(var.to_string())
+        } else if let Some(command) = s.strip_prefix("cmd:") {
+            KeySource::Command(command.to_string())
+        } else {
+            KeySource::Hex(s.to_string())
+        })
+    }
+}
+
+impl KeySource {
+    /// The key as read from its source, surrounding whitespace removed.
+    pub fn read(&self) -> Result<String> {
+        let key = match self {
+            KeySource::Hex(key) => key.clone(),
+            KeySource::File(path) => std::fs::read_to_string(path)?,
+            KeySource::Env(var) => std::env::var(var)
+                .map_err(|e| anyhow!("cannot read environment variable {}: {:?}", var, e))?,
+            KeySource::Command(command) => {
+                let output = std::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(command)
+                    .output()?;
+                if !output.status.success() {
+                    bail!(anyhow!(
+                        "key command exited with {}: {}",
+                        output.status,
+                        String::from_utf8_lossy(&output.stderr)
+                    ));
+                }
+                String::from_utf8(output.stdout)?
+            }
+        };
+        Ok(key.trim().to_string())
+    }
+
+    fn load(&self) -> Result<[u8; 32]> {
+        let key = hex::decode(self.read()?.trim_start_matches("0x"))?;
+        key.try_into()
+            .map_err(|_| anyhow!("encryption key must be 32 bytes"))
+    }
+}
+
+#[derive(Clone)]
+pub struct EncryptionConfig {
+    /// Key used to encrypt new values.
+    pub active_key_id: u8,
+    /// All keys still referenced by stored values, including retired ones.
+    pub keys: Vec<(u8, KeySource)>,
+}
+
+/// AES-256-GCM keys indexed by id. Every encrypted value is stored as
+/// `key id || nonce || ciphertext`, with the database key as associated data.
+pub struct Keyring {
+    active_key_id: u8,
+    ciphers: HashMap<u8, Aes256Gcm>,
+}
+
+impl Keyring {
+    pub fn new(active_key_id: u8, keys: Vec<(u8, [u8; 32])>) -> Result<Self> {
+        let ciphers: HashMap<u8, Aes256Gcm> = keys
+            .into_iter()
+            .map(|(id, key)| (id, Aes256Gcm::new(&key.into())))
+            .collect();
+        if !ciphers.contains_key(&active_key_id) {
+            bail!(anyhow!(
+                "active encryption key {} is missing",
+                active_key_id
+            ));
+        }
+        Ok(Self {
+            active_key_id,
+            ciphers,
+        })
+    }
+
+    pub fn load(config: &EncryptionConfig) -> Result<Self> {
+        let mut keys = vec![];
+        for (id, source) in config.keys.iter() {
+            keys.push((
+                *id,
+                source
+                    .load()
+                    .map_err(|e| anyhow!("cannot load encryption key {}: {:?}", id, e))?,
+            ));
+        }
+        Self::new(config.active_key_id, keys)
+    }
+
+    pub fn active_key_id(&self) -> u8 {
+        self.active_key_id
+    }
+
+    pub fn encrypt(&self, db_key: &[u8], value: &[u8]) -> Result<Vec<u8>> {
+        let cipher = &self.ciphers[&self.active_key_id];
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: value,
+                    aad: db_key,
+                },
+            )
+            .map_err(|_| anyhow!("encryption failed"))?;
+        Ok(once(self.active_key_id)
+            .chain(nonce)
+            .chain(ciphertext)
+            .collect())
+    }
+
+    pub fn decrypt(&self, db_key: &[u8], value: &[u8]) -> Result<Vec<u8>> {
+        if value.len() < 1 + NONCE_LEN {
+            bail!(anyhow!("encrypted value too short"));
+        }
+        let cipher = self
+            .ciphers
+            .get(&value[0])
+            .ok_or_else(|| anyhow!("unknown encryption key {}", value[0]))?;
+        cipher
+            .decrypt(
+                Nonce::from_slice(&value[1..1 + NONCE_LEN]),
+                Payload {
+                    msg: &value[1 + NONCE_LEN..],
+                    aad: db_key,
+                },
+            )
+            .map_err(|_| anyhow!("cannot decrypt value, data corrupted or wrong key"))
+    }
+}
+
+/// Progress of the re-encryption to a key, values before it are already rewritten.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ReencryptionCursor {
+    key_id: u8,
+    epoch: u64,
+    /// Last rewritten database key of the epoch, empty before the first one.
+    last_key: Vec<u8>,
+}
+
+#[async_trait]
+pub trait EncryptionDB {
+    /// Re-encrypt slice values of `epoch` stored with a retired key in transactions of
+    /// `batch_size` values, returns the number of rewritten values. The cursor is written with
+    /// every batch, an interrupted epoch resumes after the last rewritten value.
+    async fn reencrypt_epoch(&self, epoch: u64, batch_size: usize) -> Result<usize>;
+
+    /// Epoch the re-encryption to the active key resumes from, `None` if it has not started.
+    async fn get_reencryption_epoch(&self) -> Result<Option<u64>>;
+}
+
+impl Storage {
+    /// Enable encryption of slice values. Encryption can only be turned on for an empty
+    /// database, and a database once encrypted cannot be opened without a keyring.
+    pub fn with_encryption(mut self, keyring: Option<Keyring>) -> Result<Self> {
+        let encrypted = self.db.get(COL_MISC, ENCRYPTED_KEY)?.is_some();
+        match (&keyring, encrypted) {
+            (Some(_), false) => {
+                if KeyValueDB::iter(&*self.db, COL_SLICE).next().is_some() {
+                    bail!(anyhow!(
+                        "encryption cannot be enabled on a database with unencrypted slices"
+                    ));
+                }
+                let mut tx = self.db.transaction();
+                tx.put(COL_MISC, ENCRYPTED_KEY, &[1]);
+                self.db.write(tx)?;
+            }
+            (None, true) => bail!(anyhow!(
+                "database is encrypted but no encryption key is configured"
+            )),
+            _ => {}
+        }
+        self.keyring = keyring;
+        Ok(self)
+    }
+
+    pub(crate) fn encrypt_value(&self, db_key: &[u8], value: Vec<u8>) -> Result<Vec<u8>> {
+        match &self.keyring {
+            Some(keyring) => keyring.encrypt(db_key, &value),
+            None => Ok(value),
+        }
+    }
+
+    pub(crate) fn decrypt_value(&self, db_key: &[u8], value: Vec<u8>) -> Result<Vec<u8>> {
+        match &self.keyring {
+            Some(keyring) => keyring.decrypt(db_key, &value),
+            None => Ok(value),
+        }
+    }
+
+    fn get_reencryption_cursor(&self) -> Result<Option<ReencryptionCursor>> {
+        match self.db.get(COL_MISC, REENCRYPTION_CURSOR_KEY)? {
+            Some(raw_data) => Ok(Some(bincode::deserialize(&raw_data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Re-encrypt a batch of values and write them with the cursor after them, returns the number
+    /// of rewritten values. Each value is read again under the shard lock of its quorum, so a
+    /// record deleted or tiered since the scan is not written back.
+    fn reencrypt_batch(
+        &self,
+        keyring: &Keyring,
+        epoch: u64,
+        keys: &[Vec<u8>],
+        cursor: &ReencryptionCursor,
+    ) -> Result<usize> {
+        // slice and data keys both start with prefix, epoch and quorum id
+        let _guards = self.locks.lock_many(keys.iter().map(|key| {
+            let quorum_id = u64::from_be_bytes(key[9..17].try_into().unwrap());
+            (epoch, quorum_id)
+        }));
+        let mut tx = self.db.transaction();
+        let mut rewritten = 0;
+        for key in keys {
+            match self.db.get(COL_SLICE, key)? {
+                Some(value) if value.first() != Some(&keyring.active_key_id) => {
+                    let plain = keyring.decrypt(key, &value)?;
+                    tx.put(COL_SLICE, key, &keyring.encrypt(key, &plain)?);
+                    rewritten += 1;
+                }
+                _ => {}
+            }
+        }
+        tx.put(
+            COL_MISC,
+            REENCRYPTION_CURSOR_KEY,
+            &bincode::serialize(cursor)?,
+        );
+        self.db.write(tx)?;
+        Ok(rewritten)
+    }
+}
+
+#[async_trait]
+impl EncryptionDB for Storage {
+    async fn reencrypt_epoch(&self, epoch: u64, batch_size: usize) -> Result<usize> {
+        let keyring = match &self.keyring {
+            Some(keyring) => keyring,
+            None => return Ok(0),
+        };
+        let resume_after = match self.get_reencryption_cursor()? {
+            Some(cursor) if cursor.key_id == keyring.active_key_id && cursor.epoch == epoch => {
+                cursor.last_key
+            }
+            _ => vec![],
+        };
+        let mut keys = vec![];
+        let mut rewritten = 0;
+        // slice keys of the epoch are iterated in ascending order, before its data keys
+        for prefix in [SLICE_PREFIX, DATA_PREFIX] {
+            let key_prefix: Vec<u8> = once(prefix).chain(epoch.to_be_bytes()).collect();
+            for item in KeyValueDB::iter_with_prefix(&*self.db, COL_SLICE, &key_prefix) {
+                let (key, value) = item?;
+                if key[..] <= resume_after[..] || value.first() == Some(&keyring.active_key_id) {
+                    continue;
+                }
+                keys.push(key.to_vec());
+                if keys.len() == batch_size {
+                    let cursor = ReencryptionCursor {
+                        key_id: keyring.active_key_id,
+                        epoch,
+                        last_key: key.to_vec(),
+                    };
+                    rewritten += self.reencrypt_batch(keyring, epoch, &keys, &cursor)?;
+                    keys.clear();
+                }
+            }
+        }
+        let cursor = ReencryptionCursor {
+            key_id: keyring.active_key_id,
+            epoch: epoch + 1,
+            last_key: vec![],
+        };
+        rewritten += self.reencrypt_batch(keyring, epoch, &keys, &cursor)?;
+        Ok(rewritten)
+    }
+
+    async fn get_reencryption_epoch(&self) -> Result<Option<u64>> {
+        let active_key_id = match &self.keyring {
+            Some(keyring) => keyring.active_key_id,
+            None => return Ok(None),
+        };
+        Ok(self
+            .get_reencryption_cursor()?
+            .filter(|cursor| cursor.key_id == active_key_id)
+            .map(|cursor| cursor.epoch))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keyring_rotation_test() {
+        let old = Keyring::new(1, vec![(1, [1u8; 32])]).unwrap();
+        let encrypted = old.encrypt(b"key", b"value").unwrap();
+        assert_eq!(encrypted[0], 1);
+        assert!(old.decrypt(b"other key", &encrypted).is_err());
+
+        let new = Keyring::new(2, vec![(1, [1u8; 32]), (2, [2u8; 32])]).unwrap();
+        assert_eq!(new.decrypt(b"key", &encrypted).unwrap(), b"value");
+        let reencrypted = new.encrypt(b"key", b"value").unwrap();
+        assert_eq!(reencrypted[0], 2);
+        assert!(old.decrypt(b"key", &reencrypted).is_err());
+        assert!(Keyring::new(3, vec![(1, [1u8; 32])]).is_err());
+    }
+
+    #[tokio::test]
+    async fn reencrypt_epoch_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut db = Storage::new(dir.path())
+            .unwrap()
+            .with_encryption(Some(Keyring::new(1, vec![(1, [1u8; 32])]).unwrap()))
+            .unwrap();
+        let keys: Vec<Vec<u8>> = [SLICE_PREFIX, SLICE_PREFIX, SLICE_PREFIX, DATA_PREFIX]
+            .iter()
+            .enumerate()
+            .map(|(i, prefix)| {
+                once(*prefix)
+                    .chain(1u64.to_be_bytes())
+                    .chain(0u64.to_be_bytes())
+                    .chain([i as u8])
+                    .collect()
+            })
+            .collect();
+        let mut tx = db.db.transaction();
+        for key in keys.iter() {
+            tx.put(COL_SLICE, key, &db.encrypt_value(key, key.clone()).unwrap());
+        }
+        db.db.write(tx).unwrap();
+
+        db.keyring = Some(Keyring::new(2, vec![(1, [1u8; 32]), (2, [2u8; 32])]).unwrap());
+        assert_eq!(db.get_reencryption_epoch().await.unwrap(), None);
+        // resume an interrupted run after the first value
+        let cursor = ReencryptionCursor {
+            key_id: 2,
+            epoch: 1,
+            last_key: keys[0].clone(),
+        };
+        let mut tx = db.db.transaction();
+        tx.put(
+            COL_MISC,
+            REENCRYPTION_CURSOR_KEY,
+            &bincode::serialize(&cursor).unwrap(),
+        );
+        db.db.write(tx).unwrap();
+        assert_eq!(db.reencrypt_epoch(1, 2).await.unwrap(), 3);
+        assert_eq!(db.get_reencryption_epoch().await.unwrap(), Some(2));
+        let key_ids: Vec<u8> = keys
+            .iter()
+            .map(|key| db.db.get(COL_SLICE, key).unwrap().unwrap()[0])
+            .collect();
+        assert_eq!(key_ids, vec![1, 2, 2, 2]);
+
+        assert_eq!(db.reencrypt_epoch(1, 2).await.unwrap(), 1);
+        for key in keys.iter() {
+            let value = db.db.get(COL_SLICE, key).unwrap().unwrap();
+            assert_eq!(value[0], 2);
+            assert_eq!(&db.decrypt_value(key, value).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn key_source_test() {
+        std::env::set_var("KEY_SOURCE_TEST_KEY", " 123\n");
+        assert_eq!(
+            KeySource::from_str("env:KEY_SOURCE_TEST_KEY")
+                .unwrap()
+                .read()
+                .unwrap(),
+            "123"
+        );
+        assert_eq!(
+            KeySource::from_str("cmd:echo 0x01")
+                .unwrap()
+                .read()
+                .unwrap(),
+            "0x01"
+        );
+        assert_eq!(KeySource::from_str("42").unwrap().read().unwrap(), "42");
+        assert!(KeySource::from_str("env:KEY_SOURCE_TEST_MISSING")
+            .unwrap()
+            .read()
+            .is_err());
+    }
+}
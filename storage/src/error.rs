@@ -0,0 +1,96 @@
+use std::{fmt, io};
+
+/// Messages of RocksDB statuses caused by temporary resource limits, which may succeed when retried.
+const TRANSIENT_MESSAGES: [&str; 7] = [
+    "Resource busy",
+    "Operation timed out",
+    "Try again",
+    "Result incomplete",
+    "Memory limit reached",
+    "Space limit reached",
+    "No space left on device",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageErrorKind {
+    /// A temporary resource limit, the operation may succeed when retried.
+    Transient,
+    /// Corruption or another failure that retrying does not fix.
+    Permanent,
+}
+
+/// A database write or read failure, classified by whether it is worth retrying.
+#[derive(Debug)]
+pub struct StorageError {
+    pub kind: StorageErrorKind,
+    pub source: io::Error,
+}
+
+impl StorageError {
+    /// Kind of a storage error, errors not raised by the database are permanent.
+    pub fn kind_of(e: &anyhow::Error) -> StorageErrorKind {
+        e.downcast_ref::<StorageError>()
+            .map_or(StorageErrorKind::Permanent, |e| e.kind)
+    }
+}
+
+impl From<io::Error> for StorageError {
+    fn from(source: io::Error) -> Self {
+        let message = source.to_string();
+        let transient = matches!(
+            source.kind(),
+            io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+        ) || TRANSIENT_MESSAGES.iter().any(|x| message.contains(x));
+        Self {
+            kind: if transient {
+                StorageErrorKind::Transient
+            } else {
+                StorageErrorKind::Permanent
+            },
+            source,
+        }
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} storage error: {}", self.kind, self.source)
+    }
+}
+
+impl std::error::Error for StorageError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_error_kind_test() {
+        let error = |message: &str| {
+            anyhow::Error::from(StorageError::from(io::Error::new(
+                io::ErrorKind::Other,
+                message,
+            )))
+        };
+        assert_eq!(
+            StorageError::kind_of(&error("Resource busy: ")),
+            StorageErrorKind::Transient
+        );
+        assert_eq!(
+            StorageError::kind_of(&error("IO error: No space left on device")),
+            StorageErrorKind::Transient
+        );
+        assert_eq!(
+            StorageError::kind_of(&error("Corruption: block checksum mismatch")),
+            StorageErrorKind::Permanent
+        );
+        assert_eq!(
+            StorageError::kind_of(&anyhow::anyhow!("Resource busy")),
+            StorageErrorKind::Permanent
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use std::iter::once;
+
+use crate::COL_CHAIN_EVENT;
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+use kvdb::KeyValueDB;
+use serde::{Deserialize, Serialize};
+
+const EVENT_PREFIX: u8 = 0;
+const EPOCH_INDEX_PREFIX: u8 = 1;
+const DATA_ROOT_INDEX_PREFIX: u8 = 2;
+
+/// A decoded `DAEntrance` event, amounts in wei.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ChainEventKind {
+    DataUpload {
+        sender: [u8; 20],
+        data_root: [u8; 32],
+        epoch: u64,
+        quorum_id: u64,
+        blob_price: u128,
+    },
+    ErasureCommitmentVerified {
+        data_root: [u8; 32],
+        epoch: u64,
+        quorum_id: u64,
+    },
+    NewSampleRound {
+        sample_round: u64,
+        sample_height: u64,
+        sample_seed: [u8; 32],
+        /// Big endian.
+        podas_target: [u8; 32],
+    },
+    DasReward {
+        beneficiary: [u8; 20],
+        sample_round: u64,
+        epoch: u64,
+        quorum_id: u64,
+        data_root: [u8; 32],
+        /// Big endian.
+        quality: [u8; 32],
+        line_index: u64,
+        subline_index: u64,
+        reward: u128,
+    },
+}
+
+/// An event of the indexed contract, with where it was emitted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChainEvent {
+    pub block_number: u64,
+    pub log_index: u64,
+    pub tx_hash: [u8; 32],
+    pub kind: ChainEventKind,
+}
+
+impl ChainEvent {
+    pub fn epoch(&self) -> Option<u64> {
+        match self.kind {
+            ChainEventKind::DataUpload { epoch, .. }
+            | ChainEventKind::ErasureCommitmentVerified { epoch, .. }
+            | ChainEventKind::DasReward { epoch, .. } => Some(epoch),
+            ChainEventKind::NewSampleRound { .. } => None,
+        }
+    }
+
+    pub fn data_root(&self) -> Option<[u8; 32]> {
+        match self.kind {
+            ChainEventKind::DataUpload { data_root, .. }
+            | ChainEventKind::ErasureCommitmentVerified { data_root, .. }
+            | ChainEventKind::DasReward { data_root, .. } => Some(data_root),
+            ChainEventKind::NewSampleRound { .. } => None,
+        }
+    }
+
+    /// Position of the event on chain, events are ordered by it in every index.
+    fn position(&self) -> impl Iterator<Item = u8> {
+        self.block_number
+            .to_be_bytes()
+            .into_iter()
+            .chain(self.log_index.to_be_bytes())
+    }
+}
+
+#[async_trait]
+pub trait ChainEventDB {
+    /// Store events and their indexes, events stored again are overwritten.
+    async fn put_chain_events(&self, events: &[ChainEvent]) -> Result<()>;
+    /// Events of the blocks `from..=to` in chain order, at most `limit`.
+    async fn get_chain_events(&self, from: u64, to: u64, limit: usize) -> Result<Vec<ChainEvent>>;
+    /// Events of an epoch in chain order, at most `limit`.
+    async fn get_chain_events_by_epoch(&self, epoch: u64, limit: usize) -> Result<Vec<ChainEvent>>;
+    /// Events of a data root in chain order, at most `limit`.
+    async fn get_chain_events_by_data_root(
+        &self,
+        data_root: [u8; 32],
+        limit: usize,
+    ) -> Result<Vec<ChainEvent>>;
+}
+
+fn get_event_key(position: impl Iterator<Item = u8>) -> Vec<u8> {
+    once(EVENT_PREFIX).chain(position).collect()
+}
+
+impl Storage {
+    /// Events whose position follows `prefix` in the keys of an index.
+    fn get_indexed_events(&self, prefix: &[u8], limit: usize) -> Result<Vec<ChainEvent>> {
+        let mut events = vec![];
+        for item in KeyValueDB::iter_with_prefix(&*self.db, COL_CHAIN_EVENT, prefix).take(limit) {
+            let (key, _) = item?;
+            let key = get_event_key(key[prefix.len()..].iter().copied());
+            if let Some(value) = self.db.get(COL_CHAIN_EVENT, &key)? {
+                events.push(bincode::deserialize(&value)?);
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl ChainEventDB for Storage {
+    async fn put_chain_events(&self, events: &[ChainEvent]) -> Result<()> {
+        let mut tx = self.db.transaction();
+        for event in events {
+            tx.put(
+                COL_CHAIN_EVENT,
+                &get_event_key(event.position()),
+                &bincode::serialize(event)?,
+            );
+            if let Some(epoch) = event.epoch() {
+                let key: Vec<u8> = once(EPOCH_INDEX_PREFIX)
+                    .chain(epoch.to_be_bytes())
+                    .chain(event.position())
+                    .collect();
+                tx.put(COL_CHAIN_EVENT, &key, &[]);
+            }
+            if let Some(data_root) = event.data_root() {
+                let key: Vec<u8> = once(DATA_ROOT_INDEX_PREFIX)
+                    .chain(data_root)
+                    .chain(event.position())
+                    .collect();
+                tx.put(COL_CHAIN_EVENT, &key, &[]);
+            }
+        }
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_chain_events(&self, from: u64, to: u64, limit: usize) -> Result<Vec<ChainEvent>> {
+        let mut events = vec![];
+        for item in KeyValueDB::iter_with_prefix(&*self.db, COL_CHAIN_EVENT, &[EVENT_PREFIX]) {
+            let (key, value) = item?;
+            let block_number = u64::from_be_bytes(key[1..9].try_into()?);
+            if block_number < from {
+                continue;
+            }
+            if block_number > to || events.len() >= limit {
+                break;
+            }
+            events.push(bincode::deserialize(&value)?);
+        }
+        Ok(events)
+    }
+
+    async fn get_chain_events_by_epoch(&self, epoch: u64, limit: usize) -> Result<Vec<ChainEvent>> {
+        let prefix: Vec<u8> = once(EPOCH_INDEX_PREFIX)
+            .chain(epoch.to_be_bytes())
+            .collect();
+        self.get_indexed_events(&prefix, limit)
+    }
+
+    async fn get_chain_events_by_data_root(
+        &self,
+        data_root: [u8; 32],
+        limit: usize,
+    ) -> Result<Vec<ChainEvent>> {
+        let prefix: Vec<u8> = once(DATA_ROOT_INDEX_PREFIX).chain(data_root).collect();
+        self.get_indexed_events(&prefix, limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upload(block_number: u64, epoch: u64, data_root: [u8; 32]) -> ChainEvent {
+        ChainEvent {
+            block_number,
+            log_index: 0,
+            tx_hash: [0; 32],
+            kind: ChainEventKind::DataUpload {
+                sender: [0; 20],
+                data_root,
+                epoch,
+                quorum_id: 0,
+                blob_price: 1,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn query_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Storage::new(dir.path()).unwrap();
+        let verified = ChainEvent {
+            block_number: 300,
+            log_index: 2,
+            tx_hash: [0; 32],
+            kind: ChainEventKind::ErasureCommitmentVerified {
+                data_root: [1; 32],
+                epoch: 1,
+                quorum_id: 0,
+            },
+        };
+        db.put_chain_events(&[
+            upload(300, 2, [2; 32]),
+            upload(100, 1, [1; 32]),
+            verified.clone(),
+        ])
+        .await
+        .unwrap();
+
+        let events = db.get_chain_events(100, 299, 10).await.unwrap();
+        assert_eq!(events, vec![upload(100, 1, [1; 32])]);
+        assert_eq!(db.get_chain_events(0, 1000, 2).await.unwrap().len(), 2);
+        assert_eq!(
+            db.get_chain_events_by_epoch(1, 10).await.unwrap(),
+            vec![upload(100, 1, [1; 32]), verified.clone()]
+        );
+        assert_eq!(
+            db.get_chain_events_by_data_root([1; 32], 10).await.unwrap(),
+            vec![upload(100, 1, [1; 32]), verified]
+        );
+        assert!(db
+            .get_chain_events_by_data_root([3; 32], 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,188 @@
+use std::{collections::BTreeMap, iter::once};
+
+use crate::{
+    cold_storage::object_key,
+    slice_db::{BLOB_PREFIX, DATA_PREFIX, SLICE_PREFIX},
+    usage_db::UsageDelta,
+    COL_CORRUPT_SLICE, COL_MISC, COL_OPENING_PROOF, COL_SLICE, COL_TIERED_SLICE,
+};
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+use kvdb::KeyValueDB;
+
+const EXPIRED_EPOCH_KEY: &[u8] = &[9];
+
+#[async_trait]
+pub trait ExpiryDB {
+    /// Epochs before it are expired on chain and their slices deleted.
+    async fn get_expired_epoch(&self) -> Result<Option<u64>>;
+
+    async fn put_expired_epoch(&self, epoch: u64) -> Result<()>;
+
+    /// Delete at most `limit` slice records of a quorum in an expired epoch, with their opening
+    /// proofs and tiered and corrupt marks. Returns the number of records deleted, 0 once the
+    /// quorum is empty. Objects of tiered slices are deleted from the cold store first.
+    async fn delete_expired_slices(&self, epoch: u64, quorum_id: u64, limit: usize) -> Result<u64>;
+}
+
+/// `epoch ++ quorum_id`, the start of every per slice key of the quorum.
+fn quorum_key(epoch: u64, quorum_id: u64) -> Vec<u8> {
+    epoch
+        .to_be_bytes()
+        .into_iter()
+        .chain(quorum_id.to_be_bytes())
+        .collect()
+}
+
+#[async_trait]
+impl ExpiryDB for Storage {
+    async fn get_expired_epoch(&self) -> Result<Option<u64>> {
+        if let Some(raw_data) = self.db.get(COL_MISC, EXPIRED_EPOCH_KEY)? {
+            return Ok(Some(u64::from_be_bytes(raw_data.try_into().unwrap())));
+        }
+        Ok(None)
+    }
+
+    async fn put_expired_epoch(&self, epoch: u64) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(COL_MISC, EXPIRED_EPOCH_KEY, &epoch.to_be_bytes());
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn delete_expired_slices(&self, epoch: u64, quorum_id: u64, limit: usize) -> Result<u64> {
+        let suffix = quorum_key(epoch, quorum_id);
+        // the tiered marks deleted below are among the first `limit` ones, their objects go first
+        // so none is left behind if the node stops in between
+        if let Some(store) = &self.cold_store {
+            let mut tiered = vec![];
+            for prefix in [SLICE_PREFIX, DATA_PREFIX] {
+                let prefix: Vec<u8> = once(prefix).chain(suffix.iter().copied()).collect();
+                for item in KeyValueDB::iter_with_prefix(&*self.db, COL_TIERED_SLICE, &prefix)
+                    .take(limit - tiered.len())
+                {
+                    let (key, _) = item?;
+                    tiered.push(key);
+                }
+            }
+            for key in tiered {
+                store.delete_object(&object_key(&key)).await?;
+            }
+        }
+
+        let _guard = self.locks.lock(epoch, quorum_id);
+        let mut tx = self.db.transaction();
+        let mut usage = UsageDelta::default();
+        let mut deleted = 0;
+        for prefix in [SLICE_PREFIX, DATA_PREFIX, BLOB_PREFIX] {
+            let prefix: Vec<u8> = once(prefix).chain(suffix.iter().copied()).collect();
+            for item in
+                KeyValueDB::iter_with_prefix(&*self.db, COL_SLICE, &prefix).take(limit - deleted)
+            {
+                let (key, value) = item?;
+                if key[0] != BLOB_PREFIX {
+                    usage.replace(&key, Some(value.len()), None);
+                }
+                tx.delete(COL_SLICE, &key);
+                deleted += 1;
+            }
+            // tiered and corrupt marks are keyed as the slice records
+            for col in [COL_TIERED_SLICE, COL_CORRUPT_SLICE] {
+                for item in
+                    KeyValueDB::iter_with_prefix(&*self.db, col, &prefix).take(limit - deleted)
+                {
+                    let (key, _) = item?;
+                    tx.delete(col, &key);
+                    deleted += 1;
+                }
+            }
+        }
+        for item in KeyValueDB::iter_with_prefix(&*self.db, COL_OPENING_PROOF, &suffix)
+            .take(limit - deleted)
+        {
+            let (key, _) = item?;
+            tx.delete(COL_OPENING_PROOF, &key);
+            deleted += 1;
+        }
+        self.apply_slice_usage(&mut tx, BTreeMap::from([((epoch, quorum_id), usage)]))?;
+        self.db.write(tx)?;
+        Ok(deleted as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        opening_proof_db::OpeningProofDB,
+        slice_db::{SliceDB, SliceIndex},
+        usage_db::UsageDB,
+    };
+
+    use super::*;
+
+    #[tokio::test]
+    async fn delete_expired_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Storage::new(dir.path()).unwrap();
+        let mut tx = db.db.transaction();
+        let mut usage = BTreeMap::new();
+        for (epoch, quorum_id) in [(1, 0), (1, 1)] {
+            for index in 0..3u64 {
+                let key = SliceIndex {
+                    epoch,
+                    quorum_id,
+                    storage_root: [1; 32],
+                    index,
+                }
+                .to_slice_key();
+                tx.put(COL_SLICE, &key, &[1; 10]);
+                usage
+                    .entry((epoch, quorum_id))
+                    .or_insert_with(UsageDelta::default)
+                    .replace(&key, None, Some(10));
+            }
+            let blob_key: Vec<u8> = once(BLOB_PREFIX)
+                .chain(quorum_key(epoch, quorum_id))
+                .chain([1; 32])
+                .collect();
+            tx.put(
+                COL_SLICE,
+                &blob_key,
+                &bcs::to_bytes(&vec![0u16, 1, 2]).unwrap(),
+            );
+            db.put_opening_proofs(epoch, quorum_id, [1; 32], vec![(0, vec![6; 8])])
+                .await
+                .unwrap();
+        }
+        db.apply_slice_usage(&mut tx, usage).unwrap();
+        db.db.write(tx).unwrap();
+
+        // 3 slices, the blob record and the opening proof
+        assert_eq!(db.delete_expired_slices(1, 0, 3).await.unwrap(), 3);
+        assert_eq!(db.delete_expired_slices(1, 0, 3).await.unwrap(), 2);
+        assert_eq!(db.delete_expired_slices(1, 0, 3).await.unwrap(), 0);
+        let usage = db.get_slice_usage(Some(1)).await.unwrap();
+        assert_eq!(usage[&(1, 0)].slices, 0);
+        assert_eq!(usage[&(1, 1)].slices, 3);
+        let blobs = db.get_epoch_info(1).await.unwrap();
+        assert_eq!(
+            blobs.iter().map(|x| x.quorum_id).collect::<Vec<_>>(),
+            vec![1]
+        );
+        assert!(db
+            .get_opening_proof(1, 0, [1; 32], 0)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(db
+            .get_opening_proof(1, 1, [1; 32], 0)
+            .await
+            .unwrap()
+            .is_some());
+
+        db.put_expired_epoch(2).await.unwrap();
+        assert_eq!(db.get_expired_epoch().await.unwrap(), Some(2));
+    }
+}
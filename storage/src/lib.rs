@@ -1,29 +1,125 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
 
 use anyhow::Result;
+use cold_storage::ObjectStore;
+use encryption::Keyring;
 use kvdb_rocksdb::{Database, DatabaseConfig};
+use shard_lock::ShardLocks;
 
 pub mod blob_status_db;
+pub mod cold_storage;
+pub mod das_reward_db;
+pub mod encryption;
+pub mod error;
+pub mod event_db;
+pub mod expiry_db;
 pub mod misc_db;
+pub mod opening_proof_db;
+pub mod outbox_db;
 pub mod quorum_db;
+pub mod reconcile_db;
+pub mod registration_db;
+pub mod schema;
+pub mod scrub_db;
+mod shard_lock;
+pub mod sign_outcome_db;
+pub mod sign_quota_db;
 pub mod slice_db;
+pub mod tx_history_db;
+pub mod usage_db;
 
-pub const COL_NUM: u32 = 5;
+pub const COL_NUM: u32 = 17;
 pub const COL_MISC: u32 = 0;
 pub const COL_SLICE: u32 = 1;
 pub const COL_QUORUM: u32 = 2;
 pub const COL_QUORUM_NUM: u32 = 3;
 pub const COL_BLOB_STATUS: u32 = 4;
+pub const COL_TIERED_SLICE: u32 = 5;
+pub const COL_CORRUPT_SLICE: u32 = 6;
+pub const COL_SIGN_OUTCOME: u32 = 7;
+pub const COL_DAS_REWARD: u32 = 8;
+pub const COL_TX_HISTORY: u32 = 9;
+pub const COL_REGISTRATION: u32 = 10;
+pub const COL_SIGN_QUOTA: u32 = 11;
+pub const COL_SIGNATURE: u32 = 12;
+pub const COL_SLICE_USAGE: u32 = 13;
+pub const COL_OPENING_PROOF: u32 = 14;
+pub const COL_CHAIN_EVENT: u32 = 15;
+pub const COL_OUTBOX: u32 = 16;
+
+/// Keys and bytes stored in a column of the database.
+#[derive(Debug, Default)]
+pub struct ColumnStats {
+    pub keys: u64,
+    pub bytes: u64,
+}
 
 pub struct Storage {
     db: Arc<Database>,
+    cold_store: Option<Arc<dyn ObjectStore>>,
+    keyring: Option<Keyring>,
+    path: PathBuf,
+    /// Serializes the updates of the slice records and usage of a quorum.
+    locks: Arc<ShardLocks>,
+    /// Sequence number of the next outbox message, read from the outbox on the first push.
+    outbox_seq: Arc<Mutex<Option<u64>>>,
 }
 
 impl Storage {
     pub fn new(path: impl AsRef<Path>) -> Result<Self> {
         let mut db_config = DatabaseConfig::with_columns(COL_NUM);
         db_config.enable_statistics = true;
-        let db = Arc::new(Database::open(&db_config, path)?);
-        Ok(Storage { db })
+        let (db, columns) = match Database::open(&db_config, path.as_ref()) {
+            Ok(db) => (db, COL_NUM),
+            Err(e) => Self::open_with_fewer_columns(db_config, path.as_ref()).map_err(|_| e)?,
+        };
+        let storage = Storage {
+            db: Arc::new(db),
+            cold_store: None,
+            keyring: None,
+            path: path.as_ref().to_path_buf(),
+            locks: Arc::new(ShardLocks::new()),
+            outbox_seq: Default::default(),
+        };
+        if columns <= COL_SLICE_USAGE {
+            storage.rebuild_slice_usage()?;
+        }
+        Ok(storage)
+    }
+
+    /// Stats of every column, indexed by column. It iterates the whole database, for debugging only.
+    pub fn column_stats(&self) -> Result<Vec<ColumnStats>> {
+        (0..self.db.num_columns())
+            .map(|col| {
+                let mut stats = ColumnStats::default();
+                for item in self.db.iter(col) {
+                    let (key, value) = item?;
+                    stats.keys += 1;
+                    stats.bytes += (key.len() + value.len()) as u64;
+                }
+                Ok(stats)
+            })
+            .collect()
+    }
+
+    /// Databases created by older versions have fewer columns, open them and add the missing ones.
+    /// The number of columns the database had is returned.
+    fn open_with_fewer_columns(
+        mut db_config: DatabaseConfig,
+        path: &Path,
+    ) -> Result<(Database, u32)> {
+        for columns in (1..COL_NUM).rev() {
+            db_config.columns = columns;
+            if let Ok(mut db) = Database::open(&db_config, path) {
+                while db.num_columns() < COL_NUM {
+                    db.add_column()?;
+                }
+                return Ok((db, columns));
+            }
+        }
+        anyhow::bail!("cannot open database at {:?}", path)
     }
 }
@@ -5,12 +5,36 @@ use anyhow::Result;
 use async_trait::async_trait;
 
 const PROGRESS_KEY: &[u8] = &[0];
+const KEY_ROTATION_EPOCH_KEY: &[u8] = &[6];
+const PROGRESS_EPOCH_KEY: &[u8] = &[7];
+
+/// Progress of the DA entrance sync, the sync resumes from it on restart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncCheckpoint {
+    /// Next block to sync.
+    pub block_number: u64,
+    /// Latest epoch of the events synced, `None` before any event.
+    pub epoch: Option<u64>,
+}
 
 #[async_trait]
 pub trait MiscDB {
     async fn put_progress(&self, block_number: u64) -> Result<()>;
 
     async fn get_progress(&self) -> Result<Option<u64>>;
+
+    /// Write the block and the epoch of a checkpoint atomically.
+    async fn put_checkpoint(&self, checkpoint: SyncCheckpoint) -> Result<()>;
+
+    async fn get_checkpoint(&self) -> Result<Option<SyncCheckpoint>>;
+
+    /// First epoch signed with the rotated signer key.
+    async fn put_key_rotation_epoch(&self, epoch: u64) -> Result<()>;
+
+    async fn get_key_rotation_epoch(&self) -> Result<Option<u64>>;
+
+    /// Forget the key rotation epoch, e.g. when the rotation is not registered on chain.
+    async fn delete_key_rotation_epoch(&self) -> Result<()>;
 }
 
 #[async_trait]
@@ -28,4 +52,54 @@ impl MiscDB for Storage {
         }
         Ok(None)
     }
+
+    async fn put_checkpoint(&self, checkpoint: SyncCheckpoint) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(
+            COL_MISC,
+            PROGRESS_KEY,
+            &checkpoint.block_number.to_be_bytes(),
+        );
+        if let Some(epoch) = checkpoint.epoch {
+            tx.put(COL_MISC, PROGRESS_EPOCH_KEY, &epoch.to_be_bytes());
+        }
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_checkpoint(&self) -> Result<Option<SyncCheckpoint>> {
+        let block_number = match self.get_progress().await? {
+            Some(block_number) => block_number,
+            None => return Ok(None),
+        };
+        let epoch = self
+            .db
+            .get(COL_MISC, PROGRESS_EPOCH_KEY)?
+            .map(|raw_data| u64::from_be_bytes(raw_data.try_into().unwrap()));
+        Ok(Some(SyncCheckpoint {
+            block_number,
+            epoch,
+        }))
+    }
+
+    async fn put_key_rotation_epoch(&self, epoch: u64) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(COL_MISC, KEY_ROTATION_EPOCH_KEY, &epoch.to_be_bytes());
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_key_rotation_epoch(&self) -> Result<Option<u64>> {
+        if let Some(raw_data) = self.db.get(COL_MISC, KEY_ROTATION_EPOCH_KEY)? {
+            return Ok(Some(u64::from_be_bytes(raw_data.try_into().unwrap())));
+        }
+        Ok(None)
+    }
+
+    async fn delete_key_rotation_epoch(&self) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.delete(COL_MISC, KEY_ROTATION_EPOCH_KEY);
+        self.db.write(tx)?;
+        Ok(())
+    }
 }
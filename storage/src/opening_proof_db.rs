@@ -0,0 +1,71 @@
+use crate::{slice_db::SliceIndex, COL_OPENING_PROOF};
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Encoded slices kept whole, with the opening proof against the erasure commitment they were
+/// verified with when signed. The proof of a row cannot be computed again without the whole blob.
+#[async_trait]
+pub trait OpeningProofDB {
+    /// Store encoded slices serialized in compressed form, by row index.
+    async fn put_opening_proofs(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        slices: Vec<(u64, Vec<u8>)>,
+    ) -> Result<()>;
+    async fn get_opening_proof(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        index: u64,
+    ) -> Result<Option<Vec<u8>>>;
+}
+
+fn get_proof_key(epoch: u64, quorum_id: u64, storage_root: [u8; 32], index: u64) -> Vec<u8> {
+    // the slice key without its prefix
+    SliceIndex {
+        epoch,
+        quorum_id,
+        storage_root,
+        index,
+    }
+    .to_slice_key()[1..]
+        .to_vec()
+}
+
+#[async_trait]
+impl OpeningProofDB for Storage {
+    async fn put_opening_proofs(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        slices: Vec<(u64, Vec<u8>)>,
+    ) -> Result<()> {
+        let mut tx = self.db.transaction();
+        for (index, value) in slices {
+            let key = get_proof_key(epoch, quorum_id, storage_root, index);
+            tx.put(COL_OPENING_PROOF, &key, &self.encrypt_value(&key, value)?);
+        }
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_opening_proof(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        index: u64,
+    ) -> Result<Option<Vec<u8>>> {
+        let key = get_proof_key(epoch, quorum_id, storage_root, index);
+        match self.db.get(COL_OPENING_PROOF, &key)? {
+            Some(value) => Ok(Some(self.decrypt_value(&key, value)?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::COL_OUTBOX;
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// A message waiting to be published to the message bus.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutboxMessage {
+    pub topic: String,
+    /// Unique id of the message, consumers drop the messages delivered again by it.
+    pub key: String,
+    pub payload: Vec<u8>,
+}
+
+#[async_trait]
+pub trait OutboxDB {
+    /// Append messages to the outbox, after the messages already in it.
+    async fn push_outbox(&self, messages: Vec<OutboxMessage>) -> Result<()>;
+    /// The oldest `limit` messages of the outbox with their sequence numbers.
+    async fn peek_outbox(&self, limit: usize) -> Result<Vec<(u64, OutboxMessage)>>;
+    /// Remove published messages.
+    async fn ack_outbox(&self, seqs: &[u64]) -> Result<()>;
+}
+
+impl Storage {
+    /// Sequence number of the next message, after the last one of the outbox. `cached` is kept
+    /// locked by the caller until its messages are written.
+    fn next_outbox_seq(&self, cached: &mut Option<u64>) -> Result<u64> {
+        if let Some(seq) = *cached {
+            return Ok(seq);
+        }
+        let mut seq = 0;
+        for item in self.db.iter(COL_OUTBOX) {
+            let (key, _) = item?;
+            seq = u64::from_be_bytes(key.as_ref().try_into()?) + 1;
+        }
+        *cached = Some(seq);
+        Ok(seq)
+    }
+}
+
+#[async_trait]
+impl OutboxDB for Storage {
+    async fn push_outbox(&self, messages: Vec<OutboxMessage>) -> Result<()> {
+        let mut next_seq = self.outbox_seq.lock().unwrap();
+        let mut seq = self.next_outbox_seq(&mut next_seq)?;
+        let mut tx = self.db.transaction();
+        for message in messages {
+            tx.put(
+                COL_OUTBOX,
+                &seq.to_be_bytes(),
+                &bincode::serialize(&message)?,
+            );
+            seq += 1;
+        }
+        self.db.write(tx)?;
+        *next_seq = Some(seq);
+        Ok(())
+    }
+
+    async fn peek_outbox(&self, limit: usize) -> Result<Vec<(u64, OutboxMessage)>> {
+        let mut messages = vec![];
+        for item in self.db.iter(COL_OUTBOX).take(limit) {
+            let (key, value) = item?;
+            messages.push((
+                u64::from_be_bytes(key.as_ref().try_into()?),
+                bincode::deserialize(&value)?,
+            ));
+        }
+        Ok(messages)
+    }
+
+    async fn ack_outbox(&self, seqs: &[u64]) -> Result<()> {
+        let mut tx = self.db.transaction();
+        for seq in seqs {
+            tx.delete(COL_OUTBOX, &seq.to_be_bytes());
+        }
+        self.db.write(tx)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(key: &str) -> OutboxMessage {
+        OutboxMessage {
+            topic: "blobs".to_string(),
+            key: key.to_string(),
+            payload: vec![1, 2],
+        }
+    }
+
+    #[tokio::test]
+    async fn outbox_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Storage::new(dir.path()).unwrap();
+        db.push_outbox(vec![message("a"), message("b")])
+            .await
+            .unwrap();
+        db.ack_outbox(&[0]).await.unwrap();
+
+        // the sequence continues after the messages left, as on reopen
+        *db.outbox_seq.lock().unwrap() = None;
+        db.push_outbox(vec![message("c")]).await.unwrap();
+        assert_eq!(
+            db.peek_outbox(10).await.unwrap(),
+            vec![(1, message("b")), (2, message("c"))]
+        );
+        assert_eq!(db.peek_outbox(1).await.unwrap().len(), 1);
+    }
+}
@@ -5,14 +5,18 @@ use anyhow::Result;
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AssignedSlices(pub Vec<u64>);
 
 #[async_trait]
 pub trait QuorumDB {
+    /// Install the quorums of `epoch` at once, replacing the ones stored.
     async fn put_quorums(&self, epoch: u64, quorums: Vec<AssignedSlices>) -> Result<()>;
     async fn get_quorum_num(&self, epoch: u64) -> Result<Option<u64>>;
+    /// Assigned slices of every quorum of `epoch`, indexed by quorum id.
+    async fn get_quorums(&self, epoch: u64) -> Result<Option<Vec<AssignedSlices>>>;
+    async fn get_latest_epoch(&self) -> Result<Option<u64>>;
     async fn get_assgined_slices(
         &self,
         epoch: u64,
@@ -33,6 +37,11 @@ impl QuorumDB for Storage {
             let value = bincode::serialize(assigned).unwrap();
             tx.put(COL_QUORUM, &key, &value);
         }
+        // quorums of a previous install beyond the new ones
+        let stored = self.get_quorum_num(epoch).await?.unwrap_or(0);
+        for idx in assgined.len() as u64..stored {
+            tx.delete(COL_QUORUM, &get_quorum_key(epoch, idx));
+        }
         tx.put(
             COL_QUORUM_NUM,
             &epoch.to_be_bytes(),
@@ -42,6 +51,21 @@ impl QuorumDB for Storage {
         Ok(())
     }
 
+    async fn get_quorums(&self, epoch: u64) -> Result<Option<Vec<AssignedSlices>>> {
+        let quorum_num = match self.get_quorum_num(epoch).await? {
+            Some(quorum_num) => quorum_num,
+            None => return Ok(None),
+        };
+        let mut quorums = vec![];
+        for quorum_id in 0..quorum_num {
+            match self.get_assgined_slices(epoch, quorum_id).await? {
+                Some(assigned) => quorums.push(assigned),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(quorums))
+    }
+
     async fn get_quorum_num(&self, epoch: u64) -> Result<Option<u64>> {
         if let Some(raw_data) = self.db.get(COL_QUORUM_NUM, &epoch.to_be_bytes())? {
             return Ok(Some(u64::from_be_bytes(raw_data.try_into().unwrap())));
@@ -49,6 +73,14 @@ impl QuorumDB for Storage {
         Ok(None)
     }
 
+    async fn get_latest_epoch(&self) -> Result<Option<u64>> {
+        if let Some(item) = self.db.iter(COL_QUORUM_NUM).last() {
+            let (key, _) = item?;
+            return Ok(Some(u64::from_be_bytes(key.as_ref().try_into()?)));
+        }
+        Ok(None)
+    }
+
     async fn get_assgined_slices(
         &self,
         epoch: u64,
@@ -0,0 +1,73 @@
+use crate::COL_MISC;
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+const RECONCILE_REPORT_KEY: &[u8] = &[5];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InconsistencyKind {
+    /// The blob is verified but none of its assigned slices is stored.
+    VerifiedWithoutSlices,
+    /// Slices of the blob are stored but the blob has no status record.
+    SlicesWithoutStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReconcileAction {
+    /// The missing slices are fetched from peers.
+    Refetched,
+    /// The status is restored as verified, the commitment exists on chain.
+    RestoredVerified,
+    /// The status is restored as uploaded, the commitment is not verified on chain.
+    RestoredUploaded,
+    /// Left for the operator.
+    Flagged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inconsistency {
+    pub epoch: u64,
+    pub quorum_id: u64,
+    pub storage_root: [u8; 32],
+    pub kind: InconsistencyKind,
+    pub action: ReconcileAction,
+    /// Assigned slices still missing after reconciliation.
+    pub missing_slices: u64,
+}
+
+/// Outcome of the latest reconciliation run between blob statuses and stored slices.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReconcileReport {
+    /// Unix timestamp in seconds.
+    pub finished_at: u64,
+    pub start_epoch: u64,
+    pub end_epoch: u64,
+    pub checked_blobs: u64,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+#[async_trait]
+pub trait ReconcileDB {
+    async fn put_reconcile_report(&self, report: &ReconcileReport) -> Result<()>;
+    async fn get_reconcile_report(&self) -> Result<Option<ReconcileReport>>;
+}
+
+#[async_trait]
+impl ReconcileDB for Storage {
+    async fn put_reconcile_report(&self, report: &ReconcileReport) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(COL_MISC, RECONCILE_REPORT_KEY, &bincode::serialize(report)?);
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_reconcile_report(&self) -> Result<Option<ReconcileReport>> {
+        match self.db.get(COL_MISC, RECONCILE_REPORT_KEY)? {
+            Some(raw_data) => Ok(Some(bincode::deserialize(&raw_data)?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{COL_MISC, COL_REGISTRATION};
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+const SIGNER_REGISTRATION_KEY: &[u8] = &[8];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RegistrationStatus {
+    /// A registration transaction is being sent.
+    PENDING,
+    REGISTERED,
+    /// The last registration attempt failed, it is retried until the epoch starts.
+    FAILED,
+    /// The epoch started without the signer registered for it.
+    MISSED,
+}
+
+/// Registration of the signer for an epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpochRegistration {
+    pub epoch: u64,
+    pub status: RegistrationStatus,
+    /// Registration transactions sent for the epoch.
+    pub attempts: u64,
+    pub last_error: Option<String>,
+    /// Unix timestamp in seconds of the last update.
+    pub updated_at: u64,
+}
+
+/// Registration of the signer account, checked on startup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignerRegistration {
+    pub registered: bool,
+    /// Socket registered on chain, `None` if not registered.
+    pub socket: Option<String>,
+    /// `socket_address` of the config, the registered socket is updated to it.
+    pub configured_socket: String,
+    /// Unix timestamp in seconds of the check.
+    pub checked_at: u64,
+    /// Why the registration or the socket update failed.
+    pub last_error: Option<String>,
+}
+
+#[async_trait]
+pub trait RegistrationDB {
+    async fn put_epoch_registration(&self, registration: &EpochRegistration) -> Result<()>;
+
+    async fn get_epoch_registration(&self, epoch: u64) -> Result<Option<EpochRegistration>>;
+
+    /// Registrations of the latest `limit` epochs, newest first.
+    async fn get_epoch_registrations(&self, limit: usize) -> Result<Vec<EpochRegistration>>;
+
+    async fn put_signer_registration(&self, registration: &SignerRegistration) -> Result<()>;
+
+    async fn get_signer_registration(&self) -> Result<Option<SignerRegistration>>;
+}
+
+#[async_trait]
+impl RegistrationDB for Storage {
+    async fn put_epoch_registration(&self, registration: &EpochRegistration) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(
+            COL_REGISTRATION,
+            &registration.epoch.to_be_bytes(),
+            &bincode::serialize(registration)?,
+        );
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_epoch_registration(&self, epoch: u64) -> Result<Option<EpochRegistration>> {
+        match self.db.get(COL_REGISTRATION, &epoch.to_be_bytes())? {
+            Some(value) => Ok(Some(bincode::deserialize(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_epoch_registrations(&self, limit: usize) -> Result<Vec<EpochRegistration>> {
+        let mut registrations = vec![];
+        for item in self.db.iter(COL_REGISTRATION) {
+            let (_, value) = item?;
+            registrations.push(bincode::deserialize(&value)?);
+        }
+        registrations.reverse();
+        registrations.truncate(limit);
+        Ok(registrations)
+    }
+
+    async fn put_signer_registration(&self, registration: &SignerRegistration) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(
+            COL_MISC,
+            SIGNER_REGISTRATION_KEY,
+            &bincode::serialize(registration)?,
+        );
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_signer_registration(&self) -> Result<Option<SignerRegistration>> {
+        match self.db.get(COL_MISC, SIGNER_REGISTRATION_KEY)? {
+            Some(raw_data) => Ok(Some(bincode::deserialize(&raw_data)?)),
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,795 @@
+//! Registry of the key layouts and value encodings of every column, so external tools can parse
+//! the database. Schema versions count the columns of the database: every layout change of a
+//! release came with a new column, and older databases are opened by adding the missing ones.
+
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+
+use crate::{
+    COL_BLOB_STATUS, COL_CHAIN_EVENT, COL_CORRUPT_SLICE, COL_DAS_REWARD, COL_MISC, COL_NUM,
+    COL_OPENING_PROOF, COL_OUTBOX, COL_QUORUM, COL_QUORUM_NUM, COL_REGISTRATION, COL_SIGNATURE,
+    COL_SIGN_OUTCOME, COL_SIGN_QUOTA, COL_SLICE, COL_SLICE_USAGE, COL_TIERED_SLICE, COL_TX_HISTORY,
+};
+
+pub const SCHEMA_VERSION: u32 = COL_NUM;
+
+/// Names of the database columns, indexed by column.
+pub const COLUMN_NAMES: [&str; COL_NUM as usize] = [
+    "misc",
+    "slice",
+    "quorum",
+    "quorum_num",
+    "blob_status",
+    "tiered_slice",
+    "corrupt_slice",
+    "sign_outcome",
+    "das_reward",
+    "tx_history",
+    "registration",
+    "sign_quota",
+    "signature",
+    "slice_usage",
+    "opening_proof",
+    "chain_event",
+    "outbox",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    U32Be,
+    U64Be,
+    Bytes32,
+}
+
+impl FieldType {
+    pub fn size(self) -> usize {
+        match self {
+            FieldType::U32Be => 4,
+            FieldType::U64Be => 8,
+            FieldType::Bytes32 => 32,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FieldType::U32Be => "u32_be",
+            FieldType::U64Be => "u64_be",
+            FieldType::Bytes32 => "bytes32",
+        }
+    }
+}
+
+/// A field of a key, fields follow the prefix in order without separators.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct KeyField {
+    pub name: &'static str,
+    #[serde(rename = "type")]
+    pub field_type: FieldType,
+}
+
+const fn field(name: &'static str, field_type: FieldType) -> KeyField {
+    KeyField { name, field_type }
+}
+
+/// Encoding of a value, with the Rust type it holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "encoding", content = "type", rename_all = "snake_case")]
+pub enum ValueEncoding {
+    /// No value, the key alone is the record.
+    Empty,
+    /// The single byte `1`.
+    Flag,
+    U64Be(&'static str),
+    Bincode(&'static str),
+    Bcs(&'static str),
+    /// `ark_serialize` in compressed form.
+    ArkCompressed(&'static str),
+    /// `ark_serialize` in uncompressed form.
+    ArkUncompressed(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct KeySchema {
+    pub name: &'static str,
+    pub column: u32,
+    /// Leading bytes of every key of this layout.
+    pub prefix: &'static [u8],
+    pub fields: &'static [KeyField],
+    pub value: ValueEncoding,
+    /// Values are stored as `key id (1 byte) | nonce (12 bytes) | AES-256-GCM ciphertext`, with
+    /// the key as associated data, when the database is encrypted.
+    pub encrypted: bool,
+    /// First schema version storing keys of this layout.
+    pub since_version: u32,
+    pub description: &'static str,
+}
+
+impl KeySchema {
+    pub fn key_len(&self) -> usize {
+        self.prefix.len()
+            + self
+                .fields
+                .iter()
+                .map(|x| x.field_type.size())
+                .sum::<usize>()
+    }
+
+    pub fn matches(&self, column: u32, key: &[u8]) -> bool {
+        self.column == column && key.len() == self.key_len() && key.starts_with(self.prefix)
+    }
+
+    /// Fields of `key`, integers in decimal and bytes in 0x-prefixed hex.
+    pub fn decode_key(&self, key: &[u8]) -> Result<Vec<(&'static str, String)>> {
+        if key.len() != self.key_len() || !key.starts_with(self.prefix) {
+            bail!(anyhow!("key does not match the `{}` layout", self.name));
+        }
+        let mut offset = self.prefix.len();
+        let mut fields = vec![];
+        for field in self.fields {
+            let bytes = &key[offset..offset + field.field_type.size()];
+            offset += bytes.len();
+            let value = match field.field_type {
+                FieldType::U32Be => u32::from_be_bytes(bytes.try_into()?).to_string(),
+                FieldType::U64Be => u64::from_be_bytes(bytes.try_into()?).to_string(),
+                FieldType::Bytes32 => format!("0x{}", hex::encode(bytes)),
+            };
+            fields.push((field.name, value));
+        }
+        Ok(fields)
+    }
+}
+
+const BLOB_FIELDS: &[KeyField] = &[
+    field("epoch", FieldType::U64Be),
+    field("quorum_id", FieldType::U64Be),
+    field("storage_root", FieldType::Bytes32),
+];
+
+const SLICE_FIELDS: &[KeyField] = &[
+    field("epoch", FieldType::U64Be),
+    field("quorum_id", FieldType::U64Be),
+    field("storage_root", FieldType::Bytes32),
+    field("index", FieldType::U64Be),
+];
+
+const CHAIN_EVENT_FIELDS: &[KeyField] = &[
+    field("block_number", FieldType::U64Be),
+    field("log_index", FieldType::U64Be),
+];
+
+const fn misc(
+    name: &'static str,
+    prefix: &'static [u8],
+    value: ValueEncoding,
+    since_version: u32,
+    description: &'static str,
+) -> KeySchema {
+    KeySchema {
+        name,
+        column: COL_MISC,
+        prefix,
+        fields: &[],
+        value,
+        encrypted: false,
+        since_version,
+        description,
+    }
+}
+
+pub const KEY_SCHEMAS: &[KeySchema] = &[
+    misc(
+        "progress",
+        &[0],
+        ValueEncoding::U64Be("block number"),
+        5,
+        "Block the DA entrance events are synced to.",
+    ),
+    misc(
+        "tiered_epoch",
+        &[1],
+        ValueEncoding::U64Be("epoch"),
+        6,
+        "Last epoch whose slices are all moved to cold storage.",
+    ),
+    misc(
+        "encrypted",
+        &[2],
+        ValueEncoding::Flag,
+        6,
+        "Set once slice values are encrypted.",
+    ),
+    misc(
+        "scrub_progress",
+        &[3],
+        ValueEncoding::U64Be("epoch"),
+        7,
+        "Next epoch to scrub.",
+    ),
+    misc(
+        "reward_progress",
+        &[4],
+        ValueEncoding::U64Be("block number"),
+        9,
+        "Block the DAS reward events are synced to.",
+    ),
+    misc(
+        "reconcile_report",
+        &[5],
+        ValueEncoding::Bincode("ReconcileReport"),
+        9,
+        "Report of the latest reconciliation run.",
+    ),
+    misc(
+        "key_rotation_epoch",
+        &[6],
+        ValueEncoding::U64Be("epoch"),
+        9,
+        "First epoch signed with the rotated signer key.",
+    ),
+    misc(
+        "progress_epoch",
+        &[7],
+        ValueEncoding::U64Be("epoch"),
+        15,
+        "Latest epoch of the DA entrance events synced, written with `progress`.",
+    ),
+    misc(
+        "signer_registration",
+        &[8],
+        ValueEncoding::Bincode("SignerRegistration"),
+        16,
+        "Registration and socket of the signer account, checked on startup.",
+    ),
+    misc(
+        "expired_epoch",
+        &[9],
+        ValueEncoding::U64Be("epoch"),
+        16,
+        "Epochs before it are expired on chain and their slices deleted.",
+    ),
+    misc(
+        "reencryption_cursor",
+        &[10],
+        ValueEncoding::Bincode("ReencryptionCursor"),
+        17,
+        "Key, epoch and last database key of the re-encryption of slice values.",
+    ),
+    KeySchema {
+        name: "blob_slices",
+        column: COL_SLICE,
+        prefix: &[0],
+        fields: BLOB_FIELDS,
+        value: ValueEncoding::Bcs("Vec<u16>"),
+        encrypted: false,
+        since_version: 5,
+        description: "Sorted indexes of the slices stored for a blob.",
+    },
+    KeySchema {
+        name: "slice",
+        column: COL_SLICE,
+        prefix: &[1],
+        fields: SLICE_FIELDS,
+        value: ValueEncoding::ArkCompressed("LightEncodedSlice"),
+        encrypted: true,
+        since_version: 5,
+        description: "Merkle proof and roots of a slice.",
+    },
+    KeySchema {
+        name: "slice_data",
+        column: COL_SLICE,
+        prefix: &[2],
+        fields: SLICE_FIELDS,
+        value: ValueEncoding::ArkUncompressed("Vec<[u8; 32]>"),
+        encrypted: true,
+        since_version: 5,
+        description: "Merkle row of a slice.",
+    },
+    KeySchema {
+        name: "quorum",
+        column: COL_QUORUM,
+        prefix: &[],
+        fields: &[
+            field("epoch", FieldType::U64Be),
+            field("quorum_id", FieldType::U64Be),
+        ],
+        value: ValueEncoding::Bincode("AssignedSlices"),
+        encrypted: false,
+        since_version: 5,
+        description: "Slice indexes assigned to the signer in a quorum.",
+    },
+    KeySchema {
+        name: "quorum_num",
+        column: COL_QUORUM_NUM,
+        prefix: &[],
+        fields: &[field("epoch", FieldType::U64Be)],
+        value: ValueEncoding::U64Be("number of quorums"),
+        encrypted: false,
+        since_version: 5,
+        description: "Number of quorums of an epoch.",
+    },
+    KeySchema {
+        name: "blob_status",
+        column: COL_BLOB_STATUS,
+        prefix: &[],
+        fields: BLOB_FIELDS,
+        value: ValueEncoding::U64Be("BlobStatus"),
+        encrypted: false,
+        since_version: 5,
+        description: "Whether a blob is signed or verified on chain.",
+    },
+    KeySchema {
+        name: "tiered_slice",
+        column: COL_TIERED_SLICE,
+        prefix: &[1],
+        fields: SLICE_FIELDS,
+        value: ValueEncoding::Empty,
+        encrypted: false,
+        since_version: 6,
+        description: "Slice moved to cold storage, the value is the object `slices/{hex key}`.",
+    },
+    KeySchema {
+        name: "tiered_slice_data",
+        column: COL_TIERED_SLICE,
+        prefix: &[2],
+        fields: SLICE_FIELDS,
+        value: ValueEncoding::Empty,
+        encrypted: false,
+        since_version: 6,
+        description:
+            "Merkle row moved to cold storage, the value is the object `slices/{hex key}`.",
+    },
+    KeySchema {
+        name: "corrupt_slice",
+        column: COL_CORRUPT_SLICE,
+        prefix: &[1],
+        fields: SLICE_FIELDS,
+        value: ValueEncoding::Empty,
+        encrypted: false,
+        since_version: 7,
+        description: "Slice found corrupt or missing, cleared once the slice is written again.",
+    },
+    KeySchema {
+        name: "sign_outcome",
+        column: COL_SIGN_OUTCOME,
+        prefix: &[],
+        fields: BLOB_FIELDS,
+        value: ValueEncoding::U64Be("SignOutcome"),
+        encrypted: false,
+        since_version: 8,
+        description: "Whether the signer signed or rejected a blob.",
+    },
+    KeySchema {
+        name: "das_submission",
+        column: COL_DAS_REWARD,
+        prefix: &[0],
+        fields: &[
+            field("epoch", FieldType::U64Be),
+            field("quorum_id", FieldType::U64Be),
+            field("data_root", FieldType::Bytes32),
+            field("line_index", FieldType::U32Be),
+            field("subline_index", FieldType::U32Be),
+            field("sample_seed", FieldType::Bytes32),
+        ],
+        value: ValueEncoding::Bincode("SampleSubmission"),
+        encrypted: false,
+        since_version: 9,
+        description: "Sampling answer submitted to the DASample contract.",
+    },
+    KeySchema {
+        name: "das_reward",
+        column: COL_DAS_REWARD,
+        prefix: &[1],
+        fields: &[
+            field("epoch", FieldType::U64Be),
+            field("sample_round", FieldType::U64Be),
+            field("quorum_id", FieldType::U64Be),
+            field("data_root", FieldType::Bytes32),
+            field("line_index", FieldType::U64Be),
+            field("subline_index", FieldType::U64Be),
+        ],
+        value: ValueEncoding::Bincode("DasReward"),
+        encrypted: false,
+        since_version: 9,
+        description: "DAS reward paid to the miner.",
+    },
+    KeySchema {
+        name: "tx_attempt",
+        column: COL_TX_HISTORY,
+        prefix: &[],
+        fields: &[field("seq", FieldType::U64Be)],
+        value: ValueEncoding::Bincode("TxAttempt"),
+        encrypted: false,
+        since_version: 10,
+        description: "Transaction sent by the transactor, the latest 1000 are kept.",
+    },
+    KeySchema {
+        name: "epoch_registration",
+        column: COL_REGISTRATION,
+        prefix: &[],
+        fields: &[field("epoch", FieldType::U64Be)],
+        value: ValueEncoding::Bincode("EpochRegistration"),
+        encrypted: false,
+        since_version: 11,
+        description: "Registration of the signer for an epoch.",
+    },
+    KeySchema {
+        name: "sign_quota_usage",
+        column: COL_SIGN_QUOTA,
+        prefix: &[],
+        fields: &[field("epoch", FieldType::U64Be)],
+        value: ValueEncoding::Bincode("BTreeMap<String, QuotaUsage>"),
+        encrypted: false,
+        since_version: 12,
+        description: "Blobs and bytes signed for each sign client in an epoch.",
+    },
+    KeySchema {
+        name: "signature",
+        column: COL_SIGNATURE,
+        prefix: &[],
+        fields: BLOB_FIELDS,
+        value: ValueEncoding::ArkUncompressed("G1Affine"),
+        encrypted: false,
+        since_version: 13,
+        description: "Signature of a signed blob, served by GetBlobStatus.",
+    },
+    KeySchema {
+        name: "slice_usage",
+        column: COL_SLICE_USAGE,
+        prefix: &[],
+        fields: &[
+            field("epoch", FieldType::U64Be),
+            field("quorum_id", FieldType::U64Be),
+        ],
+        value: ValueEncoding::Bincode("SliceUsage"),
+        encrypted: false,
+        since_version: 14,
+        description: "Slices of a quorum stored locally and their bytes.",
+    },
+    KeySchema {
+        name: "opening_proof",
+        column: COL_OPENING_PROOF,
+        prefix: &[],
+        fields: SLICE_FIELDS,
+        value: ValueEncoding::ArkCompressed("EncodedSlice"),
+        encrypted: true,
+        since_version: 15,
+        description:
+            "Whole encoded slice with its opening proof, kept if store_opening_proofs is set.",
+    },
+    KeySchema {
+        name: "chain_event",
+        column: COL_CHAIN_EVENT,
+        prefix: &[0],
+        fields: CHAIN_EVENT_FIELDS,
+        value: ValueEncoding::Bincode("ChainEvent"),
+        encrypted: false,
+        since_version: 16,
+        description: "Decoded DA entrance event, kept if log_sync.index_events is set.",
+    },
+    KeySchema {
+        name: "chain_event_by_epoch",
+        column: COL_CHAIN_EVENT,
+        prefix: &[1],
+        fields: &[
+            field("epoch", FieldType::U64Be),
+            field("block_number", FieldType::U64Be),
+            field("log_index", FieldType::U64Be),
+        ],
+        value: ValueEncoding::Empty,
+        encrypted: false,
+        since_version: 16,
+        description: "Index of the chain events of an epoch.",
+    },
+    KeySchema {
+        name: "chain_event_by_data_root",
+        column: COL_CHAIN_EVENT,
+        prefix: &[2],
+        fields: &[
+            field("data_root", FieldType::Bytes32),
+            field("block_number", FieldType::U64Be),
+            field("log_index", FieldType::U64Be),
+        ],
+        value: ValueEncoding::Empty,
+        encrypted: false,
+        since_version: 16,
+        description: "Index of the chain events of a data root.",
+    },
+    KeySchema {
+        name: "outbox",
+        column: COL_OUTBOX,
+        prefix: &[],
+        fields: &[field("seq", FieldType::U64Be)],
+        value: ValueEncoding::Bincode("OutboxMessage"),
+        encrypted: false,
+        since_version: 17,
+        description: "Message waiting to be published to the message bus, removed once published.",
+    },
+];
+
+/// Layout of a key read from `column`.
+pub fn find_key_schema(column: u32, key: &[u8]) -> Option<&'static KeySchema> {
+    KEY_SCHEMAS
+        .iter()
+        .find(|schema| schema.matches(column, key))
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnSchema {
+    pub column: u32,
+    pub name: &'static str,
+    pub keys: Vec<KeySchema>,
+}
+
+/// The whole registry, grouped by column.
+#[derive(Debug, Serialize)]
+pub struct SchemaDump {
+    pub version: u32,
+    pub columns: Vec<ColumnSchema>,
+}
+
+pub fn schema_dump() -> SchemaDump {
+    SchemaDump {
+        version: SCHEMA_VERSION,
+        columns: (0..COL_NUM)
+            .map(|column| ColumnSchema {
+                column,
+                name: COLUMN_NAMES[column as usize],
+                keys: KEY_SCHEMAS
+                    .iter()
+                    .filter(|schema| schema.column == column)
+                    .copied()
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blob_status_db::{BlobStatus, BlobStatusDB},
+        cold_storage::ColdStorageDB,
+        das_reward_db::{DasReward, DasRewardDB, SampleSubmission, SubmissionStatus},
+        event_db::{ChainEvent, ChainEventDB, ChainEventKind},
+        expiry_db::ExpiryDB,
+        misc_db::{MiscDB, SyncCheckpoint},
+        opening_proof_db::OpeningProofDB,
+        outbox_db::{OutboxDB, OutboxMessage},
+        quorum_db::{AssignedSlices, QuorumDB},
+        reconcile_db::{ReconcileDB, ReconcileReport},
+        registration_db::{
+            EpochRegistration, RegistrationDB, RegistrationStatus, SignerRegistration,
+        },
+        scrub_db::ScrubDB,
+        sign_outcome_db::{SignOutcome, SignOutcomeDB},
+        sign_quota_db::{QuotaUsage, SignQuotaDB},
+        slice_db::SliceIndex,
+        tx_history_db::{TxAttempt, TxHistoryDB, TxOutcome},
+        usage_db::UsageDelta,
+        Storage,
+    };
+
+    #[test]
+    fn registry_test() {
+        for (i, a) in KEY_SCHEMAS.iter().enumerate() {
+            assert!(a.column < COL_NUM && a.since_version <= SCHEMA_VERSION);
+            // a key matches one layout at most
+            for b in KEY_SCHEMAS[i + 1..].iter() {
+                assert!(
+                    a.column != b.column
+                        || a.key_len() != b.key_len()
+                        || (!a.prefix.starts_with(b.prefix) && !b.prefix.starts_with(a.prefix)),
+                    "{} and {} overlap",
+                    a.name,
+                    b.name
+                );
+            }
+        }
+        let index = SliceIndex {
+            epoch: 3,
+            quorum_id: 1,
+            storage_root: [7; 32],
+            index: 1000,
+        };
+        let key = index.to_slice_key();
+        let schema = find_key_schema(COL_SLICE, &key).unwrap();
+        assert_eq!(schema.name, "slice");
+        assert_eq!(
+            schema.decode_key(&key).unwrap(),
+            vec![
+                ("epoch", "3".to_string()),
+                ("quorum_id", "1".to_string()),
+                ("storage_root", format!("0x{}", "07".repeat(32))),
+                ("index", "1000".to_string()),
+            ]
+        );
+        assert_eq!(
+            find_key_schema(COL_CORRUPT_SLICE, &key).unwrap().name,
+            "corrupt_slice"
+        );
+        assert!(find_key_schema(COL_SLICE, &key[..56]).is_none());
+    }
+
+    /// Every key written through the storage traits is described by the registry.
+    #[tokio::test]
+    async fn compatibility_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Storage::new(dir.path()).unwrap();
+        db.put_progress(10).await.unwrap();
+        db.put_key_rotation_epoch(2).await.unwrap();
+        db.put_checkpoint(SyncCheckpoint {
+            block_number: 12,
+            epoch: Some(4),
+        })
+        .await
+        .unwrap();
+        db.put_scrub_progress(3).await.unwrap();
+        db.put_reward_progress(11).await.unwrap();
+        db.put_tiered_epoch(1).await.unwrap();
+        db.put_reconcile_report(&ReconcileReport::default())
+            .await
+            .unwrap();
+        db.put_quorums(4, vec![AssignedSlices(vec![1, 2])])
+            .await
+            .unwrap();
+        db.put_blob(4, 0, [1; 32], BlobStatus::UPLOADED)
+            .await
+            .unwrap();
+        db.put_sign_outcome(4, 0, [1; 32], SignOutcome::SIGNED)
+            .await
+            .unwrap();
+        db.put_corrupt_slice(&SliceIndex {
+            epoch: 4,
+            quorum_id: 0,
+            storage_root: [1; 32],
+            index: 2,
+        })
+        .await
+        .unwrap();
+        db.put_sample_submission(&SampleSubmission {
+            sample_seed: [2; 32],
+            epoch: 4,
+            quorum_id: 0,
+            data_root: [1; 32],
+            line_index: 6,
+            subline_index: 7,
+            tx_hash: None,
+            status: SubmissionStatus::SUBMITTED,
+        })
+        .await
+        .unwrap();
+        db.put_das_reward(&DasReward {
+            sample_round: 5,
+            epoch: 4,
+            quorum_id: 0,
+            data_root: [1; 32],
+            line_index: 6,
+            subline_index: 7,
+            reward: 1,
+            tx_hash: [3; 32],
+        })
+        .await
+        .unwrap();
+        db.put_tx_attempt(&TxAttempt {
+            timestamp: 1,
+            info: "register".to_string(),
+            tx_hash: None,
+            outcome: TxOutcome::SUCCEEDED,
+            reason: None,
+        })
+        .await
+        .unwrap();
+        db.put_epoch_registration(&EpochRegistration {
+            epoch: 4,
+            status: RegistrationStatus::REGISTERED,
+            attempts: 1,
+            last_error: None,
+            updated_at: 1,
+        })
+        .await
+        .unwrap();
+        db.put_signer_registration(&SignerRegistration {
+            registered: true,
+            socket: Some("127.0.0.1:34000".to_string()),
+            configured_socket: "127.0.0.1:34000".to_string(),
+            checked_at: 1,
+            last_error: None,
+        })
+        .await
+        .unwrap();
+        db.put_expired_epoch(2).await.unwrap();
+        db.put_signature(4, 0, [1; 32], &[5; 64]).await.unwrap();
+        let mut tx = db.db.transaction();
+        let mut usage = UsageDelta::default();
+        usage.replace(&[1; 57], None, Some(10));
+        db.apply_slice_usage(&mut tx, [((4, 0), usage)].into())
+            .unwrap();
+        db.db.write(tx).unwrap();
+        db.put_opening_proofs(4, 0, [1; 32], vec![(2, vec![6; 8])])
+            .await
+            .unwrap();
+        db.put_sign_quota_usage(
+            4,
+            &[("batcher".to_string(), QuotaUsage { blobs: 1, bytes: 2 })].into(),
+        )
+        .await
+        .unwrap();
+        db.put_chain_events(&[ChainEvent {
+            block_number: 12,
+            log_index: 1,
+            tx_hash: [3; 32],
+            kind: ChainEventKind::ErasureCommitmentVerified {
+                data_root: [1; 32],
+                epoch: 4,
+                quorum_id: 0,
+            },
+        }])
+        .await
+        .unwrap();
+        db.push_outbox(vec![OutboxMessage {
+            topic: "blobs".to_string(),
+            key: "a".to_string(),
+            payload: vec![1],
+        }])
+        .await
+        .unwrap();
+
+        let mut found = vec![];
+        for column in 0..COL_NUM {
+            for item in db.db.iter(column) {
+                let (key, value) = item.unwrap();
+                let schema = find_key_schema(column, &key)
+                    .unwrap_or_else(|| panic!("unknown key {:?} in column {}", key, column));
+                let fields = schema.decode_key(&key).unwrap();
+                match schema.value {
+                    ValueEncoding::Empty => assert!(value.is_empty()),
+                    ValueEncoding::U64Be(_) => assert_eq!(value.len(), 8),
+                    _ => assert!(!value.is_empty()),
+                }
+                found.push((schema.name, fields));
+            }
+        }
+        let names: Vec<_> = found.iter().map(|(name, _)| *name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "progress",
+                "tiered_epoch",
+                "scrub_progress",
+                "reward_progress",
+                "reconcile_report",
+                "key_rotation_epoch",
+                "progress_epoch",
+                "signer_registration",
+                "expired_epoch",
+                "quorum",
+                "quorum_num",
+                "blob_status",
+                "corrupt_slice",
+                "sign_outcome",
+                "das_submission",
+                "das_reward",
+                "tx_attempt",
+                "epoch_registration",
+                "sign_quota_usage",
+                "signature",
+                "slice_usage",
+                "opening_proof",
+                "chain_event",
+                "chain_event_by_epoch",
+                "chain_event_by_data_root",
+                "outbox",
+            ]
+        );
+        let reward = &found
+            .iter()
+            .find(|(name, _)| *name == "das_reward")
+            .unwrap()
+            .1;
+        assert_eq!(reward[0], ("epoch", "4".to_string()));
+        assert_eq!(reward[1], ("sample_round", "5".to_string()));
+        assert_eq!(reward[5], ("subline_index", "7".to_string()));
+    }
+}
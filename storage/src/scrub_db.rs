@@ -0,0 +1,63 @@
+use crate::{slice_db::SliceIndex, COL_CORRUPT_SLICE, COL_MISC};
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+use kvdb::KeyValueDB;
+
+const SCRUB_PROGRESS_KEY: &[u8] = &[3];
+
+#[async_trait]
+pub trait ScrubDB {
+    /// The next epoch to be scrubbed.
+    async fn get_scrub_progress(&self) -> Result<Option<u64>>;
+
+    async fn put_scrub_progress(&self, epoch: u64) -> Result<()>;
+
+    /// Flag a slice found corrupt or missing, the flag is cleared once the slice is written again.
+    async fn put_corrupt_slice(&self, index: &SliceIndex) -> Result<()>;
+
+    async fn is_corrupt_slice(&self, index: &SliceIndex) -> Result<bool>;
+
+    async fn get_corrupt_slices(&self) -> Result<Vec<SliceIndex>>;
+}
+
+#[async_trait]
+impl ScrubDB for Storage {
+    async fn get_scrub_progress(&self) -> Result<Option<u64>> {
+        if let Some(raw_data) = self.db.get(COL_MISC, SCRUB_PROGRESS_KEY)? {
+            return Ok(Some(u64::from_be_bytes(raw_data.try_into().unwrap())));
+        }
+        Ok(None)
+    }
+
+    async fn put_scrub_progress(&self, epoch: u64) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(COL_MISC, SCRUB_PROGRESS_KEY, &epoch.to_be_bytes());
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn put_corrupt_slice(&self, index: &SliceIndex) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(COL_CORRUPT_SLICE, &index.to_slice_key(), &[]);
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn is_corrupt_slice(&self, index: &SliceIndex) -> Result<bool> {
+        Ok(self
+            .db
+            .get(COL_CORRUPT_SLICE, &index.to_slice_key())?
+            .is_some())
+    }
+
+    async fn get_corrupt_slices(&self) -> Result<Vec<SliceIndex>> {
+        let mut answer = vec![];
+        for item in KeyValueDB::iter(&*self.db, COL_CORRUPT_SLICE) {
+            let (key, _) = item?;
+            answer.push(SliceIndex::from_slice_key(&key)?);
+        }
+        Ok(answer)
+    }
+}
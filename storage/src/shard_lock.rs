@@ -0,0 +1,64 @@
+use std::{
+    collections::BTreeSet,
+    sync::{Mutex, MutexGuard},
+};
+
+const SHARDS: usize = 64;
+
+/// Locks of the read-modify-write updates of the slice records and usage of a quorum in an epoch,
+/// sharded so writes to unrelated quorums do not wait on each other. Other records are written
+/// blindly and need no lock, RocksDB orders concurrent writes.
+pub(crate) struct ShardLocks {
+    shards: Vec<Mutex<()>>,
+}
+
+impl ShardLocks {
+    pub fn new() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Mutex::new(())).collect(),
+        }
+    }
+
+    fn shard(epoch: u64, quorum_id: u64) -> usize {
+        (epoch.wrapping_mul(31).wrapping_add(quorum_id) % SHARDS as u64) as usize
+    }
+
+    pub fn lock(&self, epoch: u64, quorum_id: u64) -> MutexGuard<'_, ()> {
+        self.shards[Self::shard(epoch, quorum_id)].lock().unwrap()
+    }
+
+    /// Lock the shards of several quorums, always in shard order so lockers do not deadlock.
+    pub fn lock_many(
+        &self,
+        quorums: impl IntoIterator<Item = (u64, u64)>,
+    ) -> Vec<MutexGuard<'_, ()>> {
+        let shards: BTreeSet<usize> = quorums
+            .into_iter()
+            .map(|(epoch, quorum_id)| Self::shard(epoch, quorum_id))
+            .collect();
+        shards
+            .into_iter()
+            .map(|shard| self.shards[shard].lock().unwrap())
+            .collect()
+    }
+
+    pub fn lock_all(&self) -> Vec<MutexGuard<'_, ()>> {
+        self.shards.iter().map(|x| x.lock().unwrap()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_many_test() {
+        let locks = ShardLocks::new();
+        let guards = locks.lock_many([(1, 0), (1, 0), (1, 1), (2, 0)]);
+        assert_eq!(guards.len(), 3);
+        assert!(locks.shards[ShardLocks::shard(1, 0)].try_lock().is_err());
+        drop(guards);
+        drop(locks.lock(1, 0));
+        assert_eq!(locks.lock_all().len(), SHARDS);
+    }
+}
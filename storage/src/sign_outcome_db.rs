@@ -0,0 +1,123 @@
+use crate::{COL_SIGNATURE, COL_SIGN_OUTCOME};
+
+use super::Storage;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+
+use std::convert::TryFrom;
+
+/// Outcome of the last sign request of a blob received by this node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignOutcome {
+    SIGNED = 1,
+    REJECTED = 2,
+}
+
+impl TryFrom<u64> for SignOutcome {
+    type Error = ();
+
+    fn try_from(v: u64) -> Result<Self, Self::Error> {
+        match v {
+            x if x == SignOutcome::SIGNED as u64 => Ok(SignOutcome::SIGNED),
+            x if x == SignOutcome::REJECTED as u64 => Ok(SignOutcome::REJECTED),
+            _ => Err(()),
+        }
+    }
+}
+
+#[async_trait]
+pub trait SignOutcomeDB {
+    async fn put_sign_outcome(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        outcome: SignOutcome,
+    ) -> Result<()>;
+    async fn get_sign_outcome(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    ) -> Result<Option<SignOutcome>>;
+    /// Signature of a signed blob, as returned to the client.
+    async fn put_signature(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        signature: &[u8],
+    ) -> Result<()>;
+    async fn get_signature(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    ) -> Result<Option<Vec<u8>>>;
+}
+
+fn get_outcome_key(epoch: u64, quorum_id: u64, storage_root: [u8; 32]) -> Vec<u8> {
+    epoch
+        .to_be_bytes()
+        .into_iter()
+        .chain(quorum_id.to_be_bytes())
+        .chain(storage_root)
+        .collect()
+}
+
+#[async_trait]
+impl SignOutcomeDB for Storage {
+    async fn put_sign_outcome(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        outcome: SignOutcome,
+    ) -> Result<()> {
+        let key = get_outcome_key(epoch, quorum_id, storage_root);
+        let mut tx = self.db.transaction();
+        tx.put(COL_SIGN_OUTCOME, &key, &(outcome as u64).to_be_bytes());
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_sign_outcome(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    ) -> Result<Option<SignOutcome>> {
+        let key = get_outcome_key(epoch, quorum_id, storage_root);
+        if let Some(raw_data) = self.db.get(COL_SIGN_OUTCOME, &key)? {
+            let outcome: SignOutcome = u64::from_be_bytes(raw_data.try_into().unwrap())
+                .try_into()
+                .map_err(|_| anyhow!("error when convert u64 to SignOutcome"))?;
+            return Ok(Some(outcome));
+        }
+        Ok(None)
+    }
+
+    async fn put_signature(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        signature: &[u8],
+    ) -> Result<()> {
+        let key = get_outcome_key(epoch, quorum_id, storage_root);
+        let mut tx = self.db.transaction();
+        tx.put(COL_SIGNATURE, &key, signature);
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_signature(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+    ) -> Result<Option<Vec<u8>>> {
+        let key = get_outcome_key(epoch, quorum_id, storage_root);
+        Ok(self.db.get(COL_SIGNATURE, &key)?)
+    }
+}
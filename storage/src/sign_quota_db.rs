@@ -0,0 +1,72 @@
+use std::{collections::BTreeMap, ops::Add};
+
+use crate::COL_SIGN_QUOTA;
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Blobs and bytes of encoded slices signed for a client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    pub blobs: u64,
+    pub bytes: u64,
+}
+
+impl Add for QuotaUsage {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            blobs: self.blobs + other.blobs,
+            bytes: self.bytes + other.bytes,
+        }
+    }
+}
+
+impl QuotaUsage {
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self {
+            blobs: self.blobs.saturating_sub(other.blobs),
+            bytes: self.bytes.saturating_sub(other.bytes),
+        }
+    }
+}
+
+#[async_trait]
+pub trait SignQuotaDB {
+    /// Usage of every client in `epoch`, by client name.
+    async fn get_sign_quota_usage(&self, epoch: u64) -> Result<BTreeMap<String, QuotaUsage>>;
+
+    async fn put_sign_quota_usage(
+        &self,
+        epoch: u64,
+        usage: &BTreeMap<String, QuotaUsage>,
+    ) -> Result<()>;
+}
+
+#[async_trait]
+impl SignQuotaDB for Storage {
+    async fn get_sign_quota_usage(&self, epoch: u64) -> Result<BTreeMap<String, QuotaUsage>> {
+        match self.db.get(COL_SIGN_QUOTA, &epoch.to_be_bytes())? {
+            Some(value) => Ok(bincode::deserialize(&value)?),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    async fn put_sign_quota_usage(
+        &self,
+        epoch: u64,
+        usage: &BTreeMap<String, QuotaUsage>,
+    ) -> Result<()> {
+        let mut tx = self.db.transaction();
+        tx.put(
+            COL_SIGN_QUOTA,
+            &epoch.to_be_bytes(),
+            &bincode::serialize(usage)?,
+        );
+        self.db.write(tx)?;
+        Ok(())
+    }
+}
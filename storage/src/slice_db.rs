@@ -1,15 +1,15 @@
 use std::{collections::BTreeSet, iter::once};
 
-use crate::COL_SLICE;
+use crate::{error::StorageError, usage_db::UsageDelta, COL_CORRUPT_SLICE, COL_SLICE};
 
 use super::Storage;
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use async_trait::async_trait;
-use kvdb::KeyValueDB;
+use kvdb::{DBTransaction, KeyValueDB};
 use zg_encoder::{EncodedSlice, LightEncodedSlice};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SliceIndex {
     pub epoch: u64,
     pub quorum_id: u64,
@@ -24,12 +24,21 @@ pub struct BlobInfo {
     pub indicies: Vec<u16>,
 }
 
-const BLOB_PREFIX: u8 = 0;
-const SLICE_PREFIX: u8 = 1;
-const DATA_PREFIX: u8 = 2;
+/// Slice and data records of the slices of a blob, serialized and encrypted.
+pub struct SerializedSlices {
+    epoch: u64,
+    quorum_id: u64,
+    storage_root: [u8; 32],
+    indicies: Vec<u16>,
+    records: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+pub(crate) const BLOB_PREFIX: u8 = 0;
+pub(crate) const SLICE_PREFIX: u8 = 1;
+pub(crate) const DATA_PREFIX: u8 = 2;
 
 impl SliceIndex {
-    fn to_slice_key(&self) -> Vec<u8> {
+    pub(crate) fn to_slice_key(&self) -> Vec<u8> {
         self.to_key_with_prefix(SLICE_PREFIX)
     }
     fn to_data_key(&self) -> Vec<u8> {
@@ -44,6 +53,18 @@ impl SliceIndex {
             .chain(self.index.to_be_bytes())
             .collect()
     }
+
+    pub(crate) fn from_slice_key(key: &[u8]) -> Result<Self> {
+        if key.len() != 1 + 8 + 8 + 32 + 8 {
+            bail!(anyhow!("Incorrect key format"));
+        }
+        Ok(Self {
+            epoch: u64::from_be_bytes(key[1..9].try_into()?),
+            quorum_id: u64::from_be_bytes(key[9..17].try_into()?),
+            storage_root: key[17..49].try_into()?,
+            index: u64::from_be_bytes(key[49..57].try_into()?),
+        })
+    }
 }
 
 #[async_trait]
@@ -80,9 +101,78 @@ pub trait SliceDB {
         slices: Vec<EncodedSlice>,
     ) -> Result<()>;
 
+    /// Serialize and encrypt the slices of a blob, to store with `put_serialized_slices`.
+    fn serialize_slices(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        slices: Vec<EncodedSlice>,
+    ) -> Result<SerializedSlices>;
+
+    /// Store serialized slices as the slices of their blob, like `put_slice`. The slices are kept
+    /// by the caller, so a failed write can be attempted again without serializing them again.
+    async fn put_serialized_slices(&self, slices: &SerializedSlices) -> Result<()>;
+
+    /// Store slices recovered in light form, e.g. fetched from peers, and add them to the blob.
+    async fn put_light_slices(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        slices: Vec<(LightEncodedSlice, Vec<[u8; 32]>)>,
+    ) -> Result<()>;
+
     async fn get_epoch_info(&self, epoch: u64) -> Result<BTreeSet<BlobInfo>>;
 }
 
+fn get_blob_key(epoch: u64, quorum_id: u64, storage_root: [u8; 32]) -> Vec<u8> {
+    once(BLOB_PREFIX)
+        .chain(epoch.to_be_bytes())
+        .chain(quorum_id.to_be_bytes())
+        .chain(storage_root)
+        .collect()
+}
+
+impl Storage {
+    /// Encrypted slice and data records of a slice, serialized by the caller.
+    fn slice_records(
+        &self,
+        index: SliceIndex,
+        light_slice: Vec<u8>,
+        data: Vec<u8>,
+    ) -> Result<[(Vec<u8>, Vec<u8>); 2]> {
+        let slice_key = index.to_slice_key();
+        let data_key = index.to_data_key();
+        Ok([
+            (
+                slice_key.clone(),
+                self.encrypt_value(&slice_key, light_slice)?,
+            ),
+            (data_key.clone(), self.encrypt_value(&data_key, data)?),
+        ])
+    }
+
+    /// Put slice and data records, accounting their size in `usage`. The caller holds the shard
+    /// lock of their quorum.
+    fn put_slice_records(
+        &self,
+        tx: &mut DBTransaction,
+        usage: &mut UsageDelta,
+        records: &[(Vec<u8>, Vec<u8>)],
+    ) -> Result<()> {
+        for (key, value) in records {
+            let old_len = self.db.get(COL_SLICE, key)?.map(|x| x.len());
+            usage.replace(key, old_len, Some(value.len()));
+            tx.put(COL_SLICE, key, value);
+            if key.first() == Some(&SLICE_PREFIX) {
+                tx.delete(COL_CORRUPT_SLICE, key);
+            }
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl SliceDB for Storage {
     async fn get_raw_slice(
@@ -98,11 +188,15 @@ impl SliceDB for Storage {
             storage_root,
             index: index as u64,
         };
-        if let Some(slice) = self.db.get(COL_SLICE, &index.to_data_key())? {
-            Ok(Some(slice))
+        let key = index.to_data_key();
+        let raw_slice = if let Some(slice) = self.db.get(COL_SLICE, &key)? {
+            slice
+        } else if let Some(slice) = self.get_tiered_value(&key).await? {
+            slice
         } else {
-            Ok(None)
-        }
+            return Ok(None);
+        };
+        Ok(Some(self.decrypt_value(&key, raw_slice)?))
     }
 
     async fn get_slice_data(
@@ -138,11 +232,15 @@ impl SliceDB for Storage {
             storage_root,
             index: index as u64,
         };
-        let raw_slice = if let Some(slice) = self.db.get(COL_SLICE, &index.to_slice_key())? {
+        let key = index.to_slice_key();
+        let raw_slice = if let Some(slice) = self.db.get(COL_SLICE, &key)? {
+            slice
+        } else if let Some(slice) = self.get_tiered_value(&key).await? {
             slice
         } else {
             return Ok(None);
         };
+        let raw_slice = self.decrypt_value(&key, raw_slice)?;
         let check = if cfg!(test) {
             ark_serialize::Validate::Yes
         } else {
@@ -164,18 +262,22 @@ impl SliceDB for Storage {
         storage_root: [u8; 32],
         slices: Vec<EncodedSlice>,
     ) -> Result<()> {
-        let mut tx = self.db.transaction();
-
-        let blob_key: Vec<u8> = once(BLOB_PREFIX)
-            .chain(epoch.to_be_bytes())
-            .chain(quorum_id.to_be_bytes())
-            .chain(storage_root)
-            .collect();
+        let slices = self.serialize_slices(epoch, quorum_id, storage_root, slices)?;
+        self.put_serialized_slices(&slices).await
+    }
 
+    fn serialize_slices(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        slices: Vec<EncodedSlice>,
+    ) -> Result<SerializedSlices> {
         // TODO: should we consider the update logic here?
         let indicies: Vec<u16> = slices.iter().map(|slice| slice.index as u16).collect();
-        tx.put(COL_SLICE, &blob_key, &bcs::to_bytes(&indicies).unwrap());
 
+        // serialize and encrypt before locking the quorum
+        let mut records = vec![];
         for slice in slices.into_iter() {
             let index = SliceIndex {
                 epoch,
@@ -186,17 +288,83 @@ impl SliceDB for Storage {
             let data = slice.merkle_row();
             let light_slice = slice.into_light_slice();
 
-            let mut value: Vec<u8> = Vec::new();
+            let mut slice_value: Vec<u8> = Vec::new();
             // Note: Slice is stored in compressed form
-            light_slice.serialize_compressed(&mut value).unwrap();
-            tx.put(COL_SLICE, &index.to_slice_key(), &value);
+            light_slice.serialize_compressed(&mut slice_value).unwrap();
+            let mut data_value: Vec<u8> = Vec::new();
+            data.serialize_uncompressed(&mut data_value).unwrap();
+            records.extend(self.slice_records(index, slice_value, data_value)?);
+        }
+        Ok(SerializedSlices {
+            epoch,
+            quorum_id,
+            storage_root,
+            indicies,
+            records,
+        })
+    }
 
-            let mut value: Vec<u8> = Vec::new();
-            data.serialize_uncompressed(&mut value).unwrap();
-            tx.put(COL_SLICE, &index.to_data_key(), &value);
+    async fn put_serialized_slices(&self, slices: &SerializedSlices) -> Result<()> {
+        let (epoch, quorum_id) = (slices.epoch, slices.quorum_id);
+        let mut tx = self.db.transaction();
+        let mut usage = UsageDelta::default();
+
+        let blob_key = get_blob_key(epoch, quorum_id, slices.storage_root);
+        tx.put(
+            COL_SLICE,
+            &blob_key,
+            &bcs::to_bytes(&slices.indicies).unwrap(),
+        );
+
+        let _guard = self.locks.lock(epoch, quorum_id);
+        self.put_slice_records(&mut tx, &mut usage, &slices.records)?;
+        self.apply_slice_usage(&mut tx, [((epoch, quorum_id), usage)].into())?;
+        self.db.write(tx).map_err(StorageError::from)?;
+        Ok(())
+    }
+
+    async fn put_light_slices(
+        &self,
+        epoch: u64,
+        quorum_id: u64,
+        storage_root: [u8; 32],
+        slices: Vec<(LightEncodedSlice, Vec<[u8; 32]>)>,
+    ) -> Result<()> {
+        let mut tx = self.db.transaction();
+        let mut usage = UsageDelta::default();
+
+        let mut records = vec![];
+        let mut new_indicies = vec![];
+        for (light_slice, data) in slices.into_iter() {
+            new_indicies.push(light_slice.index as u16);
+            let index = SliceIndex {
+                epoch,
+                quorum_id,
+                storage_root,
+                index: light_slice.index as u64,
+            };
+
+            let mut slice_value: Vec<u8> = Vec::new();
+            // Note: Slice is stored in compressed form
+            light_slice.serialize_compressed(&mut slice_value).unwrap();
+            let mut data_value: Vec<u8> = Vec::new();
+            data.serialize_uncompressed(&mut data_value).unwrap();
+            records.extend(self.slice_records(index, slice_value, data_value)?);
         }
 
-        self.db.write(tx)?;
+        let _guard = self.locks.lock(epoch, quorum_id);
+        let blob_key = get_blob_key(epoch, quorum_id, storage_root);
+        let mut indicies: BTreeSet<u16> = match self.db.get(COL_SLICE, &blob_key)? {
+            Some(value) => bcs::from_bytes::<Vec<u16>>(&value)?.into_iter().collect(),
+            None => BTreeSet::new(),
+        };
+        indicies.extend(new_indicies);
+        let indicies: Vec<u16> = indicies.into_iter().collect();
+        tx.put(COL_SLICE, &blob_key, &bcs::to_bytes(&indicies).unwrap());
+
+        self.put_slice_records(&mut tx, &mut usage, &records)?;
+        self.apply_slice_usage(&mut tx, [((epoch, quorum_id), usage)].into())?;
+        self.db.write(tx).map_err(StorageError::from)?;
         Ok(())
     }
 
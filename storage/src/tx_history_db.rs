@@ -0,0 +1,70 @@
+use crate::COL_TX_HISTORY;
+
+use super::Storage;
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+/// Attempts kept, older ones are pruned.
+const MAX_TX_ATTEMPTS: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxOutcome {
+    SUCCEEDED,
+    REVERTED,
+    /// The nonce is taken by another transaction.
+    DROPPED,
+    /// The transaction is not sent or its receipt is not available.
+    FAILED,
+}
+
+/// A transaction submitted by the transactor, with the decoded reason if it did not succeed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxAttempt {
+    /// Unix timestamp in seconds.
+    pub timestamp: u64,
+    /// What the transaction does.
+    pub info: String,
+    pub tx_hash: Option<[u8; 32]>,
+    pub outcome: TxOutcome,
+    pub reason: Option<String>,
+}
+
+#[async_trait]
+pub trait TxHistoryDB {
+    async fn put_tx_attempt(&self, attempt: &TxAttempt) -> Result<()>;
+    /// The latest `limit` attempts, newest first.
+    async fn get_tx_attempts(&self, limit: usize) -> Result<Vec<TxAttempt>>;
+}
+
+#[async_trait]
+impl TxHistoryDB for Storage {
+    async fn put_tx_attempt(&self, attempt: &TxAttempt) -> Result<()> {
+        let seq = match self.db.iter(COL_TX_HISTORY).last() {
+            Some(item) => u64::from_be_bytes(item?.0.as_ref().try_into()?) + 1,
+            None => 0,
+        };
+        let mut tx = self.db.transaction();
+        tx.put(
+            COL_TX_HISTORY,
+            &seq.to_be_bytes(),
+            &bincode::serialize(attempt)?,
+        );
+        if seq >= MAX_TX_ATTEMPTS {
+            tx.delete(COL_TX_HISTORY, &(seq - MAX_TX_ATTEMPTS).to_be_bytes());
+        }
+        self.db.write(tx)?;
+        Ok(())
+    }
+
+    async fn get_tx_attempts(&self, limit: usize) -> Result<Vec<TxAttempt>> {
+        let mut attempts = vec![];
+        for item in self.db.iter(COL_TX_HISTORY) {
+            let (_, value) = item?;
+            attempts.push(bincode::deserialize(&value)?);
+        }
+        attempts.reverse();
+        attempts.truncate(limit);
+        Ok(attempts)
+    }
+}
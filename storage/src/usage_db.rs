@@ -0,0 +1,254 @@
+use std::{collections::BTreeMap, fs, iter::once, path::Path};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use kvdb::{DBTransaction, KeyValueDB};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    blob_status_db::{BlobStatus, BlobStatusDB},
+    slice_db::{BLOB_PREFIX, DATA_PREFIX, SLICE_PREFIX},
+    COL_SLICE, COL_SLICE_USAGE,
+};
+
+use super::Storage;
+
+/// Slices of a quorum in an epoch stored in the local database, and their bytes with keys.
+/// Slices tiered to cold storage are not counted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SliceUsage {
+    pub slices: u64,
+    pub bytes: u64,
+}
+
+/// Change of the usage of a quorum in an epoch, by a write of slices.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct UsageDelta {
+    pub slices: i64,
+    pub bytes: i64,
+}
+
+impl UsageDelta {
+    /// Account the replacement of a slice record of `key`, stored with `old_len` bytes if any.
+    pub(crate) fn replace(&mut self, key: &[u8], old_len: Option<usize>, new_len: Option<usize>) {
+        let len = |value_len: Option<usize>| value_len.map_or(0, |x| (key.len() + x) as i64);
+        self.bytes += len(new_len) - len(old_len);
+        if key.first() == Some(&SLICE_PREFIX) {
+            self.slices += new_len.is_some() as i64 - old_len.is_some() as i64;
+        }
+    }
+}
+
+#[async_trait]
+pub trait UsageDB {
+    /// Usage of every quorum of `epoch`, or of all epochs, by `(epoch, quorum_id)`.
+    async fn get_slice_usage(&self, epoch: Option<u64>)
+        -> Result<BTreeMap<(u64, u64), SliceUsage>>;
+
+    /// Estimated usage of blobs whose commitment is not verified, by `(epoch, quorum_id)`. It
+    /// scans the blob records, the bytes of a blob are estimated from the average slice size of
+    /// its quorum.
+    async fn get_reclaimable_usage(
+        &self,
+        epoch: Option<u64>,
+    ) -> Result<BTreeMap<(u64, u64), SliceUsage>>;
+}
+
+fn get_usage_key(epoch: u64, quorum_id: u64) -> Vec<u8> {
+    [epoch.to_be_bytes(), quorum_id.to_be_bytes()].concat()
+}
+
+fn parse_usage_key(key: &[u8]) -> Result<(u64, u64)> {
+    if key.len() != 16 {
+        bail!(anyhow!("Incorrect key format"));
+    }
+    Ok((
+        u64::from_be_bytes(key[..8].try_into()?),
+        u64::from_be_bytes(key[8..].try_into()?),
+    ))
+}
+
+fn dir_size(path: &Path) -> Result<u64> {
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        size += if metadata.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            metadata.len()
+        };
+    }
+    Ok(size)
+}
+
+impl Storage {
+    /// Size of the database files on disk.
+    pub fn disk_size(&self) -> Result<u64> {
+        dir_size(&self.path)
+    }
+
+    /// Add the usage changes of a write to `tx`. The caller holds the shard locks of the quorums
+    /// until `tx` is written, so concurrent writes of a quorum do not lose updates.
+    pub(crate) fn apply_slice_usage(
+        &self,
+        tx: &mut DBTransaction,
+        deltas: BTreeMap<(u64, u64), UsageDelta>,
+    ) -> Result<()> {
+        for ((epoch, quorum_id), delta) in deltas {
+            let key = get_usage_key(epoch, quorum_id);
+            let usage: SliceUsage = match self.db.get(COL_SLICE_USAGE, &key)? {
+                Some(value) => bincode::deserialize(&value)?,
+                None => SliceUsage::default(),
+            };
+            let usage = SliceUsage {
+                slices: usage.slices.saturating_add_signed(delta.slices),
+                bytes: usage.bytes.saturating_add_signed(delta.bytes),
+            };
+            tx.put(COL_SLICE_USAGE, &key, &bincode::serialize(&usage).unwrap());
+        }
+        Ok(())
+    }
+
+    /// Account the slices stored before the usage was tracked, on the first open by this version.
+    pub(crate) fn rebuild_slice_usage(&self) -> Result<()> {
+        let _guards = self.locks.lock_all();
+        let mut usage: BTreeMap<(u64, u64), SliceUsage> = BTreeMap::new();
+        for prefix in [SLICE_PREFIX, DATA_PREFIX] {
+            for item in KeyValueDB::iter_with_prefix(&*self.db, COL_SLICE, &[prefix]) {
+                let (key, value) = item?;
+                let entry = usage
+                    .entry((
+                        u64::from_be_bytes(key[1..9].try_into()?),
+                        u64::from_be_bytes(key[9..17].try_into()?),
+                    ))
+                    .or_default();
+                entry.slices += (prefix == SLICE_PREFIX) as u64;
+                entry.bytes += (key.len() + value.len()) as u64;
+            }
+        }
+        let mut tx = self.db.transaction();
+        for ((epoch, quorum_id), usage) in usage {
+            tx.put(
+                COL_SLICE_USAGE,
+                &get_usage_key(epoch, quorum_id),
+                &bincode::serialize(&usage).unwrap(),
+            );
+        }
+        self.db.write(tx)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UsageDB for Storage {
+    async fn get_slice_usage(
+        &self,
+        epoch: Option<u64>,
+    ) -> Result<BTreeMap<(u64, u64), SliceUsage>> {
+        let prefix = epoch.map_or(vec![], |epoch| epoch.to_be_bytes().to_vec());
+        let mut answer = BTreeMap::new();
+        for item in KeyValueDB::iter_with_prefix(&*self.db, COL_SLICE_USAGE, &prefix) {
+            let (key, value) = item?;
+            answer.insert(parse_usage_key(&key)?, bincode::deserialize(&value)?);
+        }
+        Ok(answer)
+    }
+
+    async fn get_reclaimable_usage(
+        &self,
+        epoch: Option<u64>,
+    ) -> Result<BTreeMap<(u64, u64), SliceUsage>> {
+        let usage = self.get_slice_usage(epoch).await?;
+        let prefix: Vec<u8> = once(BLOB_PREFIX)
+            .chain(epoch.map_or(vec![], |epoch| epoch.to_be_bytes().to_vec()))
+            .collect();
+        let mut blobs = vec![];
+        for item in KeyValueDB::iter_with_prefix(&*self.db, COL_SLICE, &prefix) {
+            let (key, value) = item?;
+            if key.len() != 1 + 8 + 8 + 32 {
+                bail!(anyhow!("Incorrect key format"));
+            }
+            let epoch = u64::from_be_bytes(key[1..9].try_into()?);
+            let quorum_id = u64::from_be_bytes(key[9..17].try_into()?);
+            let storage_root: [u8; 32] = key[17..49].try_into()?;
+            let slices = bcs::from_bytes::<Vec<u16>>(&value)?.len() as u64;
+            blobs.push((epoch, quorum_id, storage_root, slices));
+        }
+
+        let mut answer: BTreeMap<(u64, u64), SliceUsage> = BTreeMap::new();
+        for (epoch, quorum_id, storage_root, slices) in blobs {
+            if let Some(BlobStatus::VERIFIED) =
+                self.get_blob_status(epoch, quorum_id, storage_root).await?
+            {
+                continue;
+            }
+            let quorum = usage.get(&(epoch, quorum_id)).copied().unwrap_or_default();
+            let entry = answer.entry((epoch, quorum_id)).or_default();
+            entry.slices += slices;
+            entry.bytes += quorum.bytes.checked_div(quorum.slices).unwrap_or(0) * slices;
+        }
+        Ok(answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn slice_usage_test() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Storage::new(dir.path()).unwrap();
+        let slice_key = |epoch: u64, prefix: u8| -> Vec<u8> {
+            once(prefix)
+                .chain(epoch.to_be_bytes())
+                .chain([0; 8 + 32 + 8])
+                .collect()
+        };
+
+        for (epoch, old_len, new_len) in [
+            (1, None, Some(100)),
+            (1, Some(100), Some(50)),
+            (2, None, Some(10)),
+        ] {
+            let mut tx = db.db.transaction();
+            let mut delta = UsageDelta::default();
+            delta.replace(&slice_key(epoch, SLICE_PREFIX), old_len, new_len);
+            delta.replace(
+                &slice_key(epoch, DATA_PREFIX),
+                old_len.map(|_| 20),
+                Some(20),
+            );
+            db.apply_slice_usage(&mut tx, [((epoch, 0), delta)].into())
+                .unwrap();
+            db.db.write(tx).unwrap();
+        }
+        assert_eq!(
+            db.get_slice_usage(Some(1)).await.unwrap(),
+            [(
+                (1, 0),
+                SliceUsage {
+                    slices: 1,
+                    bytes: (50 + 57) + (20 + 57)
+                }
+            )]
+            .into()
+        );
+        assert_eq!(db.get_slice_usage(None).await.unwrap().len(), 2);
+
+        let mut tx = db.db.transaction();
+        let mut delta = UsageDelta::default();
+        delta.replace(&slice_key(2, SLICE_PREFIX), Some(10), None);
+        db.apply_slice_usage(&mut tx, [((2, 0), delta)].into())
+            .unwrap();
+        db.db.write(tx).unwrap();
+        assert_eq!(
+            db.get_slice_usage(Some(2)).await.unwrap()[&(2, 0)],
+            SliceUsage {
+                slices: 0,
+                bytes: 20 + 57
+            }
+        );
+    }
+}